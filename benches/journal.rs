@@ -0,0 +1,63 @@
+//! Replay benchmarks over synthetic journals.
+//!
+//! Run with `cargo bench --features bench`. Journal sizes default to 10k and 100k
+//! events; set `BANKERO_BENCH_SIZES=10000,100000,1000000` to include the 1M journal
+//! (generating it takes a while). Each benchmark runs the release binary end to end,
+//! so the numbers include process start-up and opening the database.
+
+use criterion::{BenchmarkId, Criterion, criterion_group, criterion_main};
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::time::Duration;
+
+const BIN: &str = env!("CARGO_BIN_EXE_bankero");
+
+fn sizes() -> Vec<u64> {
+    std::env::var("BANKERO_BENCH_SIZES")
+        .unwrap_or_else(|_| "10000,100000".to_string())
+        .split(',')
+        .filter_map(|s| s.trim().parse().ok())
+        .collect()
+}
+
+fn bankero(home: &Path, args: &[&str]) {
+    let status = Command::new(BIN)
+        .env("BANKERO_HOME", home)
+        .args(args)
+        .stdout(Stdio::null())
+        .status()
+        .expect("run bankero");
+    assert!(status.success(), "bankero {args:?} failed");
+}
+
+fn journal(c: &mut Criterion) {
+    let mut group = c.benchmark_group("journal");
+    group.sample_size(10);
+    group.measurement_time(Duration::from_secs(20));
+
+    for size in sizes() {
+        let home = tempfile::tempdir().expect("tempdir");
+        let sync_dir = tempfile::tempdir().expect("tempdir");
+        let events = size.to_string();
+        bankero(home.path(), &["bench-gen", "--events", &events]);
+
+        // `report` without filters prints every event: dominated by list_events.
+        group.bench_with_input(BenchmarkId::new("list_events", size), &size, |b, _| {
+            b.iter(|| bankero(home.path(), &["report"]))
+        });
+        group.bench_with_input(BenchmarkId::new("balance", size), &size, |b, _| {
+            b.iter(|| bankero(home.path(), &["balance"]))
+        });
+        group.bench_with_input(BenchmarkId::new("budget_report", size), &size, |b, _| {
+            b.iter(|| bankero(home.path(), &["budget", "report", "--month", "2026-06"]))
+        });
+        let dir = sync_dir.path().to_string_lossy().to_string();
+        group.bench_with_input(BenchmarkId::new("sync_export", size), &size, |b, _| {
+            b.iter(|| bankero(home.path(), &["sync", "--dir", &dir, "now"]))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, journal);
+criterion_main!(benches);
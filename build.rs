@@ -0,0 +1,8 @@
+fn main() {
+    // `bankero upgrade` picks the release asset built for the same target triple.
+    let target = std::env::var("TARGET").expect("TARGET is set by cargo");
+    println!("cargo:rustc-env=BANKERO_TARGET={target}");
+    println!("cargo:rerun-if-changed=build.rs");
+    // Official releases embed the minisign public key their assets are signed with.
+    println!("cargo:rerun-if-env-changed=BANKERO_RELEASE_PUBKEY");
+}
@@ -24,11 +24,14 @@ rm -rf %{buildroot}
 install -D -m 0755 bankero %{buildroot}%{_bindir}/bankero
 install -D -m 0644 README.md %{buildroot}%{_docdir}/%{name}/README.md
 install -D -m 0644 PRD.md %{buildroot}%{_docdir}/%{name}/PRD.md
+install -d %{buildroot}%{_mandir}/man1
+install -m 0644 man/*.1 %{buildroot}%{_mandir}/man1/
 
 %files
 %{_bindir}/bankero
 %doc %{_docdir}/%{name}/README.md
 %doc %{_docdir}/%{name}/PRD.md
+%{_mandir}/man1/*.1*
 
 %changelog
 * Tue Feb 25 2026 Bankero Maintainers <noreply@github.com> - %{crate_version}-1
@@ -34,6 +34,8 @@ mkdir -p "$STAGE/$PKG_DIR"
 cp -f "$BIN" "$STAGE/$PKG_DIR/bankero"
 cp -f "$ROOT_DIR/README.md" "$STAGE/$PKG_DIR/README.md"
 cp -f "$ROOT_DIR/PRD.md" "$STAGE/$PKG_DIR/PRD.md"
+mkdir -p "$STAGE/$PKG_DIR/man"
+"$BIN" generate-man --out-dir "$STAGE/$PKG_DIR/man" >/dev/null
 
 TARBALL="$TOPDIR/SOURCES/bankero-${VERSION}.tar.gz"
 (
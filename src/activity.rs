@@ -0,0 +1,144 @@
+//! Coordination between bankero processes writing to the same journal.
+//!
+//! The sync server, scheduled task runs and interactive commands may all write to one
+//! workspace at once. SQLite serializes the writes; this module makes the waiting
+//! visible. Long writers (a sync, a task run) record what they are doing in the
+//! `activity/` directory next to the journal for as long as they run, one file each, and
+//! a process that finds the journal busy prints "Waiting for <that> to finish…" once and
+//! retries instead of failing with "database is locked". Records left behind by a
+//! process that no longer runs (it crashed or was killed) are ignored and removed.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::time::Duration;
+
+const ACTIVITY_DIR: &str = "activity";
+
+/// Pause between retries while another process holds the write lock.
+const RETRY_EVERY: Duration = Duration::from_millis(50);
+
+/// How long a command waits before giving up with "database is locked".
+const GIVE_UP_AFTER: Duration = Duration::from_secs(120);
+
+/// `activity/` of the journal this process has open, for the waiting message.
+static WATCHED: OnceLock<PathBuf> = OnceLock::new();
+
+/// Tells apart the records of several guards alive in one process.
+static NEXT_GUARD: AtomicU32 = AtomicU32::new(0);
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Record {
+    pid: u32,
+    what: String,
+}
+
+/// A long write in progress; its record is removed when this is dropped.
+pub struct Activity {
+    path: PathBuf,
+}
+
+impl Activity {
+    /// Records that this process is doing `what` (e.g. "a sync with laptop") in `dir`.
+    pub fn begin(dir: &Path, what: &str) -> Result<Self> {
+        let dir = dir.join(ACTIVITY_DIR);
+        std::fs::create_dir_all(&dir)
+            .with_context(|| format!("Failed to create {}", dir.display()))?;
+        let pid = std::process::id();
+        let guard = NEXT_GUARD.fetch_add(1, Ordering::Relaxed);
+        let path = dir.join(format!("{pid}-{guard}.json"));
+        let record = Record {
+            pid,
+            what: what.to_string(),
+        };
+        std::fs::write(&path, serde_json::to_vec(&record)?)
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+        Ok(Self { path })
+    }
+}
+
+impl Drop for Activity {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+/// Remembers the journal directory so [`wait_for_writer`] can name the busy writer.
+pub fn watch(dir: &Path) {
+    let _ = WATCHED.set(dir.join(ACTIVITY_DIR));
+}
+
+/// Whether process `pid` is still running.
+#[cfg(target_os = "linux")]
+fn alive(pid: u32) -> bool {
+    Path::new("/proc").join(pid.to_string()).exists()
+}
+
+#[cfg(all(unix, not(target_os = "linux")))]
+fn alive(pid: u32) -> bool {
+    std::process::Command::new("kill")
+        .args(["-0", &pid.to_string()])
+        .stderr(std::process::Stdio::null())
+        .status()
+        .is_ok_and(|s| s.success())
+}
+
+#[cfg(windows)]
+fn alive(pid: u32) -> bool {
+    std::process::Command::new("tasklist")
+        .args(["/FI", &format!("PID eq {pid}"), "/NH", "/FO", "CSV"])
+        .output()
+        .is_ok_and(|out| String::from_utf8_lossy(&out.stdout).contains(&format!("\"{pid}\"")))
+}
+
+#[cfg(not(any(unix, windows)))]
+fn alive(_pid: u32) -> bool {
+    true
+}
+
+/// What another running process recorded it is doing, if anything. Records of
+/// processes that are gone are removed on the way.
+fn current() -> Option<Record> {
+    let mut paths: Vec<PathBuf> = std::fs::read_dir(WATCHED.get()?)
+        .ok()?
+        .filter_map(|entry| Some(entry.ok()?.path()))
+        .filter(|p| p.extension().is_some_and(|e| e == "json"))
+        .collect();
+    paths.sort();
+    let mut found = None;
+    for path in paths {
+        let Some(record) = std::fs::read(&path)
+            .ok()
+            .and_then(|raw| serde_json::from_slice::<Record>(&raw).ok())
+        else {
+            continue;
+        };
+        if record.pid == std::process::id() {
+            continue;
+        }
+        if !alive(record.pid) {
+            let _ = std::fs::remove_file(&path);
+            continue;
+        }
+        found.get_or_insert(record);
+    }
+    found
+}
+
+/// SQLite busy handler: says once what it is waiting for, then retries until the limit.
+pub fn wait_for_writer(attempt: i32) -> bool {
+    if attempt == 0 {
+        match current() {
+            Some(r) => eprintln!("Waiting for {} (pid {}) to finish…", r.what, r.pid),
+            None => eprintln!("Waiting for another bankero process to finish writing…"),
+        }
+    }
+    if RETRY_EVERY * attempt.max(0) as u32 >= GIVE_UP_AFTER {
+        eprintln!("Still busy after {}s; giving up.", GIVE_UP_AFTER.as_secs());
+        return false;
+    }
+    std::thread::sleep(RETRY_EVERY);
+    true
+}
@@ -0,0 +1,397 @@
+//! Analytics export (`bankero export analytics`).
+//!
+//! Writes the workspace as Parquet files for DuckDB, pandas or Polars:
+//!
+//! ```text
+//! analytics/
+//!   events/2026-03.parquet     one row per event, partitioned by effective month
+//!   postings/2026-03.parquet   one row per posting
+//!   rates.parquet
+//!   budgets.parquet
+//!   manifest.json              content fingerprint of every file written
+//! ```
+//!
+//! Runs are incremental: a month's files are rewritten only when its events changed
+//! (new, edited or removed), and rates/budgets only when their rows did. Months that
+//! no longer have events are deleted. Amounts and rates are `DECIMAL(38, 18)`.
+
+use crate::config::now_utc;
+use crate::db::Db;
+use crate::domain::{EventPayload, Posting, StoredEvent};
+use anyhow::{Context, Result, anyhow};
+use arrow_array::{
+    ArrayRef, Decimal128Array, Int32Array, RecordBatch, StringArray, TimestampMicrosecondArray,
+};
+use arrow_schema::{DataType, Field, Schema, TimeUnit};
+use chrono::{DateTime, Utc};
+use parquet::arrow::ArrowWriter;
+use parquet::basic::Compression;
+use parquet::file::properties::WriterProperties;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::Arc;
+
+const MANIFEST_VERSION: u32 = 1;
+const SCALE: u32 = 18;
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Manifest {
+    version: u32,
+    generated_at: Option<DateTime<Utc>>,
+    /// Relative file path -> fingerprint of the rows it was written from.
+    files: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Default)]
+struct ExportStats {
+    written: Vec<String>,
+    unchanged: usize,
+    removed: usize,
+}
+
+fn fingerprint(parts: impl IntoIterator<Item = String>) -> String {
+    let mut hasher = Sha256::new();
+    for part in parts {
+        hasher.update(part.as_bytes());
+        hasher.update([0u8]);
+    }
+    hasher
+        .finalize()
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect()
+}
+
+fn decimal_field(name: &str) -> Field {
+    Field::new(name, DataType::Decimal128(38, SCALE as i8), false)
+}
+
+fn timestamp_field(name: &str, nullable: bool) -> Field {
+    Field::new(
+        name,
+        DataType::Timestamp(TimeUnit::Microsecond, Some("UTC".into())),
+        nullable,
+    )
+}
+
+fn decimals(values: impl IntoIterator<Item = Decimal>) -> Result<ArrayRef> {
+    let raw = values
+        .into_iter()
+        .map(|d| {
+            // `Decimal` keeps at most 28 digits, so rescaling anything above ~7.9e10 to
+            // 18 places would not fit; widen the mantissa in i128 instead.
+            let d = d.round_dp(SCALE);
+            10i128
+                .checked_pow(SCALE - d.scale())
+                .and_then(|factor| d.mantissa().checked_mul(factor))
+                .filter(|m| m.unsigned_abs() < 10u128.pow(38))
+                .ok_or_else(|| anyhow!("{d} does not fit DECIMAL(38, {SCALE})"))
+        })
+        .collect::<Result<Vec<i128>>>()?;
+    Ok(Arc::new(
+        Decimal128Array::from(raw).with_precision_and_scale(38, SCALE as i8)?,
+    ))
+}
+
+fn timestamps(values: impl IntoIterator<Item = Option<DateTime<Utc>>>) -> ArrayRef {
+    let micros: Vec<Option<i64>> = values
+        .into_iter()
+        .map(|t| t.map(|t| t.timestamp_micros()))
+        .collect();
+    Arc::new(TimestampMicrosecondArray::from(micros).with_timezone("UTC"))
+}
+
+fn strings<S: AsRef<str>>(values: impl IntoIterator<Item = Option<S>>) -> ArrayRef {
+    let values: Vec<Option<S>> = values.into_iter().collect();
+    Arc::new(StringArray::from_iter(
+        values.iter().map(|v| v.as_ref().map(|s| s.as_ref())),
+    ))
+}
+
+/// Writes `batch` to `path` through a temporary file, so readers never see half a file.
+fn write_parquet(path: &Path, batch: RecordBatch) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create {}", parent.display()))?;
+    }
+    let tmp = path.with_extension("parquet.tmp");
+    let file = std::fs::File::create(&tmp)
+        .with_context(|| format!("Failed to create {}", tmp.display()))?;
+    let props = WriterProperties::builder()
+        .set_compression(Compression::SNAPPY)
+        .build();
+    let mut writer = ArrowWriter::try_new(file, batch.schema(), Some(props))?;
+    writer.write(&batch)?;
+    writer.close()?;
+    std::fs::rename(&tmp, path).with_context(|| format!("Failed to write {}", path.display()))?;
+    Ok(())
+}
+
+fn payload<'a>(e: &&'a StoredEvent) -> &'a EventPayload {
+    &e.payload
+}
+
+fn events_batch(events: &[&StoredEvent]) -> Result<RecordBatch> {
+    let schema = Schema::new(vec![
+        Field::new("event_id", DataType::Utf8, false),
+        Field::new("action", DataType::Utf8, false),
+        timestamp_field("effective_at", false),
+        timestamp_field("created_at", false),
+        Field::new("workspace", DataType::Utf8, false),
+        Field::new("project", DataType::Utf8, false),
+        Field::new("category", DataType::Utf8, true),
+        Field::new("note", DataType::Utf8, true),
+        Field::new("reference", DataType::Utf8, true),
+        Field::new("tags", DataType::Utf8, true),
+        Field::new("provider", DataType::Utf8, true),
+        Field::new("rate_base", DataType::Utf8, true),
+        Field::new("rate_quote", DataType::Utf8, true),
+        Field::new("override_rate", DataType::Utf8, true),
+        Field::new("metadata_json", DataType::Utf8, true),
+    ]);
+    let columns: Vec<ArrayRef> =
+        vec![
+            strings(events.iter().map(|e| Some(e.event_id.to_string()))),
+            strings(events.iter().map(|e| Some(e.action.as_str()))),
+            timestamps(events.iter().map(|e| Some(e.effective_at))),
+            timestamps(events.iter().map(|e| Some(e.created_at))),
+            strings(events.iter().map(|e| Some(payload(e).workspace.as_str()))),
+            strings(events.iter().map(|e| Some(payload(e).project.as_str()))),
+            strings(events.iter().map(|e| payload(e).category.as_deref())),
+            strings(events.iter().map(|e| payload(e).note.as_deref())),
+            strings(events.iter().map(|e| payload(e).reference.as_deref())),
+            strings(
+                events
+                    .iter()
+                    .map(|e| (!payload(e).tags.is_empty()).then(|| payload(e).tags.join(","))),
+            ),
+            strings(
+                events
+                    .iter()
+                    .map(|e| payload(e).rate_context.provider.as_deref()),
+            ),
+            strings(
+                events
+                    .iter()
+                    .map(|e| payload(e).rate_context.base.as_deref()),
+            ),
+            strings(
+                events
+                    .iter()
+                    .map(|e| payload(e).rate_context.quote.as_deref()),
+            ),
+            strings(
+                events
+                    .iter()
+                    .map(|e| payload(e).rate_context.override_rate.map(|r| r.to_string())),
+            ),
+            strings(events.iter().map(|e| {
+                (!payload(e).metadata.is_null()).then(|| payload(e).metadata.to_string())
+            })),
+        ];
+    Ok(RecordBatch::try_new(Arc::new(schema), columns)?)
+}
+
+fn postings_batch(events: &[&StoredEvent]) -> Result<RecordBatch> {
+    let schema = Schema::new(vec![
+        Field::new("event_id", DataType::Utf8, false),
+        Field::new("posting_index", DataType::Int32, false),
+        Field::new("action", DataType::Utf8, false),
+        timestamp_field("effective_at", false),
+        Field::new("account", DataType::Utf8, false),
+        Field::new("commodity", DataType::Utf8, false),
+        decimal_field("amount"),
+        Field::new("category", DataType::Utf8, true),
+    ]);
+    let rows: Vec<(&StoredEvent, usize, &Posting)> = events
+        .iter()
+        .flat_map(|e| {
+            e.payload
+                .postings
+                .iter()
+                .enumerate()
+                .map(move |(i, p)| (*e, i, p))
+        })
+        .collect();
+    let columns: Vec<ArrayRef> = vec![
+        strings(rows.iter().map(|(e, _, _)| Some(e.event_id.to_string()))),
+        Arc::new(Int32Array::from_iter_values(
+            rows.iter().map(|(_, i, _)| *i as i32),
+        )),
+        strings(rows.iter().map(|(e, _, _)| Some(e.action.as_str()))),
+        timestamps(rows.iter().map(|(e, _, _)| Some(e.effective_at))),
+        strings(rows.iter().map(|(_, _, p)| Some(p.account.as_str()))),
+        strings(rows.iter().map(|(_, _, p)| Some(p.commodity.as_str()))),
+        decimals(rows.iter().map(|(_, _, p)| p.amount))?,
+        strings(rows.iter().map(|(e, _, _)| e.payload.category.as_deref())),
+    ];
+    Ok(RecordBatch::try_new(Arc::new(schema), columns)?)
+}
+
+fn export_analytics(db: &Db, out: &Path, full: bool) -> Result<ExportStats> {
+    std::fs::create_dir_all(out).with_context(|| format!("Failed to create {}", out.display()))?;
+    let manifest_path = out.join("manifest.json");
+    let previous: Manifest = match std::fs::read_to_string(&manifest_path) {
+        Ok(raw) if !full => serde_json::from_str(&raw)
+            .with_context(|| format!("Invalid {}", manifest_path.display()))?,
+        _ => Manifest::default(),
+    };
+    let mut manifest = Manifest {
+        version: MANIFEST_VERSION,
+        generated_at: Some(now_utc()),
+        files: BTreeMap::new(),
+    };
+    let mut stats = ExportStats::default();
+    let mut emit = |name: String,
+                    hash: String,
+                    build: &dyn Fn() -> Result<RecordBatch>,
+                    manifest: &mut Manifest|
+     -> Result<()> {
+        let path = out.join(&name);
+        if previous.files.get(&name) == Some(&hash) && path.exists() {
+            stats.unchanged += 1;
+        } else {
+            write_parquet(&path, build()?)?;
+            stats.written.push(name.clone());
+        }
+        manifest.files.insert(name, hash);
+        Ok(())
+    };
+
+    let events = crate::snapshot::replay_events(db, true)?;
+    let mut by_month: BTreeMap<String, Vec<&StoredEvent>> = BTreeMap::new();
+    for e in &events {
+        by_month
+            .entry(e.effective_at.format("%Y-%m").to_string())
+            .or_default()
+            .push(e);
+    }
+    for (month, month_events) in &by_month {
+        let mut parts: Vec<String> = month_events
+            .iter()
+            .map(|e| {
+                Ok(format!(
+                    "{}:{}",
+                    e.event_id,
+                    serde_json::to_string(&e.payload)?
+                ))
+            })
+            .collect::<Result<_>>()?;
+        parts.sort();
+        let hash = fingerprint(parts);
+        emit(
+            format!("events/{month}.parquet"),
+            hash.clone(),
+            &|| events_batch(month_events),
+            &mut manifest,
+        )?;
+        emit(
+            format!("postings/{month}.parquet"),
+            hash,
+            &|| postings_batch(month_events),
+            &mut manifest,
+        )?;
+    }
+
+    let rates = db.list_all_rates()?;
+    emit(
+        "rates.parquet".to_string(),
+        fingerprint(rates.iter().map(|r| {
+            format!(
+                "{}|{}|{}|{}|{}|{}",
+                r.provider,
+                r.base,
+                r.quote,
+                r.as_of.to_rfc3339(),
+                r.rate,
+                r.source.as_deref().unwrap_or("")
+            )
+        })),
+        &|| {
+            let schema = Schema::new(vec![
+                Field::new("provider", DataType::Utf8, false),
+                Field::new("base", DataType::Utf8, false),
+                Field::new("quote", DataType::Utf8, false),
+                timestamp_field("as_of", false),
+                decimal_field("rate"),
+                Field::new("source", DataType::Utf8, true),
+            ]);
+            let columns: Vec<ArrayRef> = vec![
+                strings(rates.iter().map(|r| Some(r.provider.as_str()))),
+                strings(rates.iter().map(|r| Some(r.base.as_str()))),
+                strings(rates.iter().map(|r| Some(r.quote.as_str()))),
+                timestamps(rates.iter().map(|r| Some(r.as_of))),
+                decimals(rates.iter().map(|r| r.rate))?,
+                strings(rates.iter().map(|r| r.source.as_deref())),
+            ];
+            Ok(RecordBatch::try_new(Arc::new(schema), columns)?)
+        },
+        &mut manifest,
+    )?;
+
+    let budgets = db.list_budgets()?;
+    emit(
+        "budgets.parquet".to_string(),
+        fingerprint(budgets.iter().map(|b| format!("{b:?}"))),
+        &|| {
+            let schema = Schema::new(vec![
+                Field::new("name", DataType::Utf8, false),
+                decimal_field("amount"),
+                Field::new("commodity", DataType::Utf8, false),
+                Field::new("month", DataType::Utf8, true),
+                Field::new("category", DataType::Utf8, true),
+                Field::new("account", DataType::Utf8, true),
+                Field::new("provider", DataType::Utf8, true),
+                Field::new("project", DataType::Utf8, true),
+                timestamp_field("created_at", false),
+            ]);
+            let columns: Vec<ArrayRef> = vec![
+                strings(budgets.iter().map(|b| Some(b.name.as_str()))),
+                decimals(budgets.iter().map(|b| b.amount))?,
+                strings(budgets.iter().map(|b| Some(b.commodity.as_str()))),
+                strings(budgets.iter().map(|b| b.month.as_deref())),
+                strings(budgets.iter().map(|b| b.category.as_deref())),
+                strings(budgets.iter().map(|b| b.account.as_deref())),
+                strings(budgets.iter().map(|b| b.provider.as_deref())),
+                strings(budgets.iter().map(|b| b.project.as_deref())),
+                timestamps(budgets.iter().map(|b| Some(b.created_at))),
+            ];
+            Ok(RecordBatch::try_new(Arc::new(schema), columns)?)
+        },
+        &mut manifest,
+    )?;
+
+    // Months whose events are all gone.
+    for name in previous.files.keys() {
+        if !manifest.files.contains_key(name) {
+            let path = out.join(name);
+            if path.exists() {
+                std::fs::remove_file(&path)
+                    .with_context(|| format!("Failed to remove {}", path.display()))?;
+            }
+            stats.removed += 1;
+        }
+    }
+
+    std::fs::write(&manifest_path, serde_json::to_string_pretty(&manifest)?)
+        .with_context(|| format!("Failed to write {}", manifest_path.display()))?;
+    Ok(stats)
+}
+
+pub fn handle_analytics(db: &Db, out: &Path, full: bool) -> Result<()> {
+    let stats = export_analytics(db, out, full)?;
+    for name in &stats.written {
+        println!("wrote\t{name}");
+    }
+    println!(
+        "Analytics in {}: {} file(s) written, {} unchanged, {} removed.",
+        out.display(),
+        stats.written.len(),
+        stats.unchanged,
+        stats.removed
+    );
+    Ok(())
+}
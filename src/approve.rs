@@ -0,0 +1,137 @@
+//! Attribution and review for shared workspaces (`bankero approve`).
+//!
+//! Event commands record who entered them in `metadata.entered_by`. An event entered by
+//! someone else is pending until an `approve` event lists it in `metadata.approves`.
+//! Approvals are ordinary journal events, so they travel with sync.
+
+use crate::cli::ApproveArgs;
+use crate::config::{AppConfig, now_utc};
+use crate::db::Db;
+use crate::domain::{EventPayload, RateContext, StoredEvent};
+use anyhow::{Result, anyhow};
+use std::collections::HashSet;
+use uuid::Uuid;
+
+/// Who entered the event, when it was recorded.
+pub fn event_entered_by(e: &StoredEvent) -> Option<&str> {
+    e.payload
+        .metadata
+        .get("entered_by")
+        .and_then(|v| v.as_str())
+}
+
+/// Ids of events entered by someone other than `me` that no `approve` event covers yet.
+pub fn pending_approval(events: &[StoredEvent], me: &str) -> HashSet<Uuid> {
+    let approved = approved_ids(events);
+    events
+        .iter()
+        .filter(|e| e.action != "approve")
+        .filter(|e| event_entered_by(e).is_some_and(|who| who != me))
+        .filter(|e| !approved.contains(&e.event_id))
+        .map(|e| e.event_id)
+        .collect()
+}
+
+fn approved_ids(events: &[StoredEvent]) -> HashSet<Uuid> {
+    events
+        .iter()
+        .filter(|e| e.action == "approve")
+        .filter_map(|e| e.payload.metadata.get("approves")?.as_array().cloned())
+        .flatten()
+        .filter_map(|v| v.as_str()?.parse().ok())
+        .collect()
+}
+
+pub fn handle_approve(db: &Db, cfg: &AppConfig, args: ApproveArgs) -> Result<()> {
+    let me = crate::entered_by(cfg, args.approver.as_deref());
+    let events = db.list_events()?;
+    let pending = pending_approval(&events, &me);
+
+    if args.event_ids.is_empty() && !args.all {
+        let waiting: Vec<&StoredEvent> = events
+            .iter()
+            .filter(|e| pending.contains(&e.event_id))
+            .collect();
+        if waiting.is_empty() {
+            println!("Nothing waiting for approval by {me}.");
+            return Ok(());
+        }
+        println!("effective_at\taction\tevent_id\tentered_by\tnote");
+        for e in waiting {
+            println!(
+                "{}\t{}\t{}\t{}\t{}",
+                e.effective_at.to_rfc3339(),
+                e.action,
+                e.event_id,
+                event_entered_by(e).unwrap_or("-"),
+                e.payload.note.as_deref().unwrap_or("")
+            );
+        }
+        return Ok(());
+    }
+
+    let ids: Vec<Uuid> = if args.all {
+        events
+            .iter()
+            .filter(|e| pending.contains(&e.event_id))
+            .map(|e| e.event_id)
+            .collect()
+    } else {
+        let mut ids = Vec::new();
+        for raw in &args.event_ids {
+            let id: Uuid = raw
+                .parse()
+                .map_err(|_| anyhow!("Invalid event id: {raw}"))?;
+            let Some(e) = events.iter().find(|e| e.event_id == id) else {
+                return Err(anyhow!("No such event: {id}"));
+            };
+            if !pending.contains(&id) {
+                let reason = match event_entered_by(e) {
+                    Some(who) if who == me => "you entered it",
+                    None => "it has no entered_by",
+                    Some(_) => "it is already approved",
+                };
+                return Err(anyhow!("Event {id} does not need your approval: {reason}"));
+            }
+            ids.push(id);
+        }
+        ids
+    };
+    if ids.is_empty() {
+        println!("Nothing waiting for approval by {me}.");
+        return Ok(());
+    }
+
+    let event_id = Uuid::new_v4();
+    let now = now_utc();
+    let payload = EventPayload {
+        schema_version: 1,
+        device_id: cfg.device_id,
+        workspace: cfg.current_workspace.clone(),
+        project: cfg.current_project.clone(),
+        action: "approve".to_string(),
+        created_at: now,
+        effective_at: now,
+        postings: vec![],
+        tags: vec![],
+        category: None,
+        note: None,
+        reference: None,
+        rate_context: RateContext {
+            provider: None,
+            override_rate: None,
+            base: None,
+            quote: None,
+            as_of: now,
+        },
+        basis: None,
+        metadata: serde_json::json!({
+            "event_id": event_id.to_string(),
+            "entered_by": me,
+            "approves": ids.iter().map(|id| id.to_string()).collect::<Vec<_>>(),
+        }),
+    };
+    db.insert_event(event_id, &payload)?;
+    println!("Approved {} event(s) as {me}.", ids.len());
+    Ok(())
+}
@@ -0,0 +1,297 @@
+//! Files attached to events (`--attach`, `bankero event attachments`).
+//!
+//! Receipts, invoices and warranty PDFs are stored once per workspace under
+//! `attachments/<sha256>` next to the journal, named by the SHA-256 of their content.
+//! Events reference them from `metadata.attachments` (`[{"sha256", "name", "size"}]`),
+//! so the journal stays small, identical files share one blob, and sync copies only the
+//! blobs a device is missing. A device keeps only blobs that events in its journal
+//! refer to, so a peer can't fill its store with unrelated files.
+
+use crate::db::Db;
+use crate::domain::{EventPayload, StoredEvent};
+use anyhow::{Context, Result, anyhow};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+
+/// Metadata key listing an event's attachments.
+pub const META_KEY: &str = "attachments";
+
+/// One attached file as recorded in the event.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Attachment {
+    pub sha256: String,
+    /// File name it was attached under (no directories).
+    pub name: String,
+    pub size: u64,
+}
+
+/// A file read for attaching; it reaches the blob store only when its event is written.
+pub struct Pending {
+    attachment: Attachment,
+    bytes: Vec<u8>,
+}
+
+pub fn sha256_hex(bytes: &[u8]) -> String {
+    to_hex(&Sha256::digest(bytes))
+}
+
+/// Reads and hashes `--attach` files.
+pub fn read(files: &[PathBuf]) -> Result<Vec<Pending>> {
+    files
+        .iter()
+        .map(|path| {
+            let bytes = std::fs::read(path)
+                .with_context(|| format!("Failed to read attachment {}", path.display()))?;
+            let name = path
+                .file_name()
+                .map(|n| n.to_string_lossy().into_owned())
+                .ok_or_else(|| anyhow!("--attach {} is not a file", path.display()))?;
+            Ok(Pending {
+                attachment: Attachment {
+                    sha256: sha256_hex(&bytes),
+                    name,
+                    size: bytes.len() as u64,
+                },
+                bytes,
+            })
+        })
+        .collect()
+}
+
+/// Records `pending` in the event's metadata.
+pub fn add_to(payload: &mut EventPayload, pending: &[Pending]) {
+    if pending.is_empty() {
+        return;
+    }
+    let list: Vec<&Attachment> = pending.iter().map(|p| &p.attachment).collect();
+    payload.metadata[META_KEY] = serde_json::json!(list);
+}
+
+/// Writes the blobs of `pending` to the store.
+pub fn save(db: &Db, pending: &[Pending]) -> Result<()> {
+    for p in pending {
+        put(db, &p.attachment.sha256, &p.bytes)?;
+    }
+    Ok(())
+}
+
+/// The attachments an event lists.
+///
+/// Entries synced from elsewhere are only trusted as far as a hash and a bare file name
+/// go, so neither can point outside the store or the `--out` directory.
+pub fn of(payload: &EventPayload) -> Vec<Attachment> {
+    let list: Vec<Attachment> = payload
+        .metadata
+        .get(META_KEY)
+        .and_then(|v| serde_json::from_value(v.clone()).ok())
+        .unwrap_or_default();
+    list.into_iter()
+        .filter(|a| a.sha256.len() == 64 && a.sha256.bytes().all(|b| b.is_ascii_hexdigit()))
+        .filter_map(|a| {
+            let name = Path::new(&a.name)
+                .file_name()?
+                .to_string_lossy()
+                .into_owned();
+            Some(Attachment { name, ..a })
+        })
+        .collect()
+}
+
+/// Every blob `events` refer to.
+pub fn referenced(events: &[StoredEvent]) -> BTreeSet<String> {
+    referenced_by(events.iter().map(|e| &e.payload))
+}
+
+/// Every blob the payloads refer to.
+pub fn referenced_by<'a>(payloads: impl IntoIterator<Item = &'a EventPayload>) -> BTreeSet<String> {
+    payloads
+        .into_iter()
+        .flat_map(of)
+        .map(|a| a.sha256)
+        .collect()
+}
+
+/// The blobs this device has.
+pub fn held(db: &Db) -> Result<BTreeSet<String>> {
+    let dir = db.attachments_dir();
+    if !dir.exists() {
+        return Ok(BTreeSet::new());
+    }
+    let mut out = BTreeSet::new();
+    for entry in
+        std::fs::read_dir(&dir).with_context(|| format!("Failed to read {}", dir.display()))?
+    {
+        let name = entry?.file_name().to_string_lossy().into_owned();
+        if !name.starts_with('.') {
+            out.insert(name);
+        }
+    }
+    Ok(out)
+}
+
+fn blob_path(db: &Db, sha256: &str) -> PathBuf {
+    db.attachments_dir().join(sha256)
+}
+
+/// The stored blob, if this device has it.
+pub fn get(db: &Db, sha256: &str) -> Result<Option<Vec<u8>>> {
+    let path = blob_path(db, sha256);
+    if !path.exists() {
+        return Ok(None);
+    }
+    let bytes =
+        std::fs::read(&path).with_context(|| format!("Failed to read {}", path.display()))?;
+    Ok(Some(bytes))
+}
+
+/// Stores a blob under its hash; returns whether it was new.
+///
+/// Blobs from other devices are checked against the name they arrive under, so a
+/// truncated or corrupted copy never replaces a missing one silently.
+pub fn put(db: &Db, sha256: &str, bytes: &[u8]) -> Result<bool> {
+    if sha256_hex(bytes) != sha256 {
+        return Err(anyhow!(
+            "Attachment {sha256} does not match its content; not stored"
+        ));
+    }
+    let path = blob_path(db, sha256);
+    if path.exists() {
+        return Ok(false);
+    }
+    let dir = db.attachments_dir();
+    std::fs::create_dir_all(&dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+    let tmp = dir.join(format!(".{sha256}.tmp"));
+    std::fs::write(&tmp, bytes).with_context(|| format!("Failed to write {}", tmp.display()))?;
+    std::fs::rename(&tmp, &path).with_context(|| format!("Failed to write {}", path.display()))?;
+    Ok(true)
+}
+
+/// Size of the blobs in a sync folder's `attachments` directory that are in `wanted`
+/// and missing here: what [`import_dir`] would copy.
+pub fn missing_bytes(db: &Db, dir: &Path, wanted: &BTreeSet<String>) -> Result<u64> {
+    if !dir.exists() {
+        return Ok(0);
+    }
+    let mut bytes = 0;
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        let sha256 = entry.file_name().to_string_lossy().into_owned();
+        if wanted.contains(&sha256) && !blob_path(db, &sha256).exists() {
+            bytes += entry.metadata()?.len();
+        }
+    }
+    Ok(bytes)
+}
+
+/// Copies the blobs in `wanted` from a sync folder's `attachments` directory into the
+/// store.
+pub fn import_dir(db: &Db, dir: &Path, wanted: &BTreeSet<String>) -> Result<usize> {
+    if !dir.exists() {
+        return Ok(0);
+    }
+    let mut imported = 0;
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        let sha256 = entry.file_name().to_string_lossy().into_owned();
+        if !wanted.contains(&sha256) || blob_path(db, &sha256).exists() {
+            continue;
+        }
+        let bytes = std::fs::read(entry.path())
+            .with_context(|| format!("Failed to read {}", entry.path().display()))?;
+        match put(db, &sha256, &bytes) {
+            Ok(true) => imported += 1,
+            Ok(false) => {}
+            Err(e) => eprintln!("Skipping {}: {e:#}", entry.path().display()),
+        }
+    }
+    Ok(imported)
+}
+
+/// Copies the blobs `events` refer to into a sync folder's `attachments` directory.
+///
+/// Each blob is written under a dot-name and renamed into place, so a device reading
+/// the folder mid-sync never sees half a file.
+pub fn export_dir(db: &Db, events: &[StoredEvent], dir: &Path) -> Result<()> {
+    for sha256 in referenced(events) {
+        let target = dir.join(&sha256);
+        if target.exists() {
+            continue;
+        }
+        let Some(bytes) = get(db, &sha256)? else {
+            continue;
+        };
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("Failed to create {}", dir.display()))?;
+        let tmp = dir.join(format!(".{sha256}.tmp"));
+        std::fs::write(&tmp, bytes)
+            .with_context(|| format!("Failed to write {}", tmp.display()))?;
+        std::fs::rename(&tmp, &target)
+            .with_context(|| format!("Failed to write {}", target.display()))?;
+    }
+    Ok(())
+}
+
+/// Hex text for blobs sent over the line-based LAN protocol.
+pub fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+pub fn from_hex(text: &str) -> Result<Vec<u8>> {
+    if !text.is_ascii() || !text.len().is_multiple_of(2) {
+        return Err(anyhow!("Attachment data is not hex"));
+    }
+    (0..text.len())
+        .step_by(2)
+        .map(|i| {
+            u8::from_str_radix(&text[i..i + 2], 16)
+                .map_err(|_| anyhow!("Attachment data is not hex"))
+        })
+        .collect()
+}
+
+pub fn handle_attachments(db: &Db, event_id: &str, out: Option<&Path>) -> Result<()> {
+    let events = crate::snapshot::load_events(db, true)?;
+    let e = crate::show::find_event(&events, event_id)?;
+    let attachments = of(&e.payload);
+    if attachments.is_empty() {
+        println!("(no attachments)");
+        return Ok(());
+    }
+    if let Some(out) = out {
+        std::fs::create_dir_all(out)
+            .with_context(|| format!("Failed to create {}", out.display()))?;
+    }
+    for a in attachments {
+        let path = blob_path(db, &a.sha256);
+        if !path.exists() {
+            println!(
+                "{}\t{}\t{} bytes\t(not on this device yet; sync with the device that attached it)",
+                &a.sha256[..12],
+                a.name,
+                a.size
+            );
+            continue;
+        }
+        match out {
+            Some(out) => {
+                let mut target = out.join(&a.name);
+                if target.exists() {
+                    target = out.join(format!("{}-{}", &a.sha256[..8], a.name));
+                }
+                std::fs::copy(&path, &target)
+                    .with_context(|| format!("Failed to write {}", target.display()))?;
+                println!("Saved {}", target.display());
+            }
+            None => println!(
+                "{}\t{}\t{} bytes\t{}",
+                &a.sha256[..12],
+                a.name,
+                a.size,
+                path.display()
+            ),
+        }
+    }
+    Ok(())
+}
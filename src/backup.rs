@@ -0,0 +1,87 @@
+//! Portable event backups (`bankero export events`, `bankero import events`).
+//!
+//! The file is JSONL in the sync folder's `WireEvent` shape, one `{"id", "payload"}` per
+//! line, so a backup is independent of the sync layout but readable by the same code.
+//! Events keep their ids: restoring a file twice, or onto a device that already synced
+//! the events, adds nothing. Only events of the current workspace are restored, and a
+//! file over the sync quotas is checked like a sync would be.
+
+use crate::cli::EventsFormat;
+use crate::config::AppConfig;
+use crate::db::Db;
+use crate::sync::WireEvent;
+use anyhow::{Context, Result};
+use std::io::Write;
+use std::path::Path;
+
+pub fn export_events(
+    db: &Db,
+    range: Option<&str>,
+    format: EventsFormat,
+    out: Option<&Path>,
+) -> Result<()> {
+    let window = range.map(crate::parse_date_range).transpose()?;
+    // Raw history: archived events instead of the snapshots that replaced them.
+    let mut events = crate::snapshot::load_events(db, true)?;
+    if let Some((start, end)) = window {
+        events.retain(|e| e.effective_at >= start && e.effective_at <= end);
+    }
+
+    let mut buf = Vec::new();
+    match format {
+        EventsFormat::Jsonl => {
+            for e in &events {
+                serde_json::to_writer(
+                    &mut buf,
+                    &WireEvent {
+                        id: e.event_id,
+                        payload: e.payload.clone(),
+                    },
+                )?;
+                buf.push(b'\n');
+            }
+        }
+    }
+    match out {
+        Some(path) => {
+            std::fs::write(path, &buf)
+                .with_context(|| format!("Failed to write {}", path.display()))?;
+            eprintln!("Wrote {} event(s) to {}", events.len(), path.display());
+        }
+        None => std::io::stdout().write_all(&buf)?,
+    }
+    Ok(())
+}
+
+pub fn import_events(db: &Db, cfg: &AppConfig, file: &Path, accept_large: bool) -> Result<()> {
+    let mut incoming = Vec::new();
+    let mut other_workspaces = 0usize;
+    for (i, line) in crate::sync::jsonl_read_lines(file)?.iter().enumerate() {
+        let ev: WireEvent = serde_json::from_str(line)
+            .with_context(|| format!("Failed to parse event {} in {}", i + 1, file.display()))?;
+        if ev.payload.workspace != cfg.current_workspace {
+            other_workspaces += 1;
+            continue;
+        }
+        incoming.push((ev.id, ev.payload));
+    }
+    let source = format!("{}", file.display());
+    crate::sync::check_quota(db, cfg, &source, &incoming, 0, accept_large)?;
+    let total = incoming.len();
+    let (imported, rejected) = crate::revoke::import_events(db, incoming)?;
+    print!(
+        "Imported {imported} event(s) from {}; {} already present",
+        file.display(),
+        total - imported - rejected
+    );
+    if rejected > 0 {
+        print!(", {rejected} rejected from revoked devices");
+    }
+    if other_workspaces > 0 {
+        print!(
+            ", {other_workspaces} skipped from other workspaces (check out their workspace with `bankero ws checkout` to restore them)"
+        );
+    }
+    println!(".");
+    Ok(())
+}
@@ -0,0 +1,218 @@
+//! Many commands in one run (`bankero batch`).
+//!
+//! Lines are split like a shell would (quotes and backslashes), parsed with the same
+//! CLI definitions as typed commands, and only then run, all inside one SQLite
+//! transaction. A typo on line 40 therefore fails before line 1 is written, and an error
+//! while recording rolls back the whole batch.
+//!
+//! Nothing in a batch can prompt: an event over `confirm_over` fails its line unless the
+//! batch runs with `--yes`. "Wrote event" lines are held until the transaction commits,
+//! so a batch that rolls back never claims to have written anything.
+
+use crate::cli::{BatchArgs, Cli, Command, RateCommand};
+use crate::config::{AppConfig, AppPaths};
+use crate::db::Db;
+use anyhow::{Context, Result, anyhow};
+use clap::Parser;
+use std::cell::RefCell;
+use std::ffi::OsString;
+use std::io::Read;
+use std::path::Path;
+
+/// The batch running on this thread: its `--yes` and the "Wrote event" lines it holds.
+struct Running {
+    yes: bool,
+    wrote: Vec<String>,
+}
+
+thread_local! {
+    static RUNNING: RefCell<Option<Running>> = const { RefCell::new(None) };
+}
+
+/// Inside a batch, whether it was given `--yes`; `None` outside one.
+pub fn yes() -> Option<bool> {
+    RUNNING.with(|r| r.borrow().as_ref().map(|r| r.yes))
+}
+
+/// Prints a "Wrote event" line, or holds it until the running batch commits.
+pub fn wrote(line: String) {
+    RUNNING.with(|r| match r.borrow_mut().as_mut() {
+        Some(running) => running.wrote.push(line),
+        None => println!("{line}"),
+    });
+}
+
+/// Whether a batch line may run `cmd`: commands that record events, and `rate set`.
+fn allowed(cmd: &Command) -> bool {
+    match cmd {
+        Command::Deposit(_)
+        | Command::Withdraw(_)
+        | Command::Move(_)
+        | Command::Exchange(_)
+        | Command::Settle(_)
+        | Command::Lend(_)
+        | Command::Borrow(_)
+        | Command::Refund(_)
+        | Command::Buy(_)
+        | Command::Sell(_)
+        | Command::Q(_) => true,
+        Command::Rate(args) => matches!(args.command, RateCommand::Set(_)),
+        _ => false,
+    }
+}
+
+/// Splits a line into words: whitespace separates, quotes group, `\` escapes.
+fn split_words(line: &str) -> Result<Vec<String>> {
+    let mut words = Vec::new();
+    let mut word: Option<String> = None;
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => {
+                if let Some(w) = word.take() {
+                    words.push(w);
+                }
+            }
+            '\'' => {
+                let w = word.get_or_insert_with(String::new);
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(c) => w.push(c),
+                        None => return Err(anyhow!("Unclosed ' quote")),
+                    }
+                }
+            }
+            '"' => {
+                let w = word.get_or_insert_with(String::new);
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some(c @ ('"' | '\\')) => w.push(c),
+                            Some(c) => {
+                                w.push('\\');
+                                w.push(c);
+                            }
+                            None => return Err(anyhow!("Unclosed \" quote")),
+                        },
+                        Some(c) => w.push(c),
+                        None => return Err(anyhow!("Unclosed \" quote")),
+                    }
+                }
+            }
+            '\\' => {
+                let next = chars.next().ok_or_else(|| anyhow!("Trailing \\"))?;
+                word.get_or_insert_with(String::new).push(next);
+            }
+            c => word.get_or_insert_with(String::new).push(c),
+        }
+    }
+    words.extend(word);
+    Ok(words)
+}
+
+/// Parses one batch line into a command, expanding templates.
+fn parse_line(line: &str, db: &Db, globals: &[OsString]) -> Result<Command> {
+    let words = split_words(line)?;
+    if words.iter().any(|w| w == "--confirm") {
+        return Err(anyhow!(
+            "--confirm prompts for each event; batches run without prompts"
+        ));
+    }
+    let mut argv = globals.to_vec();
+    argv.extend(words.iter().map(OsString::from));
+    let parse = |argv: Vec<OsString>| {
+        Cli::try_parse_from(crate::exchange::strip_arrow(argv)?).map_err(|e| {
+            anyhow!(
+                "{}",
+                e.render()
+                    .to_string()
+                    .lines()
+                    .next()
+                    .unwrap_or_default()
+                    .trim_start_matches("error: ")
+            )
+        })
+    };
+    let cli = parse(argv)?;
+    let cli = match crate::templates::expand(&cli, db)? {
+        Some(argv) => parse(argv)?,
+        None => cli,
+    };
+    if !allowed(&cli.command) {
+        return Err(anyhow!(
+            "`{}` cannot run in a batch; only event commands, `rate set` and templates can",
+            words.first().map(String::as_str).unwrap_or_default()
+        ));
+    }
+    Ok(cli.command)
+}
+
+pub fn handle_batch(
+    args: BatchArgs,
+    db: &Db,
+    db_path: &Path,
+    paths: &AppPaths,
+    globals: &[OsString],
+    cfg: &mut AppConfig,
+    cfg_path: &Path,
+) -> Result<()> {
+    let text = match &args.file {
+        Some(file) => std::fs::read_to_string(file)
+            .with_context(|| format!("Failed to read {}", file.display()))?,
+        None => {
+            let mut text = String::new();
+            std::io::stdin()
+                .read_to_string(&mut text)
+                .context("Failed to read commands from stdin")?;
+            text
+        }
+    };
+
+    let mut commands = Vec::new();
+    let mut errors = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        match parse_line(line, db, globals) {
+            Ok(cmd) => commands.push((i + 1, cmd)),
+            Err(e) => errors.push(format!("line {}: {e:#}", i + 1)),
+        }
+    }
+    if !errors.is_empty() {
+        return Err(anyhow!(
+            "{} invalid line(s); nothing was written:\n  {}",
+            errors.len(),
+            errors.join("\n  ")
+        ));
+    }
+    if commands.is_empty() {
+        println!("(no commands)");
+        return Ok(());
+    }
+
+    let count = commands.len();
+    RUNNING.with(|r| {
+        *r.borrow_mut() = Some(Running {
+            yes: args.yes,
+            wrote: Vec::new(),
+        })
+    });
+    let result = db.with_transaction(|| {
+        for (line, cmd) in commands {
+            crate::dispatch(cmd, db, db_path, paths, globals, cfg, cfg_path)
+                .with_context(|| format!("line {line} failed; nothing was written"))?;
+        }
+        Ok(())
+    });
+    let running = RUNNING.with(|r| r.borrow_mut().take());
+    result?;
+    for line in running.map(|r| r.wrote).unwrap_or_default() {
+        println!("{line}");
+    }
+    println!("Batch: {count} command(s) written in one transaction.");
+    Ok(())
+}
@@ -0,0 +1,155 @@
+//! Synthetic journals for benchmarks (`bankero bench-gen`, `bench` feature only).
+//!
+//! Produces a deterministic mix of salary deposits, categorized buys and transfers
+//! spread over consecutive months, plus one budget per expense category, so
+//! `benches/journal.rs` can time realistic replays at 10k/100k/1M events.
+
+use crate::cli::BenchGenArgs;
+use crate::config::AppConfig;
+use crate::db::{Db, StoredBudget};
+use crate::domain::{EventPayload, Posting, RateContext};
+use anyhow::{Result, anyhow};
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use rust_decimal::Decimal;
+use uuid::Uuid;
+
+const CATEGORIES: [&str; 5] = [
+    "expenses:food",
+    "expenses:rent",
+    "expenses:transport",
+    "expenses:fun",
+    "expenses:health",
+];
+
+/// Small xorshift generator; the journal only needs to be reproducible, not random.
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn below(&mut self, n: u64) -> u64 {
+        self.next() % n
+    }
+}
+
+pub fn handle_bench_gen(db: &Db, cfg: &AppConfig, args: BenchGenArgs) -> Result<()> {
+    if args.events == 0 {
+        return Err(anyhow!("--events must be at least 1"));
+    }
+    if args.months == 0 {
+        return Err(anyhow!("--months must be at least 1"));
+    }
+    let start = Utc
+        .with_ymd_and_hms(2026, 1, 1, 0, 0, 0)
+        .single()
+        .ok_or_else(|| anyhow!("Invalid start date"))?;
+    let span = Duration::days(30 * i64::from(args.months));
+    let mut rng = Rng(args.seed.max(1));
+
+    db.with_transaction(|| {
+        for n in 0..args.events {
+            // Events are spread evenly over the span, so month filters see similar volumes.
+            let offset = span.num_seconds() * n as i64 / args.events as i64;
+            let at = start + Duration::seconds(offset);
+            let payload = synthetic_event(cfg, &mut rng, at);
+            db.insert_event(Uuid::new_v4(), &payload)?;
+        }
+        for category in CATEGORIES {
+            db.insert_budget(&StoredBudget {
+                id: Uuid::new_v4(),
+                name: category.trim_start_matches("expenses:").to_string(),
+                amount: Decimal::from(500u32),
+                commodity: "USD".to_string(),
+                month: None,
+                category: Some(category.to_string()),
+                account: None,
+                provider: None,
+                auto_reserve_from: None,
+                auto_reserve_until_amount: None,
+                device_id: Some(cfg.device_id),
+                project: None,
+                created_at: start,
+                updated_at: None,
+            })?;
+        }
+        Ok(())
+    })?;
+
+    println!(
+        "Generated {} event(s) over {} month(s) and {} budget(s).",
+        args.events,
+        args.months,
+        CATEGORIES.len()
+    );
+    Ok(())
+}
+
+fn synthetic_event(cfg: &AppConfig, rng: &mut Rng, at: DateTime<Utc>) -> EventPayload {
+    let cents = |rng: &mut Rng, max: u64| Decimal::new((1 + rng.below(max * 100)) as i64, 2);
+    let (action, from, to, amount, category) = match rng.below(10) {
+        0 => (
+            "deposit",
+            "income:salary".to_string(),
+            "assets:bank".to_string(),
+            cents(rng, 3000),
+            None,
+        ),
+        1 => (
+            "move",
+            "assets:bank".to_string(),
+            "assets:cash".to_string(),
+            cents(rng, 200),
+            None,
+        ),
+        _ => {
+            let category = CATEGORIES[rng.below(CATEGORIES.len() as u64) as usize];
+            (
+                "buy",
+                "assets:bank".to_string(),
+                category.to_string(),
+                cents(rng, 120),
+                Some(category.to_string()),
+            )
+        }
+    };
+
+    EventPayload {
+        schema_version: 1,
+        device_id: cfg.device_id,
+        workspace: cfg.current_workspace.clone(),
+        project: cfg.current_project.clone(),
+        action: action.to_string(),
+        created_at: at,
+        effective_at: at,
+        postings: vec![
+            Posting {
+                account: from,
+                commodity: "USD".to_string(),
+                amount: -amount,
+            },
+            Posting {
+                account: to,
+                commodity: "USD".to_string(),
+                amount,
+            },
+        ],
+        tags: vec![],
+        category,
+        note: None,
+        reference: None,
+        rate_context: RateContext {
+            provider: None,
+            override_rate: None,
+            base: None,
+            quote: None,
+            as_of: at,
+        },
+        basis: None,
+        metadata: serde_json::json!({ "synthetic": true }),
+    }
+}
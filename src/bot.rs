@@ -0,0 +1,249 @@
+//! Chat bot capture channel (`bankero bot`).
+//!
+//! Messages are quick entries (`45.2 USD groceries @cash`) or commands (`/balance`).
+//! Entries are built with the same event builders as the CLI and answered with a
+//! preview; nothing is written until the chat replies `/yes`.
+//!
+//! Transports: Telegram long polling (`--telegram-token`) or line-based stdin/stdout
+//! (`--stdin`), which is handy for scripting and offline testing.
+//!
+//! With `--api-token` the bot is limited to that token's scope: a `read-only` token
+//! answers `/balance` but refuses entries.
+
+use crate::cli::{BotArgs, CommonEventFlags};
+use crate::config::{ApiToken, AppConfig};
+use crate::db::Db;
+use crate::domain::EventPayload;
+use crate::tokens::TokenScope;
+use anyhow::{Context, Result, anyhow};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::io::{self, BufRead, Write};
+use std::time::Duration;
+use uuid::Uuid;
+
+const TELEGRAM_API: &str = "https://api.telegram.org";
+const POLL_TIMEOUT_SECS: u64 = 30;
+
+/// Events awaiting `/yes`, keyed by chat id.
+type Pending = HashMap<i64, (Uuid, EventPayload)>;
+
+pub fn handle_bot(db: &Db, cfg: &AppConfig, args: BotArgs) -> Result<()> {
+    let api_token = args
+        .api_token
+        .as_deref()
+        .map(|raw| crate::tokens::authenticate(cfg, raw))
+        .transpose()?;
+    if let Some(t) = api_token {
+        eprintln!("Using {} token '{}'.", t.scope.as_str(), t.name);
+    }
+    if args.stdin {
+        return run_stdin(db, cfg, api_token);
+    }
+    let Some(token) = args.telegram_token else {
+        return Err(anyhow!(
+            "Choose a transport: --telegram-token <token> (or BANKERO_TELEGRAM_TOKEN) or --stdin"
+        ));
+    };
+    if args.allow_chat.is_empty() {
+        eprintln!(
+            "Warning: no --allow-chat given; every chat will be refused. Message the bot once to learn your chat id."
+        );
+    }
+    run_telegram(db, cfg, &token, &args.allow_chat, api_token)
+}
+
+fn run_stdin(db: &Db, cfg: &AppConfig, token: Option<&ApiToken>) -> Result<()> {
+    let mut pending = Pending::new();
+    let stdin = io::stdin();
+    let mut out = io::stdout();
+    for line in stdin.lock().lines() {
+        let line = line.context("Failed to read stdin")?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let reply = reply_to(db, cfg, token, &mut pending, 0, &line);
+        writeln!(out, "{reply}")?;
+        out.flush().ok();
+    }
+    Ok(())
+}
+
+#[derive(Debug, Deserialize)]
+struct TgResponse<T> {
+    ok: bool,
+    result: Option<T>,
+    description: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TgUpdate {
+    update_id: i64,
+    message: Option<TgMessage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TgMessage {
+    chat: TgChat,
+    text: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TgChat {
+    id: i64,
+}
+
+fn run_telegram(
+    db: &Db,
+    cfg: &AppConfig,
+    token: &str,
+    allowed: &[i64],
+    api_token: Option<&ApiToken>,
+) -> Result<()> {
+    let client = reqwest::blocking::Client::builder()
+        .timeout(Duration::from_secs(POLL_TIMEOUT_SECS + 10))
+        .build()?;
+    let base = format!("{TELEGRAM_API}/bot{token}");
+    let mut pending = Pending::new();
+    let mut offset: i64 = 0;
+
+    eprintln!("Bot running (Telegram long polling). Press Ctrl+C to stop.");
+    loop {
+        let resp: TgResponse<Vec<TgUpdate>> = match client
+            .get(format!("{base}/getUpdates"))
+            .query(&[
+                ("timeout", POLL_TIMEOUT_SECS.to_string()),
+                ("offset", offset.to_string()),
+            ])
+            .send()
+            .and_then(|r| r.json())
+        {
+            Ok(r) => r,
+            Err(e) => {
+                eprintln!("getUpdates failed: {e}; retrying in 5s");
+                std::thread::sleep(Duration::from_secs(5));
+                continue;
+            }
+        };
+        if !resp.ok {
+            return Err(anyhow!(
+                "Telegram rejected getUpdates: {}",
+                resp.description.unwrap_or_default()
+            ));
+        }
+
+        for update in resp.result.unwrap_or_default() {
+            offset = offset.max(update.update_id + 1);
+            let Some(msg) = update.message else {
+                continue;
+            };
+            let Some(text) = msg.text else {
+                continue;
+            };
+            let chat_id = msg.chat.id;
+            let reply = if allowed.contains(&chat_id) {
+                reply_to(db, cfg, api_token, &mut pending, chat_id, &text)
+            } else {
+                eprintln!("Refused message from chat {chat_id} (not in --allow-chat).");
+                format!("Chat {chat_id} is not allowed. Start the bot with --allow-chat {chat_id}.")
+            };
+            let sent = client
+                .post(format!("{base}/sendMessage"))
+                .json(&serde_json::json!({ "chat_id": chat_id, "text": reply }))
+                .send();
+            if let Err(e) = sent {
+                eprintln!("sendMessage failed: {e}");
+            }
+        }
+    }
+}
+
+/// Handles one incoming message and returns the reply text.
+fn reply_to(
+    db: &Db,
+    cfg: &AppConfig,
+    token: Option<&ApiToken>,
+    pending: &mut Pending,
+    chat_id: i64,
+    text: &str,
+) -> String {
+    match respond(db, cfg, token, pending, chat_id, text.trim()) {
+        Ok(reply) => reply,
+        Err(e) => format!("Error: {e:#}"),
+    }
+}
+
+fn respond(
+    db: &Db,
+    cfg: &AppConfig,
+    token: Option<&ApiToken>,
+    pending: &mut Pending,
+    chat_id: i64,
+    text: &str,
+) -> Result<String> {
+    let (command, rest) = match text.split_once(char::is_whitespace) {
+        Some((c, r)) => (c, r.trim()),
+        None => (text, ""),
+    };
+    match command {
+        "/start" | "/help" => Ok(HELP.to_string()),
+        "/balance" => {
+            let events = crate::snapshot::replay_posted(db, false, false)?;
+            let prefix = (!rest.is_empty()).then_some(rest);
+            let balances = crate::compute_balances(&events, prefix);
+            if balances.is_empty() {
+                return Ok("(no balances)".to_string());
+            }
+            Ok(balances
+                .iter()
+                .map(|((acct, comm), amt)| format!("{acct} {amt} {comm}"))
+                .collect::<Vec<_>>()
+                .join("\n"))
+        }
+        "/yes" => {
+            crate::tokens::require(token, TokenScope::WriteEvents, "writing events")?;
+            let Some((event_id, payload)) = pending.remove(&chat_id) else {
+                return Ok("Nothing to confirm.".to_string());
+            };
+            db.insert_event(event_id, &payload)?;
+            Ok(format!("Wrote event {event_id}."))
+        }
+        "/no" => Ok(match pending.remove(&chat_id) {
+            Some(_) => "Discarded.".to_string(),
+            None => "Nothing to discard.".to_string(),
+        }),
+        c if c.starts_with('/') => Ok(format!("Unknown command {c}. Send /help.")),
+        _ => {
+            crate::tokens::require(token, TokenScope::WriteEvents, "writing events")?;
+            let event_id = Uuid::new_v4();
+            let mut payload = crate::build_quick_event(
+                db,
+                cfg,
+                event_id,
+                text.to_string(),
+                CommonEventFlags::default(),
+            )?;
+            crate::commodities::check_known(db, cfg, &payload)?;
+            crate::commodities::check_account_defaults(db, &payload)?;
+            crate::limits::enforce(db, &mut payload, false)?;
+            let preview = preview(&payload);
+            pending.insert(chat_id, (event_id, payload));
+            Ok(format!("{preview}\nReply /yes to write or /no to discard."))
+        }
+    }
+}
+
+fn preview(payload: &EventPayload) -> String {
+    let mut lines = vec![format!("Preview ({}):", payload.action)];
+    for p in &payload.postings {
+        lines.push(format!("  {} {} {}", p.account, p.amount, p.commodity));
+    }
+    if !payload.tags.is_empty() {
+        lines.push(format!("  tags: {}", payload.tags.join(", ")));
+    }
+    lines.join("\n")
+}
+
+const HELP: &str = "Send a quick entry like \"45.2 USD groceries @cash #tag\", then /yes to write it.\n\
+/balance [account-prefix] shows balances.\n\
+/no discards the pending entry.";
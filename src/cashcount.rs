@@ -0,0 +1,201 @@
+//! Cash drawer reconciliation (`bankero cashcount`).
+//!
+//! The user lists the bills and coins they counted; the totals per commodity are
+//! compared with the cash account's balance at the time of the count. A difference can
+//! be posted as a `cashcount` event against a counter account (by default
+//! `expenses:cash-difference`), which brings the ledger back in line with the drawer.
+
+use crate::cli::CashcountArgs;
+use crate::config::{AppConfig, now_utc};
+use crate::db::Db;
+use crate::domain::{EventPayload, Posting, RateContext};
+use anyhow::{Result, anyhow};
+use rust_decimal::Decimal;
+use std::collections::BTreeMap;
+use std::io::IsTerminal;
+use uuid::Uuid;
+
+/// One counted pile: `count` pieces of `denomination` `commodity`.
+struct Pile {
+    denomination: Decimal,
+    count: Decimal,
+    commodity: String,
+}
+
+/// Parses "20x10 USD, 5x5 USD". The commodity may be left out when the account has a
+/// default commodity.
+fn parse_denominations(raw: &str, default: Option<&str>) -> Result<Vec<Pile>> {
+    let mut piles = Vec::new();
+    for item in raw
+        .split([',', ';'])
+        .map(str::trim)
+        .filter(|i| !i.is_empty())
+    {
+        let invalid = || {
+            anyhow!(
+                "Invalid denomination '{item}'. Expected <denomination>x<count> <commodity>, e.g. 20x10 USD"
+            )
+        };
+        let mut parts = item.split_whitespace();
+        let pile = parts.next().ok_or_else(invalid)?;
+        let commodity = match (parts.next(), default) {
+            (Some(c), _) => c.to_ascii_uppercase(),
+            (None, Some(c)) => c.to_string(),
+            (None, None) => return Err(invalid()),
+        };
+        if parts.next().is_some() {
+            return Err(invalid());
+        }
+        let (denomination, count) = pile
+            .to_ascii_lowercase()
+            .split_once(['x', '*'])
+            .map(|(d, c)| (d.trim().parse::<Decimal>(), c.trim().parse::<Decimal>()))
+            .ok_or_else(invalid)?;
+        let (Ok(denomination), Ok(count)) = (denomination, count) else {
+            return Err(invalid());
+        };
+        if denomination <= Decimal::ZERO || count < Decimal::ZERO || !count.fract().is_zero() {
+            return Err(invalid());
+        }
+        piles.push(Pile {
+            denomination,
+            count,
+            commodity,
+        });
+    }
+    if piles.is_empty() {
+        return Err(anyhow!("--denominations must list at least one pile"));
+    }
+    Ok(piles)
+}
+
+pub fn handle_cashcount(db: &Db, cfg: &AppConfig, args: CashcountArgs) -> Result<()> {
+    let account = args.account.trim();
+    let default = db.account_commodities()?.remove(account);
+    let piles = parse_denominations(&args.denominations, default.as_deref())?;
+    let at = crate::parse_rfc3339_or_now(args.effective_at.as_deref())?;
+
+    let mut counted: BTreeMap<String, Decimal> = BTreeMap::new();
+    println!("Counted {account}:");
+    for pile in &piles {
+        let total = pile.denomination * pile.count;
+        println!(
+            "  {} {} x {} = {}",
+            pile.denomination.normalize(),
+            pile.commodity,
+            pile.count.normalize(),
+            total.normalize()
+        );
+        *counted
+            .entry(pile.commodity.clone())
+            .or_insert(Decimal::ZERO) += total;
+    }
+
+    let events = crate::snapshot::replay_posted(db, false, false)?;
+    let mut ledger: BTreeMap<String, Decimal> = BTreeMap::new();
+    for e in events.iter().filter(|e| e.effective_at <= at) {
+        for p in e.payload.postings.iter().filter(|p| p.account == account) {
+            *ledger.entry(p.commodity.clone()).or_insert(Decimal::ZERO) += p.amount;
+        }
+    }
+
+    let mut differences = Vec::new();
+    for (commodity, total) in &counted {
+        let balance = ledger.get(commodity).copied().unwrap_or(Decimal::ZERO);
+        let difference = *total - balance;
+        println!(
+            "{commodity}\tcounted {}\tledger {}\tdifference {}",
+            total.normalize(),
+            balance.normalize(),
+            difference.normalize()
+        );
+        if !difference.is_zero() {
+            differences.push((commodity.clone(), *total, balance, difference));
+        }
+    }
+    if differences.is_empty() {
+        println!("The count matches the ledger.");
+        return Ok(());
+    }
+
+    let summary = differences
+        .iter()
+        .map(|(c, _, _, d)| format!("{} {c}", d.normalize()))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let write = if args.yes {
+        true
+    } else if !std::io::stdin().is_terminal() {
+        println!("Re-run with --yes to post the adjustment ({summary}).");
+        false
+    } else {
+        crate::prompt_yes_no(&format!(
+            "Post an adjustment of {summary} to {account} against {}? [Y/n] ",
+            args.counter_account
+        ))?
+    };
+    if !write {
+        return Ok(());
+    }
+
+    let mut postings = Vec::new();
+    for (commodity, _, _, difference) in &differences {
+        postings.push(Posting {
+            account: account.to_string(),
+            commodity: commodity.clone(),
+            amount: *difference,
+        });
+        postings.push(Posting {
+            account: args.counter_account.clone(),
+            commodity: commodity.clone(),
+            amount: -*difference,
+        });
+    }
+    let event_id = Uuid::new_v4();
+    let payload = EventPayload {
+        schema_version: 1,
+        device_id: cfg.device_id,
+        workspace: cfg.current_workspace.clone(),
+        project: cfg.current_project.clone(),
+        action: "cashcount".to_string(),
+        created_at: now_utc(),
+        effective_at: at,
+        postings,
+        tags: vec![],
+        category: Some(args.counter_account.clone()),
+        note: Some(format!("Cash count of {account}")),
+        reference: None,
+        rate_context: RateContext {
+            provider: None,
+            override_rate: None,
+            base: None,
+            quote: None,
+            as_of: at,
+        },
+        basis: None,
+        metadata: serde_json::json!({
+            "event_id": event_id.to_string(),
+            "entered_by": crate::entered_by(cfg, None),
+            "denominations": args.denominations.trim(),
+            "counted": differences
+                .iter()
+                .map(|(c, total, _, _)| (c.clone(), total.to_string()))
+                .collect::<BTreeMap<_, _>>(),
+            "ledger": differences
+                .iter()
+                .map(|(c, _, balance, _)| (c.clone(), balance.to_string()))
+                .collect::<BTreeMap<_, _>>(),
+        }),
+    };
+    // The count was already confirmed above (or with --yes).
+    let options = crate::WriteOptions {
+        approved: true,
+        ..Default::default()
+    };
+    if !crate::maybe_confirm_and_insert(db, cfg, event_id, &payload, &options)? {
+        return Ok(());
+    }
+    println!("Posted the adjustment ({summary}).");
+    println!("Wrote event {event_id}");
+    Ok(())
+}
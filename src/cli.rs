@@ -9,19 +9,44 @@ use rust_decimal::Decimal;
     long_about = None,
     version,
     propagate_version = true,
-    infer_long_args = true
+    infer_long_args = true,
+    disable_help_subcommand = true
 )]
 pub struct Cli {
     /// Override Bankero home directory (config/data subdirs will be created inside it).
     #[arg(long, env = "BANKERO_HOME")]
     pub home: Option<std::path::PathBuf>,
 
+    /// Config profile to use (its own device, workspaces and sync settings).
+    #[arg(long, global = true, env = "BANKERO_PROFILE")]
+    pub profile: Option<String>,
+
     #[command(subcommand)]
     pub command: Command,
 }
 
 #[derive(Debug, Subcommand)]
 pub enum Command {
+    #[command(
+        about = "Set up Bankero interactively (first run)",
+        long_about = r#"Set up Bankero interactively (first run).
+
+Asks for the reference commodity, your timezone (UTC offset), the default account for
+quick entries, an optional sync folder, and opening balances, then writes the config
+and one `opening` event balanced against equity:opening-balances.
+
+Opening balances are entered as `ACCOUNT AMOUNT [COMMODITY]`, one per line; owed money
+is negative (e.g. `liabilities:card -300`). Flags pre-fill the answers; with --yes no
+questions are asked. Opening balances are skipped if the journal already has events.
+
+Examples:
+    bankero init
+    bankero init --yes --commodity EUR --timezone +01:00 --default-account assets:cash \
+        --opening "assets:bank 2500" --opening "liabilities:card -300"
+"#
+    )]
+    Init(InitArgs),
+
     #[command(
         about = "Record a deposit between two accounts",
         long_about = r#"Record a deposit between two accounts.
@@ -37,6 +62,22 @@ Examples:
     )]
     Deposit(DepositArgs),
 
+    #[command(
+        about = "Record a cash withdrawal",
+        long_about = r#"Record a cash withdrawal (ATM, bank teller, cash back).
+
+Writes a "withdraw" event that takes the amount out of --from (usually a bank
+account) and puts it into --to (usually a cash account). It moves money like
+`deposit`, but its own action keeps withdrawals apart in reports
+(`report --action withdraw`).
+
+Examples:
+    bankero withdraw 200 USD --from assets:bank --to assets:cash
+    bankero withdraw 40 EUR --from assets:bank --to assets:wallet -m "airport ATM"
+"#
+    )]
+    Withdraw(WithdrawArgs),
+
     #[command(
         about = "Move value between accounts (optionally cross-currency)",
         long_about = r#"Move value between accounts.
@@ -53,26 +94,161 @@ Cross-currency move (compute quote amount from stored provider rate):
 
 Provider context (used in --confirm preview for value/rate):
     bankero move 100 USD --from assets:usd --to assets:ves 3600 VES @binance --confirm
+
+Money in transit (wires, Zelle): --pending posts to assets:in-transit (config key
+transit_account) and `bankero settle` completes the move on the arrival date:
+    bankero move 500 USD --from assets:chase --to assets:mercantil --pending
+    bankero settle 3f2a9c1e --effective-at 2026-03-04T10:00:00Z
 "#
     )]
     Move(MoveArgs),
 
+    #[command(
+        about = "Swap one currency for another between your own accounts",
+        long_about = r#"Swap one currency for another between your own accounts.
+
+The rate always comes from the provider token: its stored rate at --effective-at (or
+--as-of), or an explicit one like @binance:36.5. The received amount is computed from
+it and rounded by the target commodity's rule; what rounding drops goes to the
+rounding account. With --vs, the spread against a reference provider is printed and
+recorded in the event's metadata.
+
+The arrow is optional; quote it in a shell, where a bare > redirects output.
+
+Examples:
+    bankero exchange 100 USD '->' VES @bcv --from assets:usd --to assets:ves
+    bankero exchange 100 USD VES @binance:36.5 --from assets:usd --to assets:ves --vs @bcv
+    bankero exchange 0.5 BTC USDT @binance --from assets:btc --to assets:usdt --fee 2 USDT
+"#
+    )]
+    Exchange(ExchangeArgs),
+
+    #[command(
+        about = "Complete a pending (in-transit) move",
+        long_about = r#"Complete a pending (in-transit) move.
+
+`move --pending` takes the money out of the source account right away but parks it
+in assets:in-transit (config key transit_account). `settle` writes the second half
+on the day the funds arrive, so neither balance is wrong in between. Without an
+event id it lists the transfers still in transit. Repayments of loans between people
+are `bankero debts settle`.
+
+Examples:
+    bankero settle
+    bankero settle 3f2a9c1e
+    bankero settle 3f2a9c1e --effective-at 2026-03-04T10:00:00Z
+"#
+    )]
+    Settle(SettleArgs),
+
+    #[command(
+        about = "Record money you lent to someone",
+        long_about = r#"Record money you lent to someone.
+
+Writes a "lend" event that moves the amount from --from into the person's receivable
+account (assets:receivable:<person>). `bankero debts` shows who owes you what, and
+`bankero debts settle` records repayments.
+
+Examples:
+    bankero lend ana 50 USD --from assets:cash
+    bankero lend "Carlos P" 200 VES --from assets:bank -m "taxi fare"
+"#
+    )]
+    Lend(LendArgs),
+
+    #[command(
+        about = "Record money you borrowed from someone",
+        long_about = r#"Record money you borrowed from someone.
+
+Writes a "borrow" event that puts the amount into --to and books it as owed to the
+person (liabilities:payable:<person>). `bankero debts` shows what you owe, and
+`bankero debts settle` records paying it back.
+
+Examples:
+    bankero borrow bob 100 USD --to assets:bank
+"#
+    )]
+    Borrow(BorrowArgs),
+
+    #[command(
+        about = "Who owes whom: outstanding loans per person, and repayments",
+        long_about = r#"Who owes whom: outstanding loans per person, and repayments.
+
+Lists the net balance with each person per commodity: positive means they owe you,
+negative means you owe them. Money lent and borrowed with the same person nets out.
+Settled people are hidden unless --all.
+
+`debts settle` records a repayment in either direction (whichever way the debt
+runs), in full by default or in part with an amount.
+
+Examples:
+    bankero debts
+    bankero debts settle ana 20 USD --account assets:cash
+    bankero debts settle bob --account assets:bank
+"#
+    )]
+    Debts(DebtsArgs),
+
+    #[command(
+        about = "Count the cash in hand and reconcile it with the ledger",
+        long_about = r#"Count the cash in hand and reconcile it with the ledger.
+
+--denominations lists what is in the drawer as <denomination>x<count> <commodity>,
+comma-separated: "20x10 USD" is ten 20 USD bills (the commodity may be left out when
+the account has one set with `commodity default`). The total per commodity is compared
+with the account's balance; on a difference you are offered a "cashcount" event that
+posts it against --counter-account, so the ledger matches the drawer again.
+
+Examples:
+    bankero cashcount assets:cash --denominations "20x10 USD, 5x5 USD"
+    bankero cashcount assets:cash --denominations "100x3 VES, 1x12 USD" --yes
+"#
+    )]
+    Cashcount(CashcountArgs),
+
+    #[command(
+        about = "Refund an earlier purchase, in full or in part",
+        long_about = r#"Refund an earlier purchase, in full or in part.
+
+Writes a "refund" event with the buy's postings reversed and links it to the buy
+(metadata.refunds). Without --amount it refunds whatever is left of the purchase;
+--amount refunds part of it, in the commodity that was paid, and every leg (splits,
+tax, tip) is scaled by the same share. Refunds keep the buy's category, so category
+budgets count them against the month's spending.
+
+Examples:
+    bankero refund 3f2a9c1e
+    bankero refund 3f2a9c1e --amount 15 -m "one item returned"
+    bankero refund 3f2a9c1e --effective-at 2026-03-10T12:00:00Z
+"#
+    )]
+    Refund(RefundArgs),
+
     #[command(
         about = "Record a buy (payee form or split form)",
         long_about = r#"Record a buy.
 
 Two supported forms:
 
-1) Payee form (3-arg):
+1) Payee form (3-arg, or explicit --payee):
     bankero buy <payee> <amount> <commodity> --from <account>
+    bankero buy <amount> <commodity> --payee <payee> --from <account>
 
 2) Split form (2-arg):
     bankero buy <amount> <commodity> --from <account> --to <account:amount> [--to ...]
 
 Examples:
     bankero buy merchant:amazon 39.99 USD --from assets:cash
+    bankero buy 39.99 USD --payee merchant:amazon --from assets:cash
     bankero buy 100 USD --from assets:cash --to expenses:food:60 --to expenses:transport:40
 
+Tax and tip (computed on the amount, charged to the config's tax/tip accounts):
+    bankero buy external:restaurant 1000 VES --from assets:cash --tax 16% --tip 10%
+
+Multi-currency splits (a split may name its own commodity; it is valued through the
+provider and balanced via equity:conversion):
+    bankero buy 100 USD --from assets:cash --to expenses:import:30 --to expenses:local:2100:VES @bcv
+
 Provider context (used in --confirm preview for value/rate):
     bankero buy 100 USD --from assets:cash --to expenses:food:100 @bcv --confirm
 "#
@@ -91,6 +267,11 @@ Example:
 
 Tip:
     Use --confirm to preview the computed value and optionally provide rates via a provider.
+
+With --confirm, if the sold holdings have a recorded basis (a fixed --basis on the events
+that acquired them, or `tag <account> --set-basis`), the realized gain or loss (proceeds
+minus the average-cost basis of what is sold) is previewed and stored in the event's
+metadata as realized_gain.
 "#
     )]
     Sell(SellArgs),
@@ -109,6 +290,120 @@ Examples:
     )]
     Tag(TagArgs),
 
+    #[command(
+        about = "Quick entry: record a buy from a one-line shorthand",
+        long_about = r#"Quick entry.
+
+Grammar (only the amount is required):
+    <amount> [COMMODITY] [category] [@account-alias] [#tag ...]
+
+- COMMODITY defaults to the reference commodity.
+- category "groceries" posts to expenses:groceries (names with ':' are used as-is).
+- @alias is looked up in `account_aliases` in config, else means assets:<alias>;
+  without it, `default_account` from config is used.
+
+Examples:
+    bankero q "45.2 USD groceries @cash"
+    bankero q "12 coffee #work"
+    bankero q "300 VES transport @banesco #commute" --confirm
+"#
+    )]
+    Q(QuickArgs),
+
+    #[command(
+        about = "Parse a pasted bank SMS/notification into a buy",
+        long_about = r#"Parse a pasted bank SMS/notification into a buy.
+
+Reads the text from stdin (or --text), extracts amount, commodity and payee with
+regex rules, and proposes a prefilled buy event. Rules come from `paste_rules`
+in config.json (tried first) and a few built-in patterns.
+
+A rule uses named groups `amount` (required), `commodity` and `payee`:
+    {"name": "banesco", "pattern": "Compra por Bs\\.(?P<amount>[\\d.,]+) en (?P<payee>[A-Z ]+)",
+     "commodity": "VES", "from": "assets:banesco"}
+
+Optional `date` and `value_date` groups (2026-01-05, 05/01/2026, 05.01.2026) date
+the event like statement imports: by the posting date, or by the value date with
+`bankero config set import_date value`. --effective-at still wins.
+
+Examples:
+    pbpaste | bankero paste --from assets:banesco
+    bankero paste --text "Compra por Bs. 1.234,56 en FARMATODO." --from assets:banesco
+    pbpaste | bankero paste --yes
+"#
+    )]
+    Paste(PasteArgs),
+
+    #[command(
+        about = "Save frequently entered commands as named templates",
+        long_about = r#"Save frequently entered commands as named templates.
+
+A template is a saved command line (any event command: buy, move, deposit, ...) with
+its accounts, category and tags. It belongs to the current workspace. Run it with
+`bankero template use <name>` or just `bankero <name>`; --amount replaces the
+template's amount (its first numeric argument) and anything after the name is added
+to the command, e.g. a --note or --effective-at for this occurrence.
+
+Templates are checked when they are saved, and names of built-in commands are refused.
+
+Examples:
+    bankero template add rent -- buy expenses:rent 480 USD --from assets:bank --category housing --tag home
+    bankero rent
+    bankero template use rent --amount 500 --note "March, after the raise"
+    bankero rent --amount 500 --effective-at 2026-03-01
+    bankero template list
+    bankero template remove rent
+"#
+    )]
+    Template(TemplateArgs),
+
+    #[command(
+        about = "Record many commands at once from a file or stdin",
+        long_about = r#"Record many commands at once from a file or stdin.
+
+One command per line, written as you would type it after `bankero` (quotes work as
+in a shell). Blank lines and lines starting with `#` are skipped. Event commands
+(deposit, withdraw, move, exchange, buy, sell, refund, lend, borrow, settle, q),
+`rate set` and templates are allowed.
+
+Every line is parsed before anything is written; then all of them run in one SQLite
+transaction. If any line fails, nothing from the batch is kept. A batch never prompts:
+an event over `confirm_over` fails its line unless you pass --yes.
+
+Example transactions.txt:
+    # March backlog
+    buy expenses:rent 480 USD --from assets:bank --effective-at 2026-03-01
+    buy store:market 62.5 USD --from assets:bank --category expenses:food --effective-at 2026-03-03
+    rent --effective-at 2026-04-01
+
+Examples:
+    bankero batch < transactions.txt
+    bankero batch --file transactions.txt
+    bankero batch --file transactions.txt --yes
+"#
+    )]
+    Batch(BatchArgs),
+
+    #[command(
+        about = "Run a chat bot for quick entries and balance queries",
+        long_about = r#"Run a chat bot for quick entries and balance queries.
+
+Messages are quick entries (same grammar as `bankero q`) or commands:
+    /balance [account-prefix]   show balances
+    /yes                        write the previewed entry
+    /no                         discard it
+
+Every entry is previewed first and only written after /yes.
+
+Telegram (long polling; only chats listed with --allow-chat are served):
+    bankero bot --telegram-token 123:ABC --allow-chat 987654321
+
+Line-based stdin/stdout (scripting and offline testing):
+    printf '45.2 USD groceries @cash\n/yes\n' | bankero bot --stdin
+"#
+    )]
+    Bot(BotArgs),
+
     #[command(
         about = "Show balances",
         long_about = r#"Show balances.
@@ -120,10 +415,67 @@ Examples:
     bankero balance
     bankero balance assets
     bankero balance assets:cash
+    bankero balance --output csv --out balances.csv
 "#
     )]
     Balance(BalanceArgs),
 
+    #[command(
+        about = "Show postings with running balances",
+        long_about = r#"Show postings with running balances.
+
+Lists every posting for accounts under an optional prefix, oldest first, with the
+running balance per account and commodity.
+
+Examples:
+    bankero register assets:cash
+    bankero register expenses --month 2026-02
+    bankero register --output csv --out register.csv
+"#
+    )]
+    Register(RegisterArgs),
+
+    #[command(
+        about = "Import books from other tools",
+        long_about = r#"Import books from other tools.
+
+Accounts and transactions become events (action "import"); prices become rate rows.
+Re-running an import skips transactions that were already imported.
+
+`import events` restores a backup written by `bankero export events`: events keep their
+ids, so importing the same file twice (or into a device that already synced them) adds
+nothing.
+
+Examples:
+    bankero import gnucash ~/books/personal.gnucash
+    bankero import ledger ~/finance/2025.journal
+    bankero import events backup.jsonl
+"#
+    )]
+    Import(ImportArgs),
+
+    #[command(
+        about = "Summarize a multi-commodity wallet (holdings, rates, total, 30-day change)",
+        long_about = r#"Summarize a multi-commodity wallet.
+
+Lists every commodity held under an account prefix with its latest provider rate,
+its value in a target commodity (the reference commodity by default), the
+converted total, and the change in total value over the last 30 days.
+
+Examples:
+    bankero wallet assets:binance @binance
+    bankero wallet assets:binance @binance --in USDT
+    bankero wallet assets:binance @binance --as-of 2026-03-01T00:00:00Z
+
+When rates are captured only now and then, --interpolate linear values holdings
+on the line between the stored rates around each date instead of the older one,
+so a series of snapshots has no staircase jumps. Events never use interpolated
+rates.
+    bankero wallet assets:binance @binance --as-of 2026-03-03T00:00:00Z --interpolate linear
+"#
+    )]
+    Wallet(WalletArgs),
+
     #[command(
         about = "Generate a report (filters by time/account/category/tag/commodity)",
         long_about = r#"Generate a report.
@@ -131,7 +483,7 @@ Examples:
 Reports are derived by replaying the journal and then applying filters.
 
 Time filters:
-    --month YYYY-MM
+    --month YYYY-MM (also 02/2026, feb-2026, febrero 2026)
     --range YYYY-MM-DD..YYYY-MM-DD
 
 Other filters:
@@ -139,15 +491,85 @@ Other filters:
     --category <category>
     --tag <tag>
     --commodity <commodity>
+    --ref <text>            (external reference, e.g. an invoice number)
+    --project <project>
 
 Examples:
     bankero report --month 2026-02
     bankero report --range 2026-02-01..2026-02-15 --account expenses
     bankero report --month 2026-02 --category income:freelance
+
+CSV for spreadsheets (one row per posting, ISO dates), or the same table as HTML:
+    bankero report --month 2026-02 --output csv --out feb.csv
+    bankero report --month 2026-02 --output html --out feb.html
+
+Totals footer (money in and out of your assets/liabilities per commodity; when
+--account names one of those accounts, only its legs count):
+    bankero report --range 2026-01-01..2026-03-31 --account income:client:acme --totals
+    bankero report --month 2026-02 --account assets:bank --totals
+
+Baselines (snapshot now, later show only new/removed events and changed totals):
+    bankero report --month 2026-02 --save-baseline monthly
+    bankero report --month 2026-02 --diff-baseline monthly
+
+Rate audit (cross-currency events with their rate, provider and where the rate
+came from: stored, override (hand-typed), inferred (from typed amounts) or missing):
+    bankero report --rates
+    bankero report --rates --month 2026-02 --output csv
+
+Annual totals per tax bucket (see `bankero report tax --help`):
+    bankero report tax --year 2026 --map tax-map.toml --convert @bcv
 "#
     )]
     Report(ReportArgs),
 
+    #[command(
+        about = "Export a redacted report to share (landlord, accountant)",
+        long_about = r#"Export a redacted report to share with a landlord or accountant.
+
+The export lists one row per posting (date, action, account, category, commodity,
+amount) and never includes notes, references, tags, event ids or who entered an
+event. --redact hides more:
+
+    accounts     generalize accounts to their kind: assets:* -> Bank,
+                 liabilities:* -> Debt, income:* -> Income, expenses:* -> Expenses,
+                 equity:* -> Equity, anything else -> Other; categories
+                 are generalized the same way
+    categories   leave the category column empty
+
+Examples:
+    bankero share report --month 2026-02 --redact accounts
+    bankero share report --month 2026-02 --redact accounts,categories --output csv --out feb.csv
+    bankero share report --range 2026-01-01..2026-03-31 --output html --out q1.html
+"#
+    )]
+    Share(ShareArgs),
+
+    #[command(
+        about = "Export the workspace for other tools",
+        long_about = r#"Export the workspace for other tools.
+
+`export analytics` writes events, postings, rates and budgets as Parquet files
+for DuckDB, pandas or Polars. Events and postings are split per month
+(events/2026-03.parquet); later runs only rewrite months whose events changed,
+so the directory can be refreshed from cron or a task. Amounts and rates are
+DECIMAL(38, 18); timestamps are UTC.
+
+`export events` writes the raw event payloads as JSONL, one `{"id", "payload"}` object
+per line (the same records the sync folder holds). Archived events are included and
+snapshots left out, so the file is a complete, portable backup that `bankero import
+events` can restore on any device.
+
+Examples:
+    bankero export analytics --out ./analytics/
+    bankero export analytics --out ./analytics/ --full
+    bankero export events --out backup.jsonl
+    bankero export events --range 2026-01-01..2026-03-31 --format jsonl > q1.jsonl
+    duckdb -c "SELECT account, sum(amount) FROM 'analytics/postings/*.parquet' GROUP BY 1"
+"#
+    )]
+    Export(ExportArgs),
+
     #[command(
         about = "Manage offline provider FX rates",
         long_about = r#"Manage offline provider FX rates.
@@ -164,16 +586,194 @@ Examples:
     Rate(RateArgs),
 
     #[command(
-        about = "Upgrade Bankero (check GitHub releases and update via APT)",
+        about = "Describe rate providers (name, kind, source)",
+        long_about = r#"Describe rate providers.
+
+Providers are created implicitly by `rate set`; registering one adds a friendly
+description, its kind (official, parallel, exchange, manual) and a source URL.
+Previews, `balance --convert` and `rate usage` show the description, so an official
+rate is never mistaken for a parallel one. Adding an existing provider updates it.
+
+Examples:
+    bankero provider add @bcv --description "Banco Central" --kind official --url https://www.bcv.org.ve
+    bankero provider add @binance --description "Binance P2P" --kind parallel
+    bankero provider list
+    bankero provider remove @binance
+"#
+    )]
+    Provider(ProviderArgs),
+
+    #[command(
+        about = "Set how computed amounts of a commodity are rounded",
+        long_about = r#"Set how computed amounts of a commodity are rounded.
+
+Amounts Bankero computes from a rate (a `move` into another commodity, a converted
+buy split, a provider basis) are rounded to the target commodity's decimal places
+before they are posted; the unrounded rate stays in the event's rate context and
+what rounding drops is posted to equity:rounding (config key rounding_account).
+Commodities without a rule keep up to 8 decimal places (bankers rounding).
+Previews show two places unless a rule says otherwise.
+
+--rounding bankers rounds halves to the nearest even digit; half-up rounds them away
+from zero.
+
+Examples:
+    bankero commodity set VES --places 2 --rounding half-up
+    bankero commodity set BTC --places 8
+    bankero commodity list
+    bankero commodity remove VES
+"#
+    )]
+    Commodity(CommodityArgs),
+
+    #[command(
+        about = "Show one event, optionally revalued at another provider's rate",
+        long_about = r#"Show one event: everything stored for it, including the rate it was
+recorded with and whether it was voided or amended since. `bankero event show` is
+the same command.
+
+The event id may be shortened to any unique prefix. --json prints the stored payload
+as JSON instead. --revalue @provider replays a
+cross-currency event with that provider's rate at the event's as-of time (or --as-of)
+and prints the delta against what was recorded, in both directions. Useful to compare
+the official and parallel rate on a past transfer. Nothing is written.
+
+Examples:
+    bankero show 4f1c2a9e
+    bankero show 4f1c2a9e --json
+    bankero show 4f1c2a9e --revalue @binance
+    bankero show 4f1c2a9e --revalue @binance --as-of 2026-03-01T12:00:00Z
+"#
+    )]
+    Show(ShowArgs),
+
+    #[command(
+        about = "Inspect and correct recorded events without rewriting the journal",
+        long_about = r#"Inspect and correct recorded events without rewriting the journal.
+
+The journal is append-only (that is what keeps sync safe), so corrections are new
+events that point at the one they fix. `event show` prints an event as stored.
+
+Examples:
+    bankero event show 4f1c2a9e --json
+    bankero event search farmatodo --month 2026-02
+    bankero event void 4f1c2a9e
+    bankero event void 4f1c2a9e -m "typed 500 instead of 50"
+    bankero event amend 4f1c2a9e --amount 50
+"#
+    )]
+    Event(EventArgs),
+
+    #[command(
+        about = "Void the last event written on this device",
+        long_about = r#"Void the last event written on this device.
+
+Shows the most recent event this device wrote and, once you confirm, cancels it with
+a void event (like `bankero event void`). Running it again walks further back: events
+that are already voided, and the voids themselves, are skipped. Events synced from
+other devices are never picked.
+
+Examples:
+    bankero undo
+    bankero undo --yes -m "wrong account"
+"#
+    )]
+    Undo(UndoArgs),
+
+    #[command(
+        about = "List future-dated events that are not in balances yet",
+        long_about = r#"List future-dated events that are not in balances yet.
+
+An event recorded with --effective-at in the future (rent or salary scheduled in advance)
+is pending: balances, budgets, debts, reports and notifications leave it out until its
+effective time passes, then include it without any further step. `balance
+--include-pending` shows balances as if everything scheduled had already happened, and
+`report --include-pending` lists scheduled events too.
+
+Examples:
+    bankero buy landlord 800 USD --from assets:bank --effective-at 2026-11-01T09:00:00Z
+    bankero pending
+    bankero pending --account assets:bank
+"#
+    )]
+    Pending(PendingArgs),
+
+    #[command(
+        about = "Show an event's correction chain and resolve competing corrections",
+        long_about = r#"Show an event's correction chain and resolve competing corrections.
+
+Corrections never edit an event: they add a new one that supersedes it. `history`
+prints every version from the original on (indented under the version it replaces,
+* marks the one that counts) and the follow-ups that point at them: voids,
+reprice adjustments, settlements, refunds and approvals. Any event id in the chain works.
+
+If two devices corrected the same version before syncing, the chain forks. Until
+someone picks a version, the most recently created correction counts everywhere.
+--resolve asks which one is canonical and records the choice as a `resolve`
+event, so every device converges after the next sync.
+
+Examples:
+    bankero history 4f1c2a9e
+    bankero history 4f1c2a9e --resolve
+    bankero history 4f1c2a9e --pick 9b03d5e1
+"#
+    )]
+    History(HistoryArgs),
+
+    #[command(
+        about = "Correct a stored rate and compensate the events that used it",
+        long_about = r#"Correct a stored provider rate.
+
+Finds the events that resolved to the stored rate at --as-of (see `rate usage`) and,
+for amounts that were computed from it (e.g. `move 100 USD ... VES @bcv`), writes
+compensating `reprice` events at the original effective time: the destination
+account gets the difference, balanced against --counter-account. The stored rate
+is then replaced with --new-rate.
+
+Buy splits valued with the rate are listed for manual review; their settled amounts
+were entered by hand.
+
+Nothing is written without confirmation (or --yes). Use --dry-run to only print the plan.
+
+Examples:
+    bankero reprice --provider @bcv --as-of 2026-02-01T00:00:00Z --new-rate 46.1 --dry-run
+    bankero reprice --provider @bcv --as-of 2026-02-01T00:00:00Z --new-rate 46.1 --yes
+"#
+    )]
+    Reprice(RepriceArgs),
+
+    #[command(
+        about = "Upgrade Bankero (check GitHub releases and update via APT, Homebrew, winget or a release binary)",
         long_about = r#"Upgrade Bankero.
 
-Checks the GitHub repository for the latest release tag and can upgrade Bankero
-via the signed APT repository (Debian/Ubuntu).
+Checks the GitHub repository for the latest release tag, prints the release notes of
+every newer version, and can upgrade Bankero via the signed APT repository (Debian/Ubuntu) or by replacing the binary with the
+release asset built for this platform (Fedora, Arch, macOS, ...).
+
+The binary method downloads bankero-<target>.gz and the release's SHA256SUMS,
+refuses to continue if the checksum does not match, and swaps the executable
+atomically (write next to it, then rename).
+
+--method auto picks the package manager that installed Bankero: Homebrew when
+`brew list` knows the formula, winget on Windows when `winget list` knows the
+package, APT when apt-get is available, and the release binary otherwise.
+
+Official release builds embed the project's minisign public key; the binary method
+then also requires a valid bankero-<target>.gz.minisig signature, so a tampered
+release or mirror is rejected even when its checksum file was replaced too.
+
+--channel beta also considers pre-releases. Pre-releases are only published as
+release binaries, so the beta channel always uses the binary method.
 
 Examples:
     bankero upgrade
     bankero upgrade --apply
     bankero upgrade --setup-apt --apply
+    bankero upgrade --apply --method binary
+    bankero upgrade --apply --method binary --install-path ~/.local/bin/bankero
+    bankero upgrade --channel beta --apply
+    bankero upgrade --apply --method brew
+    bankero upgrade --apply --method winget --yes
 "#
     )]
     Upgrade(UpgradeArgs),
@@ -203,62 +803,204 @@ Examples:
     Ws(WsArgs),
 
     #[command(
-        about = "Project management within a workspace",
-        long_about = r#"Project management.
+        about = "List config profiles",
+        long_about = r#"List config profiles.
 
-Projects let you group activity inside a workspace.
+A profile is a fully separate Bankero setup: its own config (device name, current
+workspace, sync folder) and its own journals. Select one with --profile NAME or the
+BANKERO_PROFILE environment variable; a profile is created the first time it is used.
+Without either, the "default" profile is used.
 
 Examples:
-    bankero project list
-    bankero project add side-hustle
-    bankero project checkout side-hustle
+    bankero profile list
+    bankero --profile work ws check
+    BANKERO_PROFILE=work bankero balance
 "#
     )]
-    Project(ProjectArgs),
-
-    // Stubs for later milestones
-    #[command(about = "Task commands (stub)", long_about = "Task commands (stub).")]
-    Task(TaskArgs),
+    Profile(ProfileArgs),
 
     #[command(
-        about = "Workflow commands (stub)",
-        long_about = "Workflow commands (stub)."
+        about = "Get or change config settings",
+        long_about = r#"Get or change config settings.
+
+Settings are stored in the active profile's config.json. Keys:
+- data_dir: where workspace journals are stored. Defaults to the platform data dir
+  (e.g. ~/.local/share/bankero); point it at an encrypted external drive to keep the
+  ledger off the laptop. Existing journals are not moved.
+- device_name: friendly name shown to sync peers.
+- locale: language for month names in summaries (en or es; default en).
+- strict_commodities: true to reject events in commodities the journal has never seen
+  (see `bankero commodity declare`).
+- reference_commodity: commodity used as the reference for conversions.
+- sync_max_events, sync_max_mb: how many new events (default 10000) and megabytes of
+  them (default 50) one sync session may import before asking for confirmation.
+- timezone: UTC offset (e.g. -04:00) used to interpret plain dates.
+
+Examples:
+    bankero config set data_dir /media/vault/bankero
+    bankero config get data_dir
+    bankero config unset data_dir
+"#
     )]
-    Workflow(WorkflowArgs),
+    Config(ConfigArgs),
 
     #[command(
-        about = "Login / device setup",
-        long_about = r#"Login / device setup.
+        about = "Share paste rules, category rules and aliases as a TOML file",
+        long_about = r#"Share paste rules, category rules and aliases as a TOML file.
 
-This is an MVP helper for multi-device sync.
+Events sync between devices; the rules that shape new events live in each
+device's config.json. `rules export` writes them in a portable TOML format and
+`rules import` merges such a file, so everyone in a shared workspace parses bank
+notifications and categorizes purchases the same way.
+
+Category rules (config key category_rules, or [[categorize]] in the file) set the
+category of a buy or imported transaction whose payee or note contains the text,
+unless --category is given. Importing replaces rules with the same paste name or
+category text and keeps the rest; --replace drops every local rule first.
 
 Examples:
-    bankero login
-    bankero login --sync-dir ~/bankero-sync
+    bankero rules export family-rules.toml
+    bankero rules import family-rules.toml
+    bankero rules import family-rules.toml --replace
 "#
     )]
-    Login(LoginArgs),
+    Rules(RulesArgs),
 
     #[command(
-        about = "Sync commands",
-        long_about = r#"Multi-device sync (MVP).
+        about = "Access tokens for the bot and other remote surfaces",
+        long_about = r#"Access tokens for the bot and other remote surfaces.
 
-This implementation syncs via a shared folder (e.g., Syncthing, Dropbox, NFS).
+Each token belongs to the current workspace and has a scope:
+- read-only: balances and reports, nothing else (e.g. a wall dashboard).
+- write-events: also record new events (e.g. a phone bot).
 
-Examples:
-    bankero login --sync-dir ~/bankero-sync
-    bankero sync status
-    bankero sync now
+The token is printed once when created; config.json keeps only a hash of it.
+Surfaces that accept tokens refuse anything outside the token's scope.
 
-You can also override the folder per command:
-    bankero sync now --dir /mnt/shared/bankero
+Examples:
+    bankero token create phone-bot --scope write-events
+    bankero bot --telegram-token <t> --allow-chat 12345 --api-token <token>
+    bankero token list
+    bankero token revoke phone-bot
 "#
     )]
-    Sync(SyncArgs),
+    Token(TokenArgs),
 
     #[command(
-        about = "Piggy banks (savings goals)",
-        long_about = r#"Piggy banks (savings goals).
+        about = "Diagnostics for bug reports",
+        long_about = r#"Diagnostics for bug reports.
+
+`debug bundle` writes a zip to attach to an issue: version and platform, the
+config with personal values stripped, the database schema, row counts, and the most
+recent events with every amount scaled by one random factor and account names,
+tags and free text replaced by salted hashes. Commodities, providers and dates are
+kept because most bugs depend on them. Look inside before you share it.
+
+Examples:
+    bankero debug bundle
+    bankero debug bundle --out issue-123.zip --sample 200
+"#
+    )]
+    Debug(DebugArgs),
+
+    #[command(
+        about = "Project management within a workspace",
+        long_about = r#"Project management.
+
+Projects let you group activity inside a workspace.
+
+Examples:
+    bankero project list
+    bankero project add side-hustle
+    bankero project checkout side-hustle
+"#
+    )]
+    Project(ProjectArgs),
+
+    #[command(
+        about = "Asset lifecycle commands (depreciation)",
+        long_about = r#"Asset lifecycle commands.
+
+Depreciation schedules are recurring tasks: each monthly occurrence writes a
+`depreciation` event moving value from the asset to an expense account, charged
+to the project that was checked out when the schedule was created.
+
+Examples:
+    bankero asset depreciate assets:equipment:laptop --method straight-line --years 3
+    bankero asset depreciate assets:equipment:laptop --years 3 --cost 3600 USD
+    bankero task run depreciation-assets-equipment-laptop
+"#
+    )]
+    Asset(AssetArgs),
+
+    #[command(
+        about = "Recurring tasks",
+        long_about = r#"Recurring tasks.
+
+Tasks materialize journal events (or report files) on a schedule. `task run`
+catches up on every occurrence that is due (use --as-of to run as of a specific time).
+
+Examples:
+    bankero task list
+    bankero task create monthly-report --report "report --month {prev-month} --output html" --out ~/reports/
+    bankero task run depreciation-assets-equipment-laptop
+    bankero task disable depreciation-assets-equipment-laptop
+"#
+    )]
+    Task(TaskArgs),
+
+    // Stubs for later milestones
+    #[command(
+        about = "Workflow commands (stub)",
+        long_about = "Workflow commands (stub)."
+    )]
+    Workflow(WorkflowArgs),
+
+    #[command(
+        about = "Login / device setup",
+        long_about = r#"Login / device setup.
+
+This is an MVP helper for multi-device sync.
+
+Examples:
+    bankero login
+    bankero login --sync-dir ~/bankero-sync
+"#
+    )]
+    Login(LoginArgs),
+
+    #[command(
+        about = "Sync commands",
+        long_about = r#"Multi-device sync (MVP).
+
+This implementation syncs via a shared folder (e.g., Syncthing, Dropbox, NFS).
+
+Examples:
+    bankero login --sync-dir ~/bankero-sync
+    bankero sync status
+    bankero sync now
+
+You can also override the folder per command:
+    bankero sync now --dir /mnt/shared/bankero
+
+Files attached to events (`--attach`) travel with them: the folder keeps a copy under
+each device's attachments/, and LAN sync sends the ones the events refer to that the
+other device lacks. A device only keeps blobs its events refer to.
+
+Quotas: a session that would import more new events than `sync_max_events` (10000),
+more than `sync_max_mb` (50 MB) of them and their attachments, or over ten times as many events as the
+journal already holds asks before importing anything; a wrong workspace or a corrupted
+peer dump usually looks like that. Without a terminal it stops instead, unless
+--accept-large is given. A device serving `sync expose` refuses such pushes.
+    bankero sync now --accept-large
+    bankero config set sync_max_events 50000
+"#
+    )]
+    Sync(SyncArgs),
+
+    #[command(
+        about = "Piggy banks (savings goals)",
+        long_about = r#"Piggy banks (savings goals).
 
 Piggies are virtual reservations that reduce your effective balance without moving money.
 
@@ -270,11 +1012,143 @@ Examples:
 "#
     )]
     Piggy(PiggyArgs),
+
+    #[command(
+        about = "Plan paying off debts with a monthly budget",
+        long_about = r#"Plan paying off debts with a monthly budget.
+
+Debts are the accounts under --accounts (default liabilities) with a negative balance in
+the budget commodity. Their annual rates come from --rate ACCOUNT=PCT or from the
+account's interest task (`task create interest`); otherwise they are treated as 0%.
+
+Each month interest accrues on every debt, then the whole budget goes to the debts in
+strategy order:
+    avalanche   highest interest rate first (least interest paid)
+    snowball    smallest balance first (quickest first payoffs)
+
+With --create-task --from <account>, a monthly `payoff-<strategy>` task is created that
+writes the payments as moves from that account, re-ordering against the live balances.
+
+Examples:
+    bankero payoff --strategy avalanche --budget 400 USD
+    bankero payoff --strategy snowball --budget 400 USD --rate liabilities:card=29%
+    bankero payoff --strategy avalanche --budget 400 USD --create-task --from assets:bank
+"#
+    )]
+    Payoff(PayoffArgs),
+
+    #[command(
+        about = "Review and approve events entered by others",
+        long_about = r#"Review and approve events entered by others.
+
+In a shared workspace every event records who entered it (`--entered-by`, defaulting
+to the device name). Events entered by someone else stay pending until approved.
+Approving writes an `approve` event, so approvals sync like any other event.
+
+Without event ids, lists the events waiting for your approval.
+
+Examples:
+    bankero approve
+    bankero approve 3f0c9a4e-...
+    bankero approve --all --as maria
+    bankero report --unapproved
+"#
+    )]
+    Approve(ApproveArgs),
+
+    #[command(
+        about = "Monthly spending limits with optional hard blocks",
+        long_about = r#"Monthly spending limits.
+
+A limit caps what an account (and its sub-accounts) may receive in one commodity per
+calendar month. Events that would cross a soft limit are written with a warning; a
+hard limit blocks them unless the event command is given --override. Overrides are
+recorded on the event and listed by `limit overrides`.
+
+Examples:
+    bankero limit set expenses:gambling 0 USD --hard
+    bankero limit set expenses:food 600 USD
+    bankero limit list
+    bankero limit overrides
+"#
+    )]
+    Limit(LimitArgs),
+
+    #[command(
+        about = "Print a digest of everything that needs attention",
+        long_about = r#"Print a digest of everything that needs attention.
+
+Sections: budgets exceeded this month, piggies that reached their target, tasks with
+an occurrence due, hard limits passed with --override, and events waiting for your
+approval. Empty sections are omitted.
+
+--since last-run only reports what changed since the previous `notify` (the first run
+reports everything): tasks that fell due and entries recorded after it, and budgets
+and piggies that crossed their amount since. --since also accepts an RFC3339
+timestamp. --quiet prints nothing
+when there is nothing to report, which suits cron jobs that pipe into mail or ntfy.
+
+Examples:
+    bankero notify
+    bankero notify --since last-run --quiet | mail -E -s "bankero" me@example.com
+    bankero notify --since last-run --quiet | curl -s -d @- ntfy.sh/my-bankero
+"#
+    )]
+    Notify(NotifyArgs),
+
+    #[command(
+        about = "Compact old history into opening-balance snapshots",
+        long_about = r#"Compact old history into opening-balance snapshots.
+
+`snapshot create --before <date>` writes one `snapshot` event with the balance of every
+account and commodity just before the date, and moves the events it replaces to an
+archive table. Replays (balance, report, budgets, tasks) then start from the snapshot.
+Nothing is deleted: `report`, `balance` and `register` accept --include-archived to
+replay the raw history instead.
+
+Snapshots are local: sync does not send them, and archived events received again from
+peers are skipped.
+
+Examples:
+    bankero snapshot create --before 2025-01-01 --yes
+    bankero snapshot list
+    bankero report --range 2024-01-01..2024-12-31 --include-archived
+"#
+    )]
+    Snapshot(SnapshotArgs),
+
+    #[command(
+        about = "Print help for a command or an offline topic",
+        long_about = r#"Print help for a command or an offline topic.
+
+Topics are long-form guides that work without a network connection: accounts,
+commodities, providers, sync and budgets. Anything else is looked up as a command.
+
+Examples:
+    bankero help topics
+    bankero help providers
+    bankero help budget create
+"#
+    )]
+    Help(HelpArgs),
+
+    /// Write man pages generated from the CLI definitions (used by release packaging).
+    #[command(hide = true)]
+    GenerateMan(GenerateManArgs),
+
+    /// Write a synthetic journal for benchmarks (bench builds only).
+    #[cfg(feature = "bench")]
+    #[command(hide = true)]
+    BenchGen(BenchGenArgs),
+
+    /// A template name used as a command (`bankero rent`).
+    #[command(external_subcommand)]
+    External(Vec<String>),
 }
 
 #[derive(Debug, Args, Clone)]
 pub struct UpgradeArgs {
-    /// Actually apply the upgrade (runs apt-get commands).
+    /// Actually apply the upgrade (runs apt-get or replaces the binary).
     #[arg(long)]
     pub apply: bool,
 
@@ -286,7 +1160,7 @@ pub struct UpgradeArgs {
     #[arg(long)]
     pub skip_check: bool,
 
-    /// Pass -y to apt-get.
+    /// Do not ask (apt-get -y, winget --accept-*-agreements).
     #[arg(long)]
     pub yes: bool,
 
@@ -312,6 +1186,82 @@ pub struct UpgradeArgs {
     /// Sources list path.
     #[arg(long, default_value = "/etc/apt/sources.list.d/bankero.list")]
     pub sources_path: String,
+
+    /// How to apply the upgrade.
+    #[arg(long, value_enum, default_value_t = UpgradeMethod::Auto)]
+    pub method: UpgradeMethod,
+
+    /// Homebrew formula Bankero is installed from.
+    #[arg(long, default_value = "jocarrasco/tap/bankero")]
+    pub brew_formula: String,
+
+    /// winget package identifier.
+    #[arg(long, default_value = "JoCarrasco.Bankero")]
+    pub winget_id: String,
+
+    /// Release tag to install with the binary method (default: the latest release).
+    #[arg(long)]
+    pub tag: Option<String>,
+
+    /// Base URL release assets are downloaded from (<url>/<tag>/<asset>).
+    #[arg(
+        long,
+        default_value = "https://github.com/JoCarrasco/bankero/releases/download"
+    )]
+    pub release_url: String,
+
+    /// Executable to replace with the binary method (default: the running one).
+    #[arg(long)]
+    pub install_path: Option<std::path::PathBuf>,
+
+    /// Release channel: stable releases only, or pre-releases too.
+    #[arg(long, value_enum, default_value_t = UpgradeChannel::Stable)]
+    pub channel: UpgradeChannel,
+
+    /// GitHub API base URL used for the release check.
+    #[arg(long, default_value = "https://api.github.com")]
+    pub api_url: String,
+
+    /// Do not print the release notes of newer versions.
+    #[arg(long)]
+    pub no_changelog: bool,
+
+    /// Minisign public key (base64) release assets must be signed with
+    /// (default: the key built into official releases).
+    #[arg(long)]
+    pub public_key: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum UpgradeChannel {
+    /// Latest non-pre-release.
+    Stable,
+    /// Highest version, including pre-releases.
+    Beta,
+}
+
+impl UpgradeChannel {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            UpgradeChannel::Stable => "stable",
+            UpgradeChannel::Beta => "beta",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum UpgradeMethod {
+    /// Homebrew or winget when they manage Bankero, APT when apt-get is available,
+    /// otherwise the release binary.
+    Auto,
+    /// The signed APT repository (Debian/Ubuntu).
+    Apt,
+    /// `brew upgrade` (macOS, Linuxbrew).
+    Brew,
+    /// `winget upgrade` (Windows).
+    Winget,
+    /// Download the release asset for this target and replace the executable.
+    Binary,
 }
 
 #[derive(Debug, Args)]
@@ -356,371 +1306,2048 @@ If you provide a provider and base, Bankero lists the latest stored rate for eac
 
 If you provide a provider, base and quote, Bankero lists the history for that pair.
 
+--verbose adds where each rate came from: manual (`rate set`), reprice,
+import:<tool>, or "synced from <device> (<origin>)" for rates received by sync.
+
 Example:
     bankero rate list @bcv
     bankero rate list @bcv USD
     bankero rate list @bcv USD VES
+    bankero rate list @bcv USD VES --verbose
 "#
     )]
     List(RateListArgs),
-}
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum)]
-pub enum RateListFormat {
-    Table,
-    Tsv,
+    #[command(
+        about = "Show which events relied on a provider's rates",
+        long_about = r#"Audit rate usage.
+
+Scans each event's rate context (and the conversions recorded for buy splits) and groups
+the events by the stored rate they resolved to at their as-of time, so you can see what a
+corrected rate would affect. Explicit override rates are listed separately, as are events
+that had no stored rate to rely on.
+
+Optional BASE/QUOTE limit the audit to one pair (either direction).
+
+Examples:
+    bankero rate usage @bcv --month 2026-02
+    bankero rate usage @bcv USD VES --range 2026-02-01..2026-02-15
+"#
+    )]
+    Usage(RateUsageArgs),
 }
 
-#[derive(Debug, Args, Clone)]
-pub struct CommonEventFlags {
-    #[arg(long, short = 'm', alias = "note")]
-    pub note: Option<String>,
+#[derive(Debug, Args)]
+pub struct ProviderArgs {
+    #[command(subcommand)]
+    pub cmd: ProviderCmd,
+}
 
-    #[arg(long = "tag")]
-    pub tags: Vec<String>,
+#[derive(Debug, Subcommand)]
+pub enum ProviderCmd {
+    #[command(
+        about = "Register (or update) a provider",
+        long_about = r#"Register (or update) a provider's description, kind and source URL.
 
-    #[arg(long)]
-    pub category: Option<String>,
+Providers that only publish on business days (like BCV) can say so with
+--business-days. Rates for a Saturday, Sunday or --holiday then come from the last
+business day before it, and events that used such a rate record the fallback in
+their metadata (`rate_fallback`).
 
-    /// Asks for confirmation before writing an event.
-    #[arg(
-        long,
-        long_help = r#"Ask for confirmation before writing an event.
+Adding a provider again updates only what is given: other fields, business days and
+earlier holidays are kept. --no-business-days turns business days (and holidays) off.
 
-In confirm mode Bankero may prompt you for additional information (like an FX rate)
-and will print a preview (e.g., transaction value) before it writes to the journal.
+Examples:
+    bankero provider add @bcv --kind official --business-days --holiday 2026-12-24 --holiday 2026-12-25
+    bankero provider add @bcv --holiday 2027-01-01
 "#
     )]
-    pub confirm: bool,
+    Add {
+        /// Provider name, e.g. @bcv.
+        provider: String,
 
-    /// Financial time for ordering/reporting (RFC3339). Defaults to now.
-    #[arg(
-        long,
-        long_help = r#"Financial time for ordering/reporting (RFC3339).
+        #[arg(long)]
+        description: Option<String>,
 
-Defaults to now.
-Example:
-    --effective-at 2026-02-25T10:30:00Z
-"#
-    )]
-    pub effective_at: Option<String>,
+        #[arg(long, value_enum)]
+        kind: Option<ProviderKind>,
 
-    /// As-of timestamp for rate resolution (RFC3339). Defaults to effective_at.
-    #[arg(
-        long,
-        long_help = r#"As-of timestamp for rate resolution (RFC3339).
+        /// Where the rates come from.
+        #[arg(long)]
+        url: Option<String>,
 
-Defaults to effective_at.
-"#
+        /// Rates are only published Monday to Friday (and not on --holiday dates).
+        #[arg(long)]
+        business_days: bool,
+
+        /// Rates are published every day; forgets the holidays.
+        #[arg(long, conflicts_with_all = ["business_days", "holidays"])]
+        no_business_days: bool,
+
+        /// A date (YYYY-MM-DD) the provider does not publish on. Repeatable; adds to the
+        /// holidays already registered.
+        #[arg(long = "holiday", value_name = "DATE")]
+        holidays: Vec<chrono::NaiveDate>,
+    },
+
+    #[command(
+        about = "Remove a provider's description",
+        long_about = "Remove a provider's description. Its stored rates are kept."
     )]
-    pub as_of: Option<String>,
+    Remove { provider: String },
 
-    /// Basis (intrinsic value) as either fixed "<amount> <commodity>" (use --basis-amount/--basis-commodity) or provider token like "@binance".
-    #[arg(
-        long,
-        short = 'b',
-        long_help = r#"Basis (intrinsic value) for an asset.
+    #[command(
+        about = "List registered providers",
+        long_about = "List registered providers with their kind and source, then provider aliases."
+    )]
+    List,
 
-Accepts either:
-- fixed basis: "<amount> <commodity>" (example: --basis "2000 USD")
-- provider token: "@provider" (example: --basis "@binance")
+    #[command(
+        about = "Make one provider name another name for a second one's rates",
+        long_about = r#"Make one provider name another name for a second one's rates.
 
-In confirm mode, provider basis can prompt you to materialize the basis amount.
+After a provider is renamed (say @dolar became @binance), events recorded under the
+old name and new entries under the canonical one should price against the same
+series. With an alias, a rate lookup for either name searches the rates stored under
+both and uses the latest one at or before the requested time. Stored rates and
+events are not rewritten.
+
+Examples:
+    bankero provider alias @dolar @binance
+    bankero provider unalias @dolar
 "#
     )]
-    pub basis: Option<String>,
+    Alias {
+        /// The old or alternative name, e.g. @dolar.
+        alias: String,
+        /// The canonical provider, e.g. @binance.
+        provider: String,
+    },
+
+    #[command(
+        about = "Remove a provider alias",
+        long_about = "Remove a provider alias. Rates stored under either name are kept."
+    )]
+    Unalias { alias: String },
 }
 
-#[derive(Debug, Args)]
-pub struct RateSetArgs {
-    /// Provider token like "@binance" (the leading '@' is optional).
-    pub provider: String,
-    pub base: String,
-    pub quote: String,
-    pub rate: Decimal,
+#[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum)]
+pub enum ProviderKind {
+    /// Published by a central bank or government.
+    Official,
+    /// Market or street rate.
+    Parallel,
+    /// Quoted by an exchange.
+    Exchange,
+    /// Entered by hand.
+    Manual,
+}
 
-    /// As-of timestamp (RFC3339). Defaults to now.
-    #[arg(long)]
-    pub as_of: Option<String>,
+impl ProviderKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ProviderKind::Official => "official",
+            ProviderKind::Parallel => "parallel",
+            ProviderKind::Exchange => "exchange",
+            ProviderKind::Manual => "manual",
+        }
+    }
 }
 
 #[derive(Debug, Args)]
-pub struct RateGetArgs {
-    /// Provider token like "@binance" (the leading '@' is optional).
-    pub provider: String,
-    pub base: String,
-    pub quote: String,
+pub struct ShowArgs {
+    /// Event id (or a unique prefix of it)
+    pub event_id: String,
 
-    /// As-of timestamp (RFC3339). Defaults to now.
-    #[arg(long)]
+    /// Print the stored payload as JSON.
+    #[arg(long, conflicts_with = "revalue")]
+    pub json: bool,
+
+    /// Replay the event at this provider's rate and show the delta
+    #[arg(long, value_name = "@PROVIDER")]
+    pub revalue: Option<String>,
+
+    /// Rate time for --revalue (RFC3339; default: the event's own as-of)
+    #[arg(long, requires = "revalue")]
     pub as_of: Option<String>,
 }
 
 #[derive(Debug, Args)]
-pub struct RateListArgs {
-    /// Provider token like "@binance" (the leading '@' is optional).
-    pub provider: String,
+pub struct EventArgs {
+    #[command(subcommand)]
+    pub cmd: EventCmd,
+}
 
-    /// Optional base commodity (e.g., USD). If provided without quote, lists latest rates for this base.
-    pub base: Option<String>,
+#[derive(Debug, Subcommand)]
+pub enum EventCmd {
+    #[command(
+        about = "Cancel an event with a compensating void event",
+        long_about = r#"Cancel an event with a compensating void event.
+
+Writes a "void" event with the original's postings inverted, dated like the original
+(or --effective-at), and links it through metadata.voids. Balances net to zero,
+`report` marks the original as voided and budgets ignore both.
+
+Examples:
+    bankero event void 4f1c2a9e
+    bankero event void 4f1c2a9e -m "duplicate entry"
+"#
+    )]
+    Void {
+        /// Event id (or a unique prefix of it)
+        event_id: String,
+
+        #[arg(long, short = 'm', alias = "note")]
+        note: Option<String>,
+
+        /// When the void takes effect (RFC3339). Defaults to the original's effective time.
+        #[arg(long)]
+        effective_at: Option<String>,
+    },
+
+    #[command(
+        about = "Correct an event by writing a new version that supersedes it",
+        long_about = r#"Correct an event by writing a new version that supersedes it.
+
+Copies the event with the given fixes applied and links it to the old version
+(metadata.supersedes). Balances, reports and budgets replay only the current
+version; `bankero history` shows every version. --amount rescales every leg by the
+same factor (so a cross-currency move keeps its rate); --commodity renames the
+commodity that was paid.
+
+Examples:
+    bankero event amend 4f1c2a9e --amount 50
+    bankero event amend 4f1c2a9e --commodity USD
+    bankero event amend 4f1c2a9e --category expenses:groceries -m "weekly shop"
+"#
+    )]
+    Amend(EventAmendArgs),
+
+    #[command(
+        about = "Find events by free text in notes, payees, tags and categories",
+        long_about = r#"Find events by free text in notes, payees, tags and categories.
+
+Matches case-insensitively anywhere in the note, payee, tags, category or
+reference, and prints each matching event's id, date, action and a summary. Only the
+current version of an amended event is searched. --account, --month and --range
+narrow the search like they do for `report`.
+
+Examples:
+    bankero event search farmatodo
+    bankero event search "farmatodo" --account assets:bank --month 2026-02
+    bankero event search INV-7 --range 2026-01-01..2026-03-31
+"#
+    )]
+    Search(EventSearchArgs),
+
+    #[command(
+        about = "Print everything stored for one event (same as `bankero show`)",
+        long_about = r#"Print everything stored for one event. Same as `bankero show`.
+
+Shows the full payload as written: postings, the rate it was recorded with, rate
+context, basis, tags, note, reference and metadata, plus whether the event was voided
+or amended since. --json prints the stored payload as JSON instead, for scripts and
+bug reports.
+
+Examples:
+    bankero event show 4f1c2a9e
+    bankero event show 4f1c2a9e --json
+    bankero event show 4f1c2a9e --revalue @binance
+"#
+    )]
+    Show(ShowArgs),
+
+    #[command(
+        about = "List or save the files attached to an event",
+        long_about = r#"List or save the files attached to an event.
+
+Files are attached with --attach on any event command and kept in the workspace's
+attachments/ folder under their SHA-256, so each line shows the hash, original file
+name, size and where the stored copy is. Sync carries them to your other devices; an
+attachment made elsewhere shows as missing until this device has synced it. --out
+copies the files to a folder under their original names.
+
+Examples:
+    bankero buy external:bestbuy 899 USD --from assets:bank --attach ~/receipts/tv.pdf
+    bankero event attachments 4f1c2a9e
+    bankero event attachments 4f1c2a9e --out ~/warranty
+"#
+    )]
+    Attachments {
+        /// Event id (or a unique prefix of it)
+        event_id: String,
+
+        /// Copy the attached files into this folder.
+        #[arg(long, value_name = "DIR")]
+        out: Option<std::path::PathBuf>,
+    },
+}
+
+#[derive(Debug, Args)]
+pub struct EventSearchArgs {
+    /// Text to look for (case-insensitive)
+    pub query: String,
+
+    /// Only events with a posting under this account prefix.
+    #[arg(long)]
+    pub account: Option<String>,
+
+    #[arg(long, value_parser = crate::period::parse_month)]
+    pub month: Option<String>,
+
+    /// YYYY-MM-DD..YYYY-MM-DD
+    #[arg(long, conflicts_with = "month")]
+    pub range: Option<String>,
+}
+
+#[derive(Debug, Args)]
+pub struct EventAmendArgs {
+    /// Event id (or a unique prefix of it)
+    pub event_id: String,
+
+    /// New amount paid, in the event's primary commodity.
+    #[arg(long)]
+    pub amount: Option<String>,
+
+    /// Replace the paid commodity (e.g. a mistyped UDS -> USD).
+    #[arg(long)]
+    pub commodity: Option<String>,
+
+    #[arg(long)]
+    pub category: Option<String>,
+
+    #[arg(long, short = 'm', alias = "note")]
+    pub note: Option<String>,
+
+    #[arg(long = "ref", value_name = "REF")]
+    pub reference: Option<String>,
+
+    /// New financial time (RFC3339).
+    #[arg(long)]
+    pub effective_at: Option<String>,
+}
+
+#[derive(Debug, Args)]
+pub struct PendingArgs {
+    /// Only events with a posting under this account prefix.
+    #[arg(long)]
+    pub account: Option<String>,
+}
+
+#[derive(Debug, Args)]
+pub struct UndoArgs {
+    /// Void without asking.
+    #[arg(long, short = 'y')]
+    pub yes: bool,
+
+    #[arg(long, short = 'm', alias = "note")]
+    pub note: Option<String>,
+}
+
+#[derive(Debug, Args)]
+pub struct HistoryArgs {
+    /// Event id (or a unique prefix of it); any version in the chain
+    pub event_id: String,
+
+    /// Pick the canonical version where corrections compete (prompts for each fork)
+    #[arg(long)]
+    pub resolve: bool,
+
+    /// Resolve the only fork by naming the correction to keep (no prompt)
+    #[arg(long, value_name = "EVENT_ID")]
+    pub pick: Option<String>,
+}
+
+#[derive(Debug, Args)]
+pub struct CommodityArgs {
+    #[command(subcommand)]
+    pub cmd: CommodityCmd,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum CommodityCmd {
+    #[command(
+        about = "Declare commodities that strict mode accepts",
+        long_about = r#"Declare commodities that strict mode accepts.
+
+With strict_commodities on, events may only post commodities the journal already
+knows: declared ones, the reference commodity, and any already posted or rated.
+Declare a new one before its first event, so a typo like UDS is rejected instead of
+opening a new balance bucket.
+
+Examples:
+    bankero config set strict_commodities true
+    bankero commodity declare USD VES USDT
+"#
+    )]
+    Declare {
+        #[arg(required = true)]
+        commodities: Vec<String>,
+    },
+
+    #[command(
+        about = "Set the commodity an account holds by default",
+        long_about = r#"Set the commodity an account holds by default.
+
+`deposit` and `withdraw` may then leave out the commodity: it is taken from --to,
+else --from. Every event is checked against these defaults, so posting another
+commodity to the account is rejected (a VES deposit into a USD account, say).
+
+Examples:
+    bankero commodity default assets:bank USD
+    bankero deposit 100 --to assets:bank --from income:salary
+    bankero commodity default assets:bank --clear
+"#
+    )]
+    Default {
+        /// Exact account name (e.g. assets:bank).
+        account: String,
+
+        #[arg(required_unless_present = "clear")]
+        commodity: Option<String>,
+
+        /// Remove the account's default.
+        #[arg(long, conflicts_with = "commodity")]
+        clear: bool,
+    },
+
+    #[command(
+        about = "Set (or replace) a commodity's rounding rule",
+        long_about = "Set (or replace) the decimal places and rounding mode for computed amounts of a commodity."
+    )]
+    Set {
+        commodity: String,
+
+        /// Decimal places kept in computed amounts.
+        #[arg(long)]
+        places: u32,
+
+        #[arg(long, value_enum, default_value_t = RoundingMode::Bankers)]
+        rounding: RoundingMode,
+    },
+
+    #[command(
+        about = "Remove a commodity's rounding rule",
+        long_about = "Remove a commodity's rounding rule (back to 8 places, bankers) and its declaration."
+    )]
+    Remove { commodity: String },
+
+    #[command(
+        about = "List declared commodities, rounding rules and account defaults",
+        long_about = "List declared commodities, rounding rules and account default commodities."
+    )]
+    List,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum)]
+pub enum RoundingMode {
+    /// Halves go to the nearest even digit (2.345 -> 2.34).
+    Bankers,
+    /// Halves go away from zero (2.345 -> 2.35).
+    HalfUp,
+}
+
+impl RoundingMode {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            RoundingMode::Bankers => "bankers",
+            RoundingMode::HalfUp => "half-up",
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum)]
+pub enum RateListFormat {
+    Table,
+    Tsv,
+}
+
+#[derive(Debug, Args, Clone, Default)]
+pub struct CommonEventFlags {
+    #[arg(long, short = 'm', alias = "note")]
+    pub note: Option<String>,
+
+    /// External reference: invoice, receipt or bank transaction number (`report --ref`).
+    #[arg(long = "ref", value_name = "REF")]
+    pub reference: Option<String>,
+
+    #[arg(long = "tag")]
+    pub tags: Vec<String>,
+
+    #[arg(long)]
+    pub category: Option<String>,
+
+    /// Attach a file, e.g. a receipt photo or invoice PDF (repeatable; `event attachments`).
+    #[arg(long, value_name = "PATH")]
+    pub attach: Vec<std::path::PathBuf>,
+
+    /// Asks for confirmation before writing an event.
+    #[arg(
+        long,
+        long_help = r#"Ask for confirmation before writing an event.
+
+In confirm mode Bankero may prompt you for additional information (like an FX rate)
+and will print a preview (e.g., transaction value) before it writes to the journal.
+
+Large events get this even without --confirm once a threshold is set:
+    bankero config set confirm_over "100 USD"
+"#
+    )]
+    pub confirm: bool,
+
+    /// Shows the event that would be written (postings, rate, basis) and writes nothing.
+    #[arg(
+        long,
+        long_help = r#"Show the event that would be written, then exit without writing it.
+
+Rates and provider basis are resolved as in --confirm mode and the postings, rate and
+basis are printed, but there is no prompt and nothing reaches the journal, so it works
+in scripts.
+Examples:
+    bankero buy groceries 42 USD --from assets:bank --dry-run
+    bankero move 100 USD --from assets:bank --to assets:binance 99 USDT @binance --dry-run
+"#
+    )]
+    pub dry_run: bool,
+
+    /// Financial time for ordering/reporting (RFC3339, a date or e.g. "last friday"). Defaults to now.
+    #[arg(
+        long,
+        long_help = r#"Financial time for ordering/reporting.
+
+Defaults to now. Takes RFC3339, a date (optionally with HH:MM), or a relative day:
+today, yesterday, tomorrow, "3 days ago", "friday", "last friday", "next monday".
+Dates without a time use `time_of_day` from config (12:00 by default) in the
+configured timezone.
+Examples:
+    --effective-at 2026-02-25T10:30:00Z
+    --effective-at 2026-02-25
+    --effective-at "last friday"
+"#
+    )]
+    pub effective_at: Option<String>,
+
+    /// As-of timestamp for rate resolution (RFC3339). Defaults to effective_at.
+    #[arg(
+        long,
+        long_help = r#"As-of timestamp for rate resolution (RFC3339).
+
+Defaults to effective_at.
+"#
+    )]
+    pub as_of: Option<String>,
+
+    /// Basis (intrinsic value) as either fixed "<amount> <commodity>" (use --basis-amount/--basis-commodity) or provider token like "@binance".
+    #[arg(
+        long,
+        short = 'b',
+        long_help = r#"Basis (intrinsic value) for an asset.
+
+Accepts either:
+- fixed basis: "<amount> <commodity>" (example: --basis "2000 USD")
+- provider token: "@provider" (example: --basis "@binance")
+
+In confirm mode, provider basis can prompt you to materialize the basis amount.
+"#
+    )]
+    pub basis: Option<String>,
+
+    /// Who entered this event (defaults to the device name). See `bankero approve`.
+    #[arg(long, value_name = "NAME")]
+    pub entered_by: Option<String>,
+
+    /// Extra KEY=VALUE stored in the event's metadata (repeatable; `report --meta`).
+    #[arg(long, value_name = "KEY=VALUE")]
+    pub meta: Vec<String>,
+
+    /// Write the event even if it breaks a hard spending limit (the override is logged).
+    #[arg(long = "override")]
+    pub override_limits: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct RateSetArgs {
+    /// Provider token like "@binance" (the leading '@' is optional).
+    pub provider: String,
+    pub base: String,
+    pub quote: String,
+    pub rate: Decimal,
+
+    /// As-of timestamp (RFC3339). Defaults to now.
+    #[arg(long)]
+    pub as_of: Option<String>,
+}
+
+#[derive(Debug, Args)]
+pub struct RateGetArgs {
+    /// Provider token like "@binance" (the leading '@' is optional).
+    pub provider: String,
+    pub base: String,
+    pub quote: String,
+
+    /// As-of timestamp (RFC3339). Defaults to now.
+    #[arg(long)]
+    pub as_of: Option<String>,
+
+    /// Estimate the rate between the stored points around --as-of.
+    #[arg(long, value_enum, value_name = "MODE")]
+    pub interpolate: Option<Interpolation>,
+}
+
+/// How valuations estimate a rate between two stored rate points.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum)]
+pub enum Interpolation {
+    /// Straight line between the stored points before and after.
+    Linear,
+}
+
+#[derive(Debug, Args)]
+pub struct RateListArgs {
+    /// Provider token like "@binance" (the leading '@' is optional).
+    pub provider: String,
+
+    /// Optional base commodity (e.g., USD). If provided without quote, lists latest rates for this base.
+    pub base: Option<String>,
+
+    /// Optional quote commodity (e.g., VES). If provided, base is required.
+    pub quote: Option<String>,
+
+    /// Output format.
+    #[arg(long, value_enum, default_value_t = RateListFormat::Table)]
+    pub format: RateListFormat,
+
+    /// Max rows to print.
+    #[arg(long, default_value_t = 50)]
+    pub limit: usize,
+
+    /// Also show where each rate came from (manual, reprice, import, synced from a device).
+    #[arg(long, short = 'v')]
+    pub verbose: bool,
+}
+
+#[derive(Debug, Args)]
+#[command(about = "Reprice: correct a stored rate with compensating events")]
+pub struct RepriceArgs {
+    /// Provider token like "@bcv" (the leading '@' is optional).
+    #[arg(long)]
+    pub provider: String,
+
+    /// As-of timestamp of the stored rate to correct (RFC3339, as shown by `rate list`).
+    #[arg(long)]
+    pub as_of: String,
+
+    /// The corrected rate (quote per base, like `rate set`).
+    #[arg(long)]
+    pub new_rate: Decimal,
+
+    /// Base commodity, when several pairs have a rate at --as-of.
+    #[arg(long)]
+    pub base: Option<String>,
+
+    /// Quote commodity, when several pairs have a rate at --as-of.
+    #[arg(long)]
+    pub quote: Option<String>,
+
+    /// Account that balances the adjustments.
+    #[arg(long, default_value = "equity:conversion")]
+    pub counter_account: String,
+
+    /// Print the adjustments without writing anything.
+    #[arg(long)]
+    pub dry_run: bool,
+
+    /// Write without asking.
+    #[arg(long, short = 'y', conflicts_with = "dry_run")]
+    pub yes: bool,
+}
+
+#[cfg(feature = "bench")]
+#[derive(Debug, Args)]
+pub struct BenchGenArgs {
+    /// Number of events to write.
+    #[arg(long, default_value_t = 10_000)]
+    pub events: u64,
+
+    /// Months the events are spread over (starting 2026-01).
+    #[arg(long, default_value_t = 12)]
+    pub months: u32,
+
+    /// Seed for the deterministic generator.
+    #[arg(long, default_value_t = 42)]
+    pub seed: u64,
+}
+
+#[derive(Debug, Args)]
+pub struct SnapshotArgs {
+    #[command(subcommand)]
+    pub cmd: SnapshotCmd,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum SnapshotCmd {
+    #[command(
+        about = "Snapshot balances before a date and archive the older events",
+        long_about = "Snapshot balances before a date and archive the older events.\n\nThe cut-off moves back to the first of its month (`--before 2025-03-15` snapshots before 2025-03-01), so month reports never see half a month."
+    )]
+    Create {
+        /// Cut-off date (YYYY-MM-DD or RFC3339), moved back to the first of its month; events effective before it are archived.
+        #[arg(long)]
+        before: String,
+
+        /// Write without asking.
+        #[arg(long, short = 'y')]
+        yes: bool,
+    },
+
+    #[command(about = "List snapshots", long_about = "List snapshots.")]
+    List,
+}
+
+#[derive(Debug, Args)]
+pub struct HelpArgs {
+    /// "topics", a topic name, or a command path.
+    pub topic: Vec<String>,
+}
+
+#[derive(Debug, Args)]
+pub struct GenerateManArgs {
+    /// Directory the .1 files are written to.
+    #[arg(long, default_value = "target/man")]
+    pub out_dir: std::path::PathBuf,
+}
+
+#[derive(Debug, Args)]
+pub struct InitArgs {
+    /// Reference commodity (default: current config).
+    #[arg(long)]
+    pub commodity: Option<String>,
+
+    /// UTC offset such as -04:00 (default: this machine's offset).
+    #[arg(long)]
+    pub timezone: Option<String>,
+
+    /// Account `bankero q` pays from.
+    #[arg(long)]
+    pub default_account: Option<String>,
+
+    /// Shared folder for file-based sync.
+    #[arg(long)]
+    pub sync_dir: Option<String>,
+
+    /// Opening balance as "ACCOUNT AMOUNT [COMMODITY]" (repeatable).
+    #[arg(long = "opening")]
+    pub openings: Vec<String>,
+
+    /// Date of the opening balances (YYYY-MM-DD, midnight in --timezone; default: today).
+    #[arg(long)]
+    pub as_of: Option<String>,
+
+    /// Do not ask; use the flags and defaults.
+    #[arg(long, short = 'y')]
+    pub yes: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct NotifyArgs {
+    /// Only what changed since "last-run" or an RFC3339 timestamp.
+    #[arg(long)]
+    pub since: Option<String>,
+
+    /// Evaluate budgets and due tasks as of this RFC3339 timestamp instead of now.
+    #[arg(long)]
+    pub as_of: Option<String>,
+
+    /// Print nothing when there is nothing to report.
+    #[arg(long, short = 'q')]
+    pub quiet: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct LimitArgs {
+    #[command(subcommand)]
+    pub cmd: LimitCmd,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum LimitCmd {
+    #[command(
+        about = "Set (or replace) a monthly limit",
+        long_about = "Set (or replace) a monthly limit on an account subtree."
+    )]
+    Set {
+        account: String,
+        amount: String,
+        commodity: String,
+
+        /// Block events that cross the limit (unless --override is given).
+        #[arg(long)]
+        hard: bool,
+    },
+
+    #[command(about = "Remove a limit", long_about = "Remove a limit.")]
+    Remove {
+        account: String,
+
+        /// Only the limit in this commodity.
+        commodity: Option<String>,
+    },
+
+    #[command(
+        about = "List limits with this month's spend",
+        long_about = "List limits with the spend for the current (or given) month."
+    )]
+    List {
+        /// Month to report (YYYY-MM, 02/2026 or feb-2026). Defaults to the current month.
+        #[arg(long, value_parser = crate::period::parse_month)]
+        month: Option<String>,
+    },
+
+    #[command(
+        about = "List events written with --override",
+        long_about = "List events written past a hard limit with --override."
+    )]
+    Overrides,
+}
+
+#[derive(Debug, Args)]
+pub struct ApproveArgs {
+    /// Event ids to approve.
+    pub event_ids: Vec<String>,
+
+    /// Approve every pending event.
+    #[arg(long, conflicts_with = "event_ids")]
+    pub all: bool,
+
+    /// Approve as this person (defaults to the device name).
+    #[arg(long = "as", value_name = "NAME")]
+    pub approver: Option<String>,
+}
+
+#[derive(Debug, Args)]
+pub struct PayoffArgs {
+    /// Which debt gets extra money first.
+    #[arg(long, value_enum)]
+    pub strategy: crate::payoff::PayoffStrategy,
+
+    /// Monthly budget for debt payments: <amount> <commodity>.
+    #[arg(long, num_args = 2, value_names = ["AMOUNT", "COMMODITY"], required = true)]
+    pub budget: Vec<String>,
+
+    /// Account prefix holding the debts.
+    #[arg(long, default_value = "liabilities")]
+    pub accounts: String,
+
+    /// Annual rate for a debt, as ACCOUNT=PCT (repeatable; overrides interest tasks).
+    #[arg(long = "rate", value_name = "ACCOUNT=PCT")]
+    pub rates: Vec<String>,
+
+    /// Plan from balances at this RFC3339 timestamp instead of now.
+    #[arg(long)]
+    pub as_of: Option<String>,
+
+    /// Create a monthly task that writes the payments.
+    #[arg(long, requires = "from")]
+    pub create_task: bool,
+
+    /// Account the task pays from.
+    #[arg(long)]
+    pub from: Option<String>,
+}
+
+#[derive(Debug, Args)]
+pub struct RateUsageArgs {
+    /// Provider token like "@bcv" (the leading '@' is optional).
+    pub provider: String,
+
+    /// Optional base commodity (e.g., USD).
+    pub base: Option<String>,
+
+    /// Optional quote commodity (e.g., VES). If provided, base is required.
+    pub quote: Option<String>,
+
+    /// Only events effective in this month (YYYY-MM).
+    #[arg(long, value_parser = crate::period::parse_month)]
+    pub month: Option<String>,
+
+    /// Only events effective in this range (YYYY-MM-DD..YYYY-MM-DD).
+    #[arg(long, conflicts_with = "month")]
+    pub range: Option<String>,
+}
+
+#[derive(Debug, Args)]
+#[command(
+    about = "Deposit: move value between two accounts",
+    long_about = r#"Deposit command.
+
+Writes a journal event that credits the destination account and debits the source.
+
+Example:
+    bankero deposit 1200 USD --from assets:cash --to income:salary
+
+Accounts with a default commodity (`bankero commodity default`) can leave it out:
+    bankero deposit 100 --to assets:bank --from income:salary
+"#
+)]
+pub struct DepositArgs {
+    pub amount: String,
+    /// Omit to use the default commodity of --to (else --from); see `commodity default`.
+    pub commodity: Option<String>,
+
+    #[arg(long)]
+    pub from: String,
+
+    #[arg(long)]
+    pub to: String,
+
+    #[command(flatten)]
+    pub common: CommonEventFlags,
+}
+
+#[derive(Debug, Args)]
+pub struct WithdrawArgs {
+    pub amount: String,
+    /// Omit to use the default commodity of --to (else --from); see `commodity default`.
+    pub commodity: Option<String>,
+
+    /// Account the cash comes out of (e.g. assets:bank).
+    #[arg(long)]
+    pub from: String,
+
+    /// Account that holds the cash (e.g. assets:cash).
+    #[arg(long)]
+    pub to: String,
+
+    #[command(flatten)]
+    pub common: CommonEventFlags,
+}
+
+#[derive(Debug, Args)]
+#[command(
+    about = "Move: transfer value between accounts",
+    long_about = r#"Move command.
+
+Same-currency:
+    bankero move 25 USD --from assets:cash --to expenses:food
+
+Cross-currency (provide quote amount + commodity):
+    bankero move 100 USD --from assets:usd --to assets:ves 3600 VES
+
+Provider context:
+    bankero move 100 USD --from assets:usd --to assets:ves 3600 VES @binance --confirm
+
+Fees (paid from --from when in the sent commodity, from --to when in the received one):
+    bankero move 100 USD --from assets:usd --to assets:ves 3600 VES --fee 1 USD
+"#
+)]
+pub struct MoveArgs {
+    pub amount: String,
+    pub commodity: String,
+
+    #[arg(long)]
+    pub from: String,
+
+    #[arg(long)]
+    pub to: String,
+
+    #[command(flatten)]
+    pub common: CommonEventFlags,
+
+    /// Park the money in the transit account until `bankero settle` (wires, Zelle).
+    #[arg(long)]
+    pub pending: bool,
+
+    #[command(flatten)]
+    pub fee: FeeFlags,
+
+    /// Optional tail supporting same- or cross-currency moves.
+    ///
+    /// Supported forms:
+    /// - same-currency: (no tail)
+    /// - same-currency with provider context: `@provider` or `@provider:rate`
+    /// - cross-currency (explicit quote): `<to_amount> <to_commodity> [@provider[:rate]]`
+    /// - cross-currency (computed quote): `<to_commodity> @provider[:rate]`
+    #[arg(num_args = 0..=3)]
+    pub tail: Vec<String>,
+}
+
+#[derive(Debug, Args)]
+pub struct ExchangeArgs {
+    /// Amount to swap.
+    pub amount: String,
+
+    /// Commodity given up (e.g. USD).
+    pub commodity: String,
+
+    /// Commodity received (e.g. VES). May follow an `->` arrow.
+    pub to_commodity: String,
+
+    /// Provider token whose rate applies, e.g. @bcv or @binance:36.5.
+    pub provider: String,
+
+    /// Account the swapped amount comes out of.
+    #[arg(long)]
+    pub from: String,
+
+    /// Account that receives the other commodity.
+    #[arg(long)]
+    pub to: String,
+
+    /// Reference provider (e.g. @bcv) to measure the rate's spread against.
+    #[arg(long, value_name = "PROVIDER")]
+    pub vs: Option<String>,
+
+    #[command(flatten)]
+    pub fee: FeeFlags,
+
+    #[command(flatten)]
+    pub common: CommonEventFlags,
+}
+
+#[derive(Debug, Args)]
+pub struct SettleArgs {
+    /// The pending move (full id or unique prefix). Omit to list transfers in transit.
+    pub event_id: Option<String>,
+
+    /// When the funds arrived (RFC3339). Defaults to now.
+    #[arg(long)]
+    pub effective_at: Option<String>,
+
+    #[arg(long)]
+    pub note: Option<String>,
+}
+
+#[derive(Debug, Args)]
+pub struct LendArgs {
+    /// Who you lent to.
+    pub person: String,
+    pub amount: String,
+    pub commodity: String,
+
+    /// Account the money left (e.g. assets:cash).
+    #[arg(long)]
+    pub from: String,
+
+    #[command(flatten)]
+    pub common: CommonEventFlags,
+}
+
+#[derive(Debug, Args)]
+pub struct BorrowArgs {
+    /// Who you borrowed from.
+    pub person: String,
+    pub amount: String,
+    pub commodity: String,
+
+    /// Account the money went into (e.g. assets:bank).
+    #[arg(long)]
+    pub to: String,
+
+    #[command(flatten)]
+    pub common: CommonEventFlags,
+}
+
+#[derive(Debug, Args)]
+pub struct DebtsArgs {
+    #[command(subcommand)]
+    pub cmd: Option<DebtsCmd>,
+
+    /// Also list people whose debts are settled.
+    #[arg(long)]
+    pub all: bool,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum DebtsCmd {
+    #[command(
+        about = "Record a repayment, in full or in part",
+        long_about = r#"Record a repayment, in full or in part.
+
+If the person owes you, the money comes into --account; if you owe them, it goes
+out of --account. Without an amount the whole balance is settled; the commodity may
+be left out when only one is outstanding with that person.
+
+Examples:
+    bankero debts settle ana --account assets:cash
+    bankero debts settle ana 20 USD --account assets:cash
+"#
+    )]
+    Settle(DebtSettleArgs),
+}
+
+#[derive(Debug, Args)]
+pub struct DebtSettleArgs {
+    pub person: String,
+
+    /// Amount repaid. Defaults to the whole outstanding balance.
+    pub amount: Option<String>,
+
+    pub commodity: Option<String>,
+
+    /// Account the repayment is paid into or out of.
+    #[arg(long)]
+    pub account: String,
+
+    #[command(flatten)]
+    pub common: CommonEventFlags,
+}
+
+#[derive(Debug, Args)]
+pub struct CashcountArgs {
+    /// Cash account to reconcile (exact name, e.g. assets:cash).
+    pub account: String,
+
+    /// What was counted, e.g. "20x10 USD, 5x5 USD" (denomination x count).
+    #[arg(long)]
+    pub denominations: String,
+
+    /// Account that absorbs the difference.
+    #[arg(long, default_value = "expenses:cash-difference")]
+    pub counter_account: String,
+
+    /// When the count was taken (RFC3339). Defaults to now.
+    #[arg(long)]
+    pub effective_at: Option<String>,
+
+    /// Post the adjustment without asking.
+    #[arg(long, short = 'y')]
+    pub yes: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct RefundArgs {
+    /// The buy to refund (full id or unique prefix).
+    pub event_id: String,
+
+    /// How much to refund, in the commodity that was paid. Defaults to what is left.
+    #[arg(long)]
+    pub amount: Option<String>,
+
+    #[command(flatten)]
+    pub common: CommonEventFlags,
+}
+
+#[derive(Debug, Args)]
+#[command(
+    about = "Buy: record a purchase",
+    long_about = r#"Buy command.
+
+Payee form (3 args):
+    bankero buy <payee> <amount> <commodity> --from <account>
+
+Split form (2 args):
+    bankero buy <amount> <commodity> --from <account> --to <account:amount> [--to ...]
+
+Split amounts may be percentages of the buy amount. Shares are rounded down to the
+commodity's places and the last percentage split takes the leftover cents:
+    bankero buy 100 USD --from assets:bank --to expenses:rent:60% --to expenses:utilities:40%
+
+Paying from several accounts: repeat --from as <account>:<amount>[:<commodity>]. A leg
+without a commodity uses the account's default commodity, else the buy's. Legs in
+another commodity are valued with the provider token and together must cover the
+amount (plus any --tax/--tip):
+    bankero buy store 50 USD --from assets:cash-usd:30 --from assets:banesco:500:VES @bcv
+"#
+)]
+pub struct BuyArgs {
+    /// Either a payee/target account (3-arg form) OR the amount (2-arg split form).
+    ///
+    /// Supported forms:
+    /// - `bankero buy <payee> <amount> <commodity> --from ...`
+    /// - `bankero buy <amount> <commodity> --from ... --to <account:amount> [--to ...]`
+    pub payee_or_amount: String,
+
+    /// Either the amount (3-arg form) OR the commodity (2-arg split form).
+    pub amount_or_commodity: String,
+
+    /// Present only in the 3-arg form.
+    pub commodity: Option<String>,
+
+    /// Explicit payee/target account (replaces the positional payee).
+    ///
+    /// With --to splits, the payee is recorded as metadata only.
+    #[arg(long)]
+    pub payee: Option<String>,
+
+    /// Account that pays. As "<account>:<amount>[:<commodity>]", a leg of the payment;
+    /// repeat to pay from several.
+    #[arg(long, required = true)]
+    pub from: Vec<String>,
+
+    /// Optional splits like "expenses:rent:450" (account + amount), "expenses:rent:60%" or "expenses:local:2100:VES".
+    #[arg(long = "to")]
+    pub to_splits: Vec<String>,
+
+    #[command(flatten)]
+    pub charges: BuyChargeFlags,
+
+    #[command(flatten)]
+    pub common: CommonEventFlags,
+
+    /// Optional provider token like "@bcv".
+    pub provider: Option<String>,
+}
+
+/// Tax/tip helpers for `buy`: percentages of the buy amount posted to extra accounts.
+#[derive(Debug, Args, Clone, Default)]
+pub struct BuyChargeFlags {
+    /// Tax on top of the amount (e.g. 16% or 0.16).
+    #[arg(long)]
+    pub tax: Option<String>,
+
+    /// Tip on top of the amount (e.g. 10% or 0.10).
+    #[arg(long)]
+    pub tip: Option<String>,
+
+    /// Account for --tax. Defaults to `tax_account` in config (expenses:tax).
+    #[arg(long)]
+    pub tax_account: Option<String>,
+
+    /// Account for --tip. Defaults to `tip_account` in config (expenses:tip).
+    #[arg(long)]
+    pub tip_account: Option<String>,
+}
+
+/// Transaction fee for `move`/`sell`: an extra posting from the account that paid it.
+#[derive(Debug, Args, Clone, Default)]
+pub struct FeeFlags {
+    /// Fee charged on the transaction, in the sent or the received commodity (e.g. --fee 1.5 USD).
+    #[arg(long, num_args = 2, value_names = ["AMOUNT", "COMMODITY"])]
+    pub fee: Vec<String>,
+
+    /// Account for --fee. Defaults to `fee_account` in config (expenses:fees).
+    #[arg(long, requires = "fee")]
+    pub fee_account: Option<String>,
+}
+
+#[derive(Debug, Args)]
+#[command(
+    about = "Sell: record a sale",
+    long_about = r#"Sell command.
+
+Provide the base amount (what you sell) and the quote amount/commodity (what you receive).
+
+Example:
+    bankero sell 0.01 BTC --to assets:cash 2400 USD @binance
+
+With the exchange's fee (taken from what you receive):
+    bankero sell 0.01 BTC --to assets:cash 2400 USD @binance --fee 2.40 USD
+"#
+)]
+pub struct SellArgs {
+    pub amount: String,
+    pub commodity: String,
+
+    #[arg(long)]
+    pub from: Option<String>,
+
+    #[arg(long)]
+    pub to: String,
+
+    #[command(flatten)]
+    pub common: CommonEventFlags,
+
+    /// Required quote amount (e.g., the VES received).
+    pub to_amount: Decimal,
+
+    /// Required quote commodity (e.g., VES).
+    pub to_commodity: String,
+
+    /// Optional provider token like "@binance".
+    pub provider: Option<String>,
+
+    #[command(flatten)]
+    pub fee: FeeFlags,
+}
+
+#[derive(Debug, Args)]
+#[command(
+    about = "Tag: attach metadata to an account/asset",
+    long_about = r#"Tag command.
+
+Use --tag to add tags and/or --set-basis to record intrinsic value metadata.
+
+Note: provider-based basis computation (e.g. "@binance") requires a movement event
+with an outgoing posting (like `buy`/`sell`/`move --confirm`). For `tag`, use a
+fixed basis like "2000 USD".
+"#
+)]
+pub struct TagArgs {
+    /// Target account or asset to tag (e.g., assets:gold-bar)
+    pub target: String,
+
+    /// Update intrinsic value without movement
+    #[arg(long = "set-basis")]
+    pub set_basis: Option<String>,
+
+    #[command(flatten)]
+    pub common: CommonEventFlags,
+}
+
+#[derive(Debug, Args)]
+#[command(about = "Quick entry: one-line buy shorthand")]
+pub struct QuickArgs {
+    /// Entry text, e.g. "45.2 USD groceries @cash #tag".
+    #[arg(required = true, num_args = 1..)]
+    pub text: Vec<String>,
+
+    #[command(flatten)]
+    pub common: CommonEventFlags,
+}
+
+#[derive(Debug, Args)]
+#[command(about = "Paste: turn bank notification text into a buy")]
+pub struct PasteArgs {
+    /// Text to parse. Defaults to reading stdin.
+    #[arg(long)]
+    pub text: Option<String>,
+
+    /// Account to pay from (overrides the rule and default_account).
+    #[arg(long)]
+    pub from: Option<String>,
+
+    /// Commodity (overrides what the rule extracted).
+    #[arg(long)]
+    pub commodity: Option<String>,
+
+    /// Write the proposed event without asking.
+    #[arg(long, short = 'y')]
+    pub yes: bool,
+
+    #[command(flatten)]
+    pub common: CommonEventFlags,
+}
+
+#[derive(Debug, Args)]
+#[command(about = "Bot: mobile capture channel")]
+pub struct BotArgs {
+    /// Telegram bot token (from @BotFather).
+    #[arg(long, env = "BANKERO_TELEGRAM_TOKEN", hide_env_values = true)]
+    pub telegram_token: Option<String>,
+
+    /// Telegram chat id allowed to use the bot (repeatable).
+    #[arg(long)]
+    pub allow_chat: Vec<i64>,
+
+    /// Read messages from stdin and print replies to stdout instead of Telegram.
+    #[arg(long, conflicts_with = "telegram_token")]
+    pub stdin: bool,
+
+    /// Access token from `bankero token create`; the bot is limited to its scope.
+    #[arg(long, env = "BANKERO_API_TOKEN", hide_env_values = true)]
+    pub api_token: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// Tab-separated text for the terminal.
+    #[default]
+    Text,
+    /// Spreadsheet-friendly CSV with a header row and ISO dates.
+    Csv,
+    /// A standalone HTML page with the same table as CSV.
+    Html,
+}
+
+/// Output selection shared by report-style commands.
+#[derive(Debug, Args, Clone, Default)]
+pub struct OutputFlags {
+    /// Output format.
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    pub output: OutputFormat,
+
+    /// Write the output to this file instead of stdout (CSV or HTML only).
+    #[arg(long)]
+    pub out: Option<std::path::PathBuf>,
+}
+
+impl OutputFlags {
+    /// CSV or HTML: formats rendered as one table by `output::write_table`.
+    pub fn is_table(&self) -> bool {
+        self.output != OutputFormat::Text
+    }
+
+    pub fn require_table_for_out(&self) -> anyhow::Result<()> {
+        if self.out.is_some() {
+            return Err(anyhow::anyhow!("--out requires --output csv or html"));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Args)]
+#[command(
+    about = "Balance: show balances",
+    long_about = r#"Balance command.
+
+Examples:
+    bankero balance
+    bankero balance assets
+    bankero balance assets --month 2026-02
+    bankero balance assets --convert @bcv
+    bankero balance assets:bank --explain
+    bankero balance --include-pending
+    bankero balance --by commodity
+
+A totals section sums each commodity across the listed accounts. With --convert,
+the totals are also converted with that provider's rates into a single grand total
+in the reference commodity.
+
+Events dated in the future are pending (see `bankero pending`) and left out until their
+effective time passes, unless --include-pending is given.
+
+--explain lists every budget and piggy behind the reserved sections, the device that
+created it and, for piggies, how much each device funded. When two synced devices show
+different effective balances, compare their lists to find the rows one of them lacks.
+
+--by commodity prints one block per commodity, listing its accounts and a subtotal,
+instead of interleaving account/commodity rows.
+"#
+)]
+pub struct BalanceArgs {
+    /// Optional month context used for budget reservations (YYYY-MM).
+    #[arg(long, value_parser = crate::period::parse_month)]
+    pub month: Option<String>,
+
+    pub account: Option<String>,
+
+    /// Provider token (e.g. "@bcv") used to add a grand total in the reference commodity.
+    #[arg(long, value_name = "PROVIDER")]
+    pub convert: Option<String>,
+
+    /// Replay the raw events archived by `snapshot create` instead of the snapshots.
+    #[arg(long)]
+    pub include_archived: bool,
+
+    /// List the budgets and piggies behind each reservation and the devices that created them.
+    #[arg(long)]
+    pub explain: bool,
+
+    /// Count future-dated (pending) events as if they had already happened.
+    #[arg(long)]
+    pub include_pending: bool,
+
+    /// Group rows by account (default) or by commodity with a subtotal per commodity.
+    #[arg(long, value_enum, default_value_t = BalanceGrouping::Account)]
+    pub by: BalanceGrouping,
+
+    #[command(flatten)]
+    pub output: OutputFlags,
+}
+
+/// How `balance` groups its rows.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum)]
+pub enum BalanceGrouping {
+    /// One row per account and commodity, sorted by account.
+    Account,
+    /// One block per commodity listing its accounts and a subtotal.
+    Commodity,
+}
+
+#[derive(Debug, Args)]
+#[command(
+    about = "Register: postings with running balances",
+    long_about = r#"Register command.
+
+Examples:
+    bankero register assets:cash
+    bankero register expenses --month 2026-02 --output csv --out feb.csv
+"#
+)]
+pub struct RegisterArgs {
+    /// Account prefix to list (defaults to all accounts).
+    pub account: Option<String>,
+
+    /// Only postings in this month (YYYY-MM). Running balances still include earlier activity.
+    #[arg(long, value_parser = crate::period::parse_month)]
+    pub month: Option<String>,
+
+    /// Only postings in this date range (YYYY-MM-DD..YYYY-MM-DD).
+    #[arg(long, conflicts_with = "month")]
+    pub range: Option<String>,
+
+    /// Replay the raw events archived by `snapshot create` instead of the snapshots.
+    #[arg(long)]
+    pub include_archived: bool,
+
+    #[command(flatten)]
+    pub output: OutputFlags,
+}
+
+#[derive(Debug, Args)]
+pub struct ImportArgs {
+    #[command(subcommand)]
+    pub cmd: ImportCmd,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ImportCmd {
+    #[command(
+        about = "Import a GnuCash book (XML, compressed XML, or SQLite)",
+        long_about = r#"Import a GnuCash book.
+
+Account types map to top-level accounts (ASSET/BANK/CASH -> assets, LIABILITY/CREDIT ->
+liabilities, INCOME -> income, EXPENSE -> expenses, EQUITY -> equity). Splits keep their
+account commodity; multi-currency transactions are balanced through equity:conversion.
+The price database is stored as rates under the @gnucash provider.
+
+Example:
+    bankero import gnucash ~/books/personal.gnucash
+"#
+    )]
+    Gnucash { file: std::path::PathBuf },
+
+    #[command(
+        about = "Import a ledger/hledger journal",
+        long_about = r#"Import a plain-text ledger/hledger journal.
+
+Postings keep their accounts (lowercased, spaces become dashes) and commodities
+($ -> USD, € -> EUR, £ -> GBP). One posting per transaction may omit its amount.
+`@`/`@@` costs are balanced through equity:conversion, `P` directives become rates
+under the @ledger provider, and `:tag:` / `tag:value` comments become tags.
+
+Transactions are dated by their primary (posting) date. With `bankero config set
+import_date value`, a secondary date (`2026-01-05=2026-01-07`, the statement's value
+date) is used instead when present. A secondary date without a year (`=01-07`)
+takes the year closest to the primary date, so `2025-12-30=01-02` is 2026-01-02.
+
+Example:
+    bankero import ledger ~/finance/2025.journal
+"#
+    )]
+    Ledger { file: std::path::PathBuf },
+
+    #[command(
+        about = "Restore events from a `bankero export events` file",
+        long_about = "Restore events from a `bankero export events` JSONL file. Events already in the journal are skipped, and so are events from other workspaces. A file over the sync quotas (`sync_max_events`, `sync_max_mb`) asks first, as a sync does."
+    )]
+    Events {
+        file: std::path::PathBuf,
+
+        /// Import a file that exceeds the sync quotas without asking.
+        #[arg(long)]
+        accept_large: bool,
+    },
+}
+
+#[derive(Debug, Args)]
+#[command(
+    about = "Wallet: per-venue portfolio snapshot",
+    long_about = r#"Wallet command.
+
+Without a provider token, rates come from the provider named after the venue in
+the account: `assets:binance` and `assets:binance:spot` both use @binance.
+
+Examples:
+    bankero wallet assets:binance
+    bankero wallet assets:binance @binance --in USDT
+    bankero wallet assets:banesco @bcv
+"#
+)]
+pub struct WalletArgs {
+    /// Account prefix holding the wallet (e.g. assets:binance).
+    pub account: String,
+
+    /// Provider token used for conversions (e.g. "@binance"). Defaults to the venue in the account.
+    pub provider: Option<String>,
+
+    /// Commodity to value the wallet in. Defaults to the reference commodity.
+    #[arg(long = "in")]
+    pub in_commodity: Option<String>,
+
+    /// Snapshot time (RFC3339). Defaults to now.
+    #[arg(long)]
+    pub as_of: Option<String>,
+
+    /// Estimate rates between stored points instead of using the last one (valuation only).
+    #[arg(long, value_enum, value_name = "MODE")]
+    pub interpolate: Option<Interpolation>,
+}
+
+#[derive(Debug, Args)]
+#[command(
+    about = "Report: list events and totals (filtered)",
+    long_about = r#"Report command.
+
+Examples:
+    bankero report --month 2026-02
+    bankero report --range 2026-02-01..2026-02-15 --account expenses
+    bankero report --meta trip=lisbon-2026
+    bankero report --month 2026-02 --renderer ./my-renderer --out feb.svg
+
+--renderer runs an external program, writes the report to its stdin as JSON and shows
+(or, with --out, saves) whatever it prints. The JSON has "version" (1), "generated_at",
+"workspace", "filters", "events" (each with event_id, effective_at, action, payee,
+category, tags, note, reference, project, postings and voided_by) and "totals" (money in,
+out and net per commodity, as in --totals). Amounts are decimal strings. A renderer that
+exits with an error fails the report with its stderr.
+"#
+)]
+#[command(args_conflicts_with_subcommands = true)]
+pub struct ReportArgs {
+    #[command(subcommand)]
+    pub cmd: Option<ReportCmd>,
+
+    #[arg(long, value_parser = crate::period::parse_month)]
+    pub month: Option<String>,
+
+    #[arg(long)]
+    pub range: Option<String>,
+
+    #[arg(long)]
+    pub account: Option<String>,
+
+    #[arg(long)]
+    pub category: Option<String>,
+
+    #[arg(long)]
+    pub tag: Option<String>,
 
-    /// Optional quote commodity (e.g., VES). If provided, base is required.
-    pub quote: Option<String>,
+    #[arg(long)]
+    pub commodity: Option<String>,
 
-    /// Output format.
-    #[arg(long, value_enum, default_value_t = RateListFormat::Table)]
-    pub format: RateListFormat,
+    /// Only events with this action (deposit, withdraw, move, buy, ...).
+    #[arg(long)]
+    pub action: Option<String>,
 
-    /// Max rows to print.
-    #[arg(long, default_value_t = 50)]
-    pub limit: usize,
-}
+    /// Only events whose --ref contains this text (case-insensitive).
+    #[arg(long = "ref", value_name = "REF")]
+    pub reference: Option<String>,
 
-#[derive(Debug, Args)]
-#[command(
-    about = "Deposit: move value between two accounts",
-    long_about = r#"Deposit command.
+    /// Only events recorded under this project.
+    #[arg(long)]
+    pub project: Option<String>,
 
-Writes a journal event that credits the destination account and debits the source.
+    /// Only events entered by this person (see `--entered-by` on event commands).
+    #[arg(long, value_name = "NAME")]
+    pub entered_by: Option<String>,
 
-Example:
-    bankero deposit 1200 USD --from assets:cash --to income:salary
-"#
-)]
-pub struct DepositArgs {
-    pub amount: String,
-    pub commodity: String,
+    /// Only events whose metadata has KEY=VALUE (repeatable; all must match).
+    #[arg(long, value_name = "KEY=VALUE")]
+    pub meta: Vec<String>,
 
+    /// Only events still waiting for approval (entered by someone else, not yet approved).
     #[arg(long)]
-    pub from: String,
+    pub unapproved: bool,
 
+    /// Replay the raw events archived by `snapshot create` instead of the snapshots.
     #[arg(long)]
-    pub to: String,
+    pub include_archived: bool,
+
+    /// List future-dated (pending) events too; by default they wait until they happen.
+    #[arg(long)]
+    pub include_pending: bool,
+
+    /// List cross-currency events with their rate and its source instead of postings.
+    #[arg(long, conflicts_with_all = ["save_baseline", "diff_baseline"])]
+    pub rates: bool,
+
+    /// Print a footer with money in and out per commodity for the listed events.
+    #[arg(long, conflicts_with_all = ["rates", "save_baseline", "diff_baseline"])]
+    pub totals: bool,
+
+    /// Store this report's events and totals under NAME (replaces an existing baseline).
+    #[arg(long, value_name = "NAME", conflicts_with = "diff_baseline")]
+    pub save_baseline: Option<String>,
+
+    /// Show only what changed since the baseline saved under NAME.
+    #[arg(long, value_name = "NAME")]
+    pub diff_baseline: Option<String>,
+
+    /// Pipe the report as JSON to this program and show (or --out) its output.
+    #[arg(long, value_name = "PROGRAM", conflicts_with_all = ["rates", "totals", "save_baseline", "diff_baseline"])]
+    pub renderer: Option<std::path::PathBuf>,
 
     #[command(flatten)]
-    pub common: CommonEventFlags,
+    pub output: OutputFlags,
 }
 
-#[derive(Debug, Args)]
-#[command(
-    about = "Move: transfer value between accounts",
-    long_about = r#"Move command.
+#[derive(Debug, Subcommand)]
+pub enum ReportCmd {
+    #[command(
+        about = "Annual totals per tax bucket from a category map",
+        long_about = r#"Annual totals per tax bucket.
 
-Same-currency:
-    bankero move 25 USD --from assets:cash --to expenses:food
+The map is a TOML file that assigns ledger categories to tax buckets. A posting counts
+towards a bucket when its account is one of the bucket's categories (or below it); an
+event whose --category is mapped counts its outgoing amount when none of its postings
+match. Income is reported as a positive amount.
 
-Cross-currency (provide quote amount + commodity):
-    bankero move 100 USD --from assets:usd --to assets:ves 3600 VES
+    # tax-map.toml
+    [buckets.deductible]
+    label = "Deductible expenses"
+    categories = ["expenses:health", "expenses:education"]
 
-Provider context:
-    bankero move 100 USD --from assets:usd --to assets:ves 3600 VES @binance --confirm
+    [buckets.vat-reclaimable]
+    categories = ["expenses:office"]
+
+    [buckets.taxable-income]
+    label = "Taxable income"
+    categories = ["income:salary", "income:freelance"]
+
+Totals are in the reference commodity. Amounts in other commodities are converted with
+--convert at each event's time; without a usable rate they are left out, with a note.
+
+Examples:
+    bankero report tax --year 2026 --map tax-map.toml
+    bankero report tax --year 2026 --map tax-map.toml --convert @bcv --output csv
 "#
-)]
-pub struct MoveArgs {
-    pub amount: String,
-    pub commodity: String,
+    )]
+    Tax(ReportTaxArgs),
+
+    #[command(
+        about = "Spending totals per tag",
+        long_about = r#"Spending totals per tag.
+
+Sums what tagged events spent (postings into expenses: and external: accounts),
+per tag and commodity. An event with several tags counts toward each one, which
+makes tags usable as projects or trips with their own totals.
+
+Examples:
+    bankero report tags --month 2026-02
+    bankero report tags --range 2026-01-01..2026-03-31 --prefix client:
+    bankero report tags --month 2026-02 --output csv --out tags.csv
+"#
+    )]
+    Tags(ReportTagsArgs),
+}
+
+#[derive(Debug, Args)]
+pub struct ReportTagsArgs {
+    #[arg(long, value_parser = crate::period::parse_month, conflicts_with = "range")]
+    pub month: Option<String>,
 
     #[arg(long)]
-    pub from: String,
+    pub range: Option<String>,
 
+    /// Only tags starting with this text (e.g. `client:` or `trip:`).
     #[arg(long)]
-    pub to: String,
+    pub prefix: Option<String>,
 
     #[command(flatten)]
-    pub common: CommonEventFlags,
-
-    /// Optional tail supporting same- or cross-currency moves.
-    ///
-    /// Supported forms:
-    /// - same-currency: (no tail)
-    /// - same-currency with provider context: `@provider` or `@provider:rate`
-    /// - cross-currency (explicit quote): `<to_amount> <to_commodity> [@provider[:rate]]`
-    /// - cross-currency (computed quote): `<to_commodity> @provider[:rate]`
-    #[arg(num_args = 0..=3)]
-    pub tail: Vec<String>,
+    pub output: OutputFlags,
 }
 
 #[derive(Debug, Args)]
-#[command(
-    about = "Buy: record a purchase",
-    long_about = r#"Buy command.
+pub struct ReportTaxArgs {
+    /// Calendar year to total (YYYY).
+    #[arg(long)]
+    pub year: i32,
 
-Payee form (3 args):
-    bankero buy <payee> <amount> <commodity> --from <account>
+    /// TOML file mapping categories to tax buckets.
+    #[arg(long)]
+    pub map: std::path::PathBuf,
 
-Split form (2 args):
-    bankero buy <amount> <commodity> --from <account> --to <account:amount> [--to ...]
+    /// Provider used to convert other commodities into the reference commodity.
+    #[arg(long, value_name = "@PROVIDER")]
+    pub convert: Option<String>,
+
+    /// Estimate --convert rates between stored points instead of using the last one.
+    #[arg(long, value_enum, value_name = "MODE", requires = "convert")]
+    pub interpolate: Option<Interpolation>,
+
+    #[command(flatten)]
+    pub output: OutputFlags,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum)]
+pub enum DepreciationMethod {
+    StraightLine,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum AssetCmd {
+    #[command(
+        about = "Schedule monthly depreciation for an asset",
+        long_about = r#"Schedule monthly depreciation for an asset.
+
+The depreciable cost defaults to the asset's latest fixed basis (`tag --set-basis`),
+falling back to its current balance. Pass --cost to set it explicitly.
+
+Example:
+    bankero asset depreciate assets:equipment:laptop --method straight-line --years 3
 "#
-)]
-pub struct BuyArgs {
-    /// Either a payee/target account (3-arg form) OR the amount (2-arg split form).
-    ///
-    /// Supported forms:
-    /// - `bankero buy <payee> <amount> <commodity> --from ...`
-    /// - `bankero buy <amount> <commodity> --from ... --to <account:amount> [--to ...]`
-    pub payee_or_amount: String,
+    )]
+    Depreciate {
+        account: String,
 
-    /// Either the amount (3-arg form) OR the commodity (2-arg split form).
-    pub amount_or_commodity: String,
+        #[arg(long, value_enum, default_value_t = DepreciationMethod::StraightLine)]
+        method: DepreciationMethod,
 
-    /// Present only in the 3-arg form.
-    pub commodity: Option<String>,
+        /// Useful life in years (depreciated monthly).
+        #[arg(long)]
+        years: u32,
 
-    #[arg(long)]
-    pub from: String,
+        /// Depreciable cost (defaults to the latest fixed basis or current balance).
+        #[arg(long, value_names = ["AMOUNT", "COMMODITY"], num_args = 2)]
+        cost: Option<Vec<String>>,
 
-    /// Optional splits like "expenses:rent:450" (account + amount).
-    #[arg(long = "to")]
-    pub to_splits: Vec<String>,
+        /// Expense account receiving the depreciation.
+        #[arg(long, default_value = "expenses:depreciation")]
+        expense_account: String,
 
-    #[command(flatten)]
-    pub common: CommonEventFlags,
+        /// First depreciation posting (RFC3339). Defaults to the end of the current month.
+        #[arg(long)]
+        start: Option<String>,
 
-    /// Optional provider token like "@bcv".
-    pub provider: Option<String>,
+        /// Task id for the schedule (defaults to `depreciation-<account>`).
+        #[arg(long)]
+        id: Option<String>,
+    },
 }
 
 #[derive(Debug, Args)]
-#[command(
-    about = "Sell: record a sale",
-    long_about = r#"Sell command.
+pub struct AssetArgs {
+    #[command(subcommand)]
+    pub cmd: AssetCmd,
+}
 
-Provide the base amount (what you sell) and the quote amount/commodity (what you receive).
+#[derive(Debug, Subcommand)]
+pub enum WsCmd {
+    #[command(
+        about = "Show the current workspace",
+        long_about = r#"Show the current workspace.
 
-Example:
-    bankero sell 0.01 BTC --to assets:cash 2400 USD @binance
+Prints the workspace name currently selected in your Bankero config.
 "#
-)]
-pub struct SellArgs {
-    pub amount: String,
-    pub commodity: String,
+    )]
+    Check,
 
-    #[arg(long)]
-    pub from: Option<String>,
+    #[command(
+        about = "Add a new workspace",
+        long_about = r#"Add a new workspace.
 
-    #[arg(long)]
-    pub to: String,
+Creates workspace metadata and makes it available for checkout.
+"#
+    )]
+    Add { name: String },
 
-    #[command(flatten)]
-    pub common: CommonEventFlags,
+    #[command(
+        about = "Switch to an existing workspace",
+        long_about = r#"Switch to an existing workspace.
 
-    /// Required quote amount (e.g., the VES received).
-    pub to_amount: Decimal,
+After checkout, new events are written to that workspace's journal.
+"#
+    )]
+    Checkout { name: String },
+}
 
-    /// Required quote commodity (e.g., VES).
-    pub to_commodity: String,
+#[derive(Debug, Args)]
+pub struct WsArgs {
+    #[command(subcommand)]
+    pub cmd: WsCmd,
+}
 
-    /// Optional provider token like "@binance".
-    pub provider: Option<String>,
+#[derive(Debug, Subcommand)]
+pub enum ConfigCmd {
+    #[command(
+        about = "Print a setting",
+        long_about = "Print a setting. data_dir prints the effective location, including the default."
+    )]
+    Get { key: String },
+
+    #[command(about = "Change a setting", long_about = "Change a setting.")]
+    Set { key: String, value: String },
+
+    #[command(
+        about = "Reset a setting to its default",
+        long_about = "Reset a setting to its default."
+    )]
+    Unset { key: String },
 }
 
 #[derive(Debug, Args)]
-#[command(
-    about = "Tag: attach metadata to an account/asset",
-    long_about = r#"Tag command.
+pub struct ConfigArgs {
+    #[command(subcommand)]
+    pub cmd: ConfigCmd,
+}
 
-Use --tag to add tags and/or --set-basis to record intrinsic value metadata.
+#[derive(Debug, Args)]
+pub struct ShareArgs {
+    #[command(subcommand)]
+    pub cmd: ShareCmd,
+}
 
-Note: provider-based basis computation (e.g. "@binance") requires a movement event
-with an outgoing posting (like `buy`/`sell`/`move --confirm`). For `tag`, use a
-fixed basis like "2000 USD".
-"#
-)]
-pub struct TagArgs {
-    /// Target account or asset to tag (e.g., assets:gold-bar)
-    pub target: String,
+#[derive(Debug, Subcommand)]
+pub enum ShareCmd {
+    #[command(
+        about = "Postings for a month or range, without notes and ids",
+        long_about = "Postings for a month or range, without notes, references, tags or ids. See `bankero share --help` for --redact."
+    )]
+    Report(ShareReportArgs),
+}
 
-    /// Update intrinsic value without movement
-    #[arg(long = "set-basis")]
-    pub set_basis: Option<String>,
+#[derive(Debug, Args)]
+pub struct ShareReportArgs {
+    #[arg(long, value_parser = crate::period::parse_month, conflicts_with = "range")]
+    pub month: Option<String>,
+
+    #[arg(long)]
+    pub range: Option<String>,
+
+    /// What else to hide (comma-separated).
+    #[arg(long, value_enum, value_delimiter = ',')]
+    pub redact: Vec<Redaction>,
 
     #[command(flatten)]
-    pub common: CommonEventFlags,
+    pub output: OutputFlags,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum)]
+pub enum Redaction {
+    /// Generalize account names and categories to their kind (Bank, Debt, Income, ...).
+    Accounts,
+    /// Leave categories out.
+    Categories,
 }
 
 #[derive(Debug, Args)]
-#[command(
-    about = "Balance: show balances",
-    long_about = r#"Balance command.
+pub struct ExportArgs {
+    #[command(subcommand)]
+    pub cmd: ExportCmd,
+}
 
-Examples:
-    bankero balance
-    bankero balance assets
-    bankero balance assets --month 2026-02
-"#
-)]
-pub struct BalanceArgs {
-    /// Optional month context used for budget reservations (YYYY-MM).
-    #[arg(long)]
-    pub month: Option<String>,
+#[derive(Debug, Subcommand)]
+pub enum ExportCmd {
+    #[command(
+        about = "Write Parquet files for DuckDB/pandas (incremental)",
+        long_about = "Write Parquet files for DuckDB/pandas (incremental). See `bankero export --help` for the layout."
+    )]
+    Analytics {
+        /// Output directory; created if missing.
+        #[arg(long)]
+        out: std::path::PathBuf,
+
+        /// Rewrite every file instead of only what changed since the last run.
+        #[arg(long)]
+        full: bool,
+    },
+
+    #[command(
+        about = "Write the raw events as JSONL (a portable backup)",
+        long_about = "Write the raw event payloads as JSONL, one event per line. See `bankero export --help` for what is included."
+    )]
+    Events {
+        /// Only events effective in this range (YYYY-MM-DD..YYYY-MM-DD).
+        #[arg(long)]
+        range: Option<String>,
+
+        #[arg(long, value_enum, default_value_t = EventsFormat::Jsonl)]
+        format: EventsFormat,
+
+        /// File to write. Defaults to stdout.
+        #[arg(long)]
+        out: Option<std::path::PathBuf>,
+    },
+}
 
-    pub account: Option<String>,
+#[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum)]
+pub enum EventsFormat {
+    /// One `{"id", "payload"}` object per line.
+    Jsonl,
 }
 
 #[derive(Debug, Args)]
-#[command(
-    about = "Report: list events and totals (filtered)",
-    long_about = r#"Report command.
+pub struct DebugArgs {
+    #[command(subcommand)]
+    pub cmd: DebugCmd,
+}
 
-Examples:
-    bankero report --month 2026-02
-    bankero report --range 2026-02-01..2026-02-15 --account expenses
-"#
-)]
-pub struct ReportArgs {
-    #[arg(long)]
-    pub month: Option<String>,
+#[derive(Debug, Subcommand)]
+pub enum DebugCmd {
+    #[command(
+        about = "Write an anonymized repro bundle (zip)",
+        long_about = "Write an anonymized repro bundle (zip). See `bankero debug --help` for what it contains."
+    )]
+    Bundle {
+        /// Zip to write. Defaults to bankero-debug-<timestamp>.zip in the current directory.
+        #[arg(long)]
+        out: Option<std::path::PathBuf>,
 
-    #[arg(long)]
-    pub range: Option<String>,
+        /// How many of the most recent events to include.
+        #[arg(long, default_value_t = 50)]
+        sample: usize,
+    },
+}
 
-    #[arg(long)]
-    pub account: Option<String>,
+#[derive(Debug, Args)]
+pub struct RulesArgs {
+    #[command(subcommand)]
+    pub cmd: RulesCmd,
+}
 
-    #[arg(long)]
-    pub category: Option<String>,
+#[derive(Debug, Subcommand)]
+pub enum RulesCmd {
+    #[command(
+        about = "Write the rules to a TOML file (stdout without a file)",
+        long_about = "Write this device's paste rules, category rules and account aliases to a TOML file (stdout without a file)."
+    )]
+    Export { file: Option<std::path::PathBuf> },
 
-    #[arg(long)]
-    pub tag: Option<String>,
+    #[command(
+        about = "Merge rules from a TOML file",
+        long_about = "Merge paste rules, category rules and account aliases from a TOML file into this device's config."
+    )]
+    Import {
+        file: std::path::PathBuf,
 
-    #[arg(long)]
-    pub commodity: Option<String>,
+        /// Drop every local rule and alias before importing.
+        #[arg(long)]
+        replace: bool,
+    },
+}
+
+#[derive(Debug, Args)]
+pub struct TokenArgs {
+    #[command(subcommand)]
+    pub cmd: TokenCmd,
 }
 
 #[derive(Debug, Subcommand)]
-pub enum WsCmd {
+pub enum TokenCmd {
     #[command(
-        about = "Show the current workspace",
-        long_about = r#"Show the current workspace.
-
-Prints the workspace name currently selected in your Bankero config.
-"#
+        about = "Create a token for the current workspace",
+        long_about = "Create a token for the current workspace and print it once."
     )]
-    Check,
+    Create {
+        name: String,
+
+        /// What the token may do.
+        #[arg(long, value_enum)]
+        scope: crate::tokens::TokenScope,
+    },
 
     #[command(
-        about = "Add a new workspace",
-        long_about = r#"Add a new workspace.
+        about = "List tokens",
+        long_about = "List tokens (names and scopes, never the tokens themselves)."
+    )]
+    List,
 
-Creates workspace metadata and makes it available for checkout.
-"#
+    #[command(
+        about = "Revoke a token",
+        long_about = "Revoke a token of the current workspace. Surfaces using it are refused from then on."
     )]
-    Add { name: String },
+    Revoke { name: String },
+}
 
+#[derive(Debug, Subcommand)]
+pub enum ProfileCmd {
     #[command(
-        about = "Switch to an existing workspace",
-        long_about = r#"Switch to an existing workspace.
+        about = "List profiles",
+        long_about = "List profiles that exist on disk, marking the active one with '*'."
+    )]
+    List,
 
-After checkout, new events are written to that workspace's journal.
-"#
+    #[command(
+        about = "Show the active profile",
+        long_about = "Show the active profile and where its config and data live."
     )]
-    Checkout { name: String },
+    Current,
 }
 
 #[derive(Debug, Args)]
-pub struct WsArgs {
+pub struct ProfileArgs {
     #[command(subcommand)]
-    pub cmd: WsCmd,
+    pub cmd: ProfileCmd,
 }
 
 #[derive(Debug, Subcommand)]
@@ -746,17 +3373,42 @@ pub struct ProjectArgs {
 
 #[derive(Debug, Subcommand)]
 pub enum BudgetCmd {
-    #[command(about = "Create a budget", long_about = "Create a budget.")]
+    #[command(
+        about = "Create a budget",
+        long_about = r#"Create a budget.
+
+With --category, spend is what `buy` events in that category pay. With only
+--account, spend is everything that leaves the account (buys, moves, sells, ...),
+net of transfers between its own subaccounts.
+
+With --project, only events recorded under that project (see `bankero project
+checkout`) count, so a project's spending cap sits alongside the category budgets;
+combine it with --category or --account to narrow it further.
+
+A trailing provider token converts postings in other commodities into the budget
+commodity (at each event's effective time). Reservations on an account that holds a
+single other commodity are shown in that commodity.
+
+Examples:
+    bankero budget create "Food" 300 USD --month 2026-02 --category expenses:food
+    bankero budget create "Cash" 500 USD --month 2026-02 --account assets:cash
+    bankero budget create "Bolivares" 100 USD --month 2026-02 --account assets:ves @bcv
+    bankero budget create "Roof materials" 2000 USD --project "Fix roof"
+"#
+    )]
     Create {
         name: String,
         amount: String,
         commodity: String,
-        #[arg(long)]
+        #[arg(long, value_parser = crate::period::parse_month)]
         month: Option<String>,
         #[arg(long)]
         category: Option<String>,
         #[arg(long)]
         account: Option<String>,
+        /// Only count events recorded under this project.
+        #[arg(long)]
+        project: Option<String>,
         #[arg(trailing_var_arg = true)]
         extra: Vec<String>,
     },
@@ -771,11 +3423,30 @@ virtually when matching credits happen.
 Examples:
     bankero budget update "Food" --auto-reserve-from income:salary --until 200 USD
     bankero budget update "Food" --clear-auto-reserve
+
+Savings sweep: at each month end a task moves what is left of the budget into a
+piggy (capped at the piggy's remaining target) and writes a `budget_sweep` event
+recording the month, the remaining amount and the contribution:
+    bankero budget update "Food" --sweep-to "Trip"
+    bankero task run budget-sweep-food
+    bankero budget update "Food" --clear-sweep
 "#
     )]
     Update {
         name: String,
 
+        /// At each month end, contribute the budget's remaining amount to this piggy.
+        #[arg(long = "sweep-to", value_name = "PIGGY", conflicts_with_all = ["auto_reserve_from", "until", "clear_auto_reserve"])]
+        sweep_to: Option<String>,
+
+        /// First sweep (RFC3339). Defaults to the end of the current month.
+        #[arg(long, requires = "sweep_to")]
+        start: Option<String>,
+
+        /// Stop the month-end sweep.
+        #[arg(long = "clear-sweep", conflicts_with = "sweep_to")]
+        clear_sweep: bool,
+
         /// Enable auto-reserve (virtual siphoning) when credits come from this account prefix.
         #[arg(long = "auto-reserve-from")]
         auto_reserve_from: Option<String>,
@@ -789,10 +3460,70 @@ Examples:
         clear_auto_reserve: bool,
     },
 
-    #[command(about = "Show a budget report", long_about = "Show a budget report.")]
+    #[command(
+        about = "Plan a month zero-based: allocate projected income",
+        long_about = r#"Plan a month zero-based: give every unit of projected income a job.
+
+The interactive flow asks for the projected income, then an amount for each budget
+of the month and each piggy in that commodity (Enter keeps the amount shown), then
+any other category until a blank line. It prints what is left unallocated (or how far
+the plan is overallocated) and saves the plan; running it again edits the plan.
+
+--status shows the plan against the month so far: spending per budget and category,
+piggy funding, income received, and the unallocated or overallocated amount.
+`budget report` for a planned month ends with the same unallocated line.
+
+Examples:
+    bankero budget plan 2026-03
+    bankero budget plan 2026-03 --income 3000 USD
+    bankero budget plan 2026-03 --status
+"#
+    )]
+    Plan {
+        /// Month to plan (YYYY-MM, 03/2026 or mar-2026).
+        #[arg(value_parser = crate::period::parse_month)]
+        month: String,
+
+        /// Projected income (skips the income prompt).
+        #[arg(long, value_names = ["AMOUNT", "COMMODITY"], num_args = 2)]
+        income: Option<Vec<String>>,
+
+        /// Show the saved plan against actual spending instead of editing it.
+        #[arg(long, conflicts_with = "income")]
+        status: bool,
+    },
+
+    #[command(
+        about = "Show a budget report",
+        long_about = r#"Show a budget report.
+
+Category budgets count buys filed under exactly that category. With --rollup a
+budget also counts its sub-categories, so an expenses:food budget includes
+expenses:food:restaurants and expenses:food:groceries.
+
+--unbudgeted adds the month's spending in categories no budget covers, largest
+first, so gaps in the plan show up next to it.
+
+Examples:
+    bankero budget report --month 2026-03
+    bankero budget report --month 2026-03 --rollup
+    bankero budget report --month 2026-03 --unbudgeted
+"#
+    )]
     Report {
-        #[arg(long)]
+        #[arg(long, value_parser = crate::period::parse_month)]
         month: Option<String>,
+
+        /// Count spend in child categories toward parent category budgets.
+        #[arg(long)]
+        rollup: bool,
+
+        /// Also list spending in categories that have no budget this month.
+        #[arg(long)]
+        unbudgeted: bool,
+
+        #[command(flatten)]
+        output: OutputFlags,
     },
 }
 
@@ -854,6 +3585,28 @@ pub enum SyncCmd {
         test_print_ports: bool,
     },
 
+    #[command(
+        about = "Revoke a lost or stolen device",
+        long_about = r#"Revoke a lost or stolen device.
+
+Records a revocation event that reaches peers on their next sync. From then on,
+events claiming that device that a journal hasn't seen yet are rejected on import,
+and `sync expose` refuses connections from it. Events it synced before stay.
+Device ids show up in `bankero sync status` on each device.
+
+Examples:
+    bankero sync revoke-device 3f2b9c1e-6d0a-4e57-9a43-1c2d3e4f5a6b
+    bankero sync revoke-device 3f2b9c1e --note "phone stolen"
+"#
+    )]
+    RevokeDevice {
+        /// Device id (or a unique prefix of one seen in this journal).
+        device_id: String,
+
+        #[arg(long, short = 'm')]
+        note: Option<String>,
+    },
+
     #[command(external_subcommand)]
     External(Vec<String>),
 }
@@ -864,6 +3617,10 @@ pub struct SyncArgs {
     #[arg(long, env = "BANKERO_SYNC_DIR")]
     pub dir: Option<String>,
 
+    /// Import a session that exceeds the sync quotas without asking.
+    #[arg(long, global = true)]
+    pub accept_large: bool,
+
     #[command(subcommand)]
     pub cmd: SyncCmd,
 }
@@ -885,8 +3642,42 @@ pub struct LoginArgs {
 
 #[derive(Debug, Subcommand)]
 pub enum TaskCmd {
-    #[command(about = "Create a task", long_about = "Create a task.")]
-    Create { task_id: String },
+    #[command(
+        about = "Create a task",
+        long_about = r#"Create a task.
+
+Interest accrual (posts balance x rate / 12 to the account every month):
+    bankero task create interest --account liabilities:loan --rate 24% --monthly
+
+The counter account defaults to expenses:interest for debts (negative balances)
+and income:interest for savings (positive balances).
+
+Monthly report file (runs `bankero report ...` and saves its output in --out):
+    bankero task create monthly-report --report "report --month {prev-month} --output html" --out ~/reports/
+
+Report tasks fire on the 1st of each month (or --start) and write
+<task>-<YYYY-MM-DD>.<html|csv|txt>. Placeholders in --report: {month} and
+{prev-month} (YYYY-MM of the occurrence and the month before), {date} (YYYY-MM-DD).
+
+Dollar-cost averaging (converts --dca of --commodity into --into at the provider's
+stored rate every week or month, never more than --monthly-cap per calendar month):
+    bankero task create btc-dca --dca 50 --commodity USD --into BTC --provider @binance \
+        --from assets:usd --to assets:btc --weekly --monthly-cap 150
+
+Each purchase is a "move" event carrying the rate used and its USD cost as basis.
+A stored rate in either direction works (USD->BTC or BTC->USD); without one the run
+stops with an error instead of guessing.
+
+Recurring transaction (repeats an existing event's postings, category, tags, note and
+payee every week or month):
+    bankero buy landlord 800 USD --from assets:bank --category rent --effective-at 2026-03-01T09:00:00Z
+    bankero task create rent --from-event <event_id> --monthly
+
+The template event counts as the first occurrence, so the task starts one period after
+it (or at --start). `bankero task run rent` writes every occurrence that is due.
+"#
+    )]
+    Create(Box<TaskCreateArgs>),
 
     #[command(about = "Update a task", long_about = "Update a task.")]
     Update { task_id: String },
@@ -897,13 +3688,154 @@ pub enum TaskCmd {
     #[command(about = "Disable a task", long_about = "Disable a task.")]
     Disable { task_id: String },
 
-    #[command(about = "Run a task", long_about = "Run a task.")]
-    Run { task_id: String },
+    #[command(
+        about = "Run a task (materialize due occurrences)",
+        long_about = "Run a task. Writes an event for every occurrence due at or before --as-of (defaults to now)."
+    )]
+    Run {
+        task_id: String,
+
+        /// Run as of this timestamp (RFC3339). Defaults to now.
+        #[arg(long)]
+        as_of: Option<String>,
+    },
 
     #[command(about = "List tasks", long_about = "List tasks.")]
     List,
 }
 
+#[derive(Debug, Args)]
+pub struct TaskCreateArgs {
+    pub task_id: String,
+
+    /// Event to repeat at each occurrence (id or unique prefix).
+    #[arg(long, value_name = "EVENT_ID", conflicts_with_all = ["dca", "report", "account", "rate"])]
+    pub from_event: Option<String>,
+
+    /// Amount to convert at each occurrence (dollar-cost averaging).
+    #[arg(long, value_name = "AMOUNT", conflicts_with_all = ["report", "account", "rate"])]
+    pub dca: Option<String>,
+
+    /// Commodity spent by --dca (e.g. USD).
+    #[arg(long, requires = "dca")]
+    pub commodity: Option<String>,
+
+    /// Commodity bought by --dca (e.g. BTC).
+    #[arg(long, requires = "dca")]
+    pub into: Option<String>,
+
+    /// Provider whose stored rate prices each purchase (e.g. @binance).
+    #[arg(long, requires = "dca")]
+    pub provider: Option<String>,
+
+    /// Account --dca spends from.
+    #[arg(long, requires = "dca")]
+    pub from: Option<String>,
+
+    /// Account --dca buys into.
+    #[arg(long, requires = "dca")]
+    pub to: Option<String>,
+
+    /// Most --dca may spend per calendar month (in --commodity).
+    #[arg(long, value_name = "AMOUNT", requires = "dca")]
+    pub monthly_cap: Option<String>,
+
+    /// Report arguments to run at each occurrence (e.g. "report --month {prev-month}").
+    #[arg(long, value_name = "ARGS", conflicts_with_all = ["account", "rate", "counter_account"])]
+    pub report: Option<String>,
+
+    /// Directory report files are written to.
+    #[arg(long, value_name = "DIR", requires = "report")]
+    pub out: Option<String>,
+
+    /// Account whose balance accrues interest.
+    #[arg(long)]
+    pub account: Option<String>,
+
+    /// Annual interest rate (e.g. 24% or 0.24).
+    #[arg(long)]
+    pub rate: Option<String>,
+
+    /// Counter account for accrued interest.
+    #[arg(long)]
+    pub counter_account: Option<String>,
+
+    /// Run once per calendar month.
+    #[arg(long)]
+    pub monthly: bool,
+
+    /// Run once a week (dollar-cost averaging and recurring transactions).
+    #[arg(long, conflicts_with_all = ["monthly", "report", "rate"])]
+    pub weekly: bool,
+
+    /// First occurrence (RFC3339). Defaults to the end of the current month.
+    #[arg(long)]
+    pub start: Option<String>,
+}
+
+#[derive(Debug, Args)]
+pub struct BatchArgs {
+    /// Read commands from this file instead of stdin.
+    #[arg(long)]
+    pub file: Option<std::path::PathBuf>,
+
+    /// Write events over `confirm_over` without asking (a batch can't prompt).
+    #[arg(long)]
+    pub yes: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct TemplateArgs {
+    #[command(subcommand)]
+    pub cmd: TemplateCmd,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum TemplateCmd {
+    #[command(
+        about = "Save (or replace) a template",
+        long_about = r#"Save (or replace) a template. Put the command after `--`.
+
+Example:
+    bankero template add rent -- buy expenses:rent 480 USD --from assets:bank --category housing
+"#
+    )]
+    Add {
+        name: String,
+
+        /// The command to save, e.g. `buy expenses:rent 480 USD --from assets:bank ...`.
+        #[arg(required = true, last = true, allow_hyphen_values = true)]
+        command: Vec<String>,
+    },
+
+    #[command(
+        about = "Run a template",
+        long_about = r#"Run a template. `bankero rent ...` is the same as `bankero template use rent ...`.
+
+Examples:
+    bankero template use rent
+    bankero template use rent --amount 500 --note "March"
+"#
+    )]
+    Use {
+        name: String,
+
+        /// Replaces the template's amount (its first numeric argument).
+        #[arg(long)]
+        amount: Option<String>,
+
+        /// More arguments for the command, e.g. --note or --effective-at.
+        #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
+        extra: Vec<String>,
+    },
+
+    /// List the workspace's templates.
+    List,
+
+    /// Remove a template.
+    Remove { name: String },
+}
+
 #[derive(Debug, Args)]
 pub struct TaskArgs {
     #[command(subcommand)]
@@ -948,13 +3880,38 @@ pub enum PiggyCmd {
         commodity: String,
         #[arg(long)]
         from: String,
+
+        /// Allocation order for `piggy allocate` (1 is funded first).
+        #[arg(long)]
+        priority: Option<i64>,
+
+        /// Group name, so `piggy allocate --group` can target a set of piggies.
+        #[arg(long)]
+        group: Option<String>,
+
+        /// Share of an allocation relative to piggies with the same priority (default 1).
+        #[arg(long)]
+        weight: Option<String>,
     },
 
     #[command(about = "List piggies", long_about = "List piggies.")]
     List,
 
-    #[command(about = "Show piggy status", long_about = "Show piggy status.")]
-    Status { name: String },
+    #[command(
+        about = "Show piggy status",
+        long_about = "Show piggy status.\n\nBesides progress, prints an estimated completion month based on the average monthly\nfunding over the last --pace-months months (counting back from --as-of)."
+    )]
+    Status {
+        name: String,
+
+        /// Months of funding history used to estimate the pace (default 3).
+        #[arg(long, default_value_t = 3, value_parser = clap::value_parser!(u32).range(1..))]
+        pace_months: u32,
+
+        /// Estimate from this RFC3339 timestamp instead of now.
+        #[arg(long)]
+        as_of: Option<String>,
+    },
 
     #[command(about = "Fund a piggy", long_about = "Fund a piggy.")]
     Fund {
@@ -969,6 +3926,77 @@ pub enum PiggyCmd {
         #[arg(long)]
         effective_at: Option<String>,
     },
+
+    #[command(
+        about = "Update piggy automation",
+        long_about = r#"Update piggy automation.
+
+--fund-surplus-over sets a floor for the piggy's from account. At each month end a
+task reserves whatever the account holds above the floor (minus what piggies already
+reserved from it) into the piggy, up to its target. Run it with `bankero task run`.
+
+--priority, --group and --weight change how `piggy allocate` distributes money.
+
+Examples:
+    bankero piggy update "Vacation" --fund-surplus-over 500 USD
+    bankero piggy update "Vacation" --clear-fund-surplus
+    bankero piggy update "Vacation" --priority 1 --group trips
+"#
+    )]
+    Update {
+        name: String,
+
+        /// Allocation order for `piggy allocate` (1 is funded first).
+        #[arg(long)]
+        priority: Option<i64>,
+
+        /// Group name, so `piggy allocate --group` can target a set of piggies.
+        #[arg(long)]
+        group: Option<String>,
+
+        /// Share of an allocation relative to piggies with the same priority.
+        #[arg(long)]
+        weight: Option<String>,
+
+        /// Sweep the from account's balance above this floor into the piggy monthly.
+        #[arg(long, value_names = ["AMOUNT", "COMMODITY"], num_args = 2)]
+        fund_surplus_over: Option<Vec<String>>,
+
+        /// First sweep (RFC3339). Defaults to the end of the current month.
+        #[arg(long, requires = "fund_surplus_over")]
+        start: Option<String>,
+
+        /// Stop sweeping surplus into this piggy.
+        #[arg(long, conflicts_with = "fund_surplus_over")]
+        clear_fund_surplus: bool,
+    },
+
+    #[command(
+        about = "Distribute a lump contribution across piggies",
+        long_about = r#"Distribute a lump contribution across piggies.
+
+Piggies in the contribution's commodity are funded by priority (1 first, unset last).
+Piggies with the same priority share by weight; a piggy never gets more than it needs
+to reach its target, and the rest flows to the next priority. Anything left after every
+piggy is full is reported and not reserved.
+
+Examples:
+    bankero piggy allocate 300 USD
+    bankero piggy allocate 300 USD --group vacations
+"#
+    )]
+    Allocate {
+        amount: String,
+        commodity: String,
+
+        /// Only piggies in this group.
+        #[arg(long)]
+        group: Option<String>,
+
+        /// Financial time for ordering (RFC3339). Defaults to now.
+        #[arg(long)]
+        effective_at: Option<String>,
+    },
 }
 
 #[derive(Debug, Args)]
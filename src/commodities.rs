@@ -0,0 +1,298 @@
+//! Per-commodity rounding policy and declarations (`bankero commodity`).
+//!
+//! Amounts computed from a rate (a `move` into another commodity, a converted buy split,
+//! a provider basis) are rounded to the target commodity's rule before they are posted,
+//! so `452.00000000004 VES` never reaches the journal. The unrounded rate stays in the
+//! event's rate context. Commodities without a rule keep up to eight decimal places.
+//!
+//! With `strict_commodities` on, new events may only use commodities the journal already
+//! knows: declared ones (`commodity declare`, or any with a rounding rule), the reference
+//! commodity, and those already posted or rated. A typo like `UDS` is rejected instead of
+//! opening a new balance bucket.
+//!
+//! An account may also declare the commodity it holds (`commodity default`). `deposit`
+//! and `withdraw` then take the commodity from the account when it is left out, and
+//! every new event is checked so nothing else is posted to that account.
+
+use crate::cli::{CommodityCmd, RoundingMode};
+use crate::config::{AppConfig, now_utc};
+use crate::db::{Db, StoredCommodity};
+use crate::domain::EventPayload;
+use anyhow::{Result, anyhow};
+use rust_decimal::{Decimal, RoundingStrategy};
+use std::collections::BTreeSet;
+
+/// Places kept for commodities without a rule: enough for satoshis, short of float noise.
+const DEFAULT_PLACES: u32 = 8;
+
+#[derive(Debug, Clone, Copy)]
+pub struct RoundingRule {
+    pub places: u32,
+    pub mode: RoundingMode,
+}
+
+impl RoundingRule {
+    pub fn apply(self, amount: Decimal) -> Decimal {
+        let strategy = match self.mode {
+            RoundingMode::Bankers => RoundingStrategy::MidpointNearestEven,
+            RoundingMode::HalfUp => RoundingStrategy::MidpointAwayFromZero,
+        };
+        amount.round_dp_with_strategy(self.places, strategy)
+    }
+}
+
+/// The stored rule for `commodity`, if any.
+pub fn rule_for(db: &Db, commodity: &str) -> Result<Option<RoundingRule>> {
+    let Some(c) = db.get_commodity(&commodity.to_ascii_uppercase())? else {
+        return Ok(None);
+    };
+    let Some(places) = c.places else {
+        return Ok(None);
+    };
+    let mode = match c.rounding.as_deref() {
+        Some("half-up") => RoundingMode::HalfUp,
+        _ => RoundingMode::Bankers,
+    };
+    Ok(Some(RoundingRule { places, mode }))
+}
+
+/// Rounds a computed `amount` of `commodity` by its rule (default: 8 places, bankers).
+pub fn round(db: &Db, commodity: &str, amount: Decimal) -> Result<Decimal> {
+    let rule = rule_for(db, commodity)?.unwrap_or(RoundingRule {
+        places: DEFAULT_PLACES,
+        mode: RoundingMode::Bankers,
+    });
+    Ok(rule.apply(amount))
+}
+
+/// Like [`round`], but for display: commodities without a rule show two places.
+pub fn round_for_display(db: &Db, commodity: &str, amount: Decimal) -> Result<Decimal> {
+    Ok(match rule_for(db, commodity)? {
+        Some(rule) => rule.apply(amount),
+        None => amount.round_dp(2),
+    })
+}
+
+/// Commodities new events may use in strict mode.
+fn known(db: &Db, cfg: &AppConfig) -> Result<BTreeSet<String>> {
+    let mut out: BTreeSet<String> = db.list_commodities()?.into_iter().map(|c| c.code).collect();
+    out.insert(cfg.reference_commodity.to_ascii_uppercase());
+    for e in db.list_events()? {
+        out.extend(
+            e.payload
+                .postings
+                .iter()
+                .map(|p| p.commodity.to_ascii_uppercase()),
+        );
+    }
+    for r in db.list_all_rates()? {
+        out.insert(r.base.to_ascii_uppercase());
+        out.insert(r.quote.to_ascii_uppercase());
+    }
+    Ok(out)
+}
+
+/// Edit distance where swapping two adjacent letters counts as one edit.
+pub fn distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut d = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for (i, row) in d.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, cell) in d[0].iter_mut().enumerate() {
+        *cell = j;
+    }
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            d[i][j] = (d[i - 1][j] + 1)
+                .min(d[i][j - 1] + 1)
+                .min(d[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                d[i][j] = d[i][j].min(d[i - 2][j - 2] + 1);
+            }
+        }
+    }
+    d[a.len()][b.len()]
+}
+
+/// In strict mode, rejects an event that posts a commodity the journal has never seen.
+pub fn check_known(db: &Db, cfg: &AppConfig, payload: &EventPayload) -> Result<()> {
+    check_all_known(db, cfg, [payload])
+}
+
+/// [`check_known`] for a batch of events about to be written, reading the journal once.
+pub fn check_all_known<'a>(
+    db: &Db,
+    cfg: &AppConfig,
+    payloads: impl IntoIterator<Item = &'a EventPayload>,
+) -> Result<()> {
+    if !cfg.strict_commodities {
+        return Ok(());
+    }
+    let known = known(db, cfg)?;
+    for p in payloads.into_iter().flat_map(|payload| &payload.postings) {
+        let code = p.commodity.to_ascii_uppercase();
+        if known.contains(&code) {
+            continue;
+        }
+        let hint = known
+            .iter()
+            .filter(|k| distance(k, &code) == 1)
+            .map(String::as_str)
+            .collect::<Vec<_>>();
+        let hint = if hint.is_empty() {
+            String::new()
+        } else {
+            format!(" Did you mean {}?", hint.join(" or "))
+        };
+        return Err(anyhow!(
+            "Unknown commodity {code} (strict_commodities is on).{hint}\nIf it is new, declare it first: bankero commodity declare {code}"
+        ));
+    }
+    Ok(())
+}
+
+/// The commodity typed on the command line, else the first default among `accounts`.
+pub fn explicit_or_default(db: &Db, explicit: Option<String>, accounts: &[&str]) -> Result<String> {
+    if let Some(code) = explicit {
+        return Ok(code);
+    }
+    let defaults = db.account_commodities()?;
+    accounts
+        .iter()
+        .find_map(|a| defaults.get(*a).cloned())
+        .ok_or_else(|| {
+            anyhow!(
+                "Missing commodity: none of {} has a default. Pass it (e.g. 100 USD) or set one: bankero commodity default {} USD",
+                accounts.join(", "),
+                accounts.first().copied().unwrap_or("<account>")
+            )
+        })
+}
+
+/// Rejects an event that posts another commodity to an account with a default.
+pub fn check_account_defaults(db: &Db, payload: &EventPayload) -> Result<()> {
+    let defaults = db.account_commodities()?;
+    for p in &payload.postings {
+        if let Some(expected) = defaults.get(&p.account)
+            && !expected.eq_ignore_ascii_case(&p.commodity)
+        {
+            return Err(anyhow!(
+                "{} holds {expected}, not {}. Post {expected} to it, or change its default: bankero commodity default {} {}",
+                p.account,
+                p.commodity,
+                p.account,
+                p.commodity
+            ));
+        }
+    }
+    Ok(())
+}
+
+pub fn handle_commodity(db: &Db, cmd: CommodityCmd) -> Result<()> {
+    match cmd {
+        CommodityCmd::Declare { commodities } => {
+            for commodity in commodities {
+                let code = commodity.trim().to_ascii_uppercase();
+                if code.is_empty() || !code.chars().all(|c| c.is_ascii_alphanumeric()) {
+                    return Err(anyhow!(
+                        "Invalid commodity code '{commodity}' (letters and digits only)"
+                    ));
+                }
+                if db.get_commodity(&code)?.is_some() {
+                    println!("{code} is already declared.");
+                    continue;
+                }
+                db.upsert_commodity(&StoredCommodity {
+                    code: code.clone(),
+                    places: None,
+                    rounding: None,
+                    created_at: now_utc(),
+                })?;
+                println!("Declared {code}.");
+            }
+            Ok(())
+        }
+        CommodityCmd::Default {
+            account,
+            commodity,
+            clear,
+        } => {
+            let account = account.trim();
+            if clear {
+                if db.delete_account_commodity(account)? == 0 {
+                    return Err(anyhow!("{account} has no default commodity"));
+                }
+                println!("Cleared the default commodity of {account}.");
+                return Ok(());
+            }
+            let code = commodity.unwrap_or_default().trim().to_ascii_uppercase();
+            if code.is_empty() || !code.chars().all(|c| c.is_ascii_alphanumeric()) {
+                return Err(anyhow!(
+                    "Invalid commodity code '{code}' (letters and digits only)"
+                ));
+            }
+            db.set_account_commodity(account, &code, now_utc())?;
+            println!("{account} holds {code} by default.");
+            Ok(())
+        }
+        CommodityCmd::Set {
+            commodity,
+            places,
+            rounding,
+        } => {
+            let code = commodity.to_ascii_uppercase();
+            if places > 18 {
+                return Err(anyhow!("--places must be between 0 and 18"));
+            }
+            let existing = db.get_commodity(&code)?;
+            db.upsert_commodity(&StoredCommodity {
+                code: code.clone(),
+                places: Some(places),
+                rounding: Some(rounding.as_str().to_string()),
+                created_at: existing.map_or_else(now_utc, |c| c.created_at),
+            })?;
+            println!(
+                "{code}: computed amounts round to {places} place(s), {}.",
+                rounding.as_str()
+            );
+            Ok(())
+        }
+        CommodityCmd::Remove { commodity } => {
+            let code = commodity.to_ascii_uppercase();
+            if db.delete_commodity(&code)? == 0 {
+                return Err(anyhow!("No rounding rule or declaration for {code}"));
+            }
+            println!("Removed the rounding rule and declaration for {code}.");
+            Ok(())
+        }
+        CommodityCmd::List => {
+            let commodities = db.list_commodities()?;
+            if commodities.is_empty() {
+                println!(
+                    "(no commodity rules; computed amounts keep up to {DEFAULT_PLACES} places)"
+                );
+            } else {
+                println!("commodity\tplaces\trounding");
+                for c in commodities {
+                    println!(
+                        "{}\t{}\t{}",
+                        c.code,
+                        c.places.map_or_else(|| "-".to_string(), |p| p.to_string()),
+                        c.rounding.as_deref().unwrap_or("-")
+                    );
+                }
+            }
+            let defaults = db.account_commodities()?;
+            if !defaults.is_empty() {
+                println!("account\tdefault commodity");
+                for (account, commodity) in defaults {
+                    println!("{account}\t{commodity}");
+                }
+            }
+            Ok(())
+        }
+    }
+}
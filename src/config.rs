@@ -1,7 +1,8 @@
-use anyhow::{Context, Result};
+use anyhow::{Context, Result, anyhow};
 use chrono::{DateTime, Utc};
 use directories::ProjectDirs;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::fs;
 use std::path::{Path, PathBuf};
 use uuid::Uuid;
@@ -19,6 +20,14 @@ pub struct AppConfig {
     pub current_project: String,
     pub reference_commodity: String,
 
+    /// UTC offset (e.g. "-04:00") used to interpret plain dates; set by `bankero init`.
+    #[serde(default)]
+    pub timezone: Option<String>,
+
+    /// Where journals live when not in the platform data dir (e.g. an encrypted external drive).
+    #[serde(default)]
+    pub data_dir: Option<String>,
+
     /// Shared folder path used for file-based multi-device sync (MVP).
     #[serde(default)]
     pub sync_dir: Option<String>,
@@ -26,6 +35,161 @@ pub struct AppConfig {
     /// Timestamp of the last successful sync.
     #[serde(default)]
     pub last_sync_at: Option<DateTime<Utc>>,
+
+    /// Time of day ("HH:MM") for timestamps given as a bare date or a relative day.
+    #[serde(default)]
+    pub time_of_day: Option<String>,
+
+    /// Which statement date imports use as effective_at when a source has both.
+    #[serde(default)]
+    pub import_date: ImportDate,
+
+    /// Account credited by `buy --tax`.
+    #[serde(default = "default_tax_account")]
+    pub tax_account: String,
+
+    /// Account credited by `buy --tip`.
+    #[serde(default = "default_tip_account")]
+    pub tip_account: String,
+
+    /// Account credited by `move --fee` and `sell --fee`.
+    #[serde(default = "default_fee_account")]
+    pub fee_account: String,
+
+    /// Account that absorbs what rounding a converted amount leaves over.
+    #[serde(default = "default_rounding_account")]
+    pub rounding_account: String,
+
+    /// Account `move --pending` parks money in until `bankero settle`.
+    #[serde(default = "default_transit_account")]
+    pub transit_account: String,
+
+    /// Events whose primary outgoing amount is worth more than this ("100 USD") always
+    /// get the `--confirm` preview and prompt.
+    #[serde(default)]
+    pub confirm_over: Option<String>,
+
+    /// Most new events one sync session may import without confirmation (default 10000).
+    #[serde(default)]
+    pub sync_max_events: Option<usize>,
+
+    /// Most megabytes of new events one sync session may import without confirmation
+    /// (default 50).
+    #[serde(default)]
+    pub sync_max_mb: Option<u64>,
+
+    /// Language for month names in summaries ("en" or "es"). Unset means English.
+    #[serde(default)]
+    pub locale: Option<String>,
+
+    /// Reject events in commodities that were never declared, posted or rated.
+    #[serde(default)]
+    pub strict_commodities: bool,
+
+    /// `bankero q` account aliases (e.g. "cash" -> "assets:cash").
+    #[serde(default)]
+    pub account_aliases: BTreeMap<String, String>,
+
+    /// Account `bankero q` pays from when no @alias is given.
+    #[serde(default)]
+    pub default_account: Option<String>,
+
+    /// `bankero paste` rules, tried in order before the built-in ones.
+    #[serde(default)]
+    pub paste_rules: Vec<PasteRule>,
+
+    /// Categories for buys and imports whose payee or note matches, tried in order.
+    #[serde(default)]
+    pub category_rules: Vec<CategoryRule>,
+
+    /// Access tokens for the bot and other remote surfaces (`bankero token`).
+    #[serde(default)]
+    pub api_tokens: Vec<ApiToken>,
+}
+
+/// Statement date used for imported transactions. Ledger journals carry the value
+/// date as a secondary date (`2026-01-05=2026-01-07`); paste rules capture it as the
+/// `value_date` group.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ImportDate {
+    /// When the bank booked the transaction.
+    #[default]
+    Posting,
+    /// When the money actually moved, falling back to the posting date.
+    Value,
+}
+
+impl ImportDate {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ImportDate::Posting => "posting",
+            ImportDate::Value => "value",
+        }
+    }
+
+    pub fn parse(raw: &str) -> Result<Self> {
+        match raw.trim().to_ascii_lowercase().as_str() {
+            "posting" => Ok(ImportDate::Posting),
+            "value" => Ok(ImportDate::Value),
+            _ => Err(anyhow!("import_date must be 'posting' or 'value'")),
+        }
+    }
+}
+
+/// A regex that extracts a purchase from pasted bank SMS/notification text.
+///
+/// Named groups: `amount` (required), `commodity`, `payee`, `date` and `value_date`
+/// (optional).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PasteRule {
+    pub name: String,
+    pub pattern: String,
+
+    /// Commodity when the pattern has no `commodity` group.
+    #[serde(default)]
+    pub commodity: Option<String>,
+
+    /// Account the purchase is paid from (e.g. the bank that sends the SMS).
+    #[serde(default)]
+    pub from: Option<String>,
+}
+
+/// Files events whose payee or note contains `contains` (case-insensitive) under `category`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CategoryRule {
+    pub contains: String,
+    pub category: String,
+}
+
+/// An access token. Only the SHA-256 hash of the token itself is stored.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiToken {
+    pub name: String,
+    pub workspace: String,
+    pub scope: crate::tokens::TokenScope,
+    pub hash: String,
+    pub created_at: DateTime<Utc>,
+}
+
+pub fn default_rounding_account() -> String {
+    "equity:rounding".to_string()
+}
+
+pub fn default_transit_account() -> String {
+    "assets:in-transit".to_string()
+}
+
+fn default_tax_account() -> String {
+    "expenses:tax".to_string()
+}
+
+fn default_tip_account() -> String {
+    "expenses:tip".to_string()
+}
+
+fn default_fee_account() -> String {
+    "expenses:fees".to_string()
 }
 
 impl Default for AppConfig {
@@ -37,8 +201,27 @@ impl Default for AppConfig {
             current_workspace: "personal".to_string(),
             current_project: "default".to_string(),
             reference_commodity: "USD".to_string(),
+            timezone: None,
+            data_dir: None,
             sync_dir: None,
             last_sync_at: None,
+            time_of_day: None,
+            import_date: ImportDate::Posting,
+            tax_account: default_tax_account(),
+            tip_account: default_tip_account(),
+            fee_account: default_fee_account(),
+            rounding_account: default_rounding_account(),
+            transit_account: default_transit_account(),
+            confirm_over: None,
+            sync_max_events: None,
+            sync_max_mb: None,
+            locale: None,
+            strict_commodities: false,
+            account_aliases: BTreeMap::new(),
+            default_account: None,
+            paste_rules: Vec::new(),
+            category_rules: Vec::new(),
+            api_tokens: Vec::new(),
         }
     }
 }
@@ -81,29 +264,133 @@ pub fn funny_name_from_uuid(id: Uuid) -> String {
     format!("{}_{}", ADJ[a % ADJ.len()], NOUN[n % NOUN.len()])
 }
 
+/// Profile used when neither `--profile` nor `BANKERO_PROFILE` is given.
+pub const DEFAULT_PROFILE: &str = "default";
+
 #[derive(Debug, Clone)]
 pub struct AppPaths {
     pub config_dir: PathBuf,
     pub data_dir: PathBuf,
+
+    /// Active profile name.
+    pub profile: String,
+
+    /// Config dir of the default profile; named profiles live under `profiles/<name>` in it.
+    pub root_config_dir: PathBuf,
 }
 
-pub fn app_paths(override_home: Option<PathBuf>) -> Result<AppPaths> {
-    if let Some(home) = override_home {
+/// Resolves the config/data dirs for `profile`.
+///
+/// The default profile keeps the historical layout; any other profile gets its own
+/// `profiles/<name>` subdirectory in both, so configs, devices and journals never mix.
+pub fn app_paths(override_home: Option<PathBuf>, profile: Option<&str>) -> Result<AppPaths> {
+    let (config_dir, data_dir) = match override_home {
+        Some(home) => (home.join("config"), home.join("data")),
+        None => {
+            let proj = ProjectDirs::from("com", "bankero", "bankero")
+                .context("Failed to resolve platform directories")?;
+            (
+                proj.config_dir().to_path_buf(),
+                proj.data_dir().to_path_buf(),
+            )
+        }
+    };
+
+    let profile = profile.unwrap_or(DEFAULT_PROFILE).trim();
+    if profile == DEFAULT_PROFILE {
         return Ok(AppPaths {
-            config_dir: home.join("config"),
-            data_dir: home.join("data"),
+            root_config_dir: config_dir.clone(),
+            config_dir,
+            data_dir,
+            profile: DEFAULT_PROFILE.to_string(),
         });
     }
-
-    let proj = ProjectDirs::from("com", "bankero", "bankero")
-        .context("Failed to resolve platform directories")?;
+    if profile.is_empty() || workspace_slug(profile) != profile {
+        return Err(anyhow!(
+            "Invalid profile name: {profile:?}. Use lowercase letters, digits, '-' or '_'"
+        ));
+    }
 
     Ok(AppPaths {
-        config_dir: proj.config_dir().to_path_buf(),
-        data_dir: proj.data_dir().to_path_buf(),
+        config_dir: config_dir.join("profiles").join(profile),
+        data_dir: data_dir.join("profiles").join(profile),
+        profile: profile.to_string(),
+        root_config_dir: config_dir,
+    })
+}
+
+/// Points `paths.data_dir` at the configured `data_dir`, if any.
+///
+/// The directory must already exist: creating it here would silently write journals to
+/// the mount point of an unplugged drive.
+pub fn apply_data_dir(paths: &mut AppPaths, cfg: &AppConfig) -> Result<()> {
+    let Some(raw) = cfg.data_dir.as_deref() else {
+        return Ok(());
+    };
+    let dir = expand_data_dir(raw)?;
+    if !dir.is_dir() {
+        return Err(anyhow!(
+            "Data dir {} does not exist (is the drive mounted?). Run `bankero config unset data_dir` to go back to {}",
+            dir.display(),
+            paths.data_dir.display()
+        ));
+    }
+    paths.data_dir = dir;
+    Ok(())
+}
+
+/// The user's home directory: `HOME`, or `USERPROFILE` on Windows shells that lack it.
+pub fn home_dir() -> Option<PathBuf> {
+    ["HOME", "USERPROFILE"]
+        .into_iter()
+        .filter_map(std::env::var_os)
+        .find(|v| !v.is_empty())
+        .map(PathBuf::from)
+}
+
+/// Expands a leading `~`, `~/` or `~\` to the home directory; other paths are returned as is.
+pub fn expand_home(raw: &str) -> Result<PathBuf> {
+    let rest = match raw.strip_prefix('~') {
+        Some("") => "",
+        Some(rest) if rest.starts_with(['/', '\\']) => &rest[1..],
+        _ => return Ok(PathBuf::from(raw)),
+    };
+    let home = home_dir().context("Cannot expand ~: neither HOME nor USERPROFILE is set")?;
+    Ok(if rest.is_empty() {
+        home
+    } else {
+        home.join(rest)
     })
 }
 
+/// Expands a leading `~` and requires an absolute path.
+pub fn expand_data_dir(raw: &str) -> Result<PathBuf> {
+    let path = expand_home(raw)?;
+    if !path.is_absolute() {
+        return Err(anyhow!("data_dir must be an absolute path, got {raw:?}"));
+    }
+    Ok(path)
+}
+
+/// Profiles that have a config on disk, default first.
+pub fn list_profiles(paths: &AppPaths) -> Result<Vec<String>> {
+    let mut out = vec![DEFAULT_PROFILE.to_string()];
+    let dir = paths.root_config_dir.join("profiles");
+    if !dir.exists() {
+        return Ok(out);
+    }
+    let mut named = Vec::new();
+    for entry in fs::read_dir(&dir).with_context(|| format!("Failed to read {}", dir.display()))? {
+        let entry = entry?;
+        if entry.path().join("config.json").exists() {
+            named.push(entry.file_name().to_string_lossy().to_string());
+        }
+    }
+    named.sort();
+    out.extend(named);
+    Ok(out)
+}
+
 pub fn load_or_init_config(paths: &AppPaths) -> Result<(AppConfig, PathBuf)> {
     fs::create_dir_all(&paths.config_dir)
         .with_context(|| format!("Failed to create config dir {}", paths.config_dir.display()))?;
@@ -148,10 +435,10 @@ pub fn workspace_slug(name: &str) -> String {
             ' ' | ':' | '/' | '\\' => Some('-'),
             _ => None,
         };
-        if let Some(c) = mapped {
-            if !(c == '-' && out.ends_with('-')) {
-                out.push(c);
-            }
+        if let Some(c) = mapped
+            && !(c == '-' && out.ends_with('-'))
+        {
+            out.push(c);
         }
     }
 
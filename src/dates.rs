@@ -0,0 +1,117 @@
+//! Human-friendly timestamps for `--effective-at`, `--as-of` and the other time flags.
+//!
+//! Besides RFC3339, time flags accept a bare date (`2026-03-05`), a date and time
+//! (`2026-03-05 18:30`), `now`, `today`, `yesterday`, `tomorrow`, `N days ago` and
+//! weekdays: `friday` is the latest Friday up to today, `last friday` the one before
+//! today and `next friday` the first one after it. Dates without a time get
+//! `time_of_day` from config (12:00 when unset) in the configured `timezone` (UTC when
+//! unset), so a purchase entered as "yesterday" lands on yesterday in the user's day.
+
+use crate::config::AppConfig;
+use anyhow::{Result, anyhow};
+use chrono::{
+    DateTime, Datelike, Duration, FixedOffset, NaiveDate, NaiveDateTime, NaiveTime, Offset,
+    TimeZone, Utc, Weekday,
+};
+use std::sync::OnceLock;
+
+/// The local offset and default time of day, set once from config at startup.
+static LOCAL: OnceLock<(FixedOffset, NaiveTime)> = OnceLock::new();
+
+fn default_time_of_day() -> NaiveTime {
+    NaiveTime::from_hms_opt(12, 0, 0).expect("noon")
+}
+
+/// Reads `timezone` and `time_of_day` from config for later parsing.
+pub fn configure(cfg: &AppConfig) -> Result<()> {
+    let offset = match cfg.timezone.as_deref() {
+        Some(raw) => crate::init::parse_offset(raw)?,
+        None => Utc.fix(),
+    };
+    let time = match cfg.time_of_day.as_deref() {
+        Some(raw) => parse_time_of_day(raw)?,
+        None => default_time_of_day(),
+    };
+    let _ = LOCAL.set((offset, time));
+    Ok(())
+}
+
+fn local_offset() -> FixedOffset {
+    LOCAL.get().map_or_else(|| Utc.fix(), |(offset, _)| *offset)
+}
+
+/// The calendar day `at` falls on in the configured timezone.
+pub fn local_date(at: DateTime<Utc>) -> NaiveDate {
+    at.with_timezone(&local_offset()).date_naive()
+}
+
+/// The instant `day` starts in the configured timezone.
+pub fn local_midnight(day: NaiveDate) -> DateTime<Utc> {
+    local_offset()
+        .from_local_datetime(&day.and_time(NaiveTime::MIN))
+        .single()
+        .expect("fixed offsets have no gaps")
+        .with_timezone(&Utc)
+}
+
+/// Parses a `time_of_day` value such as "09:00" or "18:30:15".
+pub fn parse_time_of_day(raw: &str) -> Result<NaiveTime> {
+    let raw = raw.trim();
+    NaiveTime::parse_from_str(raw, "%H:%M")
+        .or_else(|_| NaiveTime::parse_from_str(raw, "%H:%M:%S"))
+        .map_err(|_| anyhow!("Invalid time of day: {raw}. Expected HH:MM, e.g. 09:00"))
+}
+
+/// Parses the non-RFC3339 forms described above, relative to `now`.
+pub fn parse_human(raw: &str, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    let (offset, time) = LOCAL
+        .get()
+        .copied()
+        .unwrap_or_else(|| (Utc.fix(), default_time_of_day()));
+    let text = raw.trim().to_ascii_lowercase();
+    let today = now.with_timezone(&offset).date_naive();
+    let at = |date: NaiveDate, time: NaiveTime| {
+        offset
+            .from_local_datetime(&date.and_time(time))
+            .single()
+            .map(|t| t.with_timezone(&Utc))
+    };
+
+    if text == "now" {
+        return Some(now);
+    }
+    if let Ok(date) = NaiveDate::parse_from_str(&text, "%Y-%m-%d") {
+        return at(date, time);
+    }
+    for format in ["%Y-%m-%d %H:%M", "%Y-%m-%d %H:%M:%S", "%Y-%m-%dT%H:%M"] {
+        if let Ok(t) = NaiveDateTime::parse_from_str(&text, format) {
+            return at(t.date(), t.time());
+        }
+    }
+    let date = match text.split_whitespace().collect::<Vec<_>>()[..] {
+        ["today"] => today,
+        ["yesterday"] => today - Duration::days(1),
+        ["tomorrow"] => today + Duration::days(1),
+        [n, "day" | "days", "ago"] => today - Duration::days(n.parse().ok()?),
+        [day] => {
+            let back = days_back(today, day.parse::<Weekday>().ok()?);
+            today - Duration::days(back)
+        }
+        ["last", day] => {
+            let back = days_back(today, day.parse::<Weekday>().ok()?);
+            today - Duration::days(if back == 0 { 7 } else { back })
+        }
+        ["next", day] => {
+            let ahead = (7 - days_back(today, day.parse::<Weekday>().ok()?)) % 7;
+            today + Duration::days(if ahead == 0 { 7 } else { ahead })
+        }
+        _ => return None,
+    };
+    at(date, time)
+}
+
+/// Days from the latest `day` up to and including `today` (0 when today is `day`).
+fn days_back(today: NaiveDate, day: Weekday) -> i64 {
+    let diff = today.weekday().num_days_from_monday() as i64 - day.num_days_from_monday() as i64;
+    diff.rem_euclid(7)
+}
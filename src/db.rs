@@ -1,13 +1,22 @@
 use crate::config::{AppPaths, workspace_slug};
 use crate::domain::{EventPayload, StoredEvent};
 use anyhow::{Context, Result};
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, NaiveDate, Utc};
 use rusqlite::{Connection, params};
 use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::fs;
-use std::path::{Path, PathBuf};
+use std::path::PathBuf;
 use uuid::Uuid;
 
+/// Rate provenance by `(base, quote, as_of)`; see [`Db::list_rate_sources`].
+pub type RateSources = BTreeMap<(String, String, DateTime<Utc>), Option<String>>;
+
+/// Bump whenever the SQL views change; see [`Db::migrate_views`].
+const VIEWS_VERSION: u32 = 2;
+const VIEWS_VERSION_KEY: &str = "views_version";
+
 #[derive(Debug, Clone)]
 pub struct StoredRate {
     pub provider: String,
@@ -15,9 +24,15 @@ pub struct StoredRate {
     pub quote: String,
     pub as_of: DateTime<Utc>,
     pub rate: Decimal,
+    /// Where the rate came from: "manual", "reprice", "import:<tool>" or
+    /// "synced from <device> (<origin>)". `None` for rates stored before this was tracked.
+    pub source: Option<String>,
 }
 
-#[derive(Debug, Clone)]
+/// Latest rate row for a pair: (base, quote, as_of, rate).
+pub type LatestRateRow = (String, String, DateTime<Utc>, Decimal);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StoredBudget {
     pub id: Uuid,
     pub name: String,
@@ -29,30 +44,159 @@ pub struct StoredBudget {
     pub provider: Option<String>,
     pub auto_reserve_from: Option<String>,
     pub auto_reserve_until_amount: Option<Decimal>,
+    /// Device that created it; `None` for rows from before devices were recorded.
+    #[serde(default)]
+    pub device_id: Option<Uuid>,
+    /// Only events recorded under this project count (`budget create --project`).
+    #[serde(default)]
+    pub project: Option<String>,
     pub created_at: DateTime<Utc>,
+    /// Last edit; sync keeps the most recently edited version of a row.
+    #[serde(default)]
+    pub updated_at: Option<DateTime<Utc>>,
 }
 
-#[derive(Debug, Clone)]
+impl StoredBudget {
+    /// When the row last changed.
+    pub fn changed_at(&self) -> DateTime<Utc> {
+        self.updated_at.unwrap_or(self.created_at)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StoredPiggy {
     pub id: Uuid,
     pub name: String,
     pub target_amount: Decimal,
     pub commodity: String,
     pub from_account: String,
+    /// Allocation order for `piggy allocate` (1 is funded first; unset goes last).
+    pub priority: Option<i64>,
+    pub group: Option<String>,
+    /// Share of an allocation relative to piggies with the same priority.
+    pub weight: Decimal,
+    /// Device that created it; `None` for rows from before devices were recorded.
+    #[serde(default)]
+    pub device_id: Option<Uuid>,
     pub created_at: DateTime<Utc>,
+    /// Last edit; sync keeps the most recently edited version of a row.
+    #[serde(default)]
+    pub updated_at: Option<DateTime<Utc>>,
 }
 
-#[derive(Debug, Clone)]
+impl StoredPiggy {
+    /// When the row last changed.
+    pub fn changed_at(&self) -> DateTime<Utc> {
+        self.updated_at.unwrap_or(self.created_at)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StoredPiggyFund {
     pub id: Uuid,
     pub piggy_id: Uuid,
     pub amount: Decimal,
     pub effective_at: DateTime<Utc>,
+    /// Device that added the fund; `None` for funds from before devices were recorded.
+    #[serde(default)]
+    pub device_id: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone)]
+pub struct StoredTask {
+    pub id: Uuid,
+    /// Human-facing task id (e.g. `depreciation-assets-equipment-laptop`).
+    pub task_id: String,
+    pub kind: String,
+    pub schedule: String,
+    /// Kind-specific parameters (see `crate::tasks::TaskSpec`).
+    pub spec: serde_json::Value,
+    pub enabled: bool,
+    /// First occurrence; later occurrences are derived from it and `runs`.
+    pub anchor_at: DateTime<Utc>,
+    /// Number of occurrences already materialized.
+    pub runs: u32,
+    pub last_run_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A saved `report` snapshot that later runs can diff against.
+#[derive(Debug, Clone)]
+pub struct StoredReportBaseline {
+    pub name: String,
+    /// Report filters the snapshot was taken with.
+    pub filters: serde_json::Value,
+    /// Event ids and per-account totals (see `crate::ReportSnapshot`).
+    pub snapshot: serde_json::Value,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Friendly metadata for a rate provider (`bankero provider add`).
+#[derive(Debug, Clone)]
+pub struct StoredProvider {
+    /// Normalized name, without the leading `@`.
+    pub name: String,
+    pub description: Option<String>,
+    /// `official`, `parallel`, `exchange` or `manual`.
+    pub kind: Option<String>,
+    pub url: Option<String>,
+    pub created_at: DateTime<Utc>,
+    /// Publishes only on business days: rates on weekends and `holidays` fall back
+    /// to the last business day.
+    pub business_days: bool,
+    pub holidays: Vec<NaiveDate>,
+}
+
+/// A saved command line (`bankero template add`).
+#[derive(Debug, Clone)]
+pub struct StoredTemplate {
+    pub name: String,
+    /// The command and its arguments, without the program name (`["buy", "480", ...]`).
+    pub argv: Vec<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Per-commodity settings (`bankero commodity set`).
+#[derive(Debug, Clone)]
+pub struct StoredCommodity {
+    /// Upper-cased code, e.g. `VES`.
+    pub code: String,
+    /// Decimal places kept in computed amounts.
+    pub places: Option<u32>,
+    /// `bankers` or `half-up`.
+    pub rounding: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A zero-based plan for one month (`bankero budget plan`).
+#[derive(Debug, Clone)]
+pub struct StoredBudgetPlan {
+    /// `YYYY-MM`.
+    pub month: String,
+    /// Projected income to allocate.
+    pub income: Decimal,
+    pub commodity: String,
+    /// `plan::Allocation` list, as JSON.
+    pub allocations: serde_json::Value,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A monthly spending limit on an account subtree (`bankero limit set`).
+#[derive(Debug, Clone)]
+pub struct StoredLimit {
+    pub account: String,
+    pub amount: Decimal,
+    pub commodity: String,
+    /// Hard limits block events unless `--override` is given; soft limits only warn.
+    pub hard: bool,
     pub created_at: DateTime<Utc>,
 }
 
 pub struct Db {
     conn: Connection,
+    /// The workspace directory holding the journal.
+    dir: PathBuf,
 }
 
 impl Db {
@@ -65,8 +209,10 @@ impl Db {
         let db_path = ws_dir.join("bankero.sqlite3");
         let conn = Connection::open(&db_path)
             .with_context(|| format!("Failed to open DB {}", db_path.display()))?;
+        crate::activity::watch(&ws_dir);
+        conn.busy_handler(Some(crate::activity::wait_for_writer))?;
 
-        let db = Self { conn };
+        let db = Self { conn, dir: ws_dir };
         db.migrate()?;
         Ok((db, db_path))
     }
@@ -137,6 +283,103 @@ impl Db {
 
             CREATE INDEX IF NOT EXISTS idx_piggy_funds_piggy_id ON piggy_funds(piggy_id);
             CREATE INDEX IF NOT EXISTS idx_piggy_funds_effective_at ON piggy_funds(effective_at);
+
+            CREATE TABLE IF NOT EXISTS tasks (
+                id TEXT PRIMARY KEY,
+                task_id TEXT NOT NULL,
+                kind TEXT NOT NULL,
+                schedule TEXT NOT NULL,
+                spec_json TEXT NOT NULL,
+                enabled INTEGER NOT NULL DEFAULT 1,
+                anchor_at TEXT NOT NULL,
+                runs INTEGER NOT NULL DEFAULT 0,
+                last_run_at TEXT,
+                created_at TEXT NOT NULL
+            );
+
+            CREATE UNIQUE INDEX IF NOT EXISTS idx_tasks_task_id ON tasks(task_id);
+
+            CREATE TABLE IF NOT EXISTS task_fulfillments (
+                task_id TEXT NOT NULL,
+                occurrence INTEGER NOT NULL,
+                event_id TEXT NOT NULL UNIQUE,
+                matched_at TEXT NOT NULL,
+                PRIMARY KEY (task_id, occurrence)
+            );
+
+            CREATE TABLE IF NOT EXISTS report_baselines (
+                name TEXT PRIMARY KEY,
+                filters_json TEXT NOT NULL,
+                snapshot_json TEXT NOT NULL,
+                created_at TEXT NOT NULL
+            );
+
+            CREATE TABLE IF NOT EXISTS archived_events (
+                id TEXT PRIMARY KEY,
+                action TEXT NOT NULL,
+                created_at TEXT NOT NULL,
+                effective_at TEXT NOT NULL,
+                payload_json TEXT NOT NULL,
+                snapshot_id TEXT NOT NULL,
+                archived_at TEXT NOT NULL
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_archived_events_snapshot ON archived_events(snapshot_id);
+
+            CREATE TABLE IF NOT EXISTS meta (
+                key TEXT PRIMARY KEY,
+                value TEXT NOT NULL
+            );
+
+            CREATE TABLE IF NOT EXISTS limits (
+                account TEXT NOT NULL,
+                commodity TEXT NOT NULL,
+                amount TEXT NOT NULL,
+                hard INTEGER NOT NULL DEFAULT 0,
+                created_at TEXT NOT NULL,
+                PRIMARY KEY (account, commodity)
+            );
+
+            CREATE TABLE IF NOT EXISTS commodities (
+                code TEXT PRIMARY KEY,
+                places INTEGER,
+                rounding TEXT,
+                created_at TEXT NOT NULL
+            );
+
+            CREATE TABLE IF NOT EXISTS account_commodities (
+                account TEXT PRIMARY KEY,
+                commodity TEXT NOT NULL,
+                created_at TEXT NOT NULL
+            );
+
+            CREATE TABLE IF NOT EXISTS budget_plans (
+                month TEXT PRIMARY KEY,
+                income TEXT NOT NULL,
+                commodity TEXT NOT NULL,
+                allocations_json TEXT NOT NULL,
+                created_at TEXT NOT NULL
+            );
+
+            CREATE TABLE IF NOT EXISTS providers (
+                name TEXT PRIMARY KEY,
+                description TEXT,
+                kind TEXT,
+                url TEXT,
+                created_at TEXT NOT NULL
+            );
+
+            CREATE TABLE IF NOT EXISTS provider_aliases (
+                alias TEXT PRIMARY KEY,
+                provider TEXT NOT NULL,
+                created_at TEXT NOT NULL
+            );
+
+            CREATE TABLE IF NOT EXISTS templates (
+                name TEXT PRIMARY KEY,
+                argv_json TEXT NOT NULL,
+                created_at TEXT NOT NULL
+            );
             "#,
         )?;
 
@@ -144,14 +387,142 @@ impl Db {
         // SQLite doesn't support IF NOT EXISTS for columns, so ignore duplicate-column errors.
         add_column_if_missing(&self.conn, "budgets", "auto_reserve_from", "TEXT")?;
         add_column_if_missing(&self.conn, "budgets", "auto_reserve_until_amount", "TEXT")?;
+        add_column_if_missing(&self.conn, "piggies", "priority", "INTEGER")?;
+        add_column_if_missing(&self.conn, "piggies", "group_name", "TEXT")?;
+        add_column_if_missing(&self.conn, "piggies", "weight", "TEXT")?;
+        add_column_if_missing(&self.conn, "rates", "source", "TEXT")?;
+        add_column_if_missing(&self.conn, "budgets", "device_id", "TEXT")?;
+        add_column_if_missing(&self.conn, "budgets", "project", "TEXT")?;
+        add_column_if_missing(&self.conn, "piggies", "device_id", "TEXT")?;
+        add_column_if_missing(&self.conn, "budgets", "updated_at", "TEXT")?;
+        add_column_if_missing(&self.conn, "piggies", "updated_at", "TEXT")?;
+        add_column_if_missing(&self.conn, "piggy_funds", "device_id", "TEXT")?;
+        add_column_if_missing(&self.conn, "providers", "business_days", "INTEGER")?;
+        add_column_if_missing(&self.conn, "providers", "holidays", "TEXT")?;
+
+        self.migrate_views()?;
+        Ok(())
+    }
+
+    /// Read-only query surface for external tools (Datasette, Metabase, sqlite3).
+    /// Recreated only when [`VIEWS_VERSION`] is newer than the one the workspace was
+    /// built with, so opening the journal does not rewrite the schema every time;
+    /// column names are stable, new columns are only ever appended. See `bankero help sql`.
+    fn migrate_views(&self) -> Result<()> {
+        let current = self
+            .get_meta(VIEWS_VERSION_KEY)?
+            .and_then(|v| v.parse::<u32>().ok())
+            .unwrap_or(0);
+        if current >= VIEWS_VERSION {
+            return Ok(());
+        }
+        let tx = self.conn.unchecked_transaction()?;
+        tx.execute_batch(
+            r#"
+            DROP VIEW IF EXISTS v_monthly_category_totals;
+            DROP VIEW IF EXISTS v_postings;
+            DROP VIEW IF EXISTS v_rates_latest;
+
+            -- Only the version of each amended event that replay counts: walk every
+            -- chain from its first version along the successor it follows (the one the
+            -- latest `resolve` picked, else the most recently created), as
+            -- `history::current_versions` does.
+            CREATE VIEW v_postings AS
+            WITH RECURSIVE
+            amends AS (
+                SELECT
+                    id,
+                    created_at,
+                    json_extract(payload_json, '$.metadata.supersedes') AS parent
+                FROM events
+                WHERE json_extract(payload_json, '$.metadata.correction') = 'amend'
+                  AND json_extract(payload_json, '$.metadata.supersedes')
+                      IN (SELECT id FROM events)
+            ),
+            picks AS (
+                SELECT
+                    json_extract(r.payload_json, '$.metadata.resolves') AS fork,
+                    json_extract(r.payload_json, '$.metadata.canonical') AS canonical
+                FROM events r
+                WHERE r.action = 'resolve'
+                  AND NOT EXISTS (
+                    SELECT 1 FROM events r2
+                    WHERE r2.action = 'resolve'
+                      AND json_extract(r2.payload_json, '$.metadata.resolves')
+                          = json_extract(r.payload_json, '$.metadata.resolves')
+                      AND (r2.created_at, r2.id) > (r.created_at, r.id)
+                  )
+            ),
+            followed AS (
+                SELECT
+                    a.parent,
+                    COALESCE(
+                        (SELECT p.canonical FROM picks p
+                         WHERE p.fork = a.parent
+                           AND p.canonical IN (SELECT id FROM amends WHERE parent = a.parent)),
+                        (SELECT a2.id FROM amends a2
+                         WHERE a2.parent = a.parent
+                         ORDER BY a2.created_at DESC, a2.id DESC
+                         LIMIT 1)
+                    ) AS child
+                FROM amends a
+                GROUP BY a.parent
+            ),
+            walk(id) AS (
+                SELECT id FROM events WHERE id NOT IN (SELECT id FROM amends)
+                UNION
+                SELECT f.child FROM followed f JOIN walk w ON f.parent = w.id
+            ),
+            current AS (
+                SELECT id FROM walk WHERE id NOT IN (SELECT parent FROM followed)
+            )
+            SELECT
+                e.id AS event_id,
+                e.action AS action,
+                e.effective_at AS effective_at,
+                substr(e.effective_at, 1, 7) AS month,
+                json_extract(p.value, '$.account') AS account,
+                json_extract(p.value, '$.commodity') AS commodity,
+                CAST(json_extract(p.value, '$.amount') AS TEXT) AS amount,
+                CAST(json_extract(p.value, '$.amount') AS REAL) AS amount_real,
+                json_extract(e.payload_json, '$.category') AS category,
+                json_extract(e.payload_json, '$.project') AS project,
+                json_extract(e.payload_json, '$.note') AS note
+            FROM events e, json_each(e.payload_json, '$.postings') p
+            WHERE e.id IN (SELECT id FROM current);
+
+            CREATE VIEW v_monthly_category_totals AS
+            SELECT
+                month,
+                COALESCE(category, account) AS category,
+                commodity,
+                SUM(amount_real) AS total,
+                COUNT(DISTINCT event_id) AS events
+            FROM v_postings
+            WHERE account LIKE 'expenses:%'
+               OR account LIKE 'income:%'
+               OR account LIKE 'external:%'
+            GROUP BY month, COALESCE(category, account), commodity;
+
+            CREATE VIEW v_rates_latest AS
+            SELECT r.provider, r.base, r.quote, r.rate, r.as_of
+            FROM rates r
+            WHERE r.as_of = (
+                SELECT MAX(r2.as_of) FROM rates r2
+                WHERE r2.provider = r.provider AND r2.base = r.base AND r2.quote = r.quote
+            );
+            "#,
+        )?;
+        self.set_meta(VIEWS_VERSION_KEY, &VIEWS_VERSION.to_string())?;
+        tx.commit()?;
         Ok(())
     }
 
     pub fn insert_piggy(&self, piggy: &StoredPiggy) -> Result<()> {
         self.conn.execute(
             r#"
-            INSERT INTO piggies (id, name, target_amount, commodity, from_account, created_at)
-            VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+            INSERT INTO piggies (id, name, target_amount, commodity, from_account, priority, group_name, weight, created_at, device_id, updated_at)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)
             "#,
             params![
                 piggy.id.to_string(),
@@ -159,78 +530,154 @@ impl Db {
                 piggy.target_amount.to_string(),
                 piggy.commodity,
                 piggy.from_account,
+                piggy.priority,
+                piggy.group,
+                piggy.weight.to_string(),
                 piggy.created_at.to_rfc3339(),
+                piggy.device_id.map(|d| d.to_string()),
+                piggy.updated_at.map(|t| t.to_rfc3339()),
             ],
         )?;
         Ok(())
     }
 
-    pub fn get_piggy_by_name(&self, name: &str) -> Result<Option<StoredPiggy>> {
-        let mut stmt = self.conn.prepare(
+    /// Replaces a piggy's settings with a newer version synced from another device.
+    pub fn update_piggy(&self, piggy: &StoredPiggy) -> Result<()> {
+        self.conn.execute(
             r#"
-            SELECT id, name, target_amount, commodity, from_account, created_at
-            FROM piggies
-            WHERE name = ?1
-            LIMIT 1
+            UPDATE piggies SET
+                name = ?2, target_amount = ?3, commodity = ?4, from_account = ?5,
+                priority = ?6, group_name = ?7, weight = ?8, updated_at = ?9
+            WHERE id = ?1
             "#,
+            params![
+                piggy.id.to_string(),
+                piggy.name,
+                piggy.target_amount.to_string(),
+                piggy.commodity,
+                piggy.from_account,
+                piggy.priority,
+                piggy.group,
+                piggy.weight.to_string(),
+                piggy.updated_at.map(|t| t.to_rfc3339()),
+            ],
         )?;
+        Ok(())
+    }
 
-        let mut rows = stmt.query(params![name])?;
-        let Some(row) = rows.next()? else {
-            return Ok(None);
-        };
-
-        let id: String = row.get(0)?;
-        let name: String = row.get(1)?;
-        let target_amount: String = row.get(2)?;
-        let commodity: String = row.get(3)?;
-        let from_account: String = row.get(4)?;
-        let created_at: String = row.get(5)?;
+    /// Updates allocation settings; `None` leaves a field unchanged.
+    pub fn set_piggy_allocation(
+        &self,
+        name: &str,
+        priority: Option<i64>,
+        group: Option<&str>,
+        weight: Option<Decimal>,
+    ) -> Result<usize> {
+        let changed = self.conn.execute(
+            r#"
+            UPDATE piggies SET
+                priority = COALESCE(?2, priority),
+                group_name = COALESCE(?3, group_name),
+                weight = COALESCE(?4, weight),
+                updated_at = ?5
+            WHERE name = ?1
+            "#,
+            params![
+                name,
+                priority,
+                group,
+                weight.map(|w| w.to_string()),
+                crate::config::now_utc().to_rfc3339(),
+            ],
+        )?;
+        Ok(changed)
+    }
 
-        let id = Uuid::parse_str(&id).context("Invalid piggy UUID")?;
-        let target_amount = target_amount
-            .parse::<Decimal>()
-            .context("Invalid decimal target_amount in piggies table")?;
-        let created_at = DateTime::parse_from_rfc3339(&created_at)
-            .context("Invalid created_at in piggies table")?
-            .with_timezone(&Utc);
+    pub fn rename_piggy(&self, id: Uuid, name: &str) -> Result<()> {
+        self.conn.execute(
+            "UPDATE piggies SET name = ?2 WHERE id = ?1",
+            params![id.to_string(), name],
+        )?;
+        Ok(())
+    }
 
-        Ok(Some(StoredPiggy {
-            id,
-            name,
-            target_amount,
-            commodity,
-            from_account,
-            created_at,
-        }))
+    pub fn get_piggy_by_name(&self, name: &str) -> Result<Option<StoredPiggy>> {
+        Ok(self
+            .query_piggies("WHERE name = ?1", params![name])?
+            .into_iter()
+            .next())
     }
 
     pub fn list_piggies(&self) -> Result<Vec<StoredPiggy>> {
-        let mut stmt = self.conn.prepare(
+        self.query_piggies("", params![])
+    }
+
+    fn query_piggies(
+        &self,
+        where_clause: &str,
+        args: &[&dyn rusqlite::ToSql],
+    ) -> Result<Vec<StoredPiggy>> {
+        let sql = format!(
             r#"
-            SELECT id, name, target_amount, commodity, from_account, created_at
+            SELECT id, name, target_amount, commodity, from_account, priority, group_name, weight, created_at, device_id, updated_at
             FROM piggies
+            {where_clause}
             ORDER BY created_at ASC
-            "#,
-        )?;
-
-        let rows = stmt.query_map([], |row| {
+            "#
+        );
+        let mut stmt = self.conn.prepare(&sql)?;
+        let rows = stmt.query_map(args, |row| {
             let id: String = row.get(0)?;
             let name: String = row.get(1)?;
             let target_amount: String = row.get(2)?;
             let commodity: String = row.get(3)?;
             let from_account: String = row.get(4)?;
-            let created_at: String = row.get(5)?;
-            Ok((id, name, target_amount, commodity, from_account, created_at))
+            let priority: Option<i64> = row.get(5)?;
+            let group: Option<String> = row.get(6)?;
+            let weight: Option<String> = row.get(7)?;
+            let created_at: String = row.get(8)?;
+            let device_id: Option<String> = row.get(9)?;
+            let updated_at: Option<String> = row.get(10)?;
+            Ok((
+                id,
+                name,
+                target_amount,
+                commodity,
+                from_account,
+                priority,
+                group,
+                weight,
+                created_at,
+                device_id,
+                updated_at,
+            ))
         })?;
 
         let mut out = Vec::new();
         for row in rows {
-            let (id, name, target_amount, commodity, from_account, created_at) = row?;
+            let (
+                id,
+                name,
+                target_amount,
+                commodity,
+                from_account,
+                priority,
+                group,
+                weight,
+                created_at,
+                device_id,
+                updated_at,
+            ) = row?;
             let id = Uuid::parse_str(&id).context("Invalid piggy UUID")?;
             let target_amount = target_amount
                 .parse::<Decimal>()
                 .context("Invalid decimal target_amount in piggies table")?;
+            let weight = match weight {
+                Some(w) => w
+                    .parse::<Decimal>()
+                    .context("Invalid decimal weight in piggies table")?,
+                None => Decimal::ONE,
+            };
             let created_at = DateTime::parse_from_rfc3339(&created_at)
                 .context("Invalid created_at in piggies table")?
                 .with_timezone(&Utc);
@@ -241,7 +688,12 @@ impl Db {
                 target_amount,
                 commodity,
                 from_account,
+                priority,
+                group,
+                weight,
+                device_id: parse_device_id(device_id)?,
                 created_at,
+                updated_at: parse_updated_at(updated_at, "piggies")?,
             });
         }
         Ok(out)
@@ -250,8 +702,8 @@ impl Db {
     pub fn insert_piggy_fund(&self, fund: &StoredPiggyFund) -> Result<()> {
         self.conn.execute(
             r#"
-            INSERT INTO piggy_funds (id, piggy_id, amount, effective_at, created_at)
-            VALUES (?1, ?2, ?3, ?4, ?5)
+            INSERT INTO piggy_funds (id, piggy_id, amount, effective_at, created_at, device_id)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6)
             "#,
             params![
                 fund.id.to_string(),
@@ -259,11 +711,72 @@ impl Db {
                 fund.amount.to_string(),
                 fund.effective_at.to_rfc3339(),
                 fund.created_at.to_rfc3339(),
+                fund.device_id.map(|d| d.to_string()),
             ],
         )?;
         Ok(())
     }
 
+    /// Inserts a fund unless one with its id exists; returns whether it was new.
+    pub fn insert_piggy_fund_ignore(&self, fund: &StoredPiggyFund) -> Result<bool> {
+        let affected = self.conn.execute(
+            r#"
+            INSERT OR IGNORE INTO piggy_funds (id, piggy_id, amount, effective_at, created_at, device_id)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+            "#,
+            params![
+                fund.id.to_string(),
+                fund.piggy_id.to_string(),
+                fund.amount.to_string(),
+                fund.effective_at.to_rfc3339(),
+                fund.created_at.to_rfc3339(),
+                fund.device_id.map(|d| d.to_string()),
+            ],
+        )?;
+        Ok(affected > 0)
+    }
+
+    /// Every fund for a piggy, oldest first.
+    pub fn list_piggy_funds(&self, piggy_id: Uuid) -> Result<Vec<StoredPiggyFund>> {
+        let mut stmt = self.conn.prepare(
+            r#"
+            SELECT id, amount, effective_at, created_at, device_id
+            FROM piggy_funds
+            WHERE piggy_id = ?1
+            ORDER BY effective_at ASC, created_at ASC
+            "#,
+        )?;
+
+        let rows = stmt.query_map(params![piggy_id.to_string()], |row| {
+            let id: String = row.get(0)?;
+            let amount: String = row.get(1)?;
+            let effective_at: String = row.get(2)?;
+            let created_at: String = row.get(3)?;
+            let device_id: Option<String> = row.get(4)?;
+            Ok((id, amount, effective_at, created_at, device_id))
+        })?;
+
+        let mut out = Vec::new();
+        for row in rows {
+            let (id, amount, effective_at, created_at, device_id) = row?;
+            out.push(StoredPiggyFund {
+                id: Uuid::parse_str(&id).context("Invalid piggy fund UUID")?,
+                piggy_id,
+                amount: amount
+                    .parse::<Decimal>()
+                    .context("Invalid decimal amount in piggy_funds table")?,
+                effective_at: DateTime::parse_from_rfc3339(&effective_at)
+                    .context("Invalid effective_at in piggy_funds table")?
+                    .with_timezone(&Utc),
+                device_id: parse_device_id(device_id)?,
+                created_at: DateTime::parse_from_rfc3339(&created_at)
+                    .context("Invalid created_at in piggy_funds table")?
+                    .with_timezone(&Utc),
+            });
+        }
+        Ok(out)
+    }
+
     pub fn piggy_funded_total(&self, piggy_id: Uuid) -> Result<Decimal> {
         let mut stmt = self.conn.prepare(
             r#"
@@ -296,19 +809,76 @@ impl Db {
         quote: &str,
         as_of: DateTime<Utc>,
         rate: Decimal,
+        source: &str,
     ) -> Result<()> {
+        // Re-storing the same value (e.g. a peer sending back our own rate) keeps its
+        // original provenance; a different value takes the new source.
         self.conn.execute(
             r#"
-            INSERT INTO rates (provider, base, quote, as_of, rate)
-            VALUES (?1, ?2, ?3, ?4, ?5)
-            ON CONFLICT(provider, base, quote, as_of) DO UPDATE SET rate = excluded.rate
+            INSERT INTO rates (provider, base, quote, as_of, rate, source)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+            ON CONFLICT(provider, base, quote, as_of) DO UPDATE SET
+                source = CASE
+                    WHEN rates.rate = excluded.rate AND rates.source IS NOT NULL THEN rates.source
+                    ELSE excluded.source
+                END,
+                rate = excluded.rate
             "#,
-            params![provider, base, quote, as_of.to_rfc3339(), rate.to_string(),],
+            params![
+                provider,
+                base,
+                quote,
+                as_of.to_rfc3339(),
+                rate.to_string(),
+                source
+            ],
         )?;
         Ok(())
     }
 
+    /// Where each of the provider's rates came from (see [`StoredRate::source`]), keyed by
+    /// `(base, quote, as_of)` and optionally limited to one base or pair. Rates stored
+    /// under the provider's aliases count too, like in [`Db::get_rate_as_of`]; the
+    /// provider's own row wins when both have one at the same instant.
+    pub fn list_rate_sources(
+        &self,
+        provider: &str,
+        base: Option<&str>,
+        quote: Option<&str>,
+    ) -> Result<RateSources> {
+        let mut stmt = self.conn.prepare(
+            r#"
+            SELECT base, quote, as_of, source
+            FROM rates
+            WHERE provider IN (
+                SELECT ?1
+                UNION SELECT provider FROM provider_aliases WHERE alias = ?1
+                UNION SELECT alias FROM provider_aliases
+                    WHERE provider = ?1
+                       OR provider = (SELECT provider FROM provider_aliases WHERE alias = ?1)
+              )
+              AND (?2 IS NULL OR base = ?2)
+              AND (?3 IS NULL OR quote = ?3)
+            ORDER BY provider = ?1 DESC
+            "#,
+        )?;
+        let mut rows = stmt.query(params![provider, base, quote])?;
+        let mut out = RateSources::new();
+        while let Some(row) = rows.next()? {
+            let as_of: String = row.get(2)?;
+            let as_of = DateTime::parse_from_rfc3339(&as_of)
+                .context("Invalid as_of in rates table")?
+                .with_timezone(&Utc);
+            out.entry((row.get(0)?, row.get(1)?, as_of))
+                .or_insert(row.get(3)?);
+        }
+        Ok(out)
+    }
+
     /// Returns the latest known rate at or before `as_of`.
+    ///
+    /// A provider's series includes the rates stored under its aliases (and, for an
+    /// alias, under the provider it points to and that provider's other aliases).
     pub fn get_rate_as_of(
         &self,
         provider: &str,
@@ -320,11 +890,17 @@ impl Db {
             r#"
             SELECT as_of, rate
             FROM rates
-            WHERE provider = ?1
+            WHERE provider IN (
+                SELECT ?1
+                UNION SELECT provider FROM provider_aliases WHERE alias = ?1
+                UNION SELECT alias FROM provider_aliases
+                    WHERE provider = ?1
+                       OR provider = (SELECT provider FROM provider_aliases WHERE alias = ?1)
+              )
               AND base = ?2
               AND quote = ?3
               AND as_of <= ?4
-            ORDER BY as_of DESC
+            ORDER BY as_of DESC, provider = ?1 DESC
             LIMIT 1
             "#,
         )?;
@@ -347,32 +923,124 @@ impl Db {
         Ok(Some((as_of, rate)))
     }
 
-    pub fn list_rates(
+    /// The most recent rate stored for `base`/`quote` by any provider, with that provider.
+    pub fn get_latest_rate(
         &self,
-        provider: &str,
         base: &str,
         quote: &str,
-        limit: usize,
-    ) -> Result<Vec<(DateTime<Utc>, Decimal)>> {
+    ) -> Result<Option<(String, DateTime<Utc>, Decimal)>> {
         let mut stmt = self.conn.prepare(
             r#"
-            SELECT as_of, rate
+            SELECT provider, as_of, rate
             FROM rates
-            WHERE provider = ?1
-              AND base = ?2
-              AND quote = ?3
-            ORDER BY as_of DESC
-            LIMIT ?4
+            WHERE base = ?1 AND quote = ?2
+            ORDER BY as_of DESC, provider ASC
+            LIMIT 1
             "#,
         )?;
 
-        let rows = stmt.query_map(params![provider, base, quote, limit as i64], |row| {
-            let as_of_raw: String = row.get(0)?;
-            let rate_raw: String = row.get(1)?;
-            Ok((as_of_raw, rate_raw))
-        })?;
+        let mut rows = stmt.query(params![base, quote])?;
+        let Some(row) = rows.next()? else {
+            return Ok(None);
+        };
 
-        let mut out = Vec::new();
+        let provider: String = row.get(0)?;
+        let as_of_raw: String = row.get(1)?;
+        let rate_raw: String = row.get(2)?;
+
+        let as_of = DateTime::parse_from_rfc3339(&as_of_raw)
+            .context("Invalid as_of in rates table")?
+            .with_timezone(&Utc);
+        let rate = rate_raw
+            .parse::<Decimal>()
+            .context("Invalid decimal rate in rates table")?;
+
+        Ok(Some((provider, as_of, rate)))
+    }
+
+    /// The first stored rate strictly after `as_of` (the upper point for interpolation).
+    pub fn get_rate_after(
+        &self,
+        provider: &str,
+        base: &str,
+        quote: &str,
+        as_of: DateTime<Utc>,
+    ) -> Result<Option<(DateTime<Utc>, Decimal)>> {
+        let mut stmt = self.conn.prepare(
+            r#"
+            SELECT as_of, rate
+            FROM rates
+            WHERE provider IN (
+                SELECT ?1
+                UNION SELECT provider FROM provider_aliases WHERE alias = ?1
+                UNION SELECT alias FROM provider_aliases
+                    WHERE provider = ?1
+                       OR provider = (SELECT provider FROM provider_aliases WHERE alias = ?1)
+              )
+              AND base = ?2
+              AND quote = ?3
+              AND as_of > ?4
+            ORDER BY as_of ASC, provider = ?1 DESC
+            LIMIT 1
+            "#,
+        )?;
+
+        let mut rows = stmt.query(params![provider, base, quote, as_of.to_rfc3339()])?;
+        let Some(row) = rows.next()? else {
+            return Ok(None);
+        };
+
+        let as_of_raw: String = row.get(0)?;
+        let rate_raw: String = row.get(1)?;
+        let as_of = DateTime::parse_from_rfc3339(&as_of_raw)
+            .context("Invalid as_of in rates table")?
+            .with_timezone(&Utc);
+        let rate = rate_raw
+            .parse::<Decimal>()
+            .context("Invalid decimal rate in rates table")?;
+
+        Ok(Some((as_of, rate)))
+    }
+
+    /// The latest `limit` rates of the pair, newest first, from the provider's series
+    /// including its aliases (one rate per time, preferring the name asked for).
+    pub fn list_rates(
+        &self,
+        provider: &str,
+        base: &str,
+        quote: &str,
+        limit: usize,
+    ) -> Result<Vec<(DateTime<Utc>, Decimal)>> {
+        let mut stmt = self.conn.prepare(
+            r#"
+            SELECT as_of, rate
+            FROM (
+                SELECT as_of, rate,
+                       ROW_NUMBER() OVER (PARTITION BY as_of ORDER BY provider = ?1 DESC) AS pick
+                FROM rates
+                WHERE provider IN (
+                    SELECT ?1
+                    UNION SELECT provider FROM provider_aliases WHERE alias = ?1
+                    UNION SELECT alias FROM provider_aliases
+                        WHERE provider = ?1
+                           OR provider = (SELECT provider FROM provider_aliases WHERE alias = ?1)
+                  )
+                  AND base = ?2
+                  AND quote = ?3
+            )
+            WHERE pick = 1
+            ORDER BY as_of DESC
+            LIMIT ?4
+            "#,
+        )?;
+
+        let rows = stmt.query_map(params![provider, base, quote, limit as i64], |row| {
+            let as_of_raw: String = row.get(0)?;
+            let rate_raw: String = row.get(1)?;
+            Ok((as_of_raw, rate_raw))
+        })?;
+
+        let mut out = Vec::new();
         for row in rows {
             let (as_of_raw, rate_raw) = row?;
             let as_of = DateTime::parse_from_rfc3339(&as_of_raw)
@@ -386,11 +1054,44 @@ impl Db {
         Ok(out)
     }
 
+    /// Rates stored for `provider` at exactly `as_of`, one per pair.
+    pub fn list_rates_at(
+        &self,
+        provider: &str,
+        as_of: DateTime<Utc>,
+    ) -> Result<Vec<(String, String, Decimal)>> {
+        let mut stmt = self.conn.prepare(
+            r#"
+            SELECT base, quote, rate
+            FROM rates
+            WHERE provider = ?1 AND as_of = ?2
+            ORDER BY base ASC, quote ASC
+            "#,
+        )?;
+
+        let rows = stmt.query_map(params![provider, as_of.to_rfc3339()], |row| {
+            let base: String = row.get(0)?;
+            let quote: String = row.get(1)?;
+            let rate_raw: String = row.get(2)?;
+            Ok((base, quote, rate_raw))
+        })?;
+
+        let mut out = Vec::new();
+        for row in rows {
+            let (base, quote, rate_raw) = row?;
+            let rate = rate_raw
+                .parse::<Decimal>()
+                .context("Invalid decimal rate in rates table")?;
+            out.push((base, quote, rate));
+        }
+        Ok(out)
+    }
+
     pub fn list_latest_rates_for_provider(
         &self,
         provider: &str,
         limit: usize,
-    ) -> Result<Vec<(String, String, DateTime<Utc>, Decimal)>> {
+    ) -> Result<Vec<LatestRateRow>> {
         let mut stmt = self.conn.prepare(
             r#"
             SELECT r.base, r.quote, r.as_of, r.rate
@@ -435,7 +1136,7 @@ impl Db {
         provider: &str,
         base: &str,
         limit: usize,
-    ) -> Result<Vec<(String, String, DateTime<Utc>, Decimal)>> {
+    ) -> Result<Vec<LatestRateRow>> {
         let mut stmt = self.conn.prepare(
             r#"
             SELECT r.base, r.quote, r.as_of, r.rate
@@ -491,12 +1192,43 @@ impl Db {
         Ok(())
     }
 
-    /// Inserts an event if it does not exist yet.
+    /// Where this workspace keeps attachment blobs (`attachments/<sha256>`).
+    pub fn attachments_dir(&self) -> PathBuf {
+        self.dir.join("attachments")
+    }
+
+    /// Marks a long write (`what`) in progress until the returned guard is dropped.
+    pub fn activity(&self, what: &str) -> Result<crate::activity::Activity> {
+        crate::activity::Activity::begin(&self.dir, what)
+    }
+
+    /// Runs `f` inside one SQLite transaction (bulk inserts are much faster this way).
+    ///
+    /// The write lock is taken up front, so other writers wait for the whole of `f`.
+    pub fn with_transaction<T>(&self, f: impl FnOnce() -> Result<T>) -> Result<T> {
+        self.conn.execute_batch("BEGIN IMMEDIATE")?;
+        match f() {
+            Ok(out) => {
+                self.conn.execute_batch("COMMIT")?;
+                Ok(out)
+            }
+            Err(e) => {
+                self.conn.execute_batch("ROLLBACK").ok();
+                Err(e)
+            }
+        }
+    }
+
+    /// Inserts an event if it does not exist yet (live or archived by a snapshot).
     /// Returns true if inserted, false if it already existed.
     pub fn insert_event_ignore(&self, id: Uuid, payload: &EventPayload) -> Result<bool> {
         let json = serde_json::to_string(payload)?;
         let affected = self.conn.execute(
-            "INSERT OR IGNORE INTO events (id, action, created_at, effective_at, payload_json) VALUES (?1, ?2, ?3, ?4, ?5)",
+            r#"
+            INSERT OR IGNORE INTO events (id, action, created_at, effective_at, payload_json)
+            SELECT ?1, ?2, ?3, ?4, ?5
+            WHERE NOT EXISTS (SELECT 1 FROM archived_events WHERE id = ?1)
+            "#,
             params![
                 id.to_string(),
                 payload.action,
@@ -508,6 +1240,16 @@ impl Db {
         Ok(affected > 0)
     }
 
+    /// Whether the journal already holds this event (live or archived by a snapshot).
+    pub fn has_event(&self, id: Uuid) -> Result<bool> {
+        let count: i64 = self.conn.query_row(
+            "SELECT (SELECT COUNT(*) FROM events WHERE id = ?1) + (SELECT COUNT(*) FROM archived_events WHERE id = ?1)",
+            params![id.to_string()],
+            |row| row.get(0),
+        )?;
+        Ok(count > 0)
+    }
+
     pub fn count_events(&self) -> Result<i64> {
         let mut stmt = self.conn.prepare("SELECT COUNT(*) FROM events")?;
         let count: i64 = stmt.query_row([], |row| row.get(0))?;
@@ -523,7 +1265,7 @@ impl Db {
     pub fn list_all_rates(&self) -> Result<Vec<StoredRate>> {
         let mut stmt = self.conn.prepare(
             r#"
-            SELECT provider, base, quote, as_of, rate
+            SELECT provider, base, quote, as_of, rate, source
             FROM rates
             ORDER BY provider ASC, base ASC, quote ASC, as_of ASC
             "#,
@@ -535,12 +1277,13 @@ impl Db {
             let quote: String = row.get(2)?;
             let as_of_raw: String = row.get(3)?;
             let rate_raw: String = row.get(4)?;
-            Ok((provider, base, quote, as_of_raw, rate_raw))
+            let source: Option<String> = row.get(5)?;
+            Ok((provider, base, quote, as_of_raw, rate_raw, source))
         })?;
 
         let mut out = Vec::new();
         for row in rows {
-            let (provider, base, quote, as_of_raw, rate_raw) = row?;
+            let (provider, base, quote, as_of_raw, rate_raw, source) = row?;
             let as_of = DateTime::parse_from_rfc3339(&as_of_raw)
                 .context("Invalid as_of in rates table")?
                 .with_timezone(&Utc);
@@ -553,15 +1296,25 @@ impl Db {
                 quote,
                 as_of,
                 rate,
+                source,
             });
         }
         Ok(out)
     }
 
     pub fn list_events(&self) -> Result<Vec<StoredEvent>> {
-        let mut stmt = self.conn.prepare(
-            "SELECT id, action, created_at, effective_at, payload_json FROM events ORDER BY effective_at ASC, created_at ASC",
-        )?;
+        self.query_events("events")
+    }
+
+    /// Events moved out of the journal by `snapshot create`.
+    pub fn list_archived_events(&self) -> Result<Vec<StoredEvent>> {
+        self.query_events("archived_events")
+    }
+
+    fn query_events(&self, table: &str) -> Result<Vec<StoredEvent>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT id, action, created_at, effective_at, payload_json FROM {table} ORDER BY effective_at ASC, created_at ASC"
+        ))?;
 
         let mut out = Vec::new();
         let rows = stmt.query_map([], |row| {
@@ -598,11 +1351,34 @@ impl Db {
         Ok(out)
     }
 
+    /// Moves the given events into `archived_events`, tagged with the snapshot that replaced them.
+    pub fn archive_events(
+        &self,
+        ids: &[Uuid],
+        snapshot_id: Uuid,
+        archived_at: DateTime<Utc>,
+    ) -> Result<usize> {
+        let archived_at = archived_at.to_rfc3339();
+        let mut moved = 0;
+        for id in ids {
+            moved += self.conn.execute(
+                r#"
+                INSERT INTO archived_events (id, action, created_at, effective_at, payload_json, snapshot_id, archived_at)
+                SELECT id, action, created_at, effective_at, payload_json, ?2, ?3 FROM events WHERE id = ?1
+                "#,
+                params![id.to_string(), snapshot_id.to_string(), archived_at],
+            )?;
+            self.conn
+                .execute("DELETE FROM events WHERE id = ?1", params![id.to_string()])?;
+        }
+        Ok(moved)
+    }
+
     pub fn insert_budget(&self, budget: &StoredBudget) -> Result<()> {
         self.conn.execute(
             r#"
-            INSERT INTO budgets (id, name, amount, commodity, month, category, account, provider, auto_reserve_from, auto_reserve_until_amount, created_at)
-            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)
+            INSERT INTO budgets (id, name, amount, commodity, month, category, account, provider, auto_reserve_from, auto_reserve_until_amount, created_at, device_id, project, updated_at)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)
             "#,
             params![
                 budget.id.to_string(),
@@ -616,15 +1392,54 @@ impl Db {
                 budget.auto_reserve_from,
                 budget.auto_reserve_until_amount.map(|d| d.to_string()),
                 budget.created_at.to_rfc3339(),
+                budget.device_id.map(|d| d.to_string()),
+                budget.project,
+                budget.updated_at.map(|t| t.to_rfc3339()),
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Replaces a budget's settings with a newer version synced from another device.
+    pub fn update_budget(&self, budget: &StoredBudget) -> Result<()> {
+        self.conn.execute(
+            r#"
+            UPDATE budgets SET
+                name = ?2, amount = ?3, commodity = ?4, month = ?5, category = ?6,
+                account = ?7, provider = ?8, auto_reserve_from = ?9,
+                auto_reserve_until_amount = ?10, project = ?11, updated_at = ?12
+            WHERE id = ?1
+            "#,
+            params![
+                budget.id.to_string(),
+                budget.name,
+                budget.amount.to_string(),
+                budget.commodity,
+                budget.month,
+                budget.category,
+                budget.account,
+                budget.provider,
+                budget.auto_reserve_from,
+                budget.auto_reserve_until_amount.map(|d| d.to_string()),
+                budget.project,
+                budget.updated_at.map(|t| t.to_rfc3339()),
             ],
         )?;
         Ok(())
     }
 
+    pub fn rename_budget(&self, id: Uuid, name: &str) -> Result<()> {
+        self.conn.execute(
+            "UPDATE budgets SET name = ?2 WHERE id = ?1",
+            params![id.to_string(), name],
+        )?;
+        Ok(())
+    }
+
     pub fn get_budget_by_name(&self, name: &str) -> Result<Option<StoredBudget>> {
         let mut stmt = self.conn.prepare(
             r#"
-            SELECT id, name, amount, commodity, month, category, account, provider, auto_reserve_from, auto_reserve_until_amount, created_at
+            SELECT id, name, amount, commodity, month, category, account, provider, auto_reserve_from, auto_reserve_until_amount, created_at, device_id, project, updated_at
             FROM budgets
             WHERE name = ?1
             LIMIT 1
@@ -647,6 +1462,9 @@ impl Db {
         let auto_reserve_from: Option<String> = row.get(8)?;
         let auto_reserve_until_amount: Option<String> = row.get(9)?;
         let created_at: String = row.get(10)?;
+        let device_id: Option<String> = row.get(11)?;
+        let project: Option<String> = row.get(12)?;
+        let updated_at: Option<String> = row.get(13)?;
 
         let id = Uuid::parse_str(&id).context("Invalid budget UUID")?;
         let amount = amount
@@ -671,7 +1489,10 @@ impl Db {
             provider,
             auto_reserve_from,
             auto_reserve_until_amount,
+            device_id: parse_device_id(device_id)?,
+            project,
             created_at,
+            updated_at: parse_updated_at(updated_at, "budgets")?,
         }))
     }
 
@@ -685,10 +1506,16 @@ impl Db {
             r#"
             UPDATE budgets
             SET auto_reserve_from = ?2,
-                auto_reserve_until_amount = ?3
+                auto_reserve_until_amount = ?3,
+                updated_at = ?4
             WHERE name = ?1
             "#,
-            params![name, from_prefix, until_amount.map(|d| d.to_string()),],
+            params![
+                name,
+                from_prefix,
+                until_amount.map(|d| d.to_string()),
+                crate::config::now_utc().to_rfc3339(),
+            ],
         )?;
         Ok(changed)
     }
@@ -696,7 +1523,7 @@ impl Db {
     pub fn list_budgets(&self) -> Result<Vec<StoredBudget>> {
         let mut stmt = self.conn.prepare(
             r#"
-            SELECT id, name, amount, commodity, month, category, account, provider, auto_reserve_from, auto_reserve_until_amount, created_at
+            SELECT id, name, amount, commodity, month, category, account, provider, auto_reserve_from, auto_reserve_until_amount, created_at, device_id, project, updated_at
             FROM budgets
             ORDER BY created_at ASC
             "#,
@@ -714,6 +1541,9 @@ impl Db {
             let auto_reserve_from: Option<String> = row.get(8)?;
             let auto_reserve_until_amount: Option<String> = row.get(9)?;
             let created_at: String = row.get(10)?;
+            let device_id: Option<String> = row.get(11)?;
+            let project: Option<String> = row.get(12)?;
+            let updated_at: Option<String> = row.get(13)?;
             Ok((
                 id,
                 name,
@@ -726,6 +1556,9 @@ impl Db {
                 auto_reserve_from,
                 auto_reserve_until_amount,
                 created_at,
+                device_id,
+                project,
+                updated_at,
             ))
         })?;
 
@@ -743,6 +1576,9 @@ impl Db {
                 auto_reserve_from,
                 auto_reserve_until_amount,
                 created_at,
+                device_id,
+                project,
+                updated_at,
             ) = row?;
             let id = Uuid::parse_str(&id).context("Invalid budget UUID")?;
             let amount = amount
@@ -767,12 +1603,737 @@ impl Db {
                 provider,
                 auto_reserve_from,
                 auto_reserve_until_amount,
+                device_id: parse_device_id(device_id)?,
+                project,
+                created_at,
+                updated_at: parse_updated_at(updated_at, "budgets")?,
+            });
+        }
+
+        Ok(out)
+    }
+
+    /// Saves (or replaces) the report baseline called `baseline.name`.
+    pub fn upsert_report_baseline(&self, baseline: &StoredReportBaseline) -> Result<()> {
+        self.conn.execute(
+            r#"
+            INSERT INTO report_baselines (name, filters_json, snapshot_json, created_at)
+            VALUES (?1, ?2, ?3, ?4)
+            ON CONFLICT(name) DO UPDATE SET
+                filters_json = excluded.filters_json,
+                snapshot_json = excluded.snapshot_json,
+                created_at = excluded.created_at
+            "#,
+            params![
+                baseline.name,
+                serde_json::to_string(&baseline.filters)?,
+                serde_json::to_string(&baseline.snapshot)?,
+                baseline.created_at.to_rfc3339(),
+            ],
+        )?;
+        Ok(())
+    }
+
+    pub fn get_report_baseline(&self, name: &str) -> Result<Option<StoredReportBaseline>> {
+        let mut stmt = self.conn.prepare(
+            r#"
+            SELECT name, filters_json, snapshot_json, created_at
+            FROM report_baselines
+            WHERE name = ?1
+            LIMIT 1
+            "#,
+        )?;
+
+        let mut rows = stmt.query(params![name])?;
+        let Some(row) = rows.next()? else {
+            return Ok(None);
+        };
+
+        let name: String = row.get(0)?;
+        let filters_json: String = row.get(1)?;
+        let snapshot_json: String = row.get(2)?;
+        let created_at: String = row.get(3)?;
+
+        Ok(Some(StoredReportBaseline {
+            name,
+            filters: serde_json::from_str(&filters_json)
+                .context("Invalid filters_json in report_baselines table")?,
+            snapshot: serde_json::from_str(&snapshot_json)
+                .context("Invalid snapshot_json in report_baselines table")?,
+            created_at: DateTime::parse_from_rfc3339(&created_at)
+                .context("Invalid created_at in report_baselines table")?
+                .with_timezone(&Utc),
+        }))
+    }
+
+    /// `(name, CREATE statement)` of every table and index, for diagnostics.
+    pub fn schema(&self) -> Result<Vec<(String, String)>> {
+        let mut stmt = self.conn.prepare(
+            r#"
+            SELECT name, sql
+            FROM sqlite_master
+            WHERE sql IS NOT NULL AND name NOT LIKE 'sqlite_%'
+            ORDER BY type DESC, name ASC
+            "#,
+        )?;
+        let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        Ok(rows.collect::<rusqlite::Result<_>>()?)
+    }
+
+    /// Row count of every table, for diagnostics.
+    pub fn table_counts(&self) -> Result<Vec<(String, i64)>> {
+        let mut out = Vec::new();
+        for (name, sql) in self.schema()? {
+            if !sql
+                .trim_start()
+                .to_ascii_uppercase()
+                .starts_with("CREATE TABLE")
+            {
+                continue;
+            }
+            let count: i64 =
+                self.conn
+                    .query_row(&format!("SELECT COUNT(*) FROM \"{name}\""), [], |row| {
+                        row.get(0)
+                    })?;
+            out.push((name, count));
+        }
+        Ok(out)
+    }
+
+    /// Small per-workspace state (e.g. when `notify` last ran).
+    pub fn get_meta(&self, key: &str) -> Result<Option<String>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT value FROM meta WHERE key = ?1 LIMIT 1")?;
+        let mut rows = stmt.query(params![key])?;
+        match rows.next()? {
+            Some(row) => Ok(Some(row.get(0)?)),
+            None => Ok(None),
+        }
+    }
+
+    pub fn set_meta(&self, key: &str, value: &str) -> Result<()> {
+        self.conn.execute(
+            "INSERT INTO meta (key, value) VALUES (?1, ?2) ON CONFLICT(key) DO UPDATE SET value = excluded.value",
+            params![key, value],
+        )?;
+        Ok(())
+    }
+
+    /// Saves (or replaces) the limit for `limit.account` in `limit.commodity`.
+    pub fn upsert_limit(&self, limit: &StoredLimit) -> Result<()> {
+        self.conn.execute(
+            r#"
+            INSERT INTO limits (account, commodity, amount, hard, created_at)
+            VALUES (?1, ?2, ?3, ?4, ?5)
+            ON CONFLICT(account, commodity) DO UPDATE SET
+                amount = excluded.amount,
+                hard = excluded.hard,
+                created_at = excluded.created_at
+            "#,
+            params![
+                limit.account,
+                limit.commodity,
+                limit.amount.to_string(),
+                limit.hard as i64,
+                limit.created_at.to_rfc3339(),
+            ],
+        )?;
+        Ok(())
+    }
+
+    pub fn delete_limit(&self, account: &str, commodity: Option<&str>) -> Result<usize> {
+        Ok(self.conn.execute(
+            "DELETE FROM limits WHERE account = ?1 AND (?2 IS NULL OR commodity = ?2)",
+            params![account, commodity],
+        )?)
+    }
+
+    pub fn list_limits(&self) -> Result<Vec<StoredLimit>> {
+        let mut stmt = self.conn.prepare(
+            r#"
+            SELECT account, commodity, amount, hard, created_at
+            FROM limits
+            ORDER BY account ASC, commodity ASC
+            "#,
+        )?;
+
+        let mut rows = stmt.query([])?;
+        let mut out = Vec::new();
+        while let Some(row) = rows.next()? {
+            let amount: String = row.get(2)?;
+            let hard: i64 = row.get(3)?;
+            let created_at: String = row.get(4)?;
+            out.push(StoredLimit {
+                account: row.get(0)?,
+                commodity: row.get(1)?,
+                amount: amount.parse().context("Invalid amount in limits table")?,
+                hard: hard != 0,
+                created_at: DateTime::parse_from_rfc3339(&created_at)
+                    .context("Invalid created_at in limits table")?
+                    .with_timezone(&Utc),
+            });
+        }
+        Ok(out)
+    }
+
+    /// Saves (or replaces) the metadata for `provider.name`.
+    pub fn upsert_provider(&self, provider: &StoredProvider) -> Result<()> {
+        self.conn.execute(
+            r#"
+            INSERT INTO providers (name, description, kind, url, created_at, business_days, holidays)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+            ON CONFLICT(name) DO UPDATE SET
+                description = excluded.description,
+                kind = excluded.kind,
+                url = excluded.url,
+                business_days = excluded.business_days,
+                holidays = excluded.holidays
+            "#,
+            params![
+                provider.name,
+                provider.description,
+                provider.kind,
+                provider.url,
+                provider.created_at.to_rfc3339(),
+                provider.business_days,
+                provider
+                    .holidays
+                    .iter()
+                    .map(|d| d.to_string())
+                    .collect::<Vec<_>>()
+                    .join(","),
+            ],
+        )?;
+        Ok(())
+    }
+
+    pub fn delete_provider(&self, name: &str) -> Result<usize> {
+        Ok(self
+            .conn
+            .execute("DELETE FROM providers WHERE name = ?1", params![name])?)
+    }
+
+    pub fn get_provider(&self, name: &str) -> Result<Option<StoredProvider>> {
+        Ok(self
+            .query_providers("WHERE name = ?1", params![name])?
+            .pop())
+    }
+
+    pub fn list_providers(&self) -> Result<Vec<StoredProvider>> {
+        self.query_providers("", params![])
+    }
+
+    fn query_providers(
+        &self,
+        filter: &str,
+        args: impl rusqlite::Params,
+    ) -> Result<Vec<StoredProvider>> {
+        let mut stmt = self.conn.prepare(&format!(
+            r#"
+            SELECT name, description, kind, url, created_at, business_days, holidays
+            FROM providers
+            {filter}
+            ORDER BY name ASC
+            "#,
+        ))?;
+
+        let mut rows = stmt.query(args)?;
+        let mut out = Vec::new();
+        while let Some(row) = rows.next()? {
+            let created_at: String = row.get(4)?;
+            let holidays: Option<String> = row.get(6)?;
+            out.push(StoredProvider {
+                name: row.get(0)?,
+                description: row.get(1)?,
+                kind: row.get(2)?,
+                url: row.get(3)?,
+                created_at: DateTime::parse_from_rfc3339(&created_at)
+                    .context("Invalid created_at in providers table")?
+                    .with_timezone(&Utc),
+                business_days: row.get::<_, Option<bool>>(5)?.unwrap_or(false),
+                holidays: holidays
+                    .unwrap_or_default()
+                    .split(',')
+                    .filter(|d| !d.is_empty())
+                    .map(|d| d.parse::<NaiveDate>())
+                    .collect::<std::result::Result<_, _>>()
+                    .context("Invalid holidays in providers table")?,
+            });
+        }
+        Ok(out)
+    }
+
+    /// Makes `alias` another name for `provider`'s rate series.
+    pub fn set_provider_alias(&self, alias: &str, provider: &str) -> Result<()> {
+        self.conn.execute(
+            r#"
+            INSERT INTO provider_aliases (alias, provider, created_at)
+            VALUES (?1, ?2, ?3)
+            ON CONFLICT(alias) DO UPDATE SET provider = excluded.provider
+            "#,
+            params![alias, provider, crate::config::now_utc().to_rfc3339()],
+        )?;
+        Ok(())
+    }
+
+    pub fn delete_provider_alias(&self, alias: &str) -> Result<usize> {
+        Ok(self.conn.execute(
+            "DELETE FROM provider_aliases WHERE alias = ?1",
+            params![alias],
+        )?)
+    }
+
+    /// The provider `alias` points to, if it is an alias.
+    pub fn provider_alias(&self, alias: &str) -> Result<Option<String>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT provider FROM provider_aliases WHERE alias = ?1")?;
+        let mut rows = stmt.query(params![alias])?;
+        let Some(row) = rows.next()? else {
+            return Ok(None);
+        };
+        Ok(Some(row.get(0)?))
+    }
+
+    /// `(alias, provider)` pairs, by alias.
+    pub fn list_provider_aliases(&self) -> Result<Vec<(String, String)>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT alias, provider FROM provider_aliases ORDER BY alias ASC")?;
+        let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        Ok(rows.collect::<std::result::Result<_, _>>()?)
+    }
+
+    /// Saves (or replaces) the template `template.name`.
+    pub fn upsert_template(&self, template: &StoredTemplate) -> Result<()> {
+        self.conn.execute(
+            r#"
+            INSERT INTO templates (name, argv_json, created_at)
+            VALUES (?1, ?2, ?3)
+            ON CONFLICT(name) DO UPDATE SET argv_json = excluded.argv_json
+            "#,
+            params![
+                template.name,
+                serde_json::to_string(&template.argv)?,
+                template.created_at.to_rfc3339(),
+            ],
+        )?;
+        Ok(())
+    }
+
+    pub fn delete_template(&self, name: &str) -> Result<usize> {
+        Ok(self
+            .conn
+            .execute("DELETE FROM templates WHERE name = ?1", params![name])?)
+    }
+
+    pub fn get_template(&self, name: &str) -> Result<Option<StoredTemplate>> {
+        Ok(self
+            .query_templates("WHERE name = ?1", params![name])?
+            .pop())
+    }
+
+    pub fn list_templates(&self) -> Result<Vec<StoredTemplate>> {
+        self.query_templates("", params![])
+    }
+
+    fn query_templates(
+        &self,
+        filter: &str,
+        args: impl rusqlite::Params,
+    ) -> Result<Vec<StoredTemplate>> {
+        let mut stmt = self.conn.prepare(&format!(
+            r#"
+            SELECT name, argv_json, created_at
+            FROM templates
+            {filter}
+            ORDER BY name ASC
+            "#,
+        ))?;
+
+        let mut rows = stmt.query(args)?;
+        let mut out = Vec::new();
+        while let Some(row) = rows.next()? {
+            let argv: String = row.get(1)?;
+            let created_at: String = row.get(2)?;
+            out.push(StoredTemplate {
+                name: row.get(0)?,
+                argv: serde_json::from_str(&argv)
+                    .context("Invalid argv_json in templates table")?,
+                created_at: DateTime::parse_from_rfc3339(&created_at)
+                    .context("Invalid created_at in templates table")?
+                    .with_timezone(&Utc),
+            });
+        }
+        Ok(out)
+    }
+
+    /// Saves (or replaces) the settings for `commodity.code`.
+    pub fn upsert_commodity(&self, commodity: &StoredCommodity) -> Result<()> {
+        self.conn.execute(
+            r#"
+            INSERT INTO commodities (code, places, rounding, created_at)
+            VALUES (?1, ?2, ?3, ?4)
+            ON CONFLICT(code) DO UPDATE SET
+                places = excluded.places,
+                rounding = excluded.rounding
+            "#,
+            params![
+                commodity.code,
+                commodity.places,
+                commodity.rounding,
+                commodity.created_at.to_rfc3339(),
+            ],
+        )?;
+        Ok(())
+    }
+
+    pub fn delete_commodity(&self, code: &str) -> Result<usize> {
+        Ok(self
+            .conn
+            .execute("DELETE FROM commodities WHERE code = ?1", params![code])?)
+    }
+
+    pub fn get_commodity(&self, code: &str) -> Result<Option<StoredCommodity>> {
+        Ok(self
+            .query_commodities("WHERE code = ?1", params![code])?
+            .pop())
+    }
+
+    pub fn list_commodities(&self) -> Result<Vec<StoredCommodity>> {
+        self.query_commodities("", params![])
+    }
+
+    fn query_commodities(
+        &self,
+        filter: &str,
+        args: impl rusqlite::Params,
+    ) -> Result<Vec<StoredCommodity>> {
+        let mut stmt = self.conn.prepare(&format!(
+            r#"
+            SELECT code, places, rounding, created_at
+            FROM commodities
+            {filter}
+            ORDER BY code ASC
+            "#,
+        ))?;
+
+        let mut rows = stmt.query(args)?;
+        let mut out = Vec::new();
+        while let Some(row) = rows.next()? {
+            let created_at: String = row.get(3)?;
+            out.push(StoredCommodity {
+                code: row.get(0)?,
+                places: row.get(1)?,
+                rounding: row.get(2)?,
+                created_at: DateTime::parse_from_rfc3339(&created_at)
+                    .context("Invalid created_at in commodities table")?
+                    .with_timezone(&Utc),
+            });
+        }
+        Ok(out)
+    }
+
+    /// Sets (or replaces) the commodity `account` holds by default.
+    pub fn set_account_commodity(
+        &self,
+        account: &str,
+        commodity: &str,
+        created_at: DateTime<Utc>,
+    ) -> Result<()> {
+        self.conn.execute(
+            r#"
+            INSERT INTO account_commodities (account, commodity, created_at)
+            VALUES (?1, ?2, ?3)
+            ON CONFLICT(account) DO UPDATE SET commodity = excluded.commodity
+            "#,
+            params![account, commodity, created_at.to_rfc3339()],
+        )?;
+        Ok(())
+    }
+
+    pub fn delete_account_commodity(&self, account: &str) -> Result<usize> {
+        Ok(self.conn.execute(
+            "DELETE FROM account_commodities WHERE account = ?1",
+            params![account],
+        )?)
+    }
+
+    /// Account -> its default commodity.
+    pub fn account_commodities(&self) -> Result<BTreeMap<String, String>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT account, commodity FROM account_commodities ORDER BY account ASC")?;
+        let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        Ok(rows.collect::<rusqlite::Result<_>>()?)
+    }
+
+    /// Saves (or replaces) the plan for `plan.month`.
+    pub fn upsert_budget_plan(&self, plan: &StoredBudgetPlan) -> Result<()> {
+        self.conn.execute(
+            r#"
+            INSERT INTO budget_plans (month, income, commodity, allocations_json, created_at)
+            VALUES (?1, ?2, ?3, ?4, ?5)
+            ON CONFLICT(month) DO UPDATE SET
+                income = excluded.income,
+                commodity = excluded.commodity,
+                allocations_json = excluded.allocations_json
+            "#,
+            params![
+                plan.month,
+                plan.income.to_string(),
+                plan.commodity,
+                plan.allocations.to_string(),
+                plan.created_at.to_rfc3339(),
+            ],
+        )?;
+        Ok(())
+    }
+
+    pub fn get_budget_plan(&self, month: &str) -> Result<Option<StoredBudgetPlan>> {
+        let mut stmt = self.conn.prepare(
+            r#"
+            SELECT month, income, commodity, allocations_json, created_at
+            FROM budget_plans
+            WHERE month = ?1
+            "#,
+        )?;
+
+        let mut rows = stmt.query(params![month])?;
+        let Some(row) = rows.next()? else {
+            return Ok(None);
+        };
+        let income: String = row.get(1)?;
+        let allocations: String = row.get(3)?;
+        let created_at: String = row.get(4)?;
+        Ok(Some(StoredBudgetPlan {
+            month: row.get(0)?,
+            income: income
+                .parse::<Decimal>()
+                .context("Invalid income in budget_plans table")?,
+            commodity: row.get(2)?,
+            allocations: serde_json::from_str(&allocations)
+                .context("Invalid allocations_json in budget_plans table")?,
+            created_at: DateTime::parse_from_rfc3339(&created_at)
+                .context("Invalid created_at in budget_plans table")?
+                .with_timezone(&Utc),
+        }))
+    }
+
+    pub fn insert_task(&self, task: &StoredTask) -> Result<()> {
+        self.conn.execute(
+            r#"
+            INSERT INTO tasks (id, task_id, kind, schedule, spec_json, enabled, anchor_at, runs, last_run_at, created_at)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)
+            "#,
+            params![
+                task.id.to_string(),
+                task.task_id,
+                task.kind,
+                task.schedule,
+                serde_json::to_string(&task.spec)?,
+                task.enabled,
+                task.anchor_at.to_rfc3339(),
+                task.runs,
+                task.last_run_at.map(|t| t.to_rfc3339()),
+                task.created_at.to_rfc3339(),
+            ],
+        )?;
+        Ok(())
+    }
+
+    pub fn get_task(&self, task_id: &str) -> Result<Option<StoredTask>> {
+        Ok(self
+            .query_tasks("WHERE task_id = ?1", params![task_id])?
+            .into_iter()
+            .next())
+    }
+
+    pub fn list_tasks(&self) -> Result<Vec<StoredTask>> {
+        self.query_tasks("", params![])
+    }
+
+    fn query_tasks(
+        &self,
+        where_clause: &str,
+        args: &[&dyn rusqlite::ToSql],
+    ) -> Result<Vec<StoredTask>> {
+        let sql = format!(
+            r#"
+            SELECT id, task_id, kind, schedule, spec_json, enabled, anchor_at, runs, last_run_at, created_at
+            FROM tasks
+            {where_clause}
+            ORDER BY task_id ASC
+            "#
+        );
+        let mut stmt = self.conn.prepare(&sql)?;
+        let rows = stmt.query_map(args, |row| {
+            let id: String = row.get(0)?;
+            let task_id: String = row.get(1)?;
+            let kind: String = row.get(2)?;
+            let schedule: String = row.get(3)?;
+            let spec_json: String = row.get(4)?;
+            let enabled: bool = row.get(5)?;
+            let anchor_at: String = row.get(6)?;
+            let runs: u32 = row.get(7)?;
+            let last_run_at: Option<String> = row.get(8)?;
+            let created_at: String = row.get(9)?;
+            Ok((
+                id,
+                task_id,
+                kind,
+                schedule,
+                spec_json,
+                enabled,
+                anchor_at,
+                runs,
+                last_run_at,
+                created_at,
+            ))
+        })?;
+
+        let mut out = Vec::new();
+        for row in rows {
+            let (
+                id,
+                task_id,
+                kind,
+                schedule,
+                spec_json,
+                enabled,
+                anchor_at,
+                runs,
+                last_run_at,
+                created_at,
+            ) = row?;
+            let id = Uuid::parse_str(&id).context("Invalid task UUID")?;
+            let spec =
+                serde_json::from_str(&spec_json).context("Invalid spec_json in tasks table")?;
+            let anchor_at = DateTime::parse_from_rfc3339(&anchor_at)
+                .context("Invalid anchor_at in tasks table")?
+                .with_timezone(&Utc);
+            let last_run_at = last_run_at
+                .map(|s| DateTime::parse_from_rfc3339(&s).map(|t| t.with_timezone(&Utc)))
+                .transpose()
+                .context("Invalid last_run_at in tasks table")?;
+            let created_at = DateTime::parse_from_rfc3339(&created_at)
+                .context("Invalid created_at in tasks table")?
+                .with_timezone(&Utc);
+
+            out.push(StoredTask {
+                id,
+                task_id,
+                kind,
+                schedule,
+                spec,
+                enabled,
+                anchor_at,
+                runs,
+                last_run_at,
                 created_at,
             });
         }
+        Ok(out)
+    }
+
+    pub fn set_task_enabled(&self, task_id: &str, enabled: bool) -> Result<usize> {
+        let changed = self.conn.execute(
+            "UPDATE tasks SET enabled = ?2 WHERE task_id = ?1",
+            params![task_id, enabled],
+        )?;
+        Ok(changed)
+    }
+
+    /// Replaces a task's spec and re-enables it.
+    pub fn set_task_spec(&self, task_id: &str, spec: &serde_json::Value) -> Result<usize> {
+        let changed = self.conn.execute(
+            "UPDATE tasks SET spec_json = ?2, enabled = 1 WHERE task_id = ?1",
+            params![task_id, serde_json::to_string(spec)?],
+        )?;
+        Ok(changed)
+    }
+
+    /// Records that `event_id` (usually synced from another device) already covers the
+    /// task's `occurrence` (1-based). Returns false if either side was already matched.
+    pub fn insert_task_fulfillment(
+        &self,
+        task_id: &str,
+        occurrence: u32,
+        event_id: Uuid,
+        matched_at: DateTime<Utc>,
+    ) -> Result<bool> {
+        let changed = self.conn.execute(
+            r#"
+            INSERT OR IGNORE INTO task_fulfillments (task_id, occurrence, event_id, matched_at)
+            VALUES (?1, ?2, ?3, ?4)
+            "#,
+            params![
+                task_id,
+                occurrence,
+                event_id.to_string(),
+                matched_at.to_rfc3339()
+            ],
+        )?;
+        Ok(changed > 0)
+    }
+
+    pub fn get_task_fulfillment(&self, task_id: &str, occurrence: u32) -> Result<Option<Uuid>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT event_id FROM task_fulfillments WHERE task_id = ?1 AND occurrence = ?2",
+        )?;
+        let mut rows = stmt.query(params![task_id, occurrence])?;
+        match rows.next()? {
+            Some(row) => {
+                let raw: String = row.get(0)?;
+                Ok(Some(
+                    Uuid::parse_str(&raw).context("Invalid event id in task_fulfillments")?,
+                ))
+            }
+            None => Ok(None),
+        }
+    }
 
+    /// Event ids already matched to some task occurrence.
+    pub fn list_fulfilling_event_ids(&self) -> Result<Vec<Uuid>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT event_id FROM task_fulfillments")?;
+        let rows = stmt.query_map([], |row| row.get::<_, String>(0))?;
+        let mut out = Vec::new();
+        for r in rows {
+            out.push(Uuid::parse_str(&r?).context("Invalid event id in task_fulfillments")?);
+        }
         Ok(out)
     }
+
+    pub fn set_task_progress(
+        &self,
+        task_id: &str,
+        runs: u32,
+        last_run_at: DateTime<Utc>,
+    ) -> Result<()> {
+        self.conn.execute(
+            "UPDATE tasks SET runs = ?2, last_run_at = ?3 WHERE task_id = ?1",
+            params![task_id, runs, last_run_at.to_rfc3339()],
+        )?;
+        Ok(())
+    }
+}
+
+fn parse_device_id(raw: Option<String>) -> Result<Option<Uuid>> {
+    raw.map(|id| Uuid::parse_str(&id).context("Invalid device_id"))
+        .transpose()
+}
+
+fn parse_updated_at(raw: Option<String>, table: &str) -> Result<Option<DateTime<Utc>>> {
+    raw.map(|t| {
+        DateTime::parse_from_rfc3339(&t)
+            .map(|t| t.with_timezone(&Utc))
+            .with_context(|| format!("Invalid updated_at in {table} table"))
+    })
+    .transpose()
 }
 
 fn add_column_if_missing(conn: &Connection, table: &str, column: &str, ty: &str) -> Result<()> {
@@ -789,11 +2350,3 @@ fn add_column_if_missing(conn: &Connection, table: &str, column: &str, ty: &str)
         }
     }
 }
-
-pub fn ensure_parent_dir(path: &Path) -> Result<()> {
-    if let Some(parent) = path.parent() {
-        fs::create_dir_all(parent)
-            .with_context(|| format!("Failed to create dir {}", parent.display()))?;
-    }
-    Ok(())
-}
@@ -0,0 +1,250 @@
+//! Money lent to and borrowed from people (`bankero lend`, `borrow`, `debts`).
+//!
+//! Each person gets two accounts: `assets:receivable:<person>` for what they owe you
+//! and `liabilities:payable:<person>` for what you owe them. `debts` nets the two per
+//! commodity, so "who owes whom how much" is one line per person, and it reads plain
+//! balances: anything posted to those accounts by hand counts too. A repayment
+//! (`debts settle`) runs whichever way the net balance points.
+
+use crate::cli::{BorrowArgs, CommonEventFlags, DebtSettleArgs, LendArgs};
+use crate::config::{AppConfig, now_utc};
+use crate::db::Db;
+use crate::domain::{EventPayload, Posting, RateContext, StoredEvent};
+use anyhow::{Result, anyhow};
+use rust_decimal::Decimal;
+use std::collections::BTreeMap;
+use uuid::Uuid;
+
+const RECEIVABLE_PREFIX: &str = "assets:receivable:";
+const PAYABLE_PREFIX: &str = "liabilities:payable:";
+
+/// The account segment for a person: lowercase, spaces and colons become dashes.
+fn person_slug(raw: &str) -> Result<String> {
+    let slug = raw
+        .trim()
+        .to_lowercase()
+        .split([' ', ':'])
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("-");
+    if slug.is_empty() {
+        return Err(anyhow!("Person must not be empty"));
+    }
+    Ok(slug)
+}
+
+fn receivable(person: &str) -> String {
+    format!("{RECEIVABLE_PREFIX}{person}")
+}
+
+fn payable(person: &str) -> String {
+    format!("{PAYABLE_PREFIX}{person}")
+}
+
+/// (person, commodity) -> net balance; positive means they owe you.
+fn balances(events: &[StoredEvent]) -> BTreeMap<(String, String), Decimal> {
+    let mut out = BTreeMap::new();
+    for e in events {
+        for p in &e.payload.postings {
+            let Some(person) = p
+                .account
+                .strip_prefix(RECEIVABLE_PREFIX)
+                .or_else(|| p.account.strip_prefix(PAYABLE_PREFIX))
+            else {
+                continue;
+            };
+            *out.entry((person.to_string(), p.commodity.clone()))
+                .or_insert(Decimal::ZERO) += p.amount;
+        }
+    }
+    out
+}
+
+fn debt_event(
+    cfg: &AppConfig,
+    event_id: Uuid,
+    action: &str,
+    person: &str,
+    postings: Vec<Posting>,
+    common: CommonEventFlags,
+) -> Result<EventPayload> {
+    let effective_at = crate::parse_rfc3339_or_now(common.effective_at.as_deref())?;
+    Ok(EventPayload {
+        schema_version: 1,
+        device_id: cfg.device_id,
+        workspace: cfg.current_workspace.clone(),
+        project: cfg.current_project.clone(),
+        action: action.to_string(),
+        created_at: now_utc(),
+        effective_at,
+        postings,
+        tags: common.tags,
+        category: common.category,
+        note: common.note,
+        reference: common.reference,
+        rate_context: RateContext {
+            provider: None,
+            override_rate: None,
+            base: None,
+            quote: None,
+            as_of: effective_at,
+        },
+        basis: None,
+        metadata: crate::with_meta(
+            serde_json::json!({
+                "event_id": event_id.to_string(),
+                "counterparty": person,
+                "confirm": common.confirm,
+                "entered_by": crate::entered_by(cfg, common.entered_by.as_deref()),
+            }),
+            &common.meta,
+        )?,
+    })
+}
+
+fn positive_amount(raw: String) -> Result<Decimal> {
+    let amount = crate::parse_decimal(raw, "amount")?;
+    if amount <= Decimal::ZERO {
+        return Err(anyhow!("amount must be > 0"));
+    }
+    Ok(amount)
+}
+
+pub fn build_lend_event(cfg: &AppConfig, event_id: Uuid, args: LendArgs) -> Result<EventPayload> {
+    let person = person_slug(&args.person)?;
+    let amount = positive_amount(args.amount)?;
+    let commodity = args.commodity.to_ascii_uppercase();
+    let postings = vec![
+        Posting {
+            account: args.from,
+            commodity: commodity.clone(),
+            amount: -amount,
+        },
+        Posting {
+            account: receivable(&person),
+            commodity,
+            amount,
+        },
+    ];
+    debt_event(cfg, event_id, "lend", &person, postings, args.common)
+}
+
+pub fn build_borrow_event(
+    cfg: &AppConfig,
+    event_id: Uuid,
+    args: BorrowArgs,
+) -> Result<EventPayload> {
+    let person = person_slug(&args.person)?;
+    let amount = positive_amount(args.amount)?;
+    let commodity = args.commodity.to_ascii_uppercase();
+    let postings = vec![
+        Posting {
+            account: payable(&person),
+            commodity: commodity.clone(),
+            amount: -amount,
+        },
+        Posting {
+            account: args.to,
+            commodity,
+            amount,
+        },
+    ];
+    debt_event(cfg, event_id, "borrow", &person, postings, args.common)
+}
+
+pub fn build_settle_event(
+    db: &Db,
+    cfg: &AppConfig,
+    event_id: Uuid,
+    args: DebtSettleArgs,
+) -> Result<EventPayload> {
+    let person = person_slug(&args.person)?;
+    let events = crate::snapshot::replay_posted(db, false, false)?;
+    let open: Vec<(String, Decimal)> = balances(&events)
+        .into_iter()
+        .filter(|((p, _), balance)| *p == person && !balance.is_zero())
+        .map(|((_, commodity), balance)| (commodity, balance))
+        .collect();
+    if open.is_empty() {
+        return Err(anyhow!("Nothing is owed between you and {person}"));
+    }
+
+    let (commodity, outstanding) = match args.commodity.as_deref() {
+        Some(raw) => {
+            let code = raw.to_ascii_uppercase();
+            let balance = open
+                .iter()
+                .find(|(c, _)| *c == code)
+                .map(|(_, b)| *b)
+                .ok_or_else(|| anyhow!("Nothing in {code} is owed between you and {person}"))?;
+            (code, balance)
+        }
+        None => match open.as_slice() {
+            [(c, b)] => (c.clone(), *b),
+            _ => {
+                return Err(anyhow!(
+                    "{person} has balances in {}; name the commodity (e.g. 20 {})",
+                    open.iter()
+                        .map(|(c, _)| c.as_str())
+                        .collect::<Vec<_>>()
+                        .join(", "),
+                    open[0].0
+                ));
+            }
+        },
+    };
+    let amount = match args.amount {
+        Some(raw) => positive_amount(raw)?,
+        None => outstanding.abs(),
+    };
+    if amount > outstanding.abs() {
+        return Err(anyhow!(
+            "Cannot settle {amount} {commodity}: only {} {commodity} is outstanding with {person}",
+            outstanding.abs()
+        ));
+    }
+
+    // They owe you: the money comes in. You owe them: it goes out.
+    let (debt_account, signed) = if outstanding > Decimal::ZERO {
+        (receivable(&person), amount)
+    } else {
+        (payable(&person), -amount)
+    };
+    let postings = vec![
+        Posting {
+            account: args.account,
+            commodity: commodity.clone(),
+            amount: signed,
+        },
+        Posting {
+            account: debt_account,
+            commodity,
+            amount: -signed,
+        },
+    ];
+    debt_event(cfg, event_id, "debt_settle", &person, postings, args.common)
+}
+
+pub fn print_debts(db: &Db, all: bool) -> Result<()> {
+    let events = crate::snapshot::replay_posted(db, false, false)?;
+    let rows: Vec<_> = balances(&events)
+        .into_iter()
+        .filter(|(_, balance)| all || !balance.is_zero())
+        .collect();
+    if rows.is_empty() {
+        println!("(no outstanding debts)");
+        return Ok(());
+    }
+    println!("person\tcommodity\tbalance\tstatus");
+    for ((person, commodity), balance) in rows {
+        let status = if balance > Decimal::ZERO {
+            "owes you"
+        } else if balance < Decimal::ZERO {
+            "you owe"
+        } else {
+            "settled"
+        };
+        println!("{person}\t{commodity}\t{}\t{status}", balance.normalize());
+    }
+    Ok(())
+}
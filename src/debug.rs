@@ -0,0 +1,282 @@
+//! Anonymized repro bundles for bug reports (`bankero debug bundle`).
+//!
+//! The bundle is a zip with what a maintainer needs to reproduce a problem without
+//! seeing anyone's finances: the config with personal values stripped, the SQLite
+//! schema, row counts, and a sample of recent events. In the sample every amount is
+//! multiplied by one random factor (so events still balance), and account segments,
+//! workspace/project names, tags and free text are replaced by salted hashes. The
+//! factor and the salt are never written anywhere.
+
+use crate::cli::DebugCmd;
+use crate::config::{AppConfig, now_utc};
+use crate::db::Db;
+use crate::domain::{BasisContext, StoredEvent};
+use anyhow::{Context, Result};
+use rust_decimal::Decimal;
+use serde_json::{Value, json};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::PathBuf;
+use uuid::Uuid;
+
+/// Top-level account kinds; they carry no personal information and keep reports readable.
+const ACCOUNT_KINDS: &[&str] = &[
+    "assets",
+    "liabilities",
+    "income",
+    "expenses",
+    "equity",
+    "external",
+];
+
+struct Obfuscator {
+    salt: String,
+    factor: Decimal,
+}
+
+impl Obfuscator {
+    fn new() -> Self {
+        let random = Uuid::new_v4().as_u128();
+        // 0.50..=2.00, never exactly 1.
+        let mut hundredths = 50 + (random % 151) as i64;
+        if hundredths == 100 {
+            hundredths = 137;
+        }
+        Self {
+            salt: Uuid::new_v4().to_string(),
+            factor: Decimal::new(hundredths, 2),
+        }
+    }
+
+    fn hash(&self, raw: &str) -> String {
+        let digest = Sha256::digest(format!("{}:{raw}", self.salt));
+        let hex: String = digest.iter().map(|b| format!("{b:02x}")).collect();
+        format!("h{}", &hex[..10])
+    }
+
+    fn amount(&self, amount: Decimal) -> String {
+        (amount * self.factor).round_dp(8).normalize().to_string()
+    }
+
+    /// `expenses:food:market` -> `expenses:h1a2b...:h3c4d...`.
+    fn account(&self, account: &str) -> String {
+        account
+            .split(':')
+            .enumerate()
+            .map(|(i, segment)| {
+                if i == 0 && ACCOUNT_KINDS.contains(&segment) {
+                    segment.to_string()
+                } else {
+                    self.hash(segment)
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(":")
+    }
+
+    /// Keeps the shape of metadata: numbers stay, decimal strings are scaled, other
+    /// strings are hashed.
+    fn metadata(&self, value: &Value) -> Value {
+        match value {
+            Value::String(s) => match s.parse::<Decimal>() {
+                Ok(d) => Value::String(self.amount(d)),
+                Err(_) => Value::String(self.hash(s)),
+            },
+            Value::Array(items) => Value::Array(items.iter().map(|v| self.metadata(v)).collect()),
+            Value::Object(map) => Value::Object(
+                map.iter()
+                    .map(|(k, v)| (k.clone(), self.metadata(v)))
+                    .collect(),
+            ),
+            other => other.clone(),
+        }
+    }
+
+    fn event(&self, e: &StoredEvent) -> Value {
+        let p = &e.payload;
+        let rc = &p.rate_context;
+        let basis = p.basis.as_ref().map(|b| match b {
+            BasisContext::Fixed { amount, commodity } => {
+                json!({"kind": "fixed", "amount": self.amount(*amount), "commodity": commodity})
+            }
+            BasisContext::Provider { provider } => {
+                json!({"kind": "provider", "provider": provider})
+            }
+        });
+        json!({
+            "event_id": e.event_id.to_string(),
+            "action": e.action,
+            "schema_version": p.schema_version,
+            "created_at": e.created_at.to_rfc3339(),
+            "effective_at": e.effective_at.to_rfc3339(),
+            "device": self.hash(&p.device_id.to_string()),
+            "workspace": self.hash(&p.workspace),
+            "project": self.hash(&p.project),
+            "postings": p.postings.iter().map(|posting| json!({
+                "account": self.account(&posting.account),
+                "commodity": posting.commodity,
+                "amount": self.amount(posting.amount),
+            })).collect::<Vec<_>>(),
+            "tags": p.tags.iter().map(|t| self.hash(t)).collect::<Vec<_>>(),
+            "category": p.category.as_deref().map(|c| self.account(c)),
+            "has_note": p.note.is_some(),
+            "has_reference": p.reference.is_some(),
+            "rate_context": {
+                "provider": rc.provider,
+                "override_rate": rc.override_rate.map(|r| r.to_string()),
+                "base": rc.base,
+                "quote": rc.quote,
+                "as_of": rc.as_of.to_rfc3339(),
+            },
+            "basis": basis,
+            "metadata": self.metadata(&p.metadata),
+        })
+    }
+}
+
+/// The config as JSON, without device identity, paths, names or rule contents.
+///
+/// Built field by field rather than by stripping a serialized copy, so a setting added
+/// later stays out of bundles until it is listed here; the exhaustive destructuring makes
+/// that a compile error instead of a silent leak.
+fn stripped_config(cfg: &AppConfig, obf: &Obfuscator) -> Value {
+    let AppConfig {
+        device_id: _,
+        device_name: _,
+        current_workspace,
+        current_project,
+        reference_commodity,
+        timezone,
+        data_dir,
+        sync_dir,
+        last_sync_at,
+        time_of_day,
+        import_date,
+        tax_account,
+        tip_account,
+        fee_account,
+        rounding_account,
+        transit_account,
+        confirm_over,
+        sync_max_events,
+        sync_max_mb,
+        locale,
+        strict_commodities,
+        account_aliases,
+        default_account,
+        paste_rules,
+        category_rules,
+        api_tokens,
+    } = cfg;
+    let set = |v: &Option<String>| v.as_ref().map(|_| "<set>");
+    json!({
+        "device_id": "<redacted>",
+        "device_name": "<redacted>",
+        "current_workspace": obf.hash(current_workspace),
+        "current_project": obf.hash(current_project),
+        "reference_commodity": reference_commodity,
+        "timezone": timezone,
+        "data_dir": set(data_dir),
+        "sync_dir": set(sync_dir),
+        "last_sync_at": last_sync_at.map(|t| t.to_rfc3339()),
+        "time_of_day": time_of_day,
+        "import_date": import_date.as_str(),
+        "tax_account": obf.account(tax_account),
+        "tip_account": obf.account(tip_account),
+        "fee_account": obf.account(fee_account),
+        "rounding_account": obf.account(rounding_account),
+        "transit_account": obf.account(transit_account),
+        "confirm_over": confirm_over,
+        "sync_max_events": sync_max_events,
+        "sync_max_mb": sync_max_mb,
+        "locale": locale,
+        "strict_commodities": strict_commodities,
+        "account_aliases": account_aliases
+            .iter()
+            .map(|(alias, account)| (obf.hash(alias), Value::String(obf.account(account))))
+            .collect::<serde_json::Map<_, _>>(),
+        "default_account": default_account.as_deref().map(|a| obf.account(a)),
+        "paste_rules": paste_rules.len(),
+        "category_rules": category_rules.len(),
+        "api_tokens": api_tokens.len(),
+    })
+}
+
+pub fn handle_debug(db: &Db, cfg: &AppConfig, cmd: DebugCmd) -> Result<()> {
+    match cmd {
+        DebugCmd::Bundle { out, sample } => {
+            let obf = Obfuscator::new();
+            let out = out.unwrap_or_else(|| {
+                PathBuf::from(format!(
+                    "bankero-debug-{}.zip",
+                    now_utc().format("%Y%m%d-%H%M%S")
+                ))
+            });
+
+            let events = db.list_events()?;
+            let mut by_action: BTreeMap<&str, usize> = BTreeMap::new();
+            for e in &events {
+                *by_action.entry(e.action.as_str()).or_insert(0) += 1;
+            }
+            let counts = json!({
+                "tables": db.table_counts()?.into_iter().collect::<BTreeMap<_, _>>(),
+                "events_by_action": by_action,
+                "commodities": events
+                    .iter()
+                    .flat_map(|e| e.payload.postings.iter().map(|p| p.commodity.as_str()))
+                    .collect::<std::collections::BTreeSet<_>>(),
+            });
+            let info = json!({
+                "bankero_version": env!("CARGO_PKG_VERSION"),
+                "os": std::env::consts::OS,
+                "arch": std::env::consts::ARCH,
+                "generated_at": now_utc().to_rfc3339(),
+                "events_sampled": sample.min(events.len()),
+                "note": "Amounts are scaled by an undisclosed factor; names are salted hashes.",
+            });
+            let schema: String = db
+                .schema()?
+                .into_iter()
+                .map(|(_, sql)| format!("{};\n", sql.trim()))
+                .collect::<Vec<_>>()
+                .join("\n");
+            // Most recent events: the ones a bug report is usually about.
+            let mut sampled = String::new();
+            for e in events.iter().rev().take(sample).rev() {
+                sampled.push_str(&serde_json::to_string(&obf.event(e))?);
+                sampled.push('\n');
+            }
+
+            let file = std::fs::File::create(&out)
+                .with_context(|| format!("Failed to create {}", out.display()))?;
+            let mut zip = zip::ZipWriter::new(file);
+            let options = zip::write::SimpleFileOptions::default()
+                .compression_method(zip::CompressionMethod::Deflated);
+            let entries = [
+                ("info.json", serde_json::to_string_pretty(&info)?),
+                (
+                    "config.json",
+                    serde_json::to_string_pretty(&stripped_config(cfg, &obf))?,
+                ),
+                ("schema.sql", schema),
+                ("counts.json", serde_json::to_string_pretty(&counts)?),
+                ("events.jsonl", sampled),
+            ];
+            for (name, contents) in entries {
+                zip.start_file(name, options)?;
+                zip.write_all(contents.as_bytes())?;
+            }
+            zip.finish()?;
+
+            println!(
+                "Wrote {} ({} of {} event(s) sampled, amounts scaled, names hashed).",
+                out.display(),
+                sample.min(events.len()),
+                events.len()
+            );
+            println!("Review it before attaching it to an issue.");
+            Ok(())
+        }
+    }
+}
@@ -48,6 +48,9 @@ pub struct EventPayload {
     pub tags: Vec<String>,
     pub category: Option<String>,
     pub note: Option<String>,
+    /// External reference (`--ref`): invoice, receipt or bank transaction number.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reference: Option<String>,
 
     pub rate_context: RateContext,
     pub basis: Option<BasisContext>,
@@ -113,3 +116,70 @@ pub fn parse_basis_arg(raw: &str) -> Option<BasisContext> {
     }
     None
 }
+
+/// A parsed `bankero q` line: `<amount> [COMMODITY] [category] [@account-alias] [#tag ...]`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct QuickEntry {
+    pub amount: Decimal,
+    pub commodity: Option<String>,
+    pub category: Option<String>,
+    pub account_alias: Option<String>,
+    pub tags: Vec<String>,
+}
+
+pub fn parse_quick_entry(raw: &str) -> Result<QuickEntry, String> {
+    let mut tokens = raw.split_whitespace();
+    let amount_raw = tokens
+        .next()
+        .ok_or_else(|| "empty entry; expected e.g. \"45.2 USD groceries @cash\"".to_string())?;
+    let amount = amount_raw
+        .parse::<Decimal>()
+        .map_err(|_| format!("expected an amount first, got '{amount_raw}'"))?;
+
+    let mut entry = QuickEntry {
+        amount,
+        commodity: None,
+        category: None,
+        account_alias: None,
+        tags: Vec::new(),
+    };
+    let mut words: Vec<&str> = Vec::new();
+    for token in tokens {
+        if let Some(alias) = token.strip_prefix('@') {
+            if alias.is_empty() || entry.account_alias.is_some() {
+                return Err(format!("expected a single @account alias, got '{token}'"));
+            }
+            entry.account_alias = Some(alias.to_string());
+        } else if let Some(tag) = token.strip_prefix('#') {
+            if !tag.is_empty() {
+                entry.tags.push(tag.to_string());
+            }
+        } else {
+            words.push(token);
+        }
+    }
+
+    // An all-uppercase word right after the amount is the commodity (e.g. USD, VES, USDT).
+    if let Some(first) = words.first()
+        && first
+            .chars()
+            .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit())
+        && first.chars().any(|c| c.is_ascii_uppercase())
+    {
+        entry.commodity = Some(first.to_string());
+        words.remove(0);
+    }
+
+    match words.as_slice() {
+        [] => {}
+        [category] => entry.category = Some(category.to_string()),
+        _ => {
+            return Err(format!(
+                "expected at most one category word, got '{}'",
+                words.join(" ")
+            ));
+        }
+    }
+
+    Ok(entry)
+}
@@ -0,0 +1,406 @@
+//! Corrections to recorded events (`bankero event`).
+//!
+//! The journal is append-only, so an event is never edited or deleted. `event void`
+//! appends a `void` event with the original's postings inverted and its id in
+//! `metadata.voids`. Balances net to zero on their own; reports mark the original as
+//! voided, and budgets skip both events.
+//!
+//! `event amend` appends a full new version of the event with the fixes applied and the
+//! old id in `metadata.supersedes` (and `metadata.correction = "amend"`, so a stray
+//! `supersedes` key from elsewhere never hides an event). Replay (`snapshot::replay_events`) keeps only the
+//! current version of each chain, so balances and reports see the corrected event and
+//! `history` still shows every version.
+//!
+//! `undo` voids the newest event this device wrote that still counts.
+//!
+//! `event show` is `bankero show` (see `show`), `event search` finds events by free text, and `event attachments` lists an event's
+//! files (see `attachments`).
+
+use crate::cli::{EventAmendArgs, EventArgs, EventCmd, EventSearchArgs, UndoArgs};
+use crate::config::{AppConfig, now_utc};
+use crate::db::Db;
+use crate::domain::{EventPayload, Posting, RateContext, StoredEvent};
+use anyhow::{Result, anyhow};
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use std::collections::{BTreeMap, HashMap};
+use std::io::IsTerminal;
+use uuid::Uuid;
+
+pub const VOID_ACTION: &str = "void";
+
+/// `metadata.correction` on versions written by `event amend`; only those supersede.
+pub const AMEND_CORRECTION: &str = "amend";
+
+/// Voided event id -> the void event that cancelled it.
+pub fn voided(events: &[StoredEvent]) -> HashMap<Uuid, &StoredEvent> {
+    events
+        .iter()
+        .filter(|e| e.action == VOID_ACTION)
+        .filter_map(|e| {
+            let id = e.payload.metadata.get("voids")?.as_str()?.parse().ok()?;
+            Some((id, e))
+        })
+        .collect()
+}
+
+/// `postings` with every amount negated: what a void of them posts.
+pub fn inverted(postings: &[Posting]) -> Vec<Posting> {
+    postings
+        .iter()
+        .map(|p| Posting {
+            account: p.account.clone(),
+            commodity: p.commodity.clone(),
+            amount: -p.amount,
+        })
+        .collect()
+}
+
+/// `postings` multiplied by `ratio`, rounded so every commodity still balances (any
+/// rounding residual lands on that commodity's first leg).
+pub fn scaled_postings(postings: &[Posting], ratio: Decimal) -> Vec<Posting> {
+    let mut out: Vec<Posting> = postings
+        .iter()
+        .map(|p| Posting {
+            account: p.account.clone(),
+            commodity: p.commodity.clone(),
+            amount: (p.amount * ratio).round_dp(8).normalize(),
+        })
+        .collect();
+    let mut residual: BTreeMap<String, Decimal> = BTreeMap::new();
+    for p in &out {
+        *residual.entry(p.commodity.clone()).or_insert(Decimal::ZERO) += p.amount;
+    }
+    for (commodity, rest) in residual {
+        if let Some(first) = out.iter_mut().find(|p| p.commodity == commodity) {
+            first.amount -= rest;
+        }
+    }
+    out
+}
+
+pub fn handle_event(db: &Db, cfg: &AppConfig, args: EventArgs) -> Result<()> {
+    match args.cmd {
+        EventCmd::Void {
+            event_id,
+            note,
+            effective_at,
+        } => void(db, cfg, &event_id, note, effective_at.as_deref()),
+        EventCmd::Amend(args) => amend(db, cfg, args),
+        EventCmd::Show(args) => crate::show::handle_show(db, cfg, args),
+        EventCmd::Search(args) => search(db, args),
+        EventCmd::Attachments { event_id, out } => {
+            crate::attachments::handle_attachments(db, &event_id, out.as_deref())
+        }
+    }
+}
+
+/// Rejects events a void or amendment cannot sensibly replace.
+fn check_correctable(events: &[StoredEvent], e: &StoredEvent) -> Result<()> {
+    if e.action == VOID_ACTION {
+        return Err(anyhow!(
+            "Event {} is itself a void; record the event again instead",
+            e.event_id
+        ));
+    }
+    if e.action == crate::snapshot::SNAPSHOT_ACTION {
+        return Err(anyhow!(
+            "Event {} is an archive snapshot and cannot be corrected",
+            e.event_id
+        ));
+    }
+    let voided = voided(events);
+    if let Some(v) = voided.get(&e.event_id) {
+        return Err(anyhow!(
+            "Event {} was already voided by event {}",
+            e.event_id,
+            v.event_id
+        ));
+    }
+    let current = crate::history::current_version(events, e);
+    if current.event_id != e.event_id {
+        return Err(anyhow!(
+            "Event {} was amended; its current version is {} (see: bankero history {})",
+            e.event_id,
+            current.event_id,
+            &e.event_id.to_string()[..8]
+        ));
+    }
+    if let Some(r) = events.iter().find(|r| {
+        crate::refund::refunds(r) == Some(e.event_id) && !voided.contains_key(&r.event_id)
+    }) {
+        return Err(anyhow!(
+            "Event {} was refunded by event {}; void the refund first",
+            e.event_id,
+            r.event_id
+        ));
+    }
+    if e.payload.postings.is_empty() {
+        return Err(anyhow!(
+            "Event {} ({}) has no postings to correct",
+            e.event_id,
+            e.action
+        ));
+    }
+    Ok(())
+}
+
+fn void(
+    db: &Db,
+    cfg: &AppConfig,
+    raw: &str,
+    note: Option<String>,
+    effective_at: Option<&str>,
+) -> Result<()> {
+    let events = db.list_events()?;
+    let e = crate::show::find_event(&events, raw)?;
+    check_correctable(&events, e)?;
+    // By default the void lands where the original did, so that period nets to zero.
+    let effective_at = match effective_at {
+        Some(raw) => crate::parse_rfc3339_or_now(Some(raw))?,
+        None => e.effective_at,
+    };
+    let event_id = write_void(db, cfg, e, note, effective_at)?;
+
+    println!("Voided {} ({}).", e.event_id, e.action);
+    println!("Wrote event {event_id}");
+    Ok(())
+}
+
+/// Appends the void of `e` and returns its id.
+fn write_void(
+    db: &Db,
+    cfg: &AppConfig,
+    e: &StoredEvent,
+    note: Option<String>,
+    effective_at: DateTime<Utc>,
+) -> Result<Uuid> {
+    let event_id = Uuid::new_v4();
+    let payload = EventPayload {
+        schema_version: 1,
+        device_id: cfg.device_id,
+        workspace: cfg.current_workspace.clone(),
+        project: e.payload.project.clone(),
+        action: VOID_ACTION.to_string(),
+        created_at: now_utc(),
+        effective_at,
+        postings: inverted(&e.payload.postings),
+        tags: vec![],
+        category: e.payload.category.clone(),
+        note,
+        reference: e.payload.reference.clone(),
+        rate_context: RateContext {
+            provider: None,
+            override_rate: None,
+            base: None,
+            quote: None,
+            as_of: effective_at,
+        },
+        basis: None,
+        metadata: serde_json::json!({
+            "event_id": event_id.to_string(),
+            "voids": e.event_id.to_string(),
+            "entered_by": crate::entered_by(cfg, None),
+        }),
+    };
+    db.insert_event(event_id, &payload)?;
+    Ok(event_id)
+}
+
+/// `bankero undo`: voids the newest event this device wrote that still counts.
+pub fn undo(db: &Db, cfg: &AppConfig, args: UndoArgs) -> Result<()> {
+    let events = db.list_events()?;
+    let voided = voided(&events);
+    let Some(e) = events
+        .iter()
+        .filter(|e| {
+            e.payload.device_id == cfg.device_id
+                && e.action != VOID_ACTION
+                && e.action != crate::snapshot::SNAPSHOT_ACTION
+                && !e.payload.postings.is_empty()
+                && !voided.contains_key(&e.event_id)
+        })
+        .max_by_key(|e| (e.created_at, e.event_id))
+    else {
+        println!("Nothing to undo: this device has not written an event that still counts.");
+        return Ok(());
+    };
+    check_correctable(&events, e)?;
+
+    println!(
+        "Last event: {}\t{}\t{}\t{}",
+        e.event_id,
+        e.effective_at.to_rfc3339(),
+        e.action,
+        crate::history::summary(e)
+    );
+    let write = if args.yes {
+        true
+    } else if !std::io::stdin().is_terminal() {
+        println!("Re-run with --yes to void it.");
+        false
+    } else {
+        crate::prompt_yes_no("Void this event? [Y/n] ")?
+    };
+    if !write {
+        return Ok(());
+    }
+    let event_id = write_void(db, cfg, e, args.note, e.effective_at)?;
+    println!("Voided {} ({}).", e.event_id, e.action);
+    println!("Wrote event {event_id}");
+    Ok(())
+}
+
+fn amend(db: &Db, cfg: &AppConfig, args: EventAmendArgs) -> Result<()> {
+    if args.amount.is_none()
+        && args.commodity.is_none()
+        && args.category.is_none()
+        && args.note.is_none()
+        && args.reference.is_none()
+        && args.effective_at.is_none()
+    {
+        return Err(anyhow!(
+            "Nothing to amend. Pass --amount, --commodity, --category, --note, --ref or --effective-at"
+        ));
+    }
+    let events = db.list_events()?;
+    let e = crate::show::find_event(&events, &args.event_id)?;
+    check_correctable(&events, e)?;
+
+    let mut payload = e.payload.clone();
+    let mut changes = Vec::new();
+    let primary = crate::primary_outgoing_amount(&payload.postings);
+    if let Some(raw) = args.amount {
+        let amount = crate::parse_decimal(raw, "amount")?;
+        if amount <= Decimal::ZERO {
+            return Err(anyhow!("--amount must be > 0"));
+        }
+        let Some((old, commodity)) = &primary else {
+            return Err(anyhow!("Event {} has no outgoing amount", e.event_id));
+        };
+        payload.postings = scaled_postings(&payload.postings, amount / old);
+        changes.push(format!("amount {old} -> {amount} {commodity}"));
+    }
+    if let Some(raw) = args.commodity {
+        let code = raw.trim().to_ascii_uppercase();
+        let Some((_, old)) = &primary else {
+            return Err(anyhow!("Event {} has no outgoing amount", e.event_id));
+        };
+        for p in payload.postings.iter_mut().filter(|p| p.commodity == *old) {
+            p.commodity = code.clone();
+        }
+        changes.push(format!("commodity {old} -> {code}"));
+    }
+    if let Some(category) = args.category {
+        changes.push(format!(
+            "category {} -> {category}",
+            payload.category.as_deref().unwrap_or("-")
+        ));
+        payload.category = Some(category);
+    }
+    if let Some(note) = args.note {
+        payload.note = Some(note);
+        changes.push("note".to_string());
+    }
+    if let Some(reference) = args.reference {
+        payload.reference = Some(reference);
+        changes.push("ref".to_string());
+    }
+    if let Some(raw) = args.effective_at {
+        payload.effective_at = crate::parse_rfc3339_or_now(Some(&raw))?;
+        changes.push(format!(
+            "effective_at {} -> {}",
+            e.effective_at.to_rfc3339(),
+            payload.effective_at.to_rfc3339()
+        ));
+    }
+
+    let event_id = Uuid::new_v4();
+    payload.device_id = cfg.device_id;
+    payload.created_at = now_utc();
+    payload.metadata["event_id"] = serde_json::json!(event_id.to_string());
+    payload.metadata["supersedes"] = serde_json::json!(e.event_id.to_string());
+    payload.metadata["correction"] = serde_json::json!(AMEND_CORRECTION);
+    payload.metadata["entered_by"] = serde_json::json!(crate::entered_by(cfg, None));
+    crate::commodities::check_known(db, cfg, &payload)?;
+    crate::commodities::check_account_defaults(db, &payload)?;
+    db.insert_event(event_id, &payload)?;
+
+    println!(
+        "Amended {} ({}): {}.",
+        e.event_id,
+        e.action,
+        changes.join(", ")
+    );
+    println!("Wrote event {event_id}");
+    Ok(())
+}
+
+/// Whether any free-text field of `e` contains `needle` (already lowercased).
+fn matches_text(e: &StoredEvent, needle: &str) -> bool {
+    let p = &e.payload;
+    let payee = p.metadata.get("payee").and_then(|v| v.as_str());
+    [
+        p.note.as_deref(),
+        payee,
+        p.category.as_deref(),
+        p.reference.as_deref(),
+    ]
+    .into_iter()
+    .flatten()
+    .chain(p.tags.iter().map(String::as_str))
+    .any(|text| text.to_lowercase().contains(needle))
+}
+
+fn search(db: &Db, args: EventSearchArgs) -> Result<()> {
+    let needle = args.query.trim().to_lowercase();
+    if needle.is_empty() {
+        return Err(anyhow!("Search text must not be empty"));
+    }
+    let window = match (&args.month, &args.range) {
+        (Some(m), _) => Some(crate::period::month_range(m)?),
+        (None, Some(r)) => Some(crate::parse_date_range(r)?),
+        (None, None) => None,
+    };
+    let events = crate::snapshot::replay_events(db, false)?;
+    let voided = voided(&events);
+    let mut found = 0;
+    for e in &events {
+        if let Some((start, end)) = window
+            && (e.effective_at < start || e.effective_at > end)
+        {
+            continue;
+        }
+        if let Some(prefix) = &args.account
+            && !e
+                .payload
+                .postings
+                .iter()
+                .any(|p| p.account.starts_with(prefix))
+        {
+            continue;
+        }
+        if !matches_text(e, &needle) {
+            continue;
+        }
+        found += 1;
+        let mut line = format!(
+            "{}\t{}\t{}\t{}",
+            e.event_id,
+            crate::output::iso_date(e.effective_at),
+            e.action,
+            crate::history::summary(e)
+        );
+        if let Some(payee) = e.payload.metadata.get("payee").and_then(|v| v.as_str()) {
+            line.push_str(&format!("\tpayee:{payee}"));
+        }
+        if let Some(v) = voided.get(&e.event_id) {
+            line.push_str(&format!("\tvoided by {}", v.event_id));
+        }
+        println!("{line}");
+    }
+    if found == 0 {
+        println!("(no events match \"{}\")", args.query.trim());
+    }
+    Ok(())
+}
@@ -0,0 +1,231 @@
+//! Currency swaps between your own accounts (`bankero exchange`).
+//!
+//! An exchange is a cross-currency move with a mandatory provider: the rate is always
+//! resolved (or taken from `@provider:rate`) and recorded, and the received amount is
+//! computed from it. With `--vs @reference`, the spread between the two providers'
+//! rates is kept in `metadata.spread` so street-vs-official losses can be reported.
+
+use crate::cli::ExchangeArgs;
+use crate::config::{AppConfig, now_utc};
+use crate::db::Db;
+use crate::domain::{EventPayload, Posting, ProviderToken, parse_provider_token};
+use anyhow::{Result, anyhow};
+use chrono::{DateTime, Utc};
+use clap::CommandFactory;
+use rust_decimal::Decimal;
+use uuid::Uuid;
+
+pub const EXCHANGE_ACTION: &str = "exchange";
+
+/// Drops the quoted `'->'` in `exchange 100 USD '->' VES`, which clap would read as a flag.
+///
+/// Unquoted, a shell splits `->` into `-` and a `>` redirect to a file named after the
+/// target commodity; that is caught here too, since the command's output would vanish.
+pub fn strip_arrow(mut args: Vec<std::ffi::OsString>) -> Result<Vec<std::ffi::OsString>> {
+    let Some(i) = subcommand_index(&args).filter(|&i| args[i] == "exchange") else {
+        return Ok(args);
+    };
+    match args.get(i + 3).and_then(|a| a.to_str()) {
+        Some("->") => {
+            args.remove(i + 3);
+        }
+        Some("-") => {
+            return Err(anyhow!(
+                "The shell read `->` as `-` plus a `>` redirect (and may have created a file named after the commodity). Quote the arrow ('->') or leave it out: bankero exchange 100 USD VES @bcv --from <account> --to <account>"
+            ));
+        }
+        _ => {}
+    }
+    Ok(args)
+}
+
+/// Where the subcommand sits in `args`: the first word that is neither a global option
+/// nor its value. Only that word decides whether this is an exchange, so
+/// `report --action exchange` or `event search exchange` are left alone.
+fn subcommand_index(args: &[std::ffi::OsString]) -> Option<usize> {
+    let cli = crate::cli::Cli::command();
+    let takes_value = |arg: Option<&clap::Arg>| arg.is_some_and(|a| a.get_action().takes_values());
+    let mut i = 1;
+    while i < args.len() {
+        let word = args[i].to_str()?;
+        if word == "--" {
+            return None;
+        }
+        if let Some(long) = word.strip_prefix("--") {
+            if !long.contains('=')
+                && takes_value(cli.get_arguments().find(|a| a.get_long() == Some(long)))
+            {
+                i += 1;
+            }
+        } else if let Some(short) = word.strip_prefix('-').filter(|s| !s.is_empty()) {
+            let mut chars = short.chars();
+            if let (Some(c), None) = (chars.next(), chars.next())
+                && takes_value(cli.get_arguments().find(|a| a.get_short() == Some(c)))
+            {
+                i += 1;
+            }
+        } else {
+            return Some(i);
+        }
+        i += 1;
+    }
+    None
+}
+
+/// Target commodity per unit of `from` under `token` at `as_of`.
+fn rate_for(
+    db: &Db,
+    token: &ProviderToken,
+    from: &str,
+    to: &str,
+    as_of: DateTime<Utc>,
+) -> Result<Decimal> {
+    if let Some(rate) = token.override_rate {
+        if rate <= Decimal::ZERO {
+            return Err(anyhow!("@{}:{rate}: rate must be positive", token.provider));
+        }
+        return Ok(rate);
+    }
+    let provider = crate::normalize_provider(&token.provider);
+    let (value, ..) =
+        crate::try_resolve_and_convert(db, &provider, from, to, as_of, Decimal::ONE, None)?
+            .ok_or_else(|| {
+                anyhow!(
+                    "No @{provider} rate between {from} and {to} at or before {}. Set one with: bankero rate set @{provider} {from} {to} <rate>, or pass it as @{provider}:<rate>",
+                    as_of.to_rfc3339()
+                )
+            })?;
+    Ok(value)
+}
+
+/// Builds the exchange event and a one-line summary of what it does.
+pub fn build_exchange_event(
+    db: &Db,
+    cfg: &AppConfig,
+    event_id: Uuid,
+    args: ExchangeArgs,
+) -> Result<(EventPayload, String)> {
+    let provider_raw = args.provider.as_str();
+    let token = parse_provider_token(provider_raw).ok_or_else(|| {
+        anyhow!("Expected a provider token like @bcv or @bcv:36.5, got '{provider_raw}'")
+    })?;
+    let from_commodity = args.commodity.to_ascii_uppercase();
+    let to_commodity = args.to_commodity.to_ascii_uppercase();
+    if from_commodity == to_commodity {
+        return Err(anyhow!(
+            "Nothing to exchange: both sides are {from_commodity}. Use `bankero move` for same-currency transfers"
+        ));
+    }
+    let amount = crate::parse_decimal(args.amount, "amount")?;
+    if amount <= Decimal::ZERO {
+        return Err(anyhow!("Amount must be positive"));
+    }
+
+    let effective_at = crate::parse_rfc3339_or_now(args.common.effective_at.as_deref())?;
+    let as_of = crate::parse_as_of(&args.common, effective_at)?;
+    let rate = rate_for(db, &token, &from_commodity, &to_commodity, as_of)?;
+    let raw = amount * rate;
+    let received = crate::commodities::round(db, &to_commodity, raw)?;
+
+    let mut postings = vec![
+        Posting {
+            account: args.from,
+            commodity: from_commodity.clone(),
+            amount: -amount,
+        },
+        Posting {
+            account: args.to,
+            commodity: to_commodity.clone(),
+            amount: received,
+        },
+    ];
+    if received != raw {
+        postings.push(Posting {
+            account: cfg.rounding_account.clone(),
+            commodity: to_commodity.clone(),
+            amount: (raw - received).normalize(),
+        });
+    }
+
+    let provider_label = format!("@{}", crate::normalize_provider(&token.provider));
+    let mut summary = format!(
+        "Exchanged {amount} {from_commodity} -> {} {to_commodity} at {} {to_commodity} per {from_commodity} ({provider_label}).",
+        received.normalize(),
+        rate.round_dp(8).normalize()
+    );
+    let mut spread = serde_json::Value::Null;
+    if let Some(vs) = &args.vs {
+        let reference = parse_provider_token(vs)
+            .or_else(|| parse_provider_token(&format!("@{vs}")))
+            .ok_or_else(|| anyhow!("--vs expects a provider token like @bcv, got '{vs}'"))?;
+        let reference_rate = rate_for(db, &reference, &from_commodity, &to_commodity, as_of)?;
+        let percent = ((rate - reference_rate) / reference_rate * Decimal::ONE_HUNDRED).round_dp(2);
+        let difference =
+            crate::commodities::round(db, &to_commodity, received - amount * reference_rate)?;
+        let reference_label = format!("@{}", crate::normalize_provider(&reference.provider));
+        summary.push_str(&format!(
+            "\nSpread vs {reference_label} ({} {to_commodity} per {from_commodity}): {}{percent}% ({}{} {to_commodity})",
+            reference_rate.round_dp(8).normalize(),
+            if percent > Decimal::ZERO { "+" } else { "" },
+            if difference > Decimal::ZERO { "+" } else { "" },
+            difference.normalize(),
+        ));
+        spread = serde_json::json!({
+            "reference": reference_label,
+            "reference_rate": reference_rate.to_string(),
+            "rate": rate.to_string(),
+            "percent": percent.to_string(),
+            "difference": difference.to_string(),
+            "commodity": to_commodity,
+        });
+    }
+
+    let mut metadata = crate::with_meta(
+        serde_json::json!({
+            "event_id": event_id.to_string(),
+            "confirm": args.common.confirm,
+            "entered_by": crate::entered_by(cfg, args.common.entered_by.as_deref()),
+        }),
+        &args.common.meta,
+    )?;
+    if received != raw {
+        metadata["unrounded_to_amount"] = serde_json::Value::String(raw.to_string());
+    }
+    if !spread.is_null() {
+        metadata["spread"] = spread;
+    }
+
+    let basis = args
+        .common
+        .basis
+        .as_deref()
+        .and_then(crate::domain::parse_basis_arg)
+        .or_else(|| crate::parse_fixed_basis(&args.common.basis));
+    let mut payload = EventPayload {
+        schema_version: 1,
+        device_id: cfg.device_id,
+        workspace: cfg.current_workspace.clone(),
+        project: cfg.current_project.clone(),
+        action: EXCHANGE_ACTION.to_string(),
+        created_at: now_utc(),
+        effective_at,
+        postings,
+        tags: args.common.tags,
+        category: args.common.category,
+        note: args.common.note,
+        reference: args.common.reference,
+        rate_context: crate::build_rate_context(
+            Some(ProviderToken {
+                provider: token.provider,
+                override_rate: Some(rate),
+            }),
+            as_of,
+            Some(from_commodity),
+            Some(to_commodity),
+        ),
+        basis,
+        metadata,
+    };
+    crate::apply_fee(cfg, &mut payload, &args.fee)?;
+    Ok((payload, summary))
+}
@@ -0,0 +1,162 @@
+//! Realized gain preview for `sell --confirm`.
+//!
+//! The cost of what is sold comes from the basis recorded when it was acquired: a fixed
+//! `--basis` on the events that put the commodity into the account, or the latest
+//! `tag <account> --set-basis`. Holdings are costed at their average; selling part of a
+//! position releases the same share of its basis. Without a complete basis nothing is
+//! previewed, since a guessed gain would be worse than none.
+
+use crate::db::Db;
+use crate::domain::{BasisContext, EventPayload, StoredEvent};
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+
+/// What `account` held of `commodity` at `at`, and the basis of those holdings.
+struct Position {
+    held: Decimal,
+    cost: Decimal,
+    cost_commodity: String,
+}
+
+fn position(
+    events: &[StoredEvent],
+    account: &str,
+    commodity: &str,
+    at: DateTime<Utc>,
+) -> Option<Position> {
+    let mut held = Decimal::ZERO;
+    // `None` once some of the holdings were acquired without a basis.
+    let mut cost = Some(Decimal::ZERO);
+    let mut cost_commodity: Option<String> = None;
+
+    for e in events.iter().filter(|e| e.effective_at <= at) {
+        let fixed_basis = match &e.payload.basis {
+            Some(BasisContext::Fixed { amount, commodity }) => {
+                Some((*amount, commodity.to_ascii_uppercase()))
+            }
+            _ => None,
+        };
+        let targets_account =
+            e.payload.metadata.get("target").and_then(|t| t.as_str()) == Some(account);
+        if e.action == "tag" && targets_account {
+            if let Some((amount, c)) = fixed_basis {
+                cost = Some(amount);
+                cost_commodity = Some(c);
+            }
+            continue;
+        }
+
+        for p in &e.payload.postings {
+            if p.account != account || !p.commodity.eq_ignore_ascii_case(commodity) {
+                continue;
+            }
+            if p.amount > Decimal::ZERO {
+                held += p.amount;
+                cost = match (&fixed_basis, cost, &cost_commodity) {
+                    (Some((amount, c)), Some(total), None) => {
+                        cost_commodity = Some(c.clone());
+                        Some(total + amount)
+                    }
+                    (Some((amount, c)), Some(total), Some(known)) if c == known => {
+                        Some(total + amount)
+                    }
+                    _ => None,
+                };
+            } else if held > Decimal::ZERO {
+                let remaining = (held + p.amount).max(Decimal::ZERO);
+                cost = cost.map(|total| total * remaining / held);
+                held = remaining;
+            }
+        }
+    }
+
+    Some(Position {
+        held,
+        cost: cost?,
+        cost_commodity: cost_commodity?,
+    })
+    .filter(|p| p.held > Decimal::ZERO)
+}
+
+/// Previews the realized gain of a sell and records it in `metadata.realized_gain`.
+///
+/// Proceeds in another commodity than the basis are converted with the sell's provider.
+pub fn preview_sell(db: &Db, payload: &mut EventPayload) -> Result<()> {
+    let (Some(sold), Some(received)) = (payload.postings.first(), payload.postings.get(1)) else {
+        return Ok(());
+    };
+    let (account, commodity, quantity) =
+        (sold.account.clone(), sold.commodity.clone(), -sold.amount);
+    let (proceeds, proceeds_commodity) = (received.amount, received.commodity.to_ascii_uppercase());
+
+    let events = crate::snapshot::replay_events(db, false)?;
+    let Some(pos) = position(&events, &account, &commodity, payload.effective_at) else {
+        eprintln!("No basis recorded for {commodity} in {account}; gain not previewed.");
+        return Ok(());
+    };
+
+    let proceeds_in_basis = if proceeds_commodity == pos.cost_commodity {
+        proceeds
+    } else {
+        let provider = payload
+            .rate_context
+            .provider
+            .as_deref()
+            .map(crate::normalize_provider)
+            .filter(|p| p != "derived");
+        let converted = provider.and_then(|p| {
+            crate::resolve_and_convert(
+                db,
+                &p,
+                &proceeds_commodity,
+                &pos.cost_commodity,
+                payload.rate_context.as_of,
+                proceeds,
+            )
+            .ok()
+        });
+        let Some((value, ..)) = converted else {
+            eprintln!(
+                "Basis is in {} but proceeds are in {}; add a provider with a stored rate to preview the gain.",
+                pos.cost_commodity, proceeds_commodity
+            );
+            return Ok(());
+        };
+        value
+    };
+
+    let sold_share = quantity.min(pos.held) / pos.held;
+    let basis =
+        crate::commodities::round(db, &pos.cost_commodity, pos.cost * sold_share)?.normalize();
+    let proceeds_in_basis =
+        crate::commodities::round(db, &pos.cost_commodity, proceeds_in_basis)?.normalize();
+    let gain = (proceeds_in_basis - basis).normalize();
+
+    if quantity > pos.held {
+        eprintln!(
+            "Note: selling {quantity} {commodity} but {account} held {}; the excess has no basis.",
+            pos.held
+        );
+    }
+    eprintln!(
+        "Realized {}: {} {} (proceeds {} - basis {}).",
+        if gain.is_sign_negative() {
+            "loss"
+        } else {
+            "gain"
+        },
+        gain,
+        pos.cost_commodity,
+        proceeds_in_basis,
+        basis
+    );
+    payload.metadata["realized_gain"] = serde_json::json!({
+        "amount": gain.to_string(),
+        "commodity": pos.cost_commodity,
+        "proceeds": proceeds_in_basis.to_string(),
+        "basis": basis.to_string(),
+        "method": "average",
+    });
+    Ok(())
+}
@@ -0,0 +1,285 @@
+//! Offline documentation (`bankero help`, hidden `bankero generate-man`).
+//!
+//! `help <topic>` prints long-form guides that do not fit a single command's `--help`;
+//! `help <command>` keeps working as clap's built-in help did. Man pages are rendered
+//! from the clap definitions at release time, so they never drift from `--help`.
+
+use crate::cli::{Cli, GenerateManArgs, HelpArgs};
+use anyhow::{Context, Result, anyhow};
+use clap::CommandFactory;
+use std::fs;
+use std::path::Path;
+
+struct Topic {
+    name: &'static str,
+    summary: &'static str,
+    body: &'static str,
+}
+
+const TOPICS: &[Topic] = &[
+    Topic {
+        name: "accounts",
+        summary: "Account names, the five roots, and how postings balance",
+        body: r#"ACCOUNTS
+
+Accounts are colon-separated paths created on first use; there is nothing to
+declare up front. The first segment says what kind of account it is:
+
+    assets:       what you own (assets:bank, assets:cash, assets:wallet:usdt)
+    liabilities:  what you owe (liabilities:credit-card, liabilities:loan)
+    income:       where money comes from (income:salary, income:freelance)
+    expenses:     where money goes (expenses:food:groceries, expenses:rent)
+    equity:       bookkeeping offsets (equity:conversion, equity:opening-balances)
+    external:     payees outside your books (external:landlord)
+
+Every event is a set of postings that add up to zero per commodity. A deposit
+of 100 USD from income:salary to assets:bank posts -100 to income:salary and
++100 to assets:bank. That is why income balances are negative and liabilities
+are negative while you owe money.
+
+`lend` and `borrow` keep loans between people in assets:receivable:<person> and
+liabilities:payable:<person>; `bankero debts` nets the two per person.
+
+Balances and reports accept a prefix and include every sub-account:
+
+    bankero balance assets
+    bankero balance expenses:food
+    bankero register assets:bank
+
+Aliases for `bankero q` ("cash" -> "assets:cash") and the default account live in
+config.json; `bankero init` sets the default account.
+"#,
+    },
+    Topic {
+        name: "commodities",
+        summary: "Currencies, crypto and other units, and the reference commodity",
+        body: r#"COMMODITIES
+
+A commodity is any unit an amount is counted in: fiat (USD, VES, EUR), crypto
+(USDT, BTC) or anything else (GOLD-G). Codes are upper-cased and never converted
+implicitly: 100 USD and 100 USDT are different balances.
+
+Events that involve two commodities (a `move` from USD to VES, a `sell`) keep
+each commodity balanced through equity:conversion, so both sides stay exact.
+
+The reference commodity (config key reference_commodity, default USD) is what
+conversions and summaries default to. Change it with:
+
+    bankero config set reference_commodity EUR
+
+Convert totals at report time with a provider:
+
+    bankero balance assets --convert @bcv
+
+Amounts computed from a rate are rounded to the target commodity's rule (default:
+up to 8 places, bankers rounding):
+
+    bankero commodity set VES --places 2 --rounding half-up
+
+See also: bankero help providers
+"#,
+    },
+    Topic {
+        name: "providers",
+        summary: "Exchange-rate providers (@bcv, @binance), overrides and the rate store",
+        body: r#"PROVIDERS
+
+A provider is a named source of exchange rates, written with a leading @:
+@bcv, @binance, @parallel, or any name you choose. Rates are stored locally
+per provider, pair (base/quote) and time, so everything works offline.
+
+Record rates:
+
+    bankero rate set @bcv USD VES 45.2 --as-of 2026-02-25T12:00:00Z
+    bankero rate list @bcv USD VES
+
+Use them on events. The rate in effect at the event's effective time is used
+and the provider is stored on the event, so reports can show where a value
+came from:
+
+    bankero move 100 USD --from assets:bank --to assets:banesco VES @bcv
+
+Override the stored rate for one event with @provider:rate:
+
+    bankero move 100 USD --from assets:bank --to assets:banesco 42000 VES @manual:420
+
+--basis / -b records an intrinsic value next to the settled amount (a fixed
+amount or another provider), which is how the gap between an official and a
+market rate is tracked.
+
+To see what a past event would have been worth at another provider's rate:
+
+    bankero show <event-id> --revalue @binance
+
+If a stored rate was wrong, `bankero reprice` writes compensating events.
+"#,
+    },
+    Topic {
+        name: "sync",
+        summary: "Multi-device sync through a shared folder or the LAN",
+        body: r#"SYNC
+
+Bankero is local-first: each device keeps its own SQLite journal and never
+needs a server. Devices exchange append-only events, so nothing is overwritten
+and every device converges to the same history.
+
+Folder sync works with any shared folder (Syncthing, Dropbox, a USB stick):
+
+    bankero login --sync-dir ~/Sync/bankero --name laptop
+    bankero sync now
+    bankero sync status
+
+Each device exports its events into the folder and imports the other devices'
+events; events already seen are skipped.
+
+Budgets, piggy banks and piggy funds sync too, matched by id. If two devices
+each created a budget or piggy with the same name, the older one keeps it and
+the other is renamed with its id prefix, e.g. "Food (1a2b3c4d)", the same way
+on every device. `sync now` prints each rename.
+
+LAN sync talks to a peer directly:
+
+    bankero sync expose        # on one device
+    bankero sync discover      # on another; prints @N handles
+    bankero sync @1 all
+
+Recurring tasks (depreciation, interest, debt payments) run on every device that
+has them. Imported events the same task wrote elsewhere are matched to their
+occurrence, and `task run` skips it. A payment entered by hand is not matched;
+let the task write it instead.
+
+A lost or stolen device can be revoked from any other one:
+
+    bankero sync revoke-device <device-id>
+
+The revocation keeps the events that device had already synced. Anything else
+claiming to come from it is rejected on import, and `sync expose` refuses it.
+
+Sync settings belong to the active profile and workspace, so personal and work
+ledgers never mix (see `bankero profile list`).
+"#,
+    },
+    Topic {
+        name: "budgets",
+        summary: "Monthly budgets, reports, piggy banks and spending limits",
+        body: r#"BUDGETS
+
+A budget caps what a category or account may receive per month, in one
+commodity. Budgets without --month repeat every month.
+
+    bankero budget create "Food" 400 USD --category expenses:food
+    bankero budget create "Cash" 500 USD --month 2026-03 --account assets:cash
+    bankero budget report --month 2026-03
+
+The report compares the budget with what was actually posted to the category
+during the month.
+
+Zero-based planning gives every unit of projected income a job (budgets,
+piggies, other categories) and tracks what is left unallocated:
+
+    bankero budget plan 2026-03
+    bankero budget plan 2026-03 --status
+
+Related tools:
+
+    piggy banks   savings goals funded from surpluses (bankero piggy --help)
+    limits        per-account monthly caps; hard limits block events unless
+                  --override is given (bankero limit --help)
+    notify        a digest of exceeded budgets and other actionable items
+"#,
+    },
+    Topic {
+        name: "sql",
+        summary: "Read-only SQL views for Datasette, Metabase and sqlite3",
+        body: r#"SQL VIEWS
+
+Each workspace is one SQLite file (the path is printed after every write, e.g.
+data/workspaces/personal/bankero.sqlite3). Events are stored as JSON payloads;
+these views flatten them into a stable, read-only surface for other tools:
+
+    v_postings                 one row per posting of each event's current
+                               version (replaced versions are left out):
+                               event_id, action, effective_at, month (YYYY-MM),
+                               account, commodity, amount (exact decimal text),
+                               amount_real (float, for charts), category,
+                               project, note
+    v_monthly_category_totals  month, category, commodity, total, events; the
+                               category is the event's category or, without one,
+                               the expenses:/income:/external: account posted to
+    v_rates_latest             provider, base, quote, rate, as_of: the newest
+                               stored rate per provider and pair
+
+The views are created once and only replaced by a bankero release that changes
+them. Their columns are stable: new ones may be added at the end, existing ones
+are not renamed or removed. Tables are an implementation detail and may change between versions.
+
+Query them, never write to the file from other tools; open it read-only:
+
+    sqlite3 -readonly bankero.sqlite3 "SELECT * FROM v_monthly_category_totals"
+    datasette bankero.sqlite3 --immutable
+
+Archived events (bankero snapshot) are not included. `total` and `amount_real`
+are floats; use `amount` when every cent matters.
+"#,
+    },
+];
+
+pub fn handle_help(args: HelpArgs) -> Result<()> {
+    let mut cli = Cli::command();
+    let Some(first) = args.topic.first() else {
+        cli.print_long_help()?;
+        return Ok(());
+    };
+
+    if first == "topics" && args.topic.len() == 1 {
+        println!("Help topics (bankero help <topic>):");
+        for topic in TOPICS {
+            println!("  {:<12} {}", topic.name, topic.summary);
+        }
+        return Ok(());
+    }
+    if args.topic.len() == 1
+        && let Some(topic) = TOPICS.iter().find(|t| t.name == first)
+    {
+        print!("{}", topic.body);
+        return Ok(());
+    }
+
+    // Anything else is a command path, as with clap's own `help` subcommand.
+    let mut cmd = &mut cli;
+    for name in &args.topic {
+        cmd = cmd.find_subcommand_mut(name).ok_or_else(|| {
+            anyhow!("No help topic or command named '{name}'. See `bankero help topics`.")
+        })?;
+    }
+    cmd.print_long_help()?;
+    Ok(())
+}
+
+pub fn handle_generate_man(args: GenerateManArgs) -> Result<()> {
+    fs::create_dir_all(&args.out_dir)
+        .with_context(|| format!("Failed to create {}", args.out_dir.display()))?;
+    let mut cli = Cli::command();
+    cli.build();
+    let written = write_pages(&cli, &args.out_dir)?;
+    println!("Wrote {written} man page(s) to {}.", args.out_dir.display());
+    Ok(())
+}
+
+/// Writes `bankero.1` plus one `bankero-<sub>[-<sub>].1` page per visible subcommand.
+fn write_pages(cmd: &clap::Command, out_dir: &Path) -> Result<usize> {
+    let name = cmd.get_display_name().unwrap_or(cmd.get_name());
+    let path = out_dir.join(format!("{name}.1"));
+    let mut buf = Vec::new();
+    clap_mangen::Man::new(cmd.clone()).render(&mut buf)?;
+    fs::write(&path, buf).with_context(|| format!("Failed to write {}", path.display()))?;
+
+    let mut written = 1;
+    for sub in cmd.get_subcommands() {
+        if sub.is_hide_set() || sub.get_name() == "help" {
+            continue;
+        }
+        written += write_pages(sub, out_dir)?;
+    }
+    Ok(written)
+}
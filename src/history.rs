@@ -0,0 +1,455 @@
+//! Supersession chains (`bankero history`).
+//!
+//! Events are never edited. A correction is a new event naming the one it replaces in
+//! `metadata.supersedes`; other follow-ups point back with `voids`, `adjusts`, `settles`,
+//! `refunds` or `approves`. `history` walks those links from any event in a chain.
+//!
+//! Two devices can correct the same event offline, so one version may end up with two
+//! successors after sync. Until someone picks one, every device follows the most recently
+//! created successor (so they all agree) and `history` flags the fork. `--resolve` records
+//! the choice as a `resolve` event (`metadata.resolves` + `metadata.canonical`), which syncs
+//! like any other event.
+
+use crate::cli::HistoryArgs;
+use crate::config::{AppConfig, now_utc};
+use crate::db::Db;
+use crate::domain::{EventPayload, RateContext, StoredEvent};
+use anyhow::{Result, anyhow};
+use std::collections::{HashMap, HashSet};
+use std::io::Write;
+use uuid::Uuid;
+
+pub const RESOLVE_ACTION: &str = "resolve";
+
+/// Back-links from follow-ups that point at an event without replacing it.
+const FOLLOW_UPS: &[&str] = &["voids", "adjusts", "settles", "refunds"];
+
+fn link(e: &StoredEvent, key: &str) -> Option<Uuid> {
+    e.payload.metadata.get(key)?.as_str()?.parse().ok()
+}
+
+/// The version `e` replaces; only versions written by `event amend` replace anything.
+fn supersedes(e: &StoredEvent) -> Option<Uuid> {
+    if e.payload.metadata["correction"].as_str() != Some(crate::event::AMEND_CORRECTION) {
+        return None;
+    }
+    link(e, "supersedes")
+}
+
+fn approves(e: &StoredEvent) -> Vec<Uuid> {
+    e.payload.metadata["approves"]
+        .as_array()
+        .map(|ids| {
+            ids.iter()
+                .filter_map(|id| id.as_str()?.parse().ok())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+struct Chains<'a> {
+    by_id: HashMap<Uuid, &'a StoredEvent>,
+    /// Event id -> events that supersede it, oldest first.
+    successors: HashMap<Uuid, Vec<&'a StoredEvent>>,
+    /// Forked event id -> the successor picked by the latest `resolve` event.
+    resolved: HashMap<Uuid, Uuid>,
+}
+
+impl<'a> Chains<'a> {
+    fn new(events: &'a [StoredEvent]) -> Self {
+        let mut successors: HashMap<Uuid, Vec<&StoredEvent>> = HashMap::new();
+        let mut resolutions: HashMap<Uuid, &StoredEvent> = HashMap::new();
+        for e in events {
+            if let Some(parent) = supersedes(e) {
+                successors.entry(parent).or_default().push(e);
+            }
+            if e.action == RESOLVE_ACTION
+                && let Some(fork) = link(e, "resolves")
+            {
+                let latest = resolutions.entry(fork).or_insert(e);
+                if (e.payload.created_at, e.event_id) > (latest.payload.created_at, latest.event_id)
+                {
+                    *latest = e;
+                }
+            }
+        }
+        for list in successors.values_mut() {
+            list.sort_by_key(|e| (e.payload.created_at, e.event_id));
+        }
+        Self {
+            by_id: events.iter().map(|e| (e.event_id, e)).collect(),
+            successors,
+            resolved: resolutions
+                .into_iter()
+                .filter_map(|(fork, e)| Some((fork, link(e, "canonical")?)))
+                .collect(),
+        }
+    }
+
+    /// The first version of the chain `e` belongs to.
+    fn root(&self, mut e: &'a StoredEvent) -> &'a StoredEvent {
+        let mut steps = 0;
+        while let Some(parent) = supersedes(e).and_then(|id| self.by_id.get(&id)) {
+            e = parent;
+            steps += 1;
+            if steps > self.by_id.len() {
+                break;
+            }
+        }
+        e
+    }
+
+    /// Whether `id` has several successors and nobody picked one yet.
+    fn unresolved(&self, id: Uuid) -> bool {
+        self.successors.get(&id).is_some_and(|s| s.len() > 1) && !self.resolved.contains_key(&id)
+    }
+
+    /// The successor every device follows from `id`, if it was superseded.
+    fn next(&self, id: Uuid) -> Option<&'a StoredEvent> {
+        let successors = self.successors.get(&id)?;
+        if let Some(picked) = self.resolved.get(&id)
+            && let Some(e) = successors.iter().find(|e| e.event_id == *picked)
+        {
+            return Some(e);
+        }
+        successors.last().copied()
+    }
+
+    /// The version that counts, starting from the root.
+    fn canonical(&self, root: &'a StoredEvent) -> &'a StoredEvent {
+        let mut e = root;
+        let mut steps = 0;
+        while let Some(next) = self.next(e.event_id) {
+            e = next;
+            steps += 1;
+            if steps > self.by_id.len() {
+                break;
+            }
+        }
+        e
+    }
+
+    /// Every version in the chain, depth first, with its depth.
+    fn versions(&self, root: &'a StoredEvent) -> Vec<(usize, &'a StoredEvent)> {
+        let mut out = Vec::new();
+        let mut stack = vec![(0, root)];
+        while let Some((depth, e)) = stack.pop() {
+            if out
+                .iter()
+                .any(|(_, seen): &(usize, &StoredEvent)| seen.event_id == e.event_id)
+            {
+                continue;
+            }
+            out.push((depth, e));
+            if let Some(successors) = self.successors.get(&e.event_id) {
+                for s in successors.iter().rev() {
+                    stack.push((depth + 1, s));
+                }
+            }
+        }
+        out
+    }
+}
+
+/// The journal as replay sees it: every version a later correction replaced is dropped,
+/// keeping the one each chain follows (the newest, or the one a `resolve` picked).
+///
+/// A void of a replaced version (one device amended the event while another voided it,
+/// before they synced) is applied to the chain's current version instead, so the event
+/// is cancelled rather than netted against a version that no longer counts. If the
+/// current version was voided as well, the stale void is dropped.
+pub fn current_versions(events: Vec<StoredEvent>) -> Vec<StoredEvent> {
+    let (keep, redirects) = {
+        let chains = Chains::new(&events);
+        let keep: HashSet<Uuid> = events
+            .iter()
+            .filter(|e| chains.canonical(chains.root(e)).event_id == e.event_id)
+            .map(|e| e.event_id)
+            .collect();
+        let mut voided: HashSet<Uuid> = crate::event::voided(&events)
+            .into_keys()
+            .filter(|id| keep.contains(id))
+            .collect();
+        // Void id -> the version it cancels instead, or `None` to drop it.
+        let mut redirects: HashMap<Uuid, Option<StoredEvent>> = HashMap::new();
+        let mut stale: Vec<(Uuid, &StoredEvent)> = crate::event::voided(&events)
+            .into_iter()
+            .filter(|(target, _)| !keep.contains(target))
+            .collect();
+        stale.sort_by_key(|(_, v)| (v.payload.created_at, v.event_id));
+        for (target, void) in stale {
+            let Some(original) = chains.by_id.get(&target) else {
+                continue;
+            };
+            let current = chains.canonical(chains.root(original));
+            if current.event_id == target {
+                continue;
+            }
+            let redirect = voided.insert(current.event_id).then(|| current.clone());
+            redirects.insert(void.event_id, redirect);
+        }
+        (keep, redirects)
+    };
+    events
+        .into_iter()
+        .filter(|e| keep.contains(&e.event_id))
+        .filter_map(|mut e| match redirects.get(&e.event_id) {
+            None => Some(e),
+            Some(None) => None,
+            Some(Some(current)) => {
+                e.payload.postings = crate::event::inverted(&current.payload.postings);
+                e.payload.metadata["voids"] = current.event_id.to_string().into();
+                Some(e)
+            }
+        })
+        .collect()
+}
+
+/// Event id -> the group it replays with: every version of a chain, the voids of any of
+/// them and the `resolve` events picking between them. [`current_versions`] of a whole
+/// group never depends on events outside it, so compaction moves groups, not events.
+pub fn replay_groups(events: &[StoredEvent]) -> HashMap<Uuid, Uuid> {
+    fn find(parent: &mut HashMap<Uuid, Uuid>, id: Uuid) -> Uuid {
+        let mut root = id;
+        while let Some(&up) = parent.get(&root)
+            && up != root
+        {
+            root = up;
+        }
+        parent.insert(id, root);
+        root
+    }
+    let mut parent: HashMap<Uuid, Uuid> = HashMap::new();
+    for e in events {
+        let mut links = vec![supersedes(e)];
+        if e.action == crate::event::VOID_ACTION {
+            links.push(link(e, "voids"));
+        }
+        if e.action == RESOLVE_ACTION {
+            links.extend([link(e, "resolves"), link(e, "canonical")]);
+        }
+        let group = find(&mut parent, e.event_id);
+        for other in links.into_iter().flatten() {
+            let other = find(&mut parent, other);
+            parent.insert(other, group);
+        }
+    }
+    let ids: Vec<Uuid> = parent.keys().copied().collect();
+    ids.into_iter()
+        .map(|id| (id, find(&mut parent, id)))
+        .collect()
+}
+
+/// The version `e`'s chain currently follows (`e` itself when nothing superseded it).
+pub fn current_version<'a>(events: &'a [StoredEvent], e: &'a StoredEvent) -> &'a StoredEvent {
+    let chains = Chains::new(events);
+    chains.canonical(chains.root(e))
+}
+
+/// "50 USD groceries \"weekly shop\"": the paid amount, category and note.
+pub fn summary(e: &StoredEvent) -> String {
+    let mut parts = Vec::new();
+    if let Some((amount, commodity)) = crate::primary_outgoing_amount(&e.payload.postings) {
+        parts.push(format!("{amount} {commodity}"));
+    }
+    if let Some(category) = &e.payload.category {
+        parts.push(category.clone());
+    }
+    if let Some(note) = &e.payload.note {
+        parts.push(format!("\"{note}\""));
+    }
+    if parts.is_empty() {
+        "-".to_string()
+    } else {
+        parts.join(" ")
+    }
+}
+
+fn short(id: Uuid) -> String {
+    id.to_string()[..8].to_string()
+}
+
+pub fn handle_history(db: &Db, cfg: &AppConfig, args: HistoryArgs) -> Result<()> {
+    let events = crate::snapshot::load_events(db, true)?;
+    let chains = Chains::new(&events);
+    let start = crate::show::find_event(&events, &args.event_id)?;
+    let root = chains.root(start);
+    let versions = chains.versions(root);
+
+    if args.resolve || args.pick.is_some() {
+        return resolve(db, cfg, &chains, &versions, args.pick.as_deref());
+    }
+
+    let canonical = chains.canonical(root);
+    println!("History of {}:", root.event_id);
+    for (depth, e) in &versions {
+        let relation = match supersedes(e) {
+            Some(parent) => format!("supersedes {}", short(parent)),
+            None => "original".to_string(),
+        };
+        let marker = if e.event_id == canonical.event_id {
+            "*"
+        } else {
+            " "
+        };
+        println!(
+            "{marker} {}{}\t{}\t{}\t{}\t{}",
+            "  ".repeat(*depth),
+            short(e.event_id),
+            e.created_at.to_rfc3339(),
+            e.action,
+            relation,
+            summary(e)
+        );
+    }
+
+    // Follow-ups that point at any version without replacing it.
+    let in_chain: Vec<Uuid> = versions.iter().map(|(_, e)| e.event_id).collect();
+    for e in &events {
+        let targets: Vec<(&str, Uuid)> = FOLLOW_UPS
+            .iter()
+            .filter_map(|key| Some((*key, link(e, key)?)))
+            .chain(approves(e).into_iter().map(|id| ("approves", id)))
+            .filter(|(_, id)| in_chain.contains(id))
+            .collect();
+        for (key, target) in targets {
+            println!(
+                "  {}\t{}\t{}\t{key} {}\t{}",
+                short(e.event_id),
+                e.created_at.to_rfc3339(),
+                e.action,
+                short(target),
+                summary(e)
+            );
+        }
+    }
+
+    println!("* = current version");
+    let forks: Vec<Uuid> = versions
+        .iter()
+        .map(|(_, e)| e.event_id)
+        .filter(|id| chains.unresolved(*id))
+        .collect();
+    for fork in &forks {
+        println!(
+            "Conflict: {} was corrected on more than one device; the newest correction counts until you pick one.",
+            short(*fork)
+        );
+    }
+    if !forks.is_empty() {
+        println!(
+            "Resolve with: bankero history {} --resolve",
+            short(root.event_id)
+        );
+    }
+    Ok(())
+}
+
+fn resolve(
+    db: &Db,
+    cfg: &AppConfig,
+    chains: &Chains,
+    versions: &[(usize, &StoredEvent)],
+    pick: Option<&str>,
+) -> Result<()> {
+    let forks: Vec<Uuid> = versions
+        .iter()
+        .map(|(_, e)| e.event_id)
+        .filter(|id| chains.unresolved(*id))
+        .collect();
+    if forks.is_empty() {
+        println!("Nothing to resolve: this chain has no competing corrections.");
+        return Ok(());
+    }
+    if pick.is_some() && forks.len() > 1 {
+        return Err(anyhow!(
+            "This chain has {} forks; resolve them one at a time without --pick",
+            forks.len()
+        ));
+    }
+
+    for fork in forks {
+        let options = &chains.successors[&fork];
+        let chosen = match pick {
+            Some(raw) => {
+                let needle = raw.trim().to_ascii_lowercase();
+                let matches: Vec<&&StoredEvent> = options
+                    .iter()
+                    .filter(|e| e.event_id.to_string().starts_with(&needle))
+                    .collect();
+                match matches.as_slice() {
+                    [e] => **e,
+                    [] => {
+                        return Err(anyhow!(
+                            "{raw} is not one of the competing corrections of {}",
+                            short(fork)
+                        ));
+                    }
+                    _ => return Err(anyhow!("Event id prefix {raw} is ambiguous")),
+                }
+            }
+            None => {
+                println!("{} has competing corrections:", short(fork));
+                for (i, e) in options.iter().enumerate() {
+                    println!(
+                        "  {}) {}\t{}\tby {}\t{}",
+                        i + 1,
+                        short(e.event_id),
+                        e.created_at.to_rfc3339(),
+                        crate::approve::event_entered_by(e).unwrap_or("-"),
+                        summary(e)
+                    );
+                }
+                eprint!("Pick the canonical version [1-{}]: ", options.len());
+                std::io::stderr().flush().ok();
+                let mut line = String::new();
+                std::io::stdin().read_line(&mut line)?;
+                let n: usize = line
+                    .trim()
+                    .parse()
+                    .ok()
+                    .filter(|n| (1..=options.len()).contains(n))
+                    .ok_or_else(|| anyhow!("Expected a number from 1 to {}", options.len()))?;
+                options[n - 1]
+            }
+        };
+
+        let event_id = Uuid::new_v4();
+        let now = now_utc();
+        let payload = EventPayload {
+            schema_version: 1,
+            device_id: cfg.device_id,
+            workspace: cfg.current_workspace.clone(),
+            project: cfg.current_project.clone(),
+            action: RESOLVE_ACTION.to_string(),
+            created_at: now,
+            effective_at: now,
+            postings: vec![],
+            tags: vec![],
+            category: None,
+            note: None,
+            reference: None,
+            rate_context: RateContext {
+                provider: None,
+                override_rate: None,
+                base: None,
+                quote: None,
+                as_of: now,
+            },
+            basis: None,
+            metadata: serde_json::json!({
+                "event_id": event_id.to_string(),
+                "resolves": fork.to_string(),
+                "canonical": chosen.event_id.to_string(),
+                "entered_by": crate::entered_by(cfg, None),
+            }),
+        };
+        db.insert_event(event_id, &payload)?;
+        println!(
+            "Resolved {}: {} is the canonical version. Wrote event {event_id}",
+            short(fork),
+            short(chosen.event_id)
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,384 @@
+//! GnuCash books: compressed or plain XML, and the SQLite backend.
+
+use super::{ImportBatch, ImportedPrice, ImportedTxn, account_segment, balance_with_conversions};
+use crate::domain::Posting;
+use anyhow::{Context, Result, anyhow};
+use chrono::{DateTime, NaiveDateTime, Utc};
+use rust_decimal::Decimal;
+use std::collections::HashMap;
+use std::io::Read;
+use std::path::Path;
+
+const PROVIDER: &str = "gnucash";
+
+struct Account {
+    name: String,
+    kind: String,
+    parent: Option<String>,
+    commodity: Option<String>,
+}
+
+struct Split {
+    account: String,
+    value: Decimal,
+    quantity: Decimal,
+}
+
+struct Txn {
+    id: String,
+    currency: String,
+    posted: DateTime<Utc>,
+    description: Option<String>,
+    splits: Vec<Split>,
+}
+
+pub fn parse_file(path: &Path) -> Result<ImportBatch> {
+    let raw = std::fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+
+    if raw.starts_with(b"SQLite format 3\0") {
+        return parse_sqlite(path);
+    }
+    let xml = if raw.starts_with(&[0x1f, 0x8b]) {
+        let mut out = String::new();
+        flate2::read::GzDecoder::new(raw.as_slice())
+            .read_to_string(&mut out)
+            .context("Failed to decompress GnuCash file")?;
+        out
+    } else {
+        String::from_utf8(raw).context("GnuCash XML is not valid UTF-8")?
+    };
+    parse_xml(&xml)
+}
+
+fn build_batch(
+    accounts: &HashMap<String, Account>,
+    txns: Vec<Txn>,
+    prices: Vec<ImportedPrice>,
+) -> Result<ImportBatch> {
+    let mut paths: HashMap<&str, String> = HashMap::new();
+    for guid in accounts.keys() {
+        paths.insert(guid, account_path(accounts, guid));
+    }
+
+    let mut out = Vec::new();
+    for txn in txns {
+        let mut postings = Vec::new();
+        let mut values = Vec::new();
+        for split in &txn.splits {
+            let account = accounts.get(&split.account).ok_or_else(|| {
+                anyhow!(
+                    "Transaction {} references unknown account {}",
+                    txn.id,
+                    split.account
+                )
+            })?;
+            let commodity = account
+                .commodity
+                .clone()
+                .unwrap_or_else(|| txn.currency.clone());
+            postings.push(Posting {
+                account: paths[split.account.as_str()].clone(),
+                commodity,
+                amount: split.quantity,
+            });
+            values.push(split.value);
+        }
+        balance_with_conversions(&mut postings, &values, &txn.currency);
+        out.push(ImportedTxn {
+            source_id: txn.id,
+            effective_at: txn.posted,
+            description: txn.description.filter(|d| !d.is_empty()),
+            postings,
+            tags: Vec::new(),
+        });
+    }
+
+    Ok(ImportBatch { txns: out, prices })
+}
+
+/// Maps a GnuCash account type to the bankero top-level account.
+fn top_level(kind: &str) -> &'static str {
+    match kind {
+        "LIABILITY" | "CREDIT" | "PAYABLE" => "liabilities",
+        "INCOME" => "income",
+        "EXPENSE" => "expenses",
+        "EQUITY" => "equity",
+        "TRADING" => "equity:trading",
+        _ => "assets",
+    }
+}
+
+/// "Expenses > Food > Groceries" (type EXPENSE) becomes expenses:food:groceries.
+fn account_path(accounts: &HashMap<String, Account>, guid: &str) -> String {
+    let mut names = Vec::new();
+    let mut kind = "ASSET";
+    let mut cursor = Some(guid);
+    while let Some(id) = cursor {
+        let Some(acct) = accounts.get(id) else {
+            break;
+        };
+        if acct.kind == "ROOT" {
+            break;
+        }
+        names.push(account_segment(&acct.name));
+        kind = &acct.kind;
+        cursor = acct.parent.as_deref();
+    }
+    names.reverse();
+
+    let top = top_level(kind);
+    // Books usually name their top-level accounts "Assets", "Expenses", ...; don't repeat them.
+    if names.first().map(String::as_str) == top.split(':').next_back() {
+        names.remove(0);
+    }
+    let mut path = top.to_string();
+    for n in names {
+        path.push(':');
+        path.push_str(&n);
+    }
+    path
+}
+
+/// GnuCash stores amounts as rationals like "12345/100".
+fn parse_rational(raw: &str) -> Result<Decimal> {
+    let (num, denom) = raw.trim().split_once('/').unwrap_or((raw.trim(), "1"));
+    let num: Decimal = num
+        .parse()
+        .with_context(|| format!("Invalid GnuCash amount '{raw}'"))?;
+    let denom: Decimal = denom
+        .parse()
+        .with_context(|| format!("Invalid GnuCash amount '{raw}'"))?;
+    if denom.is_zero() {
+        return Err(anyhow!("Invalid GnuCash amount '{raw}': zero denominator"));
+    }
+    Ok((num / denom).normalize())
+}
+
+fn parse_xml_date(raw: &str) -> Result<DateTime<Utc>> {
+    DateTime::parse_from_str(raw.trim(), "%Y-%m-%d %H:%M:%S %z")
+        .map(|d| d.with_timezone(&Utc))
+        .with_context(|| format!("Invalid GnuCash date '{raw}'"))
+}
+
+fn parse_sql_date(raw: &str) -> Result<DateTime<Utc>> {
+    NaiveDateTime::parse_from_str(raw.trim(), "%Y-%m-%d %H:%M:%S")
+        .or_else(|_| NaiveDateTime::parse_from_str(raw.trim(), "%Y%m%d%H%M%S"))
+        .map(|d| d.and_utc())
+        .with_context(|| format!("Invalid GnuCash date '{raw}'"))
+}
+
+fn parse_xml(xml: &str) -> Result<ImportBatch> {
+    let doc = roxmltree::Document::parse(xml).context("Failed to parse GnuCash XML")?;
+
+    fn child<'a, 'i>(node: roxmltree::Node<'a, 'i>, name: &str) -> Option<roxmltree::Node<'a, 'i>> {
+        node.children().find(|c| c.tag_name().name() == name)
+    }
+    fn text(node: roxmltree::Node, name: &str) -> Option<String> {
+        child(node, name)
+            .and_then(|c| c.text())
+            .map(|t| t.trim().to_string())
+    }
+    fn cmdty(node: roxmltree::Node, name: &str) -> Option<String> {
+        child(node, name).and_then(|c| text(c, "id"))
+    }
+    fn date(node: roxmltree::Node, name: &str) -> Option<String> {
+        child(node, name).and_then(|c| text(c, "date"))
+    }
+
+    let mut accounts = HashMap::new();
+    let mut txns = Vec::new();
+    let mut prices = Vec::new();
+    for node in doc.descendants().filter(|n| n.is_element()) {
+        match (
+            node.tag_name().name(),
+            node.parent_element().map(|p| p.tag_name().name()),
+        ) {
+            ("account", Some("book")) => {
+                let id = text(node, "id").ok_or_else(|| anyhow!("GnuCash account without id"))?;
+                accounts.insert(
+                    id,
+                    Account {
+                        name: text(node, "name").unwrap_or_default(),
+                        kind: text(node, "type").unwrap_or_default(),
+                        parent: text(node, "parent"),
+                        commodity: cmdty(node, "commodity"),
+                    },
+                );
+            }
+            ("transaction", Some("book")) => {
+                let id =
+                    text(node, "id").ok_or_else(|| anyhow!("GnuCash transaction without id"))?;
+                let posted = date(node, "date-posted")
+                    .ok_or_else(|| anyhow!("GnuCash transaction {id} without date-posted"))?;
+                let mut splits = Vec::new();
+                if let Some(list) = child(node, "splits") {
+                    for split in list.children().filter(|c| c.tag_name().name() == "split") {
+                        splits.push(Split {
+                            account: text(split, "account").unwrap_or_default(),
+                            value: parse_rational(&text(split, "value").unwrap_or_default())?,
+                            quantity: parse_rational(&text(split, "quantity").unwrap_or_default())?,
+                        });
+                    }
+                }
+                txns.push(Txn {
+                    currency: cmdty(node, "currency")
+                        .ok_or_else(|| anyhow!("GnuCash transaction {id} without currency"))?,
+                    posted: parse_xml_date(&posted)?,
+                    description: text(node, "description"),
+                    splits,
+                    id,
+                });
+            }
+            ("price", Some("pricedb")) => {
+                let (Some(base), Some(quote), Some(at), Some(value)) = (
+                    cmdty(node, "commodity"),
+                    cmdty(node, "currency"),
+                    date(node, "time"),
+                    text(node, "value"),
+                ) else {
+                    continue;
+                };
+                prices.push(ImportedPrice {
+                    provider: PROVIDER.to_string(),
+                    base,
+                    quote,
+                    as_of: parse_xml_date(&at)?,
+                    rate: parse_rational(&value)?,
+                });
+            }
+            _ => {}
+        }
+    }
+
+    build_batch(&accounts, txns, prices)
+}
+
+fn parse_sqlite(path: &Path) -> Result<ImportBatch> {
+    let conn =
+        rusqlite::Connection::open_with_flags(path, rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY)
+            .with_context(|| format!("Failed to open GnuCash database {}", path.display()))?;
+
+    let mut mnemonics: HashMap<String, String> = HashMap::new();
+    {
+        let mut stmt = conn.prepare("SELECT guid, mnemonic FROM commodities")?;
+        let rows = stmt.query_map([], |r| Ok((r.get::<_, String>(0)?, r.get::<_, String>(1)?)))?;
+        for row in rows {
+            let (guid, mnemonic) = row?;
+            mnemonics.insert(guid, mnemonic);
+        }
+    }
+
+    let mut accounts = HashMap::new();
+    {
+        let mut stmt = conn.prepare(
+            "SELECT guid, name, account_type, parent_guid, commodity_guid FROM accounts",
+        )?;
+        let rows = stmt.query_map([], |r| {
+            Ok((
+                r.get::<_, String>(0)?,
+                r.get::<_, String>(1)?,
+                r.get::<_, String>(2)?,
+                r.get::<_, Option<String>>(3)?,
+                r.get::<_, Option<String>>(4)?,
+            ))
+        })?;
+        for row in rows {
+            let (guid, name, kind, parent, commodity) = row?;
+            accounts.insert(
+                guid,
+                Account {
+                    name,
+                    kind,
+                    parent,
+                    commodity: commodity.and_then(|c| mnemonics.get(&c).cloned()),
+                },
+            );
+        }
+    }
+
+    let mut splits_by_tx: HashMap<String, Vec<Split>> = HashMap::new();
+    {
+        let mut stmt = conn.prepare(
+            "SELECT tx_guid, account_guid, value_num, value_denom, quantity_num, quantity_denom FROM splits",
+        )?;
+        let rows = stmt.query_map([], |r| {
+            Ok((
+                r.get::<_, String>(0)?,
+                r.get::<_, String>(1)?,
+                r.get::<_, i64>(2)?,
+                r.get::<_, i64>(3)?,
+                r.get::<_, i64>(4)?,
+                r.get::<_, i64>(5)?,
+            ))
+        })?;
+        for row in rows {
+            let (tx, account, vn, vd, qn, qd) = row?;
+            splits_by_tx.entry(tx).or_default().push(Split {
+                account,
+                value: parse_rational(&format!("{vn}/{vd}"))?,
+                quantity: parse_rational(&format!("{qn}/{qd}"))?,
+            });
+        }
+    }
+
+    let mut txns = Vec::new();
+    {
+        let mut stmt = conn.prepare(
+            "SELECT guid, currency_guid, post_date, description FROM transactions ORDER BY post_date",
+        )?;
+        let rows = stmt.query_map([], |r| {
+            Ok((
+                r.get::<_, String>(0)?,
+                r.get::<_, String>(1)?,
+                r.get::<_, Option<String>>(2)?,
+                r.get::<_, Option<String>>(3)?,
+            ))
+        })?;
+        for row in rows {
+            let (id, currency, posted, description) = row?;
+            let posted =
+                posted.ok_or_else(|| anyhow!("GnuCash transaction {id} without post_date"))?;
+            txns.push(Txn {
+                currency: mnemonics
+                    .get(&currency)
+                    .cloned()
+                    .ok_or_else(|| anyhow!("GnuCash transaction {id} has unknown currency"))?,
+                posted: parse_sql_date(&posted)?,
+                description,
+                splits: splits_by_tx.remove(&id).unwrap_or_default(),
+                id,
+            });
+        }
+    }
+
+    let mut prices = Vec::new();
+    {
+        let mut stmt = conn.prepare(
+            "SELECT commodity_guid, currency_guid, date, value_num, value_denom FROM prices",
+        )?;
+        let rows = stmt.query_map([], |r| {
+            Ok((
+                r.get::<_, String>(0)?,
+                r.get::<_, String>(1)?,
+                r.get::<_, String>(2)?,
+                r.get::<_, i64>(3)?,
+                r.get::<_, i64>(4)?,
+            ))
+        })?;
+        for row in rows {
+            let (base, quote, at, num, denom) = row?;
+            let (Some(base), Some(quote)) = (mnemonics.get(&base), mnemonics.get(&quote)) else {
+                continue;
+            };
+            prices.push(ImportedPrice {
+                provider: PROVIDER.to_string(),
+                base: base.clone(),
+                quote: quote.clone(),
+                as_of: parse_sql_date(&at)?,
+                rate: parse_rational(&format!("{num}/{denom}"))?,
+            });
+        }
+    }
+
+    build_batch(&accounts, txns, prices)
+}
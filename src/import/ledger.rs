@@ -0,0 +1,436 @@
+//! Plain-text ledger/hledger journals.
+//!
+//! Supported: dated transactions (status marks, codes, secondary dates), postings with
+//! one elided amount, `@`/`@@` costs, `P` price directives and `:tag:` / `tag:value`
+//! comments. Periodic (`~`) and automated (`=`) transactions and other directives are
+//! skipped.
+//!
+//! The secondary date (`2026-01-05=2026-01-07`) is read as the statement's value date
+//! and becomes effective_at when `import_date` is `value` in config.
+
+use super::{ImportBatch, ImportedPrice, ImportedTxn, account_segment, balance_with_conversions};
+use crate::config::ImportDate;
+use crate::domain::Posting;
+use anyhow::{Context, Result, anyhow};
+use chrono::{DateTime, Datelike, NaiveDate, Utc};
+use rust_decimal::Decimal;
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+const PROVIDER: &str = "ledger";
+
+struct RawAmount {
+    amount: Decimal,
+    commodity: String,
+    /// Per-unit cost as (price, price commodity).
+    cost: Option<(Decimal, String)>,
+}
+
+struct RawPosting {
+    account: String,
+    amount: Option<RawAmount>,
+}
+
+struct RawTxn {
+    line: usize,
+    date: DateTime<Utc>,
+    /// Secondary (value) date, if the header has one.
+    value_date: Option<DateTime<Utc>>,
+    description: String,
+    tags: Vec<String>,
+    postings: Vec<RawPosting>,
+}
+
+pub fn parse_file(path: &Path, import_date: ImportDate) -> Result<ImportBatch> {
+    let raw = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    parse_journal(&raw, import_date)
+}
+
+fn parse_journal(raw: &str, import_date: ImportDate) -> Result<ImportBatch> {
+    let mut batch = ImportBatch::default();
+    let mut txns: Vec<RawTxn> = Vec::new();
+    let mut current: Option<RawTxn> = None;
+    let mut skipping_block = false;
+
+    for (idx, line) in raw.lines().enumerate() {
+        let lineno = idx + 1;
+        let indented = line.starts_with(' ') || line.starts_with('\t');
+        let trimmed = line.trim();
+
+        if indented {
+            if skipping_block || trimmed.is_empty() {
+                continue;
+            }
+            let Some(txn) = current.as_mut() else {
+                continue;
+            };
+            let (body, comment) = split_comment(trimmed);
+            txn.tags.extend(parse_tags(comment));
+            if body.is_empty() {
+                continue;
+            }
+            txn.postings
+                .push(parse_posting(body).with_context(|| format!("ledger line {lineno}"))?);
+            continue;
+        }
+
+        // Any unindented line ends the current transaction.
+        if let Some(txn) = current.take() {
+            txns.push(txn);
+        }
+        skipping_block = false;
+        if trimmed.is_empty() || trimmed.starts_with([';', '#', '%', '|', '*']) {
+            continue;
+        }
+
+        if trimmed.starts_with(|c: char| c.is_ascii_digit()) {
+            current = Some(parse_header(trimmed, lineno)?);
+        } else if let Some(rest) = trimmed.strip_prefix("P ") {
+            batch
+                .prices
+                .push(parse_price(rest).with_context(|| format!("ledger line {lineno}"))?);
+        } else {
+            // account/commodity/include/~/= and friends: ignore the directive and its body.
+            skipping_block = true;
+        }
+    }
+    if let Some(txn) = current.take() {
+        txns.push(txn);
+    }
+
+    let mut seen: HashMap<String, usize> = HashMap::new();
+    for txn in txns {
+        let value_date = txn.value_date;
+        let mut imported = resolve_txn(txn)?;
+        // Content-based ids survive edits elsewhere in the journal; the counter keeps
+        // identical transactions (two coffees on the same day) apart.
+        let key = format!(
+            "{}|{}|{}",
+            imported.effective_at.date_naive(),
+            imported.description.clone().unwrap_or_default(),
+            imported
+                .postings
+                .iter()
+                .map(|p| format!("{} {} {}", p.account, p.amount, p.commodity))
+                .collect::<Vec<_>>()
+                .join(";")
+        );
+        let n = seen.entry(key.clone()).or_insert(0);
+        *n += 1;
+        // The id keeps the posting date so switching `import_date` never re-imports.
+        if import_date == ImportDate::Value
+            && let Some(value_date) = value_date
+        {
+            imported.effective_at = value_date;
+        }
+        batch.txns.push(ImportedTxn {
+            source_id: format!("{key}#{n}"),
+            ..imported
+        });
+    }
+    Ok(batch)
+}
+
+fn split_comment(s: &str) -> (&str, &str) {
+    match s.find(';') {
+        Some(i) => (s[..i].trim(), s[i + 1..].trim()),
+        None => (s.trim(), ""),
+    }
+}
+
+/// `:food:weekly:` and `project:house, trip:` style tags.
+fn parse_tags(comment: &str) -> Vec<String> {
+    let mut out = Vec::new();
+    for part in comment.split([',', ' ']).filter(|p| !p.is_empty()) {
+        if part.starts_with(':') && part.ends_with(':') && part.len() > 1 {
+            out.extend(
+                part.split(':')
+                    .filter(|t| !t.is_empty())
+                    .map(str::to_string),
+            );
+        } else if let Some(name) = part.strip_suffix(':')
+            && !name.contains(':')
+            && !name.is_empty()
+        {
+            out.push(name.to_string());
+        }
+    }
+    // hledger `tag:value` pairs
+    for pair in comment.split(',') {
+        if let Some((name, value)) = pair.trim().split_once(':')
+            && !name.is_empty()
+            && !name.contains([' ', ':'])
+            && !value.trim().is_empty()
+            && !value.contains(':')
+        {
+            out.push(format!("{}:{}", name, value.trim()));
+        }
+    }
+    out
+}
+
+fn parse_date(raw: &str) -> Result<DateTime<Utc>> {
+    let normalized = raw.replace(['/', '.'], "-");
+    let date = NaiveDate::parse_from_str(&normalized, "%Y-%m-%d")
+        .with_context(|| format!("Invalid ledger date '{raw}'"))?;
+    Ok(date.and_hms_opt(0, 0, 0).expect("midnight").and_utc())
+}
+
+fn parse_header(line: &str, lineno: usize) -> Result<RawTxn> {
+    let (body, comment) = split_comment(line);
+    let (date_raw, rest) = body.split_once(char::is_whitespace).unwrap_or((body, ""));
+    let (date_raw, value_raw) = match date_raw.split_once('=') {
+        Some((date_raw, value_raw)) => (date_raw, Some(value_raw)),
+        None => (date_raw, None),
+    };
+    let date = parse_date(date_raw).with_context(|| format!("ledger line {lineno}"))?;
+    // A secondary date without a year (`=01-07`) takes the year that puts it closest to
+    // the primary date, so `2025-12-30=01-02` is 2026-01-02.
+    let value_date = value_raw
+        .map(
+            |raw| match raw.replace(['/', '.'], "-").matches('-').count() {
+                1 => {
+                    let mut nearest: Option<DateTime<Utc>> = None;
+                    for year in [date.year() - 1, date.year(), date.year() + 1] {
+                        let Ok(candidate) = parse_date(&format!("{year}-{raw}")) else {
+                            continue;
+                        };
+                        if nearest.is_none_or(|n| (candidate - date).abs() < (n - date).abs()) {
+                            nearest = Some(candidate);
+                        }
+                    }
+                    nearest.ok_or_else(|| anyhow!("Invalid ledger date '{raw}'"))
+                }
+                _ => parse_date(raw),
+            },
+        )
+        .transpose()
+        .with_context(|| format!("ledger line {lineno}"))?;
+
+    let mut rest = rest.trim();
+    if let Some(r) = rest.strip_prefix(['*', '!']) {
+        rest = r.trim_start();
+    }
+    if rest.starts_with('(')
+        && let Some(end) = rest.find(')')
+    {
+        rest = rest[end + 1..].trim_start();
+    }
+
+    Ok(RawTxn {
+        line: lineno,
+        date,
+        value_date,
+        description: rest.to_string(),
+        tags: parse_tags(comment),
+        postings: Vec::new(),
+    })
+}
+
+fn parse_posting(body: &str) -> Result<RawPosting> {
+    // Account and amount are separated by a tab or at least two spaces.
+    let split_at = body
+        .find('\t')
+        .or_else(|| body.find("  "))
+        .unwrap_or(body.len());
+    let account_raw = body[..split_at].trim();
+    let amount_raw = body[split_at..].trim();
+
+    // Virtual postings: (acct) and [acct].
+    let account_raw = account_raw.trim_matches(|c| matches!(c, '(' | ')' | '[' | ']'));
+    let account = account_raw
+        .split(':')
+        .map(account_segment)
+        .collect::<Vec<_>>()
+        .join(":");
+
+    if amount_raw.is_empty() {
+        return Ok(RawPosting {
+            account,
+            amount: None,
+        });
+    }
+
+    let (amount_part, cost) = if let Some((a, c)) = amount_raw.split_once("@@") {
+        let (amount, commodity) = parse_amount(a)?;
+        let (total, cost_commodity) = parse_amount(c)?;
+        if amount.is_zero() {
+            return Err(anyhow!("Zero amount with total cost: '{body}'"));
+        }
+        (
+            (amount, commodity),
+            Some(((total / amount).abs(), cost_commodity)),
+        )
+    } else if let Some((a, c)) = amount_raw.split_once('@') {
+        (parse_amount(a)?, Some(parse_amount(c)?))
+    } else {
+        (parse_amount(amount_raw)?, None)
+    };
+
+    Ok(RawPosting {
+        account,
+        amount: Some(RawAmount {
+            amount: amount_part.0,
+            commodity: amount_part.1,
+            cost,
+        }),
+    })
+}
+
+fn symbol_commodity(symbol: &str) -> Option<&'static str> {
+    match symbol {
+        "$" => Some("USD"),
+        "€" => Some("EUR"),
+        "£" => Some("GBP"),
+        "¥" => Some("JPY"),
+        _ => None,
+    }
+}
+
+/// `45.20 USD`, `USD 45.20`, `$45.20`, `-$1,200.00`, `"MY FUND" 3`.
+fn parse_amount(raw: &str) -> Result<(Decimal, String)> {
+    let raw = raw.trim();
+    let is_number_char = |c: char| c.is_ascii_digit() || matches!(c, '.' | ',' | '-' | '+');
+
+    let (number, commodity): (String, String) = if let Some(start) = raw.find(is_number_char) {
+        let tail = &raw[start..];
+        let end = tail
+            .find(|c: char| !is_number_char(c))
+            .unwrap_or(tail.len());
+        let number = tail[..end].to_string();
+        let before = raw[..start].trim();
+        let after = tail[end..].trim();
+        let symbol = if before.is_empty() { after } else { before };
+        // `-$5` puts the sign before the symbol.
+        let (negative, symbol) = match symbol.strip_prefix('-') {
+            Some(s) => (true, s.trim()),
+            None => (false, symbol),
+        };
+        let symbol = symbol.trim_matches('"');
+        let commodity = symbol_commodity(symbol)
+            .map(str::to_string)
+            .unwrap_or_else(|| symbol.to_string());
+        let number = if negative {
+            format!("-{number}")
+        } else {
+            number
+        };
+        (number, commodity)
+    } else {
+        return Err(anyhow!("Invalid ledger amount '{raw}'"));
+    };
+
+    if commodity.is_empty() {
+        return Err(anyhow!("Ledger amount '{raw}' has no commodity"));
+    }
+    let amount: Decimal = number
+        .replace(',', "")
+        .parse()
+        .with_context(|| format!("Invalid ledger amount '{raw}'"))?;
+    Ok((amount, commodity))
+}
+
+fn parse_price(rest: &str) -> Result<ImportedPrice> {
+    let mut parts = rest.split_whitespace();
+    let date = parts
+        .next()
+        .ok_or_else(|| anyhow!("P directive without date"))?;
+    let mut next = parts
+        .next()
+        .ok_or_else(|| anyhow!("P directive without commodity"))?;
+    // Optional time after the date.
+    if next.contains(':') {
+        next = parts
+            .next()
+            .ok_or_else(|| anyhow!("P directive without commodity"))?;
+    }
+    let base = next.trim_matches('"').to_string();
+    let (rate, quote) = parse_amount(&parts.collect::<Vec<_>>().join(" "))?;
+    Ok(ImportedPrice {
+        provider: PROVIDER.to_string(),
+        base,
+        quote,
+        as_of: parse_date(date)?,
+        rate,
+    })
+}
+
+fn resolve_txn(txn: RawTxn) -> Result<ImportedTxn> {
+    let line = txn.line;
+    let mut postings = Vec::new();
+    let mut values = Vec::new();
+    let mut cost_commodity: Option<String> = None;
+    let mut elided: Option<String> = None;
+    // Running sum of values per commodity; the elided posting absorbs what is left.
+    let mut open: BTreeMap<String, Decimal> = BTreeMap::new();
+
+    for p in txn.postings {
+        match p.amount {
+            None => {
+                if elided.is_some() {
+                    return Err(anyhow!(
+                        "ledger line {line}: more than one posting without an amount"
+                    ));
+                }
+                elided = Some(p.account);
+            }
+            Some(RawAmount {
+                amount,
+                commodity,
+                cost,
+            }) => {
+                let (value, value_commodity) = match cost {
+                    Some((price, price_commodity)) => {
+                        if cost_commodity
+                            .as_ref()
+                            .is_some_and(|c| *c != price_commodity)
+                        {
+                            return Err(anyhow!(
+                                "ledger line {line}: costs in more than one commodity are not supported"
+                            ));
+                        }
+                        cost_commodity = Some(price_commodity.clone());
+                        (amount * price, price_commodity)
+                    }
+                    None => (amount, commodity.clone()),
+                };
+                *open.entry(value_commodity).or_insert(Decimal::ZERO) += value;
+                postings.push(Posting {
+                    account: p.account,
+                    commodity,
+                    amount,
+                });
+                values.push(value);
+            }
+        }
+    }
+
+    if let Some(account) = elided {
+        for (commodity, total) in &open {
+            if total.is_zero() {
+                continue;
+            }
+            postings.push(Posting {
+                account: account.clone(),
+                commodity: commodity.clone(),
+                amount: -*total,
+            });
+            values.push(-*total);
+        }
+    } else if open.values().any(|v| !v.is_zero()) {
+        return Err(anyhow!("ledger line {line}: transaction does not balance"));
+    }
+
+    if let Some(currency) = cost_commodity {
+        balance_with_conversions(&mut postings, &values, &currency);
+    }
+
+    Ok(ImportedTxn {
+        source_id: String::new(),
+        effective_at: txn.date,
+        description: Some(txn.description).filter(|d| !d.is_empty()),
+        postings,
+        tags: txn.tags,
+    })
+}
@@ -0,0 +1,208 @@
+//! Importers that migrate books from other tools into bankero events.
+//!
+//! Each importer parses its source into an [`ImportBatch`]; writing the batch is shared.
+//! Transactions keep their source id in metadata, so re-running an import skips what
+//! was already imported.
+
+mod gnucash;
+mod ledger;
+
+use crate::cli::ImportCmd;
+use crate::config::{AppConfig, now_utc};
+use crate::db::Db;
+use crate::domain::{EventPayload, Posting, RateContext};
+use anyhow::{Result, anyhow};
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use std::collections::{BTreeMap, HashSet};
+use uuid::Uuid;
+
+/// Balancing account for transactions that convert between commodities.
+const CONVERSION_ACCOUNT: &str = "equity:conversion";
+
+#[derive(Debug, Clone)]
+pub struct ImportedTxn {
+    /// Stable id in the source book (GUID, line number, ...).
+    pub source_id: String,
+    pub effective_at: DateTime<Utc>,
+    pub description: Option<String>,
+    pub postings: Vec<Posting>,
+    pub tags: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ImportedPrice {
+    pub provider: String,
+    pub base: String,
+    pub quote: String,
+    pub as_of: DateTime<Utc>,
+    pub rate: Decimal,
+}
+
+#[derive(Debug, Default)]
+pub struct ImportBatch {
+    pub txns: Vec<ImportedTxn>,
+    pub prices: Vec<ImportedPrice>,
+}
+
+pub fn handle_import(db: &Db, cfg: &AppConfig, cmd: ImportCmd) -> Result<()> {
+    let (source, file, batch) = match cmd {
+        ImportCmd::Gnucash { file } => {
+            let batch = gnucash::parse_file(&file)?;
+            ("gnucash", file, batch)
+        }
+        ImportCmd::Ledger { file } => {
+            let batch = ledger::parse_file(&file, cfg.import_date)?;
+            ("ledger", file, batch)
+        }
+        ImportCmd::Events { file, accept_large } => {
+            return crate::backup::import_events(db, cfg, &file, accept_large);
+        }
+    };
+
+    let stats = write_batch(db, cfg, source, &batch)?;
+    println!(
+        "Imported {} from {}: {} event(s) written, {} already present, {} rate(s) stored.",
+        source,
+        file.display(),
+        stats.written,
+        stats.skipped,
+        stats.rates
+    );
+    Ok(())
+}
+
+pub struct ImportStats {
+    pub written: usize,
+    pub skipped: usize,
+    pub rates: usize,
+}
+
+/// Adds `equity:conversion` postings so every commodity in the transaction nets to zero.
+///
+/// `values` are the postings' worth in the transaction currency, aligned with `postings`.
+pub fn balance_with_conversions(postings: &mut Vec<Posting>, values: &[Decimal], currency: &str) {
+    let mut foreign: BTreeMap<String, Decimal> = BTreeMap::new();
+    let mut currency_value = Decimal::ZERO;
+    for (p, value) in postings.iter().zip(values) {
+        if p.commodity != currency {
+            *foreign.entry(p.commodity.clone()).or_insert(Decimal::ZERO) += p.amount;
+            currency_value += *value;
+        }
+    }
+    if foreign.is_empty() {
+        return;
+    }
+    for (commodity, total) in foreign {
+        if !total.is_zero() {
+            postings.push(Posting {
+                account: CONVERSION_ACCOUNT.to_string(),
+                commodity,
+                amount: -total,
+            });
+        }
+    }
+    if !currency_value.is_zero() {
+        postings.push(Posting {
+            account: CONVERSION_ACCOUNT.to_string(),
+            commodity: currency.to_string(),
+            amount: currency_value,
+        });
+    }
+}
+
+fn write_batch(db: &Db, cfg: &AppConfig, source: &str, batch: &ImportBatch) -> Result<ImportStats> {
+    let mut already: HashSet<String> = HashSet::new();
+    for e in db.list_events()? {
+        if e.payload
+            .metadata
+            .get("import_source")
+            .and_then(|v| v.as_str())
+            == Some(source)
+            && let Some(id) = e.payload.metadata.get("import_id").and_then(|v| v.as_str())
+        {
+            already.insert(id.to_string());
+        }
+    }
+
+    let mut stats = ImportStats {
+        written: 0,
+        skipped: 0,
+        rates: 0,
+    };
+    let mut pending = Vec::new();
+    for txn in &batch.txns {
+        if already.contains(&txn.source_id) {
+            stats.skipped += 1;
+            continue;
+        }
+        if txn.postings.is_empty() {
+            return Err(anyhow!(
+                "{source} transaction {} has no postings",
+                txn.source_id
+            ));
+        }
+
+        let event_id = Uuid::new_v4();
+        let rule = crate::rules::categorize(
+            &cfg.category_rules,
+            &[txn.description.as_deref().unwrap_or_default()],
+        );
+        let mut payload = EventPayload {
+            schema_version: 1,
+            device_id: cfg.device_id,
+            workspace: cfg.current_workspace.clone(),
+            project: cfg.current_project.clone(),
+            action: "import".to_string(),
+            created_at: now_utc(),
+            effective_at: txn.effective_at,
+            postings: txn.postings.clone(),
+            tags: txn.tags.clone(),
+            category: rule.map(|r| r.category.clone()),
+            note: txn.description.clone(),
+            reference: None,
+            rate_context: RateContext {
+                provider: None,
+                override_rate: None,
+                base: None,
+                quote: None,
+                as_of: txn.effective_at,
+            },
+            basis: None,
+            metadata: serde_json::json!({
+                "event_id": event_id.to_string(),
+                "import_source": source,
+                "import_id": txn.source_id,
+            }),
+        };
+        if let Some(rule) = rule {
+            payload.metadata["category_rule"] = serde_json::Value::String(rule.contains.clone());
+        }
+        pending.push((event_id, payload));
+    }
+
+    // Checked as a whole so a strict-mode rejection leaves nothing half imported.
+    crate::commodities::check_all_known(db, cfg, pending.iter().map(|(_, payload)| payload))?;
+    for (event_id, payload) in &pending {
+        db.insert_event(*event_id, payload)?;
+        stats.written += 1;
+    }
+
+    for price in &batch.prices {
+        db.set_rate(
+            &price.provider,
+            &price.base,
+            &price.quote,
+            price.as_of,
+            price.rate,
+            &format!("import:{source}"),
+        )?;
+        stats.rates += 1;
+    }
+    Ok(stats)
+}
+
+/// Turns an account name into a bankero account segment ("Credit Card" -> "credit-card").
+pub fn account_segment(name: &str) -> String {
+    crate::config::workspace_slug(name)
+}
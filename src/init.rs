@@ -0,0 +1,258 @@
+//! First-run setup wizard (`bankero init`).
+//!
+//! Asks the handful of questions a newcomer would otherwise have to discover from the
+//! docs, writes them to the config, and records opening balances as a single `opening`
+//! event balanced against `equity:opening-balances`.
+
+use crate::cli::InitArgs;
+use crate::config::{AppConfig, now_utc, write_config};
+use crate::db::Db;
+use crate::domain::{EventPayload, Posting, RateContext};
+use anyhow::{Context, Result, anyhow};
+use chrono::{DateTime, FixedOffset, Local, NaiveDate, Offset, TimeZone, Utc};
+use rust_decimal::Decimal;
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+use std::path::Path;
+use uuid::Uuid;
+
+pub const OPENING_ACTION: &str = "opening";
+const OPENING_EQUITY: &str = "equity:opening-balances";
+
+/// Parses a UTC offset such as "+02:00", "-0430" or "UTC".
+pub fn parse_offset(raw: &str) -> Result<FixedOffset> {
+    let raw = raw.trim();
+    if raw.eq_ignore_ascii_case("utc") || raw.eq_ignore_ascii_case("z") {
+        return Ok(Utc.fix());
+    }
+    let invalid = || anyhow!("Invalid timezone: {raw}. Expected a UTC offset like -04:00");
+    let (sign, rest) = match raw.split_at_checked(1) {
+        Some(("+", rest)) => (1, rest),
+        Some(("-", rest)) => (-1, rest),
+        _ => return Err(invalid()),
+    };
+    let digits = rest.replace(':', "");
+    if digits.len() != 4 || !digits.chars().all(|c| c.is_ascii_digit()) {
+        return Err(invalid());
+    }
+    let hours: i32 = digits[..2].parse().map_err(|_| invalid())?;
+    let minutes: i32 = digits[2..].parse().map_err(|_| invalid())?;
+    FixedOffset::east_opt(sign * (hours * 3600 + minutes * 60)).ok_or_else(invalid)
+}
+
+/// Prints `question [default]: ` and returns the answer, or the default on an empty line.
+fn ask(question: &str, default: &str) -> Result<String> {
+    if default.is_empty() {
+        eprint!("{question}: ");
+    } else {
+        eprint!("{question} [{default}]: ");
+    }
+    io::stderr().flush().ok();
+    let mut line = String::new();
+    io::stdin().read_line(&mut line)?;
+    let answer = line.trim();
+    Ok(if answer.is_empty() {
+        default.to_string()
+    } else {
+        answer.to_string()
+    })
+}
+
+/// Parses "ACCOUNT AMOUNT [COMMODITY]".
+fn parse_opening(raw: &str, reference: &str) -> Result<Posting> {
+    let parts: Vec<&str> = raw.split_whitespace().collect();
+    let (account, amount, commodity) = match parts.as_slice() {
+        [account, amount] => (*account, *amount, reference),
+        [account, amount, commodity] => (*account, *amount, *commodity),
+        _ => {
+            return Err(anyhow!(
+                "Invalid opening balance \"{raw}\". Expected ACCOUNT AMOUNT [COMMODITY]"
+            ));
+        }
+    };
+    if !account.contains(':') {
+        return Err(anyhow!(
+            "Invalid opening balance account \"{account}\". Use a full account like assets:bank"
+        ));
+    }
+    Ok(Posting {
+        account: account.to_string(),
+        commodity: commodity.to_ascii_uppercase(),
+        amount: crate::parse_decimal(amount.to_string(), "opening balance")?,
+    })
+}
+
+pub fn handle_init(db: &Db, cfg: &mut AppConfig, cfg_path: &Path, args: InitArgs) -> Result<()> {
+    let interactive = !args.yes;
+    if interactive {
+        eprintln!("Welcome to Bankero. Press Enter to keep the value in brackets.");
+    }
+
+    let default = args
+        .commodity
+        .clone()
+        .unwrap_or_else(|| cfg.reference_commodity.clone());
+    let commodity = if interactive {
+        ask("Reference commodity", &default)?
+    } else {
+        default
+    }
+    .to_ascii_uppercase();
+
+    let default = args
+        .timezone
+        .clone()
+        .or_else(|| cfg.timezone.clone())
+        .unwrap_or_else(|| Local::now().offset().to_string());
+    let timezone = if interactive {
+        ask("Timezone (UTC offset)", &default)?
+    } else {
+        default
+    };
+    let offset = parse_offset(&timezone)?;
+
+    let default = args
+        .default_account
+        .clone()
+        .or_else(|| cfg.default_account.clone())
+        .unwrap_or_else(|| "assets:cash".to_string());
+    let default_account = if interactive {
+        ask("Default account for quick entries", &default)?
+    } else {
+        default
+    };
+
+    let default = args
+        .sync_dir
+        .clone()
+        .or_else(|| cfg.sync_dir.clone())
+        .unwrap_or_default();
+    let sync_dir = if interactive {
+        ask("Sync folder (blank to skip)", &default)?
+    } else {
+        default
+    };
+
+    let mut openings = Vec::new();
+    for raw in &args.openings {
+        openings.push(parse_opening(raw, &commodity)?);
+    }
+    if interactive {
+        eprintln!("Opening balances: ACCOUNT AMOUNT [COMMODITY], owed money negative.");
+        loop {
+            let line = ask("Opening balance (blank to finish)", "")?;
+            if line.is_empty() {
+                break;
+            }
+            match parse_opening(&line, &commodity) {
+                Ok(posting) => openings.push(posting),
+                Err(err) => eprintln!("{err}"),
+            }
+        }
+    }
+
+    cfg.reference_commodity = commodity;
+    cfg.timezone = Some(offset.to_string());
+    cfg.default_account = Some(default_account);
+    cfg.sync_dir = (!sync_dir.is_empty()).then_some(sync_dir);
+    write_config(cfg_path, cfg)?;
+
+    println!("reference_commodity\t{}", cfg.reference_commodity);
+    println!("timezone\t{offset}");
+    println!(
+        "default_account\t{}",
+        cfg.default_account.as_deref().unwrap_or("")
+    );
+    println!(
+        "sync_dir\t{}",
+        cfg.sync_dir.as_deref().unwrap_or("<not set>")
+    );
+
+    openings.retain(|p| !p.amount.is_zero());
+    if !openings.is_empty() {
+        let existing = db.list_events()?.len();
+        if existing > 0 {
+            println!(
+                "Journal already has {existing} event(s); skipped opening balances. Record them with `bankero move`."
+            );
+        } else {
+            let at = opening_at(args.as_of.as_deref(), offset)?;
+            write_opening(db, cfg, openings, at)?;
+        }
+    }
+
+    println!("Next: bankero q 4.50 coffee #food, bankero balance, bankero --help");
+    Ok(())
+}
+
+fn opening_at(as_of: Option<&str>, offset: FixedOffset) -> Result<DateTime<Utc>> {
+    let date = match as_of {
+        Some(raw) => NaiveDate::parse_from_str(raw, "%Y-%m-%d")
+            .with_context(|| format!("Invalid --as-of: {raw}. Expected YYYY-MM-DD"))?,
+        None => now_utc().with_timezone(&offset).date_naive(),
+    };
+    offset
+        .from_local_datetime(&date.and_hms_opt(0, 0, 0).unwrap_or_default())
+        .single()
+        .map(|t| t.with_timezone(&Utc))
+        .ok_or_else(|| anyhow!("Invalid opening date {date}"))
+}
+
+fn write_opening(
+    db: &Db,
+    cfg: &AppConfig,
+    openings: Vec<Posting>,
+    at: DateTime<Utc>,
+) -> Result<()> {
+    let mut equity: BTreeMap<String, Decimal> = BTreeMap::new();
+    for p in &openings {
+        *equity.entry(p.commodity.clone()).or_default() -= p.amount;
+    }
+    let count = openings.len();
+    let mut postings = openings;
+    postings.extend(
+        equity
+            .into_iter()
+            .filter(|(_, amount)| !amount.is_zero())
+            .map(|(commodity, amount)| Posting {
+                account: OPENING_EQUITY.to_string(),
+                commodity,
+                amount,
+            }),
+    );
+
+    let event_id = Uuid::new_v4();
+    let payload = EventPayload {
+        schema_version: 1,
+        device_id: cfg.device_id,
+        workspace: cfg.current_workspace.clone(),
+        project: cfg.current_project.clone(),
+        action: OPENING_ACTION.to_string(),
+        created_at: now_utc(),
+        effective_at: at,
+        postings,
+        tags: vec![],
+        category: None,
+        note: Some("Opening balances".to_string()),
+        reference: None,
+        rate_context: RateContext {
+            provider: None,
+            override_rate: None,
+            base: None,
+            quote: None,
+            as_of: at,
+        },
+        basis: None,
+        metadata: serde_json::json!({
+            "event_id": event_id.to_string(),
+            "entered_by": crate::entered_by(cfg, None),
+        }),
+    };
+    crate::commodities::check_known(db, cfg, &payload)?;
+    db.insert_event(event_id, &payload)?;
+    println!(
+        "Wrote opening balances for {count} account(s) as of {}.",
+        at.to_rfc3339()
+    );
+    Ok(())
+}
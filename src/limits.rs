@@ -0,0 +1,184 @@
+//! Monthly spending limits (`bankero limit`).
+//!
+//! A limit caps what an account subtree may receive in one commodity per calendar month.
+//! Every event written from the CLI is checked before it is stored: crossing a soft limit
+//! prints a warning, crossing a hard one fails unless `--override` is given. Overrides
+//! are recorded on the event (`metadata.limit_overrides`) so `limit overrides` can list them.
+
+use crate::cli::LimitCmd;
+use crate::config::now_utc;
+use crate::db::{Db, StoredLimit};
+use crate::domain::{EventPayload, StoredEvent};
+use anyhow::{Result, anyhow};
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+
+fn under(account: &str, limit: &StoredLimit) -> bool {
+    account == limit.account || account.starts_with(&format!("{}:", limit.account))
+}
+
+/// Net amount `postings` add to the limited subtree.
+fn added(payload: &EventPayload, limit: &StoredLimit) -> Decimal {
+    payload
+        .postings
+        .iter()
+        .filter(|p| p.commodity == limit.commodity && under(&p.account, limit))
+        .map(|p| p.amount)
+        .sum()
+}
+
+/// What the subtree received during the calendar month containing `at`.
+fn month_total(events: &[StoredEvent], limit: &StoredLimit, at: DateTime<Utc>) -> Result<Decimal> {
+    let (start, end) = crate::period::month_range(&crate::current_month_yyyy_mm(at))?;
+    Ok(events
+        .iter()
+        .filter(|e| e.effective_at >= start && e.effective_at <= end)
+        .map(|e| added(&e.payload, limit))
+        .sum())
+}
+
+/// Checks `payload` against every limit; records overrides in its metadata.
+pub fn enforce(db: &Db, payload: &mut EventPayload, override_limits: bool) -> Result<()> {
+    let limits = db.list_limits()?;
+    if limits.is_empty() {
+        return Ok(());
+    }
+    let events = crate::snapshot::replay_events(db, false)?;
+    let mut overrides = Vec::new();
+    for limit in &limits {
+        let adding = added(payload, limit);
+        if adding <= Decimal::ZERO {
+            continue;
+        }
+        let before = month_total(&events, limit, payload.effective_at)?;
+        let after = before + adding;
+        if after <= limit.amount {
+            continue;
+        }
+
+        let detail = format!(
+            "{} is limited to {} {} per month (this month: {}, this event adds {})",
+            limit.account, limit.amount, limit.commodity, before, adding
+        );
+        if !limit.hard {
+            eprintln!("Warning: over limit. {detail}.");
+        } else if override_limits {
+            eprintln!("Override: hard limit exceeded. {detail}. This override is logged.");
+            overrides.push(serde_json::json!({
+                "account": limit.account,
+                "commodity": limit.commodity,
+                "limit": limit.amount.to_string(),
+                "month_total": after.to_string(),
+            }));
+        } else {
+            return Err(anyhow!(
+                "Blocked by hard limit: {detail}. Pass --override to write it anyway (overrides are logged)."
+            ));
+        }
+    }
+    if !overrides.is_empty() {
+        payload.metadata["limit_overrides"] = serde_json::Value::Array(overrides);
+    }
+    Ok(())
+}
+
+pub fn handle_limit(db: &Db, cmd: LimitCmd) -> Result<()> {
+    match cmd {
+        LimitCmd::Set {
+            account,
+            amount,
+            commodity,
+            hard,
+        } => {
+            let amount = crate::parse_decimal(amount, "amount")?;
+            if amount < Decimal::ZERO {
+                return Err(anyhow!("Limit amount must be >= 0"));
+            }
+            let limit = StoredLimit {
+                account: account.trim_end_matches(':').to_string(),
+                amount,
+                commodity: commodity.to_ascii_uppercase(),
+                hard,
+                created_at: now_utc(),
+            };
+            db.upsert_limit(&limit)?;
+            println!(
+                "Set {} limit on {}: {} {} per month.",
+                if hard { "hard" } else { "soft" },
+                limit.account,
+                limit.amount,
+                limit.commodity
+            );
+            Ok(())
+        }
+        LimitCmd::Remove { account, commodity } => {
+            let commodity = commodity.map(|c| c.to_ascii_uppercase());
+            if db.delete_limit(&account, commodity.as_deref())? == 0 {
+                return Err(anyhow!("No limit on {account}"));
+            }
+            println!("Removed limit on {account}.");
+            Ok(())
+        }
+        LimitCmd::List { month } => {
+            let limits = db.list_limits()?;
+            if limits.is_empty() {
+                println!("(no limits)");
+                return Ok(());
+            }
+            let at = match month {
+                Some(m) => crate::period::month_range(&m)?.0,
+                None => now_utc(),
+            };
+            let events = crate::snapshot::replay_events(db, false)?;
+            println!("account\tcommodity\tlimit\tkind\tspent\tremaining");
+            for limit in &limits {
+                let spent = month_total(&events, limit, at)?;
+                println!(
+                    "{}\t{}\t{}\t{}\t{}\t{}",
+                    limit.account,
+                    limit.commodity,
+                    limit.amount,
+                    if limit.hard { "hard" } else { "soft" },
+                    spent,
+                    limit.amount - spent
+                );
+            }
+            Ok(())
+        }
+        LimitCmd::Overrides => {
+            let events = crate::snapshot::replay_events(db, false)?;
+            let mut any = false;
+            for e in &events {
+                let Some(entries) = e
+                    .payload
+                    .metadata
+                    .get("limit_overrides")
+                    .and_then(|v| v.as_array())
+                else {
+                    continue;
+                };
+                if !any {
+                    println!("effective_at\taction\tevent_id\taccount\tlimit\tmonth_total");
+                    any = true;
+                }
+                for entry in entries {
+                    let field = |k: &str| entry.get(k).and_then(|v| v.as_str()).unwrap_or("");
+                    println!(
+                        "{}\t{}\t{}\t{}\t{} {}\t{}",
+                        e.effective_at.to_rfc3339(),
+                        e.action,
+                        e.event_id,
+                        field("account"),
+                        field("limit"),
+                        field("commodity"),
+                        field("month_total")
+                    );
+                }
+            }
+            if !any {
+                println!("(no overrides)");
+            }
+            Ok(())
+        }
+    }
+}
@@ -1,8 +1,53 @@
+mod activity;
+mod analytics;
+mod approve;
+mod attachments;
+mod backup;
+mod batch;
+#[cfg(feature = "bench")]
+mod benchgen;
+mod bot;
+mod cashcount;
 mod cli;
+mod commodities;
 mod config;
+mod dates;
 mod db;
+mod debts;
+mod debug;
 mod domain;
+mod event;
+mod exchange;
+mod gains;
+mod help;
+mod history;
+mod import;
+mod init;
+mod limits;
+mod merge;
+mod notify;
+mod output;
+mod paste;
+mod payoff;
+mod pending;
+mod period;
+mod plan;
+mod providers;
+mod refund;
+mod renderer;
+mod reprice;
+mod revoke;
+mod rules;
+mod settle;
+mod share;
+mod show;
+mod snapshot;
 mod sync;
+mod tags;
+mod tasks;
+mod tax;
+mod templates;
+mod tokens;
 mod upgrade;
 
 use anyhow::{Context, Result, anyhow};
@@ -10,12 +55,14 @@ use chrono::{DateTime, Datelike, NaiveDate, NaiveDateTime, NaiveTime, TimeZone,
 use clap::Parser;
 use rust_decimal::Decimal;
 use rust_decimal::prelude::ToPrimitive;
-use std::collections::BTreeMap;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
 use std::io::{self, Write};
 use uuid::Uuid;
 
 use crate::cli::{
-    BudgetCmd, Cli, Command, PiggyCmd, ProjectCmd, RateCommand, WsCmd, parse_provider_opt,
+    AssetCmd, BudgetCmd, Cli, Command, ConfigCmd, Interpolation, PiggyCmd, ProfileCmd, ProjectCmd,
+    RateCommand, WsCmd, parse_provider_opt,
 };
 use crate::config::{AppConfig, app_paths, load_or_init_config, now_utc, write_config};
 use crate::db::Db;
@@ -31,9 +78,31 @@ fn main() {
 }
 
 fn run() -> Result<()> {
-    let cli = Cli::parse();
-    let paths = app_paths(cli.home.clone())?;
+    let cli = Cli::parse_from(crate::exchange::strip_arrow(std::env::args_os().collect())?);
+    let mut paths = app_paths(cli.home.clone(), cli.profile.as_deref())?;
     let (mut cfg, cfg_path) = load_or_init_config(&paths)?;
+    // Templates live in the journal, so a template use opens it to rebuild the command
+    // line; the command it stands for then reuses that journal.
+    let mut journal = None;
+    let cli = if crate::templates::is_use(&cli.command) {
+        let mut data_paths = paths.clone();
+        crate::config::apply_data_dir(&mut data_paths, &cfg)?;
+        let (db, db_path) = Db::open(&data_paths, &cfg.current_workspace)?;
+        let expanded = crate::templates::expand(&cli, &db)?;
+        journal = Some((db, db_path));
+        match expanded {
+            Some(argv) => Cli::parse_from(argv),
+            None => cli,
+        }
+    } else {
+        cli
+    };
+    // `config` must keep working when the configured data dir is unreachable.
+    if let Command::Config(args) = cli.command {
+        return handle_config(args.cmd, &paths, &mut cfg, &cfg_path);
+    }
+    crate::config::apply_data_dir(&mut paths, &cfg)?;
+    crate::dates::configure(&cfg)?;
 
     match cli.command {
         Command::Login(args) => {
@@ -44,178 +113,518 @@ fn run() -> Result<()> {
             handle_ws(args.cmd, &paths, &mut cfg, &cfg_path)?;
             Ok(())
         }
+        Command::Profile(args) => handle_profile(args.cmd, &paths, &cfg),
+        Command::Rules(args) => crate::rules::handle_rules(args.cmd, &mut cfg, &cfg_path),
+        Command::Token(args) => crate::tokens::handle_token(args.cmd, &mut cfg, &cfg_path),
         Command::Project(args) => {
             handle_project(args.cmd, &paths, &mut cfg, &cfg_path)?;
             Ok(())
         }
         Command::Upgrade(args) => crate::upgrade::handle_upgrade(args),
+        Command::Help(args) => crate::help::handle_help(args),
+        Command::GenerateMan(args) => crate::help::handle_generate_man(args),
         cmd => {
-            let (db, db_path) = Db::open(&paths, &cfg.current_workspace)?;
-
-            match cmd {
-                Command::Deposit(args) => {
-                    let confirm = args.common.confirm;
-                    let event_id = Uuid::new_v4();
-                    let payload = build_deposit_event(
-                        &cfg,
-                        "deposit",
-                        event_id,
-                        args.amount,
-                        args.commodity,
-                        args.from,
-                        args.to,
-                        None,
-                        args.common,
-                    )?;
-                    maybe_confirm_and_insert(&db, &cfg, event_id, &payload, confirm)?;
-                    println!("Wrote event {event_id} to {}", db_path.display());
-                }
-                Command::Move(args) => {
-                    let (to_amount, to_commodity, provider) = parse_move_tail(&args.tail)?;
-                    let confirm = args.common.confirm;
-                    let event_id = Uuid::new_v4();
-
-                    // If the user supplied only a destination commodity + provider, compute the quote amount.
-                    let (to_amount, provider) = match (to_amount, to_commodity.as_ref(), provider) {
-                        (None, Some(to_commodity), Some(mut provider)) => {
-                            let amount = parse_decimal(args.amount.clone(), "amount")?;
-                            let effective_at =
-                                parse_rfc3339_or_now(args.common.effective_at.as_deref())?;
-                            let as_of = parse_as_of(&args.common, effective_at)?;
-
-                            let base = args.commodity.to_ascii_uppercase();
-                            let quote = to_commodity.to_ascii_uppercase();
-
-                            let rate = if let Some(r) = provider.override_rate {
-                                r
-                            } else {
-                                let Some((_found_as_of, r)) =
-                                    db.get_rate_as_of(&provider.provider, &base, &quote, as_of)?
-                                else {
-                                    return Err(anyhow!(
-                                        "No stored rate for @{} {} per {} at or before {}. Set one with: bankero rate set @{} {} {} <rate> --as-of <rfc3339>",
-                                        provider.provider,
-                                        quote,
-                                        base,
-                                        as_of.to_rfc3339(),
-                                        provider.provider,
-                                        base,
-                                        quote,
-                                    ));
-                                };
-                                r
-                            };
-
-                            provider.override_rate = Some(rate);
-                            let computed_to_amount = amount * rate;
-                            (Some(computed_to_amount), Some(provider))
-                        }
-                        (to_amount, _, provider) => (to_amount, provider),
-                    };
+            let (db, db_path) = match journal {
+                Some(journal) => journal,
+                None => Db::open(&paths, &cfg.current_workspace)?,
+            };
+            let globals = crate::templates::global_args(&cli.home, &cli.profile);
+            dispatch(cmd, &db, &db_path, &paths, &globals, &mut cfg, &cfg_path)
+        }
+    }
+}
 
-                    let payload = build_move_event(
-                        &cfg,
-                        event_id,
-                        args.amount,
-                        args.commodity,
-                        args.from,
-                        args.to,
-                        provider,
-                        to_amount,
-                        to_commodity,
-                        args.common,
-                    )?;
-                    maybe_confirm_and_insert(&db, &cfg, event_id, &payload, confirm)?;
-                    println!("Wrote event {event_id} to {}", db_path.display());
-                }
-                Command::Buy(args) => {
-                    let provider = parse_provider_opt(&args.provider);
-                    let confirm = args.common.confirm;
-                    let event_id = Uuid::new_v4();
-
-                    let (payee, amount, commodity) = if let Some(commodity) = args.commodity {
-                        (
-                            Some(args.payee_or_amount),
-                            args.amount_or_commodity,
-                            commodity,
-                        )
+/// Runs a command that works on the current workspace's journal.
+fn dispatch(
+    cmd: Command,
+    db: &Db,
+    db_path: &std::path::Path,
+    paths: &crate::config::AppPaths,
+    globals: &[std::ffi::OsString],
+    cfg: &mut AppConfig,
+    cfg_path: &std::path::Path,
+) -> Result<()> {
+    match cmd {
+        Command::Deposit(args) => {
+            let write = WriteOptions::of(&args.common);
+            let event_id = Uuid::new_v4();
+            let commodity = crate::commodities::explicit_or_default(
+                db,
+                args.commodity,
+                &[&args.to, &args.from],
+            )?;
+            let payload = build_deposit_event(
+                cfg,
+                "deposit",
+                event_id,
+                args.amount,
+                commodity,
+                args.from,
+                args.to,
+                None,
+                args.common,
+            )?;
+            if maybe_confirm_and_insert(db, cfg, event_id, &payload, &write)? {
+                crate::batch::wrote(format!("Wrote event {event_id} to {}", db_path.display()));
+            }
+        }
+        Command::Withdraw(args) => {
+            let write = WriteOptions::of(&args.common);
+            let event_id = Uuid::new_v4();
+            let commodity = crate::commodities::explicit_or_default(
+                db,
+                args.commodity,
+                &[&args.to, &args.from],
+            )?;
+            let payload = build_deposit_event(
+                cfg,
+                "withdraw",
+                event_id,
+                args.amount,
+                commodity,
+                args.from,
+                args.to,
+                None,
+                args.common,
+            )?;
+            if maybe_confirm_and_insert(db, cfg, event_id, &payload, &write)? {
+                crate::batch::wrote(format!("Wrote event {event_id} to {}", db_path.display()));
+            }
+        }
+        Command::Refund(args) => {
+            let write = WriteOptions::of(&args.common);
+            let event_id = Uuid::new_v4();
+            let payload = crate::refund::build_refund_event(db, cfg, event_id, args)?;
+            if maybe_confirm_and_insert(db, cfg, event_id, &payload, &write)? {
+                crate::batch::wrote(format!("Wrote event {event_id} to {}", db_path.display()));
+            }
+        }
+        Command::Exchange(args) => {
+            let write = WriteOptions::of(&args.common);
+            let event_id = Uuid::new_v4();
+            let (payload, summary) =
+                crate::exchange::build_exchange_event(db, cfg, event_id, args)?;
+            let written = maybe_confirm_and_insert(db, cfg, event_id, &payload, &write)?;
+            println!("{summary}");
+            if written {
+                crate::batch::wrote(format!("Wrote event {event_id} to {}", db_path.display()));
+            }
+        }
+        Command::Move(args) => {
+            let (to_amount, to_commodity, provider) = parse_move_tail(&args.tail)?;
+            let write = WriteOptions::of(&args.common);
+            let event_id = Uuid::new_v4();
+
+            // If the user supplied only a destination commodity + provider, compute the quote amount.
+            let mut unrounded_to_amount = None;
+            let (to_amount, provider) = match (to_amount, to_commodity.as_ref(), provider) {
+                (None, Some(to_commodity), Some(mut provider)) => {
+                    let amount = parse_decimal(args.amount.clone(), "amount")?;
+                    let effective_at = parse_rfc3339_or_now(args.common.effective_at.as_deref())?;
+                    let as_of = parse_as_of(&args.common, effective_at)?;
+
+                    let base = args.commodity.to_ascii_uppercase();
+                    let quote = to_commodity.to_ascii_uppercase();
+
+                    let rate = if let Some(r) = provider.override_rate {
+                        r
                     } else {
-                        (None, args.payee_or_amount, args.amount_or_commodity)
+                        let Some((_found_as_of, r)) = crate::providers::rate_as_of(
+                            db,
+                            &provider.provider,
+                            &base,
+                            &quote,
+                            as_of,
+                        )?
+                        else {
+                            return Err(anyhow!(
+                                "No stored rate for @{} {} per {} at or before {}. Set one with: bankero rate set @{} {} {} <rate> --as-of <rfc3339>",
+                                provider.provider,
+                                quote,
+                                base,
+                                as_of.to_rfc3339(),
+                                provider.provider,
+                                base,
+                                quote,
+                            ));
+                        };
+                        r
                     };
 
-                    let payload = build_buy_event(
-                        &cfg,
-                        event_id,
-                        payee,
-                        amount,
-                        commodity,
-                        args.from,
-                        args.to_splits,
-                        provider,
-                        args.common,
-                    )?;
-                    maybe_confirm_and_insert(&db, &cfg, event_id, &payload, confirm)?;
-                    println!("Wrote event {event_id} to {}", db_path.display());
-                }
-                Command::Sell(args) => {
-                    let provider = parse_provider_opt(&args.provider);
-                    let confirm = args.common.confirm;
-                    let event_id = Uuid::new_v4();
-                    let payload = build_sell_event(
-                        &cfg,
-                        event_id,
-                        args.amount,
-                        args.commodity,
-                        args.from,
-                        args.to,
-                        args.to_amount,
-                        args.to_commodity,
-                        provider,
-                        args.common,
-                    )?;
-                    maybe_confirm_and_insert(&db, &cfg, event_id, &payload, confirm)?;
-                    println!("Wrote event {event_id} to {}", db_path.display());
-                }
-                Command::Tag(args) => {
-                    let confirm = args.common.confirm;
-                    let event_id = Uuid::new_v4();
-                    let payload =
-                        build_tag_event(&cfg, event_id, args.target, args.set_basis, args.common)?;
-                    maybe_confirm_and_insert(&db, &cfg, event_id, &payload, confirm)?;
-                    println!("Wrote event {event_id} to {}", db_path.display());
-                }
-                Command::Balance(args) => {
-                    let events = db.list_events()?;
-                    print_balance(&db, &events, args.account.as_deref(), args.month.as_deref())?;
-                }
-                Command::Report(args) => {
-                    let events = db.list_events()?;
-                    let filtered = filter_events(&events, &args)?;
-                    print_report(&filtered);
-                }
-                Command::Rate(args) => {
-                    handle_rate(&db, args.command)?;
-                }
-                Command::Budget(args) => {
-                    handle_budget(&db, args.cmd)?;
-                }
-                Command::Piggy(args) => {
-                    handle_piggy(&db, args.cmd)?;
+                    provider.override_rate = Some(rate);
+                    let raw = amount * rate;
+                    let computed_to_amount = crate::commodities::round(db, &quote, raw)?;
+                    if computed_to_amount != raw {
+                        unrounded_to_amount = Some(raw);
+                    }
+                    (Some(computed_to_amount), Some(provider))
                 }
-                Command::Sync(args) => {
-                    crate::sync::handle_sync(&db, args, &mut cfg, &cfg_path)?;
+                (to_amount, _, provider) => (to_amount, provider),
+            };
+
+            // A pending move lands in the transit account; `settle` moves it on.
+            let pending_to = args.pending.then(|| args.to.clone());
+            if pending_to.as_deref() == Some(cfg.transit_account.as_str()) {
+                return Err(anyhow!(
+                    "--pending moves go through {}; --to must be the final account",
+                    cfg.transit_account
+                ));
+            }
+            let to = match &pending_to {
+                Some(_) => cfg.transit_account.clone(),
+                None => args.to,
+            };
+            let mut payload = build_move_event(
+                cfg,
+                event_id,
+                args.amount,
+                args.commodity,
+                args.from,
+                to,
+                provider,
+                to_amount,
+                to_commodity,
+                args.common,
+            )?;
+            // Post what rounding dropped so the destination leg still matches the rate.
+            if let (Some(raw), Some(to_amount), Some(quote)) = (
+                unrounded_to_amount,
+                to_amount,
+                payload.rate_context.quote.clone(),
+            ) {
+                payload.postings.push(Posting {
+                    account: cfg.rounding_account.clone(),
+                    commodity: quote,
+                    amount: (raw - to_amount).normalize(),
+                });
+                payload.metadata["unrounded_to_amount"] =
+                    serde_json::Value::String(raw.to_string());
+            }
+            apply_fee(cfg, &mut payload, &args.fee)?;
+            if let Some(final_to) = &pending_to {
+                payload.metadata["pending_to"] = serde_json::json!(final_to);
+                payload.metadata["transit_account"] = serde_json::json!(cfg.transit_account);
+            }
+            let written = maybe_confirm_and_insert(db, cfg, event_id, &payload, &write)?;
+            if written {
+                crate::batch::wrote(format!("Wrote event {event_id} to {}", db_path.display()));
+            }
+            if written && let Some(final_to) = pending_to {
+                println!(
+                    "In transit to {final_to}; when it arrives run: bankero settle {}",
+                    &event_id.to_string()[..8]
+                );
+            }
+        }
+        Command::Lend(args) => {
+            let write = WriteOptions::of(&args.common);
+            let event_id = Uuid::new_v4();
+            let payload = crate::debts::build_lend_event(cfg, event_id, args)?;
+            if maybe_confirm_and_insert(db, cfg, event_id, &payload, &write)? {
+                crate::batch::wrote(format!("Wrote event {event_id} to {}", db_path.display()));
+            }
+        }
+        Command::Borrow(args) => {
+            let write = WriteOptions::of(&args.common);
+            let event_id = Uuid::new_v4();
+            let payload = crate::debts::build_borrow_event(cfg, event_id, args)?;
+            if maybe_confirm_and_insert(db, cfg, event_id, &payload, &write)? {
+                crate::batch::wrote(format!("Wrote event {event_id} to {}", db_path.display()));
+            }
+        }
+        Command::Debts(crate::cli::DebtsArgs {
+            cmd: Some(crate::cli::DebtsCmd::Settle(args)),
+            ..
+        }) => {
+            let write = WriteOptions::of(&args.common);
+            let event_id = Uuid::new_v4();
+            let payload = crate::debts::build_settle_event(db, cfg, event_id, args)?;
+            if maybe_confirm_and_insert(db, cfg, event_id, &payload, &write)? {
+                crate::batch::wrote(format!("Wrote event {event_id} to {}", db_path.display()));
+            }
+        }
+        Command::Debts(args) => {
+            crate::debts::print_debts(db, args.all)?;
+        }
+        Command::Cashcount(args) => {
+            crate::cashcount::handle_cashcount(db, cfg, args)?;
+        }
+        Command::Settle(args) => {
+            crate::settle::handle_settle(db, cfg, args)?;
+        }
+        Command::Buy(args) => {
+            let write = WriteOptions::of(&args.common);
+            let event_id = Uuid::new_v4();
+
+            // In the 2-arg forms a trailing provider token lands in the third positional.
+            let (third, provider_raw) = match (args.commodity, args.provider) {
+                (Some(t), None) if t.starts_with('@') => (None, Some(t)),
+                (third, provider_raw) => (third, provider_raw),
+            };
+            let provider = parse_provider_opt(&provider_raw);
+            let legs_provider = provider.clone();
+            // `--from <account>:<amount>` is a leg even when it is the only one; the buy
+            // itself is built from the first leg's account.
+            let has_suffix = |raw: &String| parse_split_to(raw, "").is_ok();
+            let legs = (args.from.len() > 1 || args.from.iter().any(has_suffix))
+                .then(|| args.from.clone());
+            let from_account = match &legs {
+                Some(legs) => parse_split_to(&legs[0], "")?.0,
+                None => args.from[0].clone(),
+            };
+
+            let (payee, amount, commodity) = resolve_buy_positionals(
+                args.payee,
+                args.payee_or_amount,
+                args.amount_or_commodity,
+                third,
+            )?;
+
+            let mut payload = build_buy_event(
+                db,
+                cfg,
+                event_id,
+                payee,
+                amount,
+                commodity,
+                from_account,
+                args.to_splits,
+                args.charges,
+                provider,
+                args.common,
+            )?;
+            if let Some(legs) = legs {
+                apply_payment_legs(db, cfg, &mut payload, &legs, legs_provider.as_ref())?;
+            }
+            if maybe_confirm_and_insert(db, cfg, event_id, &payload, &write)? {
+                crate::batch::wrote(format!("Wrote event {event_id} to {}", db_path.display()));
+            }
+        }
+        Command::Sell(args) => {
+            let provider = parse_provider_opt(&args.provider);
+            let write = WriteOptions::of(&args.common);
+            let event_id = Uuid::new_v4();
+            let mut payload = build_sell_event(
+                cfg,
+                event_id,
+                args.amount,
+                args.commodity,
+                args.from,
+                args.to,
+                args.to_amount,
+                args.to_commodity,
+                provider,
+                args.common,
+            )?;
+            apply_fee(cfg, &mut payload, &args.fee)?;
+            if maybe_confirm_and_insert(db, cfg, event_id, &payload, &write)? {
+                crate::batch::wrote(format!("Wrote event {event_id} to {}", db_path.display()));
+            }
+        }
+        Command::Q(args) => {
+            let write = WriteOptions::of(&args.common);
+            let event_id = Uuid::new_v4();
+            let payload = build_quick_event(db, cfg, event_id, args.text.join(" "), args.common)?;
+            if maybe_confirm_and_insert(db, cfg, event_id, &payload, &write)? {
+                crate::batch::wrote(format!("Wrote event {event_id} to {}", db_path.display()));
+            }
+        }
+        Command::Paste(args) => {
+            crate::paste::handle_paste(db, cfg, args)?;
+        }
+        Command::Bot(args) => {
+            crate::bot::handle_bot(db, cfg, args)?;
+        }
+        Command::Tag(args) => {
+            let write = WriteOptions::of(&args.common);
+            let event_id = Uuid::new_v4();
+            let payload = build_tag_event(cfg, event_id, args.target, args.set_basis, args.common)?;
+            if maybe_confirm_and_insert(db, cfg, event_id, &payload, &write)? {
+                crate::batch::wrote(format!("Wrote event {event_id} to {}", db_path.display()));
+            }
+        }
+        Command::Balance(args) => {
+            if args.explain && args.output.is_table() {
+                return Err(anyhow!("--explain is only available with text output"));
+            }
+            let events =
+                crate::snapshot::replay_posted(db, args.include_archived, args.include_pending)?;
+            print_balance(db, &events, &args, &cfg.reference_commodity)?;
+            if args.explain {
+                print_reservation_sources(
+                    db,
+                    cfg,
+                    &events,
+                    args.account.as_deref(),
+                    args.month.as_deref(),
+                )?;
+            }
+        }
+        Command::Import(args) => {
+            crate::import::handle_import(db, cfg, args.cmd)?;
+        }
+        Command::Wallet(args) => {
+            let events = crate::snapshot::replay_posted(db, false, false)?;
+            print_wallet(db, cfg, &events, args)?;
+        }
+        Command::Report(crate::cli::ReportArgs {
+            cmd: Some(crate::cli::ReportCmd::Tax(args)),
+            ..
+        }) => {
+            crate::tax::handle_tax_report(db, cfg, args)?;
+        }
+        Command::Report(crate::cli::ReportArgs {
+            cmd: Some(crate::cli::ReportCmd::Tags(args)),
+            ..
+        }) => {
+            crate::tags::handle_tags_report(db, args)?;
+        }
+        Command::Report(args) => {
+            let events =
+                crate::snapshot::replay_posted(db, args.include_archived, args.include_pending)?;
+            let filtered = filter_events(cfg, &events, &args)?;
+            if let Some(name) = &args.save_baseline {
+                save_report_baseline(db, name, &args, &filtered)?;
+            } else if let Some(name) = &args.diff_baseline {
+                diff_report_baseline(db, name, &args, &filtered)?;
+            } else if args.rates {
+                print_rate_report(db, &filtered, &args.output)?;
+            } else if let Some(program) = &args.renderer {
+                if args.output.is_table() {
+                    return Err(anyhow!(
+                        "--renderer replaces --output; the renderer decides the format"
+                    ));
                 }
-                Command::Task(_) | Command::Workflow(_) => {
-                    eprintln!("This command is a stub for later milestones.");
+                let report = crate::renderer::report_json(
+                    cfg,
+                    &args,
+                    &filtered,
+                    &events,
+                    &report_totals(&filtered, &events, args.account.as_deref()),
+                );
+                crate::renderer::render(program, &report, args.output.out.as_deref())?;
+            } else {
+                if args.totals && args.output.is_table() {
+                    return Err(anyhow!("--totals is only available with text output"));
                 }
-                Command::Ws(_) | Command::Project(_) | Command::Upgrade(_) | Command::Login(_) => {
-                    unreachable!()
+                print_report(&filtered, &events, &args.output)?;
+                if args.totals {
+                    print_report_totals(&filtered, &events, args.account.as_deref());
                 }
             }
-
-            Ok(())
+        }
+        Command::Batch(args) => {
+            crate::batch::handle_batch(args, db, db_path, paths, globals, cfg, cfg_path)?;
+        }
+        Command::Template(args) => {
+            crate::templates::handle_template(db, args.cmd)?;
+        }
+        Command::External(argv) => {
+            return Err(anyhow!(
+                "Template '{}' was not expanded before running; this is a bug",
+                argv.first().map(String::as_str).unwrap_or_default()
+            ));
+        }
+        Command::Export(args) => match args.cmd {
+            crate::cli::ExportCmd::Analytics { out, full } => {
+                crate::analytics::handle_analytics(db, &out, full)?;
+            }
+            crate::cli::ExportCmd::Events { range, format, out } => {
+                crate::backup::export_events(db, range.as_deref(), format, out.as_deref())?;
+            }
+        },
+        Command::Debug(args) => {
+            crate::debug::handle_debug(db, cfg, args.cmd)?;
+        }
+        Command::Share(args) => {
+            crate::share::handle_share(db, args.cmd)?;
+        }
+        Command::Register(args) => {
+            let events = crate::snapshot::replay_posted(db, args.include_archived, false)?;
+            print_register(&events, &args)?;
+        }
+        Command::Rate(args) => {
+            handle_rate(db, args.command)?;
+        }
+        Command::Commodity(args) => {
+            crate::commodities::handle_commodity(db, args.cmd)?;
+        }
+        Command::Provider(args) => {
+            crate::providers::handle_provider(db, args.cmd)?;
+        }
+        Command::Show(args) => {
+            crate::show::handle_show(db, cfg, args)?;
+        }
+        Command::Event(args) => {
+            crate::event::handle_event(db, cfg, args)?;
+        }
+        Command::Undo(args) => {
+            crate::event::undo(db, cfg, args)?;
+        }
+        Command::Pending(args) => {
+            crate::pending::handle_pending(db, args)?;
+        }
+        Command::History(args) => {
+            crate::history::handle_history(db, cfg, args)?;
+        }
+        Command::Reprice(args) => {
+            crate::reprice::handle_reprice(db, cfg, args)?;
+        }
+        Command::Budget(args) => {
+            handle_budget(db, cfg, args.cmd)?;
+        }
+        Command::Piggy(args) => {
+            handle_piggy(db, cfg, args.cmd)?;
+        }
+        Command::Payoff(args) => {
+            crate::payoff::handle_payoff(db, cfg, args)?;
+        }
+        Command::Approve(args) => {
+            crate::approve::handle_approve(db, cfg, args)?;
+        }
+        Command::Limit(args) => {
+            crate::limits::handle_limit(db, args.cmd)?;
+        }
+        Command::Notify(args) => {
+            crate::notify::handle_notify(db, cfg, args)?;
+        }
+        Command::Init(args) => {
+            crate::init::handle_init(db, cfg, cfg_path, args)?;
+        }
+        Command::Snapshot(args) => {
+            crate::snapshot::handle_snapshot(db, cfg, args.cmd)?;
+        }
+        #[cfg(feature = "bench")]
+        Command::BenchGen(args) => {
+            crate::benchgen::handle_bench_gen(db, cfg, args)?;
+        }
+        Command::Sync(args) => {
+            crate::sync::handle_sync(db, args, cfg, cfg_path)?;
+        }
+        Command::Asset(args) => {
+            handle_asset(db, cfg, args.cmd)?;
+        }
+        Command::Task(args) => {
+            crate::tasks::handle_task(db, cfg, args.cmd)?;
+        }
+        Command::Workflow(_) => {
+            eprintln!("This command is a stub for later milestones.");
+        }
+        Command::Ws(_)
+        | Command::Profile(_)
+        | Command::Config(_)
+        | Command::Rules(_)
+        | Command::Token(_)
+        | Command::Help(_)
+        | Command::GenerateMan(_)
+        | Command::Project(_)
+        | Command::Upgrade(_)
+        | Command::Login(_) => {
+            unreachable!()
         }
     }
+
+    Ok(())
 }
 
 fn normalize_provider(raw: &str) -> String {
@@ -226,7 +635,7 @@ fn current_month_yyyy_mm(now: DateTime<Utc>) -> String {
     format!("{:04}-{:02}", now.year(), now.month())
 }
 
-fn handle_budget(db: &Db, cmd: BudgetCmd) -> Result<()> {
+fn handle_budget(db: &Db, cfg: &AppConfig, cmd: BudgetCmd) -> Result<()> {
     match cmd {
         BudgetCmd::Create {
             name,
@@ -235,10 +644,11 @@ fn handle_budget(db: &Db, cmd: BudgetCmd) -> Result<()> {
             month,
             category,
             account,
+            project,
             extra,
         } => {
             if let Some(m) = month.as_deref() {
-                let _ = parse_month_range(m)?;
+                let _ = crate::period::month_range(m)?;
             }
 
             let amount = parse_decimal(amount, "amount")?;
@@ -257,7 +667,10 @@ fn handle_budget(db: &Db, cmd: BudgetCmd) -> Result<()> {
                 provider,
                 auto_reserve_from: None,
                 auto_reserve_until_amount: None,
+                device_id: Some(cfg.device_id),
+                project,
                 created_at: now_utc(),
+                updated_at: None,
             };
 
             db.insert_budget(&budget)?;
@@ -266,6 +679,9 @@ fn handle_budget(db: &Db, cmd: BudgetCmd) -> Result<()> {
         }
         BudgetCmd::Update {
             name,
+            sweep_to,
+            start,
+            clear_sweep,
             auto_reserve_from,
             until,
             clear_auto_reserve,
@@ -273,6 +689,65 @@ fn handle_budget(db: &Db, cmd: BudgetCmd) -> Result<()> {
             let Some(budget) = db.get_budget_by_name(&name)? else {
                 return Err(anyhow!("No such budget: '{name}'"));
             };
+            let sweep_task_id = format!("budget-sweep-{}", crate::config::workspace_slug(&name));
+
+            if clear_sweep {
+                if db.set_task_enabled(&sweep_task_id, false)? == 0 {
+                    return Err(anyhow!("Budget '{name}' has no sweep to clear"));
+                }
+                println!("Cleared the month-end sweep for budget '{name}'.");
+                return Ok(());
+            }
+
+            if let Some(piggy_name) = sweep_to {
+                let Some(piggy) = db.get_piggy_by_name(&piggy_name)? else {
+                    return Err(anyhow!("No such piggy: '{piggy_name}'"));
+                };
+                if !piggy.commodity.eq_ignore_ascii_case(&budget.commodity) {
+                    return Err(anyhow!(
+                        "Piggy '{}' is in {} but budget '{}' is in {}",
+                        piggy.name,
+                        piggy.commodity,
+                        budget.name,
+                        budget.commodity
+                    ));
+                }
+                let spec = crate::tasks::TaskSpec::BudgetSweep {
+                    budget: budget.name.clone(),
+                    piggy: piggy.name.clone(),
+                };
+                let task = match db.get_task(&sweep_task_id)? {
+                    Some(_) => {
+                        db.set_task_spec(&sweep_task_id, &serde_json::to_value(&spec)?)?;
+                        db.set_task_enabled(&sweep_task_id, true)?;
+                        db.get_task(&sweep_task_id)?
+                            .ok_or_else(|| anyhow!("Task '{sweep_task_id}' disappeared"))?
+                    }
+                    None => {
+                        let anchor_at = match start {
+                            Some(raw) => parse_rfc3339_or_now(Some(&raw))?,
+                            None => {
+                                crate::period::month_range(&current_month_yyyy_mm(now_utc()))?.1
+                            }
+                        };
+                        crate::tasks::create_task(
+                            db,
+                            &sweep_task_id,
+                            crate::tasks::Schedule::Monthly,
+                            anchor_at,
+                            &spec,
+                        )?
+                    }
+                };
+                let next = crate::tasks::next_occurrence(&task)?
+                    .map(|t| t.to_rfc3339())
+                    .unwrap_or_else(|| "(done)".to_string());
+                println!(
+                    "Updated budget '{}': sweep the month-end remainder into piggy '{}' (task '{}', next run {}).",
+                    budget.name, piggy.name, sweep_task_id, next
+                );
+                return Ok(());
+            }
 
             if clear_auto_reserve {
                 let changed = db.set_budget_auto_reserve(&name, None, None)?;
@@ -341,9 +816,14 @@ fn handle_budget(db: &Db, cmd: BudgetCmd) -> Result<()> {
 
             Ok(())
         }
-        BudgetCmd::Report { month } => {
+        BudgetCmd::Report {
+            month,
+            rollup,
+            unbudgeted,
+            output,
+        } => {
             let month = month.unwrap_or_else(|| current_month_yyyy_mm(now_utc()));
-            let (start, end) = parse_month_range(&month)?;
+            let (start, end) = crate::period::month_range(&month)?;
 
             let budgets = db.list_budgets()?;
             let mut budgets: Vec<_> = budgets
@@ -355,38 +835,101 @@ fn handle_budget(db: &Db, cmd: BudgetCmd) -> Result<()> {
                 .collect();
             budgets.sort_by(|a, b| a.name.cmp(&b.name));
 
-            if budgets.is_empty() {
+            if budgets.is_empty() && !unbudgeted && !output.is_table() {
                 println!("(no budgets)");
                 return Ok(());
             }
 
-            let events = db.list_events()?;
-            println!("month\tname\tcommodity\tbudget\tactual\tremaining");
-            for b in budgets {
-                let actual = compute_budget_actual(&events, start, end, &b);
-                let remaining = b.amount - actual;
-                println!(
-                    "{}\t{}\t{}\t{}\t{}\t{}",
-                    month, b.name, b.commodity, b.amount, actual, remaining
-                );
+            let events = crate::snapshot::replay_posted(db, false, false)?;
+            let headers = [
+                "month",
+                "name",
+                "commodity",
+                "budget",
+                "actual",
+                "remaining",
+            ];
+            let mut rows: Vec<Vec<String>> = budgets
+                .iter()
+                .map(|b| {
+                    // A spend that can't be converted leaves its row unknown, not the report.
+                    let (actual, remaining) =
+                        match compute_budget_actual(db, &events, start, end, b, rollup) {
+                            Ok(actual) => (actual.to_string(), (b.amount - actual).to_string()),
+                            Err(e) => {
+                                eprintln!("Budget '{}': {e:#}", b.name);
+                                ("?".to_string(), "?".to_string())
+                            }
+                        };
+                    vec![
+                        month.clone(),
+                        b.name.clone(),
+                        b.commodity.clone(),
+                        b.amount.to_string(),
+                        actual,
+                        remaining,
+                    ]
+                })
+                .collect();
+            let gaps = if unbudgeted {
+                unbudgeted_spend(&events, start, end, &budgets, rollup)
+            } else {
+                Vec::new()
+            };
+            if output.is_table() {
+                // Unbudgeted categories become rows without a budget or remainder.
+                rows.extend(gaps.into_iter().map(|(category, commodity, spent)| {
+                    vec![
+                        month.clone(),
+                        category,
+                        commodity,
+                        String::new(),
+                        spent.to_string(),
+                        String::new(),
+                    ]
+                }));
+                return crate::output::write_table(&output, &headers, &rows);
+            }
+            output.require_table_for_out()?;
+
+            if !rows.is_empty() {
+                println!("{}", headers.join("\t"));
+            }
+            for row in rows {
+                println!("{}", row.join("\t"));
+            }
+            for (category, commodity, spent) in gaps {
+                println!("unbudgeted\t{category}\t{commodity}\t{spent}");
+            }
+            if let Some(gap) = crate::plan::gap_for_month(db, &month)? {
+                println!("plan\t{gap}");
             }
             Ok(())
         }
+        BudgetCmd::Plan {
+            month,
+            income,
+            status,
+        } => crate::plan::handle_plan(db, cfg, month, income, status),
     }
 }
 
-fn handle_piggy(db: &Db, cmd: PiggyCmd) -> Result<()> {
+fn handle_piggy(db: &Db, cfg: &AppConfig, cmd: PiggyCmd) -> Result<()> {
     match cmd {
         PiggyCmd::Create {
             name,
             amount,
             commodity,
             from,
+            priority,
+            group,
+            weight,
         } => {
             let target_amount = parse_decimal(amount, "amount")?;
             if target_amount <= Decimal::ZERO {
                 return Err(anyhow!("Piggy target amount must be > 0"));
             }
+            let weight = weight.map(parse_piggy_weight).transpose()?;
 
             let piggy = crate::db::StoredPiggy {
                 id: Uuid::new_v4(),
@@ -394,7 +937,12 @@ fn handle_piggy(db: &Db, cmd: PiggyCmd) -> Result<()> {
                 target_amount,
                 commodity: commodity.to_ascii_uppercase(),
                 from_account: from,
+                priority,
+                group,
+                weight: weight.unwrap_or(Decimal::ONE),
+                device_id: Some(cfg.device_id),
                 created_at: now_utc(),
+                updated_at: None,
             };
 
             db.insert_piggy(&piggy)
@@ -412,7 +960,7 @@ fn handle_piggy(db: &Db, cmd: PiggyCmd) -> Result<()> {
                 return Ok(());
             }
 
-            println!("name\tcommodity\ttarget\tfunded\tpercent\tfrom");
+            println!("name\tcommodity\ttarget\tfunded\tpercent\tfrom\tpriority\tgroup");
             for p in piggies {
                 let funded = db.piggy_funded_total(p.id)?;
                 let funded_capped = funded.min(p.target_amount);
@@ -422,21 +970,33 @@ fn handle_piggy(db: &Db, cmd: PiggyCmd) -> Result<()> {
                     Decimal::ZERO
                 };
                 println!(
-                    "{}\t{}\t{}\t{}\t{}\t{}",
+                    "{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}",
                     p.name,
                     p.commodity,
                     p.target_amount,
                     funded,
                     percent.round_dp(2),
-                    p.from_account
+                    p.from_account,
+                    p.priority
+                        .map(|n| n.to_string())
+                        .unwrap_or_else(|| "-".to_string()),
+                    p.group.as_deref().unwrap_or("-")
                 );
             }
             Ok(())
         }
-        PiggyCmd::Status { name } => {
+        PiggyCmd::Status {
+            name,
+            pace_months,
+            as_of,
+        } => {
             let Some(piggy) = db.get_piggy_by_name(&name)? else {
                 return Err(anyhow!("No such piggy: '{name}'"));
             };
+            let as_of = parse_rfc3339_or_now(as_of.as_deref())?;
+            let window_start = as_of
+                .checked_sub_months(chrono::Months::new(pace_months))
+                .ok_or_else(|| anyhow!("--pace-months is too large"))?;
 
             let funded = db.piggy_funded_total(piggy.id)?;
             let funded_capped = funded.min(piggy.target_amount);
@@ -459,6 +1019,33 @@ fn handle_piggy(db: &Db, cmd: PiggyCmd) -> Result<()> {
             );
             println!("remaining\t{}\t{}", piggy.commodity, remaining);
             println!("from\t{}", piggy.from_account);
+
+            if remaining.is_zero() {
+                println!("Goal reached.");
+                return Ok(());
+            }
+            let recent: Decimal = db
+                .list_piggy_funds(piggy.id)?
+                .iter()
+                .filter(|f| f.effective_at > window_start && f.effective_at <= as_of)
+                .map(|f| f.amount)
+                .sum();
+            let pace = recent / Decimal::from(pace_months);
+            if pace <= Decimal::ZERO {
+                println!("ETA unknown (no funding in the last {pace_months} month(s))");
+                return Ok(());
+            }
+            let months_needed = (remaining / pace).ceil().to_u32().unwrap_or(u32::MAX);
+            match as_of.checked_add_months(chrono::Months::new(months_needed)) {
+                Some(eta) => println!(
+                    "ETA {} at current pace ({} {}/month over the last {} month(s))",
+                    eta.format("%b %Y"),
+                    pace.round_dp(2),
+                    piggy.commodity,
+                    pace_months
+                ),
+                None => println!("ETA beyond the calendar at current pace"),
+            }
             Ok(())
         }
         PiggyCmd::Fund {
@@ -494,6 +1081,7 @@ fn handle_piggy(db: &Db, cmd: PiggyCmd) -> Result<()> {
                 piggy_id: piggy.id,
                 amount,
                 effective_at,
+                device_id: Some(cfg.device_id),
                 created_at: now_utc(),
             };
             db.insert_piggy_fund(&fund)?;
@@ -503,84 +1091,567 @@ fn handle_piggy(db: &Db, cmd: PiggyCmd) -> Result<()> {
             );
             Ok(())
         }
-    }
-}
+        PiggyCmd::Update {
+            name,
+            priority,
+            group,
+            weight,
+            fund_surplus_over,
+            start,
+            clear_fund_surplus,
+        } => {
+            let Some(piggy) = db.get_piggy_by_name(&name)? else {
+                return Err(anyhow!("No such piggy: '{name}'"));
+            };
+            let task_id = format!("piggy-surplus-{}", crate::config::workspace_slug(&name));
 
-fn parse_budget_provider(extra: &[String]) -> Result<Option<String>> {
-    let mut provider: Option<String> = None;
-    for token in extra {
-        if let Some(p) = crate::domain::parse_provider_token(token) {
-            if provider.is_some() {
+            let weight = weight.map(parse_piggy_weight).transpose()?;
+            let allocation_changed = priority.is_some() || group.is_some() || weight.is_some();
+            if allocation_changed {
+                db.set_piggy_allocation(&name, priority, group.as_deref(), weight)?;
+                let updated = db.get_piggy_by_name(&name)?.unwrap_or(piggy.clone());
+                println!(
+                    "Updated piggy '{}': priority {}, group {}, weight {}.",
+                    updated.name,
+                    updated
+                        .priority
+                        .map(|n| n.to_string())
+                        .unwrap_or_else(|| "(none)".to_string()),
+                    updated.group.as_deref().unwrap_or("(none)"),
+                    updated.weight
+                );
+            }
+
+            if clear_fund_surplus {
+                if db.set_task_enabled(&task_id, false)? == 0 {
+                    return Err(anyhow!("Piggy '{name}' has no surplus sweep to clear"));
+                }
+                println!("Cleared surplus sweep for piggy '{name}'.");
+                return Ok(());
+            }
+
+            let Some(parts) = fund_surplus_over else {
+                if allocation_changed {
+                    return Ok(());
+                }
                 return Err(anyhow!(
-                    "budget create accepts at most one provider token (e.g. @binance)"
+                    "Nothing to update. Use --priority/--group/--weight, --fund-surplus-over <amount> <commodity> or --clear-fund-surplus"
                 ));
-            }
-            if p.override_rate.is_some() {
+            };
+            let floor = parse_decimal(parts[0].clone(), "surplus floor")?;
+            let comm = parts[1].to_ascii_uppercase();
+            if comm != piggy.commodity {
                 return Err(anyhow!(
-                    "budget provider token must not include an override rate (use @provider, not @provider:rate)"
+                    "--fund-surplus-over commodity must match piggy commodity ({} != {})",
+                    comm,
+                    piggy.commodity
                 ));
             }
-            provider = Some(normalize_provider(&p.provider));
-        } else {
-            return Err(anyhow!(
-                "Unrecognized extra argument for budget create: {token}"
-            ));
-        }
-    }
-    Ok(provider)
-}
-
-fn compute_budget_actual(
-    events: &[StoredEvent],
-    start: DateTime<Utc>,
-    end: DateTime<Utc>,
-    budget: &crate::db::StoredBudget,
-) -> Decimal {
-    let mut total = Decimal::ZERO;
-    let budget_comm = budget.commodity.to_ascii_uppercase();
 
-    for e in events {
-        if e.action != "buy" {
-            continue;
-        }
-        if e.effective_at < start || e.effective_at > end {
-            continue;
+            let spec = crate::tasks::TaskSpec::PiggySurplus {
+                piggy: piggy.name.clone(),
+                floor,
+                commodity: comm.clone(),
+            };
+            let task = match db.get_task(&task_id)? {
+                Some(_) => {
+                    db.set_task_spec(&task_id, &serde_json::to_value(&spec)?)?;
+                    db.get_task(&task_id)?
+                        .ok_or_else(|| anyhow!("Task '{task_id}' disappeared"))?
+                }
+                None => {
+                    let anchor_at = match start {
+                        Some(raw) => parse_rfc3339_or_now(Some(&raw))?,
+                        None => crate::period::month_range(&current_month_yyyy_mm(now_utc()))?.1,
+                    };
+                    crate::tasks::create_task(
+                        db,
+                        &task_id,
+                        crate::tasks::Schedule::Monthly,
+                        anchor_at,
+                        &spec,
+                    )?
+                }
+            };
+            let next = crate::tasks::next_occurrence(&task)?
+                .map(|t| t.to_rfc3339())
+                .unwrap_or_else(|| "(done)".to_string());
+            println!(
+                "Updated piggy '{}': sweep {} above {} {} monthly (task '{}', next run {}).",
+                piggy.name, piggy.from_account, floor, comm, task_id, next
+            );
+            Ok(())
         }
-        if let Some(cat) = &budget.category {
-            if e.payload.category.as_deref() != Some(cat.as_str()) {
-                continue;
+        PiggyCmd::Allocate {
+            amount,
+            commodity,
+            group,
+            effective_at,
+        } => {
+            let amount = parse_decimal(amount, "amount")?;
+            if amount <= Decimal::ZERO {
+                return Err(anyhow!("Allocation amount must be > 0"));
             }
-        }
+            let commodity = commodity.to_ascii_uppercase();
+            let effective_at = parse_rfc3339_or_now(effective_at.as_deref())?;
 
-        for p in &e.payload.postings {
-            if p.amount >= Decimal::ZERO {
-                continue;
+            let mut candidates = Vec::new();
+            for p in db.list_piggies()? {
+                if p.commodity != commodity {
+                    continue;
+                }
+                if let Some(g) = &group
+                    && p.group.as_deref() != Some(g.as_str())
+                {
+                    continue;
+                }
+                let room = (p.target_amount - db.piggy_funded_total(p.id)?).max(Decimal::ZERO);
+                if room > Decimal::ZERO {
+                    candidates.push((p, room));
+                }
             }
-            if p.commodity.to_ascii_uppercase() != budget_comm {
-                continue;
+            if candidates.is_empty() {
+                let scope = group
+                    .map(|g| format!(" in group '{g}'"))
+                    .unwrap_or_default();
+                return Err(anyhow!("No {commodity} piggy{scope} has room left to fund"));
             }
-            if let Some(acct) = &budget.account {
-                if !p.account.starts_with(acct) {
+
+            let shares = allocate_by_priority(
+                amount,
+                &candidates
+                    .iter()
+                    .map(|(p, room)| (p.priority, p.weight, *room))
+                    .collect::<Vec<_>>(),
+            );
+            let mut allocated = Decimal::ZERO;
+            for ((piggy, _), share) in candidates.iter().zip(shares) {
+                if share <= Decimal::ZERO {
                     continue;
                 }
+                db.insert_piggy_fund(&crate::db::StoredPiggyFund {
+                    id: Uuid::new_v4(),
+                    piggy_id: piggy.id,
+                    amount: share,
+                    effective_at,
+                    device_id: Some(cfg.device_id),
+                    created_at: now_utc(),
+                })?;
+                allocated += share;
+                println!("{}\t{}\t{}", piggy.name, commodity, share);
             }
-            total += -p.amount;
+            println!("Allocated {allocated} of {amount} {commodity}.");
+            let left = amount - allocated;
+            if left > Decimal::ZERO {
+                println!("Unallocated: {left} {commodity} (every matching piggy is full).");
+            }
+            Ok(())
         }
     }
+}
 
-    total
+fn parse_piggy_weight(raw: String) -> Result<Decimal> {
+    let weight = parse_decimal(raw, "weight")?;
+    if weight <= Decimal::ZERO {
+        return Err(anyhow!("Piggy weight must be > 0"));
+    }
+    Ok(weight)
 }
 
-fn compute_budget_funded(
-    events: &[StoredEvent],
+/// Splits `amount` across piggies given as `(priority, weight, room)`.
+///
+/// Priorities are filled in order (1 first, unset last). Within a priority the amount is
+/// shared by weight; piggies whose share would exceed their room are filled and the rest
+/// is re-shared among the others. Shares are rounded down to cents, with the remainder
+/// going to the last piggy in the priority.
+fn allocate_by_priority(
+    amount: Decimal,
+    piggies: &[(Option<i64>, Decimal, Decimal)],
+) -> Vec<Decimal> {
+    let mut shares = vec![Decimal::ZERO; piggies.len()];
+    let mut priorities: Vec<Option<i64>> = piggies.iter().map(|p| p.0).collect();
+    priorities.sort_by_key(|p| (p.is_none(), *p));
+    priorities.dedup();
+
+    let mut left = amount;
+    for priority in priorities {
+        let mut open: Vec<usize> = (0..piggies.len())
+            .filter(|&i| piggies[i].0 == priority)
+            .collect();
+        while left > Decimal::ZERO && !open.is_empty() {
+            let total_weight: Decimal = open.iter().map(|&i| piggies[i].1).sum();
+            let (full, partial): (Vec<usize>, Vec<usize>) = open
+                .iter()
+                .partition(|&&i| left * piggies[i].1 / total_weight >= piggies[i].2);
+            if !full.is_empty() {
+                for i in full {
+                    shares[i] = piggies[i].2;
+                    left -= piggies[i].2;
+                }
+                open = partial;
+                continue;
+            }
+
+            let mut given = Decimal::ZERO;
+            for (n, &i) in open.iter().enumerate() {
+                let share = if n + 1 == open.len() {
+                    (left - given).min(piggies[i].2)
+                } else {
+                    (left * piggies[i].1 / total_weight)
+                        .round_dp_with_strategy(2, rust_decimal::RoundingStrategy::ToZero)
+                };
+                shares[i] = share;
+                given += share;
+            }
+            left -= given;
+            break;
+        }
+    }
+    shares
+}
+
+fn handle_asset(db: &Db, cfg: &AppConfig, cmd: AssetCmd) -> Result<()> {
+    match cmd {
+        AssetCmd::Depreciate {
+            account,
+            method,
+            years,
+            cost,
+            expense_account,
+            start,
+            id,
+        } => {
+            let method_name = match method {
+                crate::cli::DepreciationMethod::StraightLine => "straight-line",
+            };
+            if years == 0 {
+                return Err(anyhow!("--years must be > 0"));
+            }
+
+            let events = crate::snapshot::replay_events(db, false)?;
+            let (cost, commodity) = match cost {
+                Some(parts) => {
+                    if parts.len() != 2 {
+                        return Err(anyhow!("--cost expects: <amount> <commodity>"));
+                    }
+                    (
+                        parse_decimal(parts[0].clone(), "cost")?,
+                        parts[1].to_ascii_uppercase(),
+                    )
+                }
+                None => resolve_asset_cost(&events, &account)?,
+            };
+            if cost <= Decimal::ZERO {
+                return Err(anyhow!("Depreciable cost must be > 0"));
+            }
+
+            let anchor_at = match start {
+                Some(raw) => parse_rfc3339_or_now(Some(&raw))?,
+                None => crate::period::month_range(&current_month_yyyy_mm(now_utc()))?.1,
+            };
+            let periods = years * 12;
+            let task_id = id.unwrap_or_else(|| {
+                format!("depreciation-{}", crate::config::workspace_slug(&account))
+            });
+
+            let spec = crate::tasks::TaskSpec::Depreciation {
+                account: account.clone(),
+                expense_account,
+                commodity: commodity.clone(),
+                cost,
+                periods,
+                project: cfg.current_project.clone(),
+            };
+            crate::tasks::create_task(
+                db,
+                &task_id,
+                crate::tasks::Schedule::Monthly,
+                anchor_at,
+                &spec,
+            )?;
+
+            println!(
+                "Scheduled {} depreciation for {}: {} {} over {} months starting {} (task {}).",
+                method_name,
+                account,
+                cost,
+                commodity,
+                periods,
+                anchor_at.to_rfc3339(),
+                task_id
+            );
+            println!("Run due periods with: bankero task run {task_id}");
+            Ok(())
+        }
+    }
+}
+
+/// Depreciable cost of an asset: latest fixed basis from `tag --set-basis`, else its balance.
+fn resolve_asset_cost(events: &[StoredEvent], account: &str) -> Result<(Decimal, String)> {
+    let latest_basis = events
+        .iter()
+        .filter(|e| e.action == "tag")
+        .filter(|e| e.payload.metadata.get("target").and_then(|t| t.as_str()) == Some(account))
+        .filter_map(|e| match &e.payload.basis {
+            Some(BasisContext::Fixed { amount, commodity }) => {
+                Some((*amount, commodity.to_ascii_uppercase()))
+            }
+            _ => None,
+        })
+        .next_back();
+    if let Some(basis) = latest_basis {
+        return Ok(basis);
+    }
+
+    let mut by_commodity: BTreeMap<String, Decimal> = BTreeMap::new();
+    for e in events {
+        for p in &e.payload.postings {
+            if p.account == account {
+                *by_commodity
+                    .entry(p.commodity.clone())
+                    .or_insert(Decimal::ZERO) += p.amount;
+            }
+        }
+    }
+    let held: Vec<_> = by_commodity
+        .into_iter()
+        .filter(|(_, amt)| *amt > Decimal::ZERO)
+        .collect();
+    match held.as_slice() {
+        [(commodity, amount)] => Ok((*amount, commodity.clone())),
+        [] => Err(anyhow!(
+            "Cannot determine cost for {account}: no basis and no positive balance. Pass --cost <amount> <commodity>"
+        )),
+        _ => Err(anyhow!(
+            "Cannot determine cost for {account}: it holds several commodities. Pass --cost <amount> <commodity>"
+        )),
+    }
+}
+
+fn parse_budget_provider(extra: &[String]) -> Result<Option<String>> {
+    let mut provider: Option<String> = None;
+    for token in extra {
+        if let Some(p) = crate::domain::parse_provider_token(token) {
+            if provider.is_some() {
+                return Err(anyhow!(
+                    "budget create accepts at most one provider token (e.g. @binance)"
+                ));
+            }
+            if p.override_rate.is_some() {
+                return Err(anyhow!(
+                    "budget provider token must not include an override rate (use @provider, not @provider:rate)"
+                ));
+            }
+            provider = Some(normalize_provider(&p.provider));
+        } else {
+            return Err(anyhow!(
+                "Unrecognized extra argument for budget create: {token}"
+            ));
+        }
+    }
+    Ok(provider)
+}
+
+/// Spend counted against a budget in `[start, end]`, in the budget commodity.
+///
+/// Category budgets (and unscoped ones) count `buy` outflows. A budget scoped only to an
+/// account counts every event's net outflow from that account (buys, moves, sells, ...),
+/// so transfers between its own subaccounts cancel out.
+///
+/// A budget with a project only counts events recorded under that project.
+///
+/// Postings in other commodities count only when the budget has a provider; they are
+/// converted at the event's effective time.
+fn compute_budget_actual(
+    db: &Db,
+    events: &[StoredEvent],
     start: DateTime<Utc>,
     end: DateTime<Utc>,
-    to_account_prefix: &str,
+    budget: &crate::db::StoredBudget,
+    rollup: bool,
+) -> Result<Decimal> {
+    let mut total = Decimal::ZERO;
+    let voided = crate::event::voided(events);
+
+    for e in events {
+        if e.effective_at < start || e.effective_at > end {
+            continue;
+        }
+        if e.action == crate::event::VOID_ACTION || voided.contains_key(&e.event_id) {
+            continue;
+        }
+        if budget
+            .project
+            .as_deref()
+            .is_some_and(|project| e.payload.project != project)
+        {
+            continue;
+        }
+
+        if budget.category.is_none()
+            && let Some(acct) = &budget.account
+        {
+            let mut net_by_commodity: BTreeMap<String, Decimal> = BTreeMap::new();
+            for p in &e.payload.postings {
+                if p.account.starts_with(acct.as_str()) {
+                    *net_by_commodity
+                        .entry(p.commodity.to_ascii_uppercase())
+                        .or_insert(Decimal::ZERO) += p.amount;
+                }
+            }
+            let mut net = Decimal::ZERO;
+            for (comm, amount) in net_by_commodity {
+                if let Some(value) = budget_value(db, budget, &comm, amount, e.effective_at)? {
+                    net += value;
+                }
+            }
+            if net < Decimal::ZERO {
+                total += -net;
+            }
+            continue;
+        }
+
+        let refund = e.action == crate::refund::REFUND_ACTION;
+        if e.action != "buy" && !refund {
+            continue;
+        }
+        if let Some(cat) = &budget.category {
+            let event_cat = e.payload.category.as_deref().unwrap_or_default();
+            let matches = if rollup {
+                event_cat == cat || event_cat.starts_with(&format!("{cat}:"))
+            } else {
+                event_cat == cat
+            };
+            if !matches {
+                continue;
+            }
+        }
+
+        for p in &e.payload.postings {
+            // A buy spends through its negative legs; a refund gives back through its
+            // positive ones, which lowers the actual.
+            let spends = if refund {
+                p.amount > Decimal::ZERO
+            } else {
+                p.amount < Decimal::ZERO
+            };
+            if !spends {
+                continue;
+            }
+            if let Some(acct) = &budget.account
+                && !p.account.starts_with(acct)
+            {
+                continue;
+            }
+            if let Some(value) = budget_value(db, budget, &p.commodity, p.amount, e.effective_at)? {
+                total += -value;
+            }
+        }
+    }
+
+    Ok(total)
+}
+
+/// Spending per (category, commodity) between `start` and `end` that no category budget
+/// in `budgets` covers, by commodity and largest first within each. Buys without a
+/// category count as "(uncategorized)"; refunds lower their category like they lower a
+/// budget's actual.
+fn unbudgeted_spend(
+    events: &[StoredEvent],
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    budgets: &[crate::db::StoredBudget],
+    rollup: bool,
+) -> Vec<(String, String, Decimal)> {
+    let voided = crate::event::voided(events);
+    let covered = |category: &str, project: &str| {
+        budgets.iter().any(|b| {
+            b.project.as_deref().is_none_or(|p| p == project)
+                && b.category.as_deref().is_some_and(|cat| {
+                    category == cat || (rollup && category.starts_with(&format!("{cat}:")))
+                })
+        })
+    };
+
+    let mut spent: BTreeMap<(String, String), Decimal> = BTreeMap::new();
+    for e in events {
+        if e.effective_at < start || e.effective_at > end || voided.contains_key(&e.event_id) {
+            continue;
+        }
+        let refund = e.action == crate::refund::REFUND_ACTION;
+        if e.action != "buy" && !refund {
+            continue;
+        }
+        let category = e
+            .payload
+            .category
+            .clone()
+            .unwrap_or_else(|| "(uncategorized)".to_string());
+        if covered(&category, &e.payload.project) {
+            continue;
+        }
+        // Only the legs that leave your books count: what was bought, in what it cost.
+        // The paying legs and equity:conversion would count a mixed-currency buy twice.
+        for p in &e.payload.postings {
+            if is_own_account(&p.account) || p.account.split(':').next() == Some("equity") {
+                continue;
+            }
+            *spent
+                .entry((category.clone(), p.commodity.to_ascii_uppercase()))
+                .or_insert(Decimal::ZERO) += p.amount;
+        }
+    }
+
+    let mut out: Vec<_> = spent
+        .into_iter()
+        .filter(|(_, amount)| *amount > Decimal::ZERO)
+        .map(|((category, commodity), amount)| (category, commodity, amount.normalize()))
+        .collect();
+    out.sort_by(|a, b| {
+        a.1.cmp(&b.1)
+            .then_with(|| b.2.cmp(&a.2))
+            .then_with(|| a.0.cmp(&b.0))
+    });
+    out
+}
+
+/// `amount` of `commodity` expressed in the budget commodity, converted with the budget's
+/// provider when they differ. `None` when they differ and the budget has no provider.
+fn budget_value(
+    db: &Db,
+    budget: &crate::db::StoredBudget,
     commodity: &str,
+    amount: Decimal,
+    at: DateTime<Utc>,
+) -> Result<Option<Decimal>> {
+    let budget_comm = budget.commodity.to_ascii_uppercase();
+    let commodity = commodity.to_ascii_uppercase();
+    if commodity == budget_comm {
+        return Ok(Some(amount));
+    }
+    let Some(provider) = &budget.provider else {
+        return Ok(None);
+    };
+    let (value, ..) = resolve_and_convert(db, provider, &commodity, &budget_comm, at, amount)
+        .with_context(|| {
+            format!(
+                "Budget '{}' converts {} to {} with @{}",
+                budget.name, commodity, budget_comm, provider
+            )
+        })?;
+    Ok(Some(value))
+}
+
+fn compute_budget_funded(
+    db: &Db,
+    events: &[StoredEvent],
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    budget: &crate::db::StoredBudget,
+    to_account_prefix: &str,
     from_account_prefix: &str,
-) -> Decimal {
+) -> Result<Decimal> {
     let mut total = Decimal::ZERO;
-    let comm = commodity.to_ascii_uppercase();
 
     for e in events {
         if e.effective_at < start || e.effective_at > end {
@@ -593,13 +1664,12 @@ fn compute_budget_funded(
             if p.amount <= Decimal::ZERO {
                 continue;
             }
-            if p.commodity.to_ascii_uppercase() != comm {
-                continue;
-            }
             if !p.account.starts_with(to_account_prefix) {
                 continue;
             }
-            credit_sum += p.amount;
+            if let Some(value) = budget_value(db, budget, &p.commodity, p.amount, e.effective_at)? {
+                credit_sum += value;
+            }
         }
 
         if credit_sum.is_zero() {
@@ -620,7 +1690,7 @@ fn compute_budget_funded(
         total += credit_sum;
     }
 
-    total
+    Ok(total)
 }
 
 fn handle_rate(db: &Db, cmd: RateCommand) -> Result<()> {
@@ -630,7 +1700,7 @@ fn handle_rate(db: &Db, cmd: RateCommand) -> Result<()> {
             let base = args.base.to_ascii_uppercase();
             let quote = args.quote.to_ascii_uppercase();
             let as_of = parse_rfc3339_or_now(args.as_of.as_deref())?;
-            db.set_rate(&provider, &base, &quote, as_of, args.rate)?;
+            db.set_rate(&provider, &base, &quote, as_of, args.rate, "manual")?;
             println!(
                 "Set rate @{} {} per {} = {} (as of {}).",
                 provider,
@@ -646,7 +1716,7 @@ fn handle_rate(db: &Db, cmd: RateCommand) -> Result<()> {
             let base = args.base.to_ascii_uppercase();
             let quote = args.quote.to_ascii_uppercase();
             let as_of = parse_rfc3339_or_now(args.as_of.as_deref())?;
-            let Some((found_as_of, rate)) = db.get_rate_as_of(&provider, &base, &quote, as_of)?
+            let Some(point) = rate_at(db, &provider, &base, &quote, as_of, args.interpolate)?
             else {
                 return Err(anyhow!(
                     "No stored rate for @{} {} per {} at or before {}",
@@ -657,20 +1727,37 @@ fn handle_rate(db: &Db, cmd: RateCommand) -> Result<()> {
                 ));
             };
 
-            println!(
-                "@{} {} per {} = {} (as of {}).",
-                provider,
-                quote,
-                base,
-                rate,
-                found_as_of.to_rfc3339()
-            );
+            match point.between {
+                Some((before, after)) => println!(
+                    "@{} {} per {} = {} (interpolated at {} between {} and {}).",
+                    provider,
+                    quote,
+                    base,
+                    point.rate,
+                    point.as_of.to_rfc3339(),
+                    before.to_rfc3339(),
+                    after.to_rfc3339()
+                ),
+                None => println!(
+                    "@{} {} per {} = {} (as of {}).",
+                    provider,
+                    quote,
+                    base,
+                    point.rate,
+                    point.as_of.to_rfc3339()
+                ),
+            }
             Ok(())
         }
         RateCommand::List(args) => {
             let provider = normalize_provider(&args.provider);
             let base = args.base.as_ref().map(|b| b.to_ascii_uppercase());
             let quote = args.quote.as_ref().map(|q| q.to_ascii_uppercase());
+            let sources = if args.verbose {
+                db.list_rate_sources(&provider, base.as_deref(), quote.as_deref())?
+            } else {
+                crate::db::RateSources::new()
+            };
 
             match (base.as_deref(), quote.as_deref()) {
                 (None, None) => {
@@ -680,20 +1767,20 @@ fn handle_rate(db: &Db, cmd: RateCommand) -> Result<()> {
                         return Ok(());
                     }
 
-                    match args.format {
-                        crate::cli::RateListFormat::Table => {
-                            let mut table_rows = Vec::new();
-                            for (b, q, as_of, rate) in rows {
-                                table_rows.push(vec![b, q, as_of.to_rfc3339(), rate.to_string()]);
-                            }
-                            print_table(&["BASE", "QUOTE", "AS OF", "RATE"], &table_rows);
-                        }
-                        crate::cli::RateListFormat::Tsv => {
-                            for (b, q, as_of, rate) in rows {
-                                println!("{}\t{}\t{}\t{}", b, q, as_of.to_rfc3339(), rate);
-                            }
+                    let mut table_rows = Vec::new();
+                    for (b, q, as_of, rate) in rows {
+                        let mut row = vec![b, q, as_of.to_rfc3339(), rate.to_string()];
+                        if args.verbose {
+                            row.push(rate_source(&sources, &row[0], &row[1], as_of));
                         }
+                        table_rows.push(row);
                     }
+                    print_rate_rows(
+                        args.format,
+                        &["BASE", "QUOTE", "AS OF", "RATE"],
+                        args.verbose,
+                        &table_rows,
+                    );
                     Ok(())
                 }
                 (Some(base), None) => {
@@ -703,20 +1790,20 @@ fn handle_rate(db: &Db, cmd: RateCommand) -> Result<()> {
                         return Ok(());
                     }
 
-                    match args.format {
-                        crate::cli::RateListFormat::Table => {
-                            let mut table_rows = Vec::new();
-                            for (b, q, as_of, rate) in rows {
-                                table_rows.push(vec![b, q, as_of.to_rfc3339(), rate.to_string()]);
-                            }
-                            print_table(&["BASE", "QUOTE", "AS OF", "RATE"], &table_rows);
-                        }
-                        crate::cli::RateListFormat::Tsv => {
-                            for (b, q, as_of, rate) in rows {
-                                println!("{}\t{}\t{}\t{}", b, q, as_of.to_rfc3339(), rate);
-                            }
+                    let mut table_rows = Vec::new();
+                    for (b, q, as_of, rate) in rows {
+                        let mut row = vec![b, q, as_of.to_rfc3339(), rate.to_string()];
+                        if args.verbose {
+                            row.push(rate_source(&sources, &row[0], &row[1], as_of));
                         }
+                        table_rows.push(row);
                     }
+                    print_rate_rows(
+                        args.format,
+                        &["BASE", "QUOTE", "AS OF", "RATE"],
+                        args.verbose,
+                        &table_rows,
+                    );
                     Ok(())
                 }
                 (Some(base), Some(quote)) => {
@@ -726,20 +1813,15 @@ fn handle_rate(db: &Db, cmd: RateCommand) -> Result<()> {
                         return Ok(());
                     }
 
-                    match args.format {
-                        crate::cli::RateListFormat::Table => {
-                            let mut table_rows = Vec::new();
-                            for (as_of, rate) in rows {
-                                table_rows.push(vec![as_of.to_rfc3339(), rate.to_string()]);
-                            }
-                            print_table(&["AS OF", "RATE"], &table_rows);
-                        }
-                        crate::cli::RateListFormat::Tsv => {
-                            for (as_of, rate) in rows {
-                                println!("{}\t{}", as_of.to_rfc3339(), rate);
-                            }
+                    let mut table_rows = Vec::new();
+                    for (as_of, rate) in rows {
+                        let mut row = vec![as_of.to_rfc3339(), rate.to_string()];
+                        if args.verbose {
+                            row.push(rate_source(&sources, base, quote, as_of));
                         }
+                        table_rows.push(row);
                     }
+                    print_rate_rows(args.format, &["AS OF", "RATE"], args.verbose, &table_rows);
                     Ok(())
                 }
                 (None, Some(_)) => Err(anyhow!(
@@ -747,62 +1829,427 @@ fn handle_rate(db: &Db, cmd: RateCommand) -> Result<()> {
                 )),
             }
         }
+        RateCommand::Usage(args) => print_rate_usage(db, args),
     }
 }
 
-fn print_table(headers: &[&str], rows: &[Vec<String>]) {
-    if headers.is_empty() {
-        println!("(no columns)");
-        return;
-    }
-
-    let cols = headers.len();
-    let mut widths: Vec<usize> = headers.iter().map(|h| h.len()).collect();
+/// `rate list --verbose` SOURCE column.
+fn rate_source(
+    sources: &crate::db::RateSources,
+    base: &str,
+    quote: &str,
+    as_of: DateTime<Utc>,
+) -> String {
+    sources
+        .get(&(base.to_string(), quote.to_string(), as_of))
+        .cloned()
+        .flatten()
+        .unwrap_or_else(|| "unknown".to_string())
+}
 
-    for row in rows {
-        for (i, cell) in row.iter().take(cols).enumerate() {
-            widths[i] = widths[i].max(cell.len());
+fn print_rate_rows(
+    format: crate::cli::RateListFormat,
+    headers: &[&str],
+    verbose: bool,
+    rows: &[Vec<String>],
+) {
+    match format {
+        crate::cli::RateListFormat::Table => {
+            let mut headers = headers.to_vec();
+            if verbose {
+                headers.push("SOURCE");
+            }
+            print_table(&headers, rows);
         }
-    }
-
-    fn print_row(cells: &[String], widths: &[usize]) {
-        print!("|");
-        for (i, w) in widths.iter().enumerate() {
-            let cell = cells.get(i).map(String::as_str).unwrap_or("");
-            print!(" {:width$} |", cell, width = *w);
+        crate::cli::RateListFormat::Tsv => {
+            for row in rows {
+                println!("{}", row.join("\t"));
+            }
         }
-        println!();
     }
+}
 
-    fn print_sep(widths: &[usize]) {
-        print!("|");
-        for w in widths {
-            print!("{}|", "-".repeat(w + 2));
+/// The rate an event relied on, as grouped by `rate usage`.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+enum RateUse {
+    /// A stored row: `quote` per `base` as of `as_of`.
+    Stored {
+        base: String,
+        quote: String,
+        as_of: DateTime<Utc>,
+        rate: Decimal,
+    },
+    /// An explicit `@provider:<rate>` that matches no stored row.
+    Override {
+        base: String,
+        quote: String,
+        rate: Decimal,
+    },
+    /// Nothing was stored for the pair at the event's as-of time.
+    Missing { base: String, quote: String },
+}
+
+impl RateUse {
+    fn pair(&self) -> (&str, &str) {
+        match self {
+            RateUse::Stored { base, quote, .. }
+            | RateUse::Override { base, quote, .. }
+            | RateUse::Missing { base, quote } => (base, quote),
         }
-        println!();
     }
 
-    let header_cells: Vec<String> = headers.iter().map(|h| h.to_string()).collect();
-    print_row(&header_cells, &widths);
-    print_sep(&widths);
-    for row in rows {
-        print_row(row, &widths);
+    fn label(&self, provider: &str) -> String {
+        match self {
+            RateUse::Stored {
+                base,
+                quote,
+                as_of,
+                rate,
+            } => format!(
+                "@{provider} {quote} per {base} = {rate} (as of {})",
+                as_of.to_rfc3339()
+            ),
+            RateUse::Override { base, quote, rate } => {
+                format!("@{provider} override {quote} per {base} = {rate}")
+            }
+            RateUse::Missing { base, quote } => {
+                format!("@{provider} {quote} per {base}: no stored rate at event time")
+            }
+        }
     }
 }
 
-fn handle_ws(
-    cmd: WsCmd,
-    paths: &crate::config::AppPaths,
-    cfg: &mut AppConfig,
-    cfg_path: &std::path::Path,
-) -> Result<()> {
-    match cmd {
-        WsCmd::Check => {
-            println!("You are currently in workspace: {}", cfg.current_workspace);
-            println!("and the current project is: '{}'", cfg.current_project);
-        }
-        WsCmd::Add { name } => {
-            // Creating a workspace is just creating its db directory.
+/// Resolves which stored row (if any) a `quote` per `base` lookup hit at `as_of`.
+///
+/// Computed moves record the stored rate as an override, so an override equal to the
+/// stored rate is attributed to the stored row.
+fn resolve_rate_use(
+    db: &Db,
+    provider: &str,
+    base: &str,
+    quote: &str,
+    as_of: DateTime<Utc>,
+    override_rate: Option<Decimal>,
+) -> Result<RateUse> {
+    let stored = if let Some((found, rate)) =
+        crate::providers::rate_as_of(db, provider, base, quote, as_of)?
+    {
+        Some((base, quote, found, rate, rate))
+    } else if let Some((found, rate)) =
+        crate::providers::rate_as_of(db, provider, quote, base, as_of)?
+    {
+        let effective = if rate.is_zero() {
+            rate
+        } else {
+            Decimal::ONE / rate
+        };
+        Some((quote, base, found, rate, effective))
+    } else {
+        None
+    };
+
+    if let Some((b, q, found, rate, effective)) = stored
+        && override_rate.is_none_or(|o| o == effective || o == rate)
+    {
+        return Ok(RateUse::Stored {
+            base: b.to_string(),
+            quote: q.to_string(),
+            as_of: found,
+            rate,
+        });
+    }
+    Ok(match override_rate {
+        Some(rate) => RateUse::Override {
+            base: base.to_string(),
+            quote: quote.to_string(),
+            rate,
+        },
+        None => RateUse::Missing {
+            base: base.to_string(),
+            quote: quote.to_string(),
+        },
+    })
+}
+
+/// Every rate from `provider` the event relied on, with where it was used.
+fn event_rate_uses(db: &Db, provider: &str, e: &StoredEvent) -> Result<Vec<(RateUse, String)>> {
+    let rc = &e.payload.rate_context;
+    let mut uses = Vec::new();
+    if rc.provider.as_deref().map(normalize_provider).as_deref() == Some(provider)
+        && let (Some(base), Some(quote)) = (&rc.base, &rc.quote)
+    {
+        uses.push((
+            resolve_rate_use(db, provider, base, quote, rc.as_of, rc.override_rate)?,
+            "rate_context".to_string(),
+        ));
+    }
+
+    // Buy splits in another commodity record their own rate (split commodity per buy
+    // commodity) and the as-of of the stored row it came from.
+    let conversions = e
+        .payload
+        .metadata
+        .get("conversions")
+        .and_then(|v| v.as_array());
+    for c in conversions.into_iter().flatten() {
+        if c.get("provider")
+            .and_then(|v| v.as_str())
+            .map(normalize_provider)
+            .as_deref()
+            != Some(provider)
+        {
+            continue;
+        }
+        let (Some(split), Some(buy)) = (
+            c.get("commodity").and_then(|v| v.as_str()),
+            c.get("value_commodity").and_then(|v| v.as_str()),
+        ) else {
+            continue;
+        };
+        let account = c.get("account").and_then(|v| v.as_str()).unwrap_or("?");
+        let rate = c
+            .get("rate")
+            .and_then(|v| v.as_str())
+            .and_then(|r| r.parse::<Decimal>().ok());
+        let as_of = c
+            .get("rate_as_of")
+            .and_then(|v| v.as_str())
+            .and_then(|t| DateTime::parse_from_rfc3339(t).ok())
+            .map_or(rc.as_of, |t| t.with_timezone(&Utc));
+        uses.push((
+            resolve_rate_use(db, provider, buy, split, as_of, rate)?,
+            format!("split {account}"),
+        ));
+    }
+    Ok(uses)
+}
+
+fn print_rate_usage(db: &Db, args: crate::cli::RateUsageArgs) -> Result<()> {
+    let provider = normalize_provider(&args.provider);
+    let pair = match (args.base, args.quote) {
+        (Some(b), q) => Some((b.to_ascii_uppercase(), q.map(|q| q.to_ascii_uppercase()))),
+        (None, None) => None,
+        (None, Some(_)) => {
+            return Err(anyhow!(
+                "Invalid arguments: quote provided without base. Usage: bankero rate usage @provider [BASE] [QUOTE]"
+            ));
+        }
+    };
+    let window = match (&args.month, &args.range) {
+        (Some(m), _) => Some(crate::period::month_range(m)?),
+        (None, Some(r)) => Some(parse_date_range(r)?),
+        (None, None) => None,
+    };
+
+    let events = crate::snapshot::replay_events(db, false)?;
+    let mut groups: BTreeMap<RateUse, Vec<(&StoredEvent, String)>> = BTreeMap::new();
+    for e in &events {
+        if let Some((start, end)) = window
+            && (e.effective_at < start || e.effective_at > end)
+        {
+            continue;
+        }
+        for (usage, via) in event_rate_uses(db, &provider, e)? {
+            if let Some((base, quote)) = &pair {
+                let (b, q) = usage.pair();
+                let matches = match quote {
+                    Some(quote) => (b == base && q == quote) || (b == quote && q == base),
+                    None => b == base || q == base,
+                };
+                if !matches {
+                    continue;
+                }
+            }
+            groups.entry(usage).or_default().push((e, via));
+        }
+    }
+
+    if groups.is_empty() {
+        println!("(no events used @{provider})");
+        return Ok(());
+    }
+    if let Some(p) = db.get_provider(&provider)? {
+        println!("{}", crate::providers::describe(db, &provider)?);
+        if let Some(url) = p.url {
+            println!("source: {url}");
+        }
+    }
+    for (usage, uses) in groups {
+        println!("{}: {} event(s)", usage.label(&provider), uses.len());
+        for (e, via) in uses {
+            println!(
+                "  {}\t{}\t{}\t{}",
+                e.effective_at.to_rfc3339(),
+                e.action,
+                e.event_id,
+                via
+            );
+        }
+    }
+    Ok(())
+}
+
+/// `report --rates`: every cross-currency rate the events relied on, and its source.
+fn print_rate_report(
+    db: &Db,
+    events: &[StoredEvent],
+    output: &crate::cli::OutputFlags,
+) -> Result<()> {
+    let mut rows = Vec::new();
+    let mut counts: BTreeMap<&str, usize> = BTreeMap::new();
+    for e in events {
+        let conversions = e
+            .payload
+            .metadata
+            .get("conversions")
+            .and_then(|v| v.as_array());
+        let mut providers: Vec<String> = e
+            .payload
+            .rate_context
+            .provider
+            .iter()
+            .map(|p| normalize_provider(p))
+            .chain(
+                conversions
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|c| c.get("provider").and_then(|v| v.as_str()))
+                    .map(normalize_provider),
+            )
+            .collect();
+        providers.sort();
+        providers.dedup();
+
+        for provider in providers {
+            for (usage, via) in event_rate_uses(db, &provider, e)? {
+                let (base, quote) = usage.pair();
+                if base.eq_ignore_ascii_case(quote) {
+                    continue;
+                }
+                let (rate, source) = match &usage {
+                    RateUse::Stored { rate, .. } => (rate.to_string(), "stored"),
+                    RateUse::Override { rate, .. } if provider == "derived" => {
+                        (rate.normalize().to_string(), "inferred")
+                    }
+                    RateUse::Override { rate, .. } => (rate.to_string(), "override"),
+                    RateUse::Missing { .. } => ("-".to_string(), "missing"),
+                };
+                *counts.entry(source).or_insert(0) += 1;
+                rows.push(vec![
+                    crate::output::iso_date(e.effective_at),
+                    e.effective_at.to_rfc3339(),
+                    e.action.clone(),
+                    e.event_id.to_string(),
+                    base.to_string(),
+                    quote.to_string(),
+                    rate,
+                    format!("@{provider}"),
+                    source.to_string(),
+                    via,
+                ]);
+            }
+        }
+    }
+
+    if output.is_table() {
+        return crate::output::write_table(
+            output,
+            &[
+                "date",
+                "effective_at",
+                "action",
+                "event_id",
+                "base",
+                "quote",
+                "rate",
+                "provider",
+                "source",
+                "via",
+            ],
+            &rows,
+        );
+    }
+    output.require_table_for_out()?;
+
+    if rows.is_empty() {
+        println!("(no cross-currency events)");
+        return Ok(());
+    }
+    for row in &rows {
+        println!(
+            "{}\t{}\t{}\t{} per {}\t{}\t{}\t{}",
+            row[1], row[2], row[3], row[5], row[4], row[6], row[7], row[8]
+        );
+    }
+    let hand_typed =
+        counts.get("override").copied().unwrap_or(0) + counts.get("inferred").copied().unwrap_or(0);
+    let summary: Vec<String> = ["stored", "override", "inferred", "missing"]
+        .iter()
+        .map(|s| format!("{s} {}", counts.get(s).copied().unwrap_or(0)))
+        .collect();
+    println!(
+        "rates: {}; hand-typed {} of {} ({}%)",
+        summary.join(", "),
+        hand_typed,
+        rows.len(),
+        (Decimal::from(hand_typed * 100) / Decimal::from(rows.len())).round_dp(0)
+    );
+    Ok(())
+}
+
+fn print_table(headers: &[&str], rows: &[Vec<String>]) {
+    if headers.is_empty() {
+        println!("(no columns)");
+        return;
+    }
+
+    let cols = headers.len();
+    let mut widths: Vec<usize> = headers.iter().map(|h| h.len()).collect();
+
+    for row in rows {
+        for (i, cell) in row.iter().take(cols).enumerate() {
+            widths[i] = widths[i].max(cell.len());
+        }
+    }
+
+    fn print_row(cells: &[String], widths: &[usize]) {
+        print!("|");
+        for (i, w) in widths.iter().enumerate() {
+            let cell = cells.get(i).map(String::as_str).unwrap_or("");
+            print!(" {:width$} |", cell, width = *w);
+        }
+        println!();
+    }
+
+    fn print_sep(widths: &[usize]) {
+        print!("|");
+        for w in widths {
+            print!("{}|", "-".repeat(w + 2));
+        }
+        println!();
+    }
+
+    let header_cells: Vec<String> = headers.iter().map(|h| h.to_string()).collect();
+    print_row(&header_cells, &widths);
+    print_sep(&widths);
+    for row in rows {
+        print_row(row, &widths);
+    }
+}
+
+fn handle_ws(
+    cmd: WsCmd,
+    paths: &crate::config::AppPaths,
+    cfg: &mut AppConfig,
+    cfg_path: &std::path::Path,
+) -> Result<()> {
+    match cmd {
+        WsCmd::Check => {
+            println!("You are currently in workspace: {}", cfg.current_workspace);
+            println!("and the current project is: '{}'", cfg.current_project);
+        }
+        WsCmd::Add { name } => {
+            // Creating a workspace is just creating its db directory.
             let _ = Db::open(paths, &name)?;
             println!("Added workspace: {name}");
         }
@@ -817,6 +2264,172 @@ fn handle_ws(
     Ok(())
 }
 
+fn handle_config(
+    cmd: ConfigCmd,
+    paths: &crate::config::AppPaths,
+    cfg: &mut AppConfig,
+    cfg_path: &std::path::Path,
+) -> Result<()> {
+    const KEYS: &str = "confirm_over, data_dir, device_name, import_date, locale, reference_commodity, rounding_account, strict_commodities, sync_max_events, sync_max_mb, time_of_day, timezone, transit_account";
+    match cmd {
+        ConfigCmd::Get { key } => {
+            let value = match key.as_str() {
+                "confirm_over" => cfg
+                    .confirm_over
+                    .clone()
+                    .unwrap_or_else(|| "(not set)".to_string()),
+                "data_dir" => match cfg.data_dir.as_deref() {
+                    Some(raw) => crate::config::expand_data_dir(raw)?.display().to_string(),
+                    None => format!("{} (default)", paths.data_dir.display()),
+                },
+                "device_name" => cfg.device_name.clone().unwrap_or_default(),
+                "import_date" => cfg.import_date.as_str().to_string(),
+                "locale" => cfg
+                    .locale
+                    .clone()
+                    .unwrap_or_else(|| "en (default)".to_string()),
+                "reference_commodity" => cfg.reference_commodity.clone(),
+                "rounding_account" => cfg.rounding_account.clone(),
+                "strict_commodities" => cfg.strict_commodities.to_string(),
+                "sync_max_events" => match cfg.sync_max_events {
+                    Some(n) => n.to_string(),
+                    None => format!("{} (default)", crate::sync::DEFAULT_MAX_EVENTS),
+                },
+                "sync_max_mb" => match cfg.sync_max_mb {
+                    Some(n) => n.to_string(),
+                    None => format!("{} (default)", crate::sync::DEFAULT_MAX_MB),
+                },
+                "transit_account" => cfg.transit_account.clone(),
+                "time_of_day" => cfg
+                    .time_of_day
+                    .clone()
+                    .unwrap_or_else(|| "12:00 (default)".to_string()),
+                "timezone" => cfg
+                    .timezone
+                    .clone()
+                    .unwrap_or_else(|| "(not set)".to_string()),
+                _ => return Err(anyhow!("Unknown config key: {key}. Known keys: {KEYS}")),
+            };
+            println!("{value}");
+        }
+        ConfigCmd::Set { key, value } => {
+            match key.as_str() {
+                "confirm_over" => {
+                    let (amount, commodity) = parse_confirm_over(&value)?;
+                    cfg.confirm_over = Some(format!("{amount} {commodity}"));
+                }
+                "data_dir" => {
+                    let dir = crate::config::expand_data_dir(&value)?;
+                    std::fs::create_dir_all(&dir)
+                        .with_context(|| format!("Failed to create data dir {}", dir.display()))?;
+                    cfg.data_dir = Some(dir.display().to_string());
+                    if paths.data_dir.join("workspaces").is_dir() && dir != paths.data_dir {
+                        eprintln!(
+                            "Note: existing journals in {} were not moved; copy its workspaces/ folder to {} to keep them.",
+                            paths.data_dir.display(),
+                            dir.display()
+                        );
+                    }
+                }
+                "device_name" => cfg.device_name = Some(value.trim().to_string()),
+                "reference_commodity" => cfg.reference_commodity = value.to_ascii_uppercase(),
+                "rounding_account" => {
+                    let account = value.trim().trim_end_matches(':');
+                    if account.is_empty() {
+                        return Err(anyhow!("rounding_account must not be empty"));
+                    }
+                    cfg.rounding_account = account.to_string();
+                }
+                "transit_account" => {
+                    let account = value.trim().trim_end_matches(':');
+                    if account.is_empty() {
+                        return Err(anyhow!("transit_account must not be empty"));
+                    }
+                    cfg.transit_account = account.to_string();
+                }
+                "locale" => cfg.locale = Some(crate::period::parse_locale(&value)?),
+                "strict_commodities" => {
+                    cfg.strict_commodities = match value.trim().to_ascii_lowercase().as_str() {
+                        "true" | "on" | "yes" => true,
+                        "false" | "off" | "no" => false,
+                        _ => return Err(anyhow!("strict_commodities must be true or false")),
+                    }
+                }
+                "sync_max_events" => {
+                    cfg.sync_max_events =
+                        Some(value.trim().parse().map_err(|_| {
+                            anyhow!("sync_max_events must be a whole number of events")
+                        })?)
+                }
+                "sync_max_mb" => {
+                    cfg.sync_max_mb = Some(
+                        value
+                            .trim()
+                            .parse()
+                            .map_err(|_| anyhow!("sync_max_mb must be a whole number of MB"))?,
+                    )
+                }
+                "timezone" => cfg.timezone = Some(crate::init::parse_offset(&value)?.to_string()),
+                "time_of_day" => {
+                    let time = crate::dates::parse_time_of_day(&value)?;
+                    cfg.time_of_day = Some(time.format("%H:%M").to_string());
+                }
+                "import_date" => cfg.import_date = crate::config::ImportDate::parse(&value)?,
+                _ => return Err(anyhow!("Unknown config key: {key}. Known keys: {KEYS}")),
+            }
+            write_config(cfg_path, cfg)?;
+            println!("Set {key}.");
+        }
+        ConfigCmd::Unset { key } => {
+            match key.as_str() {
+                "confirm_over" => cfg.confirm_over = None,
+                "data_dir" => cfg.data_dir = None,
+                "locale" => cfg.locale = None,
+                "strict_commodities" => cfg.strict_commodities = false,
+                "sync_max_events" => cfg.sync_max_events = None,
+                "sync_max_mb" => cfg.sync_max_mb = None,
+                "device_name" => {
+                    cfg.device_name = Some(crate::config::funny_name_from_uuid(cfg.device_id))
+                }
+                "reference_commodity" => cfg.reference_commodity = "USD".to_string(),
+                "rounding_account" => {
+                    cfg.rounding_account = crate::config::default_rounding_account()
+                }
+                "transit_account" => cfg.transit_account = crate::config::default_transit_account(),
+                "timezone" => cfg.timezone = None,
+                "time_of_day" => cfg.time_of_day = None,
+                "import_date" => cfg.import_date = crate::config::ImportDate::Posting,
+                _ => return Err(anyhow!("Unknown config key: {key}. Known keys: {KEYS}")),
+            }
+            write_config(cfg_path, cfg)?;
+            println!("Unset {key}.");
+        }
+    }
+    Ok(())
+}
+
+fn handle_profile(cmd: ProfileCmd, paths: &crate::config::AppPaths, cfg: &AppConfig) -> Result<()> {
+    match cmd {
+        ProfileCmd::List => {
+            for name in crate::config::list_profiles(paths)? {
+                let marker = if name == paths.profile { "*" } else { " " };
+                println!("{marker} {name}");
+            }
+        }
+        ProfileCmd::Current => {
+            println!("profile\t{}", paths.profile);
+            println!(
+                "device\t{}",
+                cfg.device_name.as_deref().unwrap_or("(unnamed)")
+            );
+            println!("workspace\t{}", cfg.current_workspace);
+            println!("config_dir\t{}", paths.config_dir.display());
+            println!("data_dir\t{}", paths.data_dir.display());
+        }
+    }
+    Ok(())
+}
+
 fn handle_project(
     cmd: ProjectCmd,
     paths: &crate::config::AppPaths,
@@ -851,13 +2464,28 @@ fn parse_decimal(raw: String, field: &'static str) -> Result<Decimal> {
         .with_context(|| format!("Invalid decimal for {field}: {raw}"))
 }
 
+/// Parses a rate like `24%` (percent) or `0.24` (fraction) into a fraction.
+fn parse_percent(raw: &str, field: &'static str) -> Result<Decimal> {
+    let trimmed = raw.trim();
+    match trimmed.strip_suffix('%') {
+        Some(pct) => Ok(parse_decimal(pct.trim().to_string(), field)? / Decimal::from(100u32)),
+        None => parse_decimal(trimmed.to_string(), field),
+    }
+}
+
+/// Parses a time flag: RFC3339, or a date/relative day (see `dates`). None means now.
 fn parse_rfc3339_or_now(raw: Option<&str>) -> Result<DateTime<Utc>> {
-    match raw {
-        None => Ok(now_utc()),
-        Some(s) => Ok(DateTime::parse_from_rfc3339(s)
-            .with_context(|| format!("Invalid RFC3339 timestamp: {s}"))?
-            .with_timezone(&Utc)),
+    let Some(s) = raw else {
+        return Ok(now_utc());
+    };
+    if let Ok(t) = DateTime::parse_from_rfc3339(s) {
+        return Ok(t.with_timezone(&Utc));
     }
+    crate::dates::parse_human(s, now_utc()).ok_or_else(|| {
+        anyhow!(
+            "Invalid timestamp: {s}. Expected RFC3339, a date like 2026-03-05, or e.g. \"yesterday\", \"last friday\", \"3 days ago\""
+        )
+    })
 }
 
 fn parse_move_tail(
@@ -914,10 +2542,7 @@ fn parse_as_of(
     effective_at: DateTime<Utc>,
 ) -> Result<DateTime<Utc>> {
     if let Some(as_of) = &common.as_of {
-        let dt = DateTime::parse_from_rfc3339(as_of)
-            .with_context(|| format!("Invalid RFC3339 timestamp for --as-of: {as_of}"))?
-            .with_timezone(&Utc);
-        return Ok(dt);
+        return parse_rfc3339_or_now(Some(as_of)).context("--as-of");
     }
     Ok(effective_at)
 }
@@ -945,6 +2570,7 @@ fn infer_ref_rate_pair(reference: &str, commodity: &str) -> (Option<String>, Opt
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 fn build_deposit_event(
     cfg: &AppConfig,
     action: &str,
@@ -992,12 +2618,20 @@ fn build_deposit_event(
         tags: common.tags,
         category: common.category,
         note: common.note,
+        reference: common.reference,
         rate_context: build_rate_context(provider, as_of, None, None),
         basis,
-        metadata: serde_json::json!({"confirm": common.confirm}),
+        metadata: with_meta(
+            serde_json::json!({
+                "confirm": common.confirm,
+                "entered_by": entered_by(cfg, common.entered_by.as_deref()),
+            }),
+            &common.meta,
+        )?,
     })
 }
 
+#[allow(clippy::too_many_arguments)]
 fn build_move_event(
     cfg: &AppConfig,
     event_id: Uuid,
@@ -1072,9 +2706,17 @@ fn build_move_event(
             tags: common.tags,
             category: common.category,
             note: common.note,
+            reference: common.reference,
             rate_context: build_rate_context(p, as_of, Some(commodity), Some(tc)),
             basis,
-            metadata: serde_json::json!({"event_id": event_id.to_string(), "confirm": common.confirm}),
+            metadata: with_meta(
+                serde_json::json!({
+                    "event_id": event_id.to_string(),
+                    "confirm": common.confirm,
+                    "entered_by": entered_by(cfg, common.entered_by.as_deref()),
+                }),
+                &common.meta,
+            )?,
         });
     }
 
@@ -1103,6 +2745,7 @@ fn build_move_event(
         tags: common.tags,
         category: common.category,
         note: common.note,
+        reference: common.reference,
         rate_context: {
             let (base, quote) = match provider.as_ref() {
                 None => (None, None),
@@ -1111,11 +2754,100 @@ fn build_move_event(
             build_rate_context(provider, as_of, base, quote)
         },
         basis,
-        metadata: serde_json::json!({"event_id": event_id.to_string(), "confirm": common.confirm}),
+        metadata: with_meta(
+            serde_json::json!({
+                "event_id": event_id.to_string(),
+                "confirm": common.confirm,
+                "entered_by": entered_by(cfg, common.entered_by.as_deref()),
+            }),
+            &common.meta,
+        )?,
     })
 }
 
+/// Expands a `bankero q` line into a buy event using configured defaults.
+fn build_quick_event(
+    db: &Db,
+    cfg: &AppConfig,
+    event_id: Uuid,
+    text: String,
+    common: crate::cli::CommonEventFlags,
+) -> Result<EventPayload> {
+    let entry = crate::domain::parse_quick_entry(&text)
+        .map_err(|e| anyhow!("Invalid quick entry \"{text}\": {e}"))?;
+
+    let from = match &entry.account_alias {
+        Some(alias) => match cfg.account_aliases.get(alias) {
+            Some(account) => account.clone(),
+            None if alias.contains(':') => alias.clone(),
+            None => format!("assets:{alias}"),
+        },
+        None => cfg.default_account.clone().ok_or_else(|| {
+            anyhow!("No account in \"{text}\". Add @<alias> or set default_account in config.json")
+        })?,
+    };
+    let target = match &entry.category {
+        Some(category) if category.contains(':') => category.clone(),
+        Some(category) => format!("expenses:{category}"),
+        None => "expenses:uncategorized".to_string(),
+    };
+    let commodity = entry
+        .commodity
+        .clone()
+        .unwrap_or_else(|| cfg.reference_commodity.clone());
+
+    let mut common = common;
+    common.tags.extend(entry.tags);
+    if common.category.is_none() && entry.category.is_some() {
+        common.category = Some(target.clone());
+    }
+
+    let mut payload = build_buy_event(
+        db,
+        cfg,
+        event_id,
+        Some(target),
+        entry.amount.to_string(),
+        commodity,
+        from,
+        vec![],
+        crate::cli::BuyChargeFlags::default(),
+        None,
+        common,
+    )?;
+    payload.metadata["quick_entry"] = serde_json::Value::String(text);
+    Ok(payload)
+}
+
+/// Maps the buy positionals (and optional --payee) to (payee, amount, commodity).
+fn resolve_buy_positionals(
+    payee_flag: Option<String>,
+    first: String,
+    second: String,
+    third: Option<String>,
+) -> Result<(Option<String>, String, String)> {
+    match (payee_flag, third) {
+        (Some(payee), None) => Ok((Some(payee), first, second)),
+        (Some(payee), Some(_)) => Err(anyhow!(
+            "buy got both --payee {payee} and a positional payee ({first}); use one or the other: bankero buy <amount> <commodity> --payee {payee} --from <account>"
+        )),
+        (None, Some(commodity)) => {
+            // Catch `buy 39.99 USD merchant` style mix-ups before they become "Invalid amount".
+            if first.trim().parse::<Decimal>().is_ok() && second.trim().parse::<Decimal>().is_err()
+            {
+                return Err(anyhow!(
+                    "buy arguments look out of order: expected <payee> <amount> <commodity>, got {first} {second} {commodity}. Use: bankero buy {first} {second} --payee {commodity} --from <account>"
+                ));
+            }
+            Ok((Some(first), second, commodity))
+        }
+        (None, None) => Ok((None, first, second)),
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
 fn build_buy_event(
+    db: &Db,
     cfg: &AppConfig,
     event_id: Uuid,
     payee: Option<String>,
@@ -1123,6 +2855,7 @@ fn build_buy_event(
     commodity: String,
     from: String,
     to_splits: Vec<String>,
+    charges: crate::cli::BuyChargeFlags,
     provider: Option<ProviderToken>,
     common: crate::cli::CommonEventFlags,
 ) -> Result<EventPayload> {
@@ -1138,9 +2871,10 @@ fn build_buy_event(
         amount: -amount,
     }];
 
+    let mut conversions: Vec<SplitConversion> = Vec::new();
     if to_splits.is_empty() {
         let payee = payee.ok_or_else(|| {
-            anyhow!("buy requires either a payee/target (3-arg form) or at least one --to split (2-arg form)")
+            anyhow!("buy requires a payee/target (3-arg form or --payee) or at least one --to split (2-arg form)")
         })?;
         postings.push(Posting {
             account: payee,
@@ -1149,22 +2883,124 @@ fn build_buy_event(
         });
     } else {
         let mut sum = Decimal::ZERO;
-        for split in to_splits {
-            let (account, split_amount) = parse_split_to(&split, &commodity)?;
-            sum += split_amount;
+        for (account, split_amount, mut split_commodity) in
+            resolve_splits(db, &to_splits, amount, &commodity)?
+        {
+            if split_commodity.eq_ignore_ascii_case(&commodity) {
+                split_commodity = commodity.clone();
+                sum += split_amount;
+            } else {
+                let conversion = convert_split(
+                    db,
+                    provider.as_ref(),
+                    &account,
+                    split_amount,
+                    &split_commodity,
+                    &commodity,
+                    as_of,
+                )?;
+                sum += conversion.value;
+                conversions.push(conversion);
+            }
             postings.push(Posting {
                 account,
-                commodity: commodity.clone(),
+                commodity: split_commodity,
                 amount: split_amount,
             });
         }
-        if sum != amount {
+
+        // Converted splits are only approximately equal in the buy commodity, so allow
+        // a cent of slack there; same-currency splits must match exactly.
+        let tolerance = if conversions.is_empty() {
+            Decimal::ZERO
+        } else {
+            Decimal::new(1, 2)
+        };
+        if (sum - amount).abs() > tolerance {
             return Err(anyhow!(
                 "Split amounts must sum to the buy amount ({} != {})",
-                sum,
+                sum.round_dp(8),
                 amount
             ));
         }
+
+        // Keep every commodity balanced: the paid commodity flows into equity:conversion
+        // and each split commodity flows out of it.
+        if !conversions.is_empty() {
+            let same_commodity: Decimal = postings
+                .iter()
+                .skip(1)
+                .filter(|p| p.commodity == commodity)
+                .map(|p| p.amount)
+                .sum();
+            // The converted values may miss the buy amount by the rounding slack allowed
+            // above; that residual goes to the rounding account, not into the conversion.
+            let residual = (amount - sum).normalize();
+            postings.push(Posting {
+                account: CONVERSION_ACCOUNT.to_string(),
+                commodity: commodity.clone(),
+                amount: amount - same_commodity - residual,
+            });
+            if !residual.is_zero() {
+                postings.push(Posting {
+                    account: cfg.rounding_account.clone(),
+                    commodity: commodity.clone(),
+                    amount: residual,
+                });
+            }
+            let mut per_commodity: BTreeMap<String, Decimal> = BTreeMap::new();
+            for c in &conversions {
+                *per_commodity
+                    .entry(c.commodity.clone())
+                    .or_insert(Decimal::ZERO) += c.amount;
+            }
+            for (split_commodity, total) in per_commodity {
+                postings.push(Posting {
+                    account: CONVERSION_ACCOUNT.to_string(),
+                    commodity: split_commodity,
+                    amount: -total,
+                });
+            }
+        }
+    }
+
+    // Tax/tip are charged on top of the amount, so the paying account covers them too.
+    let mut charge_metadata = serde_json::Map::new();
+    for (kind, raw, account) in [
+        (
+            "tax",
+            charges.tax,
+            charges
+                .tax_account
+                .unwrap_or_else(|| cfg.tax_account.clone()),
+        ),
+        (
+            "tip",
+            charges.tip,
+            charges
+                .tip_account
+                .unwrap_or_else(|| cfg.tip_account.clone()),
+        ),
+    ] {
+        let Some(raw) = raw else {
+            continue;
+        };
+        let rate = parse_percent(&raw, kind)?;
+        let charge = crate::commodities::round(db, &commodity, amount * rate)?;
+        postings[0].amount -= charge;
+        postings.push(Posting {
+            account: account.clone(),
+            commodity: commodity.clone(),
+            amount: charge,
+        });
+        charge_metadata.insert(
+            kind.to_string(),
+            serde_json::json!({
+                "rate": rate.to_string(),
+                "amount": charge.to_string(),
+                "account": account,
+            }),
+        );
     }
 
     let basis = common
@@ -1173,7 +3009,7 @@ fn build_buy_event(
         .and_then(parse_basis_arg)
         .or_else(|| parse_fixed_basis(&common.basis));
 
-    Ok(EventPayload {
+    let mut payload = EventPayload {
         schema_version: 1,
         device_id: cfg.device_id,
         workspace: cfg.current_workspace.clone(),
@@ -1185,6 +3021,7 @@ fn build_buy_event(
         tags: common.tags,
         category: common.category,
         note: common.note,
+        reference: common.reference,
         rate_context: {
             let (base, quote) = match provider.as_ref() {
                 None => (None, None),
@@ -1193,30 +3030,248 @@ fn build_buy_event(
             build_rate_context(provider, as_of, base, quote)
         },
         basis,
-        metadata: serde_json::json!({
-            "event_id": event_id.to_string(),
-            "confirm": common.confirm,
-            "payee": payee_for_metadata,
-        }),
-    })
+        metadata: with_meta(
+            serde_json::json!({
+                "event_id": event_id.to_string(),
+                "confirm": common.confirm,
+                "entered_by": entered_by(cfg, common.entered_by.as_deref()),
+                "payee": payee_for_metadata,
+            }),
+            &common.meta,
+        )?,
+    };
+    for (kind, value) in charge_metadata {
+        payload.metadata[kind] = value;
+    }
+    if !conversions.is_empty() {
+        payload.metadata["conversions"] =
+            serde_json::Value::Array(conversions.iter().map(SplitConversion::to_json).collect());
+    }
+    if payload.category.is_none() {
+        let texts: Vec<&str> = [payload.metadata["payee"].as_str(), payload.note.as_deref()]
+            .into_iter()
+            .flatten()
+            .collect();
+        if let Some(rule) = crate::rules::categorize(&cfg.category_rules, &texts) {
+            let (category, matched) = (rule.category.clone(), rule.contains.clone());
+            payload.category = Some(category);
+            payload.metadata["category_rule"] = serde_json::Value::String(matched);
+        }
+    }
+    Ok(payload)
 }
 
-fn build_sell_event(
-    cfg: &AppConfig,
-    event_id: Uuid,
-    amount_raw: String,
-    commodity: String,
-    from: Option<String>,
-    to: String,
-    to_amount: Decimal,
-    to_commodity: String,
-    provider: Option<ProviderToken>,
-    common: crate::cli::CommonEventFlags,
-) -> Result<EventPayload> {
-    let amount = parse_decimal(amount_raw, "amount")?;
-    let created_at = now_utc();
-    let effective_at = parse_rfc3339_or_now(common.effective_at.as_deref())?;
-    let as_of = parse_as_of(&common, effective_at)?;
+/// Balancing account for buys whose splits settle in other commodities.
+const CONVERSION_ACCOUNT: &str = "equity:conversion";
+
+/// A `--to` split denominated in a commodity other than the buy commodity.
+struct SplitConversion {
+    account: String,
+    amount: Decimal,
+    commodity: String,
+    /// Equivalent amount in the buy commodity.
+    value: Decimal,
+    value_commodity: String,
+    provider: String,
+    rate: Decimal,
+    rate_as_of: DateTime<Utc>,
+}
+
+impl SplitConversion {
+    fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "account": self.account,
+            "amount": self.amount.to_string(),
+            "commodity": self.commodity,
+            "value": self.value.to_string(),
+            "value_commodity": self.value_commodity,
+            "provider": self.provider,
+            "rate": self.rate.to_string(),
+            "rate_as_of": self.rate_as_of.to_rfc3339(),
+        })
+    }
+}
+
+/// `buy` with several `--from <account>:<amount>[:<commodity>]` legs: replaces the single
+/// paying posting with one per leg. Legs in another commodity are valued in the buy
+/// commodity like foreign `--to` splits (and listed with them under `conversions`); all
+/// legs together must cover what the single `--from` would have paid.
+fn apply_payment_legs(
+    db: &Db,
+    cfg: &AppConfig,
+    payload: &mut EventPayload,
+    legs: &[String],
+    provider: Option<&ProviderToken>,
+) -> Result<()> {
+    let total = -payload.postings[0].amount;
+    let commodity = payload.postings[0].commodity.clone();
+    let defaults = db.account_commodities()?;
+
+    let mut leg_postings = Vec::new();
+    let mut conversions: Vec<SplitConversion> = Vec::new();
+    let mut same_commodity = Decimal::ZERO;
+    let mut sum = Decimal::ZERO;
+    for raw in legs {
+        let (account, amount, explicit) = parse_split_to(raw, "").map_err(|_| {
+            anyhow!("Invalid --from leg '{raw}'. Expected <account>:<amount>[:<commodity>]")
+        })?;
+        let SplitAmount::Fixed(amount) = amount else {
+            return Err(anyhow!(
+                "--from leg '{raw}' needs an amount, not a percentage"
+            ));
+        };
+        if amount <= Decimal::ZERO {
+            return Err(anyhow!("--from leg '{raw}' must be positive"));
+        }
+        let leg_commodity = match explicit.as_str() {
+            "" => defaults.get(&account).cloned().unwrap_or(commodity.clone()),
+            _ => explicit,
+        };
+        let leg_commodity = if leg_commodity.eq_ignore_ascii_case(&commodity) {
+            same_commodity += amount;
+            sum += amount;
+            commodity.clone()
+        } else {
+            let conversion = convert_split(
+                db,
+                provider,
+                &account,
+                amount,
+                &leg_commodity,
+                &commodity,
+                payload.rate_context.as_of,
+            )?;
+            sum += conversion.value;
+            conversions.push(conversion);
+            leg_commodity
+        };
+        leg_postings.push(Posting {
+            account,
+            commodity: leg_commodity,
+            amount: -amount,
+        });
+    }
+
+    let tolerance = if conversions.is_empty() {
+        Decimal::ZERO
+    } else {
+        Decimal::new(1, 2)
+    };
+    if (sum - total).abs() > tolerance {
+        return Err(anyhow!(
+            "--from legs must sum to what the buy pays ({} != {total} {commodity})",
+            sum.round_dp(8)
+        ));
+    }
+    payload.postings.splice(0..1, leg_postings);
+
+    // Same balancing as foreign --to splits, in the other direction: each leg commodity
+    // flows into equity:conversion and the buy commodity flows out of it.
+    if !conversions.is_empty() {
+        let residual = (total - sum).normalize();
+        payload.postings.push(Posting {
+            account: CONVERSION_ACCOUNT.to_string(),
+            commodity: commodity.clone(),
+            amount: -(total - same_commodity - residual),
+        });
+        if !residual.is_zero() {
+            payload.postings.push(Posting {
+                account: cfg.rounding_account.clone(),
+                commodity: commodity.clone(),
+                amount: -residual,
+            });
+        }
+        let mut per_commodity: BTreeMap<String, Decimal> = BTreeMap::new();
+        for c in &conversions {
+            *per_commodity
+                .entry(c.commodity.clone())
+                .or_insert(Decimal::ZERO) += c.amount;
+        }
+        for (leg_commodity, leg_total) in per_commodity {
+            payload.postings.push(Posting {
+                account: CONVERSION_ACCOUNT.to_string(),
+                commodity: leg_commodity,
+                amount: leg_total,
+            });
+        }
+        let mut all: Vec<serde_json::Value> = payload.metadata["conversions"]
+            .as_array()
+            .cloned()
+            .unwrap_or_default();
+        all.extend(conversions.iter().map(SplitConversion::to_json));
+        payload.metadata["conversions"] = serde_json::Value::Array(all);
+    }
+    Ok(())
+}
+
+/// Values a foreign-commodity split in the buy commodity using the buy's provider.
+///
+/// An override rate (`@bcv:45.2`) is read like `move`: split commodity per buy commodity.
+fn convert_split(
+    db: &Db,
+    provider: Option<&ProviderToken>,
+    account: &str,
+    amount: Decimal,
+    split_commodity: &str,
+    buy_commodity: &str,
+    as_of: DateTime<Utc>,
+) -> Result<SplitConversion> {
+    let Some(provider) = provider else {
+        return Err(anyhow!(
+            "Split '{account}' is in {split_commodity} but the buy is in {buy_commodity}; add a provider token (e.g. @bcv or @bcv:45.2) to convert it"
+        ));
+    };
+    let provider_name = normalize_provider(&provider.provider);
+
+    let (value, rate, rate_as_of) = match provider.override_rate {
+        Some(rate) => {
+            if rate.is_zero() {
+                return Err(anyhow!("Override rate must not be zero"));
+            }
+            (amount / rate, rate, as_of)
+        }
+        None => {
+            let (value, rate, _inverted, found_as_of) = resolve_and_convert(
+                db,
+                &provider_name,
+                split_commodity,
+                &buy_commodity.to_ascii_uppercase(),
+                as_of,
+                amount,
+            )?;
+            (value, rate, found_as_of)
+        }
+    };
+
+    Ok(SplitConversion {
+        account: account.to_string(),
+        amount,
+        commodity: split_commodity.to_string(),
+        value: crate::commodities::round(db, buy_commodity, value)?,
+        value_commodity: buy_commodity.to_string(),
+        provider: format!("@{provider_name}"),
+        rate,
+        rate_as_of,
+    })
+}
+
+#[allow(clippy::too_many_arguments)]
+fn build_sell_event(
+    cfg: &AppConfig,
+    event_id: Uuid,
+    amount_raw: String,
+    commodity: String,
+    from: Option<String>,
+    to: String,
+    to_amount: Decimal,
+    to_commodity: String,
+    provider: Option<ProviderToken>,
+    common: crate::cli::CommonEventFlags,
+) -> Result<EventPayload> {
+    let amount = parse_decimal(amount_raw, "amount")?;
+    let created_at = now_utc();
+    let effective_at = parse_rfc3339_or_now(common.effective_at.as_deref())?;
+    let as_of = parse_as_of(&common, effective_at)?;
 
     let from_account = from.unwrap_or_else(|| format!("assets:{}", commodity.to_ascii_lowercase()));
 
@@ -1271,12 +3326,170 @@ fn build_sell_event(
         tags: common.tags,
         category: common.category,
         note: common.note,
+        reference: common.reference,
         rate_context: build_rate_context(p, as_of, Some(commodity), Some(to_commodity.clone())),
         basis,
-        metadata: serde_json::json!({"event_id": event_id.to_string(), "confirm": common.confirm}),
+        metadata: with_meta(
+            serde_json::json!({
+                "event_id": event_id.to_string(),
+                "confirm": common.confirm,
+                "entered_by": entered_by(cfg, common.entered_by.as_deref()),
+            }),
+            &common.meta,
+        )?,
     })
 }
 
+/// Adds `--fee` to a move/sell: the fee account is credited and the account that holds
+/// the fee commodity in the event pays it (--from for the sent commodity, --to for the
+/// received one), so converted amounts reconcile with what the exchange reports.
+fn apply_fee(
+    cfg: &AppConfig,
+    payload: &mut EventPayload,
+    flags: &crate::cli::FeeFlags,
+) -> Result<()> {
+    let [amount_raw, commodity] = flags.fee.as_slice() else {
+        return Ok(());
+    };
+    let amount = parse_decimal(amount_raw.clone(), "fee")?;
+    if amount <= Decimal::ZERO {
+        return Err(anyhow!("--fee must be positive"));
+    }
+    let sent = &payload.postings[0];
+    let payer = if sent.commodity.eq_ignore_ascii_case(commodity) {
+        sent
+    } else {
+        payload
+            .postings
+            .iter()
+            .find(|p| p.amount > Decimal::ZERO && p.commodity.eq_ignore_ascii_case(commodity))
+            .ok_or_else(|| {
+                anyhow!(
+                    "--fee {amount} {commodity} must be in the sent ({}) or received commodity",
+                    sent.commodity
+                )
+            })?
+    };
+    let (payer_account, commodity) = (payer.account.clone(), payer.commodity.clone());
+    let account = flags
+        .fee_account
+        .clone()
+        .unwrap_or_else(|| cfg.fee_account.clone());
+    payload.postings.push(Posting {
+        account: payer_account.clone(),
+        commodity: commodity.clone(),
+        amount: -amount,
+    });
+    payload.postings.push(Posting {
+        account: account.clone(),
+        commodity: commodity.clone(),
+        amount,
+    });
+    payload.metadata["fee"] = serde_json::json!({
+        "amount": amount.to_string(),
+        "commodity": commodity,
+        "account": account,
+        "paid_from": payer_account,
+    });
+    Ok(())
+}
+
+/// Who an event is attributed to: `--entered-by`, else this device's name.
+fn entered_by(cfg: &AppConfig, flag: Option<&str>) -> String {
+    flag.map(str::to_string)
+        .or_else(|| cfg.device_name.clone())
+        .unwrap_or_else(|| cfg.device_id.to_string())
+}
+
+/// Metadata keys Bankero itself sets or reads back (links between events, imports,
+/// tasks, snapshots); `--meta` can't use them.
+const RESERVED_META_KEYS: &[&str] = &[
+    "adjusts",
+    "approves",
+    "archived_events",
+    "attachments",
+    "basis_from_amount",
+    "basis_from_commodity",
+    "basis_provider",
+    "basis_rate_as_of",
+    "basis_rate_inverted",
+    "basis_rate_used",
+    "before",
+    "canonical",
+    "category_rule",
+    "confirm",
+    "conversions",
+    "correction",
+    "counted",
+    "denominations",
+    "entered_by",
+    "event_id",
+    "fee",
+    "import_id",
+    "import_source",
+    "kept_events",
+    "ledger",
+    "limit_overrides",
+    "occurrence",
+    "paste_rule",
+    "paste_text",
+    "payee",
+    "pending_to",
+    "quick_entry",
+    "rate_fallback",
+    "rate_resolved_as_of",
+    "realized_gain",
+    "refunds",
+    "reprice",
+    "resolves",
+    "revoked_device",
+    "settles",
+    "spread",
+    "supersedes",
+    "task_id",
+    "tax",
+    "template_event",
+    "tip",
+    "transit_account",
+    "unrounded_to_amount",
+    "voids",
+];
+
+/// `--meta` value: "<key>=<value>".
+fn parse_meta(raw: &str) -> Result<(String, String)> {
+    let Some((key, value)) = raw.split_once('=') else {
+        return Err(anyhow!("Invalid --meta '{raw}'. Expected KEY=VALUE"));
+    };
+    let key = key.trim();
+    if key.is_empty() {
+        return Err(anyhow!("Invalid --meta '{raw}': empty key"));
+    }
+    Ok((key.to_string(), value.trim().to_string()))
+}
+
+/// Merges `--meta` pairs into an event's metadata without touching Bankero's own keys.
+fn with_meta(mut metadata: serde_json::Value, meta: &[String]) -> Result<serde_json::Value> {
+    for raw in meta {
+        let (key, value) = parse_meta(raw)?;
+        if RESERVED_META_KEYS.contains(&key.as_str()) || !metadata[key.as_str()].is_null() {
+            return Err(anyhow!(
+                "--meta key '{key}' is reserved by Bankero; pick another name"
+            ));
+        }
+        metadata[key.as_str()] = serde_json::Value::String(value);
+    }
+    Ok(metadata)
+}
+
+/// Whether `metadata` has `key` with `value` (non-string values are compared as JSON).
+fn meta_matches(metadata: &serde_json::Value, key: &str, value: &str) -> bool {
+    match &metadata[key] {
+        serde_json::Value::Null => false,
+        serde_json::Value::String(s) => s == value,
+        other => serde_json::from_str::<serde_json::Value>(value).is_ok_and(|v| v == *other),
+    }
+}
+
 fn build_tag_event(
     cfg: &AppConfig,
     event_id: Uuid,
@@ -1305,6 +3518,7 @@ fn build_tag_event(
         tags: common.tags,
         category: common.category,
         note: common.note,
+        reference: common.reference,
         rate_context: RateContext {
             provider: None,
             override_rate: None,
@@ -1313,7 +3527,15 @@ fn build_tag_event(
             as_of,
         },
         basis,
-        metadata: serde_json::json!({"target": target, "event_id": event_id.to_string(), "confirm": common.confirm}),
+        metadata: with_meta(
+            serde_json::json!({
+                "target": target,
+                "event_id": event_id.to_string(),
+                "confirm": common.confirm,
+                "entered_by": entered_by(cfg, common.entered_by.as_deref()),
+            }),
+            &common.meta,
+        )?,
     })
 }
 
@@ -1337,19 +3559,130 @@ fn parse_fixed_basis(raw: &Option<String>) -> Option<BasisContext> {
 
 // parse_cross_currency_tail removed (explicit positionals used instead)
 
-fn parse_split_to(raw: &str, commodity: &str) -> Result<(String, Decimal)> {
-    // Split format: <account>:<amount>
-    let (account, amount_raw) = raw
-        .rsplit_once(':')
-        .ok_or_else(|| anyhow!("Invalid --to split '{raw}'. Expected <account>:<amount>"))?;
-    let amount = amount_raw
-        .parse::<Decimal>()
-        .with_context(|| format!("Invalid split amount in '{raw}'"))?;
+/// A `--to` split amount: fixed, or a share of the buy amount (`60%` is 0.6).
+enum SplitAmount {
+    Fixed(Decimal),
+    Percent(Decimal),
+}
+
+fn parse_split_amount(raw: &str) -> Option<SplitAmount> {
+    match raw.strip_suffix('%') {
+        Some(pct) => pct
+            .parse::<Decimal>()
+            .ok()
+            .map(|p| SplitAmount::Percent(p / Decimal::from(100u32))),
+        None => raw.parse::<Decimal>().ok().map(SplitAmount::Fixed),
+    }
+}
+
+fn parse_split_to(raw: &str, commodity: &str) -> Result<(String, SplitAmount, String)> {
+    // Split format: <account>:<amount>[:<commodity>] where amount may be a percentage.
+    let (head, last) = raw.rsplit_once(':').ok_or_else(|| {
+        anyhow!("Invalid --to split '{raw}'. Expected <account>:<amount>[:<commodity>]")
+    })?;
+    let (account, amount_raw, split_commodity) = match head.rsplit_once(':') {
+        Some((account, amount_raw))
+            if parse_split_amount(last).is_none() && parse_split_amount(amount_raw).is_some() =>
+        {
+            (account, amount_raw, last.to_ascii_uppercase())
+        }
+        _ => (head, last, commodity.to_string()),
+    };
+    let amount =
+        parse_split_amount(amount_raw).ok_or_else(|| anyhow!("Invalid split amount in '{raw}'"))?;
     if account.is_empty() {
         return Err(anyhow!("Invalid --to split '{raw}': empty account"));
     }
-    let _ = commodity;
-    Ok((account.to_string(), amount))
+    Ok((account.to_string(), amount, split_commodity))
+}
+
+/// Parses `--to` splits, turning percentages into amounts of the buy. Each share is
+/// rounded down to the commodity's places (two without a rule); when the splits cover
+/// the whole amount, the leftover goes to the last percentage split so they still sum.
+fn resolve_splits(
+    db: &Db,
+    splits: &[String],
+    amount: Decimal,
+    commodity: &str,
+) -> Result<Vec<(String, Decimal, String)>> {
+    let places = crate::commodities::rule_for(db, commodity)?
+        .map(|rule| rule.places)
+        .unwrap_or(2);
+    let mut out: Vec<(String, Decimal, String)> = Vec::new();
+    let mut fixed = Decimal::ZERO;
+    let mut percent = Decimal::ZERO;
+    let mut last_percent = None;
+    let mut foreign = false;
+    for raw in splits {
+        let (account, split_amount, split_commodity) = parse_split_to(raw, commodity)?;
+        let same = split_commodity.eq_ignore_ascii_case(commodity);
+        let value = match split_amount {
+            SplitAmount::Fixed(v) => {
+                if same {
+                    fixed += v;
+                } else {
+                    foreign = true;
+                }
+                v
+            }
+            SplitAmount::Percent(p) => {
+                if !same {
+                    return Err(anyhow!(
+                        "Percentage split '{raw}' must be in the buy commodity {commodity}"
+                    ));
+                }
+                if p <= Decimal::ZERO {
+                    return Err(anyhow!("Percentage split '{raw}' must be positive"));
+                }
+                percent += p;
+                last_percent = Some(out.len());
+                (amount * p).round_dp_with_strategy(places, rust_decimal::RoundingStrategy::ToZero)
+            }
+        };
+        out.push((account, value, split_commodity));
+    }
+    let Some(last) = last_percent else {
+        return Ok(out);
+    };
+    if fixed.is_zero() && !foreign && percent != Decimal::ONE {
+        return Err(anyhow!(
+            "Percentage splits add up to {}%, not 100%",
+            (percent * Decimal::from(100u32)).normalize()
+        ));
+    }
+    if fixed + amount * percent == amount {
+        let allocated: Decimal = out
+            .iter()
+            .filter(|(_, _, c)| c.eq_ignore_ascii_case(commodity))
+            .map(|(_, v, _)| *v)
+            .sum();
+        out[last].1 += amount - allocated;
+    }
+    Ok(out)
+}
+
+/// The event flags that act when the event is written rather than when it is built.
+#[derive(Default)]
+struct WriteOptions {
+    confirm: bool,
+    override_limits: bool,
+    dry_run: bool,
+    attach: Vec<std::path::PathBuf>,
+    /// The user already agreed to this event (e.g. `cashcount --yes`), so
+    /// `confirm_over` does not ask again.
+    approved: bool,
+}
+
+impl WriteOptions {
+    fn of(common: &crate::cli::CommonEventFlags) -> Self {
+        Self {
+            confirm: common.confirm,
+            override_limits: common.override_limits,
+            dry_run: common.dry_run,
+            attach: common.attach.clone(),
+            approved: false,
+        }
+    }
 }
 
 fn maybe_confirm_and_insert(
@@ -1357,9 +3690,85 @@ fn maybe_confirm_and_insert(
     cfg: &AppConfig,
     event_id: Uuid,
     payload: &EventPayload,
-    confirm: bool,
-) -> Result<()> {
+    write: &WriteOptions,
+) -> Result<bool> {
     let mut payload = payload.clone();
+    crate::commodities::check_known(db, cfg, &payload)?;
+    crate::commodities::check_account_defaults(db, &payload)?;
+    crate::limits::enforce(db, &mut payload, write.override_limits)?;
+    let attachments = crate::attachments::read(&write.attach)?;
+    crate::attachments::add_to(&mut payload, &attachments);
+    // A dry run resolves rates and basis exactly like confirm mode, then stops short of
+    // the prompt.
+    let dry_run = write.dry_run;
+    // Task occurrences were approved when the task was created.
+    let mut over_threshold = !write.confirm
+        && !write.approved
+        && !dry_run
+        && payload.metadata["task_id"].is_null()
+        && over_confirm_threshold(db, cfg, &payload)?;
+    // A batch can't answer the prompt: it fails unless the batch was run with --yes.
+    match crate::batch::yes() {
+        Some(true) => over_threshold = false,
+        Some(false) if over_threshold => {
+            return Err(anyhow!(
+                "This event is over confirm_over ({}) and a batch can't ask; re-run the batch with --yes to write it",
+                cfg.confirm_over.as_deref().unwrap_or_default()
+            ));
+        }
+        _ => {}
+    }
+    let confirm = write.confirm || dry_run || over_threshold;
+    // The realized gain is only worked out for a sell someone is about to look at.
+    if confirm && payload.action == "sell" {
+        crate::gains::preview_sell(db, &mut payload)?;
+    }
+    if payload.effective_at > now_utc() && payload.metadata["task_id"].is_null() {
+        eprintln!(
+            "Pending until {}: balances leave this event out until then (see `bankero pending`).",
+            payload.effective_at.to_rfc3339()
+        );
+    }
+    // A rate typed on the command line was not looked up, so nothing fell back. Computed
+    // moves record the stored rate as an override; that one did fall back.
+    let typed_rate = match (
+        payload.rate_context.override_rate,
+        &payload.rate_context.provider,
+        &payload.rate_context.base,
+        &payload.rate_context.quote,
+    ) {
+        (None, ..) => false,
+        (Some(rate), Some(provider), Some(base), Some(quote)) => matches!(
+            resolve_rate_use(
+                db,
+                &normalize_provider(provider),
+                base,
+                quote,
+                payload.rate_context.as_of,
+                Some(rate)
+            )?,
+            RateUse::Override { .. }
+        ),
+        (Some(_), ..) => true,
+    };
+    if let Some(provider) = payload.rate_context.provider.clone()
+        && !typed_rate
+        && let Some(fallback) =
+            crate::providers::business_day_fallback(db, &provider, payload.rate_context.as_of)?
+    {
+        eprintln!(
+            "Note: @{} does not publish on {} ({}); using the rate from {}.",
+            normalize_provider(&provider),
+            fallback.requested,
+            fallback.reason,
+            fallback.business_day
+        );
+        payload.metadata["rate_fallback"] = serde_json::json!({
+            "requested": fallback.requested.to_string(),
+            "business_day": fallback.business_day.to_string(),
+            "reason": fallback.reason,
+        });
+    }
 
     // Deterministic provider resolution (offline): if a provider is set but no override rate
     // exists, in confirm mode we resolve it from the local rate store.
@@ -1388,7 +3797,9 @@ fn maybe_confirm_and_insert(
             .to_ascii_uppercase();
 
         let as_of = payload.rate_context.as_of;
-        let Some((found_as_of, rate)) = db.get_rate_as_of(&provider, &base, &quote, as_of)? else {
+        let Some((found_as_of, rate)) =
+            crate::providers::rate_as_of(db, &provider, &base, &quote, as_of)?
+        else {
             return Err(anyhow!(
                 "No stored rate for {} ({} per {}) at or before {}. Set one with: bankero rate set {} {} {} <rate> --as-of <rfc3339>\nOr pass an explicit override like {}:<rate>.",
                 provider_display,
@@ -1407,15 +3818,16 @@ fn maybe_confirm_and_insert(
             serde_json::Value::String(found_as_of.to_rfc3339());
         eprintln!(
             "Using {} rate {} (as of {}).",
-            provider_display,
+            crate::providers::describe(db, &provider_display)?,
             rate,
             found_as_of.to_rfc3339()
         );
     }
 
     if !confirm {
+        crate::attachments::save(db, &attachments)?;
         db.insert_event(event_id, &payload)?;
-        return Ok(());
+        return Ok(true);
     }
 
     // Deterministic basis computation: if a provider-based basis is requested,
@@ -1444,6 +3856,7 @@ fn maybe_confirm_and_insert(
             from_amount,
         )
         .with_context(|| format!("Failed to compute basis via {provider_display}"))?;
+        let basis_amount = crate::commodities::round(db, &to_commodity, basis_amount)?;
 
         payload.basis = Some(BasisContext::Fixed {
             amount: basis_amount,
@@ -1459,7 +3872,9 @@ fn maybe_confirm_and_insert(
 
         eprintln!(
             "Basis: {} {} (via {}).",
-            basis_amount, to_commodity, provider_display
+            basis_amount,
+            to_commodity,
+            crate::providers::describe(db, &provider_display)?
         );
     }
 
@@ -1469,24 +3884,118 @@ fn maybe_confirm_and_insert(
         payload.rate_context.override_rate,
         payload.rate_context.base.clone(),
         payload.rate_context.quote.clone(),
-    ) {
-        if let Some(quote_amount) = quote_amount_from_postings(&payload.postings, &quote) {
-            if !rate.is_zero() {
-                let value = (quote_amount / rate).round_dp(2);
-                eprintln!(
-                    "{} rate is {}. Transaction value: {} {}.",
-                    provider, rate, value, base
-                );
-            }
-        }
+    ) && let Some(quote_amount) = quote_amount_from_postings(&payload.postings, &quote)
+        && !rate.is_zero()
+    {
+        let value = crate::commodities::round_for_display(db, &base, quote_amount / rate)?;
+        eprintln!(
+            "{} rate is {}. Transaction value: {} {}.",
+            crate::providers::describe(db, &provider)?,
+            rate,
+            value,
+            base
+        );
+    }
+
+    if dry_run {
+        crate::show::print_event(db, event_id, &payload)?;
+        println!("Dry run: nothing was written.");
+        return Ok(false);
     }
 
     if !prompt_yes_no("Proceed? [Y/n] ")? {
-        return Ok(());
+        return Ok(false);
     }
 
+    crate::attachments::save(db, &attachments)?;
     db.insert_event(event_id, &payload)?;
-    Ok(())
+    Ok(true)
+}
+
+/// `confirm_over` value: "<amount> <commodity>".
+fn parse_confirm_over(raw: &str) -> Result<(Decimal, String)> {
+    let mut parts = raw.split_whitespace();
+    let (Some(amount), Some(commodity), None) = (parts.next(), parts.next(), parts.next()) else {
+        return Err(anyhow!(
+            "Invalid confirm_over '{raw}'. Expected \"<amount> <commodity>\" (e.g. \"100 USD\")"
+        ));
+    };
+    let amount = parse_decimal(amount.to_string(), "confirm_over amount")?;
+    if amount.is_sign_negative() {
+        return Err(anyhow!("confirm_over must not be negative"));
+    }
+    Ok((amount, commodity.to_ascii_uppercase()))
+}
+
+/// Whether `confirm_over` forces confirm mode for this event.
+///
+/// The primary outgoing amount is valued in the threshold's commodity with the event's own
+/// rate (its override, else its provider's stored rate). Events that can't be valued that
+/// way are not forced.
+fn over_confirm_threshold(db: &Db, cfg: &AppConfig, payload: &EventPayload) -> Result<bool> {
+    let Some(raw) = cfg.confirm_over.as_deref() else {
+        return Ok(false);
+    };
+    let (threshold, to) = parse_confirm_over(raw)?;
+    let Some((amount, from)) = primary_outgoing_amount(&payload.postings) else {
+        return Ok(false);
+    };
+    let from = from.to_ascii_uppercase();
+    let rc = &payload.rate_context;
+    let pair = |base: &str, quote: &str| {
+        rc.base.as_deref().map(str::to_ascii_uppercase).as_deref() == Some(base)
+            && rc.quote.as_deref().map(str::to_ascii_uppercase).as_deref() == Some(quote)
+    };
+    let value = if from == to {
+        Some(amount)
+    } else if let Some(rate) = rc.override_rate.filter(|r| !r.is_zero()) {
+        if pair(&from, &to) {
+            Some(amount * rate)
+        } else if pair(&to, &from) {
+            Some(amount / rate)
+        } else {
+            None
+        }
+    } else if let Some(provider) = rc.provider.as_deref() {
+        resolve_and_convert(
+            db,
+            &normalize_provider(provider),
+            &from,
+            &to,
+            rc.as_of,
+            amount,
+        )
+        .ok()
+        .map(|(value, ..)| value)
+    } else {
+        latest_rate_value(db, &from, &to, amount)?
+    };
+    let Some(value) = value else {
+        eprintln!("No {from}/{to} rate to compare {amount} {from} with confirm_over ({raw}).");
+        return Ok(true);
+    };
+    if value <= threshold {
+        return Ok(false);
+    }
+    eprintln!(
+        "{amount} {from} is worth {} {to}, over confirm_over ({raw}).",
+        crate::commodities::round_for_display(db, &to, value)?
+    );
+    Ok(true)
+}
+
+/// `amount` of `from` in `to` at the most recent rate any provider stored for the pair
+/// (either way round), for events that carry no rate of their own.
+fn latest_rate_value(db: &Db, from: &str, to: &str, amount: Decimal) -> Result<Option<Decimal>> {
+    let direct = db.get_latest_rate(from, to)?;
+    let inverse = db.get_latest_rate(to, from)?;
+    let value = match (direct, inverse) {
+        (Some((_, at, rate)), Some((_, inv_at, _))) if at >= inv_at => Some(amount * rate),
+        (Some((_, _, rate)), None) => Some(amount * rate),
+        (_, Some((_, _, rate))) if !rate.is_zero() => Some(amount / rate),
+        _ => None,
+    };
+    Ok(value)
 }
 
 fn quote_amount_from_postings(postings: &[Posting], quote_commodity: &str) -> Option<Decimal> {
@@ -1550,28 +4059,124 @@ fn resolve_and_convert(
     as_of: DateTime<Utc>,
     amount: Decimal,
 ) -> Result<(Decimal, Decimal, bool, DateTime<Utc>)> {
+    resolve_and_convert_with(db, provider, from, to, as_of, amount, None)
+}
+
+/// A stored or interpolated rate: quote per base at `as_of`.
+struct RatePoint {
+    as_of: DateTime<Utc>,
+    rate: Decimal,
+    /// The stored points an interpolated rate lies between.
+    between: Option<(DateTime<Utc>, DateTime<Utc>)>,
+}
+
+/// The rate in effect at `as_of`: the latest stored point at or before it or, with
+/// `Interpolation::Linear`, the straight line between the points around it.
+///
+/// Interpolation is for valuing holdings and reports only; recorded events always
+/// carry a rate that was actually stored.
+fn rate_at(
+    db: &Db,
+    provider: &str,
+    base: &str,
+    quote: &str,
+    as_of: DateTime<Utc>,
+    interpolate: Option<Interpolation>,
+) -> Result<Option<RatePoint>> {
+    let Some((before_at, before)) = crate::providers::rate_as_of(db, provider, base, quote, as_of)?
+    else {
+        return Ok(None);
+    };
+    let point = RatePoint {
+        as_of: before_at,
+        rate: before,
+        between: None,
+    };
+    match interpolate {
+        None => Ok(Some(point)),
+        Some(Interpolation::Linear) => {
+            if before_at == as_of {
+                return Ok(Some(point));
+            }
+            let Some((after_at, after)) = db.get_rate_after(provider, base, quote, as_of)? else {
+                return Ok(Some(point));
+            };
+            let span = Decimal::from((after_at - before_at).num_seconds());
+            let elapsed = Decimal::from((as_of - before_at).num_seconds());
+            if span.is_zero() {
+                return Ok(Some(point));
+            }
+            Ok(Some(RatePoint {
+                as_of,
+                rate: (before + (after - before) * elapsed / span)
+                    .round_dp(10)
+                    .normalize(),
+                between: Some((before_at, after_at)),
+            }))
+        }
+    }
+}
+
+fn resolve_and_convert_with(
+    db: &Db,
+    provider: &str,
+    from: &str,
+    to: &str,
+    as_of: DateTime<Utc>,
+    amount: Decimal,
+    interpolate: Option<Interpolation>,
+) -> Result<(Decimal, Decimal, bool, DateTime<Utc>)> {
+    try_resolve_and_convert(db, provider, from, to, as_of, amount, interpolate)?.ok_or_else(|| {
+        anyhow!(
+            "No stored rate for @{} between {} and {} at or before {}",
+            provider,
+            from,
+            to,
+            as_of.to_rfc3339()
+        )
+    })
+}
+
+/// Converted amount, rate used, whether it was stored inverted, and the rate's `as_of`.
+type Conversion = (Decimal, Decimal, bool, DateTime<Utc>);
+
+/// Like [`resolve_and_convert_with`], but a missing rate is `Ok(None)` so callers can
+/// word that case themselves while other failures still propagate.
+fn try_resolve_and_convert(
+    db: &Db,
+    provider: &str,
+    from: &str,
+    to: &str,
+    as_of: DateTime<Utc>,
+    amount: Decimal,
+    interpolate: Option<Interpolation>,
+) -> Result<Option<Conversion>> {
     if from == to {
-        return Ok((amount, Decimal::ONE, false, as_of));
+        return Ok(Some((amount, Decimal::ONE, false, as_of)));
     }
 
-    if let Some((found_as_of, rate)) = db.get_rate_as_of(provider, from, to, as_of)? {
-        return Ok((amount * rate, rate, false, found_as_of));
+    if let Some(RatePoint {
+        as_of: found_as_of,
+        rate,
+        ..
+    }) = rate_at(db, provider, from, to, as_of, interpolate)?
+    {
+        return Ok(Some((amount * rate, rate, false, found_as_of)));
     }
 
-    if let Some((found_as_of, rate)) = db.get_rate_as_of(provider, to, from, as_of)? {
+    if let Some(RatePoint {
+        as_of: found_as_of,
+        rate,
+        ..
+    }) = rate_at(db, provider, to, from, as_of, interpolate)?
+    {
         if rate.is_zero() {
             return Err(anyhow!("Stored rate is zero"));
         }
-        return Ok((amount / rate, rate, true, found_as_of));
+        return Ok(Some((amount / rate, rate, true, found_as_of)));
     }
 
-    Err(anyhow!(
-        "No stored rate for @{} between {} and {} at or before {}",
-        provider,
-        from,
-        to,
-        as_of.to_rfc3339()
-    ))
+    Ok(None)
 }
 
 fn prompt_yes_no(prompt: &str) -> Result<bool> {
@@ -1586,68 +4191,87 @@ fn prompt_yes_no(prompt: &str) -> Result<bool> {
     Ok(matches!(s.to_ascii_lowercase().as_str(), "y" | "yes"))
 }
 
-fn prompt_decimal(prompt: &str) -> Result<Option<Decimal>> {
-    eprint!("{prompt}");
-    io::stderr().flush().ok();
-    let mut line = String::new();
-    io::stdin().read_line(&mut line)?;
-    let s = line.trim();
-    if s.is_empty() {
-        return Ok(None);
-    }
-    Ok(Some(s.parse::<Decimal>().context("Invalid decimal")?))
-}
-
-fn print_balance(
-    db: &Db,
+/// Sums postings per (account, commodity), optionally limited to an account prefix.
+fn compute_balances(
     events: &[StoredEvent],
     account_prefix: Option<&str>,
-    month_context: Option<&str>,
-) -> Result<()> {
+) -> BTreeMap<(String, String), Decimal> {
     let mut balances: BTreeMap<(String, String), Decimal> = BTreeMap::new();
     for e in events {
         for p in &e.payload.postings {
-            if let Some(prefix) = account_prefix {
-                if !p.account.starts_with(prefix) {
-                    continue;
-                }
+            if let Some(prefix) = account_prefix
+                && !p.account.starts_with(prefix)
+            {
+                continue;
             }
             let key = (p.account.clone(), p.commodity.clone());
             *balances.entry(key).or_insert(Decimal::ZERO) += p.amount;
         }
     }
+    balances
+}
 
-    if balances.is_empty() {
-        println!("(no balances)");
-        return Ok(());
-    }
+type BalanceMap = BTreeMap<(String, String), Decimal>;
 
-    for ((acct, comm), amt) in &balances {
-        println!("{acct}\t{comm}\t{amt}");
+/// One budget or piggy that reserves part of an account's balance, with the devices
+/// behind it (for `balance --explain`).
+struct Reservation {
+    kind: &'static str,
+    name: String,
+    id: Uuid,
+    device_id: Option<Uuid>,
+    account: String,
+    commodity: String,
+    amount: Decimal,
+    /// Piggy funds summed per device that added them.
+    funds: BTreeMap<Option<Uuid>, Decimal>,
+}
+
+/// Balance output sections: real balances, then (only when something is reserved)
+/// reserved budgets, reserved piggies and the resulting effective balance. Every
+/// reservation that went into them is also pushed onto `reservations`.
+fn balance_sections(
+    db: &Db,
+    events: &[StoredEvent],
+    account_prefix: Option<&str>,
+    month_context: Option<&str>,
+    reservations: &mut Vec<Reservation>,
+) -> Result<Vec<(&'static str, BalanceMap)>> {
+    let balances = compute_balances(events, account_prefix);
+    if balances.is_empty() {
+        return Ok(Vec::new());
     }
 
     // Budget reservations (virtual deficits): only applies to budgets scoped to an account.
     // Month context: budget.month if present, else --month if provided, else current month.
     let budgets = db.list_budgets()?;
     if let Some(m) = month_context {
-        let _ = parse_month_range(m)?;
+        let _ = crate::period::month_range(m)?;
     }
     let now_month = current_month_yyyy_mm(now_utc());
     let default_month = month_context.unwrap_or(&now_month);
-    let mut reserved_budgets: BTreeMap<(String, String), Decimal> = BTreeMap::new();
+    let mut reserved_budgets: BalanceMap = BTreeMap::new();
     for b in budgets {
         let Some(acct) = &b.account else {
             continue;
         };
-        if let Some(prefix) = account_prefix {
-            if !acct.starts_with(prefix) {
-                continue;
-            }
+        if let Some(prefix) = account_prefix
+            && !acct.starts_with(prefix)
+        {
+            continue;
         }
 
         let month = b.month.clone().unwrap_or_else(|| default_month.to_string());
-        let (start, end) = parse_month_range(&month)?;
-        let actual = compute_budget_actual(events, start, end, &b);
+        let (start, end) = crate::period::month_range(&month)?;
+        // A budget whose spend can't be converted reserves nothing rather than hiding
+        // every balance.
+        let actual = match compute_budget_actual(db, events, start, end, &b, false) {
+            Ok(actual) => actual,
+            Err(e) => {
+                eprintln!("Budget '{}' reserves nothing: {e:#}", b.name);
+                continue;
+            }
+        };
         let remaining_budget = b.amount - actual;
         if remaining_budget <= Decimal::ZERO {
             continue;
@@ -1655,8 +4279,14 @@ fn print_balance(
 
         let reserve_amount = if let Some(from_prefix) = &b.auto_reserve_from {
             let until = b.auto_reserve_until_amount.unwrap_or(b.amount);
-            let funded = compute_budget_funded(events, start, end, acct, &b.commodity, from_prefix)
-                .min(until);
+            let funded = match compute_budget_funded(db, events, start, end, &b, acct, from_prefix)
+            {
+                Ok(funded) => funded.min(until),
+                Err(e) => {
+                    eprintln!("Budget '{}' reserves nothing: {e:#}", b.name);
+                    continue;
+                }
+            };
             let unspent_funded = (funded - actual).max(Decimal::ZERO);
             remaining_budget.min(unspent_funded)
         } else {
@@ -1666,18 +4296,64 @@ fn print_balance(
         if reserve_amount <= Decimal::ZERO {
             continue;
         }
-        let key = (acct.clone(), b.commodity.clone());
+
+        // With a provider, reserve in what the account actually holds when it holds a
+        // single other commodity (a USD budget on a VES account reserves VES), at the
+        // rate of the budget's month end (or now, for a month still running).
+        let mut key = (acct.clone(), b.commodity.clone());
+        let mut reserve_amount = reserve_amount;
+        if let Some(provider) = &b.provider {
+            let held: std::collections::BTreeSet<&String> = balances
+                .iter()
+                .filter(|((a, _), amt)| a.starts_with(acct.as_str()) && !amt.is_zero())
+                .map(|((_, c), _)| c)
+                .collect();
+            if let [held] = held.into_iter().collect::<Vec<_>>()[..]
+                && !held.eq_ignore_ascii_case(&b.commodity)
+            {
+                let converted = resolve_and_convert(
+                    db,
+                    provider,
+                    &b.commodity.to_ascii_uppercase(),
+                    &held.to_ascii_uppercase(),
+                    end.min(now_utc()),
+                    reserve_amount,
+                );
+                let (value, ..) = match converted {
+                    Ok(converted) => converted,
+                    Err(e) => {
+                        eprintln!(
+                            "Budget '{}' reserves nothing: converting {} to {} with @{}: {e:#}",
+                            b.name, b.commodity, held, provider
+                        );
+                        continue;
+                    }
+                };
+                key = (acct.clone(), held.clone());
+                reserve_amount = value.round_dp(2);
+            }
+        }
+        reservations.push(Reservation {
+            kind: "budget",
+            name: b.name.clone(),
+            id: b.id,
+            device_id: b.device_id,
+            account: key.0.clone(),
+            commodity: key.1.clone(),
+            amount: reserve_amount,
+            funds: BTreeMap::new(),
+        });
         *reserved_budgets.entry(key).or_insert(Decimal::ZERO) -= reserve_amount;
     }
 
     // Piggy reservations (virtual allocations): applies to the piggy's configured from_account.
     let piggies = db.list_piggies()?;
-    let mut reserved_piggies: BTreeMap<(String, String), Decimal> = BTreeMap::new();
+    let mut reserved_piggies: BalanceMap = BTreeMap::new();
     for p in piggies {
-        if let Some(prefix) = account_prefix {
-            if !p.from_account.starts_with(prefix) {
-                continue;
-            }
+        if let Some(prefix) = account_prefix
+            && !p.from_account.starts_with(prefix)
+        {
+            continue;
         }
 
         let funded = db.piggy_funded_total(p.id)?;
@@ -1686,57 +4362,439 @@ fn print_balance(
             continue;
         }
 
+        let mut funds = BTreeMap::new();
+        for f in db.list_piggy_funds(p.id)? {
+            *funds.entry(f.device_id).or_insert(Decimal::ZERO) += f.amount;
+        }
+        reservations.push(Reservation {
+            kind: "piggy",
+            name: p.name.clone(),
+            id: p.id,
+            device_id: p.device_id,
+            account: p.from_account.clone(),
+            commodity: p.commodity.clone(),
+            amount: reserved_amount,
+            funds,
+        });
+
         let key = (p.from_account.clone(), p.commodity.clone());
         *reserved_piggies.entry(key).or_insert(Decimal::ZERO) -= reserved_amount;
     }
 
     let has_any_reserved = !(reserved_budgets.is_empty() && reserved_piggies.is_empty());
+    if !has_any_reserved {
+        return Ok(vec![("balance", balances)]);
+    }
 
-    if has_any_reserved {
-        if !reserved_budgets.is_empty() {
-            println!();
-            println!("(reserved budgets)");
-            for ((acct, comm), amt) in &reserved_budgets {
-                println!("{acct}\t{comm}\t{amt}");
-            }
+    let mut effective: BalanceMap = balances.clone();
+    for (k, v) in reserved_budgets.iter().chain(reserved_piggies.iter()) {
+        *effective.entry(k.clone()).or_insert(Decimal::ZERO) += *v;
+    }
+
+    let mut sections = vec![("balance", balances)];
+    if !reserved_budgets.is_empty() {
+        sections.push(("reserved budgets", reserved_budgets));
+    }
+    if !reserved_piggies.is_empty() {
+        sections.push(("reserved piggies", reserved_piggies));
+    }
+    sections.push(("effective balance", effective));
+    Ok(sections)
+}
+
+/// `balance --explain`: each reservation behind the reserved sections, with the
+/// device that created it (and, for piggies, which devices funded it), so two
+/// devices showing different effective balances can see which rows one of them lacks.
+fn print_reservation_sources(
+    db: &Db,
+    cfg: &AppConfig,
+    events: &[StoredEvent],
+    account_prefix: Option<&str>,
+    month_context: Option<&str>,
+) -> Result<()> {
+    let mut reservations = Vec::new();
+    balance_sections(db, events, account_prefix, month_context, &mut reservations)?;
+    println!();
+    println!("Reservations:");
+    if reservations.is_empty() {
+        println!("  (nothing reserved)");
+        return Ok(());
+    }
+    let device = |id: Option<Uuid>| -> String {
+        let Some(id) = id else {
+            return "unknown device".to_string();
+        };
+        let short = &id.to_string()[..8];
+        if id == cfg.device_id {
+            let name = cfg.device_name.as_deref().unwrap_or(short);
+            return format!("{name} (this device)");
         }
+        // Other devices are known by the name on the latest event they wrote.
+        events
+            .iter()
+            .filter(|e| e.payload.device_id == id)
+            .max_by_key(|e| e.created_at)
+            .and_then(crate::approve::event_entered_by)
+            .map(|name| format!("{name} ({short})"))
+            .unwrap_or_else(|| short.to_string())
+    };
+    for r in &reservations {
+        println!(
+            "  {} {} ({})\t{}\t{}\t{}\tcreated on {}",
+            r.kind,
+            r.name,
+            &r.id.to_string()[..8],
+            r.account,
+            r.commodity,
+            r.amount,
+            device(r.device_id)
+        );
+        for (dev, amount) in &r.funds {
+            println!("    funded {amount} {} on {}", r.commodity, device(*dev));
+        }
+    }
+    Ok(())
+}
 
-        if !reserved_piggies.is_empty() {
-            println!();
-            println!("(reserved piggies)");
-            for ((acct, comm), amt) in &reserved_piggies {
-                println!("{acct}\t{comm}\t{amt}");
+fn print_balance(
+    db: &Db,
+    events: &[StoredEvent],
+    args: &crate::cli::BalanceArgs,
+    reference: &str,
+) -> Result<()> {
+    let convert = args.convert.as_deref();
+    let output = &args.output;
+    let by_commodity = args.by == crate::cli::BalanceGrouping::Commodity;
+    let sections = balance_sections(
+        db,
+        events,
+        args.account.as_deref(),
+        args.month.as_deref(),
+        &mut Vec::new(),
+    )?;
+
+    // Totals cover the listed accounts (the first section), one line per commodity.
+    let mut totals: BTreeMap<String, Decimal> = BTreeMap::new();
+    if let Some((_, map)) = sections.first() {
+        for ((_, comm), amt) in map {
+            *totals.entry(comm.clone()).or_insert(Decimal::ZERO) += *amt;
+        }
+    }
+    let grand_total = match convert {
+        Some(provider) if !totals.is_empty() => Some(convert_totals(
+            db,
+            &normalize_provider(provider),
+            reference,
+            &totals,
+        )?),
+        _ => None,
+    };
+
+    if output.is_table() {
+        let mut rows = Vec::new();
+        for (section, map) in &sections {
+            let mut section_rows: Vec<Vec<String>> = map
+                .iter()
+                .map(|((acct, comm), amt)| {
+                    vec![
+                        section.to_string(),
+                        acct.clone(),
+                        comm.clone(),
+                        amt.to_string(),
+                    ]
+                })
+                .collect();
+            if by_commodity {
+                section_rows.sort_by(|a, b| (&a[2], &a[1]).cmp(&(&b[2], &b[1])));
             }
+            rows.extend(section_rows);
+        }
+        for (comm, amt) in &totals {
+            rows.push(vec![
+                "totals".to_string(),
+                String::new(),
+                comm.clone(),
+                amt.to_string(),
+            ]);
         }
+        if let Some(total) = grand_total {
+            rows.push(vec![
+                "total".to_string(),
+                String::new(),
+                reference.to_string(),
+                total.to_string(),
+            ]);
+        }
+        return crate::output::write_table(
+            output,
+            &["section", "account", "commodity", "amount"],
+            &rows,
+        );
+    }
+    output.require_table_for_out()?;
 
+    if sections.is_empty() {
+        println!("(no balances)");
+        return Ok(());
+    }
+    for (i, (section, map)) in sections.iter().enumerate() {
+        if i > 0 {
+            println!();
+            println!("({section})");
+        }
+        if by_commodity {
+            print_balance_by_commodity(map);
+            continue;
+        }
+        for ((acct, comm), amt) in map {
+            println!("{acct}\t{comm}\t{amt}");
+        }
+    }
+    // Per-commodity subtotals already cover what the totals section would say.
+    if !by_commodity {
         println!();
-        println!("(effective balance)");
-        let mut effective: BTreeMap<(String, String), Decimal> = BTreeMap::new();
-        for (k, v) in &balances {
-            effective.insert(k.clone(), *v);
+        println!("(totals)");
+        for (comm, amt) in &totals {
+            println!("{comm}\t{amt}");
         }
-        for (k, v) in &reserved_budgets {
-            *effective.entry(k.clone()).or_insert(Decimal::ZERO) += *v;
+    }
+    if let Some(total) = grand_total {
+        if by_commodity {
+            println!();
         }
-        for (k, v) in &reserved_piggies {
-            *effective.entry(k.clone()).or_insert(Decimal::ZERO) += *v;
+        println!("total\t{reference}\t{total}");
+        if let Some(provider) = convert {
+            println!("via\t{}", crate::providers::describe(db, provider)?);
         }
+    }
+    Ok(())
+}
 
-        for ((acct, comm), amt) in &effective {
-            println!("{acct}\t{comm}\t{amt}");
+/// `balance --by commodity`: one block per commodity, its accounts indented, then a subtotal.
+fn print_balance_by_commodity(map: &BalanceMap) {
+    let mut by_commodity: BTreeMap<&str, Vec<(&str, Decimal)>> = BTreeMap::new();
+    for ((acct, comm), amt) in map {
+        by_commodity
+            .entry(comm.as_str())
+            .or_default()
+            .push((acct.as_str(), *amt));
+    }
+    for (i, (comm, rows)) in by_commodity.iter().enumerate() {
+        if i > 0 {
+            println!();
         }
+        println!("{comm}");
+        for (acct, amt) in rows {
+            println!("  {acct}\t{amt}");
+        }
+        let subtotal: Decimal = rows.iter().map(|(_, amt)| *amt).sum();
+        println!("  subtotal\t{subtotal}");
+    }
+}
+
+/// Sums per-commodity totals into `target` using `provider` rates as of now.
+/// Commodities without a usable rate are left out, with a note on stderr.
+fn convert_totals(
+    db: &Db,
+    provider: &str,
+    target: &str,
+    totals: &BTreeMap<String, Decimal>,
+) -> Result<Decimal> {
+    let now = now_utc();
+    let mut total = Decimal::ZERO;
+    let mut unpriced = Vec::new();
+    for (comm, amt) in totals {
+        match resolve_and_convert(db, provider, &comm.to_ascii_uppercase(), target, now, *amt) {
+            Ok((value, ..)) => total += value,
+            Err(_) => unpriced.push(comm.as_str()),
+        }
+    }
+    if !unpriced.is_empty() {
+        eprintln!(
+            "Note: no @{provider} rate to {target} for {}; excluded from the total.",
+            unpriced.join(", ")
+        );
+    }
+    Ok(total.round_dp(2))
+}
+
+/// Value of each commodity held under `account_prefix` at `at`, converted via `provider`.
+/// Commodities without a usable rate get `None`.
+fn wallet_holdings(
+    db: &Db,
+    events: &[StoredEvent],
+    account_prefix: &str,
+    provider: &str,
+    target: &str,
+    at: DateTime<Utc>,
+    interpolate: Option<Interpolation>,
+) -> Result<Vec<WalletHolding>> {
+    let mut amounts: BTreeMap<String, Decimal> = BTreeMap::new();
+    for e in events {
+        if e.effective_at > at {
+            continue;
+        }
+        for p in &e.payload.postings {
+            if p.account.starts_with(account_prefix) {
+                *amounts
+                    .entry(p.commodity.to_ascii_uppercase())
+                    .or_insert(Decimal::ZERO) += p.amount;
+            }
+        }
+    }
+
+    let mut out = Vec::new();
+    for (commodity, amount) in amounts {
+        if amount.is_zero() {
+            continue;
+        }
+        let priced =
+            try_resolve_and_convert(db, provider, &commodity, target, at, amount, interpolate)?
+                .map(|(value, rate, inverted, rate_as_of)| {
+                    let unit_rate = if inverted { Decimal::ONE / rate } else { rate };
+                    (unit_rate, rate_as_of, value)
+                });
+        out.push(WalletHolding {
+            commodity,
+            amount,
+            priced,
+        });
+    }
+    Ok(out)
+}
+
+struct WalletHolding {
+    commodity: String,
+    amount: Decimal,
+    /// (target per unit, rate as-of, value in target)
+    priced: Option<(Decimal, DateTime<Utc>, Decimal)>,
+}
+
+fn print_wallet(
+    db: &Db,
+    cfg: &AppConfig,
+    events: &[StoredEvent],
+    args: crate::cli::WalletArgs,
+) -> Result<()> {
+    let provider_display = match args.provider {
+        Some(provider) => provider,
+        None => {
+            let venue = args
+                .account
+                .split_once(':')
+                .map_or(args.account.as_str(), |(_, rest)| rest);
+            let venue = venue.split(':').next().unwrap_or_default();
+            if venue.is_empty() {
+                return Err(anyhow!(
+                    "No venue in '{}' to take a provider from; pass one, e.g. @binance",
+                    args.account
+                ));
+            }
+            format!("@{venue}")
+        }
+    };
+    let Some(token) = crate::domain::parse_provider_token(&provider_display) else {
+        return Err(anyhow!(
+            "Invalid provider token: {provider_display} (expected e.g. @binance)"
+        ));
+    };
+    let provider = normalize_provider(&token.provider);
+    let target = args
+        .in_commodity
+        .unwrap_or_else(|| cfg.reference_commodity.clone())
+        .to_ascii_uppercase();
+    let as_of = parse_rfc3339_or_now(args.as_of.as_deref())?;
+
+    let holdings = wallet_holdings(
+        db,
+        events,
+        &args.account,
+        &provider,
+        &target,
+        as_of,
+        args.interpolate,
+    )?;
+    if holdings.is_empty() {
+        println!("(no balances)");
+        return Ok(());
+    }
+
+    println!("commodity\tamount\trate\trate_as_of\tvalue");
+    let mut total = Decimal::ZERO;
+    let mut unpriced = 0usize;
+    for h in &holdings {
+        match &h.priced {
+            Some((rate, rate_as_of, value)) => {
+                total += *value;
+                let rate_as_of = if h.commodity == target {
+                    "-".to_string()
+                } else {
+                    rate_as_of.to_rfc3339()
+                };
+                println!(
+                    "{}\t{}\t{}\t{}\t{}",
+                    h.commodity,
+                    h.amount,
+                    rate.round_dp(8).normalize(),
+                    rate_as_of,
+                    value.round_dp(2)
+                );
+            }
+            None => {
+                unpriced += 1;
+                println!("{}\t{}\t-\t-\t-", h.commodity, h.amount);
+            }
+        }
+    }
+
+    println!();
+    println!("total\t{}\t{}", target, total.round_dp(2));
+    if unpriced > 0 {
+        eprintln!(
+            "Note: {unpriced} commodity(ies) have no {provider_display} rate to {target} and are excluded from the total."
+        );
+    }
+
+    // 30-day change: same valuation, replayed 30 days earlier.
+    let past_at = as_of - chrono::Duration::days(30);
+    let past = wallet_holdings(
+        db,
+        events,
+        &args.account,
+        &provider,
+        &target,
+        past_at,
+        args.interpolate,
+    )?;
+    if past.iter().any(|h| h.priced.is_none()) || unpriced > 0 {
+        println!("change_30d\t{target}\tn/a");
+        return Ok(());
+    }
+    let past_total: Decimal = past.iter().filter_map(|h| h.priced.map(|p| p.2)).sum();
+    let change = (total - past_total).round_dp(2);
+    if past_total.is_zero() {
+        println!("change_30d\t{target}\t{change}");
+    } else {
+        let pct = (change / past_total * Decimal::from(100u32)).round_dp(2);
+        println!("change_30d\t{target}\t{change}\t{pct}%");
     }
     Ok(())
 }
 
 fn filter_events(
+    cfg: &AppConfig,
     events: &[StoredEvent],
     args: &crate::cli::ReportArgs,
 ) -> Result<Vec<StoredEvent>> {
     let mut out = Vec::new();
+    let pending = if args.unapproved {
+        crate::approve::pending_approval(events, &entered_by(cfg, None))
+    } else {
+        HashSet::new()
+    };
 
     let month_range = if let Some(m) = &args.month {
-        Some(parse_month_range(m)?)
+        Some(crate::period::month_range(m)?)
     } else {
         None
     };
@@ -1746,37 +4804,42 @@ fn filter_events(
     } else {
         None
     };
+    let meta = args
+        .meta
+        .iter()
+        .map(|raw| parse_meta(raw))
+        .collect::<Result<Vec<_>>>()?;
 
     for e in events {
-        if let Some((start, end)) = month_range {
-            if e.effective_at < start || e.effective_at > end {
-                continue;
-            }
+        if let Some((start, end)) = month_range
+            && (e.effective_at < start || e.effective_at > end)
+        {
+            continue;
         }
-        if let Some((start, end)) = explicit_range {
-            if e.effective_at < start || e.effective_at > end {
-                continue;
-            }
+        if let Some((start, end)) = explicit_range
+            && (e.effective_at < start || e.effective_at > end)
+        {
+            continue;
         }
         if let Some(acct) = &args.account {
             let any = e
                 .payload
                 .postings
                 .iter()
-                .any(|p| p.account.starts_with(acct));
+                .any(|p| account_under(&p.account, acct));
             if !any {
                 continue;
             }
         }
-        if let Some(cat) = &args.category {
-            if e.payload.category.as_deref() != Some(cat.as_str()) {
-                continue;
-            }
+        if let Some(cat) = &args.category
+            && e.payload.category.as_deref() != Some(cat.as_str())
+        {
+            continue;
         }
-        if let Some(tag) = &args.tag {
-            if !e.payload.tags.iter().any(|t| t == tag) {
-                continue;
-            }
+        if let Some(tag) = &args.tag
+            && !e.payload.tags.iter().any(|t| t == tag)
+        {
+            continue;
         }
         if let Some(comm) = &args.commodity {
             let any = e.payload.postings.iter().any(|p| p.commodity == *comm);
@@ -1784,54 +4847,448 @@ fn filter_events(
                 continue;
             }
         }
+        if let Some(action) = &args.action
+            && e.action != *action
+        {
+            continue;
+        }
+        if let Some(needle) = &args.reference {
+            let needle = needle.to_lowercase();
+            let matches = e
+                .payload
+                .reference
+                .as_deref()
+                .is_some_and(|r| r.to_lowercase().contains(&needle));
+            if !matches {
+                continue;
+            }
+        }
+        if let Some(project) = &args.project
+            && e.payload.project != *project
+        {
+            continue;
+        }
+        if let Some(person) = &args.entered_by
+            && crate::approve::event_entered_by(e) != Some(person.as_str())
+        {
+            continue;
+        }
+        if !meta
+            .iter()
+            .all(|(k, v)| meta_matches(&e.payload.metadata, k, v))
+        {
+            continue;
+        }
+        if args.unapproved && !pending.contains(&e.event_id) {
+            continue;
+        }
 
         out.push(e.clone());
     }
     Ok(out)
 }
 
-fn print_report(events: &[StoredEvent]) {
+/// Prints `events`; `all` is the whole journal, so voids outside the filter still mark
+/// the events they cancelled.
+fn print_report(
+    events: &[StoredEvent],
+    all: &[StoredEvent],
+    output: &crate::cli::OutputFlags,
+) -> Result<()> {
+    if output.is_table() {
+        // One row per posting so spreadsheets can pivot on account/commodity.
+        let mut rows = Vec::new();
+        for e in events {
+            for p in &e.payload.postings {
+                rows.push(vec![
+                    crate::output::iso_date(e.effective_at),
+                    e.effective_at.to_rfc3339(),
+                    e.action.clone(),
+                    e.event_id.to_string(),
+                    p.account.clone(),
+                    p.commodity.clone(),
+                    p.amount.to_string(),
+                    e.payload.category.clone().unwrap_or_default(),
+                    e.payload.tags.join(";"),
+                    e.payload.note.clone().unwrap_or_default(),
+                    e.payload.project.clone(),
+                ]);
+            }
+        }
+        return crate::output::write_table(
+            output,
+            &[
+                "date",
+                "effective_at",
+                "action",
+                "event_id",
+                "account",
+                "commodity",
+                "amount",
+                "category",
+                "tags",
+                "note",
+                "project",
+            ],
+            &rows,
+        );
+    }
+    output.require_table_for_out()?;
+
     if events.is_empty() {
         println!("(no events)");
-        return;
+        return Ok(());
     }
+    let voided = crate::event::voided(all);
     for e in events {
-        println!(
+        let mut line = format!(
             "{}\t{}\t{}",
             e.effective_at.to_rfc3339(),
             e.action,
             e.event_id
         );
+        if let Some(reference) = &e.payload.reference {
+            line.push_str(&format!("\tref:{reference}"));
+        }
+        if let Some(v) = voided.get(&e.event_id) {
+            line.push_str(&format!("\tvoided by {}", v.event_id));
+        }
+        if e.action == crate::event::VOID_ACTION
+            && let Some(target) = e.payload.metadata["voids"].as_str()
+        {
+            line.push_str(&format!("\tvoids {target}"));
+        }
+        println!("{line}");
+    }
+    Ok(())
+}
+
+/// Whether `account` is one of your own (balance-sheet) accounts.
+fn is_own_account(account: &str) -> bool {
+    matches!(
+        account.split(':').next().unwrap_or_default(),
+        "assets" | "liabilities"
+    )
+}
+
+/// Whether `account` is `prefix` or one of its subaccounts (`assets:bank` covers
+/// `assets:bank:checking`, not `assets:bankinter`).
+fn account_under(account: &str, prefix: &str) -> bool {
+    account == prefix || account.starts_with(&format!("{prefix}:"))
+}
+
+/// Money in and out per commodity across the listed events. Legs on your assets and
+/// liabilities count, so "what did client:acme pay me" is the inflow of the events
+/// filtered by `--account income:client:acme`. When the filter itself names one of those
+/// accounts, only its legs count. Voided events and the voids themselves are left out,
+/// so a cancelled payment neither adds to the inflow nor to the outflow.
+fn report_totals(
+    events: &[StoredEvent],
+    all: &[StoredEvent],
+    account: Option<&str>,
+) -> BTreeMap<String, (Decimal, Decimal)> {
+    let own_filter = account.filter(|a| is_own_account(a));
+    let voided = crate::event::voided(all);
+    let mut totals: BTreeMap<String, (Decimal, Decimal)> = BTreeMap::new();
+    for e in events {
+        if e.action == crate::event::VOID_ACTION || voided.contains_key(&e.event_id) {
+            continue;
+        }
+        for p in &e.payload.postings {
+            let counts = match own_filter {
+                Some(prefix) => account_under(&p.account, prefix),
+                None => is_own_account(&p.account),
+            };
+            if !counts {
+                continue;
+            }
+            let (inflow, outflow) = totals
+                .entry(p.commodity.clone())
+                .or_insert((Decimal::ZERO, Decimal::ZERO));
+            if p.amount.is_sign_negative() {
+                *outflow -= p.amount;
+            } else {
+                *inflow += p.amount;
+            }
+        }
     }
+    totals
 }
 
-fn parse_month_range(raw: &str) -> Result<(DateTime<Utc>, DateTime<Utc>)> {
-    let (y, m) = raw
-        .split_once('-')
-        .ok_or_else(|| anyhow!("Invalid --month. Expected YYYY-MM"))?;
-    let year: i32 = y.parse()?;
-    let month: u32 = m.parse()?;
-    if !(1..=12).contains(&month) {
-        return Err(anyhow!("Invalid month value"));
+/// `report --totals`: the footer with [`report_totals`].
+fn print_report_totals(events: &[StoredEvent], all: &[StoredEvent], account: Option<&str>) {
+    let totals = report_totals(events, all, account);
+    println!("Totals:");
+    if totals.is_empty() {
+        println!("  (no money moved)");
     }
-    let start_date =
-        NaiveDate::from_ymd_opt(year, month, 1).ok_or_else(|| anyhow!("Invalid date"))?;
-    let start = Utc.from_utc_datetime(&NaiveDateTime::new(
-        start_date,
-        NaiveTime::from_hms_opt(0, 0, 0).unwrap(),
-    ));
-    let (next_year, next_month) = if month == 12 {
-        (year + 1, 1)
-    } else {
-        (year, month + 1)
+    for (commodity, (inflow, outflow)) in totals {
+        println!(
+            "  {commodity}\tin {}\tout {}\tnet {}",
+            inflow.normalize(),
+            outflow.normalize(),
+            (inflow - outflow).normalize()
+        );
+    }
+}
+
+/// What `report --save-baseline` stores: the events a report covered and its totals.
+#[derive(Debug, Serialize, Deserialize)]
+struct ReportSnapshot {
+    events: Vec<SnapshotEvent>,
+    totals: Vec<SnapshotTotal>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct SnapshotEvent {
+    event_id: Uuid,
+    effective_at: DateTime<Utc>,
+    action: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct SnapshotTotal {
+    account: String,
+    commodity: String,
+    amount: Decimal,
+}
+
+fn report_snapshot(events: &[StoredEvent], args: &crate::cli::ReportArgs) -> ReportSnapshot {
+    ReportSnapshot {
+        events: events
+            .iter()
+            .map(|e| SnapshotEvent {
+                event_id: e.event_id,
+                effective_at: e.effective_at,
+                action: e.action.clone(),
+            })
+            .collect(),
+        totals: compute_balances(events, args.account.as_deref())
+            .into_iter()
+            .map(|((account, commodity), amount)| SnapshotTotal {
+                account,
+                commodity,
+                amount,
+            })
+            .collect(),
+    }
+}
+
+fn report_filters_json(args: &crate::cli::ReportArgs) -> serde_json::Value {
+    let mut filters = serde_json::json!({
+        "month": args.month,
+        "range": args.range,
+        "account": args.account,
+        "category": args.category,
+        "tag": args.tag,
+        "commodity": args.commodity,
+        "project": args.project,
+    });
+    // Only when given, so baselines saved before --ref existed still match.
+    if let Some(reference) = &args.reference {
+        filters["ref"] = serde_json::Value::String(reference.clone());
+    }
+    if !args.meta.is_empty() {
+        filters["meta"] = serde_json::json!(args.meta);
+    }
+    if let Some(action) = &args.action {
+        filters["action"] = serde_json::Value::String(action.clone());
+    }
+    filters
+}
+
+fn save_report_baseline(
+    db: &Db,
+    name: &str,
+    args: &crate::cli::ReportArgs,
+    events: &[StoredEvent],
+) -> Result<()> {
+    if args.output.is_table() || args.output.out.is_some() {
+        return Err(anyhow!("--save-baseline does not take --output/--out"));
+    }
+    let snapshot = report_snapshot(events, args);
+    db.upsert_report_baseline(&crate::db::StoredReportBaseline {
+        name: name.to_string(),
+        filters: report_filters_json(args),
+        snapshot: serde_json::to_value(&snapshot)?,
+        created_at: now_utc(),
+    })?;
+    println!(
+        "Saved baseline '{}': {} event(s), {} total(s).",
+        name,
+        snapshot.events.len(),
+        snapshot.totals.len()
+    );
+    Ok(())
+}
+
+/// Prints events added/removed and totals changed since the named baseline.
+fn diff_report_baseline(
+    db: &Db,
+    name: &str,
+    args: &crate::cli::ReportArgs,
+    events: &[StoredEvent],
+) -> Result<()> {
+    if args.output.is_table() || args.output.out.is_some() {
+        return Err(anyhow!("--diff-baseline does not take --output/--out"));
+    }
+    let baseline = db.get_report_baseline(name)?.ok_or_else(|| {
+        anyhow!("No baseline named '{name}'. Save one with: bankero report --save-baseline {name}")
+    })?;
+    if baseline.filters != report_filters_json(args) {
+        eprintln!(
+            "Note: baseline '{}' was saved with different filters: {}",
+            name, baseline.filters
+        );
+    }
+    let before: ReportSnapshot = serde_json::from_value(baseline.snapshot)
+        .with_context(|| format!("Baseline '{name}' has an unreadable snapshot"))?;
+    let now = report_snapshot(events, args);
+
+    let before_ids: HashSet<Uuid> = before.events.iter().map(|e| e.event_id).collect();
+    let now_ids: HashSet<Uuid> = now.events.iter().map(|e| e.event_id).collect();
+    let added: Vec<&SnapshotEvent> = now
+        .events
+        .iter()
+        .filter(|e| !before_ids.contains(&e.event_id))
+        .collect();
+    let removed: Vec<&SnapshotEvent> = before
+        .events
+        .iter()
+        .filter(|e| !now_ids.contains(&e.event_id))
+        .collect();
+
+    let mut totals: BTreeMap<(String, String), (Decimal, Decimal)> = BTreeMap::new();
+    for t in before.totals {
+        totals.entry((t.account, t.commodity)).or_default().0 = t.amount;
+    }
+    for t in now.totals {
+        totals.entry((t.account, t.commodity)).or_default().1 = t.amount;
+    }
+    totals.retain(|_, (old, new)| old != new);
+
+    let saved = baseline.created_at.to_rfc3339();
+    if added.is_empty() && removed.is_empty() && totals.is_empty() {
+        println!("No changes since baseline '{name}' (saved {saved}).");
+        return Ok(());
+    }
+    println!("Changes since baseline '{name}' (saved {saved}):");
+    if !added.is_empty() {
+        println!("new events:");
+        for e in added {
+            println!(
+                "  {}\t{}\t{}",
+                e.effective_at.to_rfc3339(),
+                e.action,
+                e.event_id
+            );
+        }
+    }
+    if !removed.is_empty() {
+        println!("removed events:");
+        for e in removed {
+            println!(
+                "  {}\t{}\t{}",
+                e.effective_at.to_rfc3339(),
+                e.action,
+                e.event_id
+            );
+        }
+    }
+    if !totals.is_empty() {
+        println!("changed totals:");
+        for ((account, commodity), (old, new)) in totals {
+            let delta = new - old;
+            let sign = if delta.is_sign_positive() { "+" } else { "" };
+            println!("  {account}\t{commodity}\t{old} -> {new}\t({sign}{delta})");
+        }
+    }
+    Ok(())
+}
+
+/// Per-posting running balances for accounts under `account_prefix`.
+fn print_register(events: &[StoredEvent], args: &crate::cli::RegisterArgs) -> Result<()> {
+    let window = match (&args.month, &args.range) {
+        (Some(m), _) => Some(crate::period::month_range(m)?),
+        (None, Some(r)) => Some(parse_date_range(r)?),
+        (None, None) => None,
     };
-    let next_start_date =
-        NaiveDate::from_ymd_opt(next_year, next_month, 1).ok_or_else(|| anyhow!("Invalid date"))?;
-    let end = Utc.from_utc_datetime(&NaiveDateTime::new(
-        next_start_date,
-        NaiveTime::from_hms_opt(0, 0, 0).unwrap(),
-    )) - chrono::Duration::seconds(1);
-    Ok((start, end))
+
+    // Running balances include everything before the window; only rows inside it print.
+    let mut running: BalanceMap = BTreeMap::new();
+    let mut rows = Vec::new();
+    for e in events {
+        for p in &e.payload.postings {
+            if let Some(prefix) = &args.account
+                && !p.account.starts_with(prefix.as_str())
+            {
+                continue;
+            }
+            let balance = running
+                .entry((p.account.clone(), p.commodity.clone()))
+                .or_insert(Decimal::ZERO);
+            *balance += p.amount;
+            if let Some((start, end)) = window
+                && (e.effective_at < start || e.effective_at > end)
+            {
+                continue;
+            }
+            rows.push((e, p, *balance));
+        }
+    }
+
+    let output = &args.output;
+    if output.is_table() {
+        let rows: Vec<Vec<String>> = rows
+            .into_iter()
+            .map(|(e, p, balance)| {
+                vec![
+                    crate::output::iso_date(e.effective_at),
+                    e.effective_at.to_rfc3339(),
+                    e.action.clone(),
+                    p.account.clone(),
+                    p.commodity.clone(),
+                    p.amount.to_string(),
+                    balance.to_string(),
+                    e.payload.note.clone().unwrap_or_default(),
+                    e.event_id.to_string(),
+                ]
+            })
+            .collect();
+        return crate::output::write_table(
+            output,
+            &[
+                "date",
+                "effective_at",
+                "action",
+                "account",
+                "commodity",
+                "amount",
+                "balance",
+                "note",
+                "event_id",
+            ],
+            &rows,
+        );
+    }
+    output.require_table_for_out()?;
+
+    if rows.is_empty() {
+        println!("(no postings)");
+        return Ok(());
+    }
+    for (e, p, balance) in rows {
+        println!(
+            "{}\t{}\t{}\t{}\t{}\t{}",
+            e.effective_at.to_rfc3339(),
+            e.action,
+            p.account,
+            p.commodity,
+            p.amount,
+            balance
+        );
+    }
+    Ok(())
 }
 
 fn parse_date_range(raw: &str) -> Result<(DateTime<Utc>, DateTime<Utc>)> {
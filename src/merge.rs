@@ -0,0 +1,176 @@
+//! Merging budgets and piggy banks received through sync.
+//!
+//! Budgets and piggies are plain rows, not events, so sync ships them whole and merges
+//! them by id: a row whose id is already here is replaced only when the incoming copy
+//! was edited later (`updated_at`, falling back to `created_at`). Names are unique per
+//! database, yet two offline devices can each create a "Food" budget. When an incoming
+//! row's name is taken by a different id, the older row (by `created_at`, then id) keeps
+//! the name and the other becomes `"Food (1a2b3c4d)"`, using the first eight characters
+//! of its id. Every device applies the same rule, so they converge on the same names
+//! whichever one syncs first. Piggy funds are merged by id once their piggy exists.
+
+use crate::db::{Db, StoredBudget, StoredPiggy, StoredPiggyFund};
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use uuid::Uuid;
+
+/// Budgets, piggies and piggy funds as they travel between devices.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SharedRecords {
+    #[serde(default)]
+    pub budgets: Vec<StoredBudget>,
+    #[serde(default)]
+    pub piggies: Vec<StoredPiggy>,
+    #[serde(default)]
+    pub piggy_funds: Vec<StoredPiggyFund>,
+}
+
+impl SharedRecords {
+    pub fn local(db: &Db) -> Result<Self> {
+        let piggies = db.list_piggies()?;
+        let mut piggy_funds = Vec::new();
+        for p in &piggies {
+            piggy_funds.extend(db.list_piggy_funds(p.id)?);
+        }
+        Ok(Self {
+            budgets: db.list_budgets()?,
+            piggies,
+            piggy_funds,
+        })
+    }
+
+    pub fn extend(&mut self, other: SharedRecords) {
+        self.budgets.extend(other.budgets);
+        self.piggies.extend(other.piggies);
+        self.piggy_funds.extend(other.piggy_funds);
+    }
+}
+
+/// What a merge changed here.
+#[derive(Debug, Clone, Default)]
+pub struct MergeOutcome {
+    pub budgets: usize,
+    pub piggies: usize,
+    pub piggy_funds: usize,
+    /// "budget Food -> Food (1a2b3c4d)" for every row renamed on a collision.
+    pub renamed: Vec<String>,
+}
+
+fn suffixed(name: &str, id: Uuid) -> String {
+    format!("{name} ({})", &id.to_string()[..8])
+}
+
+/// Local rows as (id, name, created_at), the only fields the name rule needs.
+type Row = (Uuid, String, DateTime<Utc>);
+
+/// Decides the name an incoming row is stored under, renaming a younger local row that
+/// holds it. `rename` updates the local row's name in the database.
+fn claim_name(
+    local: &mut Vec<Row>,
+    kind: &str,
+    incoming: Row,
+    renamed: &mut Vec<String>,
+    mut rename: impl FnMut(Uuid, &str) -> Result<()>,
+) -> Result<String> {
+    let (id, name, created_at) = incoming;
+    let taken: HashSet<String> = local.iter().map(|(_, n, _)| n.clone()).collect();
+    let Some(holder) = local.iter_mut().find(|(_, n, _)| *n == name) else {
+        local.push((id, name.clone(), created_at));
+        return Ok(name);
+    };
+
+    let stored = if (created_at, id) < (holder.2, holder.0) {
+        // The incoming row is older: it takes the name and ours steps aside.
+        let new_name = suffixed(&name, holder.0);
+        let new_name = if taken.contains(&new_name) {
+            format!("{name} ({})", holder.0)
+        } else {
+            new_name
+        };
+        rename(holder.0, &new_name)?;
+        renamed.push(format!("{kind} {name} -> {new_name}"));
+        holder.1 = new_name;
+        name
+    } else {
+        let new_name = suffixed(&name, id);
+        let new_name = if taken.contains(&new_name) {
+            format!("{name} ({id})")
+        } else {
+            new_name
+        };
+        renamed.push(format!("{kind} {name} -> {new_name}"));
+        new_name
+    };
+    local.push((id, stored.clone(), created_at));
+    Ok(stored)
+}
+
+/// Merges rows from peers into the local database.
+pub fn merge(db: &Db, mut incoming: SharedRecords) -> Result<MergeOutcome> {
+    let mut out = MergeOutcome::default();
+
+    // Oldest first, so a batch from several peers lands the same way on every device.
+    incoming.budgets.sort_by_key(|b| (b.created_at, b.id));
+    incoming.piggies.sort_by_key(|p| (p.created_at, p.id));
+
+    let mut changed: HashMap<Uuid, DateTime<Utc>> = HashMap::new();
+    let mut local: Vec<Row> = Vec::new();
+    for b in db.list_budgets()? {
+        changed.insert(b.id, b.changed_at());
+        local.push((b.id, b.name, b.created_at));
+    }
+    for mut budget in incoming.budgets {
+        let known = changed.get(&budget.id).copied();
+        if known.is_some_and(|at| budget.changed_at() <= at) {
+            continue;
+        }
+        // A newer edit gives up its old name first, then claims its own like a new row.
+        local.retain(|(id, _, _)| *id != budget.id);
+        let row = (budget.id, budget.name.clone(), budget.created_at);
+        budget.name = claim_name(&mut local, "budget", row, &mut out.renamed, |id, name| {
+            db.rename_budget(id, name)
+        })?;
+        if known.is_some() {
+            db.update_budget(&budget)?;
+        } else {
+            db.insert_budget(&budget)?;
+        }
+        changed.insert(budget.id, budget.changed_at());
+        out.budgets += 1;
+    }
+
+    let mut changed: HashMap<Uuid, DateTime<Utc>> = HashMap::new();
+    let mut local: Vec<Row> = Vec::new();
+    for p in db.list_piggies()? {
+        changed.insert(p.id, p.changed_at());
+        local.push((p.id, p.name, p.created_at));
+    }
+    for mut piggy in incoming.piggies {
+        let known = changed.get(&piggy.id).copied();
+        if known.is_some_and(|at| piggy.changed_at() <= at) {
+            continue;
+        }
+        local.retain(|(id, _, _)| *id != piggy.id);
+        let row = (piggy.id, piggy.name.clone(), piggy.created_at);
+        piggy.name = claim_name(&mut local, "piggy", row, &mut out.renamed, |id, name| {
+            db.rename_piggy(id, name)
+        })?;
+        if known.is_some() {
+            db.update_piggy(&piggy)?;
+        } else {
+            db.insert_piggy(&piggy)?;
+        }
+        changed.insert(piggy.id, piggy.changed_at());
+        out.piggies += 1;
+    }
+
+    let piggies: HashSet<Uuid> = local.iter().map(|(id, _, _)| *id).collect();
+    for fund in &incoming.piggy_funds {
+        if piggies.contains(&fund.piggy_id) && db.insert_piggy_fund_ignore(fund)? {
+            out.piggy_funds += 1;
+        }
+    }
+    Ok(out)
+}
@@ -0,0 +1,196 @@
+//! Actionable digest (`bankero notify`).
+//!
+//! Collects what needs attention into one plain-text digest meant for cron, e.g.
+//! `bankero notify --since last-run --quiet | mail -E -s bankero me@example.com`.
+//! With `--since`, state-based items (budgets, piggies) are only reported when they
+//! changed after that time, judged by when the underlying entries were recorded;
+//! tasks only when their occurrence fell due after it, and approvals only for
+//! entries recorded after it.
+
+use crate::cli::NotifyArgs;
+use crate::config::{AppConfig, now_utc};
+use crate::db::Db;
+use crate::domain::StoredEvent;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+
+const LAST_RUN_KEY: &str = "notify.last_run";
+
+pub fn handle_notify(db: &Db, cfg: &AppConfig, args: NotifyArgs) -> Result<()> {
+    let now = crate::parse_rfc3339_or_now(args.as_of.as_deref())?;
+    let since = match args.since.as_deref() {
+        None => None,
+        Some("last-run") => db
+            .get_meta(LAST_RUN_KEY)?
+            .map(|raw| {
+                DateTime::parse_from_rfc3339(&raw)
+                    .map(|t| t.with_timezone(&Utc))
+                    .context("Invalid notify.last_run in meta table")
+            })
+            .transpose()?,
+        Some(raw) => Some(crate::parse_rfc3339_or_now(Some(raw))?),
+    };
+
+    let events = crate::snapshot::replay_posted(db, false, false)?;
+    let before: Vec<StoredEvent> = match since {
+        Some(t) => events
+            .iter()
+            .filter(|e| e.created_at <= t)
+            .cloned()
+            .collect(),
+        None => vec![],
+    };
+
+    let mut sections: Vec<(String, Vec<String>)> = Vec::new();
+
+    // Budgets over their amount this month (newly, when --since is given).
+    let month = crate::current_month_yyyy_mm(now);
+    let (start, end) = crate::period::month_range(&month)?;
+    let mut lines = Vec::new();
+    for b in db.list_budgets()? {
+        if b.month.as_deref().is_some_and(|m| m != month) {
+            continue;
+        }
+        let actual = crate::compute_budget_actual(db, &events, start, end, &b, false)?;
+        if actual <= b.amount {
+            continue;
+        }
+        if since.is_some()
+            && crate::compute_budget_actual(db, &before, start, end, &b, false)? > b.amount
+        {
+            continue;
+        }
+        lines.push(format!(
+            "{} {}: {} of {} {} (over by {})",
+            b.name,
+            month,
+            actual,
+            b.amount,
+            b.commodity,
+            actual - b.amount
+        ));
+    }
+    sections.push((
+        format!(
+            "Budgets exceeded in {}",
+            crate::period::month_label(&month, cfg)
+        ),
+        lines,
+    ));
+
+    // Piggies that reached their target.
+    let mut lines = Vec::new();
+    for p in db.list_piggies()? {
+        let funds = db.list_piggy_funds(p.id)?;
+        let funded: Decimal = funds.iter().map(|f| f.amount).sum();
+        if funded < p.target_amount {
+            continue;
+        }
+        if let Some(t) = since {
+            let funded_before: Decimal = funds
+                .iter()
+                .filter(|f| f.created_at <= t)
+                .map(|f| f.amount)
+                .sum();
+            if funded_before >= p.target_amount {
+                continue;
+            }
+        }
+        lines.push(format!(
+            "{}: {} {} reached",
+            p.name, p.target_amount, p.commodity
+        ));
+    }
+    sections.push(("Piggies completed".to_string(), lines));
+
+    // Enabled tasks with an occurrence due (that fell due after --since).
+    let mut lines = Vec::new();
+    for task in db.list_tasks()? {
+        if !task.enabled {
+            continue;
+        }
+        if let Some(next) = crate::tasks::next_occurrence(&task)?
+            && next <= now
+            && since.is_none_or(|t| next > t)
+        {
+            lines.push(format!(
+                "{}: due {} (bankero task run {})",
+                task.task_id,
+                next.to_rfc3339(),
+                task.task_id
+            ));
+        }
+    }
+    sections.push(("Tasks due".to_string(), lines));
+
+    // Hard limits passed with --override.
+    let mut lines = Vec::new();
+    for e in &events {
+        if since.is_some_and(|t| e.created_at <= t) {
+            continue;
+        }
+        let Some(entries) = e
+            .payload
+            .metadata
+            .get("limit_overrides")
+            .and_then(|v| v.as_array())
+        else {
+            continue;
+        };
+        for entry in entries {
+            let field = |k: &str| entry.get(k).and_then(|v| v.as_str()).unwrap_or("");
+            lines.push(format!(
+                "{} {} {}: {} over its {} {} limit",
+                crate::output::iso_date(e.effective_at),
+                e.action,
+                e.event_id,
+                field("account"),
+                field("limit"),
+                field("commodity")
+            ));
+        }
+    }
+    sections.push(("Limit overrides".to_string(), lines));
+
+    // Entries from others waiting for review (recorded after --since).
+    let mut pending = crate::approve::pending_approval(&events, &crate::entered_by(cfg, None));
+    if let Some(t) = since {
+        pending.retain(|id| events.iter().any(|e| e.event_id == *id && e.created_at > t));
+    }
+    let lines = if pending.is_empty() {
+        vec![]
+    } else {
+        vec![format!("{} event(s) (bankero approve)", pending.len())]
+    };
+    sections.push(("Waiting for approval".to_string(), lines));
+
+    db.set_meta(LAST_RUN_KEY, &now_utc().to_rfc3339())?;
+
+    let total: usize = sections.iter().map(|(_, lines)| lines.len()).sum();
+    if total == 0 {
+        if !args.quiet {
+            println!("Nothing to report.");
+        }
+        return Ok(());
+    }
+    match since {
+        Some(t) => println!(
+            "Bankero digest ({} item(s) since {}):",
+            total,
+            t.to_rfc3339()
+        ),
+        None => println!("Bankero digest ({total} item(s)):"),
+    }
+    for (title, lines) in sections {
+        if lines.is_empty() {
+            continue;
+        }
+        println!();
+        println!("{title}:");
+        for line in lines {
+            println!("  {line}");
+        }
+    }
+    Ok(())
+}
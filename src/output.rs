@@ -0,0 +1,89 @@
+//! CSV and HTML output shared by report, balance, budget report and register.
+
+use crate::cli::{OutputFlags, OutputFormat};
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use std::fs;
+use std::io::{self, Write};
+
+/// Quotes a field when it contains a delimiter, quote or line break (RFC 4180).
+pub fn csv_field(raw: &str) -> String {
+    if raw.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", raw.replace('"', "\"\""))
+    } else {
+        raw.to_string()
+    }
+}
+
+pub fn csv_line(fields: &[String]) -> String {
+    fields
+        .iter()
+        .map(|f| csv_field(f))
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// ISO 8601 calendar date (YYYY-MM-DD), which spreadsheets parse without a locale.
+pub fn iso_date(at: DateTime<Utc>) -> String {
+    at.format("%Y-%m-%d").to_string()
+}
+
+fn render_csv(headers: &[&str], rows: &[Vec<String>]) -> String {
+    let mut buf = String::new();
+    let headers: Vec<String> = headers.iter().map(|h| h.to_string()).collect();
+    buf.push_str(&csv_line(&headers));
+    buf.push_str("\r\n");
+    for row in rows {
+        buf.push_str(&csv_line(row));
+        buf.push_str("\r\n");
+    }
+    buf
+}
+
+fn html_escape(raw: &str) -> String {
+    raw.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// A standalone page holding one table, readable in any browser or mail client.
+fn render_html(headers: &[&str], rows: &[Vec<String>]) -> String {
+    let mut buf = String::from(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>Bankero</title>\n\
+         <style>table{border-collapse:collapse}th,td{border:1px solid #ccc;padding:2px 6px}</style>\n\
+         </head>\n<body>\n<table>\n<tr>",
+    );
+    for h in headers {
+        buf.push_str(&format!("<th>{}</th>", html_escape(h)));
+    }
+    buf.push_str("</tr>\n");
+    for row in rows {
+        buf.push_str("<tr>");
+        for field in row {
+            buf.push_str(&format!("<td>{}</td>", html_escape(field)));
+        }
+        buf.push_str("</tr>\n");
+    }
+    buf.push_str("</table>\n</body>\n</html>\n");
+    buf
+}
+
+/// Writes a header row plus `rows` as CSV or HTML to `--out`, or stdout without it.
+pub fn write_table(output: &OutputFlags, headers: &[&str], rows: &[Vec<String>]) -> Result<()> {
+    let buf = match output.output {
+        OutputFormat::Html => render_html(headers, rows),
+        _ => render_csv(headers, rows),
+    };
+
+    match output.out.as_deref() {
+        Some(path) => {
+            fs::write(path, buf).with_context(|| format!("Failed to write {}", path.display()))?;
+            eprintln!("Wrote {} row(s) to {}", rows.len(), path.display());
+        }
+        None => {
+            io::stdout().write_all(buf.as_bytes())?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,260 @@
+//! Receipt parsing for `bankero paste`.
+//!
+//! Pasted bank SMS/notification text is matched against regex rules (configured ones
+//! first, then built-ins). The first match is turned into a prefilled `buy` event.
+//! Rules may capture `date` and `value_date`; like statement imports, the event is
+//! dated by the posting date unless `import_date` is `value` in config.
+
+use crate::cli::PasteArgs;
+use crate::config::{AppConfig, ImportDate, PasteRule, workspace_slug};
+use crate::db::Db;
+use anyhow::{Context, Result, anyhow};
+use chrono::NaiveDate;
+use regex::Regex;
+use rust_decimal::Decimal;
+use std::io::{self, IsTerminal, Read};
+use uuid::Uuid;
+
+/// What a rule extracted from the pasted text.
+#[derive(Debug, Clone)]
+pub struct Extracted {
+    pub rule: String,
+    pub amount: Decimal,
+    pub commodity: Option<String>,
+    pub payee: Option<String>,
+    pub from: Option<String>,
+    /// When the bank booked the transaction.
+    pub date: Option<NaiveDate>,
+    /// When the money actually moved.
+    pub value_date: Option<NaiveDate>,
+}
+
+fn builtin_rules() -> Vec<PasteRule> {
+    vec![
+        // "Compra por Bs. 1.234,56 en FARMATODO" / "Purchase of USD 39.99 at AMAZON"
+        PasteRule {
+            name: "builtin:purchase".to_string(),
+            pattern: r"(?i)(?:compra|pago|purchase|payment)\D{0,20}?(?P<commodity>USD|VES|EUR|USDT|Bs\.?|\$)\s*(?P<amount>\d[\d.,]*)\s+(?:en|at|a)\s+(?P<payee>[\w&' -]+?)(?:[.,;]|\s+(?:el|on|ref)\b|$)".to_string(),
+            commodity: None,
+            from: None,
+        },
+        // "You spent 12.50 USD at STARBUCKS"
+        PasteRule {
+            name: "builtin:amount-first".to_string(),
+            pattern: r"(?i)(?P<amount>\d[\d.,]*)\s*(?P<commodity>USD|VES|EUR|USDT|Bs\.?)\s+(?:en|at)\s+(?P<payee>[\w&' -]+?)(?:[.,;]|\s+(?:el|on|ref)\b|$)".to_string(),
+            commodity: None,
+            from: None,
+        },
+    ]
+}
+
+/// Normalizes `1.234,56`, `1,234.56`, `1234,5` and `1234.56` to a decimal.
+pub fn parse_localized_amount(raw: &str) -> Option<Decimal> {
+    let raw = raw.trim().trim_end_matches(['.', ',']);
+    let last_dot = raw.rfind('.');
+    let last_comma = raw.rfind(',');
+    let decimal_sep = match (last_dot, last_comma) {
+        (Some(d), Some(c)) => Some(if d > c { '.' } else { ',' }),
+        // A lone separator followed by exactly three digits is a thousands separator.
+        (Some(i), None) | (None, Some(i)) => {
+            let sep = raw.as_bytes()[i] as char;
+            let repeated = raw.matches(sep).count() > 1;
+            if repeated || raw.len() - i - 1 == 3 {
+                None
+            } else {
+                Some(sep)
+            }
+        }
+        (None, None) => None,
+    };
+
+    let mut out = String::with_capacity(raw.len());
+    for ch in raw.chars() {
+        match ch {
+            '0'..='9' => out.push(ch),
+            c if Some(c) == decimal_sep => out.push('.'),
+            '.' | ',' => {}
+            _ => return None,
+        }
+    }
+    out.parse::<Decimal>().ok()
+}
+
+/// Reads `2026-01-05`, `2026/01/05`, `05/01/2026`, `05-01-2026` and `05.01.2026`.
+pub fn parse_localized_date(raw: &str) -> Option<NaiveDate> {
+    let raw = raw.trim();
+    ["%Y-%m-%d", "%Y/%m/%d", "%d/%m/%Y", "%d-%m-%Y", "%d.%m.%Y"]
+        .iter()
+        .find_map(|format| NaiveDate::parse_from_str(raw, format).ok())
+}
+
+fn normalize_commodity(raw: &str) -> String {
+    match raw
+        .trim()
+        .trim_end_matches('.')
+        .to_ascii_uppercase()
+        .as_str()
+    {
+        "BS" => "VES".to_string(),
+        "$" => "USD".to_string(),
+        other => other.to_string(),
+    }
+}
+
+/// Tries each rule in order and returns the first successful extraction.
+pub fn extract(rules: &[PasteRule], text: &str) -> Result<Option<Extracted>> {
+    for rule in rules {
+        let re = Regex::new(&rule.pattern)
+            .with_context(|| format!("Invalid regex in paste rule '{}'", rule.name))?;
+        let Some(caps) = re.captures(text) else {
+            continue;
+        };
+        let Some(amount) = caps
+            .name("amount")
+            .and_then(|m| parse_localized_amount(m.as_str()))
+        else {
+            continue;
+        };
+        let commodity = caps
+            .name("commodity")
+            .map(|m| normalize_commodity(m.as_str()))
+            .or_else(|| rule.commodity.as_deref().map(normalize_commodity));
+        let payee = caps
+            .name("payee")
+            .map(|m| m.as_str().trim().to_string())
+            .filter(|p| !p.is_empty());
+        let date_of = |group: &str| -> Result<Option<NaiveDate>> {
+            caps.name(group)
+                .map(|m| {
+                    parse_localized_date(m.as_str()).ok_or_else(|| {
+                        anyhow!(
+                            "Paste rule '{}' captured an unreadable {group}: '{}'",
+                            rule.name,
+                            m.as_str()
+                        )
+                    })
+                })
+                .transpose()
+        };
+
+        return Ok(Some(Extracted {
+            rule: rule.name.clone(),
+            amount,
+            commodity,
+            payee,
+            from: rule.from.clone(),
+            date: date_of("date")?,
+            value_date: date_of("value_date")?,
+        }));
+    }
+    Ok(None)
+}
+
+pub fn handle_paste(db: &Db, cfg: &AppConfig, args: PasteArgs) -> Result<()> {
+    let from_stdin = args.text.is_none();
+    let text = match args.text {
+        Some(t) => t,
+        None => {
+            let mut buf = String::new();
+            io::stdin()
+                .read_to_string(&mut buf)
+                .context("Failed to read pasted text from stdin")?;
+            buf
+        }
+    };
+    if text.trim().is_empty() {
+        return Err(anyhow!(
+            "Nothing to parse. Pipe the text in or pass it with --text"
+        ));
+    }
+
+    let mut rules = cfg.paste_rules.clone();
+    rules.extend(builtin_rules());
+    let Some(found) = extract(&rules, &text)? else {
+        return Err(anyhow!(
+            "No paste rule matched. Add one to paste_rules in config.json (named groups: amount, commodity, payee, date, value_date)"
+        ));
+    };
+
+    let commodity = args
+        .commodity
+        .or(found.commodity.clone())
+        .unwrap_or_else(|| cfg.reference_commodity.clone());
+    let from = args
+        .from
+        .or(found.from.clone())
+        .or(cfg.default_account.clone())
+        .ok_or_else(|| {
+            anyhow!("No account to pay from. Pass --from, set `from` on the rule, or set default_account in config.json")
+        })?;
+    let payee = match found.payee.as_deref() {
+        Some(p) => format!("external:{}", workspace_slug(p)),
+        None => "external:unknown".to_string(),
+    };
+
+    let mut common = args.common;
+    let date = match cfg.import_date {
+        ImportDate::Posting => found.date,
+        ImportDate::Value => found.value_date.or(found.date),
+    };
+    if common.effective_at.is_none()
+        && let Some(date) = date
+    {
+        common.effective_at = Some(date.to_string());
+    }
+    let dated = common
+        .effective_at
+        .as_deref()
+        .map(|at| format!(" --effective-at {at}"))
+        .unwrap_or_default();
+
+    println!(
+        "Proposed (rule {}): bankero buy {} {} {} --from {}{}",
+        found.rule, payee, found.amount, commodity, from, dated
+    );
+
+    let dry_run = common.dry_run;
+    let write = if args.yes || dry_run {
+        true
+    } else if from_stdin || !io::stdin().is_terminal() {
+        println!("Re-run with --yes to write it.");
+        false
+    } else {
+        crate::prompt_yes_no("Write this event? [Y/n] ")?
+    };
+    if !write {
+        return Ok(());
+    }
+
+    let event_id = Uuid::new_v4();
+    let override_limits = common.override_limits;
+    let attachments = crate::attachments::read(&common.attach)?;
+    let mut payload = crate::build_buy_event(
+        db,
+        cfg,
+        event_id,
+        Some(payee),
+        found.amount.to_string(),
+        commodity,
+        from,
+        vec![],
+        crate::cli::BuyChargeFlags::default(),
+        None,
+        common,
+    )?;
+    payload.metadata["paste_rule"] = serde_json::Value::String(found.rule);
+    payload.metadata["paste_text"] = serde_json::Value::String(text.trim().to_string());
+    crate::commodities::check_known(db, cfg, &payload)?;
+    crate::commodities::check_account_defaults(db, &payload)?;
+    crate::limits::enforce(db, &mut payload, override_limits)?;
+    crate::attachments::add_to(&mut payload, &attachments);
+    if dry_run {
+        crate::show::print_event(db, event_id, &payload)?;
+        println!("Dry run: nothing was written.");
+        return Ok(());
+    }
+    crate::attachments::save(db, &attachments)?;
+    db.insert_event(event_id, &payload)?;
+    println!("Wrote event {event_id}");
+    Ok(())
+}
@@ -0,0 +1,226 @@
+//! Debt payoff planning (`bankero payoff`).
+//!
+//! Debts are the accounts under the liability prefix that owe the budget commodity.
+//! Each simulated month accrues interest on every debt, then spends the monthly budget
+//! on the debts in strategy order. The same ordering drives the `payoff` task, which
+//! writes the actual monthly payments.
+
+use crate::cli::PayoffArgs;
+use crate::config::{AppConfig, now_utc};
+use crate::db::Db;
+use crate::tasks::{Schedule, TaskSpec};
+use anyhow::{Result, anyhow};
+use chrono::{DateTime, Months, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Longest plan we simulate before giving up (50 years).
+const MAX_MONTHS: u32 = 600;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, clap::ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PayoffStrategy {
+    /// Highest interest rate first.
+    Avalanche,
+    /// Smallest balance first.
+    Snowball,
+}
+
+impl PayoffStrategy {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PayoffStrategy::Avalanche => "avalanche",
+            PayoffStrategy::Snowball => "snowball",
+        }
+    }
+}
+
+/// A debt as seen by the planner: what is owed (positive) and its annual rate.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Debt {
+    pub account: String,
+    /// Annual rate as a fraction (0.24 = 24%).
+    pub rate: Decimal,
+    #[serde(skip)]
+    pub owed: Decimal,
+}
+
+/// Splits `budget` across `debts` in strategy order; returns the payment per debt.
+pub fn allocate_payment(strategy: PayoffStrategy, budget: Decimal, debts: &[Debt]) -> Vec<Decimal> {
+    let mut order: Vec<usize> = (0..debts.len()).collect();
+    order.sort_by(|&a, &b| {
+        let (a, b) = (&debts[a], &debts[b]);
+        match strategy {
+            PayoffStrategy::Avalanche => b.rate.cmp(&a.rate).then(a.owed.cmp(&b.owed)),
+            PayoffStrategy::Snowball => a.owed.cmp(&b.owed).then(b.rate.cmp(&a.rate)),
+        }
+    });
+
+    let mut payments = vec![Decimal::ZERO; debts.len()];
+    let mut left = budget;
+    for i in order {
+        if left <= Decimal::ZERO {
+            break;
+        }
+        let pay = debts[i].owed.max(Decimal::ZERO).min(left);
+        payments[i] = pay;
+        left -= pay;
+    }
+    payments
+}
+
+/// Debts under `prefix` in `commodity` as of `at`, with rates from `rates` or interest tasks.
+pub fn collect_debts(
+    db: &Db,
+    prefix: &str,
+    commodity: &str,
+    at: DateTime<Utc>,
+    rates: &BTreeMap<String, Decimal>,
+) -> Result<Vec<Debt>> {
+    let mut task_rates: BTreeMap<String, Decimal> = BTreeMap::new();
+    for task in db.list_tasks()? {
+        if !task.enabled {
+            continue;
+        }
+        if let Ok(TaskSpec::Interest { account, rate, .. }) = serde_json::from_value(task.spec) {
+            task_rates.insert(account, rate);
+        }
+    }
+
+    let events: Vec<_> = crate::snapshot::replay_events(db, false)?
+        .into_iter()
+        .filter(|e| e.effective_at <= at)
+        .collect();
+    let mut debts = Vec::new();
+    for ((account, comm), amount) in crate::compute_balances(&events, Some(prefix)) {
+        if comm != commodity || amount >= Decimal::ZERO {
+            continue;
+        }
+        let rate = rates
+            .get(&account)
+            .or_else(|| task_rates.get(&account))
+            .copied()
+            .unwrap_or(Decimal::ZERO);
+        debts.push(Debt {
+            account,
+            rate,
+            owed: -amount,
+        });
+    }
+    Ok(debts)
+}
+
+pub fn handle_payoff(db: &Db, cfg: &AppConfig, args: PayoffArgs) -> Result<()> {
+    let [amount, commodity] = args.budget.as_slice() else {
+        return Err(anyhow!("--budget expects <amount> <commodity>"));
+    };
+    let budget = crate::parse_decimal(amount.clone(), "budget")?;
+    if budget <= Decimal::ZERO {
+        return Err(anyhow!("--budget must be > 0"));
+    }
+    let commodity = commodity.to_ascii_uppercase();
+    let as_of = crate::parse_rfc3339_or_now(args.as_of.as_deref())?;
+
+    let mut rates = BTreeMap::new();
+    for raw in &args.rates {
+        let Some((account, pct)) = raw.split_once('=') else {
+            return Err(anyhow!(
+                "--rate expects ACCOUNT=PCT (e.g. liabilities:card=29%)"
+            ));
+        };
+        rates.insert(account.to_string(), crate::parse_percent(pct, "rate")?);
+    }
+
+    let debts = collect_debts(db, &args.accounts, &commodity, as_of, &rates)?;
+    if debts.is_empty() {
+        println!("No {commodity} debts under {}.", args.accounts);
+        return Ok(());
+    }
+
+    println!(
+        "Payoff plan ({}, {} {}/month):",
+        args.strategy.as_str(),
+        budget,
+        commodity
+    );
+    println!("month\taccount\tpayment\tinterest\tremaining");
+    let mut simulated = debts.clone();
+    let mut total_interest = Decimal::ZERO;
+    let mut month = 0u32;
+    while simulated.iter().any(|d| d.owed > Decimal::ZERO) {
+        month += 1;
+        if month > MAX_MONTHS {
+            return Err(anyhow!(
+                "Not paid off within {} years at {budget} {commodity}/month",
+                MAX_MONTHS / 12
+            ));
+        }
+        let label = as_of
+            .checked_add_months(Months::new(month))
+            .ok_or_else(|| anyhow!("Payoff plan out of range"))?
+            .format("%Y-%m");
+
+        let mut interest = Vec::with_capacity(simulated.len());
+        for d in &mut simulated {
+            let accrued = (d.owed * d.rate / Decimal::from(12u32)).round_dp(2);
+            d.owed += accrued;
+            interest.push(accrued);
+        }
+        let accrued_total: Decimal = interest.iter().sum();
+        if accrued_total >= budget {
+            return Err(anyhow!(
+                "{budget} {commodity}/month does not cover the {accrued_total} {commodity} of monthly interest"
+            ));
+        }
+        total_interest += accrued_total;
+
+        let payments = allocate_payment(args.strategy, budget, &simulated);
+        for ((d, pay), accrued) in simulated.iter_mut().zip(payments).zip(interest) {
+            if pay.is_zero() && accrued.is_zero() {
+                continue;
+            }
+            d.owed -= pay;
+            println!("{label}\t{}\t{}\t{}\t{}", d.account, pay, accrued, d.owed);
+            if d.owed.is_zero() && !pay.is_zero() {
+                println!("{label}\t{}\tpaid off", d.account);
+            }
+        }
+    }
+    let done = as_of
+        .checked_add_months(Months::new(month))
+        .ok_or_else(|| anyhow!("Payoff plan out of range"))?;
+    println!(
+        "Debt-free in {} month(s) ({}); total interest {} {}.",
+        month,
+        done.format("%Y-%m"),
+        total_interest,
+        commodity
+    );
+
+    if args.create_task {
+        let from = args
+            .from
+            .ok_or_else(|| anyhow!("--create-task requires --from <account>"))?;
+        let task_id = format!("payoff-{}", args.strategy.as_str());
+        let anchor_at = crate::period::month_range(&crate::current_month_yyyy_mm(now_utc()))?.1;
+        let spec = TaskSpec::Payoff {
+            strategy: args.strategy,
+            budget,
+            commodity: commodity.clone(),
+            from: from.clone(),
+            debts,
+            project: cfg.current_project.clone(),
+        };
+        crate::tasks::create_task(db, &task_id, Schedule::Monthly, anchor_at, &spec)?;
+        println!(
+            "Created task '{}': pay {} {} monthly from {}, first run {}.",
+            task_id,
+            budget,
+            commodity,
+            from,
+            anchor_at.to_rfc3339()
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,89 @@
+//! Future-dated events (`bankero pending`).
+//!
+//! An event whose effective time is still in the future (rent or salary scheduled in
+//! advance) is pending: it is in the journal and syncs like any other event, but
+//! balances, budgets, debts, reports and notifications leave it out until its effective
+//! time passes (`snapshot::replay_posted`). Nothing needs to be done when that happens;
+//! the next balance simply includes it.
+//!
+//! Occurrences written by `task run --as-of` are never pending: running a task up to a
+//! date is how you say those occurrences happened.
+
+use crate::cli::PendingArgs;
+use crate::config::now_utc;
+use crate::db::Db;
+use crate::domain::StoredEvent;
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use std::collections::BTreeMap;
+
+pub fn is_pending(e: &StoredEvent, now: DateTime<Utc>) -> bool {
+    e.effective_at > now && e.payload.metadata["task_id"].is_null()
+}
+
+/// The events that have already happened as of `now`.
+pub fn posted(events: Vec<StoredEvent>, now: DateTime<Utc>) -> Vec<StoredEvent> {
+    events.into_iter().filter(|e| !is_pending(e, now)).collect()
+}
+
+pub fn handle_pending(db: &Db, args: PendingArgs) -> Result<()> {
+    let now = now_utc();
+    let events = crate::snapshot::replay_events(db, false)?;
+    let voided = crate::event::voided(&events);
+    let mut pending: Vec<&StoredEvent> = events
+        .iter()
+        .filter(|e| is_pending(e, now) && !voided.contains_key(&e.event_id))
+        .filter(|e| e.action != crate::event::VOID_ACTION)
+        .filter(|e| match &args.account {
+            Some(prefix) => e
+                .payload
+                .postings
+                .iter()
+                .any(|p| p.account.starts_with(prefix.as_str())),
+            None => true,
+        })
+        .collect();
+    pending.sort_by_key(|e| (e.effective_at, e.payload.created_at));
+
+    if pending.is_empty() {
+        println!("(no pending events)");
+        return Ok(());
+    }
+    let mut totals: BTreeMap<(String, String), Decimal> = BTreeMap::new();
+    for e in &pending {
+        let days = (e.effective_at - now).num_days();
+        let when = match days {
+            0 => "within a day".to_string(),
+            1 => "in 1 day".to_string(),
+            n => format!("in {n} days"),
+        };
+        println!(
+            "{}\t{}\t{}\t{}\t{when}",
+            e.effective_at.to_rfc3339(),
+            e.action,
+            e.event_id,
+            crate::history::summary(e)
+        );
+        for p in &e.payload.postings {
+            if args
+                .account
+                .as_deref()
+                .is_some_and(|prefix| !p.account.starts_with(prefix))
+            {
+                continue;
+            }
+            *totals
+                .entry((p.account.clone(), p.commodity.clone()))
+                .or_insert(Decimal::ZERO) += p.amount;
+        }
+    }
+    println!();
+    println!("Change once posted:");
+    for ((account, commodity), amount) in totals {
+        if !amount.is_zero() {
+            println!("  {account}\t{commodity}\t{amount}");
+        }
+    }
+    Ok(())
+}
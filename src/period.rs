@@ -0,0 +1,141 @@
+//! Month parsing and display.
+//!
+//! Months are stored and compared as `YYYY-MM`. Input is more forgiving: `2026-02`,
+//! `02/2026`, `2/2026`, `feb-2026`, `Feb 2026`, `febrero 2026` and `2026-feb` all mean
+//! the same month. Month names are accepted in English and Spanish, full or abbreviated.
+//! Summaries print month names in the configured `locale` (`en` or `es`).
+
+use crate::config::AppConfig;
+use anyhow::{Result, anyhow};
+use chrono::{DateTime, NaiveDate, TimeZone, Utc};
+
+const LOCALES: &[&str] = &["en", "es"];
+
+const EN: [&str; 12] = [
+    "January",
+    "February",
+    "March",
+    "April",
+    "May",
+    "June",
+    "July",
+    "August",
+    "September",
+    "October",
+    "November",
+    "December",
+];
+
+const ES: [&str; 12] = [
+    "enero",
+    "febrero",
+    "marzo",
+    "abril",
+    "mayo",
+    "junio",
+    "julio",
+    "agosto",
+    "septiembre",
+    "octubre",
+    "noviembre",
+    "diciembre",
+];
+
+/// Spellings not covered by a prefix of the full names above.
+const ALIASES: &[(&str, u32)] = &[("sept", 9), ("setiembre", 9), ("set", 9)];
+
+/// 1-based month for a month name or abbreviation (at least three letters).
+fn month_from_name(raw: &str) -> Option<u32> {
+    let name = raw.trim_end_matches('.').to_lowercase();
+    if let Some((_, m)) = ALIASES.iter().find(|(alias, _)| *alias == name) {
+        return Some(*m);
+    }
+    if name.chars().count() < 3 {
+        return None;
+    }
+    (1..=12u32).find(|m| {
+        let i = (*m - 1) as usize;
+        EN[i].to_lowercase().starts_with(&name) || ES[i].starts_with(&name)
+    })
+}
+
+fn year(raw: &str) -> Option<i32> {
+    (raw.len() == 4 && raw.chars().all(|c| c.is_ascii_digit()))
+        .then(|| raw.parse().ok())
+        .flatten()
+}
+
+fn month_number(raw: &str) -> Option<u32> {
+    if raw.is_empty() || raw.len() > 2 || !raw.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    raw.parse().ok().filter(|m| (1..=12).contains(m))
+}
+
+/// Parses any accepted month spelling into `YYYY-MM`.
+pub fn parse_month(raw: &str) -> Result<String> {
+    let invalid = || {
+        anyhow!("Invalid month '{raw}'. Expected YYYY-MM, MM/YYYY or a month name like feb-2026")
+    };
+    let parts: Vec<&str> = raw
+        .trim()
+        .split(['-', '/', ' ', '.'])
+        .filter(|p| !p.is_empty())
+        .collect();
+    let [a, b] = parts.as_slice() else {
+        return Err(invalid());
+    };
+    let (y, m) = if let Some(y) = year(a) {
+        (y, month_number(b).or_else(|| month_from_name(b)))
+    } else if let Some(y) = year(b) {
+        (y, month_number(a).or_else(|| month_from_name(a)))
+    } else {
+        return Err(invalid());
+    };
+    let m = m.ok_or_else(invalid)?;
+    Ok(format!("{y:04}-{m:02}"))
+}
+
+/// First and last second (inclusive) of a month, in any accepted spelling.
+pub fn month_range(raw: &str) -> Result<(DateTime<Utc>, DateTime<Utc>)> {
+    let month = parse_month(raw)?;
+    let start = NaiveDate::parse_from_str(&format!("{month}-01"), "%Y-%m-%d")
+        .map_err(|_| anyhow!("Invalid date"))?;
+    let next = start
+        .checked_add_months(chrono::Months::new(1))
+        .ok_or_else(|| anyhow!("Invalid date"))?;
+    let start = Utc.from_utc_datetime(&start.and_hms_opt(0, 0, 0).expect("midnight"));
+    let end = Utc.from_utc_datetime(&next.and_hms_opt(0, 0, 0).expect("midnight"))
+        - chrono::Duration::seconds(1);
+    Ok((start, end))
+}
+
+/// `2026-02` -> "February 2026" (`en`) or "febrero 2026" (`es`). Falls back to the
+/// input when it is not a `YYYY-MM` month.
+pub fn month_label(month: &str, cfg: &AppConfig) -> String {
+    let Some((y, m)) = month
+        .split_once('-')
+        .and_then(|(y, m)| Some((year(y)?, month_number(m)?)))
+    else {
+        return month.to_string();
+    };
+    let names = if cfg.locale.as_deref() == Some("es") {
+        &ES
+    } else {
+        &EN
+    };
+    format!("{} {y}", names[(m - 1) as usize])
+}
+
+/// Validates a `locale` config value.
+pub fn parse_locale(raw: &str) -> Result<String> {
+    let locale = raw.trim().to_ascii_lowercase();
+    if LOCALES.contains(&locale.as_str()) {
+        Ok(locale)
+    } else {
+        Err(anyhow!(
+            "Unknown locale '{raw}'. Expected one of: {}",
+            LOCALES.join(", ")
+        ))
+    }
+}
@@ -0,0 +1,349 @@
+//! Zero-based monthly plans (`bankero budget plan`).
+//!
+//! A plan takes the month's projected income and gives every unit a job: an amount per
+//! budget, piggy or plain category. Plans are stored per month and compared with what
+//! actually happened; the gap between income and allocations is reported as unallocated
+//! (or overallocated) until it reaches zero.
+
+use crate::config::{AppConfig, now_utc};
+use crate::db::{Db, StoredBudgetPlan};
+use crate::domain::StoredEvent;
+use anyhow::{Context, Result, anyhow};
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::io::{self, Write};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum AllocationKind {
+    Budget,
+    Piggy,
+    Category,
+}
+
+impl AllocationKind {
+    fn as_str(self) -> &'static str {
+        match self {
+            AllocationKind::Budget => "budget",
+            AllocationKind::Piggy => "piggy",
+            AllocationKind::Category => "category",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Allocation {
+    kind: AllocationKind,
+    /// Budget or piggy name, or the category account.
+    name: String,
+    amount: Decimal,
+}
+
+fn allocations_of(plan: &StoredBudgetPlan) -> Result<Vec<Allocation>> {
+    serde_json::from_value(plan.allocations.clone())
+        .with_context(|| format!("Invalid allocations in the plan for {}", plan.month))
+}
+
+/// Prints `prompt` and reads one trimmed line (empty at end of input).
+fn ask(prompt: &str) -> Result<String> {
+    eprint!("{prompt}");
+    io::stderr().flush().ok();
+    let mut line = String::new();
+    io::stdin().read_line(&mut line)?;
+    Ok(line.trim().to_string())
+}
+
+/// Asks for an amount, keeping `default` on a blank answer.
+fn ask_amount(prompt: &str, default: Decimal) -> Result<Decimal> {
+    loop {
+        let raw = ask(&format!("{prompt} [{default}]: "))?;
+        if raw.is_empty() {
+            return Ok(default);
+        }
+        match crate::parse_decimal(raw, "amount") {
+            Ok(amount) if amount >= Decimal::ZERO => return Ok(amount),
+            Ok(_) => eprintln!("Amounts must be >= 0."),
+            Err(e) => eprintln!("{e}"),
+        }
+    }
+}
+
+/// `Unallocated: 100 USD`, `Overallocated: 50 USD` or `Fully allocated`.
+fn gap_line(income: Decimal, allocated: Decimal, commodity: &str) -> String {
+    let gap = (income - allocated).normalize();
+    if gap.is_zero() {
+        "Fully allocated (zero-based).".to_string()
+    } else if gap > Decimal::ZERO {
+        format!("Unallocated: {gap} {commodity}")
+    } else {
+        format!("Overallocated: {} {commodity}", -gap)
+    }
+}
+
+/// The unallocated/overallocated line for `month`, if it has a plan.
+pub fn gap_for_month(db: &Db, month: &str) -> Result<Option<String>> {
+    let Some(plan) = db.get_budget_plan(month)? else {
+        return Ok(None);
+    };
+    let allocated: Decimal = allocations_of(&plan)?.iter().map(|a| a.amount).sum();
+    Ok(Some(gap_line(plan.income, allocated, &plan.commodity)))
+}
+
+pub fn handle_plan(
+    db: &Db,
+    cfg: &AppConfig,
+    month: String,
+    income: Option<Vec<String>>,
+    status: bool,
+) -> Result<()> {
+    let (start, end) = crate::period::month_range(&month)?;
+    if status {
+        return print_status(db, cfg, &month, start, end);
+    }
+
+    let existing = db.get_budget_plan(&month)?;
+    let (income, commodity) = match income {
+        Some(parts) => parse_income(&parts)?,
+        None => loop {
+            let default = existing
+                .as_ref()
+                .map(|p| format!(" [{} {}]", p.income, p.commodity))
+                .unwrap_or_default();
+            let raw = ask(&format!(
+                "Projected income for {month} (e.g. 3000 USD){default}: "
+            ))?;
+            if raw.is_empty() {
+                match &existing {
+                    Some(p) => break (p.income, p.commodity.clone()),
+                    None => return Err(anyhow!("A plan needs the projected income")),
+                }
+            }
+            let parts: Vec<String> = raw.split_whitespace().map(str::to_string).collect();
+            match parse_income(&parts) {
+                Ok(parsed) => break parsed,
+                Err(e) => eprintln!("{e}"),
+            }
+        },
+    };
+    let previous = match &existing {
+        Some(p) if p.commodity == commodity => allocations_of(p)?,
+        _ => vec![],
+    };
+    let previous_amount = |kind: AllocationKind, name: &str| {
+        previous
+            .iter()
+            .find(|a| a.kind == kind && a.name == name)
+            .map(|a| a.amount)
+    };
+
+    let mut allocations = Vec::new();
+    let mut budgets: Vec<_> = db
+        .list_budgets()?
+        .into_iter()
+        .filter(|b| b.month.as_deref().is_none_or(|m| m == month))
+        .filter(|b| b.commodity.eq_ignore_ascii_case(&commodity))
+        .collect();
+    budgets.sort_by(|a, b| a.name.cmp(&b.name));
+    for b in budgets {
+        let scope = b
+            .category
+            .as_deref()
+            .or(b.account.as_deref())
+            .unwrap_or("-");
+        let default = previous_amount(AllocationKind::Budget, &b.name).unwrap_or(b.amount);
+        let amount = ask_amount(&format!("Budget {} ({scope})", b.name), default)?;
+        allocations.push(Allocation {
+            kind: AllocationKind::Budget,
+            name: b.name,
+            amount,
+        });
+    }
+    for p in db.list_piggies()? {
+        if !p.commodity.eq_ignore_ascii_case(&commodity) {
+            continue;
+        }
+        let default = previous_amount(AllocationKind::Piggy, &p.name).unwrap_or(Decimal::ZERO);
+        let amount = ask_amount(
+            &format!(
+                "Piggy {} (target {} {})",
+                p.name, p.target_amount, p.commodity
+            ),
+            default,
+        )?;
+        allocations.push(Allocation {
+            kind: AllocationKind::Piggy,
+            name: p.name,
+            amount,
+        });
+    }
+    for a in previous
+        .iter()
+        .filter(|a| a.kind == AllocationKind::Category)
+    {
+        let amount = ask_amount(&format!("Category {}", a.name), a.amount)?;
+        allocations.push(Allocation {
+            amount,
+            ..a.clone()
+        });
+    }
+    loop {
+        let category = ask("Other category (blank to finish): ")?;
+        if category.is_empty() {
+            break;
+        }
+        let amount = ask_amount(&format!("Category {category}"), Decimal::ZERO)?;
+        allocations.retain(|a| !(a.kind == AllocationKind::Category && a.name == category));
+        allocations.push(Allocation {
+            kind: AllocationKind::Category,
+            name: category,
+            amount,
+        });
+    }
+    allocations.retain(|a| !a.amount.is_zero());
+
+    let allocated: Decimal = allocations.iter().map(|a| a.amount).sum();
+    println!(
+        "Plan {}: allocated {} of {} {commodity}.",
+        crate::period::month_label(&month, cfg),
+        allocated.normalize(),
+        income.normalize()
+    );
+    println!("{}", gap_line(income, allocated, &commodity));
+    if !crate::prompt_yes_no("Save plan? [Y/n] ")? {
+        println!("Plan not saved.");
+        return Ok(());
+    }
+    db.upsert_budget_plan(&StoredBudgetPlan {
+        month: month.clone(),
+        income,
+        commodity,
+        allocations: serde_json::to_value(&allocations)?,
+        created_at: existing.map_or_else(now_utc, |p| p.created_at),
+    })?;
+    println!(
+        "Saved plan for {}.",
+        crate::period::month_label(&month, cfg)
+    );
+    Ok(())
+}
+
+fn parse_income(parts: &[String]) -> Result<(Decimal, String)> {
+    let [amount, commodity] = parts else {
+        return Err(anyhow!("Income expects: <amount> <commodity>"));
+    };
+    let amount = crate::parse_decimal(amount.clone(), "income")?;
+    if amount <= Decimal::ZERO {
+        return Err(anyhow!("Projected income must be > 0"));
+    }
+    Ok((amount, commodity.to_ascii_uppercase()))
+}
+
+fn under(account: &str, prefix: &str) -> bool {
+    account == prefix || account.starts_with(&format!("{prefix}:"))
+}
+
+/// Net amount posted under `prefix` in `commodity` between `start` and `end`.
+fn posted_under(
+    events: &[StoredEvent],
+    prefix: &str,
+    commodity: &str,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+) -> Decimal {
+    events
+        .iter()
+        .filter(|e| e.effective_at >= start && e.effective_at <= end)
+        .flat_map(|e| &e.payload.postings)
+        .filter(|p| under(&p.account, prefix) && p.commodity.eq_ignore_ascii_case(commodity))
+        .map(|p| p.amount)
+        .sum()
+}
+
+/// Spending in `category`: postings to it, plus what events tagged with it paid out.
+fn spent_in(
+    events: &[StoredEvent],
+    category: &str,
+    commodity: &str,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+) -> Decimal {
+    let tagged: Decimal = events
+        .iter()
+        .filter(|e| e.effective_at >= start && e.effective_at <= end)
+        .filter(|e| {
+            e.payload
+                .category
+                .as_deref()
+                .is_some_and(|c| under(c, category))
+        })
+        .filter(|e| {
+            !e.payload
+                .postings
+                .iter()
+                .any(|p| under(&p.account, category))
+        })
+        .filter_map(|e| crate::primary_outgoing_amount(&e.payload.postings))
+        .filter(|(_, c)| c.eq_ignore_ascii_case(commodity))
+        .map(|(amount, _)| amount)
+        .sum();
+    posted_under(events, category, commodity, start, end) + tagged
+}
+
+fn print_status(
+    db: &Db,
+    cfg: &AppConfig,
+    month: &str,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+) -> Result<()> {
+    let plan = db.get_budget_plan(month)?.ok_or_else(|| {
+        anyhow!("No plan for {month}. Create one with: bankero budget plan {month}")
+    })?;
+    let allocations = allocations_of(&plan)?;
+    let events = crate::snapshot::replay_events(db, false)?;
+
+    println!(
+        "Plan {} ({})",
+        crate::period::month_label(month, cfg),
+        plan.commodity
+    );
+    println!("kind\tname\tplanned\tactual\tleft");
+    for a in &allocations {
+        let actual = match a.kind {
+            AllocationKind::Budget => match db.get_budget_by_name(&a.name)? {
+                Some(b) => crate::compute_budget_actual(db, &events, start, end, &b, false)?,
+                None => Decimal::ZERO,
+            },
+            AllocationKind::Piggy => match db.get_piggy_by_name(&a.name)? {
+                Some(p) => db
+                    .list_piggy_funds(p.id)?
+                    .iter()
+                    .filter(|f| f.effective_at >= start && f.effective_at <= end)
+                    .map(|f| f.amount)
+                    .sum(),
+                None => Decimal::ZERO,
+            },
+            AllocationKind::Category => spent_in(&events, &a.name, &plan.commodity, start, end),
+        };
+        println!(
+            "{}\t{}\t{}\t{}\t{}",
+            a.kind.as_str(),
+            a.name,
+            a.amount.normalize(),
+            actual.normalize(),
+            (a.amount - actual).normalize()
+        );
+    }
+    let received = -posted_under(&events, "income", &plan.commodity, start, end);
+    println!(
+        "income\t-\t{}\t{}\t{}",
+        plan.income.normalize(),
+        received.normalize(),
+        (plan.income - received).normalize()
+    );
+
+    let allocated: Decimal = allocations.iter().map(|a| a.amount).sum();
+    println!("{}", gap_line(plan.income, allocated, &plan.commodity));
+    Ok(())
+}
@@ -0,0 +1,254 @@
+//! Rate provider registry (`bankero provider`).
+//!
+//! Providers exist as soon as a rate is stored under their name; the registry only adds
+//! what a name like `@bcv` does not say: a description, whether the rate is official or
+//! parallel, and where it comes from. Lookups are best-effort, so unregistered providers
+//! keep rendering as their bare `@name`.
+//!
+//! `provider alias @old @new` joins two names into one rate series: lookups for either
+//! name search the rates stored under both (see `Db::get_rate_as_of`).
+//!
+//! A provider registered with `--business-days` only publishes Monday to Friday, minus
+//! its holidays. Rate lookups for other days use the rate in effect at the end of the
+//! last business day before them, even if a point was stored on the day itself.
+
+use crate::cli::ProviderCmd;
+use crate::config::now_utc;
+use crate::db::{Db, StoredProvider};
+use anyhow::{Result, anyhow};
+use chrono::{DateTime, Datelike, Duration, NaiveDate, Utc, Weekday};
+use rust_decimal::Decimal;
+
+/// A lookup that fell on a day the provider does not publish.
+#[derive(Debug, Clone)]
+pub struct BusinessDayFallback {
+    pub requested: NaiveDate,
+    pub business_day: NaiveDate,
+    /// `weekend` or `holiday`.
+    pub reason: &'static str,
+    /// Lookups use the rate in effect at this instant (the end of `business_day`).
+    pub as_of: DateTime<Utc>,
+}
+
+fn closed_reason(p: &StoredProvider, day: NaiveDate) -> Option<&'static str> {
+    if p.holidays.contains(&day) {
+        Some("holiday")
+    } else if matches!(day.weekday(), Weekday::Sat | Weekday::Sun) {
+        Some("weekend")
+    } else {
+        None
+    }
+}
+
+/// The fallback for a rate lookup at `as_of`, if `provider` keeps business days and
+/// does not publish on that (local) day.
+pub fn business_day_fallback(
+    db: &Db,
+    provider: &str,
+    as_of: DateTime<Utc>,
+) -> Result<Option<BusinessDayFallback>> {
+    let Some(p) = registration(db, provider)? else {
+        return Ok(None);
+    };
+    if !p.business_days {
+        return Ok(None);
+    }
+    let requested = crate::dates::local_date(as_of);
+    let Some(reason) = closed_reason(&p, requested) else {
+        return Ok(None);
+    };
+    let mut closed = requested;
+    while closed_reason(&p, closed - Duration::days(1)).is_some() {
+        closed -= Duration::days(1);
+    }
+    Ok(Some(BusinessDayFallback {
+        requested,
+        business_day: closed - Duration::days(1),
+        reason,
+        as_of: crate::dates::local_midnight(closed) - Duration::seconds(1),
+    }))
+}
+
+/// The latest stored rate at or before `as_of`, honouring business days.
+pub fn rate_as_of(
+    db: &Db,
+    provider: &str,
+    base: &str,
+    quote: &str,
+    as_of: DateTime<Utc>,
+) -> Result<Option<(DateTime<Utc>, Decimal)>> {
+    let as_of = business_day_fallback(db, provider, as_of)?.map_or(as_of, |f| f.as_of);
+    db.get_rate_as_of(provider, base, quote, as_of)
+}
+
+/// The registry entry for `provider`, or for the provider it is an alias of when the
+/// alias itself is not registered.
+pub fn registration(db: &Db, provider: &str) -> Result<Option<StoredProvider>> {
+    let name = crate::normalize_provider(provider);
+    if let Some(p) = db.get_provider(&name)? {
+        return Ok(Some(p));
+    }
+    match db.provider_alias(&name)? {
+        Some(canonical) => db.get_provider(&canonical),
+        None => Ok(None),
+    }
+}
+
+/// `@bcv (Banco Central, official)` for registered providers (or aliases of one),
+/// `@bcv` otherwise.
+pub fn describe(db: &Db, provider: &str) -> Result<String> {
+    let name = crate::normalize_provider(provider);
+    let Some(p) = registration(db, &name)? else {
+        return Ok(format!("@{name}"));
+    };
+    let details: Vec<&str> = [p.description.as_deref(), p.kind.as_deref()]
+        .into_iter()
+        .flatten()
+        .collect();
+    if details.is_empty() {
+        return Ok(format!("@{name}"));
+    }
+    Ok(format!("@{name} ({})", details.join(", ")))
+}
+
+pub fn handle_provider(db: &Db, cmd: ProviderCmd) -> Result<()> {
+    match cmd {
+        ProviderCmd::Add {
+            provider,
+            description,
+            kind,
+            url,
+            business_days,
+            no_business_days,
+            holidays,
+        } => {
+            let name = crate::normalize_provider(&provider);
+            if name.is_empty() || name.contains(':') {
+                return Err(anyhow!(
+                    "Invalid provider name {provider:?}. Use a bare name like @bcv."
+                ));
+            }
+            // Re-adding a provider updates what was given and keeps the rest.
+            let existing = db.get_provider(&name)?.unwrap_or_else(|| StoredProvider {
+                name: name.clone(),
+                description: None,
+                kind: None,
+                url: None,
+                created_at: now_utc(),
+                business_days: false,
+                holidays: Vec::new(),
+            });
+            let business_days = !no_business_days && (business_days || existing.business_days);
+            let mut all_holidays = if no_business_days {
+                Vec::new()
+            } else {
+                existing.holidays
+            };
+            all_holidays.extend(holidays);
+            all_holidays.sort();
+            all_holidays.dedup();
+            if !business_days && !all_holidays.is_empty() {
+                return Err(anyhow!(
+                    "--holiday only applies to providers with --business-days"
+                ));
+            }
+            db.upsert_provider(&StoredProvider {
+                name: name.clone(),
+                description: description
+                    .map(|d| d.trim().to_string())
+                    .or(existing.description),
+                kind: kind.map(|k| k.as_str().to_string()).or(existing.kind),
+                url: url.or(existing.url),
+                created_at: existing.created_at,
+                business_days,
+                holidays: all_holidays,
+            })?;
+            println!("Saved provider {}.", describe(db, &name)?);
+            Ok(())
+        }
+        ProviderCmd::Remove { provider } => {
+            let name = crate::normalize_provider(&provider);
+            if db.delete_provider(&name)? == 0 {
+                return Err(anyhow!("No provider registered as @{name}"));
+            }
+            println!("Removed provider @{name}; its stored rates are kept.");
+            Ok(())
+        }
+        ProviderCmd::List => {
+            let providers = db.list_providers()?;
+            let aliases = db.list_provider_aliases()?;
+            if providers.is_empty() && aliases.is_empty() {
+                println!("(no providers)");
+                return Ok(());
+            }
+            let rows: Vec<Vec<String>> = providers
+                .into_iter()
+                .map(|p| {
+                    vec![
+                        format!("@{}", p.name),
+                        p.kind.unwrap_or_else(|| "-".to_string()),
+                        p.description.unwrap_or_else(|| "-".to_string()),
+                        p.url.unwrap_or_else(|| "-".to_string()),
+                        match (p.business_days, p.holidays.len()) {
+                            (false, _) => "-".to_string(),
+                            (true, 0) => "business days".to_string(),
+                            (true, n) => format!("business days, {n} holiday(s)"),
+                        },
+                    ]
+                })
+                .collect();
+            if !rows.is_empty() {
+                crate::print_table(
+                    &["provider", "kind", "description", "url", "publishes"],
+                    &rows,
+                );
+            }
+            for (alias, provider) in aliases {
+                println!("@{alias} -> @{provider}");
+            }
+            Ok(())
+        }
+        ProviderCmd::Alias { alias, provider } => {
+            let alias = crate::normalize_provider(&alias);
+            let provider = crate::normalize_provider(&provider);
+            for name in [&alias, &provider] {
+                if name.is_empty() || name.contains(':') {
+                    return Err(anyhow!(
+                        "Invalid provider name {name:?}. Use a bare name like @bcv."
+                    ));
+                }
+            }
+            // Aliases point straight at the canonical name, never at another alias.
+            let canonical = db.provider_alias(&provider)?;
+            if canonical.as_deref() == Some(alias.as_str()) {
+                return Err(anyhow!("@{provider} is already an alias of @{alias}"));
+            }
+            let provider = canonical.unwrap_or(provider);
+            if alias == provider {
+                return Err(anyhow!("@{alias} cannot be an alias of itself"));
+            }
+            if let Some((other, _)) = db
+                .list_provider_aliases()?
+                .into_iter()
+                .find(|(_, p)| *p == alias)
+            {
+                return Err(anyhow!(
+                    "@{alias} is the canonical name for @{other}; point @{other} at @{provider} first"
+                ));
+            }
+            db.set_provider_alias(&alias, &provider)?;
+            println!(
+                "@{alias} is now an alias of @{provider}; rate lookups for either use both series."
+            );
+            Ok(())
+        }
+        ProviderCmd::Unalias { alias } => {
+            let alias = crate::normalize_provider(&alias);
+            if db.delete_provider_alias(&alias)? == 0 {
+                return Err(anyhow!("@{alias} is not a provider alias"));
+            }
+            println!("Removed alias @{alias}; rates stored under it are kept.");
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,127 @@
+//! Refunds of earlier purchases (`bankero refund`).
+//!
+//! A refund is a new event (action `refund`) with the buy's postings inverted, scaled
+//! down for a partial refund, and the buy's id in `metadata.refunds`. It keeps the buy's
+//! category, so category budgets count it against that month's actual spend.
+
+use crate::cli::RefundArgs;
+use crate::config::{AppConfig, now_utc};
+use crate::db::Db;
+use crate::domain::{EventPayload, RateContext, StoredEvent};
+use anyhow::{Result, anyhow};
+use rust_decimal::Decimal;
+use uuid::Uuid;
+
+pub const REFUND_ACTION: &str = "refund";
+
+/// The buy a refund event gives money back for.
+pub fn refunds(e: &StoredEvent) -> Option<Uuid> {
+    if e.action != REFUND_ACTION {
+        return None;
+    }
+    e.payload.metadata.get("refunds")?.as_str()?.parse().ok()
+}
+
+pub fn build_refund_event(
+    db: &Db,
+    cfg: &AppConfig,
+    event_id: Uuid,
+    args: RefundArgs,
+) -> Result<EventPayload> {
+    let events = db.list_events()?;
+    let buy = crate::show::find_event(&events, &args.event_id)?;
+    // An amended buy is refunded as it stands now.
+    let buy = crate::history::current_version(&events, buy);
+    if buy.action != "buy" {
+        return Err(anyhow!(
+            "Event {} is a {}, not a buy; only purchases can be refunded",
+            buy.event_id,
+            buy.action
+        ));
+    }
+    let voided = crate::event::voided(&events);
+    if let Some(v) = voided.get(&buy.event_id) {
+        return Err(anyhow!(
+            "Event {} was voided by event {}; there is nothing to refund",
+            buy.event_id,
+            v.event_id
+        ));
+    }
+    let Some((paid, commodity)) = crate::primary_outgoing_amount(&buy.payload.postings) else {
+        return Err(anyhow!("Event {} has no outgoing amount", buy.event_id));
+    };
+
+    let already: Decimal = events
+        .iter()
+        .filter(|e| refunds(e) == Some(buy.event_id) && !voided.contains_key(&e.event_id))
+        .filter_map(|e| {
+            e.payload
+                .postings
+                .iter()
+                .filter(|p| p.commodity == commodity && p.amount.is_sign_positive())
+                .map(|p| p.amount)
+                .reduce(|a, b| a + b)
+        })
+        .sum();
+    let left = paid - already;
+    let amount = match args.amount {
+        Some(raw) => crate::parse_decimal(raw, "amount")?,
+        None => left,
+    };
+    if amount <= Decimal::ZERO {
+        return Err(anyhow!(
+            "Nothing left to refund: {already} {commodity} of {paid} {commodity} was already refunded"
+        ));
+    }
+    if amount > left {
+        return Err(anyhow!(
+            "Cannot refund {amount} {commodity}: only {left} {commodity} of {paid} {commodity} is left to refund"
+        ));
+    }
+
+    let effective_at = crate::parse_rfc3339_or_now(args.common.effective_at.as_deref())?;
+    if effective_at < buy.effective_at {
+        return Err(anyhow!(
+            "A refund ({}) cannot predate the purchase ({})",
+            effective_at.to_rfc3339(),
+            buy.effective_at.to_rfc3339()
+        ));
+    }
+    let common = args.common;
+    Ok(EventPayload {
+        schema_version: 1,
+        device_id: cfg.device_id,
+        workspace: cfg.current_workspace.clone(),
+        project: cfg.current_project.clone(),
+        action: REFUND_ACTION.to_string(),
+        created_at: now_utc(),
+        effective_at,
+        postings: crate::event::scaled_postings(&buy.payload.postings, -(amount / paid)),
+        tags: if common.tags.is_empty() {
+            buy.payload.tags.clone()
+        } else {
+            common.tags
+        },
+        category: common.category.or_else(|| buy.payload.category.clone()),
+        note: common.note,
+        reference: common.reference.or_else(|| buy.payload.reference.clone()),
+        rate_context: RateContext {
+            provider: None,
+            override_rate: None,
+            base: None,
+            quote: None,
+            as_of: effective_at,
+        },
+        basis: None,
+        metadata: crate::with_meta(
+            serde_json::json!({
+                "event_id": event_id.to_string(),
+                "refunds": buy.event_id.to_string(),
+                "confirm": common.confirm,
+                "entered_by": crate::entered_by(cfg, common.entered_by.as_deref()),
+                "payee": buy.payload.metadata["payee"].clone(),
+            }),
+            &common.meta,
+        )?,
+    })
+}
@@ -0,0 +1,122 @@
+//! External report renderers (`report --renderer <program>`).
+//!
+//! The report is written to the program's stdin as one JSON document (see
+//! [`report_json`]); whatever the program prints is the rendered report. Renderers can be
+//! written in any language and never need to read the journal themselves.
+
+use crate::cli::ReportArgs;
+use crate::config::{AppConfig, now_utc};
+use crate::domain::StoredEvent;
+use anyhow::{Context, Result, anyhow};
+use rust_decimal::Decimal;
+use serde_json::{Value, json};
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+/// Bumped when a field is removed or changes meaning; new fields may appear anytime.
+const REPORT_VERSION: u32 = 1;
+
+/// The canonical JSON form of a report: its filters, events and totals.
+pub fn report_json(
+    cfg: &AppConfig,
+    args: &ReportArgs,
+    events: &[StoredEvent],
+    all: &[StoredEvent],
+    totals: &BTreeMap<String, (Decimal, Decimal)>,
+) -> Value {
+    let voided = crate::event::voided(all);
+    let events: Vec<Value> = events
+        .iter()
+        .map(|e| {
+            json!({
+                "event_id": e.event_id.to_string(),
+                "effective_at": e.effective_at.to_rfc3339(),
+                "action": e.action,
+                "payee": e.payload.metadata.get("payee").and_then(Value::as_str),
+                "category": e.payload.category,
+                "tags": e.payload.tags,
+                "note": e.payload.note,
+                "reference": e.payload.reference,
+                "project": e.payload.project,
+                "postings": e.payload.postings.iter().map(|p| json!({
+                    "account": p.account,
+                    "commodity": p.commodity,
+                    "amount": p.amount.to_string(),
+                })).collect::<Vec<_>>(),
+                "voided_by": voided.get(&e.event_id).map(|v| v.event_id.to_string()),
+            })
+        })
+        .collect();
+    let totals: Vec<Value> = totals
+        .iter()
+        .map(|(commodity, (inflow, outflow))| {
+            json!({
+                "commodity": commodity,
+                "in": inflow.normalize().to_string(),
+                "out": outflow.normalize().to_string(),
+                "net": (inflow - outflow).normalize().to_string(),
+            })
+        })
+        .collect();
+    json!({
+        "version": REPORT_VERSION,
+        "generated_at": now_utc().to_rfc3339(),
+        "workspace": cfg.current_workspace,
+        "filters": {
+            "month": args.month,
+            "range": args.range,
+            "account": args.account,
+            "category": args.category,
+            "tag": args.tag,
+            "commodity": args.commodity,
+            "action": args.action,
+            "project": args.project,
+        },
+        "events": events,
+        "totals": totals,
+    })
+}
+
+/// Runs `program` with `report` on stdin and prints its stdout, or writes it to `out`.
+pub fn render(program: &Path, report: &Value, out: Option<&Path>) -> Result<()> {
+    let input = serde_json::to_vec(report)?;
+    let mut child = Command::new(program)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .with_context(|| format!("Failed to start renderer {}", program.display()))?;
+
+    // Feed stdin from another thread so a renderer that prints while reading cannot block.
+    let mut stdin = child.stdin.take().context("Renderer stdin unavailable")?;
+    let writer = std::thread::spawn(move || stdin.write_all(&input));
+    let output = child
+        .wait_with_output()
+        .with_context(|| format!("Renderer {} did not finish", program.display()))?;
+    // A renderer may stop reading early (e.g. it only needs the totals); that is fine.
+    let _ = writer.join();
+
+    if !output.status.success() {
+        return Err(anyhow!(
+            "Renderer {} failed ({}): {}",
+            program.display(),
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    match out {
+        Some(path) => {
+            std::fs::write(path, &output.stdout)
+                .with_context(|| format!("Failed to write {}", path.display()))?;
+            eprintln!(
+                "Wrote {} byte(s) to {}",
+                output.stdout.len(),
+                path.display()
+            );
+        }
+        None => std::io::stdout().write_all(&output.stdout)?,
+    }
+    Ok(())
+}
@@ -0,0 +1,314 @@
+//! Correcting a stored provider rate after the fact (`bankero reprice`).
+//!
+//! The journal is append-only, so events that computed amounts from a wrong rate are
+//! not rewritten. Instead each one gets a compensating `reprice` event, effective at the
+//! original time, that moves the destination account to what the corrected rate gives.
+
+use crate::cli::RepriceArgs;
+use crate::config::{AppConfig, now_utc};
+use crate::db::Db;
+use crate::domain::{EventPayload, Posting, RateContext, StoredEvent};
+use crate::{RateUse, event_rate_uses, normalize_provider};
+use anyhow::{Context, Result, anyhow};
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use std::io::{self, IsTerminal};
+use uuid::Uuid;
+
+/// A compensating posting for one event that computed an amount from the rate.
+struct Adjustment<'a> {
+    event: &'a StoredEvent,
+    account: String,
+    commodity: String,
+    delta: Decimal,
+}
+
+pub fn handle_reprice(db: &Db, cfg: &AppConfig, args: RepriceArgs) -> Result<()> {
+    let provider = normalize_provider(&args.provider);
+    let as_of = DateTime::parse_from_rfc3339(&args.as_of)
+        .with_context(|| format!("Invalid RFC3339 timestamp for --as-of: {}", args.as_of))?
+        .with_timezone(&Utc);
+    let base_filter = args.base.as_ref().map(|b| b.to_ascii_uppercase());
+    let quote_filter = args.quote.as_ref().map(|q| q.to_ascii_uppercase());
+
+    let mut rows = db.list_rates_at(&provider, as_of)?;
+    rows.retain(|(b, q, _)| {
+        base_filter.as_ref().is_none_or(|f| f == b) && quote_filter.as_ref().is_none_or(|f| f == q)
+    });
+    let (base, quote, old_rate) = match rows.len() {
+        0 => {
+            return Err(anyhow!(
+                "No stored @{} rate at {}. See: bankero rate list @{}",
+                provider,
+                as_of.to_rfc3339(),
+                provider
+            ));
+        }
+        1 => rows.remove(0),
+        _ => {
+            let pairs: Vec<String> = rows.iter().map(|(b, q, _)| format!("{b}/{q}")).collect();
+            return Err(anyhow!(
+                "Several @{} rates are stored at {} ({}); pass --base and --quote",
+                provider,
+                as_of.to_rfc3339(),
+                pairs.join(", ")
+            ));
+        }
+    };
+    if old_rate == args.new_rate {
+        return Err(anyhow!(
+            "@{provider} {quote} per {base} at {} is already {old_rate}",
+            as_of.to_rfc3339()
+        ));
+    }
+
+    let target = RateUse::Stored {
+        base: base.clone(),
+        quote: quote.clone(),
+        as_of,
+        rate: old_rate,
+    };
+    let events = db.list_events()?;
+    let mut adjustments = Vec::new();
+    let mut review = Vec::new();
+    let mut valuation_only = 0usize;
+    for e in &events {
+        for (usage, via) in event_rate_uses(db, &provider, e)? {
+            if usage != target {
+                continue;
+            }
+            if via != "rate_context" {
+                review.push((e, via));
+                continue;
+            }
+            match adjustment_for(e, &cfg.rounding_account, &base, old_rate, args.new_rate) {
+                Some(adj) => adjustments.push(adj),
+                None => valuation_only += 1,
+            }
+        }
+    }
+
+    println!(
+        "Correcting @{} {} per {} at {}: {} -> {}",
+        provider,
+        quote,
+        base,
+        as_of.to_rfc3339(),
+        old_rate,
+        args.new_rate
+    );
+    if adjustments.is_empty() {
+        println!("No computed amounts to adjust.");
+    } else {
+        println!("Adjustments (balanced against {}):", args.counter_account);
+        for adj in &adjustments {
+            let sign = if adj.delta.is_sign_positive() {
+                "+"
+            } else {
+                ""
+            };
+            println!(
+                "  {}\t{}\t{}\t{}\t{}{}\t{}",
+                adj.event.effective_at.to_rfc3339(),
+                adj.event.action,
+                adj.event.event_id,
+                adj.account,
+                sign,
+                adj.delta,
+                adj.commodity
+            );
+        }
+    }
+    if !review.is_empty() {
+        println!("Needs manual review (amounts entered by hand):");
+        for (e, via) in &review {
+            println!(
+                "  {}\t{}\t{}\t{}",
+                e.effective_at.to_rfc3339(),
+                e.action,
+                e.event_id,
+                via
+            );
+        }
+    }
+    if valuation_only > 0 {
+        println!("{valuation_only} event(s) used the rate for valuation only; nothing to adjust.");
+    }
+
+    if args.dry_run {
+        println!("Dry run: nothing written.");
+        return Ok(());
+    }
+    let write = if args.yes {
+        true
+    } else if !io::stdin().is_terminal() {
+        println!("Re-run with --yes to write the adjustments and update the rate.");
+        false
+    } else {
+        crate::prompt_yes_no("Write adjustments and update the stored rate? [Y/n] ")?
+    };
+    if !write {
+        return Ok(());
+    }
+
+    let events: Vec<(Uuid, EventPayload)> = adjustments
+        .iter()
+        .map(|adj| {
+            let event_id = Uuid::new_v4();
+            let payload = adjustment_event(
+                cfg,
+                event_id,
+                adj,
+                &args.counter_account,
+                &provider,
+                (&base, &quote),
+                as_of,
+                (old_rate, args.new_rate),
+            );
+            (event_id, payload)
+        })
+        .collect();
+    crate::commodities::check_all_known(db, cfg, events.iter().map(|(_, payload)| payload))?;
+    for (event_id, payload) in &events {
+        db.insert_event(*event_id, payload)?;
+    }
+    db.set_rate(&provider, &base, &quote, as_of, args.new_rate, "reprice")?;
+    println!(
+        "Wrote {} adjustment event(s); @{} {} per {} at {} is now {}.",
+        adjustments.len(),
+        provider,
+        quote,
+        base,
+        as_of.to_rfc3339(),
+        args.new_rate
+    );
+    Ok(())
+}
+
+/// The compensation for an event whose destination amount was computed from the rate.
+///
+/// `rate_base` is the base of the stored pair; events in the other direction divide.
+/// A residual posted to `rounding_account` counts towards the computed amount.
+/// Returns `None` when no posting amount derives from the old rate.
+fn adjustment_for<'a>(
+    e: &'a StoredEvent,
+    rounding_account: &str,
+    rate_base: &str,
+    old_rate: Decimal,
+    new_rate: Decimal,
+) -> Option<Adjustment<'a>> {
+    let rc = &e.payload.rate_context;
+    let (from, to) = (rc.base.as_deref()?, rc.quote.as_deref()?);
+    let paid: Decimal = -e
+        .payload
+        .postings
+        .iter()
+        .filter(|p| p.commodity == from && p.amount.is_sign_negative())
+        .map(|p| p.amount)
+        .sum::<Decimal>();
+    let residual: Decimal = e
+        .payload
+        .postings
+        .iter()
+        .filter(|p| p.commodity == to && p.account == rounding_account)
+        .map(|p| p.amount)
+        .sum();
+    let mut received = e.payload.postings.iter().filter(|p| {
+        p.commodity == to
+            && p.account != rounding_account
+            && p.amount.is_sign_positive()
+            && !p.amount.is_zero()
+    });
+    let (target, None) = (received.next()?, received.next()) else {
+        return None;
+    };
+    if paid.is_zero() {
+        return None;
+    }
+
+    let exact = |rate: Decimal| {
+        if from == rate_base {
+            paid * rate
+        } else {
+            paid / rate
+        }
+    };
+    let convert = |rate: Decimal| exact(rate).round_dp(target.amount.scale().max(2));
+    let derived = if residual.is_zero() {
+        convert(old_rate) == target.amount
+    } else {
+        exact(old_rate) == target.amount + residual
+    };
+    if !derived {
+        return None;
+    }
+    let delta = convert(new_rate) - target.amount;
+    (!delta.is_zero()).then(|| Adjustment {
+        event: e,
+        account: target.account.clone(),
+        commodity: target.commodity.clone(),
+        delta,
+    })
+}
+
+#[allow(clippy::too_many_arguments)]
+fn adjustment_event(
+    cfg: &AppConfig,
+    event_id: Uuid,
+    adj: &Adjustment<'_>,
+    counter_account: &str,
+    provider: &str,
+    (base, quote): (&str, &str),
+    rate_as_of: DateTime<Utc>,
+    (old_rate, new_rate): (Decimal, Decimal),
+) -> EventPayload {
+    let original = &adj.event.payload;
+    EventPayload {
+        schema_version: 1,
+        device_id: cfg.device_id,
+        workspace: cfg.current_workspace.clone(),
+        project: original.project.clone(),
+        action: "reprice".to_string(),
+        created_at: now_utc(),
+        effective_at: adj.event.effective_at,
+        postings: vec![
+            Posting {
+                account: adj.account.clone(),
+                commodity: adj.commodity.clone(),
+                amount: adj.delta,
+            },
+            Posting {
+                account: counter_account.to_string(),
+                commodity: adj.commodity.clone(),
+                amount: -adj.delta,
+            },
+        ],
+        tags: original.tags.clone(),
+        category: original.category.clone(),
+        note: Some(format!(
+            "Reprice of {}: @{} {} per {} {} -> {}",
+            adj.event.event_id, provider, quote, base, old_rate, new_rate
+        )),
+        reference: None,
+        rate_context: RateContext {
+            provider: Some(format!("@{provider}")),
+            override_rate: Some(new_rate),
+            base: Some(base.to_string()),
+            quote: Some(quote.to_string()),
+            as_of: original.rate_context.as_of,
+        },
+        basis: None,
+        metadata: serde_json::json!({
+            "event_id": event_id.to_string(),
+            "reprice": {
+                "adjusts": adj.event.event_id.to_string(),
+                "provider": format!("@{provider}"),
+                "base": base,
+                "quote": quote,
+                "rate_as_of": rate_as_of.to_rfc3339(),
+                "old_rate": old_rate.to_string(),
+                "new_rate": new_rate.to_string(),
+            },
+        }),
+    }
+}
@@ -0,0 +1,200 @@
+//! Device revocation (`bankero sync revoke-device`).
+//!
+//! A revocation is an ordinary event (action `revoke-device`, no postings) naming the lost
+//! device in `metadata.revoked_device`, so it reaches peers through the normal sync paths.
+//! The revocation also lists the device's events the revoking journal already held
+//! (`metadata.kept_events`): those stay valid everywhere, including on devices that join
+//! later. Any other event claiming the device is rejected on import, and `sync expose`
+//! refuses its connections.
+
+use crate::config::{AppConfig, now_utc};
+use crate::db::Db;
+use crate::domain::{EventPayload, RateContext, StoredEvent};
+use anyhow::{Result, anyhow};
+use chrono::{DateTime, Utc};
+use std::collections::{HashMap, HashSet};
+use uuid::Uuid;
+
+pub const REVOKE_ACTION: &str = "revoke-device";
+
+fn revoked_device(payload: &EventPayload) -> Option<Uuid> {
+    if payload.action != REVOKE_ACTION {
+        return None;
+    }
+    payload
+        .metadata
+        .get("revoked_device")?
+        .as_str()?
+        .parse()
+        .ok()
+}
+
+pub struct Revocation {
+    /// The earliest revocation, if several devices revoked it.
+    pub at: DateTime<Utc>,
+    /// Events by the device that were recorded before it was revoked.
+    pub kept: HashSet<Uuid>,
+}
+
+impl Revocation {
+    fn merge(&mut self, payload: &EventPayload) {
+        self.at = self.at.min(payload.effective_at);
+        if let Some(ids) = payload.metadata["kept_events"].as_array() {
+            self.kept.extend(
+                ids.iter()
+                    .filter_map(|id| id.as_str()?.parse::<Uuid>().ok()),
+            );
+        }
+    }
+}
+
+fn record(revoked: &mut HashMap<Uuid, Revocation>, device: Uuid, payload: &EventPayload) {
+    revoked
+        .entry(device)
+        .or_insert_with(|| Revocation {
+            at: payload.effective_at,
+            kept: HashSet::new(),
+        })
+        .merge(payload);
+}
+
+pub fn revoked_devices(db: &Db) -> Result<HashMap<Uuid, Revocation>> {
+    let mut out = HashMap::new();
+    for e in db.list_events()? {
+        if let Some(device) = revoked_device(&e.payload) {
+            record(&mut out, device, &e.payload);
+        }
+    }
+    Ok(out)
+}
+
+/// Imports synced events, skipping new ones that claim a revoked device.
+///
+/// Revocations in the batch are applied first, so a lost device's events are rejected
+/// even when they arrive before the revocation that names it. Returns (imported, rejected).
+pub fn import_events(db: &Db, events: Vec<(Uuid, EventPayload)>) -> Result<(usize, usize)> {
+    let mut revoked = revoked_devices(db)?;
+    let (revocations, rest): (Vec<_>, Vec<_>) = events
+        .into_iter()
+        .partition(|(_, payload)| revoked_device(payload).is_some());
+
+    let mut imported = 0usize;
+    let mut rejected = 0usize;
+    for (id, payload) in revocations.into_iter().chain(rest) {
+        if let Some(revocation) = revoked.get(&payload.device_id)
+            && !revocation.kept.contains(&id)
+        {
+            if !db.has_event(id)? {
+                rejected += 1;
+            }
+            continue;
+        }
+        if db.insert_event_ignore(id, &payload)? {
+            imported += 1;
+        }
+        if let Some(device) = revoked_device(&payload) {
+            record(&mut revoked, device, &payload);
+        }
+    }
+    Ok((imported, rejected))
+}
+
+/// Error for a sync peer whose device was revoked, if it was.
+pub fn refuse(db: &Db, device_id: Uuid) -> Result<Option<String>> {
+    Ok(revoked_devices(db)?.get(&device_id).map(|r| {
+        format!(
+            "Device {device_id} was revoked on {}; refusing to sync with it",
+            r.at.to_rfc3339()
+        )
+    }))
+}
+
+/// Device ids seen in the journal, with the last name they were entered under.
+fn known_devices(events: &[StoredEvent]) -> HashMap<Uuid, Option<String>> {
+    let mut out = HashMap::new();
+    for e in events {
+        let name = crate::approve::event_entered_by(e).map(str::to_string);
+        out.insert(e.payload.device_id, name);
+    }
+    out
+}
+
+pub fn revoke_device(db: &Db, cfg: &AppConfig, raw: &str, note: Option<String>) -> Result<()> {
+    let events = db.list_events()?;
+    let known = known_devices(&events);
+    let needle = raw.trim().to_ascii_lowercase();
+    let device = match needle.parse::<Uuid>() {
+        Ok(id) => id,
+        Err(_) => {
+            let mut matches = known
+                .keys()
+                .filter(|id| !needle.is_empty() && id.to_string().starts_with(&needle));
+            match (matches.next(), matches.next()) {
+                (Some(id), None) => *id,
+                (None, _) => return Err(anyhow!("No device {raw} in this journal")),
+                (Some(_), Some(_)) => {
+                    return Err(anyhow!(
+                        "Device id prefix {raw} is ambiguous; use more characters"
+                    ));
+                }
+            }
+        }
+    };
+    if device == cfg.device_id {
+        return Err(anyhow!("Cannot revoke this device ({device}) from itself"));
+    }
+    if let Some(r) = revoked_devices(db)?.get(&device) {
+        return Err(anyhow!(
+            "Device {device} was already revoked on {}",
+            r.at.to_rfc3339()
+        ));
+    }
+    let kept: Vec<String> = crate::snapshot::load_events(db, true)?
+        .iter()
+        .filter(|e| e.payload.device_id == device)
+        .map(|e| e.event_id.to_string())
+        .collect();
+
+    let event_id = Uuid::new_v4();
+    let now = now_utc();
+    let payload = EventPayload {
+        schema_version: 1,
+        device_id: cfg.device_id,
+        workspace: cfg.current_workspace.clone(),
+        project: cfg.current_project.clone(),
+        action: REVOKE_ACTION.to_string(),
+        created_at: now,
+        effective_at: now,
+        postings: vec![],
+        tags: vec![],
+        category: None,
+        note,
+        reference: None,
+        rate_context: RateContext {
+            provider: None,
+            override_rate: None,
+            base: None,
+            quote: None,
+            as_of: now,
+        },
+        basis: None,
+        metadata: serde_json::json!({
+            "event_id": event_id.to_string(),
+            "revoked_device": device.to_string(),
+            "kept_events": kept,
+            "entered_by": crate::entered_by(cfg, None),
+        }),
+    };
+    db.insert_event(event_id, &payload)?;
+
+    let kept = payload.metadata["kept_events"]
+        .as_array()
+        .map_or(0, Vec::len);
+    match known.get(&device) {
+        Some(Some(name)) => println!("Revoked device {device} ({name}); kept {kept} event(s)."),
+        _ => println!("Revoked device {device}; kept {kept} event(s)."),
+    }
+    println!("Peers apply the revocation on their next sync; its other events will be rejected.");
+    println!("Wrote event {event_id}");
+    Ok(())
+}
@@ -0,0 +1,144 @@
+//! Shareable rules (`bankero rules export/import`).
+//!
+//! Events sync between devices, but the rules that shape new events (paste rules for
+//! bank notifications, category rules, `q` account aliases) live in each device's
+//! config.json. A rules file carries them in a portable TOML format so everyone in a
+//! shared workspace categorizes the same way:
+//!
+//! ```toml
+//! version = 1
+//!
+//! [aliases]
+//! cash = "assets:cash"
+//!
+//! [[paste]]
+//! name = "banesco"
+//! pattern = 'Compra por Bs\. (?P<amount>[\d.,]+) en (?P<payee>\w+)'
+//! commodity = "VES"
+//! from = "assets:banesco"
+//!
+//! [[categorize]]
+//! contains = "farmatodo"
+//! category = "expenses:health"
+//! ```
+
+use crate::cli::RulesCmd;
+use crate::config::{AppConfig, CategoryRule, PasteRule, write_config};
+use anyhow::{Context, Result, anyhow};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+const RULES_VERSION: u32 = 1;
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RulesFile {
+    version: u32,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    aliases: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    paste: Vec<PasteRule>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    categorize: Vec<CategoryRule>,
+}
+
+/// The first rule whose `contains` appears in one of `texts` (case-insensitive).
+pub fn categorize<'a>(rules: &'a [CategoryRule], texts: &[&str]) -> Option<&'a CategoryRule> {
+    rules.iter().find(|r| {
+        let needle = r.contains.to_lowercase();
+        texts.iter().any(|t| t.to_lowercase().contains(&needle))
+    })
+}
+
+fn load(path: &Path) -> Result<RulesFile> {
+    let raw = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read rules file {}", path.display()))?;
+    let file: RulesFile =
+        toml::from_str(&raw).with_context(|| format!("Invalid rules file {}", path.display()))?;
+    if file.version != RULES_VERSION {
+        return Err(anyhow!(
+            "Rules file {} has version {}; this bankero reads version {RULES_VERSION}",
+            path.display(),
+            file.version
+        ));
+    }
+    for rule in &file.paste {
+        regex::Regex::new(&rule.pattern)
+            .with_context(|| format!("Invalid regex in paste rule '{}'", rule.name))?;
+    }
+    for rule in &file.categorize {
+        if rule.contains.trim().is_empty() || rule.category.trim().is_empty() {
+            return Err(anyhow!(
+                "Category rules need non-empty `contains` and `category`"
+            ));
+        }
+    }
+    Ok(file)
+}
+
+pub fn handle_rules(cmd: RulesCmd, cfg: &mut AppConfig, cfg_path: &Path) -> Result<()> {
+    match cmd {
+        RulesCmd::Export { file } => {
+            let rules = RulesFile {
+                version: RULES_VERSION,
+                aliases: cfg.account_aliases.clone(),
+                paste: cfg.paste_rules.clone(),
+                categorize: cfg.category_rules.clone(),
+            };
+            let toml = toml::to_string(&rules)?;
+            match file {
+                Some(path) => {
+                    std::fs::write(&path, toml)
+                        .with_context(|| format!("Failed to write {}", path.display()))?;
+                    println!(
+                        "Exported {} alias(es), {} paste rule(s) and {} category rule(s) to {}.",
+                        rules.aliases.len(),
+                        rules.paste.len(),
+                        rules.categorize.len(),
+                        path.display()
+                    );
+                }
+                None => print!("{toml}"),
+            }
+            Ok(())
+        }
+        RulesCmd::Import { file, replace } => {
+            let rules = load(&file)?;
+            if replace {
+                cfg.account_aliases.clear();
+                cfg.paste_rules.clear();
+                cfg.category_rules.clear();
+            }
+            let (aliases, paste, categorize) = (
+                rules.aliases.len(),
+                rules.paste.len(),
+                rules.categorize.len(),
+            );
+            cfg.account_aliases.extend(rules.aliases);
+            // Same name (paste) or same text (categorize) replaces the local rule in place.
+            for rule in rules.paste {
+                match cfg.paste_rules.iter_mut().find(|r| r.name == rule.name) {
+                    Some(existing) => *existing = rule,
+                    None => cfg.paste_rules.push(rule),
+                }
+            }
+            for rule in rules.categorize {
+                match cfg
+                    .category_rules
+                    .iter_mut()
+                    .find(|r| r.contains.eq_ignore_ascii_case(&rule.contains))
+                {
+                    Some(existing) => *existing = rule,
+                    None => cfg.category_rules.push(rule),
+                }
+            }
+            write_config(cfg_path, cfg)?;
+            println!(
+                "Imported {aliases} alias(es), {paste} paste rule(s) and {categorize} category rule(s) from {}.",
+                file.display()
+            );
+            Ok(())
+        }
+    }
+}
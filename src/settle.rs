@@ -0,0 +1,184 @@
+//! Two-phase transfers (`move --pending`, `bankero settle`).
+//!
+//! A pending move posts source -> transit account and records the real destination in
+//! `metadata.pending_to`. `settle` writes a second event, dated when the funds arrive,
+//! that moves the same amounts transit -> destination and names the move in
+//! `metadata.settles`. Until then the money shows up under the transit account instead
+//! of inflating the destination's balance.
+
+use crate::cli::SettleArgs;
+use crate::config::{AppConfig, now_utc};
+use crate::db::Db;
+use crate::domain::{EventPayload, Posting, RateContext, StoredEvent};
+use anyhow::{Result, anyhow};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+fn pending_to(e: &StoredEvent) -> Option<&str> {
+    e.payload.metadata.get("pending_to")?.as_str()
+}
+
+/// The transit account the move used (the config may have changed since).
+fn transit_account<'a>(e: &'a StoredEvent, cfg: &'a AppConfig) -> &'a str {
+    e.payload
+        .metadata
+        .get("transit_account")
+        .and_then(|v| v.as_str())
+        .unwrap_or(&cfg.transit_account)
+}
+
+/// Pending move id -> the settle event that completed it.
+fn settlements(events: &[StoredEvent]) -> HashMap<Uuid, &StoredEvent> {
+    events
+        .iter()
+        .filter(|e| e.action == "settle")
+        .filter_map(|e| {
+            let id = e.payload.metadata.get("settles")?.as_str()?.parse().ok()?;
+            Some((id, e))
+        })
+        .collect()
+}
+
+fn in_transit<'a>(e: &'a StoredEvent, cfg: &'a AppConfig) -> Vec<&'a Posting> {
+    let transit = transit_account(e, cfg);
+    e.payload
+        .postings
+        .iter()
+        .filter(|p| p.account == transit)
+        .collect()
+}
+
+pub fn handle_settle(db: &Db, cfg: &AppConfig, args: SettleArgs) -> Result<()> {
+    let events = db.list_events()?;
+    let settled = settlements(&events);
+
+    let Some(raw) = args.event_id.as_deref() else {
+        let pending: Vec<&StoredEvent> = events
+            .iter()
+            .filter(|e| pending_to(e).is_some() && !settled.contains_key(&e.event_id))
+            .collect();
+        if pending.is_empty() {
+            println!("No transfers in transit.");
+            return Ok(());
+        }
+        let now = now_utc();
+        println!("effective_at\tevent_id\tamount\tfrom\tto\tdays");
+        for e in pending {
+            let amounts: Vec<String> = in_transit(e, cfg)
+                .iter()
+                .map(|p| format!("{} {}", p.amount, p.commodity))
+                .collect();
+            let from = e
+                .payload
+                .postings
+                .iter()
+                .find(|p| p.amount.is_sign_negative())
+                .map_or("-", |p| p.account.as_str());
+            println!(
+                "{}\t{}\t{}\t{}\t{}\t{}",
+                e.effective_at.to_rfc3339(),
+                e.event_id,
+                amounts.join(", "),
+                from,
+                pending_to(e).unwrap_or("-"),
+                (now - e.effective_at).num_days()
+            );
+        }
+        return Ok(());
+    };
+
+    let e = crate::show::find_event(&events, raw)?;
+    let Some(to) = pending_to(e) else {
+        return Err(anyhow!(
+            "Event {} is not a pending transfer (record one with: bankero move ... --pending)",
+            e.event_id
+        ));
+    };
+    if let Some(s) = settled.get(&e.event_id) {
+        return Err(anyhow!(
+            "Transfer {} was already settled on {} by event {}",
+            e.event_id,
+            s.effective_at.to_rfc3339(),
+            s.event_id
+        ));
+    }
+    let effective_at = crate::parse_rfc3339_or_now(args.effective_at.as_deref())?;
+    if effective_at < e.effective_at {
+        return Err(anyhow!(
+            "Funds cannot arrive ({}) before they were sent ({})",
+            effective_at.to_rfc3339(),
+            e.effective_at.to_rfc3339()
+        ));
+    }
+    let legs = in_transit(e, cfg);
+    if legs.is_empty() {
+        return Err(anyhow!(
+            "Event {} has no postings to {}; nothing to settle",
+            e.event_id,
+            transit_account(e, cfg)
+        ));
+    }
+    let mut postings = Vec::new();
+    for leg in &legs {
+        postings.push(Posting {
+            account: leg.account.clone(),
+            commodity: leg.commodity.clone(),
+            amount: -leg.amount,
+        });
+        postings.push(Posting {
+            account: to.to_string(),
+            commodity: leg.commodity.clone(),
+            amount: leg.amount,
+        });
+    }
+
+    let event_id = Uuid::new_v4();
+    let payload = EventPayload {
+        schema_version: 1,
+        device_id: cfg.device_id,
+        workspace: cfg.current_workspace.clone(),
+        project: cfg.current_project.clone(),
+        action: "settle".to_string(),
+        created_at: now_utc(),
+        effective_at,
+        postings,
+        tags: vec![],
+        category: None,
+        note: args.note,
+        reference: e.payload.reference.clone(),
+        rate_context: RateContext {
+            provider: None,
+            override_rate: None,
+            base: None,
+            quote: None,
+            as_of: effective_at,
+        },
+        basis: None,
+        metadata: serde_json::json!({
+            "event_id": event_id.to_string(),
+            "settles": e.event_id.to_string(),
+            "entered_by": crate::entered_by(cfg, None),
+        }),
+    };
+    // The move it completes already went through confirm_over.
+    let options = crate::WriteOptions {
+        approved: true,
+        ..Default::default()
+    };
+    if !crate::maybe_confirm_and_insert(db, cfg, event_id, &payload, &options)? {
+        return Ok(());
+    }
+
+    let amounts: Vec<String> = legs
+        .iter()
+        .map(|p| format!("{} {}", p.amount, p.commodity))
+        .collect();
+    println!(
+        "Settled {}: {} arrived in {to} after {} day(s) in transit.",
+        e.event_id,
+        amounts.join(", "),
+        (effective_at - e.effective_at).num_days()
+    );
+    crate::batch::wrote(format!("Wrote event {event_id}"));
+    Ok(())
+}
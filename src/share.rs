@@ -0,0 +1,92 @@
+//! Redacted exports for people outside the ledger (`bankero share`).
+//!
+//! A share export is a posting list stripped of everything personal: notes,
+//! references, tags, event ids and who entered what. `--redact accounts` also
+//! collapses account names and categories to their kind, so a landlord sees "Bank"
+//! and "Expenses" instead of the whole account tree.
+
+use crate::cli::{Redaction, ShareCmd, ShareReportArgs};
+use crate::db::Db;
+use anyhow::Result;
+
+/// The kind of account `account` is, by its top-level segment.
+fn generalize(account: &str) -> &'static str {
+    match account.split(':').next().unwrap_or_default() {
+        "assets" => "Bank",
+        "liabilities" => "Debt",
+        "income" => "Income",
+        "expenses" => "Expenses",
+        "equity" => "Equity",
+        _ => "Other",
+    }
+}
+
+pub fn handle_share(db: &Db, cmd: ShareCmd) -> Result<()> {
+    match cmd {
+        ShareCmd::Report(args) => share_report(db, args),
+    }
+}
+
+fn share_report(db: &Db, args: ShareReportArgs) -> Result<()> {
+    let window = match (&args.month, &args.range) {
+        (Some(m), _) => Some(crate::period::month_range(m)?),
+        (None, Some(r)) => Some(crate::parse_date_range(r)?),
+        (None, None) => None,
+    };
+    let redact_accounts = args.redact.contains(&Redaction::Accounts);
+    let redact_categories = args.redact.contains(&Redaction::Categories);
+
+    let mut rows = Vec::new();
+    for e in crate::snapshot::replay_events(db, false)? {
+        if let Some((start, end)) = window
+            && (e.effective_at < start || e.effective_at > end)
+        {
+            continue;
+        }
+        for p in &e.payload.postings {
+            let account = if redact_accounts {
+                generalize(&p.account).to_string()
+            } else {
+                p.account.clone()
+            };
+            // Categories are account paths too, so they leak the same tree.
+            let category = match &e.payload.category {
+                Some(_) if redact_categories => String::new(),
+                Some(c) if redact_accounts => generalize(c).to_string(),
+                Some(c) => c.clone(),
+                None => String::new(),
+            };
+            rows.push(vec![
+                crate::output::iso_date(e.effective_at),
+                e.action.clone(),
+                account,
+                category,
+                p.commodity.clone(),
+                p.amount.to_string(),
+            ]);
+        }
+    }
+
+    let headers = [
+        "date",
+        "action",
+        "account",
+        "category",
+        "commodity",
+        "amount",
+    ];
+    if args.output.is_table() {
+        return crate::output::write_table(&args.output, &headers, &rows);
+    }
+    args.output.require_table_for_out()?;
+
+    if rows.is_empty() {
+        println!("(no postings)");
+        return Ok(());
+    }
+    println!("{}", headers.join("\t"));
+    for row in rows {
+        println!("{}", row.join("\t"));
+    }
+    Ok(())
+}
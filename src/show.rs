@@ -0,0 +1,226 @@
+//! Single-event view (`bankero show`, also reachable as `bankero event show`).
+//!
+//! Prints everything stored for an event, including archived ones: postings, the
+//! rate it was recorded with, metadata, and whether it was voided or amended since.
+//! `--json` prints the stored payload instead.
+//!
+//! `--revalue @provider` replays a cross-currency event with another provider's rate
+//! (at the event's as-of time, or `--as-of`) and shows how far the recorded amounts
+//! are from that rate, e.g. official vs parallel on a past transfer. Nothing is written.
+
+use crate::cli::ShowArgs;
+use crate::config::AppConfig;
+use crate::db::Db;
+use crate::domain::{BasisContext, EventPayload, StoredEvent};
+use anyhow::{Result, anyhow};
+use rust_decimal::Decimal;
+use uuid::Uuid;
+
+/// The event with id `raw`, or the only one whose id starts with it.
+pub fn find_event<'a>(events: &'a [StoredEvent], raw: &str) -> Result<&'a StoredEvent> {
+    let needle = raw.trim().to_ascii_lowercase();
+    if needle.is_empty() {
+        return Err(anyhow!("Event id must not be empty"));
+    }
+    let mut matches = events
+        .iter()
+        .filter(|e| e.event_id.to_string().starts_with(&needle));
+    match (matches.next(), matches.next()) {
+        (Some(e), None) => Ok(e),
+        (None, _) => Err(anyhow!("No such event: {raw}")),
+        (Some(_), Some(_)) => Err(anyhow!(
+            "Event id prefix {raw} is ambiguous; use more characters"
+        )),
+    }
+}
+
+fn signed(amount: Decimal) -> String {
+    if amount.is_sign_negative() {
+        amount.to_string()
+    } else {
+        format!("+{amount}")
+    }
+}
+
+/// Prints everything stored for an event: header fields, postings, the rate it was
+/// recorded with, basis, attachments, the raw rate context and metadata.
+///
+/// `bankero show`, `event show` and `--dry-run` previews all render through this.
+pub fn print_event(db: &Db, event_id: Uuid, payload: &EventPayload) -> Result<()> {
+    let p = payload;
+    println!("event_id:     {event_id}");
+    println!("action:       {}", p.action);
+    println!("effective_at: {}", p.effective_at.to_rfc3339());
+    println!("created_at:   {}", p.created_at.to_rfc3339());
+    println!("workspace:    {}", p.workspace);
+    println!("project:      {}", p.project);
+    println!("device_id:    {}", p.device_id);
+    println!("schema:       {}", p.schema_version);
+    if let Some(category) = &p.category {
+        println!("category:     {category}");
+    }
+    if !p.tags.is_empty() {
+        println!("tags:         {}", p.tags.join(", "));
+    }
+    if let Some(note) = &p.note {
+        println!("note:         {note}");
+    }
+    if let Some(reference) = &p.reference {
+        println!("ref:          {reference}");
+    }
+
+    println!("postings:");
+    if p.postings.is_empty() {
+        println!("  (none)");
+    }
+    for posting in &p.postings {
+        println!(
+            "  {}\t{}\t{}",
+            posting.account, posting.commodity, posting.amount
+        );
+    }
+
+    let rc = &p.rate_context;
+    let pair = rc.base.as_deref().zip(rc.quote.as_deref());
+    if let (Some(provider), Some((base, quote))) = (rc.provider.as_deref(), pair) {
+        let described = crate::providers::describe(db, provider)?;
+        match rc.override_rate {
+            Some(rate) => println!(
+                "rate:         {described} {quote} per {base} = {rate} (as of {})",
+                rc.as_of.to_rfc3339()
+            ),
+            None => println!(
+                "rate:         {described} {quote} per {base} (as of {})",
+                rc.as_of.to_rfc3339()
+            ),
+        }
+    }
+    if let Some(fallback) = p.metadata.get("rate_fallback") {
+        println!(
+            "fallback:     {} was a {}; used the {} rate",
+            fallback["requested"].as_str().unwrap_or("?"),
+            fallback["reason"].as_str().unwrap_or("non-business day"),
+            fallback["business_day"].as_str().unwrap_or("?")
+        );
+    }
+    match &p.basis {
+        Some(BasisContext::Fixed { amount, commodity }) => {
+            println!("basis:        fixed {amount} {commodity}")
+        }
+        Some(BasisContext::Provider { provider }) => println!("basis:        provider {provider}"),
+        None => {}
+    }
+    for a in crate::attachments::of(p) {
+        println!("attachment:   {} ({} bytes)", a.name, a.size);
+    }
+
+    let opt = |v: Option<&str>| v.unwrap_or("-").to_string();
+    println!("rate_context:");
+    println!("  provider:      {}", opt(rc.provider.as_deref()));
+    println!(
+        "  override_rate: {}",
+        rc.override_rate
+            .map(|r| r.to_string())
+            .unwrap_or_else(|| "-".to_string())
+    );
+    println!("  base:          {}", opt(rc.base.as_deref()));
+    println!("  quote:         {}", opt(rc.quote.as_deref()));
+    println!("  as_of:         {}", rc.as_of.to_rfc3339());
+    if !p.metadata.is_null() {
+        println!("metadata:");
+        for line in serde_json::to_string_pretty(&p.metadata)?.lines() {
+            println!("  {line}");
+        }
+    }
+    Ok(())
+}
+
+pub fn handle_show(db: &Db, cfg: &AppConfig, args: ShowArgs) -> Result<()> {
+    let events = crate::snapshot::load_events(db, true)?;
+    let e = find_event(&events, &args.event_id)?;
+    if args.json {
+        let mut out = serde_json::json!({
+            "event_id": e.event_id.to_string(),
+            "created_at": e.created_at.to_rfc3339(),
+        });
+        out["payload"] = serde_json::to_value(&e.payload)?;
+        println!("{}", serde_json::to_string_pretty(&out)?);
+        return Ok(());
+    }
+
+    let rc = &e.payload.rate_context;
+    print_event(db, e.event_id, &e.payload)?;
+    if let Some(v) = crate::event::voided(&events).get(&e.event_id) {
+        println!("Voided by {}.", v.event_id);
+    }
+    let current = crate::history::current_version(&events, e);
+    if current.event_id != e.event_id {
+        println!(
+            "Amended: the current version is {} (see: bankero history {}).",
+            current.event_id,
+            &e.event_id.to_string()[..8]
+        );
+    }
+
+    let pair = rc.base.as_deref().zip(rc.quote.as_deref());
+    let Some(provider) = args.revalue.as_deref() else {
+        return Ok(());
+    };
+    let Some((base, quote)) = pair.filter(|(b, q)| b != q) else {
+        return Err(anyhow!(
+            "Event {} has no cross-currency rate context to revalue",
+            e.event_id
+        ));
+    };
+    let paid: Decimal = -e
+        .payload
+        .postings
+        .iter()
+        .filter(|p| p.commodity == base && p.amount.is_sign_negative())
+        .map(|p| p.amount)
+        .sum::<Decimal>()
+        .normalize();
+    let received: Decimal = e
+        .payload
+        .postings
+        .iter()
+        .filter(|p| {
+            p.commodity == quote && p.account != cfg.rounding_account && p.amount > Decimal::ZERO
+        })
+        .map(|p| p.amount)
+        .sum::<Decimal>()
+        .normalize();
+    if paid.is_zero() || received.is_zero() {
+        return Err(anyhow!(
+            "Event {} does not move {base} into {quote}; nothing to revalue",
+            e.event_id
+        ));
+    }
+
+    let as_of = match args.as_of.as_deref() {
+        Some(raw) => crate::parse_rfc3339_or_now(Some(raw))?,
+        None => rc.as_of,
+    };
+    let name = crate::normalize_provider(provider);
+    let (value, _, _, found_as_of) =
+        crate::resolve_and_convert(db, &name, base, quote, as_of, paid)?;
+    let rate = value / paid;
+    let value = crate::commodities::round(db, quote, value)?.normalize();
+    let back = crate::commodities::round(db, base, received / rate)?.normalize();
+
+    println!(
+        "revalued: {} {quote} per {base} = {} (as of {})",
+        crate::providers::describe(db, &name)?,
+        rate.round_dp(8).normalize(),
+        found_as_of.to_rfc3339()
+    );
+    println!(
+        "  {paid} {base} -> {value} {quote} (recorded {received} {quote}, delta {} {quote})",
+        signed((value - received).normalize())
+    );
+    println!(
+        "  {received} {quote} -> {back} {base} (recorded {paid} {base}, delta {} {base})",
+        signed((back - paid).normalize())
+    );
+    Ok(())
+}
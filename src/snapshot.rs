@@ -0,0 +1,233 @@
+//! Journal compaction (`bankero snapshot`).
+//!
+//! `snapshot create --before <date>` replaces every event effective before the date with
+//! a single `snapshot` event holding the opening balance of each account and commodity.
+//! The replaced events are moved to the `archived_events` table, not deleted, so reports
+//! can still replay them with `--include-archived`, and sync still sends them to peers.
+//!
+//! An event is only archived together with its other versions, its voids and the
+//! `resolve` events between them; when any of those falls after the cut, all of them stay
+//! live so the snapshot never splits a correction from what it corrects.
+//!
+//! The cut-off is moved back to the first of its month, so the opening balances never
+//! fall inside a month that reports still show events for.
+
+use crate::cli::SnapshotCmd;
+use crate::config::{AppConfig, now_utc};
+use crate::db::Db;
+use crate::domain::{EventPayload, Posting, RateContext, StoredEvent};
+use anyhow::{Result, anyhow};
+use chrono::{DateTime, Datelike, Duration, NaiveDate, Utc};
+use std::io::{self, IsTerminal};
+use uuid::Uuid;
+
+pub const SNAPSHOT_ACTION: &str = "snapshot";
+
+/// The journal to replay: live events, or the raw history with archives instead of snapshots.
+pub fn load_events(db: &Db, include_archived: bool) -> Result<Vec<StoredEvent>> {
+    let mut events = db.list_events()?;
+    if !include_archived {
+        return Ok(events);
+    }
+    events.extend(db.list_archived_events()?);
+    events.retain(|e| e.action != SNAPSHOT_ACTION);
+    events.sort_by(|a, b| {
+        a.effective_at
+            .cmp(&b.effective_at)
+            .then(a.created_at.cmp(&b.created_at))
+    });
+    Ok(events)
+}
+
+/// [`load_events`] with corrected versions replaced by their corrections: what balances
+/// and reports replay.
+pub fn replay_events(db: &Db, include_archived: bool) -> Result<Vec<StoredEvent>> {
+    Ok(crate::history::current_versions(load_events(
+        db,
+        include_archived,
+    )?))
+}
+
+/// [`replay_events`] without pending (future-dated) events unless `include_pending`:
+/// what balances, budgets, debts and reports as of now replay.
+pub fn replay_posted(
+    db: &Db,
+    include_archived: bool,
+    include_pending: bool,
+) -> Result<Vec<StoredEvent>> {
+    let events = replay_events(db, include_archived)?;
+    if include_pending {
+        return Ok(events);
+    }
+    Ok(crate::pending::posted(events, crate::config::now_utc()))
+}
+
+/// The cut-off for `--before`: midnight UTC on the first of the month it falls in.
+fn parse_before(raw: &str) -> Result<DateTime<Utc>> {
+    let at = match NaiveDate::parse_from_str(raw, "%Y-%m-%d") {
+        Ok(date) => date
+            .and_hms_opt(0, 0, 0)
+            .ok_or_else(|| anyhow!("Invalid --before date: {raw}"))?
+            .and_utc(),
+        Err(_) => DateTime::parse_from_rfc3339(raw)
+            .map(|t| t.with_timezone(&Utc))
+            .map_err(|_| anyhow!("Invalid --before: {raw}. Expected YYYY-MM-DD or RFC3339"))?,
+    };
+    let first = at
+        .date_naive()
+        .with_day(1)
+        .and_then(|d| d.and_hms_opt(0, 0, 0))
+        .ok_or_else(|| anyhow!("Invalid --before date: {raw}"))?
+        .and_utc();
+    if first != at {
+        eprintln!(
+            "--before {raw} is not the start of a month; snapshotting before {} so no month is split.",
+            first.format("%Y-%m-%d")
+        );
+    }
+    Ok(first)
+}
+
+pub fn handle_snapshot(db: &Db, cfg: &AppConfig, cmd: SnapshotCmd) -> Result<()> {
+    match cmd {
+        SnapshotCmd::Create { before, yes } => {
+            let before = parse_before(&before)?;
+            // Versions, voids and resolves of one event move together: a group with any
+            // member on or after the cut, or already archived by an earlier snapshot, stays
+            // live as a whole so replay still sees the full chain. Every stored version of
+            // a moved group is archived, but only current versions count toward the opening
+            // balances; a void and the event it cancels drop out together.
+            let live = db.list_events()?;
+            let archived = db.list_archived_events()?;
+            let groups = crate::history::replay_groups(
+                &live.iter().chain(&archived).cloned().collect::<Vec<_>>(),
+            );
+            let group = |id: &Uuid| groups.get(id).copied().unwrap_or(*id);
+            let held: std::collections::HashSet<Uuid> = live
+                .iter()
+                .filter(|e| e.effective_at >= before)
+                .chain(&archived)
+                .map(|e| group(&e.event_id))
+                .collect();
+            let (prior, kept): (Vec<StoredEvent>, Vec<StoredEvent>) = live
+                .into_iter()
+                .filter(|e| e.effective_at < before)
+                .partition(|e| !held.contains(&group(&e.event_id)));
+            if !kept.is_empty() {
+                println!(
+                    "{} event(s) before {} stay live with the corrections or voids linking them to events that are not archived.",
+                    kept.len(),
+                    before.to_rfc3339()
+                );
+            }
+            if prior.is_empty() {
+                println!(
+                    "No events before {}; nothing to compact.",
+                    before.to_rfc3339()
+                );
+                return Ok(());
+            }
+            let mut counted = crate::history::current_versions(prior.clone());
+            let voided = crate::event::voided(&counted);
+            let void_ids: std::collections::HashSet<Uuid> =
+                voided.values().map(|v| v.event_id).collect();
+            let voided_ids: std::collections::HashSet<Uuid> = voided.keys().copied().collect();
+            counted
+                .retain(|e| !voided_ids.contains(&e.event_id) && !void_ids.contains(&e.event_id));
+
+            let postings: Vec<Posting> = crate::compute_balances(&counted, None)
+                .into_iter()
+                .filter(|(_, amount)| !amount.is_zero())
+                .map(|((account, commodity), amount)| Posting {
+                    account,
+                    commodity,
+                    amount,
+                })
+                .collect();
+
+            println!(
+                "Snapshot before {}: {} event(s) archived, {} opening balance(s).",
+                before.to_rfc3339(),
+                prior.len(),
+                postings.len()
+            );
+            let write = if yes {
+                true
+            } else if !io::stdin().is_terminal() {
+                println!("Re-run with --yes to write the snapshot.");
+                false
+            } else {
+                crate::prompt_yes_no("Write snapshot and archive these events? [Y/n] ")?
+            };
+            if !write {
+                return Ok(());
+            }
+
+            // Effective just before the cut so the opening balances sort ahead of later events
+            // and stay out of month-based reports for the first month after it.
+            let effective_at = before - Duration::seconds(1);
+            let snapshot_id = Uuid::new_v4();
+            let now = now_utc();
+            let payload = EventPayload {
+                schema_version: 1,
+                device_id: cfg.device_id,
+                workspace: cfg.current_workspace.clone(),
+                project: cfg.current_project.clone(),
+                action: SNAPSHOT_ACTION.to_string(),
+                created_at: now,
+                effective_at,
+                postings,
+                tags: vec![],
+                category: None,
+                note: Some(format!("Opening balances as of {}", before.to_rfc3339())),
+                reference: None,
+                rate_context: RateContext {
+                    provider: None,
+                    override_rate: None,
+                    base: None,
+                    quote: None,
+                    as_of: effective_at,
+                },
+                basis: None,
+                metadata: serde_json::json!({
+                    "event_id": snapshot_id.to_string(),
+                    "before": before.to_rfc3339(),
+                    "archived_events": prior.len(),
+                }),
+            };
+            let ids: Vec<Uuid> = prior.iter().map(|e| e.event_id).collect();
+            let archived = db.with_transaction(|| {
+                db.insert_event(snapshot_id, &payload)?;
+                db.archive_events(&ids, snapshot_id, now)
+            })?;
+            println!("Wrote snapshot {snapshot_id}; archived {archived} event(s).");
+            Ok(())
+        }
+        SnapshotCmd::List => {
+            let snapshots: Vec<StoredEvent> = db
+                .list_events()?
+                .into_iter()
+                .chain(db.list_archived_events()?)
+                .filter(|e| e.action == SNAPSHOT_ACTION)
+                .collect();
+            if snapshots.is_empty() {
+                println!("(no snapshots)");
+                return Ok(());
+            }
+            println!("snapshot_id\tbefore\tarchived_events\tcreated_at");
+            for s in snapshots {
+                let meta = &s.payload.metadata;
+                println!(
+                    "{}\t{}\t{}\t{}",
+                    s.event_id,
+                    meta.get("before").and_then(|v| v.as_str()).unwrap_or("-"),
+                    meta.get("archived_events")
+                        .and_then(|v| v.as_u64())
+                        .unwrap_or(0),
+                    s.created_at.to_rfc3339()
+                );
+            }
+            Ok(())
+        }
+    }
+}
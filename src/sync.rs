@@ -2,6 +2,7 @@ use crate::cli::{LoginArgs, SyncArgs, SyncCmd};
 use crate::config::{AppConfig, funny_name_from_uuid, now_utc, workspace_slug, write_config};
 use crate::db::{Db, StoredRate};
 use crate::domain::EventPayload;
+use crate::merge::{MergeOutcome, SharedRecords};
 use anyhow::{Context, Result, anyhow};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
@@ -14,6 +15,90 @@ use std::str::FromStr;
 use std::time::{Duration, Instant};
 use uuid::Uuid;
 
+/// `sync_max_events` when unset: more new events than this in one session need approval.
+pub const DEFAULT_MAX_EVENTS: usize = 10_000;
+
+/// `sync_max_mb` when unset: the size of the new events' payloads, in MB.
+pub const DEFAULT_MAX_MB: u64 = 50;
+
+/// A journal this small may grow past tenfold in a sync without being asked about it.
+const GROWTH_MIN_LOCAL: i64 = 50;
+
+/// Describes how `incoming` (plus `blob_bytes` of new attachments) exceeds the workspace
+/// quotas, if it does.
+fn quota_breach(
+    db: &Db,
+    cfg: &AppConfig,
+    source: &str,
+    incoming: &[(Uuid, EventPayload)],
+    blob_bytes: u64,
+) -> Result<Option<String>> {
+    let mut new_events = 0usize;
+    let mut new_bytes = blob_bytes;
+    for (id, payload) in incoming {
+        if !db.has_event(*id)? {
+            new_events += 1;
+            new_bytes += serde_json::to_vec(payload)?.len() as u64;
+        }
+    }
+    let max_events = cfg.sync_max_events.unwrap_or(DEFAULT_MAX_EVENTS);
+    let max_mb = cfg.sync_max_mb.unwrap_or(DEFAULT_MAX_MB);
+    let local = db.count_events()?;
+
+    let mut reasons = Vec::new();
+    if new_events > max_events {
+        reasons.push(format!("more than sync_max_events ({max_events})"));
+    }
+    if new_bytes > max_mb * 1024 * 1024 {
+        reasons.push(format!("more than sync_max_mb ({max_mb} MB)"));
+    }
+    if local >= GROWTH_MIN_LOCAL && new_events as i64 > local * 10 {
+        reasons.push(format!("over 10x the {local} event(s) already here"));
+    }
+    if reasons.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(format!(
+        "{source} sends {new_events} new event(s) ({:.1} MB): {}",
+        new_bytes as f64 / (1024.0 * 1024.0),
+        reasons.join("; ")
+    )))
+}
+
+/// Stops a sync whose new events exceed the quotas, unless approved.
+///
+/// Runs before anything is imported, so a refused sync leaves the journal untouched.
+/// Approval is `--accept-large`, or a yes at the prompt when stdin is a terminal.
+pub fn check_quota(
+    db: &Db,
+    cfg: &AppConfig,
+    source: &str,
+    incoming: &[(Uuid, EventPayload)],
+    blob_bytes: u64,
+    accept_large: bool,
+) -> Result<()> {
+    let Some(summary) = quota_breach(db, cfg, source, incoming, blob_bytes)? else {
+        return Ok(());
+    };
+    if accept_large {
+        eprintln!("{summary}; importing (--accept-large).");
+        return Ok(());
+    }
+    if std::io::IsTerminal::is_terminal(&std::io::stdin()) {
+        eprint!("{summary}.\nImport anyway? [y/N] ");
+        std::io::stderr().flush().ok();
+        let mut answer = String::new();
+        std::io::stdin().read_line(&mut answer)?;
+        if matches!(answer.trim().to_ascii_lowercase().as_str(), "y" | "yes") {
+            return Ok(());
+        }
+        return Err(anyhow!("Sync cancelled; nothing was imported"));
+    }
+    Err(anyhow!(
+        "{summary}. Nothing was imported; re-run with --accept-large if this is expected, or raise the limits with `bankero config set sync_max_events` / `sync_max_mb`"
+    ))
+}
+
 fn should_auto_accept_sync(test_once: bool) -> bool {
     if test_once {
         return true;
@@ -51,7 +136,7 @@ fn prompt_accept_sync(peer: Option<SocketAddr>) -> Result<bool> {
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
-struct WireEvent {
+pub(crate) struct WireEvent {
     pub id: Uuid,
     pub payload: EventPayload,
 }
@@ -63,14 +148,38 @@ struct WireRate {
     pub quote: String,
     pub as_of: DateTime<Utc>,
     pub rate: rust_decimal::Decimal,
+    /// Provenance as the receiver should store it (absent from older versions).
+    #[serde(default)]
+    pub source: Option<String>,
 }
 
-fn resolve_sync_dir(args_dir: Option<String>, cfg: &AppConfig) -> Result<PathBuf> {
-    if let Some(dir) = args_dir {
-        return Ok(PathBuf::from(dir));
+/// How this device labels itself in rate provenance.
+fn device_label(cfg: &AppConfig) -> String {
+    cfg.device_name
+        .clone()
+        .unwrap_or_else(|| cfg.device_id.to_string())
+}
+
+/// Provenance a peer stores for one of our rates; rates we received ourselves keep theirs.
+fn outgoing_source(cfg: &AppConfig, source: Option<&str>) -> String {
+    match source {
+        Some(s) if s.starts_with("synced from ") => s.to_string(),
+        other => format!(
+            "synced from {} ({})",
+            device_label(cfg),
+            other.unwrap_or("unknown")
+        ),
     }
-    if let Some(dir) = cfg.sync_dir.clone() {
-        return Ok(PathBuf::from(dir));
+}
+
+/// Provenance for a rate received from `peer`, which may predate provenance tracking.
+fn incoming_source(peer: &str, source: Option<String>) -> String {
+    source.unwrap_or_else(|| format!("synced from {peer} (unknown)"))
+}
+
+fn resolve_sync_dir(args_dir: Option<String>, cfg: &AppConfig) -> Result<PathBuf> {
+    if let Some(dir) = args_dir.or_else(|| cfg.sync_dir.clone()) {
+        return crate::config::expand_home(&dir);
     }
     Err(anyhow!(
         "No sync folder configured. Run: bankero login --sync-dir <path> (or set BANKERO_SYNC_DIR)."
@@ -117,7 +226,7 @@ fn jsonl_write<T: Serialize>(path: &Path, items: &[T]) -> Result<()> {
     atomic_write(path, &buf)
 }
 
-fn jsonl_read_lines(path: &Path) -> Result<Vec<String>> {
+pub(crate) fn jsonl_read_lines(path: &Path) -> Result<Vec<String>> {
     let file =
         fs::File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
     let reader = BufReader::new(file);
@@ -191,15 +300,40 @@ pub fn handle_sync(db: &Db, args: SyncArgs, cfg: &mut AppConfig, cfg_path: &Path
         }
         SyncCmd::Now => {
             let sync_dir = resolve_sync_dir(args.dir, cfg)?;
-            let (imported_events, imported_rates) = sync_now(db, cfg, &sync_dir)?;
+            let imported = sync_now(db, cfg, &sync_dir, args.accept_large)?;
             cfg.last_sync_at = Some(now_utc());
             write_config(cfg_path, cfg)?;
             println!(
                 "synced\t{}\t(imported events: {}, imported rates: {})",
                 sync_dir.display(),
-                imported_events,
-                imported_rates
+                imported.events,
+                imported.rates
             );
+            if imported.rejected > 0 {
+                println!(
+                    "rejected\t{} event(s) from revoked devices",
+                    imported.rejected
+                );
+            }
+            if imported.attachments > 0 {
+                println!("attachments\t{} file(s) copied", imported.attachments);
+            }
+            let merged = &imported.merged;
+            if merged.budgets + merged.piggies + merged.piggy_funds > 0 {
+                println!(
+                    "merged\t{} budget(s), {} piggy(ies), {} piggy fund(s)",
+                    merged.budgets, merged.piggies, merged.piggy_funds
+                );
+            }
+            for line in &merged.renamed {
+                println!("renamed\t{line}");
+            }
+            if imported.events > 0 {
+                let matched = crate::tasks::match_synced_events(db, cfg)?;
+                if matched > 0 {
+                    println!("matched\t{matched} synced event(s) to task occurrences");
+                }
+            }
             Ok(())
         }
         SyncCmd::Discover { timeout_ms, target } => {
@@ -223,7 +357,14 @@ pub fn handle_sync(db: &Db, args: SyncArgs, cfg: &mut AppConfig, cfg_path: &Path
             test_once,
             test_print_ports,
         ),
-        SyncCmd::External(argv) => sync_external(db, cfg, cfg_path, argv),
+        SyncCmd::RevokeDevice { device_id, note } => {
+            crate::revoke::revoke_device(db, cfg, &device_id, note)
+        }
+        SyncCmd::External(argv) => {
+            let accept_large = args.accept_large || argv.iter().any(|a| a == "--accept-large");
+            let argv = argv.into_iter().filter(|a| a != "--accept-large").collect();
+            sync_external(db, cfg, cfg_path, argv, accept_large)
+        }
     }
 }
 
@@ -235,6 +376,14 @@ const DISCOVERY_PORT: u16 = 45_667;
 const SYNC_PORT: u16 = 45_668;
 const DISCOVERY_MAGIC: &str = "bankero-sync-v1";
 
+/// LAN protocol spoken by this build, announced in `hello`/`hello_ack`. Peers that
+/// announce nothing speak version 1 (events and rates only).
+const PROTOCOL: u32 = 3;
+/// First version that understands `records` (budgets and piggies).
+const RECORDS_PROTOCOL: u32 = 2;
+/// First version that lists its blobs in `hello`/`hello_ack` and accepts `attachment`.
+const ATTACHMENTS_PROTOCOL: u32 = 3;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 struct DiscoverRequest {
     magic: String,
@@ -291,14 +440,36 @@ fn write_peers_cache(cfg_path: &Path, peers: &[CachedPeer]) -> Result<()> {
     Ok(())
 }
 
+/// `user@host` shown to peers. Unix sets `USER`/`HOSTNAME`, Windows `USERNAME`/`COMPUTERNAME`;
+/// shells that export neither host variable fall back to `/etc/hostname`, then `hostname`.
 fn local_user_host() -> String {
-    let user = std::env::var("USER").unwrap_or_else(|_| "unknown".to_string());
-    let host = std::env::var("HOSTNAME").ok().or_else(|| {
-        fs::read_to_string("/etc/hostname")
-            .ok()
-            .map(|s| s.lines().next().unwrap_or("unknown").trim().to_string())
-    });
-    format!("{}@{}", user, host.unwrap_or_else(|| "unknown".to_string()))
+    let env = |keys: &[&str]| {
+        keys.iter()
+            .filter_map(|k| std::env::var(k).ok())
+            .map(|v| v.trim().to_string())
+            .find(|v| !v.is_empty())
+    };
+    let user = env(&["USER", "USERNAME"]);
+    let host = env(&["HOSTNAME", "COMPUTERNAME"])
+        .or_else(|| {
+            fs::read_to_string("/etc/hostname")
+                .ok()
+                .and_then(|s| s.lines().next().map(|l| l.trim().to_string()))
+                .filter(|h| !h.is_empty())
+        })
+        .or_else(|| {
+            std::process::Command::new("hostname")
+                .output()
+                .ok()
+                .filter(|o| o.status.success())
+                .map(|o| String::from_utf8_lossy(&o.stdout).trim().to_string())
+                .filter(|h| !h.is_empty())
+        });
+    format!(
+        "{}@{}",
+        user.unwrap_or_else(|| "unknown".to_string()),
+        host.unwrap_or_else(|| "unknown".to_string())
+    )
 }
 
 fn sync_discover(
@@ -413,6 +584,7 @@ fn sync_discover(
     Ok(())
 }
 
+#[allow(clippy::too_many_arguments)]
 fn sync_expose(
     db: &Db,
     cfg: &mut AppConfig,
@@ -536,6 +708,16 @@ fn sync_expose(
                 println!("- sent rates: {}", stats.sent_rates);
                 println!("- imported events: {}", stats.imported_events);
                 println!("- imported rates: {}", stats.imported_rates);
+                if stats.rejected_events > 0 {
+                    println!(
+                        "- rejected events from revoked devices: {}",
+                        stats.rejected_events
+                    );
+                }
+                print_merged(&stats.merged);
+                if stats.matched_tasks > 0 {
+                    println!("- matched to task occurrences: {}", stats.matched_tasks);
+                }
             }
             Err(err) => {
                 eprintln!("sync failed: {err:#}");
@@ -553,6 +735,10 @@ fn sync_expose(
     Ok(())
 }
 
+fn legacy_protocol() -> u32 {
+    1
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type")]
 enum SyncMsg {
@@ -563,6 +749,11 @@ enum SyncMsg {
         device_name: String,
         user_host: String,
         version: String,
+        #[serde(default = "legacy_protocol")]
+        protocol: u32,
+        /// Attachment blobs the sender already has, so the other side skips them.
+        #[serde(default)]
+        attachments: Vec<String>,
     },
 
     #[serde(rename = "hello_ack")]
@@ -571,13 +762,21 @@ enum SyncMsg {
         device_name: String,
         user_host: String,
         version: String,
+        #[serde(default = "legacy_protocol")]
+        protocol: u32,
+        /// Attachment blobs the sender already has, so the other side skips them.
+        #[serde(default)]
+        attachments: Vec<String>,
     },
 
     #[serde(rename = "push_begin")]
     PushBegin { events: usize, rates: usize },
 
     #[serde(rename = "event")]
-    Event { id: Uuid, payload: EventPayload },
+    Event {
+        id: Uuid,
+        payload: Box<EventPayload>,
+    },
 
     #[serde(rename = "rate")]
     Rate {
@@ -586,8 +785,18 @@ enum SyncMsg {
         quote: String,
         as_of: DateTime<Utc>,
         rate: rust_decimal::Decimal,
+        #[serde(default)]
+        source: Option<String>,
     },
 
+    /// Budgets, piggies and piggy funds, sent once after the rates.
+    #[serde(rename = "records")]
+    Records { records: Box<SharedRecords> },
+
+    /// One attachment blob the events refer to, hex-encoded, sent after the records.
+    #[serde(rename = "attachment")]
+    Attachment { sha256: String, data: String },
+
     #[serde(rename = "push_end")]
     PushEnd,
 
@@ -614,18 +823,75 @@ fn write_msg(w: &mut BufWriter<TcpStream>, msg: &SyncMsg) -> Result<()> {
     Ok(())
 }
 
+/// Sends the blobs `events` refer to that this device has and the peer lacks; peers
+/// older than [`ATTACHMENTS_PROTOCOL`] get none.
+fn send_attachments(
+    db: &Db,
+    writer: &mut BufWriter<TcpStream>,
+    events: &[crate::domain::StoredEvent],
+    peer_protocol: u32,
+    peer_has: &std::collections::BTreeSet<String>,
+) -> Result<()> {
+    if peer_protocol < ATTACHMENTS_PROTOCOL {
+        return Ok(());
+    }
+    for sha256 in crate::attachments::referenced(events) {
+        if peer_has.contains(&sha256) {
+            continue;
+        }
+        if let Some(bytes) = crate::attachments::get(db, &sha256)? {
+            write_msg(
+                writer,
+                &SyncMsg::Attachment {
+                    sha256,
+                    data: crate::attachments::to_hex(&bytes),
+                },
+            )?;
+        }
+    }
+    Ok(())
+}
+
+/// Bytes the received hex blobs decode to, for the quota check.
+fn blob_bytes(blobs: &[(String, String)]) -> u64 {
+    blobs.iter().map(|(_, data)| data.len() as u64 / 2).sum()
+}
+
+/// Stores received blobs that events in the journal refer to, skipping any that arrived
+/// damaged.
+fn store_attachments(db: &Db, blobs: Vec<(String, String)>) -> Result<usize> {
+    let wanted = crate::attachments::referenced(&shareable_events(db)?);
+    let mut stored = 0;
+    for (sha256, data) in blobs {
+        if !wanted.contains(&sha256) {
+            continue;
+        }
+        let result = crate::attachments::from_hex(&data)
+            .and_then(|bytes| crate::attachments::put(db, &sha256, &bytes));
+        match result {
+            Ok(true) => stored += 1,
+            Ok(false) => {}
+            Err(e) => eprintln!("Skipping attachment {sha256}: {e:#}"),
+        }
+    }
+    Ok(stored)
+}
+
 fn read_msg(line: &str) -> Result<SyncMsg> {
     let msg: SyncMsg = serde_json::from_str(line)
         .with_context(|| format!("Failed to parse sync message: {}", line))?;
     Ok(msg)
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone)]
 struct SyncStats {
     imported_events: usize,
     imported_rates: usize,
     sent_events: usize,
     sent_rates: usize,
+    matched_tasks: usize,
+    rejected_events: usize,
+    merged: MergeOutcome,
 }
 
 fn handle_sync_connection_server(db: &Db, cfg: &AppConfig, stream: TcpStream) -> Result<SyncStats> {
@@ -641,10 +907,21 @@ fn handle_sync_connection_server(db: &Db, cfg: &AppConfig, stream: TcpStream) ->
             imported_rates: 0,
             sent_events: 0,
             sent_rates: 0,
+            matched_tasks: 0,
+            rejected_events: 0,
+            merged: MergeOutcome::default(),
         });
     }
     let hello = read_msg(line.trim())?;
-    let SyncMsg::Hello { workspace, .. } = hello else {
+    let SyncMsg::Hello {
+        workspace,
+        device_id: peer_device,
+        device_name: peer_name,
+        protocol: peer_protocol,
+        attachments: peer_blobs,
+        ..
+    } = hello
+    else {
         write_msg(
             &mut writer,
             &SyncMsg::Error {
@@ -656,6 +933,9 @@ fn handle_sync_connection_server(db: &Db, cfg: &AppConfig, stream: TcpStream) ->
             imported_rates: 0,
             sent_events: 0,
             sent_rates: 0,
+            matched_tasks: 0,
+            rejected_events: 0,
+            merged: MergeOutcome::default(),
         });
     };
 
@@ -674,6 +954,23 @@ fn handle_sync_connection_server(db: &Db, cfg: &AppConfig, stream: TcpStream) ->
             imported_rates: 0,
             sent_events: 0,
             sent_rates: 0,
+            matched_tasks: 0,
+            rejected_events: 0,
+            merged: MergeOutcome::default(),
+        });
+    }
+
+    if let Some(message) = crate::revoke::refuse(db, peer_device)? {
+        eprintln!("{message}");
+        write_msg(&mut writer, &SyncMsg::Error { message })?;
+        return Ok(SyncStats {
+            imported_events: 0,
+            imported_rates: 0,
+            sent_events: 0,
+            sent_rates: 0,
+            matched_tasks: 0,
+            rejected_events: 0,
+            merged: MergeOutcome::default(),
         });
     }
 
@@ -687,12 +984,16 @@ fn handle_sync_connection_server(db: &Db, cfg: &AppConfig, stream: TcpStream) ->
                 .unwrap_or_else(|| "bankero".to_string()),
             user_host: local_user_host(),
             version: env!("CARGO_PKG_VERSION").to_string(),
+            protocol: PROTOCOL,
+            attachments: crate::attachments::held(db)?.into_iter().collect(),
         },
     )?;
 
     // Receive push.
-    let mut imported_events = 0usize;
+    let mut incoming = Vec::new();
     let mut imported_rates = 0usize;
+    let mut records = SharedRecords::default();
+    let mut blobs = Vec::new();
     loop {
         line.clear();
         let n = reader.read_line(&mut line)?;
@@ -702,21 +1003,21 @@ fn handle_sync_connection_server(db: &Db, cfg: &AppConfig, stream: TcpStream) ->
         let msg = read_msg(line.trim())?;
         match msg {
             SyncMsg::PushBegin { .. } => {}
-            SyncMsg::Event { id, payload } => {
-                if db.insert_event_ignore(id, &payload)? {
-                    imported_events += 1;
-                }
-            }
+            SyncMsg::Event { id, payload } => incoming.push((id, *payload)),
             SyncMsg::Rate {
                 provider,
                 base,
                 quote,
                 as_of,
                 rate,
+                source,
             } => {
-                db.set_rate(&provider, &base, &quote, as_of, rate)?;
+                let source = incoming_source(&peer_name, source);
+                db.set_rate(&provider, &base, &quote, as_of, rate, &source)?;
                 imported_rates += 1;
             }
+            SyncMsg::Records { records: r } => records.extend(*r),
+            SyncMsg::Attachment { sha256, data } => blobs.push((sha256, data)),
             SyncMsg::PushEnd => break,
             SyncMsg::Error { .. }
             | SyncMsg::Hello { .. }
@@ -727,8 +1028,40 @@ fn handle_sync_connection_server(db: &Db, cfg: &AppConfig, stream: TcpStream) ->
         }
     }
 
+    if let Some(summary) = quota_breach(db, cfg, &peer_name, &incoming, blob_bytes(&blobs))? {
+        eprintln!("{summary}; refused.");
+        write_msg(
+            &mut writer,
+            &SyncMsg::Error {
+                message: format!(
+                    "{summary}. The receiving device refused the push; raise its sync_max_events / sync_max_mb to allow it"
+                ),
+            },
+        )?;
+        return Ok(SyncStats {
+            imported_events: 0,
+            imported_rates,
+            sent_events: 0,
+            sent_rates: 0,
+            matched_tasks: 0,
+            rejected_events: 0,
+            merged: MergeOutcome::default(),
+        });
+    }
+
+    let _activity = db.activity(&format!("a LAN sync with {peer_name}"))?;
+    let ((imported_events, rejected_events), merged) = db.with_transaction(|| {
+        Ok((
+            crate::revoke::import_events(db, incoming)?,
+            crate::merge::merge(db, records)?,
+        ))
+    })?;
+    let mut peer_has: std::collections::BTreeSet<String> = peer_blobs.into_iter().collect();
+    peer_has.extend(blobs.iter().map(|(sha256, _)| sha256.clone()));
+    store_attachments(db, blobs)?;
+
     // Send pull.
-    let events = db.list_events()?;
+    let events = shareable_events(db)?;
     let rates = db.list_all_rates()?;
     let sent_events = events.len();
     let sent_rates = rates.len();
@@ -740,12 +1073,12 @@ fn handle_sync_connection_server(db: &Db, cfg: &AppConfig, stream: TcpStream) ->
         },
     )?;
 
-    for e in events {
+    for e in &events {
         write_msg(
             &mut writer,
             &SyncMsg::Event {
                 id: e.event_id,
-                payload: e.payload,
+                payload: Box::new(e.payload.clone()),
             },
         )?;
     }
@@ -758,9 +1091,19 @@ fn handle_sync_connection_server(db: &Db, cfg: &AppConfig, stream: TcpStream) ->
                 quote: r.quote,
                 as_of: r.as_of,
                 rate: r.rate,
+                source: Some(outgoing_source(cfg, r.source.as_deref())),
+            },
+        )?;
+    }
+    if peer_protocol >= RECORDS_PROTOCOL {
+        write_msg(
+            &mut writer,
+            &SyncMsg::Records {
+                records: Box::new(SharedRecords::local(db)?),
             },
         )?;
     }
+    send_attachments(db, &mut writer, &events, peer_protocol, &peer_has)?;
     write_msg(&mut writer, &SyncMsg::PullEnd)?;
 
     write_msg(
@@ -774,15 +1117,29 @@ fn handle_sync_connection_server(db: &Db, cfg: &AppConfig, stream: TcpStream) ->
     if let Some(peer) = peer {
         let _ = peer;
     }
+    let matched_tasks = if imported_events > 0 {
+        crate::tasks::match_synced_events(db, cfg)?
+    } else {
+        0
+    };
     Ok(SyncStats {
         imported_events,
         imported_rates,
         sent_events,
         sent_rates,
+        matched_tasks,
+        rejected_events,
+        merged,
     })
 }
 
-fn sync_external(db: &Db, cfg: &mut AppConfig, cfg_path: &Path, argv: Vec<String>) -> Result<()> {
+fn sync_external(
+    db: &Db,
+    cfg: &mut AppConfig,
+    cfg_path: &Path,
+    argv: Vec<String>,
+    accept_large: bool,
+) -> Result<()> {
     // Expected: ["@1", "all"]
     if argv.len() < 2 {
         return Err(anyhow!(
@@ -837,19 +1194,32 @@ fn sync_external(db: &Db, cfg: &mut AppConfig, cfg_path: &Path, argv: Vec<String
             .unwrap_or_else(|| "bankero".to_string()),
         user_host: local_user_host(),
         version: env!("CARGO_PKG_VERSION").to_string(),
+        protocol: PROTOCOL,
+        attachments: crate::attachments::held(db)?.into_iter().collect(),
     };
     write_msg(&mut writer, &hello)?;
 
     let mut line = String::new();
     reader.read_line(&mut line)?;
     let ack = read_msg(line.trim())?;
-    match ack {
-        SyncMsg::HelloAck { .. } => {}
+    let (peer_name, peer_protocol, peer_has) = match ack {
+        SyncMsg::HelloAck {
+            device_id,
+            device_name,
+            protocol,
+            attachments,
+            ..
+        } => {
+            if let Some(message) = crate::revoke::refuse(db, device_id)? {
+                return Err(anyhow!(message));
+            }
+            (device_name, protocol, attachments.into_iter().collect())
+        }
         SyncMsg::Error { message } => return Err(anyhow!(message)),
         _ => return Err(anyhow!("Unexpected response from peer")),
-    }
+    };
 
-    let events = db.list_events()?;
+    let events = shareable_events(db)?;
     let rates = db.list_all_rates()?;
 
     let sent_events = events.len();
@@ -861,12 +1231,12 @@ fn sync_external(db: &Db, cfg: &mut AppConfig, cfg_path: &Path, argv: Vec<String
             rates: sent_rates,
         },
     )?;
-    for e in events {
+    for e in &events {
         write_msg(
             &mut writer,
             &SyncMsg::Event {
                 id: e.event_id,
-                payload: e.payload,
+                payload: Box::new(e.payload.clone()),
             },
         )?;
     }
@@ -879,16 +1249,28 @@ fn sync_external(db: &Db, cfg: &mut AppConfig, cfg_path: &Path, argv: Vec<String
                 quote: r.quote,
                 as_of: r.as_of,
                 rate: r.rate,
+                source: Some(outgoing_source(cfg, r.source.as_deref())),
             },
         )?;
     }
+    if peer_protocol >= RECORDS_PROTOCOL {
+        write_msg(
+            &mut writer,
+            &SyncMsg::Records {
+                records: Box::new(SharedRecords::local(db)?),
+            },
+        )?;
+    }
+    send_attachments(db, &mut writer, &events, peer_protocol, &peer_has)?;
     write_msg(&mut writer, &SyncMsg::PushEnd)?;
 
     // Receive pull.
-    let mut imported_events = 0usize;
+    let mut incoming = Vec::new();
     let mut imported_rates = 0usize;
     let mut peer_imported_events = 0usize;
     let mut peer_imported_rates = 0usize;
+    let mut records = SharedRecords::default();
+    let mut blobs = Vec::new();
     loop {
         line.clear();
         let n = reader.read_line(&mut line)?;
@@ -898,21 +1280,21 @@ fn sync_external(db: &Db, cfg: &mut AppConfig, cfg_path: &Path, argv: Vec<String
         let msg = read_msg(line.trim())?;
         match msg {
             SyncMsg::PullBegin { .. } => {}
-            SyncMsg::Event { id, payload } => {
-                if db.insert_event_ignore(id, &payload)? {
-                    imported_events += 1;
-                }
-            }
+            SyncMsg::Event { id, payload } => incoming.push((id, *payload)),
             SyncMsg::Rate {
                 provider,
                 base,
                 quote,
                 as_of,
                 rate,
+                source,
             } => {
-                db.set_rate(&provider, &base, &quote, as_of, rate)?;
+                let source = incoming_source(&peer_name, source);
+                db.set_rate(&provider, &base, &quote, as_of, rate, &source)?;
                 imported_rates += 1;
             }
+            SyncMsg::Records { records: r } => records.extend(*r),
+            SyncMsg::Attachment { sha256, data } => blobs.push((sha256, data)),
             SyncMsg::PullEnd => {}
             SyncMsg::Summary {
                 imported_events,
@@ -923,10 +1305,29 @@ fn sync_external(db: &Db, cfg: &mut AppConfig, cfg_path: &Path, argv: Vec<String
                 break;
             }
             SyncMsg::Error { message } => return Err(anyhow!(message)),
-            _ => {}
+            SyncMsg::Hello { .. }
+            | SyncMsg::HelloAck { .. }
+            | SyncMsg::PushBegin { .. }
+            | SyncMsg::PushEnd => {}
         }
     }
 
+    check_quota(
+        db,
+        cfg,
+        &peer_name,
+        &incoming,
+        blob_bytes(&blobs),
+        accept_large,
+    )?;
+    let _activity = db.activity(&format!("a LAN sync with {peer_name}"))?;
+    let ((imported_events, rejected_events), merged) = db.with_transaction(|| {
+        Ok((
+            crate::revoke::import_events(db, incoming)?,
+            crate::merge::merge(db, records)?,
+        ))
+    })?;
+    let imported_attachments = store_attachments(db, blobs)?;
     cfg.last_sync_at = Some(now_utc());
     write_config(cfg_path, cfg)?;
 
@@ -938,9 +1339,34 @@ fn sync_external(db: &Db, cfg: &mut AppConfig, cfg_path: &Path, argv: Vec<String
     println!("- imported rates: {imported_rates}");
     println!("- peer imported events: {peer_imported_events}");
     println!("- peer imported rates: {peer_imported_rates}");
+    if imported_attachments > 0 {
+        println!("- imported attachments: {imported_attachments}");
+    }
+    if rejected_events > 0 {
+        println!("- rejected events from revoked devices: {rejected_events}");
+    }
+    print_merged(&merged);
+    if imported_events > 0 {
+        let matched = crate::tasks::match_synced_events(db, cfg)?;
+        if matched > 0 {
+            println!("- matched to task occurrences: {matched}");
+        }
+    }
     Ok(())
 }
 
+fn print_merged(merged: &MergeOutcome) {
+    if merged.budgets + merged.piggies + merged.piggy_funds > 0 {
+        println!(
+            "- merged: {} budget(s), {} piggy(ies), {} piggy fund(s)",
+            merged.budgets, merged.piggies, merged.piggy_funds
+        );
+    }
+    for line in &merged.renamed {
+        println!("- renamed: {line}");
+    }
+}
+
 fn sync_status(db: &Db, cfg: &AppConfig, sync_dir: &Path) -> Result<()> {
     let events = db.count_events().unwrap_or(0);
     let rates = db.count_rates().unwrap_or(0);
@@ -971,11 +1397,19 @@ fn sync_status(db: &Db, cfg: &AppConfig, sync_dir: &Path) -> Result<()> {
     Ok(())
 }
 
+/// Events sent to peers. Snapshot events stay local: they summarize this device's archive,
+/// so the archived events they replaced are sent instead, and a peer that never saw them
+/// still gets the full history.
+fn shareable_events(db: &Db) -> Result<Vec<crate::domain::StoredEvent>> {
+    crate::snapshot::load_events(db, true)
+}
+
 fn export_local(db: &Db, cfg: &AppConfig, sync_dir: &Path) -> Result<()> {
     let dev_root = device_root(sync_dir, &cfg.current_workspace, cfg.device_id);
     ensure_dir(&dev_root)?;
 
-    let events = db.list_events()?;
+    let events = shareable_events(db)?;
+    crate::attachments::export_dir(db, &events, &dev_root.join("attachments"))?;
     let wire_events: Vec<WireEvent> = events
         .into_iter()
         .map(|e| WireEvent {
@@ -997,6 +1431,7 @@ fn export_local(db: &Db, cfg: &AppConfig, sync_dir: &Path) -> Result<()> {
             quote: r.quote,
             as_of: r.as_of,
             rate: r.rate,
+            source: Some(outgoing_source(cfg, r.source.as_deref())),
         })
         .collect();
 
@@ -1004,19 +1439,70 @@ fn export_local(db: &Db, cfg: &AppConfig, sync_dir: &Path) -> Result<()> {
     jsonl_write(&rates_path, &wire_rates)
         .with_context(|| format!("Failed to write {}", rates_path.display()))?;
 
+    let records = SharedRecords::local(db)?;
+    for (file, result) in [
+        (
+            "budgets.jsonl",
+            jsonl_write(&dev_root.join("budgets.jsonl"), &records.budgets),
+        ),
+        (
+            "piggies.jsonl",
+            jsonl_write(&dev_root.join("piggies.jsonl"), &records.piggies),
+        ),
+        (
+            "piggy_funds.jsonl",
+            jsonl_write(&dev_root.join("piggy_funds.jsonl"), &records.piggy_funds),
+        ),
+    ] {
+        result.with_context(|| format!("Failed to write {}", dev_root.join(file).display()))?;
+    }
+
     Ok(())
 }
 
-fn import_remote(db: &Db, cfg: &AppConfig, sync_dir: &Path) -> Result<(usize, usize)> {
+/// Parses every line of a device's jsonl file, if the device exported one.
+fn jsonl_read_all<T: serde::de::DeserializeOwned>(path: &Path) -> Result<Vec<T>> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    jsonl_read_lines(path)?
+        .iter()
+        .map(|line| {
+            serde_json::from_str(line)
+                .with_context(|| format!("Failed to parse line in {}: {}", path.display(), line))
+        })
+        .collect()
+}
+
+/// What a file sync brought in from peers' exports.
+struct FileImport {
+    events: usize,
+    rates: usize,
+    /// Events rejected because their device was revoked.
+    rejected: usize,
+    /// Attachment blobs copied from other devices.
+    attachments: usize,
+    merged: MergeOutcome,
+}
+
+/// What the other devices left in the sync folder, read before anything is written.
+#[derive(Default)]
+struct RemoteFolder {
+    events: Vec<(Uuid, EventPayload)>,
+    /// Rates with the device directory they came from.
+    rates: Vec<(String, WireRate)>,
+    records: SharedRecords,
+    blob_dirs: Vec<PathBuf>,
+}
+
+fn read_remote(cfg: &AppConfig, sync_dir: &Path) -> Result<RemoteFolder> {
     let ws_root = workspace_root(sync_dir, &cfg.current_workspace);
     let devices_root = ws_root.join("devices");
+    let mut remote = RemoteFolder::default();
     if !devices_root.exists() {
-        return Ok((0, 0));
+        return Ok(remote);
     }
 
-    let mut imported_events = 0usize;
-    let mut imported_rates = 0usize;
-
     for entry in fs::read_dir(&devices_root)
         .with_context(|| format!("Failed to read {}", devices_root.display()))?
     {
@@ -1036,13 +1522,13 @@ fn import_remote(db: &Db, cfg: &AppConfig, sync_dir: &Path) -> Result<(usize, us
                         line
                     )
                 })?;
-
-                if db.insert_event_ignore(ev.id, &ev.payload)? {
-                    imported_events += 1;
-                }
+                remote.events.push((ev.id, ev.payload));
             }
         }
 
+        remote.blob_dirs.push(path.join("attachments"));
+
+        let peer_dir = entry.file_name().to_string_lossy().into_owned();
         let rates_path = path.join("rates.jsonl");
         if rates_path.exists() {
             for line in jsonl_read_lines(&rates_path)? {
@@ -1053,24 +1539,67 @@ fn import_remote(db: &Db, cfg: &AppConfig, sync_dir: &Path) -> Result<(usize, us
                         line
                     )
                 })?;
-
-                db.set_rate(
-                    &rate.provider,
-                    &rate.base,
-                    &rate.quote,
-                    rate.as_of,
-                    rate.rate,
-                )?;
-                imported_rates += 1;
+                remote.rates.push((peer_dir.clone(), rate));
             }
         }
+
+        remote.records.extend(SharedRecords {
+            budgets: jsonl_read_all(&path.join("budgets.jsonl"))?,
+            piggies: jsonl_read_all(&path.join("piggies.jsonl"))?,
+            piggy_funds: jsonl_read_all(&path.join("piggy_funds.jsonl"))?,
+        });
     }
+    Ok(remote)
+}
 
-    Ok((imported_events, imported_rates))
+fn import_remote(db: &Db, remote: RemoteFolder) -> Result<FileImport> {
+    let imported_rates = remote.rates.len();
+    for (peer_dir, rate) in remote.rates {
+        db.set_rate(
+            &rate.provider,
+            &rate.base,
+            &rate.quote,
+            rate.as_of,
+            rate.rate,
+            &incoming_source(&peer_dir, rate.source),
+        )?;
+    }
+    let (imported_events, rejected) = crate::revoke::import_events(db, remote.events)?;
+    let wanted = crate::attachments::referenced(&shareable_events(db)?);
+    let mut attachments = 0;
+    for dir in remote.blob_dirs {
+        attachments += crate::attachments::import_dir(db, &dir, &wanted)?;
+    }
+    Ok(FileImport {
+        events: imported_events,
+        rates: imported_rates,
+        rejected,
+        attachments,
+        merged: crate::merge::merge(db, remote.records)?,
+    })
 }
 
-fn sync_now(db: &Db, cfg: &AppConfig, sync_dir: &Path) -> Result<(usize, usize)> {
+/// Exports, then imports what the other devices left.
+///
+/// The folder is read and the quotas checked before the transaction starts, so a quota
+/// prompt never holds the journal's write lock.
+fn sync_now(db: &Db, cfg: &AppConfig, sync_dir: &Path, accept_large: bool) -> Result<FileImport> {
+    let _activity = db.activity("a sync")?;
     ensure_dir(&sync_root(sync_dir))?;
     export_local(db, cfg, sync_dir)?;
-    import_remote(db, cfg, sync_dir)
+    let remote = read_remote(cfg, sync_dir)?;
+    let wanted = crate::attachments::referenced_by(remote.events.iter().map(|(_, p)| p));
+    let mut blob_bytes = 0;
+    for dir in &remote.blob_dirs {
+        blob_bytes += crate::attachments::missing_bytes(db, dir, &wanted)?;
+    }
+    check_quota(
+        db,
+        cfg,
+        "The sync folder",
+        &remote.events,
+        blob_bytes,
+        accept_large,
+    )?;
+    db.with_transaction(|| import_remote(db, remote))
 }
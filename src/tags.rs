@@ -0,0 +1,97 @@
+//! Per-tag spend report (`bankero report tags`).
+//!
+//! Tags double as lightweight projects (`client:acme`, `trip:cancun`). The report totals
+//! what each tag's events spent: postings into `expenses:` and `external:` accounts,
+//! per commodity. An event with several tags counts toward each of them, so the rows
+//! can add up to more than the month's spending. Voided events and their voids are
+//! left out.
+
+use crate::cli::ReportTagsArgs;
+use crate::db::Db;
+use anyhow::Result;
+use rust_decimal::Decimal;
+use std::collections::{BTreeMap, BTreeSet};
+
+fn is_spend_account(account: &str) -> bool {
+    ["expenses", "external"]
+        .iter()
+        .any(|root| account == *root || account.starts_with(&format!("{root}:")))
+}
+
+pub fn handle_tags_report(db: &Db, args: ReportTagsArgs) -> Result<()> {
+    let window = match (&args.month, &args.range) {
+        (Some(m), _) => Some(crate::period::month_range(m)?),
+        (None, Some(r)) => Some(crate::parse_date_range(r)?),
+        (None, None) => None,
+    };
+    let prefix = args.prefix.as_deref().map(str::to_ascii_lowercase);
+
+    // (tag, commodity) -> (total, events)
+    let mut totals: BTreeMap<(String, String), (Decimal, usize)> = BTreeMap::new();
+    let events = crate::snapshot::replay_events(db, false)?;
+    let voided = crate::event::voided(&events);
+    for e in &events {
+        if e.action == crate::event::VOID_ACTION || voided.contains_key(&e.event_id) {
+            continue;
+        }
+        if let Some((start, end)) = window
+            && (e.effective_at < start || e.effective_at > end)
+        {
+            continue;
+        }
+        let mut spent: BTreeMap<String, Decimal> = BTreeMap::new();
+        for p in &e.payload.postings {
+            if p.amount > Decimal::ZERO && is_spend_account(&p.account) {
+                *spent
+                    .entry(p.commodity.to_ascii_uppercase())
+                    .or_insert(Decimal::ZERO) += p.amount;
+            }
+        }
+        if spent.is_empty() {
+            continue;
+        }
+        let tags: BTreeSet<String> = e
+            .payload
+            .tags
+            .iter()
+            .map(|t| t.to_ascii_lowercase())
+            .filter(|t| prefix.as_deref().is_none_or(|p| t.starts_with(p)))
+            .collect();
+        for tag in tags {
+            for (commodity, amount) in &spent {
+                let entry = totals
+                    .entry((tag.clone(), commodity.clone()))
+                    .or_insert((Decimal::ZERO, 0));
+                entry.0 += *amount;
+                entry.1 += 1;
+            }
+        }
+    }
+
+    let headers = ["tag", "commodity", "total", "events"];
+    let rows: Vec<Vec<String>> = totals
+        .into_iter()
+        .map(|((tag, commodity), (total, events))| {
+            vec![
+                tag,
+                commodity,
+                total.normalize().to_string(),
+                events.to_string(),
+            ]
+        })
+        .collect();
+    if args.output.is_table() {
+        return crate::output::write_table(&args.output, &headers, &rows);
+    }
+    args.output.require_table_for_out()?;
+
+    if rows.is_empty() {
+        println!("(no tagged spending)");
+        return Ok(());
+    }
+    println!("{}", headers.join("\t"));
+    for row in rows {
+        println!("{}", row.join("\t"));
+    }
+    Ok(())
+}
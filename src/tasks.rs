@@ -0,0 +1,1344 @@
+//! Recurring task engine.
+//!
+//! A task stores a kind-specific spec plus a schedule anchor. Running a task materializes
+//! every occurrence that is due (catching up on missed ones) as regular journal events,
+//! piggy reservations or report files.
+//!
+//! Dollar-cost averaging tasks convert a fixed amount at a provider's stored rate each
+//! week or month. Their monthly cap counts every purchase the task made that month, on
+//! any device, so catching up on missed weeks never spends more than the cap.
+//!
+//! Recurring transactions copy an existing event (say, this month's rent) and write the
+//! same postings again every week or month, with its provider, basis and `--meta` keys;
+//! rates are looked up again at each occurrence's date.
+//!
+//! Occurrences are written like any other event: unknown commodities, account defaults
+//! and limits are checked. They skip the confirm_over prompt, since creating the task
+//! approved them.
+
+use crate::cli::{TaskCmd, TaskCreateArgs};
+use crate::config::{AppConfig, now_utc};
+use crate::db::{Db, StoredPiggyFund, StoredTask};
+use crate::domain::{BasisContext, EventPayload, Posting, RateContext, StoredEvent};
+use anyhow::{Context, Result, anyhow};
+use chrono::{DateTime, Datelike, Months, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use uuid::Uuid;
+
+/// Kind-specific task parameters, stored as JSON in the `tasks` table.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum TaskSpec {
+    /// Straight-line depreciation of an asset over a fixed number of monthly periods.
+    Depreciation {
+        account: String,
+        expense_account: String,
+        commodity: String,
+        cost: Decimal,
+        periods: u32,
+        /// Project the depreciation expense is charged to.
+        project: String,
+    },
+
+    /// Monthly interest accrual on an account balance (per commodity).
+    Interest {
+        account: String,
+        /// Annual rate as a fraction (0.24 = 24%).
+        rate: Decimal,
+        counter_account: Option<String>,
+        project: String,
+    },
+
+    /// At each occurrence, reserve into a piggy whatever its from_account holds above a floor.
+    PiggySurplus {
+        piggy: String,
+        floor: Decimal,
+        commodity: String,
+    },
+
+    /// Monthly debt payments from one account, split across debts in strategy order.
+    Payoff {
+        strategy: crate::payoff::PayoffStrategy,
+        budget: Decimal,
+        commodity: String,
+        from: String,
+        debts: Vec<crate::payoff::Debt>,
+        project: String,
+    },
+
+    /// At month end, contribute what is left of a budget to a piggy.
+    BudgetSweep { budget: String, piggy: String },
+
+    /// Dollar-cost averaging: converts a fixed amount into another commodity at a
+    /// provider's stored rate, spending at most `monthly_cap` per calendar month.
+    Dca {
+        amount: Decimal,
+        commodity: String,
+        into: String,
+        provider: String,
+        from: String,
+        to: String,
+        monthly_cap: Option<Decimal>,
+        project: String,
+    },
+
+    /// Writes a copy of a template event at each occurrence (rent, subscriptions, salary).
+    Recurring {
+        action: String,
+        postings: Vec<Posting>,
+        tags: Vec<String>,
+        category: Option<String>,
+        note: Option<String>,
+        reference: Option<String>,
+        payee: Option<String>,
+        /// The event the template was copied from.
+        template_event: Uuid,
+        project: String,
+        /// The template's rate context; each occurrence looks its rate up at its own date.
+        #[serde(default)]
+        rate_context: Option<RateContext>,
+        /// The template's basis, as a provider when it was computed from one.
+        #[serde(default)]
+        basis: Option<BasisContext>,
+        /// The template's `--meta` keys.
+        #[serde(default)]
+        meta: serde_json::Map<String, serde_json::Value>,
+    },
+
+    /// Runs `bankero report ...` and saves its output as a dated file in `out_dir`.
+    Report {
+        /// Arguments after `bankero`, with `{month}`-style placeholders unexpanded.
+        args: Vec<String>,
+        /// Target directory as given (`~` is expanded at run time).
+        out_dir: String,
+    },
+}
+
+impl TaskSpec {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            TaskSpec::Depreciation { .. } => "depreciation",
+            TaskSpec::Interest { .. } => "interest",
+            TaskSpec::PiggySurplus { .. } => "piggy_surplus",
+            TaskSpec::Payoff { .. } => "payoff",
+            TaskSpec::BudgetSweep { .. } => "budget_sweep",
+            TaskSpec::Dca { .. } => "dca",
+            TaskSpec::Recurring { .. } => "recurring",
+            TaskSpec::Report { .. } => "report",
+        }
+    }
+
+    /// Tasks that write journal events, so the same task running on another device
+    /// writes the same occurrences and a synced copy must not be written again.
+    fn writes_events(&self) -> bool {
+        matches!(
+            self,
+            TaskSpec::Depreciation { .. }
+                | TaskSpec::Interest { .. }
+                | TaskSpec::Payoff { .. }
+                | TaskSpec::Recurring { .. }
+                | TaskSpec::Dca { .. }
+        )
+    }
+
+    /// Total number of occurrences, or `None` for open-ended tasks.
+    fn max_runs(&self) -> Option<u32> {
+        match self {
+            TaskSpec::Depreciation { periods, .. } => Some(*periods),
+            TaskSpec::Interest { .. }
+            | TaskSpec::PiggySurplus { .. }
+            | TaskSpec::Payoff { .. }
+            | TaskSpec::BudgetSweep { .. }
+            | TaskSpec::Dca { .. }
+            | TaskSpec::Recurring { .. }
+            | TaskSpec::Report { .. } => None,
+        }
+    }
+}
+
+/// How often a task fires.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Schedule {
+    Monthly,
+    Weekly,
+}
+
+impl Schedule {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Schedule::Monthly => "monthly",
+            Schedule::Weekly => "weekly",
+        }
+    }
+
+    fn parse(raw: &str) -> Result<Self> {
+        match raw {
+            "monthly" => Ok(Schedule::Monthly),
+            "weekly" => Ok(Schedule::Weekly),
+            other => Err(anyhow!("Unknown task schedule: {other}")),
+        }
+    }
+
+    /// Returns the `n`-th occurrence (0-based) counted from `anchor`.
+    ///
+    /// Anchors on the last day of a month stay on month ends (Jan 31 -> Feb 28 -> Mar 31).
+    fn occurrence(&self, anchor: DateTime<Utc>, n: u32) -> Result<DateTime<Utc>> {
+        match self {
+            Schedule::Monthly => {
+                let at = anchor
+                    .checked_add_months(Months::new(n))
+                    .ok_or_else(|| anyhow!("Task occurrence out of range"))?;
+                if is_last_day_of_month(anchor) {
+                    Ok(last_day_of_month(at))
+                } else {
+                    Ok(at)
+                }
+            }
+            Schedule::Weekly => Ok(anchor + chrono::Duration::weeks(i64::from(n))),
+        }
+    }
+}
+
+fn is_last_day_of_month(t: DateTime<Utc>) -> bool {
+    (t + chrono::Duration::days(1)).month() != t.month()
+}
+
+fn last_day_of_month(t: DateTime<Utc>) -> DateTime<Utc> {
+    let mut out = t;
+    while !is_last_day_of_month(out) {
+        out += chrono::Duration::days(1);
+    }
+    out
+}
+
+pub fn create_task(
+    db: &Db,
+    task_id: &str,
+    schedule: Schedule,
+    anchor_at: DateTime<Utc>,
+    spec: &TaskSpec,
+) -> Result<StoredTask> {
+    if db.get_task(task_id)?.is_some() {
+        return Err(anyhow!("Task '{task_id}' already exists"));
+    }
+
+    let task = StoredTask {
+        id: Uuid::new_v4(),
+        task_id: task_id.to_string(),
+        kind: spec.kind().to_string(),
+        schedule: schedule.as_str().to_string(),
+        spec: serde_json::to_value(spec)?,
+        enabled: true,
+        anchor_at,
+        runs: 0,
+        last_run_at: None,
+        created_at: now_utc(),
+    };
+    db.insert_task(&task)?;
+    Ok(task)
+}
+
+/// Next occurrence that has not been materialized yet (None once the task is finished).
+pub fn next_occurrence(task: &StoredTask) -> Result<Option<DateTime<Utc>>> {
+    let spec = parse_spec(task)?;
+    if spec.max_runs().is_some_and(|max| task.runs >= max) {
+        return Ok(None);
+    }
+    let schedule = Schedule::parse(&task.schedule)?;
+    Ok(Some(schedule.occurrence(task.anchor_at, task.runs)?))
+}
+
+fn parse_spec(task: &StoredTask) -> Result<TaskSpec> {
+    serde_json::from_value(task.spec.clone())
+        .with_context(|| format!("Invalid spec for task '{}'", task.task_id))
+}
+
+/// What a single occurrence writes.
+enum TaskOutput {
+    Event(Box<EventPayload>),
+    PiggyFund(StoredPiggyFund),
+    File {
+        path: std::path::PathBuf,
+        contents: Vec<u8>,
+    },
+}
+
+/// Counts of what a task run wrote.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct RunStats {
+    pub events: usize,
+    pub piggy_funds: usize,
+    pub files: usize,
+    /// Occurrences skipped because a synced event already records them.
+    pub fulfilled: usize,
+}
+
+/// Whether `e` was written by this task for occurrence `n` (0-based), on any device.
+fn materialized_by(task: &StoredTask, n: u32, e: &StoredEvent) -> bool {
+    let meta = &e.payload.metadata;
+    meta.get("task_id").and_then(|v| v.as_str()) == Some(task.task_id.as_str())
+        && meta.get("occurrence").and_then(|v| v.as_u64()) == Some(u64::from(n) + 1)
+}
+
+/// Matches events synced from other devices to pending occurrences of the tasks that
+/// wrote them (by their `task_id` and `occurrence` metadata), so a later run does not
+/// write them again. Returns how many were matched.
+pub fn match_synced_events(db: &Db, cfg: &AppConfig) -> Result<usize> {
+    let tasks: HashMap<String, StoredTask> = db
+        .list_tasks()?
+        .into_iter()
+        .map(|t| (t.task_id.clone(), t))
+        .collect();
+    let mut claimed: HashSet<Uuid> = db.list_fulfilling_event_ids()?.into_iter().collect();
+
+    let mut matched = 0;
+    for e in db.list_events()? {
+        if e.payload.device_id == cfg.device_id || claimed.contains(&e.event_id) {
+            continue;
+        }
+        let meta = &e.payload.metadata;
+        let Some(task) = meta
+            .get("task_id")
+            .and_then(|v| v.as_str())
+            .and_then(|id| tasks.get(id))
+        else {
+            continue;
+        };
+        let Some(occurrence) = meta
+            .get("occurrence")
+            .and_then(|v| v.as_u64())
+            .and_then(|n| u32::try_from(n).ok())
+        else {
+            continue;
+        };
+        if occurrence <= task.runs
+            || db
+                .get_task_fulfillment(&task.task_id, occurrence)?
+                .is_some()
+        {
+            continue;
+        }
+        db.insert_task_fulfillment(&task.task_id, occurrence, e.event_id, now_utc())?;
+        claimed.insert(e.event_id);
+        matched += 1;
+    }
+    Ok(matched)
+}
+
+/// Materializes every occurrence due at or before `as_of`.
+pub fn run_task(
+    db: &Db,
+    cfg: &AppConfig,
+    task: &StoredTask,
+    as_of: DateTime<Utc>,
+) -> Result<RunStats> {
+    let spec = parse_spec(task)?;
+    let schedule = Schedule::parse(&task.schedule)?;
+
+    let mut runs = task.runs;
+    let mut stats = RunStats::default();
+    let events = if spec.writes_events() {
+        db.list_events()?
+    } else {
+        vec![]
+    };
+    loop {
+        if spec.max_runs().is_some_and(|max| runs >= max) {
+            break;
+        }
+        let at = schedule.occurrence(task.anchor_at, runs)?;
+        if at > as_of {
+            break;
+        }
+
+        let fulfilled = spec.writes_events()
+            && (db.get_task_fulfillment(&task.task_id, runs + 1)?.is_some()
+                || events.iter().any(|e| materialized_by(task, runs, e)));
+        if fulfilled {
+            stats.fulfilled += 1;
+            runs += 1;
+            db.set_task_progress(&task.task_id, runs, now_utc())?;
+            continue;
+        }
+
+        for output in materialize(db, cfg, task, &spec, runs, at)? {
+            match output {
+                TaskOutput::Event(payload) => {
+                    let write = crate::WriteOptions::default();
+                    if crate::maybe_confirm_and_insert(db, cfg, Uuid::new_v4(), &payload, &write)? {
+                        stats.events += 1;
+                    }
+                }
+                TaskOutput::PiggyFund(fund) => {
+                    db.insert_piggy_fund(&fund)?;
+                    stats.piggy_funds += 1;
+                }
+                TaskOutput::File { path, contents } => {
+                    if let Some(dir) = path.parent() {
+                        std::fs::create_dir_all(dir)
+                            .with_context(|| format!("Failed to create {}", dir.display()))?;
+                    }
+                    std::fs::write(&path, contents)
+                        .with_context(|| format!("Failed to write {}", path.display()))?;
+                    stats.files += 1;
+                }
+            }
+        }
+        runs += 1;
+        db.set_task_progress(&task.task_id, runs, now_utc())?;
+    }
+
+    Ok(stats)
+}
+
+fn materialize(
+    db: &Db,
+    cfg: &AppConfig,
+    task: &StoredTask,
+    spec: &TaskSpec,
+    n: u32,
+    at: DateTime<Utc>,
+) -> Result<Vec<TaskOutput>> {
+    match spec {
+        TaskSpec::Depreciation {
+            account,
+            expense_account,
+            commodity,
+            cost,
+            periods,
+            project,
+        } => {
+            let per_period = (*cost / Decimal::from(*periods)).round_dp(2);
+            // The final period absorbs the rounding remainder so the asset ends at exactly zero.
+            let amount = if n + 1 == *periods {
+                *cost - per_period * Decimal::from(periods - 1)
+            } else {
+                per_period
+            };
+
+            Ok(vec![TaskOutput::Event(Box::new(EventPayload {
+                schema_version: 1,
+                device_id: cfg.device_id,
+                workspace: cfg.current_workspace.clone(),
+                project: project.clone(),
+                action: "depreciation".to_string(),
+                created_at: now_utc(),
+                effective_at: at,
+                postings: vec![
+                    Posting {
+                        account: account.clone(),
+                        commodity: commodity.clone(),
+                        amount: -amount,
+                    },
+                    Posting {
+                        account: expense_account.clone(),
+                        commodity: commodity.clone(),
+                        amount,
+                    },
+                ],
+                tags: vec![],
+                category: Some(expense_account.clone()),
+                note: Some(format!(
+                    "Depreciation {}/{} for {}",
+                    n + 1,
+                    periods,
+                    account
+                )),
+                reference: None,
+                rate_context: RateContext {
+                    provider: None,
+                    override_rate: None,
+                    base: None,
+                    quote: None,
+                    as_of: at,
+                },
+                basis: None,
+                metadata: serde_json::json!({
+                    "task_id": task.task_id,
+                    "occurrence": n + 1,
+                    "method": "straight-line",
+                }),
+            }))])
+        }
+        TaskSpec::Interest {
+            account,
+            rate,
+            counter_account,
+            project,
+        } => {
+            let events = crate::snapshot::replay_events(db, false)?;
+            let mut out = Vec::new();
+            for (commodity, balance) in account_balances(&events, account, at) {
+                let interest = (balance * *rate / Decimal::from(12u32)).round_dp(2);
+                if interest.is_zero() {
+                    continue;
+                }
+                // Debts (negative balances) grow; savings (positive balances) earn.
+                let counter = counter_account.clone().unwrap_or_else(|| {
+                    if balance < Decimal::ZERO {
+                        "expenses:interest".to_string()
+                    } else {
+                        "income:interest".to_string()
+                    }
+                });
+
+                out.push(TaskOutput::Event(Box::new(EventPayload {
+                    schema_version: 1,
+                    device_id: cfg.device_id,
+                    workspace: cfg.current_workspace.clone(),
+                    project: project.clone(),
+                    action: "interest".to_string(),
+                    created_at: now_utc(),
+                    effective_at: at,
+                    postings: vec![
+                        Posting {
+                            account: account.clone(),
+                            commodity: commodity.clone(),
+                            amount: interest,
+                        },
+                        Posting {
+                            account: counter.clone(),
+                            commodity: commodity.clone(),
+                            amount: -interest,
+                        },
+                    ],
+                    tags: vec![],
+                    category: Some(counter),
+                    note: Some(format!("Interest accrual on {account}")),
+                    reference: None,
+                    rate_context: RateContext {
+                        provider: None,
+                        override_rate: None,
+                        base: None,
+                        quote: None,
+                        as_of: at,
+                    },
+                    basis: None,
+                    metadata: serde_json::json!({
+                        "task_id": task.task_id,
+                        "occurrence": n + 1,
+                        "annual_rate": rate.to_string(),
+                        "balance": balance.to_string(),
+                    }),
+                })));
+            }
+            Ok(out)
+        }
+        TaskSpec::PiggySurplus {
+            piggy,
+            floor,
+            commodity,
+        } => {
+            let Some(target) = db.get_piggy_by_name(piggy)? else {
+                return Err(anyhow!(
+                    "Task '{}' funds piggy '{}', which no longer exists",
+                    task.task_id,
+                    piggy
+                ));
+            };
+            let events = crate::snapshot::replay_events(db, false)?;
+            let balance = account_balances(&events, &target.from_account, at)
+                .get(commodity)
+                .copied()
+                .unwrap_or(Decimal::ZERO);
+
+            // Money already set aside from the same account (by any piggy) is not surplus.
+            let mut reserved = Decimal::ZERO;
+            let mut own_funded = Decimal::ZERO;
+            for p in db.list_piggies()? {
+                if p.from_account != target.from_account || p.commodity != target.commodity {
+                    continue;
+                }
+                let funded: Decimal = db
+                    .list_piggy_funds(p.id)?
+                    .iter()
+                    .filter(|f| f.effective_at <= at)
+                    .map(|f| f.amount)
+                    .sum();
+                reserved += funded.min(p.target_amount);
+                if p.id == target.id {
+                    own_funded = funded;
+                }
+            }
+
+            let surplus = balance - *floor - reserved;
+            let room = (target.target_amount - own_funded).max(Decimal::ZERO);
+            let amount = surplus.min(room).round_dp(2);
+            if amount <= Decimal::ZERO {
+                return Ok(vec![]);
+            }
+            Ok(vec![TaskOutput::PiggyFund(StoredPiggyFund {
+                id: Uuid::new_v4(),
+                piggy_id: target.id,
+                amount,
+                effective_at: at,
+                device_id: Some(cfg.device_id),
+                created_at: now_utc(),
+            })])
+        }
+        TaskSpec::Payoff {
+            strategy,
+            budget,
+            commodity,
+            from,
+            debts,
+            project,
+        } => {
+            let events = crate::snapshot::replay_events(db, false)?;
+            let current: Vec<crate::payoff::Debt> = debts
+                .iter()
+                .map(|d| crate::payoff::Debt {
+                    owed: -account_balances(&events, &d.account, at)
+                        .get(commodity)
+                        .copied()
+                        .unwrap_or(Decimal::ZERO),
+                    ..d.clone()
+                })
+                .collect();
+            let payments = crate::payoff::allocate_payment(*strategy, *budget, &current);
+
+            let mut out = Vec::new();
+            for (debt, amount) in current.iter().zip(payments) {
+                if amount <= Decimal::ZERO {
+                    continue;
+                }
+                out.push(TaskOutput::Event(Box::new(EventPayload {
+                    schema_version: 1,
+                    device_id: cfg.device_id,
+                    workspace: cfg.current_workspace.clone(),
+                    project: project.clone(),
+                    action: "move".to_string(),
+                    created_at: now_utc(),
+                    effective_at: at,
+                    postings: vec![
+                        Posting {
+                            account: from.clone(),
+                            commodity: commodity.clone(),
+                            amount: -amount,
+                        },
+                        Posting {
+                            account: debt.account.clone(),
+                            commodity: commodity.clone(),
+                            amount,
+                        },
+                    ],
+                    tags: vec![],
+                    category: None,
+                    note: Some(format!(
+                        "Debt payment ({}) to {}",
+                        strategy.as_str(),
+                        debt.account
+                    )),
+                    reference: None,
+                    rate_context: RateContext {
+                        provider: None,
+                        override_rate: None,
+                        base: None,
+                        quote: None,
+                        as_of: at,
+                    },
+                    basis: None,
+                    metadata: serde_json::json!({
+                        "task_id": task.task_id,
+                        "occurrence": n + 1,
+                        "strategy": strategy.as_str(),
+                        "owed": debt.owed.to_string(),
+                    }),
+                })));
+            }
+            Ok(out)
+        }
+        TaskSpec::BudgetSweep { budget, piggy } => {
+            let Some(b) = db.get_budget_by_name(budget)? else {
+                return Err(anyhow!(
+                    "Task '{}' sweeps budget '{}', which no longer exists",
+                    task.task_id,
+                    budget
+                ));
+            };
+            let Some(target) = db.get_piggy_by_name(piggy)? else {
+                return Err(anyhow!(
+                    "Task '{}' funds piggy '{}', which no longer exists",
+                    task.task_id,
+                    piggy
+                ));
+            };
+            let month = crate::current_month_yyyy_mm(at);
+            if b.month.as_deref().is_some_and(|m| m != month) {
+                return Ok(vec![]);
+            }
+            let (start, end) = crate::period::month_range(&month)?;
+            let events = crate::snapshot::replay_events(db, false)?;
+            let actual = crate::compute_budget_actual(db, &events, start, end, &b, false)?;
+            let remaining = b.amount - actual;
+            let funded: Decimal = db
+                .list_piggy_funds(target.id)?
+                .iter()
+                .filter(|f| f.effective_at <= at)
+                .map(|f| f.amount)
+                .sum();
+            let room = (target.target_amount - funded).max(Decimal::ZERO);
+            let amount = remaining.min(room).round_dp(2);
+            if amount <= Decimal::ZERO {
+                return Ok(vec![]);
+            }
+
+            let fund = StoredPiggyFund {
+                id: Uuid::new_v4(),
+                piggy_id: target.id,
+                amount,
+                effective_at: at,
+                device_id: Some(cfg.device_id),
+                created_at: now_utc(),
+            };
+            // The contribution itself is a reservation; the event is the audit record.
+            let audit = EventPayload {
+                schema_version: 1,
+                device_id: cfg.device_id,
+                workspace: cfg.current_workspace.clone(),
+                project: cfg.current_project.clone(),
+                action: "budget_sweep".to_string(),
+                created_at: now_utc(),
+                effective_at: at,
+                postings: vec![],
+                tags: vec![],
+                category: b.category.clone(),
+                note: Some(format!(
+                    "Budget '{}' {month}: {} of {} {} left, {} to piggy '{}'",
+                    b.name,
+                    remaining.normalize(),
+                    b.amount.normalize(),
+                    b.commodity,
+                    amount.normalize(),
+                    target.name
+                )),
+                reference: None,
+                rate_context: RateContext {
+                    provider: None,
+                    override_rate: None,
+                    base: None,
+                    quote: None,
+                    as_of: at,
+                },
+                basis: None,
+                metadata: serde_json::json!({
+                    "task_id": task.task_id,
+                    "occurrence": n + 1,
+                    "budget": b.name,
+                    "piggy": target.name,
+                    "month": month,
+                    "budget_amount": b.amount.to_string(),
+                    "actual": actual.to_string(),
+                    "remaining": remaining.to_string(),
+                    "amount": amount.to_string(),
+                    "commodity": b.commodity,
+                    "piggy_fund_id": fund.id.to_string(),
+                }),
+            };
+            Ok(vec![
+                TaskOutput::PiggyFund(fund),
+                TaskOutput::Event(Box::new(audit)),
+            ])
+        }
+        TaskSpec::Dca {
+            amount,
+            commodity,
+            into,
+            provider,
+            from,
+            to,
+            monthly_cap,
+            project,
+        } => {
+            let month = crate::current_month_yyyy_mm(at);
+            let spent = dca_spent_in_month(db, task, commodity, &month)?;
+            let amount = match monthly_cap {
+                Some(cap) => (*amount).min(*cap - spent),
+                None => *amount,
+            };
+            if amount <= Decimal::ZERO {
+                return Ok(vec![]);
+            }
+
+            // A stored rate in either direction: USD->BTC, or the usual BTC->USD quote.
+            let (base, quote, rate, bought) = if let Some((_, rate)) =
+                crate::providers::rate_as_of(db, provider, commodity, into, at)?
+            {
+                (commodity, into, rate, amount * rate)
+            } else if let Some((_, rate)) =
+                crate::providers::rate_as_of(db, provider, into, commodity, at)?
+                && !rate.is_zero()
+            {
+                (into, commodity, rate, amount / rate)
+            } else {
+                return Err(anyhow!(
+                    "Task '{}' has no @{provider} rate between {commodity} and {into} at or before {}. Set one with: bankero rate set @{provider} {into} {commodity} <rate> --as-of <rfc3339>",
+                    task.task_id,
+                    at.to_rfc3339()
+                ));
+            };
+            let bought = crate::commodities::round(db, into, bought)?;
+
+            Ok(vec![TaskOutput::Event(Box::new(EventPayload {
+                schema_version: 1,
+                device_id: cfg.device_id,
+                workspace: cfg.current_workspace.clone(),
+                project: project.clone(),
+                action: "move".to_string(),
+                created_at: now_utc(),
+                effective_at: at,
+                postings: vec![
+                    Posting {
+                        account: from.clone(),
+                        commodity: commodity.clone(),
+                        amount: -amount,
+                    },
+                    Posting {
+                        account: to.clone(),
+                        commodity: into.clone(),
+                        amount: bought,
+                    },
+                ],
+                tags: vec![],
+                category: None,
+                note: Some(format!("DCA {amount} {commodity} into {into}")),
+                reference: None,
+                rate_context: RateContext {
+                    provider: Some(format!("@{provider}")),
+                    override_rate: Some(rate),
+                    base: Some(base.clone()),
+                    quote: Some(quote.clone()),
+                    as_of: at,
+                },
+                basis: Some(BasisContext::Fixed {
+                    amount,
+                    commodity: commodity.clone(),
+                }),
+                metadata: serde_json::json!({
+                    "task_id": task.task_id,
+                    "occurrence": n + 1,
+                    "dca": true,
+                    "month_spent": (spent + amount).to_string(),
+                    "monthly_cap": monthly_cap.map(|c| c.to_string()),
+                }),
+            }))])
+        }
+        TaskSpec::Recurring {
+            action,
+            postings,
+            tags,
+            category,
+            note,
+            reference,
+            payee,
+            template_event,
+            project,
+            rate_context,
+            basis,
+            meta,
+        } => {
+            let mut metadata = serde_json::Value::Object(meta.clone());
+            metadata["task_id"] = serde_json::json!(task.task_id);
+            metadata["occurrence"] = serde_json::json!(n + 1);
+            metadata["template_event"] = serde_json::json!(template_event.to_string());
+            metadata["payee"] = serde_json::json!(payee);
+            Ok(vec![TaskOutput::Event(Box::new(EventPayload {
+                schema_version: 1,
+                device_id: cfg.device_id,
+                workspace: cfg.current_workspace.clone(),
+                project: project.clone(),
+                action: action.clone(),
+                created_at: now_utc(),
+                effective_at: at,
+                postings: postings.clone(),
+                tags: tags.clone(),
+                category: category.clone(),
+                note: note.clone(),
+                reference: reference.clone(),
+                rate_context: occurrence_rate(db, rate_context.as_ref(), at)?,
+                basis: basis.clone(),
+                metadata,
+            }))])
+        }
+        TaskSpec::Report { args, out_dir } => {
+            let args: Vec<String> = args.iter().map(|a| expand_placeholders(a, at)).collect();
+            let exe = std::env::current_exe().context("Failed to locate the bankero binary")?;
+            let run = std::process::Command::new(exe)
+                .args(&args)
+                .output()
+                .with_context(|| format!("Failed to run report task '{}'", task.task_id))?;
+            if !run.status.success() {
+                return Err(anyhow!(
+                    "Report task '{}' failed for {}: {}",
+                    task.task_id,
+                    at.to_rfc3339(),
+                    String::from_utf8_lossy(&run.stderr).trim()
+                ));
+            }
+
+            let extension = match args
+                .iter()
+                .position(|a| a == "--output")
+                .and_then(|i| args.get(i + 1))
+                .map(String::as_str)
+            {
+                Some("html") => "html",
+                Some("csv") => "csv",
+                _ => "txt",
+            };
+            let path = crate::config::expand_home(out_dir)?.join(format!(
+                "{}-{}.{extension}",
+                task.task_id,
+                at.format("%Y-%m-%d")
+            ));
+            Ok(vec![TaskOutput::File {
+                path,
+                contents: run.stdout,
+            }])
+        }
+    }
+}
+
+/// A recurring template's rate context at occurrence `at`: the provider's rate at that
+/// date when it has one stored, the template's own rate otherwise.
+fn occurrence_rate(
+    db: &Db,
+    template: Option<&RateContext>,
+    at: DateTime<Utc>,
+) -> Result<RateContext> {
+    let Some(template) = template else {
+        return Ok(RateContext {
+            provider: None,
+            override_rate: None,
+            base: None,
+            quote: None,
+            as_of: at,
+        });
+    };
+    let mut rc = RateContext {
+        as_of: at,
+        ..template.clone()
+    };
+    if let (Some(provider), Some(base), Some(quote)) = (&rc.provider, &rc.base, &rc.quote)
+        && crate::providers::rate_as_of(
+            db,
+            &crate::normalize_provider(provider),
+            &base.to_ascii_uppercase(),
+            &quote.to_ascii_uppercase(),
+            at,
+        )?
+        .is_some()
+    {
+        rc.override_rate = None;
+    }
+    Ok(rc)
+}
+
+/// Fills `{month}`, `{prev-month}` (YYYY-MM) and `{date}` (YYYY-MM-DD) for occurrence `at`.
+fn expand_placeholders(raw: &str, at: DateTime<Utc>) -> String {
+    let prev = at.checked_sub_months(Months::new(1)).unwrap_or(at);
+    raw.replace("{month}", &at.format("%Y-%m").to_string())
+        .replace("{prev-month}", &prev.format("%Y-%m").to_string())
+        .replace("{date}", &at.format("%Y-%m-%d").to_string())
+}
+
+/// Splits `--report` into arguments: whitespace-separated, with '...' or "..." quoting.
+fn split_args(raw: &str) -> Result<Vec<String>> {
+    let mut out = Vec::new();
+    let mut current: Option<String> = None;
+    let mut quote: Option<char> = None;
+    for c in raw.chars() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), c) => current.get_or_insert_default().push(c),
+            (None, '\'' | '"') => {
+                quote = Some(c);
+                current.get_or_insert_default();
+            }
+            (None, c) if c.is_whitespace() => out.extend(current.take()),
+            (None, c) => current.get_or_insert_default().push(c),
+        }
+    }
+    if quote.is_some() {
+        return Err(anyhow!("Unterminated quote in --report: {raw}"));
+    }
+    out.extend(current);
+    Ok(out)
+}
+
+/// What DCA task `task` already spent of `commodity` in `month`, on any device.
+/// Purchases that were voided since do not count against the cap.
+fn dca_spent_in_month(db: &Db, task: &StoredTask, commodity: &str, month: &str) -> Result<Decimal> {
+    let events = crate::snapshot::replay_events(db, false)?;
+    let voided = crate::event::voided(&events);
+    Ok(events
+        .iter()
+        .filter(|e| {
+            e.payload.metadata.get("task_id").and_then(|v| v.as_str())
+                == Some(task.task_id.as_str())
+                && crate::current_month_yyyy_mm(e.effective_at) == month
+                && !voided.contains_key(&e.event_id)
+        })
+        .flat_map(|e| &e.payload.postings)
+        .filter(|p| p.commodity == commodity && p.amount.is_sign_negative())
+        .map(|p| -p.amount)
+        .sum())
+}
+
+/// Per-commodity balance of exactly `account` from events effective at or before `at`.
+fn account_balances(
+    events: &[crate::domain::StoredEvent],
+    account: &str,
+    at: DateTime<Utc>,
+) -> std::collections::BTreeMap<String, Decimal> {
+    let mut out = std::collections::BTreeMap::new();
+    for e in events {
+        if e.effective_at > at {
+            continue;
+        }
+        for p in &e.payload.postings {
+            if p.account == account {
+                *out.entry(p.commodity.clone()).or_insert(Decimal::ZERO) += p.amount;
+            }
+        }
+    }
+    out
+}
+
+/// `task create`: the kind follows from which flags were given.
+fn handle_create(db: &Db, cfg: &AppConfig, args: TaskCreateArgs) -> Result<()> {
+    match args {
+        TaskCreateArgs {
+            task_id,
+            report: Some(report),
+            out,
+            start,
+            ..
+        } => {
+            let mut args = split_args(&report)?;
+            if args.first().map(String::as_str) == Some("bankero") {
+                args.remove(0);
+            }
+            if args.first().map(String::as_str) != Some("report") {
+                return Err(anyhow!(
+                    "--report runs a report, e.g. --report \"report --month {{prev-month}} --output html\""
+                ));
+            }
+            if args.iter().any(|a| a == "--out" || a.starts_with("--out=")) {
+                return Err(anyhow!(
+                    "--report must not contain --out; pass the directory to task create --out"
+                ));
+            }
+            let out_dir = out.ok_or_else(|| anyhow!("Report tasks require --out <dir>"))?;
+
+            // The 1st of next month, so {prev-month} is the month that just closed.
+            let anchor_at = match start {
+                Some(raw) => crate::parse_rfc3339_or_now(Some(&raw))?,
+                None => {
+                    let (_, end) =
+                        crate::period::month_range(&crate::current_month_yyyy_mm(now_utc()))?;
+                    end + chrono::Duration::seconds(1)
+                }
+            };
+            let spec = TaskSpec::Report {
+                args: args.clone(),
+                out_dir: out_dir.clone(),
+            };
+            create_task(db, &task_id, Schedule::Monthly, anchor_at, &spec)?;
+            println!(
+                "Created task '{}': monthly `bankero {}` into {}, first run {}.",
+                task_id,
+                args.join(" "),
+                out_dir,
+                anchor_at.to_rfc3339()
+            );
+            Ok(())
+        }
+        TaskCreateArgs {
+            task_id,
+            from_event: Some(template),
+            weekly,
+            monthly,
+            start,
+            ..
+        } => {
+            let events = crate::snapshot::load_events(db, false)?;
+            let found = crate::show::find_event(&events, &template)?;
+            // An amended event repeats as it stands now.
+            let e = crate::history::current_version(&events, found);
+            if let Some(v) = crate::event::voided(&events).get(&e.event_id) {
+                return Err(anyhow!(
+                    "Event {} was voided by event {}; pick another template",
+                    e.event_id,
+                    v.event_id
+                ));
+            }
+            if e.payload.metadata.get("task_id").is_some() {
+                return Err(anyhow!(
+                    "Event {} was written by a task; use the event it was copied from",
+                    e.event_id
+                ));
+            }
+            if e.payload.postings.is_empty() {
+                return Err(anyhow!(
+                    "Event {} has no postings to repeat ({} event)",
+                    e.event_id,
+                    e.action
+                ));
+            }
+            let schedule = match (weekly, monthly) {
+                (true, _) => Schedule::Weekly,
+                (false, true) => Schedule::Monthly,
+                (false, false) => {
+                    return Err(anyhow!(
+                        "Recurring tasks require a schedule: --weekly or --monthly"
+                    ));
+                }
+            };
+
+            // The template is the first occurrence, so the task starts one period later.
+            let anchor_at = match start {
+                Some(raw) => crate::parse_rfc3339_or_now(Some(&raw))?,
+                None => schedule.occurrence(e.effective_at, 1)?,
+            };
+            let spec = TaskSpec::Recurring {
+                action: e.action.clone(),
+                postings: e.payload.postings.clone(),
+                tags: e.payload.tags.clone(),
+                category: e.payload.category.clone(),
+                note: e.payload.note.clone(),
+                reference: e.payload.reference.clone(),
+                payee: e.payload.metadata["payee"].as_str().map(str::to_string),
+                template_event: e.event_id,
+                project: e.payload.project.clone(),
+                rate_context: e
+                    .payload
+                    .rate_context
+                    .provider
+                    .is_some()
+                    .then(|| e.payload.rate_context.clone()),
+                // A basis computed from a provider is computed again for each occurrence.
+                basis: match e.payload.metadata["basis_provider"].as_str() {
+                    Some(provider) => Some(BasisContext::Provider {
+                        provider: provider.to_string(),
+                    }),
+                    None => e.payload.basis.clone(),
+                },
+                meta: e
+                    .payload
+                    .metadata
+                    .as_object()
+                    .into_iter()
+                    .flatten()
+                    .filter(|(key, _)| !crate::RESERVED_META_KEYS.contains(&key.as_str()))
+                    .map(|(key, value)| (key.clone(), value.clone()))
+                    .collect(),
+            };
+            create_task(db, &task_id, schedule, anchor_at, &spec)?;
+            println!(
+                "Created task '{}': {} {} {}, first run {}.",
+                task_id,
+                schedule.as_str(),
+                e.action,
+                crate::history::summary(e),
+                anchor_at.to_rfc3339()
+            );
+            Ok(())
+        }
+        TaskCreateArgs {
+            task_id,
+            dca: Some(amount),
+            commodity,
+            into,
+            provider,
+            from,
+            to,
+            monthly_cap,
+            weekly,
+            monthly,
+            start,
+            ..
+        } => {
+            let amount = crate::parse_decimal(amount, "dca")?;
+            if amount <= Decimal::ZERO {
+                return Err(anyhow!("--dca must be > 0"));
+            }
+            let missing = |flag: &str| anyhow!("DCA tasks require {flag}");
+            let commodity = commodity
+                .ok_or_else(|| missing("--commodity"))?
+                .to_ascii_uppercase();
+            let into = into.ok_or_else(|| missing("--into"))?.to_ascii_uppercase();
+            if commodity == into {
+                return Err(anyhow!("--into must differ from --commodity"));
+            }
+            let provider = provider.ok_or_else(|| missing("--provider"))?;
+            let provider = crate::domain::parse_provider_token(&provider)
+                .filter(|p| p.override_rate.is_none())
+                .ok_or_else(|| anyhow!("--provider must look like @binance"))?
+                .provider;
+            let from = from.ok_or_else(|| missing("--from"))?;
+            let to = to.ok_or_else(|| missing("--to"))?;
+            let monthly_cap = monthly_cap
+                .map(|raw| crate::parse_decimal(raw, "monthly-cap"))
+                .transpose()?;
+            if monthly_cap.is_some_and(|cap| cap <= Decimal::ZERO) {
+                return Err(anyhow!("--monthly-cap must be > 0"));
+            }
+            let schedule = match (weekly, monthly) {
+                (true, _) => Schedule::Weekly,
+                (false, true) => Schedule::Monthly,
+                (false, false) => {
+                    return Err(anyhow!(
+                        "DCA tasks require a schedule: --weekly or --monthly"
+                    ));
+                }
+            };
+
+            let anchor_at = crate::parse_rfc3339_or_now(start.as_deref())?;
+            let spec = TaskSpec::Dca {
+                amount,
+                commodity: commodity.clone(),
+                into: into.clone(),
+                provider: provider.clone(),
+                from,
+                to,
+                monthly_cap,
+                project: cfg.current_project.clone(),
+            };
+            create_task(db, &task_id, schedule, anchor_at, &spec)?;
+            let cap = monthly_cap
+                .map(|c| format!(", at most {} {commodity} a month", c.normalize()))
+                .unwrap_or_default();
+            println!(
+                "Created task '{}': {} {} {commodity} into {into} at @{provider}{cap}, first run {}.",
+                task_id,
+                schedule.as_str(),
+                amount.normalize(),
+                anchor_at.to_rfc3339()
+            );
+            Ok(())
+        }
+        TaskCreateArgs {
+            task_id,
+            account,
+            rate,
+            counter_account,
+            monthly,
+            start,
+            ..
+        } => {
+            let Some(rate) = rate else {
+                return Err(anyhow!(
+                    "task create needs a task kind. Supported: recurring transactions (--from-event <event_id> --weekly|--monthly), interest accrual (--account <account> --rate <pct> --monthly), report files (--report <args> --out <dir>), dollar-cost averaging (--dca <amount> --commodity <c> --into <c> --provider @p --from <account> --to <account> --weekly|--monthly)"
+                ));
+            };
+            let account = account.ok_or_else(|| anyhow!("Interest tasks require --account"))?;
+            let rate = crate::parse_percent(&rate, "rate")?;
+            if !monthly {
+                return Err(anyhow!("Interest tasks require a schedule: --monthly"));
+            }
+
+            let anchor_at = match start {
+                Some(raw) => crate::parse_rfc3339_or_now(Some(&raw))?,
+                None => crate::period::month_range(&crate::current_month_yyyy_mm(now_utc()))?.1,
+            };
+            let spec = TaskSpec::Interest {
+                account: account.clone(),
+                rate,
+                counter_account,
+                project: cfg.current_project.clone(),
+            };
+            create_task(db, &task_id, Schedule::Monthly, anchor_at, &spec)?;
+            println!(
+                "Created task '{}': monthly interest on {} at {}% per year, first run {}.",
+                task_id,
+                account,
+                (rate * Decimal::from(100u32)).normalize(),
+                anchor_at.to_rfc3339()
+            );
+            Ok(())
+        }
+    }
+}
+
+pub fn handle_task(db: &Db, cfg: &AppConfig, cmd: TaskCmd) -> Result<()> {
+    match cmd {
+        TaskCmd::Create(args) => handle_create(db, cfg, *args),
+        TaskCmd::Update { .. } => {
+            eprintln!("This command is a stub for later milestones.");
+            Ok(())
+        }
+        TaskCmd::Enable { task_id } => {
+            if db.set_task_enabled(&task_id, true)? == 0 {
+                return Err(anyhow!("No such task: '{task_id}'"));
+            }
+            println!("Enabled task '{task_id}'.");
+            Ok(())
+        }
+        TaskCmd::Disable { task_id } => {
+            if db.set_task_enabled(&task_id, false)? == 0 {
+                return Err(anyhow!("No such task: '{task_id}'"));
+            }
+            println!("Disabled task '{task_id}'.");
+            Ok(())
+        }
+        TaskCmd::Run { task_id, as_of } => {
+            let Some(task) = db.get_task(&task_id)? else {
+                return Err(anyhow!("No such task: '{task_id}'"));
+            };
+            if !task.enabled {
+                return Err(anyhow!(
+                    "Task '{task_id}' is disabled. Enable it with: bankero task enable {task_id}"
+                ));
+            }
+            let as_of = crate::parse_rfc3339_or_now(as_of.as_deref())?;
+            let _activity = db.activity(&format!("task '{task_id}'"))?;
+            let stats = run_task(db, cfg, &task, as_of)?;
+
+            let task = db.get_task(&task_id)?.unwrap_or(task);
+            let next = next_occurrence(&task)?
+                .map(|t| t.to_rfc3339())
+                .unwrap_or_else(|| "(done)".to_string());
+            let mut funds = if stats.piggy_funds > 0 {
+                format!(", {} piggy fund(s)", stats.piggy_funds)
+            } else {
+                String::new()
+            };
+            if stats.files > 0 {
+                funds.push_str(&format!(", {} report file(s)", stats.files));
+            }
+            if stats.fulfilled > 0 {
+                funds.push_str(&format!(
+                    ", skipped {} occurrence(s) already recorded by sync",
+                    stats.fulfilled
+                ));
+            }
+            println!(
+                "Ran task '{task_id}': wrote {} event(s){funds}. Next run: {next}.",
+                stats.events
+            );
+            Ok(())
+        }
+        TaskCmd::List => {
+            let tasks = db.list_tasks()?;
+            if tasks.is_empty() {
+                println!("(no tasks)");
+                return Ok(());
+            }
+
+            println!("task_id\tkind\tschedule\tenabled\truns\tnext_run_at");
+            for t in tasks {
+                let next = next_occurrence(&t)?
+                    .map(|at| at.to_rfc3339())
+                    .unwrap_or_else(|| "(done)".to_string());
+                println!(
+                    "{}\t{}\t{}\t{}\t{}\t{}",
+                    t.task_id, t.kind, t.schedule, t.enabled, t.runs, next
+                );
+            }
+            Ok(())
+        }
+    }
+}
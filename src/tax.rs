@@ -0,0 +1,188 @@
+//! Annual tax report (`bankero report tax`).
+//!
+//! A TOML map assigns ledger categories to tax buckets (deductible, VAT-reclaimable,
+//! taxable income, ...). The report replays one calendar year and totals each bucket in
+//! the reference commodity, converting other commodities with `--convert` at event time.
+
+use crate::cli::ReportTaxArgs;
+use crate::config::AppConfig;
+use crate::db::Db;
+use crate::domain::StoredEvent;
+use anyhow::{Context, Result, anyhow};
+use chrono::{TimeZone, Utc};
+use rust_decimal::Decimal;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TaxMap {
+    buckets: BTreeMap<String, Bucket>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Bucket {
+    label: Option<String>,
+    categories: Vec<String>,
+}
+
+fn load_map(path: &std::path::Path) -> Result<TaxMap> {
+    let raw = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read tax map {}", path.display()))?;
+    let map: TaxMap =
+        toml::from_str(&raw).with_context(|| format!("Invalid tax map {}", path.display()))?;
+    if map.buckets.is_empty() {
+        return Err(anyhow!("Tax map {} defines no [buckets.*]", path.display()));
+    }
+
+    let mut owner: BTreeMap<&str, &str> = BTreeMap::new();
+    for (name, bucket) in &map.buckets {
+        for category in &bucket.categories {
+            if let Some(other) = owner.insert(category.as_str(), name.as_str()) {
+                return Err(anyhow!(
+                    "Category {category} is mapped to both {other} and {name}; each category belongs to one bucket"
+                ));
+            }
+        }
+    }
+    Ok(map)
+}
+
+fn under(account: &str, category: &str) -> bool {
+    account == category || account.starts_with(&format!("{category}:"))
+}
+
+/// What `e` adds to `bucket`, per commodity. Income counts as positive.
+fn bucket_amounts(e: &StoredEvent, bucket: &Bucket) -> Vec<(String, Decimal)> {
+    let signed = |account: &str, amount: Decimal| {
+        if under(account, "income") {
+            -amount
+        } else {
+            amount
+        }
+    };
+    let matched: Vec<(String, Decimal)> = e
+        .payload
+        .postings
+        .iter()
+        .filter(|p| bucket.categories.iter().any(|c| under(&p.account, c)))
+        .map(|p| {
+            (
+                p.commodity.to_ascii_uppercase(),
+                signed(&p.account, p.amount),
+            )
+        })
+        .collect();
+    if !matched.is_empty() {
+        return matched;
+    }
+
+    let category = e.payload.category.as_deref().unwrap_or_default();
+    match bucket.categories.iter().find(|c| under(category, c)) {
+        Some(c) => crate::primary_outgoing_amount(&e.payload.postings)
+            .map(|(amount, commodity)| (commodity.to_ascii_uppercase(), signed(c, amount)))
+            .into_iter()
+            .collect(),
+        None => vec![],
+    }
+}
+
+pub fn handle_tax_report(db: &Db, cfg: &AppConfig, args: ReportTaxArgs) -> Result<()> {
+    let map = load_map(&args.map)?;
+    let reference = cfg.reference_commodity.to_ascii_uppercase();
+    let provider = args.convert.as_deref().map(crate::normalize_provider);
+    let start = Utc
+        .with_ymd_and_hms(args.year, 1, 1, 0, 0, 0)
+        .single()
+        .ok_or_else(|| anyhow!("Invalid --year {}", args.year))?;
+    let end = Utc
+        .with_ymd_and_hms(args.year + 1, 1, 1, 0, 0, 0)
+        .single()
+        .ok_or_else(|| anyhow!("Invalid --year {}", args.year))?;
+
+    let events: Vec<StoredEvent> = crate::snapshot::replay_events(db, false)?
+        .into_iter()
+        .filter(|e| e.effective_at >= start && e.effective_at < end)
+        .collect();
+
+    let mut totals: BTreeMap<&str, Decimal> = BTreeMap::new();
+    let mut unconverted: BTreeMap<String, usize> = BTreeMap::new();
+    for (name, bucket) in &map.buckets {
+        let total = totals.entry(name.as_str()).or_insert(Decimal::ZERO);
+        for e in &events {
+            for (commodity, amount) in bucket_amounts(e, bucket) {
+                if commodity == reference {
+                    *total += amount;
+                    continue;
+                }
+                let converted = provider.as_deref().and_then(|p| {
+                    crate::resolve_and_convert_with(
+                        db,
+                        p,
+                        &commodity,
+                        &reference,
+                        e.effective_at,
+                        amount,
+                        args.interpolate,
+                    )
+                    .ok()
+                });
+                match converted {
+                    Some((value, ..)) => *total += value,
+                    None => *unconverted.entry(commodity).or_insert(0) += 1,
+                }
+            }
+        }
+        *total = crate::commodities::round_for_display(db, &reference, *total)?.normalize();
+    }
+
+    if !unconverted.is_empty() {
+        let list: Vec<String> = unconverted
+            .iter()
+            .map(|(c, n)| format!("{c} ({n} posting(s))"))
+            .collect();
+        eprintln!(
+            "Note: no rate to {reference} for {}; excluded from the totals.{}",
+            list.join(", "),
+            if provider.is_none() {
+                " Pass --convert @provider to include them."
+            } else {
+                ""
+            }
+        );
+    }
+
+    if args.output.is_table() {
+        let rows: Vec<Vec<String>> = map
+            .buckets
+            .iter()
+            .map(|(name, bucket)| {
+                vec![
+                    args.year.to_string(),
+                    name.clone(),
+                    bucket.label.clone().unwrap_or_default(),
+                    reference.clone(),
+                    totals[name.as_str()].to_string(),
+                ]
+            })
+            .collect();
+        return crate::output::write_table(
+            &args.output,
+            &["year", "bucket", "label", "commodity", "total"],
+            &rows,
+        );
+    }
+    args.output.require_table_for_out()?;
+
+    println!("Tax report {} ({reference})", args.year);
+    for (name, bucket) in &map.buckets {
+        println!(
+            "{}\t{}\t{}",
+            name,
+            bucket.label.as_deref().unwrap_or(name),
+            totals[name.as_str()]
+        );
+    }
+    Ok(())
+}
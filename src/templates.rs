@@ -0,0 +1,199 @@
+//! Saved command lines (`bankero template`).
+//!
+//! A template stores the arguments of an event command, per workspace. Using it
+//! (`bankero template use rent` or just `bankero rent`) rebuilds the command line, with
+//! `--amount` swapped in and any extra arguments appended, and parses it again as if it
+//! had been typed, so a template behaves exactly like the command it saves.
+
+use crate::cli::{Cli, Command, TemplateCmd};
+use crate::config::now_utc;
+use crate::db::{Db, StoredTemplate};
+use anyhow::{Result, anyhow};
+use clap::error::{ContextKind, ContextValue, ErrorKind};
+use clap::{CommandFactory, Parser};
+use rust_decimal::Decimal;
+use std::ffi::OsString;
+use std::path::PathBuf;
+
+/// Whether `command` is a template use (`template use rent` or just `rent`).
+pub fn is_use(command: &Command) -> bool {
+    match command {
+        Command::Template(args) => matches!(args.cmd, TemplateCmd::Use { .. }),
+        Command::External(_) => true,
+        _ => false,
+    }
+}
+
+/// The full command line a template use stands for, if `cli` is one. Templates are
+/// looked up in `db`, the current workspace's journal.
+pub fn expand(cli: &Cli, db: &Db) -> Result<Option<Vec<OsString>>> {
+    let (name, amount, extra) = match &cli.command {
+        Command::Template(args) => match &args.cmd {
+            TemplateCmd::Use {
+                name,
+                amount,
+                extra,
+            } => (name.clone(), amount.clone(), extra.clone()),
+            _ => return Ok(None),
+        },
+        Command::External(argv) => {
+            let (name, rest) = argv.split_first().expect("clap passes the subcommand name");
+            (name.clone(), None, rest.to_vec())
+        }
+        _ => return Ok(None),
+    };
+    let (flag_amount, extra) = take_amount(extra)?;
+    let amount = amount.or(flag_amount);
+
+    let Some(template) = db.get_template(&name)? else {
+        return Err(unknown_command(db, &name)?);
+    };
+
+    let mut argv = template.argv;
+    if let Some(amount) = amount {
+        let i = amount_index(&argv)
+            .ok_or_else(|| anyhow!("Template '{name}' has no amount for --amount to replace"))?;
+        argv[i] = amount;
+    }
+    argv.extend(extra);
+
+    let mut full = global_args(&cli.home, &cli.profile);
+    full.extend(argv.into_iter().map(OsString::from));
+    crate::exchange::strip_arrow(full).map(Some)
+}
+
+/// A name that is neither a command nor a template. Close misspellings of a command
+/// get clap's own "similar subcommand" error, as without templates; otherwise the
+/// error names the closest templates, if any.
+fn unknown_command(db: &Db, name: &str) -> Result<anyhow::Error> {
+    let close = |candidate: &str| crate::commodities::distance(candidate, name) <= 2;
+    let cmd = Cli::command();
+    let commands: Vec<String> = cmd
+        .get_subcommands()
+        .filter(|c| !c.is_hide_set())
+        .map(|c| c.get_name().to_string())
+        .filter(|c| close(c))
+        .collect();
+    if !commands.is_empty() {
+        let mut err = clap::Error::new(ErrorKind::InvalidSubcommand).with_cmd(&cmd);
+        err.insert(
+            ContextKind::InvalidSubcommand,
+            ContextValue::String(name.to_string()),
+        );
+        err.insert(
+            ContextKind::SuggestedSubcommand,
+            ContextValue::Strings(commands),
+        );
+        err.insert(
+            ContextKind::Usage,
+            ContextValue::StyledStr(cmd.clone().render_usage()),
+        );
+        return Ok(anyhow!("{}", err.render().to_string().trim_end()));
+    }
+    let templates: Vec<String> = db
+        .list_templates()?
+        .into_iter()
+        .map(|t| t.name)
+        .filter(|t| close(t))
+        .collect();
+    let hint = match templates.as_slice() {
+        [] => String::new(),
+        names => format!(" Did you mean {}?", names.join(" or ")),
+    };
+    Ok(anyhow!(
+        "Unknown command or template '{name}'.{hint} See `bankero --help` and `bankero template list`"
+    ))
+}
+
+/// The program name plus the `--home`/`--profile` a re-parsed command line must keep.
+pub fn global_args(home: &Option<PathBuf>, profile: &Option<String>) -> Vec<OsString> {
+    let mut args: Vec<OsString> = vec!["bankero".into()];
+    if let Some(home) = home {
+        args.extend(["--home".into(), home.clone().into_os_string()]);
+    }
+    if let Some(profile) = profile {
+        args.extend(["--profile".into(), profile.into()]);
+    }
+    args
+}
+
+/// Splits `--amount X` (or `--amount=X`) out of the extra arguments.
+fn take_amount(extra: Vec<String>) -> Result<(Option<String>, Vec<String>)> {
+    let mut amount = None;
+    let mut rest = Vec::new();
+    let mut args = extra.into_iter();
+    while let Some(arg) = args.next() {
+        if arg == "--amount" {
+            amount = Some(
+                args.next()
+                    .ok_or_else(|| anyhow!("--amount needs a value"))?,
+            );
+        } else if let Some(value) = arg.strip_prefix("--amount=") {
+            amount = Some(value.to_string());
+        } else {
+            rest.push(arg);
+        }
+    }
+    Ok((amount, rest))
+}
+
+/// The first numeric argument after the command name that is not a flag's value.
+fn amount_index(argv: &[String]) -> Option<usize> {
+    (1..argv.len()).find(|&i| !argv[i - 1].starts_with('-') && argv[i].parse::<Decimal>().is_ok())
+}
+
+pub fn handle_template(db: &Db, cmd: TemplateCmd) -> Result<()> {
+    match cmd {
+        TemplateCmd::Add { name, command } => {
+            let name = name.trim().to_ascii_lowercase();
+            if name.is_empty() || name.starts_with('-') || name.contains(char::is_whitespace) {
+                return Err(anyhow!(
+                    "Template names are single words, e.g. rent or groceries"
+                ));
+            }
+            if Cli::command().find_subcommand(&name).is_some() {
+                return Err(anyhow!(
+                    "'{name}' is a built-in command; pick another template name"
+                ));
+            }
+            let full = std::iter::once("bankero".to_string()).chain(command.iter().cloned());
+            let parsed = Cli::try_parse_from(crate::exchange::strip_arrow(
+                full.map(OsString::from).collect(),
+            )?)
+            .map_err(|e| anyhow!("Template '{name}' is not a valid command:\n{e}"))?;
+            if matches!(parsed.command, Command::Template(_) | Command::External(_)) {
+                return Err(anyhow!("A template cannot run another template"));
+            }
+
+            db.upsert_template(&StoredTemplate {
+                name: name.clone(),
+                argv: command.clone(),
+                created_at: now_utc(),
+            })?;
+            println!("Saved template '{name}': {}", command.join(" "));
+            println!("Run it with: bankero {name}");
+            Ok(())
+        }
+        TemplateCmd::List => {
+            let templates = db.list_templates()?;
+            if templates.is_empty() {
+                println!("(no templates)");
+                return Ok(());
+            }
+            for t in templates {
+                println!("{}\t{}", t.name, t.argv.join(" "));
+            }
+            Ok(())
+        }
+        TemplateCmd::Remove { name } => {
+            if db.delete_template(&name.to_ascii_lowercase())? == 0 {
+                return Err(anyhow!("No template '{name}'"));
+            }
+            println!("Removed template '{name}'.");
+            Ok(())
+        }
+        TemplateCmd::Use { name, .. } => Err(anyhow!(
+            "Template '{name}' was not expanded before running; this is a bug"
+        )),
+    }
+}
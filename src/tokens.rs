@@ -0,0 +1,151 @@
+//! Access tokens for remote surfaces (`bankero token`).
+//!
+//! A token belongs to one workspace and carries a scope: `read-only` can look but not
+//! write, `write-events` can also record events. Surfaces such as `bankero bot
+//! --api-token` check the scope before acting, so a wall dashboard token cannot post
+//! events. No surface changes the workspace itself, so no token can either.
+//!
+//! Only a SHA-256 hash of each token is kept in config.json; the token is printed once
+//! when it is created.
+
+use crate::cli::TokenCmd;
+use crate::config::{ApiToken, AppConfig, now_utc, write_config};
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::Path;
+use uuid::Uuid;
+
+const TOKEN_PREFIX: &str = "bkt_";
+
+/// What a token may do. Each scope includes the ones before it.
+#[derive(
+    Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, clap::ValueEnum, Serialize, Deserialize,
+)]
+#[serde(rename_all = "kebab-case")]
+pub enum TokenScope {
+    /// Balances and reports only.
+    ReadOnly,
+    /// Also record new events. Tokens created as `admin` before that scope was dropped
+    /// load as this, which is all they could ever do.
+    #[serde(alias = "admin")]
+    WriteEvents,
+}
+
+impl TokenScope {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TokenScope::ReadOnly => "read-only",
+            TokenScope::WriteEvents => "write-events",
+        }
+    }
+}
+
+fn hash(raw: &str) -> String {
+    Sha256::digest(raw.trim().as_bytes())
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect()
+}
+
+/// The token `raw` names, if it is valid for the current workspace.
+pub fn authenticate<'a>(cfg: &'a AppConfig, raw: &str) -> Result<&'a ApiToken> {
+    let digest = hash(raw);
+    let token = cfg
+        .api_tokens
+        .iter()
+        .find(|t| t.hash == digest)
+        .ok_or_else(|| anyhow!("Unknown or revoked API token"))?;
+    if token.workspace != cfg.current_workspace {
+        return Err(anyhow!(
+            "API token '{}' belongs to workspace '{}', not '{}'",
+            token.name,
+            token.workspace,
+            cfg.current_workspace
+        ));
+    }
+    Ok(token)
+}
+
+/// Fails unless `token` (if any) allows `needed`. No token means the local owner.
+pub fn require(token: Option<&ApiToken>, needed: TokenScope, what: &str) -> Result<()> {
+    match token {
+        Some(t) if t.scope < needed => Err(anyhow!(
+            "Token '{}' is {}; {what} needs {}",
+            t.name,
+            t.scope.as_str(),
+            needed.as_str()
+        )),
+        _ => Ok(()),
+    }
+}
+
+pub fn handle_token(cmd: TokenCmd, cfg: &mut AppConfig, cfg_path: &Path) -> Result<()> {
+    match cmd {
+        TokenCmd::Create { name, scope } => {
+            let name = name.trim().to_string();
+            if name.is_empty() {
+                return Err(anyhow!("Token name must not be empty"));
+            }
+            if cfg
+                .api_tokens
+                .iter()
+                .any(|t| t.name == name && t.workspace == cfg.current_workspace)
+            {
+                return Err(anyhow!(
+                    "Token '{name}' already exists in workspace '{}'; revoke it first",
+                    cfg.current_workspace
+                ));
+            }
+            let raw = format!(
+                "{TOKEN_PREFIX}{}{}",
+                Uuid::new_v4().simple(),
+                Uuid::new_v4().simple()
+            );
+            cfg.api_tokens.push(ApiToken {
+                name: name.clone(),
+                workspace: cfg.current_workspace.clone(),
+                scope,
+                hash: hash(&raw),
+                created_at: now_utc(),
+            });
+            write_config(cfg_path, cfg)?;
+            println!(
+                "Created {} token '{name}' for workspace '{}'. It is shown only once:",
+                scope.as_str(),
+                cfg.current_workspace
+            );
+            println!("{raw}");
+            Ok(())
+        }
+        TokenCmd::List => {
+            if cfg.api_tokens.is_empty() {
+                println!("(no tokens)");
+                return Ok(());
+            }
+            println!("name\tworkspace\tscope\tcreated_at");
+            for t in &cfg.api_tokens {
+                println!(
+                    "{}\t{}\t{}\t{}",
+                    t.name,
+                    t.workspace,
+                    t.scope.as_str(),
+                    t.created_at.to_rfc3339()
+                );
+            }
+            Ok(())
+        }
+        TokenCmd::Revoke { name } => {
+            let before = cfg.api_tokens.len();
+            let workspace = cfg.current_workspace.clone();
+            cfg.api_tokens
+                .retain(|t| !(t.name == name && t.workspace == workspace));
+            if cfg.api_tokens.len() == before {
+                return Err(anyhow!("No token '{name}' in workspace '{workspace}'"));
+            }
+            write_config(cfg_path, cfg)?;
+            println!("Revoked token '{name}'.");
+            Ok(())
+        }
+    }
+}
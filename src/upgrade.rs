@@ -3,18 +3,33 @@ use indicatif::{ProgressBar, ProgressStyle};
 use reqwest::blocking::Client;
 use semver::Version;
 use serde::Deserialize;
-use std::io::Write;
-use std::path::Path;
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
 use std::process::{Command, Stdio};
 
-use crate::cli::UpgradeArgs;
+use crate::cli::{UpgradeArgs, UpgradeChannel, UpgradeMethod};
 
 const GITHUB_REPO: &str = "JoCarrasco/bankero";
 
+/// Target triple this binary was built for (set by build.rs).
+const TARGET: &str = env!("BANKERO_TARGET");
+
+/// Minisign public key release assets are signed with; set when building official releases.
+const RELEASE_PUBLIC_KEY: Option<&str> = option_env!("BANKERO_RELEASE_PUBKEY");
+
 #[derive(Debug, Deserialize)]
 struct LatestRelease {
     tag_name: String,
     html_url: Option<String>,
+    #[serde(default)]
+    draft: bool,
+    #[serde(default)]
+    prerelease: bool,
+    /// Release notes (Markdown).
+    #[serde(default)]
+    body: Option<String>,
 }
 
 pub fn handle_upgrade(args: UpgradeArgs) -> Result<()> {
@@ -24,13 +39,17 @@ pub fn handle_upgrade(args: UpgradeArgs) -> Result<()> {
     let latest = if args.skip_check {
         None
     } else {
-        Some(fetch_latest_release()?)
+        Some(fetch_latest_release(&args.api_url, args.channel)?)
     };
 
     if let Some(latest) = &latest {
         let latest_version = parse_tag_version(&latest.tag_name)?;
         println!("Current: v{current}");
-        println!("Latest:  {}", latest.tag_name);
+        println!(
+            "Latest:  {} ({} channel)",
+            latest.tag_name,
+            args.channel.as_str()
+        );
         if let Some(url) = latest.html_url.as_deref() {
             println!("Release: {url}");
         }
@@ -41,6 +60,9 @@ pub fn handle_upgrade(args: UpgradeArgs) -> Result<()> {
         }
 
         println!("Update available: v{current} -> {}", latest.tag_name);
+        if !args.no_changelog {
+            print_changelog(&args, &current, latest, &latest_version);
+        }
     } else {
         println!("Current: v{current}");
         println!("(Skipping remote check; running local upgrade path.)");
@@ -51,6 +73,46 @@ pub fn handle_upgrade(args: UpgradeArgs) -> Result<()> {
         return Ok(());
     }
 
+    let method = match args.method {
+        UpgradeMethod::Auto if args.channel == UpgradeChannel::Beta => UpgradeMethod::Binary,
+        UpgradeMethod::Auto if brew_manages(&args.brew_formula) => UpgradeMethod::Brew,
+        UpgradeMethod::Auto if cfg!(windows) && winget_manages(&args.winget_id) => {
+            UpgradeMethod::Winget
+        }
+        UpgradeMethod::Auto if apt_available() => UpgradeMethod::Apt,
+        UpgradeMethod::Auto => UpgradeMethod::Binary,
+        method @ (UpgradeMethod::Apt | UpgradeMethod::Brew | UpgradeMethod::Winget)
+            if args.channel == UpgradeChannel::Beta =>
+        {
+            return Err(anyhow!(
+                "{} only carries stable releases. Use --method binary for the beta channel.",
+                match method {
+                    UpgradeMethod::Apt => "The APT repository",
+                    UpgradeMethod::Brew => "The Homebrew formula",
+                    _ => "The winget package",
+                }
+            ));
+        }
+        method => method,
+    };
+    match method {
+        UpgradeMethod::Brew => return run_brew_upgrade(&args.brew_formula),
+        UpgradeMethod::Winget => return run_winget_upgrade(&args.winget_id, args.yes),
+        _ => {}
+    }
+    if method == UpgradeMethod::Binary {
+        let tag = match (&args.tag, &latest) {
+            (Some(tag), _) => tag.clone(),
+            (None, Some(latest)) => latest.tag_name.clone(),
+            (None, None) => {
+                return Err(anyhow!(
+                    "--skip-check with the binary method needs --tag (e.g. --tag v{current})"
+                ));
+            }
+        };
+        return replace_binary(&args, &tag);
+    }
+
     ensure_apt_available()?;
 
     if args.setup_apt {
@@ -68,7 +130,7 @@ pub fn handle_upgrade(args: UpgradeArgs) -> Result<()> {
     run_apt_upgrade(args.yes)
 }
 
-fn fetch_latest_release() -> Result<LatestRelease> {
+fn fetch_latest_release(api_url: &str, channel: UpgradeChannel) -> Result<LatestRelease> {
     let pb = ProgressBar::new_spinner();
     pb.set_style(
         ProgressStyle::with_template("{spinner} {msg}")
@@ -78,7 +140,12 @@ fn fetch_latest_release() -> Result<LatestRelease> {
     pb.enable_steady_tick(std::time::Duration::from_millis(80));
     pb.set_message("Checking GitHub for latest release...");
 
-    let url = format!("https://api.github.com/repos/{GITHUB_REPO}/releases/latest");
+    // GitHub's "latest" endpoint skips pre-releases; the beta channel scans the list instead.
+    let api_url = api_url.trim_end_matches('/');
+    let url = match channel {
+        UpgradeChannel::Stable => format!("{api_url}/repos/{GITHUB_REPO}/releases/latest"),
+        UpgradeChannel::Beta => format!("{api_url}/repos/{GITHUB_REPO}/releases?per_page=30"),
+    };
     let client = Client::builder()
         .build()
         .context("Failed to build HTTP client")?;
@@ -97,11 +164,139 @@ fn fetch_latest_release() -> Result<LatestRelease> {
         ));
     }
 
-    let parsed: LatestRelease = resp.json().context("Invalid GitHub release JSON")?;
+    let parsed = match channel {
+        UpgradeChannel::Stable => resp.json().context("Invalid GitHub release JSON")?,
+        UpgradeChannel::Beta => {
+            let releases: Vec<LatestRelease> =
+                resp.json().context("Invalid GitHub release JSON")?;
+            versioned(releases)
+                .max_by(|(a, _), (b, _)| a.cmp(b))
+                .map(|(_, r)| r)
+                .ok_or_else(|| anyhow!("No releases found"))?
+        }
+    };
     pb.finish_and_clear();
     Ok(parsed)
 }
 
+/// Published releases with a parseable version tag.
+fn versioned(releases: Vec<LatestRelease>) -> impl Iterator<Item = (Version, LatestRelease)> {
+    releases
+        .into_iter()
+        .filter(|r| !r.draft)
+        .filter_map(|r| parse_tag_version(&r.tag_name).ok().map(|v| (v, r)))
+}
+
+/// Prints the notes of every release between the running version and `latest`, newest
+/// first, falling back to the latest release's notes if the list cannot be fetched.
+fn print_changelog(
+    args: &UpgradeArgs,
+    current: &Version,
+    latest: &LatestRelease,
+    latest_version: &Version,
+) {
+    let url = format!(
+        "{}/repos/{GITHUB_REPO}/releases?per_page=30",
+        args.api_url.trim_end_matches('/')
+    );
+    let listed = download(&url)
+        .ok()
+        .and_then(|raw| serde_json::from_slice::<Vec<LatestRelease>>(&raw).ok());
+    let mut notes: Vec<(Version, String, String)> = match listed {
+        Some(releases) => versioned(releases)
+            .filter(|(v, r)| {
+                v > current
+                    && v <= latest_version
+                    && (args.channel == UpgradeChannel::Beta || !r.prerelease)
+            })
+            .map(|(v, r)| (v, r.tag_name, r.body.unwrap_or_default()))
+            .collect(),
+        None => vec![(
+            latest_version.clone(),
+            latest.tag_name.clone(),
+            latest.body.clone().unwrap_or_default(),
+        )],
+    };
+    notes.sort_by(|a, b| b.0.cmp(&a.0));
+
+    for (_, tag, body) in notes {
+        if body.trim().is_empty() {
+            continue;
+        }
+        println!();
+        println!("What's new in {tag}:");
+        println!();
+        print!("{}", render_markdown(&body));
+    }
+}
+
+/// Renders release-note Markdown as plain terminal text: headings underlined, bullets
+/// normalized, emphasis/code markers dropped, links shown as "text (url)".
+fn render_markdown(markdown: &str) -> String {
+    let mut out = String::new();
+    let mut in_code = false;
+    for line in markdown.lines() {
+        let line = line.trim_end_matches('\r');
+        if line.trim_start().starts_with("```") {
+            in_code = !in_code;
+            continue;
+        }
+        if in_code {
+            out.push_str(&format!("    {line}\n"));
+            continue;
+        }
+
+        let trimmed = line.trim_start();
+        let level = trimmed.chars().take_while(|c| *c == '#').count();
+        if (1..=6).contains(&level) && trimmed[level..].starts_with(' ') {
+            let heading = render_inline(trimmed[level..].trim());
+            let rule = if level == 1 { '=' } else { '-' };
+            out.push_str(&format!(
+                "{heading}\n{}\n",
+                rule.to_string().repeat(heading.chars().count())
+            ));
+            continue;
+        }
+
+        let indent = &line[..line.len() - trimmed.len()];
+        let item = trimmed
+            .strip_prefix("- ")
+            .or_else(|| trimmed.strip_prefix("* "))
+            .or_else(|| trimmed.strip_prefix("+ "));
+        match item {
+            Some(item) => out.push_str(&format!("{indent}  • {}\n", render_inline(item))),
+            None => out.push_str(&format!("{}\n", render_inline(line))),
+        }
+    }
+    out
+}
+
+fn render_inline(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('[') {
+        let (before, tail) = rest.split_at(start);
+        out.push_str(before);
+        let link = tail.find("](").and_then(|mid| {
+            tail[mid..]
+                .find(')')
+                .map(|end| (&tail[1..mid], &tail[mid + 2..mid + end], mid + end + 1))
+        });
+        match link {
+            Some((label, url, consumed)) => {
+                out.push_str(&format!("{label} ({url})"));
+                rest = &tail[consumed..];
+            }
+            None => {
+                out.push('[');
+                rest = &tail[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out.replace("**", "").replace("__", "").replace('`', "")
+}
+
 fn parse_tag_version(tag: &str) -> Result<Version> {
     let raw = tag.trim();
     let raw = raw.strip_prefix('v').unwrap_or(raw);
@@ -109,6 +304,33 @@ fn parse_tag_version(tag: &str) -> Result<Version> {
 }
 
 fn print_upgrade_instructions(args: &UpgradeArgs) {
+    if cfg!(target_os = "macos") {
+        println!();
+        println!("Homebrew:");
+        println!("  bankero upgrade --apply --method brew");
+        println!(
+            "  (same as: brew update && brew upgrade {})",
+            args.brew_formula
+        );
+        println!();
+        println!("Other installs ({TARGET}):");
+        println!("  bankero upgrade --apply --method binary");
+        return;
+    }
+    if cfg!(windows) {
+        println!();
+        println!("winget:");
+        println!("  bankero upgrade --apply --method winget");
+        println!(
+            "  (same as: winget upgrade --id {} --exact)",
+            args.winget_id
+        );
+        println!();
+        println!("Other installs ({TARGET}):");
+        println!("  bankero upgrade --apply --method binary");
+        return;
+    }
+
     println!();
     println!("To configure APT + upgrade:");
     println!(
@@ -127,18 +349,205 @@ fn print_upgrade_instructions(args: &UpgradeArgs) {
     );
     println!("  sudo apt-get update");
     println!("  sudo apt-get install bankero");
+    println!();
+    println!("Other platforms ({TARGET}):");
+    println!("  bankero upgrade --apply --method binary");
 }
 
-fn ensure_apt_available() -> Result<()> {
-    let has_apt = Command::new("apt-get")
+/// Downloads the release asset for this target, checks it against the release's
+/// SHA256SUMS and atomically replaces the executable.
+fn replace_binary(args: &UpgradeArgs, tag: &str) -> Result<()> {
+    let install_path = match &args.install_path {
+        Some(path) => path.clone(),
+        None => std::env::current_exe().context("Cannot locate the running executable")?,
+    };
+    let asset = format!("bankero-{TARGET}.gz");
+    let base = format!("{}/{tag}", args.release_url.trim_end_matches('/'));
+
+    println!("Downloading {asset} ({tag})...");
+    let compressed = download(&format!("{base}/{asset}"))?;
+    let sums = String::from_utf8(download(&format!("{base}/SHA256SUMS"))?)
+        .context("SHA256SUMS is not valid UTF-8")?;
+
+    let expected = sums
+        .lines()
+        .filter_map(|line| line.split_once(char::is_whitespace))
+        .find(|(_, name)| name.trim().trim_start_matches('*') == asset)
+        .map(|(hash, _)| hash.to_ascii_lowercase())
+        .ok_or_else(|| anyhow!("{asset} is not listed in the release SHA256SUMS"))?;
+    let actual: String = Sha256::digest(&compressed)
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect();
+    if actual != expected {
+        return Err(anyhow!(
+            "Checksum mismatch for {asset}: expected {expected}, got {actual}. Nothing was replaced."
+        ));
+    }
+    println!("Checksum OK ({actual}).");
+
+    // An unset repository variable still embeds as an empty string.
+    let embedded = RELEASE_PUBLIC_KEY.filter(|k| !k.trim().is_empty());
+    match args.public_key.as_deref().or(embedded) {
+        Some(key) => {
+            let sig = String::from_utf8(download(&format!("{base}/{asset}.minisig"))?)
+                .context("Signature is not valid UTF-8")?;
+            verify_signature(key, &compressed, &sig).with_context(|| {
+                format!("Signature check failed for {asset}. Nothing was replaced.")
+            })?;
+            println!("Signature OK.");
+        }
+        None => eprintln!(
+            "Warning: this build has no release signing key; only the checksum was verified (pass --public-key to check the signature)."
+        ),
+    }
+
+    let mut binary = Vec::new();
+    flate2::read::GzDecoder::new(compressed.as_slice())
+        .read_to_end(&mut binary)
+        .with_context(|| format!("Failed to decompress {asset}"))?;
+
+    swap_executable(&install_path, &binary)?;
+    println!("Replaced {} with {tag}.", install_path.display());
+    Ok(())
+}
+
+fn verify_signature(public_key: &str, data: &[u8], signature: &str) -> Result<()> {
+    let key = minisign_verify::PublicKey::from_base64(public_key.trim())
+        .map_err(|e| anyhow!("Invalid minisign public key: {e}"))?;
+    let signature = minisign_verify::Signature::decode(signature)
+        .map_err(|e| anyhow!("Invalid minisign signature: {e}"))?;
+    key.verify(data, &signature, false)
+        .map_err(|e| anyhow!("{e}"))
+}
+
+fn download(url: &str) -> Result<Vec<u8>> {
+    let client = Client::builder()
+        .build()
+        .context("Failed to build HTTP client")?;
+    let resp = client
+        .get(url)
+        .header("User-Agent", "bankero-upgrade")
+        .send()
+        .with_context(|| format!("Failed to download {url}"))?;
+    if !resp.status().is_success() {
+        return Err(anyhow!("Failed to download {url}: HTTP {}", resp.status()));
+    }
+    Ok(resp
+        .bytes()
+        .with_context(|| format!("Failed reading {url}"))?
+        .to_vec())
+}
+
+/// Writes the new binary next to `path` and renames it into place, so a failure
+/// midway never leaves a truncated executable behind.
+fn swap_executable(path: &Path, binary: &[u8]) -> Result<()> {
+    let dir = path
+        .parent()
+        .filter(|d| !d.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+    let staged: PathBuf = dir.join(format!(".bankero-upgrade-{}", std::process::id()));
+    let write = || -> Result<()> {
+        let mut file = fs::File::create(&staged)?;
+        file.write_all(binary)?;
+        file.sync_all()?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&staged, fs::Permissions::from_mode(0o755))?;
+        }
+        Ok(())
+    };
+    if let Err(err) = write() {
+        let _ = fs::remove_file(&staged);
+        return Err(err.context(format!(
+            "Failed to write {} (re-run with sudo if the directory is not writable)",
+            staged.display()
+        )));
+    }
+
+    // Windows cannot replace a running executable, but it can rename it out of the way.
+    #[cfg(windows)]
+    {
+        let old = path.with_extension("old.exe");
+        let _ = fs::remove_file(&old);
+        if path.exists() {
+            fs::rename(path, &old)
+                .with_context(|| format!("Failed to move {} aside", path.display()))?;
+        }
+    }
+
+    fs::rename(&staged, path).map_err(|err| {
+        let _ = fs::remove_file(&staged);
+        anyhow!(err).context(format!("Failed to replace {}", path.display()))
+    })
+}
+
+/// True when `brew` is installed and has `formula` installed.
+fn brew_manages(formula: &str) -> bool {
+    Command::new("brew")
+        .args(["list", "--versions", formula])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .is_ok_and(|s| s.success())
+}
+
+/// True when `winget` is installed and lists `id` as installed.
+fn winget_manages(id: &str) -> bool {
+    Command::new("winget")
+        .args(["list", "--id", id, "--exact"])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .is_ok_and(|s| s.success())
+}
+
+/// Prints and runs one package-manager step, failing on a non-zero exit.
+fn run_step(program: &str, args: &[&str]) -> Result<()> {
+    println!("Running: {program} {}", args.join(" "));
+    let status = Command::new(program)
+        .args(args)
+        .stdout(Stdio::inherit())
+        .stderr(Stdio::inherit())
+        .status()
+        .with_context(|| format!("Failed to run {program} (is it installed and on PATH?)"))?;
+    if !status.success() {
+        return Err(anyhow!("{program} {} failed: {status}", args[0]));
+    }
+    Ok(())
+}
+
+fn run_brew_upgrade(formula: &str) -> Result<()> {
+    run_step("brew", &["update"])?;
+    run_step("brew", &["upgrade", formula])
+}
+
+fn run_winget_upgrade(id: &str, assume_yes: bool) -> Result<()> {
+    let mut args = vec!["upgrade", "--id", id, "--exact"];
+    if assume_yes {
+        args.extend([
+            "--silent",
+            "--accept-source-agreements",
+            "--accept-package-agreements",
+        ]);
+    }
+    run_step("winget", &args)
+}
+
+fn apt_available() -> bool {
+    Command::new("apt-get")
         .arg("--version")
         .stdout(Stdio::null())
         .stderr(Stdio::null())
         .status()
-        .is_ok();
-    if !has_apt {
+        .is_ok()
+}
+
+fn ensure_apt_available() -> Result<()> {
+    if !apt_available() {
         return Err(anyhow!(
-            "apt-get not found. The built-in upgrader currently supports Debian/Ubuntu via APT only."
+            "apt-get not found. Use --method binary to install the release binary instead."
         ));
     }
 
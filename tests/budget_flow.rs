@@ -30,10 +30,10 @@ fn workspace_slug(name: &str) -> String {
             ' ' | ':' | '/' | '\\' => Some('-'),
             _ => None,
         };
-        if let Some(c) = mapped {
-            if !(c == '-' && out.ends_with('-')) {
-                out.push(c);
-            }
+        if let Some(c) = mapped
+            && !(c == '-' && out.ends_with('-'))
+        {
+            out.push(c);
         }
     }
     let trimmed = out.trim_matches('-');
@@ -354,3 +354,673 @@ fn auto_reserve_reserves_only_funded_amount_minus_spend() {
     assert!(out.contains("(effective balance)"));
     assert!(out.contains("assets:bank\tUSD\t800"));
 }
+
+#[test]
+fn account_budget_without_category_counts_all_outflows_from_the_account() {
+    let home = tempfile::tempdir().expect("tempdir");
+
+    run_ok(
+        &home,
+        &[
+            "budget",
+            "create",
+            "Cash",
+            "500",
+            "USD",
+            "--month",
+            "2026-02",
+            "--account",
+            "assets:cash",
+        ],
+    );
+
+    let mv = |amount: &str, from: &str, to: &str, t: &str| {
+        run_ok(
+            &home,
+            &[
+                "move",
+                amount,
+                "USD",
+                "--from",
+                from,
+                "--to",
+                to,
+                "--effective-at",
+                t,
+            ],
+        );
+    };
+    mv(
+        "1000",
+        "income:salary",
+        "assets:cash:wallet",
+        "2026-02-01T09:00:00Z",
+    );
+    run_ok(
+        &home,
+        &[
+            "buy",
+            "external:market",
+            "100",
+            "USD",
+            "--from",
+            "assets:cash:wallet",
+            "--effective-at",
+            "2026-02-03T12:00:00Z",
+        ],
+    );
+    // Leaves the account: counts even though it is not a buy.
+    mv(
+        "150",
+        "assets:cash:wallet",
+        "assets:bank",
+        "2026-02-10T12:00:00Z",
+    );
+    // Stays within assets:cash: nets to zero.
+    mv(
+        "30",
+        "assets:cash:wallet",
+        "assets:cash:drawer",
+        "2026-02-11T12:00:00Z",
+    );
+    // Outside the budget month.
+    mv(
+        "70",
+        "assets:cash:wallet",
+        "assets:bank",
+        "2026-03-02T12:00:00Z",
+    );
+
+    let out = run_ok_out(&home, &["budget", "report", "--month", "2026-02"]);
+    assert!(
+        out.contains("2026-02\tCash\tUSD\t500\t250\t250"),
+        "budget report: {out}"
+    );
+}
+
+#[test]
+fn budget_provider_converts_actuals_and_reservations_across_commodities() {
+    let home = tempfile::tempdir().expect("tempdir");
+
+    run_ok(
+        &home,
+        &[
+            "rate",
+            "set",
+            "@bcv",
+            "USD",
+            "VES",
+            "40",
+            "--as-of",
+            "2026-02-01T00:00:00Z",
+        ],
+    );
+    run_ok(
+        &home,
+        &[
+            "budget",
+            "create",
+            "Bolivares",
+            "100",
+            "USD",
+            "--month",
+            "2026-02",
+            "--account",
+            "assets:ves",
+            "@bcv",
+        ],
+    );
+    run_ok(
+        &home,
+        &[
+            "deposit",
+            "8000",
+            "VES",
+            "--from",
+            "income:salary",
+            "--to",
+            "assets:ves",
+            "--effective-at",
+            "2026-02-02T09:00:00Z",
+        ],
+    );
+    run_ok(
+        &home,
+        &[
+            "buy",
+            "external:market",
+            "1200",
+            "VES",
+            "--from",
+            "assets:ves",
+            "--effective-at",
+            "2026-02-05T12:00:00Z",
+        ],
+    );
+
+    // 1200 VES at 40 VES/USD = 30 USD spent.
+    let out = run_ok_out(&home, &["budget", "report", "--month", "2026-02"]);
+    assert!(
+        out.contains("2026-02\tBolivares\tUSD\t100\t30\t70"),
+        "budget report: {out}"
+    );
+
+    // The remaining 70 USD is reserved as 2800 VES on the VES account.
+    let bal = run_ok_out(&home, &["balance", "assets:ves", "--month", "2026-02"]);
+    assert!(bal.contains("(reserved budgets)"), "balance: {bal}");
+    assert!(bal.contains("assets:ves\tVES\t-2800"), "balance: {bal}");
+    assert!(bal.contains("(effective balance)"), "balance: {bal}");
+    assert!(bal.contains("assets:ves\tVES\t4000"), "balance: {bal}");
+    assert!(!bal.contains("assets:ves\tUSD"), "balance: {bal}");
+
+    // A later rate doesn't move February's reservation: it converts at the month end.
+    run_ok(
+        &home,
+        &[
+            "rate",
+            "set",
+            "@bcv",
+            "USD",
+            "VES",
+            "50",
+            "--as-of",
+            "2026-03-10T00:00:00Z",
+        ],
+    );
+    let bal = run_ok_out(&home, &["balance", "assets:ves", "--month", "2026-02"]);
+    assert!(bal.contains("assets:ves\tVES\t-2800"), "balance: {bal}");
+
+    // A budget whose spend can't be converted is reported as unknown; the rest still shows.
+    run_ok(
+        &home,
+        &[
+            "budget",
+            "create",
+            "Euros",
+            "100",
+            "EUR",
+            "--month",
+            "2026-02",
+            "--account",
+            "assets:ves",
+            "@bcv",
+        ],
+    );
+    let out = bankero_cmd()
+        .env("BANKERO_HOME", home.path())
+        .args(["budget", "report", "--month", "2026-02"])
+        .assert()
+        .success()
+        .get_output()
+        .clone();
+    let stdout = String::from_utf8(out.stdout).expect("utf8 stdout");
+    let stderr = String::from_utf8(out.stderr).expect("utf8 stderr");
+    assert!(
+        stdout.contains("2026-02\tBolivares\tUSD\t100\t30\t70")
+            && stdout.contains("2026-02\tEuros\tEUR\t100\t?\t?"),
+        "budget report: {stdout}"
+    );
+    assert!(stderr.contains("Budget 'Euros'"), "stderr: {stderr}");
+    let bal = run_ok_out(&home, &["balance", "assets:ves", "--month", "2026-02"]);
+    assert!(bal.contains("assets:ves\tVES\t-2800"), "balance: {bal}");
+}
+
+#[test]
+fn zero_based_plan_allocates_income_and_reports_the_gap() {
+    let home = tempfile::tempdir().expect("tempdir");
+    run_ok(
+        &home,
+        &[
+            "budget",
+            "create",
+            "Food",
+            "300",
+            "USD",
+            "--month",
+            "2026-03",
+            "--category",
+            "expenses:food",
+        ],
+    );
+    run_ok(
+        &home,
+        &[
+            "piggy",
+            "create",
+            "Trip",
+            "1000",
+            "USD",
+            "--from",
+            "assets:bank",
+        ],
+    );
+    run_ok(
+        &home,
+        &[
+            "deposit",
+            "2500",
+            "USD",
+            "--from",
+            "income:salary",
+            "--to",
+            "assets:bank",
+            "--effective-at",
+            "2026-03-01T09:00:00Z",
+        ],
+    );
+    for (amount, category) in [("120", "expenses:food"), ("30", "expenses:fun")] {
+        run_ok(
+            &home,
+            &[
+                "buy",
+                "external:shop",
+                amount,
+                "USD",
+                "--from",
+                "assets:bank",
+                "--category",
+                category,
+                "--effective-at",
+                "2026-03-10T12:00:00Z",
+            ],
+        );
+    }
+
+    // Income, Food (keep 300), Trip, one extra category, finish, save.
+    let mut cmd = assert_cmd::Command::new(assert_cmd::cargo::cargo_bin!("bankero"));
+    cmd.env("BANKERO_HOME", home.path());
+    cmd.args(["budget", "plan", "2026-03"]);
+    cmd.write_stdin("3000 USD\n\n200\nexpenses:fun\n100\n\ny\n");
+    let out = cmd.assert().success().get_output().stdout.clone();
+    let out = String::from_utf8(out).expect("utf8 stdout");
+    assert!(
+        out.contains("Plan March 2026: allocated 600 of 3000 USD."),
+        "plan: {out}"
+    );
+    assert!(out.contains("Unallocated: 2400 USD"), "plan: {out}");
+    assert!(out.contains("Saved plan for March 2026."), "plan: {out}");
+
+    let out = run_ok_out(&home, &["budget", "plan", "2026-03", "--status"]);
+    assert!(out.contains("budget\tFood\t300\t120\t180"), "status: {out}");
+    assert!(out.contains("piggy\tTrip\t200\t0\t200"), "status: {out}");
+    assert!(
+        out.contains("category\texpenses:fun\t100\t30\t70"),
+        "status: {out}"
+    );
+    assert!(out.contains("income\t-\t3000\t2500\t500"), "status: {out}");
+
+    // Re-planning with less income keeps the allocations and reports the overrun.
+    let mut cmd = assert_cmd::Command::new(assert_cmd::cargo::cargo_bin!("bankero"));
+    cmd.env("BANKERO_HOME", home.path());
+    cmd.args(["budget", "plan", "2026-03", "--income", "500", "USD"]);
+    cmd.write_stdin("\n\n\n\ny\n");
+    cmd.assert().success();
+
+    let out = run_ok_out(&home, &["budget", "report", "--month", "2026-03"]);
+    assert!(
+        out.contains("2026-03\tFood\tUSD\t300\t120\t180"),
+        "report: {out}"
+    );
+    assert!(
+        out.contains("plan\tOverallocated: 100 USD"),
+        "report: {out}"
+    );
+}
+
+#[test]
+fn budget_report_rollup_counts_child_categories_toward_parent_budgets() {
+    let home = tempfile::tempdir().expect("tempdir");
+
+    run_ok(
+        &home,
+        &[
+            "budget",
+            "create",
+            "Food",
+            "300",
+            "USD",
+            "--month",
+            "2026-02",
+            "--category",
+            "expenses:food",
+        ],
+    );
+    for (payee, amount, category) in [
+        ("external:market", "50", "expenses:food"),
+        ("external:bistro", "30", "expenses:food:restaurants"),
+        ("external:foodtruck", "5", "expenses:foodtrucks"),
+    ] {
+        run_ok(
+            &home,
+            &[
+                "buy",
+                payee,
+                amount,
+                "USD",
+                "--from",
+                "assets:bank",
+                "--category",
+                category,
+                "--effective-at",
+                "2026-02-25T12:00:00Z",
+            ],
+        );
+    }
+
+    let exact = run_ok_out(&home, &["budget", "report", "--month", "2026-02"]);
+    assert!(
+        exact.contains("2026-02\tFood\tUSD\t300\t50\t250"),
+        "{exact}"
+    );
+
+    let rolled = run_ok_out(
+        &home,
+        &["budget", "report", "--month", "2026-02", "--rollup"],
+    );
+    assert!(
+        rolled.contains("2026-02\tFood\tUSD\t300\t80\t220"),
+        "{rolled}"
+    );
+}
+
+#[test]
+fn month_flags_accept_names_and_slashes_and_summaries_follow_the_locale() {
+    let home = tempfile::tempdir().expect("tempdir");
+    run_ok(
+        &home,
+        &[
+            "budget",
+            "create",
+            "Food",
+            "300",
+            "USD",
+            "--month",
+            "feb-2026",
+            "--category",
+            "expenses:food",
+        ],
+    );
+    run_ok(
+        &home,
+        &[
+            "buy",
+            "external:shop",
+            "50",
+            "USD",
+            "--from",
+            "assets:bank",
+            "--category",
+            "expenses:food",
+            "--effective-at",
+            "2026-02-10T12:00:00Z",
+        ],
+    );
+
+    // Every spelling lands on the month the budget was stored under.
+    for month in [
+        "2026-02",
+        "02/2026",
+        "2/2026",
+        "Feb 2026",
+        "febrero-2026",
+        "2026-feb",
+    ] {
+        let out = run_ok_out(&home, &["budget", "report", "--month", month]);
+        assert!(
+            out.contains("2026-02\tFood\tUSD\t300\t50\t250"),
+            "{month}: {out}"
+        );
+    }
+    let out = run_ok_out(&home, &["report", "--month", "ene-2026"]);
+    assert!(!out.contains("external:shop"), "{out}");
+
+    let mut cmd = bankero_cmd();
+    cmd.env("BANKERO_HOME", home.path());
+    cmd.args(["report", "--month", "13/2026"]);
+    let out = cmd.assert().failure().get_output().stderr.clone();
+    let err = String::from_utf8(out).expect("utf8 stderr");
+    assert!(err.contains("Invalid month '13/2026'"), "{err}");
+
+    run_ok(&home, &["config", "set", "locale", "es"]);
+    let mut cmd = assert_cmd::Command::new(assert_cmd::cargo::cargo_bin!("bankero"));
+    cmd.env("BANKERO_HOME", home.path());
+    cmd.args(["budget", "plan", "feb-2026"]);
+    cmd.write_stdin("1000 USD\n\n\ny\n");
+    let out = cmd.assert().success().get_output().stdout.clone();
+    let out = String::from_utf8(out).expect("utf8 stdout");
+    assert!(out.contains("Saved plan for febrero 2026."), "plan: {out}");
+    let status = run_ok_out(&home, &["budget", "plan", "02/2026", "--status"]);
+    assert!(status.contains("Plan febrero 2026 (USD)"), "{status}");
+}
+
+#[test]
+fn project_budget_counts_only_events_recorded_under_the_project() {
+    let home = tempfile::tempdir().expect("tempdir");
+    run_ok(
+        &home,
+        &[
+            "budget",
+            "create",
+            "Roof materials",
+            "2000",
+            "USD",
+            "--project",
+            "Fix roof",
+        ],
+    );
+    run_ok(
+        &home,
+        &[
+            "budget",
+            "create",
+            "Food",
+            "300",
+            "USD",
+            "--category",
+            "expenses:food",
+        ],
+    );
+
+    let buy = |payee: &str, amount: &str, category: &str| {
+        run_ok(
+            &home,
+            &[
+                "buy",
+                payee,
+                amount,
+                "USD",
+                "--from",
+                "assets:bank",
+                "--category",
+                category,
+                "--effective-at",
+                "2026-03-10T12:00:00Z",
+            ],
+        );
+    };
+    buy("store:market", "50", "expenses:food");
+    run_ok(&home, &["project", "checkout", "Fix roof"]);
+    buy("store:hardware", "700", "expenses:home");
+    buy("store:market", "20", "expenses:food");
+
+    let out = run_ok_out(&home, &["budget", "report", "--month", "2026-03"]);
+    assert!(
+        out.contains("2026-03\tFood\tUSD\t300\t70\t230"),
+        "report: {out}"
+    );
+    assert!(
+        out.contains("2026-03\tRoof materials\tUSD\t2000\t720\t1280"),
+        "report: {out}"
+    );
+}
+
+#[test]
+fn unbudgeted_lists_spending_no_budget_covers_largest_first() {
+    let home = tempfile::tempdir().expect("tempdir");
+    run_ok(
+        &home,
+        &[
+            "budget",
+            "create",
+            "Food",
+            "300",
+            "USD",
+            "--month",
+            "2026-03",
+            "--category",
+            "expenses:food",
+        ],
+    );
+
+    let buy = |payee: &str, amount: &str, category: Option<&str>| {
+        let mut args = vec![
+            "buy",
+            payee,
+            amount,
+            "USD",
+            "--from",
+            "assets:bank",
+            "--effective-at",
+            "2026-03-10T12:00:00Z",
+        ];
+        if let Some(category) = category {
+            args.extend(["--category", category]);
+        }
+        run_ok(&home, &args);
+    };
+    buy("store:market", "50", Some("expenses:food"));
+    buy("store:market", "15", Some("expenses:food:snacks"));
+    buy("airline", "400", Some("expenses:travel"));
+    buy("cinema", "30", Some("expenses:fun"));
+    buy("kiosk", "5", None);
+
+    let out = run_ok_out(&home, &["budget", "report", "--month", "2026-03"]);
+    assert!(!out.contains("unbudgeted"), "off by default: {out}");
+
+    let out = run_ok_out(
+        &home,
+        &["budget", "report", "--month", "2026-03", "--unbudgeted"],
+    );
+    let gaps: Vec<&str> = out
+        .lines()
+        .filter(|l| l.starts_with("unbudgeted\t"))
+        .collect();
+    assert_eq!(
+        gaps,
+        [
+            "unbudgeted\texpenses:travel\tUSD\t400",
+            "unbudgeted\texpenses:fun\tUSD\t30",
+            "unbudgeted\texpenses:food:snacks\tUSD\t15",
+            "unbudgeted\t(uncategorized)\tUSD\t5",
+        ],
+        "report: {out}"
+    );
+
+    // With --rollup the food budget covers its sub-categories.
+    let out = run_ok_out(
+        &home,
+        &[
+            "budget",
+            "report",
+            "--month",
+            "2026-03",
+            "--unbudgeted",
+            "--rollup",
+        ],
+    );
+    assert!(
+        out.contains("2026-03\tFood\tUSD\t300\t65\t235"),
+        "report: {out}"
+    );
+    assert!(!out.contains("expenses:food:snacks"), "report: {out}");
+}
+
+#[test]
+fn unbudgeted_counts_what_a_mixed_currency_buy_bought_once() {
+    let home = tempfile::tempdir().expect("tempdir");
+    run_ok(
+        &home,
+        &[
+            "rate",
+            "set",
+            "@bcv",
+            "USD",
+            "VES",
+            "10",
+            "--as-of",
+            "2026-03-01T00:00:00Z",
+        ],
+    );
+    for (payee, amount, category) in [
+        ("store:gadgets", "80", "expenses:gadgets"),
+        ("store:books", "20", "expenses:books"),
+    ] {
+        run_ok(
+            &home,
+            &[
+                "buy",
+                payee,
+                amount,
+                "USD",
+                "--from",
+                "assets:bank",
+                "--category",
+                category,
+                "--effective-at",
+                "2026-03-05T12:00:00Z",
+            ],
+        );
+    }
+    run_ok(
+        &home,
+        &[
+            "buy",
+            "expenses:food",
+            "50",
+            "USD",
+            "--from",
+            "assets:cash-usd:30",
+            "--from",
+            "assets:banesco:200:VES",
+            "@bcv",
+            "--category",
+            "expenses:food",
+            "--effective-at",
+            "2026-03-10T12:00:00Z",
+        ],
+    );
+    run_ok(
+        &home,
+        &[
+            "buy",
+            "kiosk",
+            "900",
+            "VES",
+            "--from",
+            "assets:banesco",
+            "--category",
+            "expenses:snacks",
+            "--effective-at",
+            "2026-03-11T12:00:00Z",
+        ],
+    );
+
+    let out = run_ok_out(
+        &home,
+        &["budget", "report", "--month", "2026-03", "--unbudgeted"],
+    );
+    let gaps: Vec<&str> = out
+        .lines()
+        .filter(|l| l.starts_with("unbudgeted\t"))
+        .collect();
+    assert_eq!(
+        gaps,
+        [
+            "unbudgeted\texpenses:gadgets\tUSD\t80",
+            "unbudgeted\texpenses:food\tUSD\t50",
+            "unbudgeted\texpenses:books\tUSD\t20",
+            "unbudgeted\texpenses:snacks\tVES\t900",
+        ],
+        "report: {out}"
+    );
+}
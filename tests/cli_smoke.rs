@@ -232,3 +232,1550 @@ fn buy_with_splits_requires_sum_match() {
         .failure()
         .stderr(predicate::str::contains("Split amounts must sum"));
 }
+
+#[test]
+fn buy_accepts_explicit_payee_flag() {
+    let (home, _cmd) = cmd_with_home();
+
+    run_ok(
+        &home,
+        &[
+            "buy",
+            "39.99",
+            "USD",
+            "--payee",
+            "merchant:amazon",
+            "--from",
+            "assets:cash",
+        ],
+    );
+
+    let out = run_ok_out(&home, &["balance"]);
+    assert!(out.contains("merchant:amazon\tUSD\t39.99"));
+    assert!(out.contains("assets:cash\tUSD\t-39.99"));
+
+    // --payee plus a positional payee is ambiguous.
+    let mut cmd = bankero_cmd();
+    cmd.env("BANKERO_HOME", home.path());
+    cmd.args([
+        "buy",
+        "merchant:amazon",
+        "39.99",
+        "USD",
+        "--payee",
+        "merchant:ebay",
+        "--from",
+        "assets:cash",
+    ]);
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("use one or the other"));
+
+    // Swapped positionals get a hint instead of a bare parse error.
+    let mut cmd = bankero_cmd();
+    cmd.env("BANKERO_HOME", home.path());
+    cmd.args([
+        "buy",
+        "39.99",
+        "USD",
+        "merchant:amazon",
+        "--from",
+        "assets:cash",
+    ]);
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("--payee merchant:amazon"));
+}
+
+#[test]
+fn buy_splits_can_settle_in_another_commodity() {
+    let (home, _cmd) = cmd_with_home();
+
+    run_ok(
+        &home,
+        &[
+            "rate",
+            "set",
+            "@bcv",
+            "USD",
+            "VES",
+            "30",
+            "--as-of",
+            "2026-02-01T00:00:00Z",
+        ],
+    );
+
+    // 30 USD paid directly + 2100 VES (= 70 USD at 30 VES/USD).
+    run_ok(
+        &home,
+        &[
+            "buy",
+            "100",
+            "USD",
+            "--from",
+            "assets:cash",
+            "--to",
+            "expenses:import:30",
+            "--to",
+            "expenses:local:2100:VES",
+            "--effective-at",
+            "2026-02-25T12:00:00Z",
+            "@bcv",
+        ],
+    );
+
+    let out = run_ok_out(&home, &["balance"]);
+    assert!(out.contains("assets:cash\tUSD\t-100"), "balance: {out}");
+    assert!(out.contains("expenses:import\tUSD\t30"), "balance: {out}");
+    assert!(out.contains("expenses:local\tVES\t2100"), "balance: {out}");
+    assert!(out.contains("equity:conversion\tUSD\t70"), "balance: {out}");
+    assert!(
+        out.contains("equity:conversion\tVES\t-2100"),
+        "balance: {out}"
+    );
+
+    // Without a provider the foreign split cannot be valued.
+    let mut cmd = bankero_cmd();
+    cmd.env("BANKERO_HOME", home.path());
+    cmd.args([
+        "buy",
+        "100",
+        "USD",
+        "--from",
+        "assets:cash",
+        "--to",
+        "expenses:import:30",
+        "--to",
+        "expenses:local:2100:VES",
+    ]);
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("add a provider token"));
+
+    // Converted splits still have to add up.
+    let mut cmd = bankero_cmd();
+    cmd.env("BANKERO_HOME", home.path());
+    cmd.args([
+        "buy",
+        "100",
+        "USD",
+        "--from",
+        "assets:cash",
+        "--to",
+        "expenses:import:30",
+        "--to",
+        "expenses:local:1500:VES",
+        "@bcv:30",
+    ]);
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("Split amounts must sum"));
+}
+
+#[test]
+fn buy_tax_and_tip_append_postings_to_configured_accounts() {
+    let (home, _cmd) = cmd_with_home();
+
+    run_ok(
+        &home,
+        &[
+            "buy",
+            "external:restaurant",
+            "1000",
+            "VES",
+            "--from",
+            "assets:cash",
+            "--tax",
+            "16%",
+            "--tip",
+            "0.10",
+            "--tip-account",
+            "expenses:gratuity",
+        ],
+    );
+
+    let out = run_ok_out(&home, &["balance"]);
+    assert!(out.contains("assets:cash\tVES\t-1260"), "balance: {out}");
+    assert!(
+        out.contains("external:restaurant\tVES\t1000"),
+        "balance: {out}"
+    );
+    assert!(out.contains("expenses:tax\tVES\t160"), "balance: {out}");
+    assert!(
+        out.contains("expenses:gratuity\tVES\t100"),
+        "balance: {out}"
+    );
+}
+
+#[test]
+fn buy_tax_and_tip_follow_the_payment_commodity_rounding_rule() {
+    let (home, _cmd) = cmd_with_home();
+    run_ok(&home, &["commodity", "set", "JPY", "--places", "0"]);
+
+    run_ok(
+        &home,
+        &[
+            "buy",
+            "external:ramen",
+            "1234",
+            "JPY",
+            "--from",
+            "assets:cash",
+            "--tax",
+            "8%",
+            "--tip",
+            "5%",
+        ],
+    );
+
+    // 98.72 and 61.7 round to whole yen.
+    let out = run_ok_out(&home, &["balance"]);
+    assert!(out.contains("expenses:tax\tJPY\t99\n"), "balance: {out}");
+    assert!(out.contains("expenses:tip\tJPY\t62\n"), "balance: {out}");
+    assert!(out.contains("assets:cash\tJPY\t-1395\n"), "balance: {out}");
+}
+
+#[test]
+fn quick_entry_expands_to_buy_with_aliases_and_defaults() {
+    let (home, _cmd) = cmd_with_home();
+
+    run_ok(&home, &["q", "45.2 USD groceries @cash #weekly"]);
+
+    let out = run_ok_out(&home, &["balance"]);
+    assert!(out.contains("assets:cash\tUSD\t-45.2"), "balance: {out}");
+    assert!(
+        out.contains("expenses:groceries\tUSD\t45.2"),
+        "balance: {out}"
+    );
+
+    let out = run_ok_out(&home, &["report", "--tag", "weekly"]);
+    assert!(out.contains("\tbuy\t"), "report: {out}");
+
+    // Without @alias or a configured default account there is nowhere to pay from.
+    let mut cmd = bankero_cmd();
+    cmd.env("BANKERO_HOME", home.path());
+    cmd.args(["q", "12 coffee"]);
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("default_account"));
+
+    let cfg_path = home.path().join("config").join("config.json");
+    let mut cfg: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(&cfg_path).unwrap()).unwrap();
+    cfg["default_account"] = serde_json::json!("assets:wallet");
+    cfg["account_aliases"] = serde_json::json!({"bank": "assets:banesco"});
+    std::fs::write(&cfg_path, serde_json::to_string_pretty(&cfg).unwrap()).unwrap();
+
+    run_ok(&home, &["q", "12", "coffee"]);
+    run_ok(&home, &["q", "300 VES transport @bank"]);
+
+    let out = run_ok_out(&home, &["balance"]);
+    assert!(out.contains("assets:wallet\tUSD\t-12"), "balance: {out}");
+    assert!(out.contains("expenses:coffee\tUSD\t12"), "balance: {out}");
+    assert!(out.contains("assets:banesco\tVES\t-300"), "balance: {out}");
+}
+
+#[test]
+fn paste_extracts_purchase_from_notification_text() {
+    let (home, _cmd) = cmd_with_home();
+
+    // Without --yes, piped text only yields a proposal.
+    let mut cmd = assert_cmd::Command::new(assert_cmd::cargo::cargo_bin!("bankero"));
+    cmd.env("BANKERO_HOME", home.path());
+    cmd.args(["paste", "--from", "assets:banesco"]);
+    cmd.write_stdin("Banesco: Compra por Bs. 1.234,56 en FARMATODO. Ref 0042\n");
+    cmd.assert().success().stdout(predicate::str::contains(
+        "bankero buy external:farmatodo 1234.56 VES --from assets:banesco",
+    ));
+    let out = run_ok_out(&home, &["balance"]);
+    assert!(out.contains("(no balances)"), "balance: {out}");
+
+    // A configured rule wins over the built-ins and can supply commodity/account.
+    let cfg_path = home.path().join("config").join("config.json");
+    let mut cfg: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(&cfg_path).unwrap()).unwrap();
+    cfg["paste_rules"] = serde_json::json!([{
+        "name": "mercantil",
+        "pattern": r"Mercantil: debito de (?P<amount>[\d.,]+) comercio (?P<payee>\w+)",
+        "commodity": "VES",
+        "from": "assets:mercantil"
+    }]);
+    std::fs::write(&cfg_path, serde_json::to_string_pretty(&cfg).unwrap()).unwrap();
+
+    run_ok(
+        &home,
+        &[
+            "paste",
+            "--yes",
+            "--text",
+            "Mercantil: debito de 840,00 comercio TRAKI",
+        ],
+    );
+    let out = run_ok_out(&home, &["balance"]);
+    assert!(
+        out.contains("assets:mercantil\tVES\t-840.00"),
+        "balance: {out}"
+    );
+    assert!(
+        out.contains("external:traki\tVES\t840.00"),
+        "balance: {out}"
+    );
+
+    // `date`/`value_date` groups date the event per `import_date`.
+    cfg["paste_rules"] = serde_json::json!([{
+        "name": "provincial",
+        "pattern": r"Provincial (?P<date>[\d/]+) valor (?P<value_date>[\d/]+): (?P<amount>[\d.,]+) en (?P<payee>\w+)",
+        "commodity": "VES",
+        "from": "assets:provincial"
+    }]);
+    std::fs::write(&cfg_path, serde_json::to_string_pretty(&cfg).unwrap()).unwrap();
+    let text = "Provincial 30/12/2025 valor 02/01/2026: 15,00 en CINEX";
+    let out = run_ok_out(&home, &["paste", "--yes", "--text", text]);
+    assert!(
+        out.contains("--from assets:provincial --effective-at 2025-12-30"),
+        "paste: {out}"
+    );
+    run_ok(&home, &["config", "set", "import_date", "value"]);
+    run_ok(&home, &["paste", "--yes", "--text", text]);
+    let out = run_ok_out(&home, &["register", "external:cinex"]);
+    assert!(out.starts_with("2025-12-30T12:00:00"), "register: {out}");
+    assert!(out.contains("\n2026-01-02T12:00:00"), "register: {out}");
+
+    let mut cmd = bankero_cmd();
+    cmd.env("BANKERO_HOME", home.path());
+    cmd.args(["paste", "--yes", "--text", "hello there"]);
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("No paste rule matched"));
+}
+
+#[test]
+fn bot_stdin_previews_entries_and_writes_after_yes() {
+    let (home, _cmd) = cmd_with_home();
+
+    let mut cmd = assert_cmd::Command::new(assert_cmd::cargo::cargo_bin!("bankero"));
+    cmd.env("BANKERO_HOME", home.path());
+    cmd.args(["bot", "--stdin"]);
+    cmd.write_stdin("45.2 USD groceries @cash\n/balance\n/yes\n/balance expenses\n/yes\n");
+    let out = cmd.assert().success().get_output().stdout.clone();
+    let out = String::from_utf8(out).expect("utf8 stdout");
+
+    assert!(
+        out.contains("expenses:groceries 45.2 USD"),
+        "bot output: {out}"
+    );
+    assert!(out.contains("(no balances)"), "bot output: {out}");
+    assert!(out.contains("Wrote event"), "bot output: {out}");
+    assert!(out.contains("Nothing to confirm."), "bot output: {out}");
+
+    let out = run_ok_out(&home, &["balance"]);
+    assert!(out.contains("assets:cash\tUSD\t-45.2"), "balance: {out}");
+}
+
+#[test]
+fn bot_api_token_scopes_limit_what_the_bot_may_do() {
+    let (home, _cmd) = cmd_with_home();
+    let token = |name: &str, scope: &str| {
+        let out = run_ok_out(&home, &["token", "create", name, "--scope", scope]);
+        out.lines().last().expect("token line").to_string()
+    };
+    let wall = token("wall", "read-only");
+    let phone = token("phone", "write-events");
+    assert!(wall.starts_with("bkt_"), "token: {wall}");
+
+    let out = run_ok_out(&home, &["token", "list"]);
+    assert!(out.contains("wall\tpersonal\tread-only\t"), "{out}");
+    assert!(out.contains("phone\tpersonal\twrite-events\t"), "{out}");
+    assert!(!out.contains(&wall), "{out}");
+
+    // No surface has admin-only operations, so there is no admin scope to hand out.
+    let mut cmd = bankero_cmd();
+    cmd.env("BANKERO_HOME", home.path());
+    cmd.args(["token", "create", "root", "--scope", "admin"]);
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("invalid value 'admin'"));
+
+    let bot = |token: &str, input: &str| {
+        let mut cmd = assert_cmd::Command::new(assert_cmd::cargo::cargo_bin!("bankero"));
+        cmd.env("BANKERO_HOME", home.path());
+        cmd.args(["bot", "--stdin", "--api-token", token]);
+        cmd.write_stdin(input.to_string());
+        cmd.assert()
+    };
+
+    // The dashboard token reads but cannot write.
+    let out = bot(&wall, "45 USD groceries @cash\n/yes\n/balance\n")
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let out = String::from_utf8(out).expect("utf8 stdout");
+    assert!(
+        out.contains("Error: Token 'wall' is read-only; writing events needs write-events"),
+        "bot output: {out}"
+    );
+    assert!(out.contains("(no balances)"), "bot output: {out}");
+
+    bot(&phone, "45 USD groceries @cash\n/yes\n")
+        .success()
+        .stdout(predicate::str::contains("Wrote event"));
+
+    // Revoked tokens and tokens of another workspace are refused outright.
+    run_ok(&home, &["token", "revoke", "wall"]);
+    bot(&wall, "/balance\n")
+        .failure()
+        .stderr(predicate::str::contains("Unknown or revoked API token"));
+    run_ok(&home, &["ws", "add", "business"]);
+    run_ok(&home, &["ws", "checkout", "business"]);
+    bot(&phone, "/balance\n")
+        .failure()
+        .stderr(predicate::str::contains(
+            "belongs to workspace 'personal', not 'business'",
+        ));
+}
+
+#[test]
+fn csv_output_escapes_fields_and_writes_files() {
+    let (home, _cmd) = cmd_with_home();
+
+    run_ok(
+        &home,
+        &[
+            "deposit",
+            "100",
+            "USD",
+            "--from",
+            "income:salary",
+            "--to",
+            "assets:cash",
+            "--note",
+            "pay, \"Feb\"",
+            "--effective-at",
+            "2026-02-01T09:00:00Z",
+        ],
+    );
+    run_ok(
+        &home,
+        &[
+            "buy",
+            "external:market",
+            "20",
+            "USD",
+            "--from",
+            "assets:cash",
+            "--effective-at",
+            "2026-02-05T09:00:00Z",
+        ],
+    );
+
+    let out = run_ok_out(&home, &["report", "--output", "csv"]);
+    let mut lines = out.lines();
+    assert_eq!(
+        lines.next(),
+        Some(
+            "date,effective_at,action,event_id,account,commodity,amount,category,tags,note,project"
+        )
+    );
+    assert!(
+        out.contains(",assets:cash,USD,100,,,\"pay, \"\"Feb\"\"\",default"),
+        "report csv: {out}"
+    );
+    assert!(out.contains("\n2026-02-05,"), "report csv: {out}");
+
+    let csv_path = home.path().join("register.csv");
+    run_ok(
+        &home,
+        &[
+            "register",
+            "assets:cash",
+            "--output",
+            "csv",
+            "--out",
+            csv_path.to_str().unwrap(),
+        ],
+    );
+    let written = std::fs::read_to_string(&csv_path).expect("register csv");
+    assert!(written.starts_with("date,effective_at,action,account,commodity,amount,balance"));
+    assert!(
+        written.contains(",buy,assets:cash,USD,-20,80,"),
+        "register csv: {written}"
+    );
+
+    let out = run_ok_out(&home, &["balance", "--output", "csv"]);
+    assert!(
+        out.contains("balance,assets:cash,USD,80"),
+        "balance csv: {out}"
+    );
+
+    let out = run_ok_out(&home, &["budget", "report", "--output", "csv"]);
+    assert_eq!(out.trim(), "month,name,commodity,budget,actual,remaining");
+
+    // --out is a CSV-only option.
+    let mut cmd = bankero_cmd();
+    cmd.env("BANKERO_HOME", home.path());
+    cmd.args(["balance", "--out", "x.csv"]);
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("--out requires --output csv"));
+}
+
+#[test]
+fn report_baseline_diff_shows_only_new_events_and_changed_totals() {
+    let (home, _cmd) = cmd_with_home();
+
+    run_ok(
+        &home,
+        &[
+            "deposit",
+            "100",
+            "USD",
+            "--from",
+            "income:salary",
+            "--to",
+            "assets:cash",
+            "--effective-at",
+            "2026-02-01T09:00:00Z",
+        ],
+    );
+    let saved = run_ok_out(
+        &home,
+        &["report", "--month", "2026-02", "--save-baseline", "monthly"],
+    );
+    assert!(
+        saved.contains("Saved baseline 'monthly': 1 event(s), 2 total(s)."),
+        "save: {saved}"
+    );
+
+    let unchanged = run_ok_out(
+        &home,
+        &["report", "--month", "2026-02", "--diff-baseline", "monthly"],
+    );
+    assert!(
+        unchanged.contains("No changes since baseline 'monthly'"),
+        "unchanged: {unchanged}"
+    );
+
+    run_ok(
+        &home,
+        &[
+            "buy",
+            "external:market",
+            "20",
+            "USD",
+            "--from",
+            "assets:cash",
+            "--effective-at",
+            "2026-02-05T09:00:00Z",
+        ],
+    );
+    let diff = run_ok_out(
+        &home,
+        &["report", "--month", "2026-02", "--diff-baseline", "monthly"],
+    );
+    assert!(diff.contains("new events:"), "diff: {diff}");
+    assert!(diff.contains("\tbuy\t"), "diff: {diff}");
+    assert!(!diff.contains("\tdeposit\t"), "diff: {diff}");
+    assert!(!diff.contains("removed events:"), "diff: {diff}");
+    assert!(
+        diff.contains("  assets:cash\tUSD\t100 -> 80\t(-20)"),
+        "diff: {diff}"
+    );
+    assert!(
+        diff.contains("  external:market\tUSD\t0 -> 20\t(+20)"),
+        "diff: {diff}"
+    );
+    assert!(!diff.contains("income:salary"), "diff: {diff}");
+
+    let mut missing = bankero_cmd();
+    missing.env("BANKERO_HOME", home.path());
+    missing.args(["report", "--diff-baseline", "weekly"]);
+    missing
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("No baseline named 'weekly'"));
+}
+
+#[test]
+fn balance_prints_commodity_totals_and_converted_grand_total() {
+    let (home, _cmd) = cmd_with_home();
+
+    run_ok(
+        &home,
+        &[
+            "rate",
+            "set",
+            "@bcv",
+            "USD",
+            "VES",
+            "45.2",
+            "--as-of",
+            "2026-02-01T00:00:00Z",
+        ],
+    );
+    for (amount, commodity, to) in [
+        ("60", "USD", "assets:cash"),
+        ("40", "USD", "assets:bank"),
+        ("4520", "VES", "assets:ves"),
+        ("5", "EUR", "assets:eur"),
+    ] {
+        run_ok(
+            &home,
+            &[
+                "deposit",
+                amount,
+                commodity,
+                "--from",
+                "income:salary",
+                "--to",
+                to,
+                "--effective-at",
+                "2026-02-02T09:00:00Z",
+            ],
+        );
+    }
+
+    let out = run_ok_out(&home, &["balance", "assets"]);
+    let totals = out.split("(totals)\n").nth(1).expect("totals section");
+    assert!(totals.contains("USD\t100\n"), "balance: {out}");
+    assert!(totals.contains("VES\t4520\n"), "balance: {out}");
+    assert!(totals.contains("EUR\t5\n"), "balance: {out}");
+    assert!(!out.contains("total\t"), "balance: {out}");
+
+    let mut cmd = bankero_cmd();
+    cmd.env("BANKERO_HOME", home.path());
+    cmd.args(["balance", "assets", "--convert", "@bcv"]);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("total\tUSD\t200"))
+        .stderr(predicate::str::contains(
+            "no @bcv rate to USD for EUR; excluded from the total",
+        ));
+
+    let csv = run_ok_out(
+        &home,
+        &["balance", "assets", "--convert", "@bcv", "--output", "csv"],
+    );
+    assert!(csv.contains("totals,,VES,4520"), "balance csv: {csv}");
+    assert!(csv.contains("total,,USD,200"), "balance csv: {csv}");
+}
+
+#[test]
+fn profiles_keep_config_and_journals_separate() {
+    let (home, _cmd) = cmd_with_home();
+
+    run_ok(
+        &home,
+        &[
+            "deposit",
+            "100",
+            "USD",
+            "--from",
+            "income:salary",
+            "--to",
+            "assets:bank",
+        ],
+    );
+    run_ok(&home, &["--profile", "work", "ws", "checkout", "acme"]);
+    run_ok(
+        &home,
+        &[
+            "--profile",
+            "work",
+            "deposit",
+            "7",
+            "USD",
+            "--from",
+            "income:payroll",
+            "--to",
+            "assets:corp",
+        ],
+    );
+
+    // The env var selects the profile too.
+    let mut cmd = bankero_cmd();
+    cmd.env("BANKERO_HOME", home.path());
+    cmd.env("BANKERO_PROFILE", "work");
+    cmd.args(["balance"]);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("assets:corp\tUSD\t7"))
+        .stdout(predicate::str::contains("assets:bank").not());
+
+    let personal = run_ok_out(&home, &["balance"]);
+    assert!(personal.contains("assets:bank\tUSD\t100"), "{personal}");
+    assert!(!personal.contains("assets:corp"), "{personal}");
+
+    let ws = run_ok_out(&home, &["ws", "check"]);
+    assert!(ws.contains("workspace: personal"), "{ws}");
+
+    let current = run_ok_out(&home, &["profile", "current", "--profile", "work"]);
+    assert!(current.contains("profile\twork"), "{current}");
+    assert!(current.contains("workspace\tacme"), "{current}");
+    let default_device = run_ok_out(&home, &["profile", "current"]);
+    let device = |out: &str| {
+        out.lines()
+            .find(|l| l.starts_with("device\t"))
+            .map(str::to_string)
+    };
+    assert_ne!(device(&current), device(&default_device));
+
+    let list = run_ok_out(&home, &["profile", "list"]);
+    assert_eq!(list, "* default\n  work\n");
+
+    let mut cmd = bankero_cmd();
+    cmd.env("BANKERO_HOME", home.path());
+    cmd.args(["--profile", "../oops", "balance"]);
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("Invalid profile name"));
+}
+
+#[test]
+fn config_data_dir_moves_journals_out_of_the_home() {
+    let (home, _cmd) = cmd_with_home();
+    let vault = tempfile::tempdir().expect("tempdir");
+    let data = vault.path().join("bankero");
+    let data_str = data.to_string_lossy().to_string();
+
+    run_ok(&home, &["config", "set", "data_dir", &data_str]);
+    let got = run_ok_out(&home, &["config", "get", "data_dir"]);
+    assert_eq!(got.trim(), data_str);
+
+    run_ok(
+        &home,
+        &[
+            "deposit",
+            "100",
+            "USD",
+            "--from",
+            "income:salary",
+            "--to",
+            "assets:bank",
+        ],
+    );
+    assert!(
+        data.join("workspaces/personal/bankero.sqlite3").exists(),
+        "journal should live in the data dir"
+    );
+    assert!(!home.path().join("data/workspaces").exists());
+
+    // An unplugged drive is an error, not a fresh empty ledger.
+    std::fs::remove_dir_all(&data).expect("remove data dir");
+    let mut cmd = bankero_cmd();
+    cmd.env("BANKERO_HOME", home.path());
+    cmd.args(["balance"]);
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("is the drive mounted?"));
+    assert!(!data.exists());
+
+    run_ok(&home, &["config", "unset", "data_dir"]);
+    let got = run_ok_out(&home, &["config", "get", "data_dir"]);
+    assert!(got.trim().ends_with("(default)"), "{got}");
+
+    let mut cmd = bankero_cmd();
+    cmd.env("BANKERO_HOME", home.path());
+    cmd.args(["config", "set", "data_dir", "relative/dir"]);
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("absolute path"));
+}
+
+#[test]
+fn init_wizard_writes_config_and_opening_balances() {
+    let (home, _cmd) = cmd_with_home();
+    let sync = tempfile::tempdir().expect("tempdir");
+
+    // Answers: commodity, timezone, default account, sync folder, two openings, blank.
+    let answers = format!(
+        "eur\n-04:00\nassets:wallet\n{}\nassets:bank 2500\nliabilities:card -300\nnot a balance\n\n",
+        sync.path().display()
+    );
+    let mut cmd = assert_cmd::Command::new(assert_cmd::cargo::cargo_bin!("bankero"));
+    cmd.env("BANKERO_HOME", home.path());
+    cmd.args(["init", "--as-of", "2026-01-01"]);
+    cmd.write_stdin(answers);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("reference_commodity\tEUR"))
+        .stdout(predicate::str::contains("timezone\t-04:00"))
+        .stdout(predicate::str::contains(
+            "Wrote opening balances for 2 account(s) as of 2026-01-01T04:00:00+00:00.",
+        ))
+        .stderr(predicate::str::contains("Invalid opening balance"));
+
+    let bal = run_ok_out(&home, &["balance"]);
+    assert!(bal.contains("assets:bank\tEUR\t2500"), "{bal}");
+    assert!(bal.contains("liabilities:card\tEUR\t-300"), "{bal}");
+    assert!(bal.contains("equity:opening-balances\tEUR\t-2200"), "{bal}");
+
+    // Quick entries now pay from the chosen default account.
+    run_ok(&home, &["q", "4.50", "coffee"]);
+    let bal = run_ok_out(&home, &["balance", "assets:wallet"]);
+    assert!(bal.contains("assets:wallet\tEUR\t-4.50"), "{bal}");
+
+    let tz = run_ok_out(&home, &["config", "get", "timezone"]);
+    assert_eq!(tz.trim(), "-04:00");
+
+    // Re-running never duplicates opening balances.
+    let out = run_ok_out(&home, &["init", "--yes", "--opening", "assets:bank 1"]);
+    assert!(out.contains("skipped opening balances"), "{out}");
+    let bal = run_ok_out(&home, &["balance", "assets:bank"]);
+    assert!(bal.contains("assets:bank\tEUR\t2500"), "{bal}");
+}
+
+#[test]
+fn help_topics_and_man_pages_work_offline() {
+    let (home, _cmd) = cmd_with_home();
+
+    let topics = run_ok_out(&home, &["help", "topics"]);
+    for topic in ["accounts", "commodities", "providers", "sync", "budgets"] {
+        assert!(topics.contains(&format!("  {topic} ")), "{topics}");
+    }
+    let providers = run_ok_out(&home, &["help", "providers"]);
+    assert!(providers.starts_with("PROVIDERS\n"), "{providers}");
+    assert!(providers.contains("bankero rate set @bcv"), "{providers}");
+
+    // Command paths still resolve like clap's built-in help subcommand.
+    let create = run_ok_out(&home, &["help", "budget", "create"]);
+    assert!(create.starts_with("Create a budget."), "{create}");
+
+    let mut cmd = bankero_cmd();
+    cmd.env("BANKERO_HOME", home.path());
+    cmd.args(["help", "nonsense"]);
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("bankero help topics"));
+
+    let out_dir = home.path().join("man");
+    let out_str = out_dir.to_string_lossy().to_string();
+    let out = run_ok_out(&home, &["generate-man", "--out-dir", &out_str]);
+    assert!(out.starts_with("Wrote "), "{out}");
+    let page = std::fs::read_to_string(out_dir.join("bankero-budget-create.1")).expect("page");
+    assert!(page.contains(".TH bankero-budget-create 1"), "{page}");
+    assert!(out_dir.join("bankero.1").exists());
+    assert!(!out_dir.join("bankero-generate-man.1").exists());
+}
+
+/// Serves `files` (path -> body) over HTTP on localhost until the test process exits.
+fn serve(files: Vec<(String, Vec<u8>)>) -> String {
+    use std::io::{BufRead, BufReader, Write};
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").expect("bind");
+    let addr = listener.local_addr().expect("addr");
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let Ok(mut stream) = stream else { continue };
+            let mut line = String::new();
+            BufReader::new(&stream).read_line(&mut line).ok();
+            let path = line.split_whitespace().nth(1).unwrap_or("/").to_string();
+            let (status, body) = match files.iter().find(|(p, _)| *p == path) {
+                Some((_, body)) => ("200 OK", body.clone()),
+                None => ("404 Not Found", Vec::new()),
+            };
+            let head = format!(
+                "HTTP/1.1 {status}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                body.len()
+            );
+            stream.write_all(head.as_bytes()).ok();
+            stream.write_all(&body).ok();
+        }
+    });
+    format!("http://{addr}")
+}
+
+#[test]
+fn upgrade_binary_method_verifies_checksum_and_swaps_executable() {
+    use sha2::{Digest, Sha256};
+    use std::io::Write;
+
+    let (home, _cmd) = cmd_with_home();
+    let asset = format!("bankero-{}.gz", env!("BANKERO_TARGET"));
+    let mut gz = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+    gz.write_all(b"new bankero build").expect("gzip");
+    let gz = gz.finish().expect("gzip");
+    let sum: String = Sha256::digest(&gz)
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect();
+
+    let good = serve(vec![
+        (format!("/v9.9.9/{asset}"), gz.clone()),
+        (
+            "/v9.9.9/SHA256SUMS".to_string(),
+            format!("{sum}  {asset}\n").into_bytes(),
+        ),
+    ]);
+    let bad = serve(vec![
+        (format!("/v9.9.9/{asset}"), gz),
+        (
+            "/v9.9.9/SHA256SUMS".to_string(),
+            format!("{}  {asset}\n", "0".repeat(64)).into_bytes(),
+        ),
+    ]);
+
+    let exe = home.path().join("bin").join("bankero");
+    std::fs::create_dir_all(exe.parent().unwrap()).expect("mkdir");
+    std::fs::write(&exe, b"old build").expect("write");
+    let exe_str = exe.to_string_lossy().to_string();
+    let upgrade = |url: &str| {
+        let mut cmd = bankero_cmd();
+        cmd.env("BANKERO_HOME", home.path());
+        cmd.args([
+            "upgrade",
+            "--apply",
+            "--skip-check",
+            "--method",
+            "binary",
+            "--tag",
+            "v9.9.9",
+            "--release-url",
+            url,
+            "--install-path",
+            &exe_str,
+        ]);
+        cmd
+    };
+
+    upgrade(&bad)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Checksum mismatch"));
+    assert_eq!(std::fs::read(&exe).expect("read"), b"old build");
+
+    upgrade(&good)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Checksum OK"))
+        .stdout(predicate::str::contains("with v9.9.9."));
+    assert_eq!(std::fs::read(&exe).expect("read"), b"new bankero build");
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = std::fs::metadata(&exe).expect("meta").permissions().mode();
+        assert_eq!(mode & 0o111, 0o111, "mode {mode:o}");
+    }
+    let leftovers = std::fs::read_dir(exe.parent().unwrap())
+        .expect("ls")
+        .count();
+    assert_eq!(leftovers, 1, "staged file should be renamed into place");
+}
+
+#[test]
+fn upgrade_beta_channel_checks_minisign_signature() {
+    use sha2::{Digest, Sha256};
+
+    // Signed with a throwaway minisign key; see the public key below.
+    const PUBLIC_KEY: &str = "RWQHBgUEAwIBAAOhB7/zzhC+HXDdGOdLwJln5NYwm6UNXx3chmQSVTG4";
+    const SIGNED_GZ: &[u8] = &[
+        31, 139, 8, 0, 0, 0, 0, 0, 2, 3, 75, 74, 45, 73, 84, 72, 74, 204, 203, 78, 45, 202, 87, 72,
+        42, 205, 204, 73, 1, 0, 96, 173, 21, 48, 18, 0, 0, 0,
+    ];
+    const SIGNATURE: &str = "untrusted comment: signature from minisign secret key\n\
+        RUQHBgUEAwIBAEb2ZVOy0K/2ugLgmmd6OuK1CZRBeNBdqYIa3uGmj2rmQjdqYJvfqUd0OEpgIEj3A+AtUubltqsDlIM8YYYb0g8=\n\
+        trusted comment: timestamp:0\tfile:bankero.gz\n\
+        FW/zNUJXYAjBLgMzbqCENrMvDLJrnig8iKRlWdvfnHESfYu1XSkPNcxWPOdm0rcmFG09amdZ8XJwC/TZZuRlAg==\n";
+
+    let (home, _cmd) = cmd_with_home();
+    let asset = format!("bankero-{}.gz", env!("BANKERO_TARGET"));
+    let releases = br#"[
+        {"tag_name": "v10.0.0", "draft": true},
+        {"tag_name": "v9.9.9-beta.1", "prerelease": true},
+        {"tag_name": "v9.9.8"}
+    ]"#
+    .to_vec();
+    let release = |gz: &[u8]| {
+        let sum: String = Sha256::digest(gz)
+            .iter()
+            .map(|b| format!("{b:02x}"))
+            .collect();
+        serve(vec![
+            (
+                "/repos/JoCarrasco/bankero/releases?per_page=30".to_string(),
+                releases.clone(),
+            ),
+            (format!("/v9.9.9-beta.1/{asset}"), gz.to_vec()),
+            (
+                "/v9.9.9-beta.1/SHA256SUMS".to_string(),
+                format!("{sum}  {asset}\n").into_bytes(),
+            ),
+            (
+                format!("/v9.9.9-beta.1/{asset}.minisig"),
+                SIGNATURE.as_bytes().to_vec(),
+            ),
+        ])
+    };
+    // A tampered asset whose SHA256SUMS was rewritten to match still fails the signature.
+    let mut tampered = SIGNED_GZ.to_vec();
+    tampered[20] ^= 1;
+    let tampered = release(&tampered);
+    let genuine = release(SIGNED_GZ);
+
+    let exe = home.path().join("bankero");
+    std::fs::write(&exe, b"old build").expect("write");
+    let exe_str = exe.to_string_lossy().to_string();
+    let upgrade = |url: &str| {
+        let mut cmd = bankero_cmd();
+        cmd.env("BANKERO_HOME", home.path());
+        cmd.args([
+            "upgrade",
+            "--apply",
+            "--channel",
+            "beta",
+            "--api-url",
+            url,
+            "--release-url",
+            url,
+            "--public-key",
+            PUBLIC_KEY,
+            "--install-path",
+            &exe_str,
+        ]);
+        cmd
+    };
+
+    upgrade(&tampered)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Signature check failed"));
+    assert_eq!(std::fs::read(&exe).expect("read"), b"old build");
+
+    upgrade(&genuine)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Latest:  v9.9.9-beta.1 (beta channel)",
+        ))
+        .stdout(predicate::str::contains("Signature OK."));
+    assert_eq!(std::fs::read(&exe).expect("read"), b"beta bankero build");
+
+    let mut cmd = upgrade(&genuine);
+    cmd.args(["--method", "apt"]);
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("only carries stable releases"));
+}
+
+#[test]
+fn upgrade_check_renders_release_notes_of_newer_versions() {
+    let (home, _cmd) = cmd_with_home();
+    let latest = serde_json::json!({
+        "tag_name": "v0.1.0",
+        "html_url": "https://example.invalid/v0.1.0",
+        "body": "unused",
+    });
+    let releases = serde_json::json!([
+        {
+            "tag_name": "v0.1.0",
+            "body": "## Added\r\n- **Profiles** via `--profile`\r\n- See [the docs](https://example.invalid/docs)\r\n",
+        },
+        {"tag_name": "v0.1.0-beta.1", "prerelease": true, "body": "beta only"},
+        {
+            "tag_name": "v0.0.9",
+            "body": "### Fixed\n* Sync ordering\n\n```\nbankero sync now\n```\n",
+        },
+        {"tag_name": "v0.0.7", "body": "already installed"},
+    ]);
+    let url = serve(vec![
+        (
+            "/repos/JoCarrasco/bankero/releases/latest".to_string(),
+            latest.to_string().into_bytes(),
+        ),
+        (
+            "/repos/JoCarrasco/bankero/releases?per_page=30".to_string(),
+            releases.to_string().into_bytes(),
+        ),
+    ]);
+
+    let out = run_ok_out(&home, &["upgrade", "--api-url", &url]);
+    let expected = "\
+What's new in v0.1.0:
+
+Added
+-----
+  • Profiles via --profile
+  • See the docs (https://example.invalid/docs)
+
+What's new in v0.0.9:
+
+Fixed
+-----
+  • Sync ordering
+
+    bankero sync now
+";
+    assert!(out.contains(expected), "{out}");
+    assert!(!out.contains("beta only"), "{out}");
+    assert!(!out.contains("already installed"), "{out}");
+
+    let quiet = run_ok_out(&home, &["upgrade", "--api-url", &url, "--no-changelog"]);
+    assert!(quiet.contains("Update available"), "{quiet}");
+    assert!(!quiet.contains("What's new"), "{quiet}");
+}
+
+#[cfg(unix)]
+#[test]
+fn upgrade_drives_homebrew_and_winget() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let (home, _cmd) = cmd_with_home();
+    let bin = home.path().join("fake-bin");
+    std::fs::create_dir_all(&bin).expect("mkdir");
+    let log = home.path().join("calls.log");
+    for tool in ["brew", "winget"] {
+        let script = bin.join(tool);
+        std::fs::write(
+            &script,
+            format!("#!/bin/sh\necho \"{tool} $*\" >> '{}'\n", log.display()),
+        )
+        .expect("write script");
+        std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755)).expect("chmod");
+    }
+    let path = format!(
+        "{}:{}",
+        bin.display(),
+        std::env::var("PATH").unwrap_or_default()
+    );
+    let upgrade = |extra: &[&str]| {
+        let mut cmd = bankero_cmd();
+        cmd.env("BANKERO_HOME", home.path());
+        cmd.env("PATH", &path);
+        cmd.args(["upgrade", "--apply", "--skip-check"]);
+        cmd.args(extra);
+        cmd.assert().success();
+        let calls = std::fs::read_to_string(&log).unwrap_or_default();
+        std::fs::remove_file(&log).ok();
+        calls
+    };
+
+    // `brew list` succeeding means Homebrew manages this install, so auto picks it.
+    assert_eq!(
+        upgrade(&[]),
+        "brew list --versions jocarrasco/tap/bankero\nbrew update\nbrew upgrade jocarrasco/tap/bankero\n"
+    );
+    assert_eq!(
+        upgrade(&["--method", "winget", "--winget-id", "Acme.Bankero", "--yes"]),
+        "winget upgrade --id Acme.Bankero --exact --silent --accept-source-agreements --accept-package-agreements\n"
+    );
+
+    let mut cmd = bankero_cmd();
+    cmd.env("BANKERO_HOME", home.path());
+    cmd.env("PATH", &path);
+    cmd.args([
+        "upgrade",
+        "--apply",
+        "--skip-check",
+        "--method",
+        "brew",
+        "--channel",
+        "beta",
+    ]);
+    cmd.assert().failure().stderr(predicate::str::contains(
+        "The Homebrew formula only carries stable releases",
+    ));
+}
+
+#[test]
+fn rules_export_import_shares_paste_and_category_rules_between_devices() {
+    let (laptop, _cmd) = cmd_with_home();
+    let (phone, _cmd) = cmd_with_home();
+
+    let shared = laptop.path().join("family-rules.toml");
+    std::fs::write(
+        &shared,
+        r#"version = 1
+
+[aliases]
+cash = "assets:cash"
+
+[[paste]]
+name = "mercantil"
+pattern = 'Mercantil: debito de (?P<amount>[\d.,]+) comercio (?P<payee>\w+)'
+commodity = "VES"
+from = "assets:mercantil"
+
+[[categorize]]
+contains = "farmatodo"
+category = "expenses:health"
+
+[[categorize]]
+contains = "traki"
+category = "expenses:clothing"
+"#,
+    )
+    .expect("write rules");
+    let out = run_ok_out(&laptop, &["rules", "import", shared.to_str().unwrap()]);
+    assert!(
+        out.contains("Imported 1 alias(es), 1 paste rule(s) and 2 category rule(s)"),
+        "import: {out}"
+    );
+
+    // Round trip through the laptop's export into the phone's config.
+    let exported = laptop.path().join("exported.toml");
+    run_ok(&laptop, &["rules", "export", exported.to_str().unwrap()]);
+    run_ok(&phone, &["rules", "import", exported.to_str().unwrap()]);
+    let out = run_ok_out(&phone, &["rules", "export"]);
+    assert!(out.contains("[[categorize]]"), "export: {out}");
+    assert!(out.contains("contains = \"traki\""), "export: {out}");
+    assert!(out.contains("cash = \"assets:cash\""), "export: {out}");
+
+    run_ok(
+        &phone,
+        &[
+            "buy",
+            "external:FarmaTodo",
+            "12",
+            "USD",
+            "--from",
+            "assets:cash",
+            "--effective-at",
+            "2026-02-25T12:00:00Z",
+        ],
+    );
+    run_ok(
+        &phone,
+        &[
+            "paste",
+            "--yes",
+            "--text",
+            "Mercantil: debito de 840,00 comercio TRAKI",
+        ],
+    );
+    // An explicit --category always wins over the rules.
+    run_ok(
+        &phone,
+        &[
+            "buy",
+            "external:farmatodo",
+            "5",
+            "USD",
+            "--from",
+            "assets:cash",
+            "--category",
+            "expenses:snacks",
+        ],
+    );
+
+    let out = run_ok_out(&phone, &["report", "--category", "expenses:health"]);
+    assert_eq!(out.lines().count(), 1, "health: {out}");
+    let out = run_ok_out(&phone, &["report", "--category", "expenses:clothing"]);
+    assert_eq!(out.lines().count(), 1, "clothing: {out}");
+
+    // --replace drops local rules that the file does not carry.
+    let only_alias = phone.path().join("alias.toml");
+    std::fs::write(
+        &only_alias,
+        "version = 1\n\n[aliases]\nbank = \"assets:bank\"\n",
+    )
+    .unwrap();
+    run_ok(
+        &phone,
+        &["rules", "import", only_alias.to_str().unwrap(), "--replace"],
+    );
+    let out = run_ok_out(&phone, &["rules", "export"]);
+    assert_eq!(
+        out.trim(),
+        "version = 1\n\n[aliases]\nbank = \"assets:bank\"",
+        "export: {out}"
+    );
+
+    std::fs::write(&only_alias, "version = 2\n").unwrap();
+    let mut cmd = bankero_cmd();
+    cmd.env("BANKERO_HOME", phone.path());
+    cmd.args(["rules", "import", only_alias.to_str().unwrap()]);
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("this bankero reads version 1"));
+}
+
+#[test]
+fn debug_bundle_zips_schema_counts_and_an_obfuscated_event_sample() {
+    use std::io::Read;
+
+    let (home, _cmd) = cmd_with_home();
+    run_ok(
+        &home,
+        &[
+            "deposit",
+            "1234.56",
+            "USD",
+            "--to",
+            "assets:banesco",
+            "--from",
+            "income:acme-payroll",
+            "--note",
+            "salary from Acme",
+        ],
+    );
+    run_ok(
+        &home,
+        &[
+            "buy",
+            "expenses:pharmacy:farmatodo",
+            "40",
+            "USD",
+            "--from",
+            "assets:banesco",
+        ],
+    );
+
+    let zip_path = home.path().join("bundle.zip");
+    let out = run_ok_out(
+        &home,
+        &["debug", "bundle", "--out", zip_path.to_str().unwrap()],
+    );
+    assert!(out.contains("2 of 2 event(s) sampled"), "out: {out}");
+
+    let mut archive =
+        zip::ZipArchive::new(std::fs::File::open(&zip_path).expect("open zip")).expect("zip");
+    let mut read = |name: &str| {
+        let mut s = String::new();
+        archive
+            .by_name(name)
+            .unwrap_or_else(|_| panic!("missing {name}"))
+            .read_to_string(&mut s)
+            .expect("read entry");
+        s
+    };
+    assert!(read("schema.sql").contains("CREATE TABLE events"));
+    let counts: serde_json::Value = serde_json::from_str(&read("counts.json")).unwrap();
+    assert_eq!(counts["tables"]["events"], 2);
+    assert_eq!(counts["events_by_action"]["deposit"], 1);
+    let config = read("config.json");
+    assert!(config.contains("<redacted>"), "config: {config}");
+    let settings: serde_json::Value = serde_json::from_str(&config).unwrap();
+    assert_eq!(settings["reference_commodity"], "USD");
+    assert_eq!(
+        settings["fee_account"]
+            .as_str()
+            .map(|a| a.starts_with("expenses:")),
+        Some(true)
+    );
+    assert_ne!(settings["fee_account"], "expenses:fees");
+    assert!(!config.contains(home.path().to_str().unwrap()));
+    let info = read("info.json");
+    assert!(info.contains("bankero_version"));
+
+    let events = read("events.jsonl");
+    for secret in [
+        "banesco",
+        "acme",
+        "Acme",
+        "farmatodo",
+        "pharmacy",
+        "1234.56",
+    ] {
+        assert!(!events.contains(secret), "{secret} leaked: {events}");
+    }
+    let first: serde_json::Value = serde_json::from_str(events.lines().next().unwrap()).unwrap();
+    assert_eq!(first["action"], "deposit");
+    assert_eq!(first["has_note"], true);
+    let postings = first["postings"].as_array().unwrap();
+    assert!(
+        postings
+            .iter()
+            .any(|p| p["account"].as_str().unwrap().starts_with("assets:h")),
+        "postings: {postings:?}"
+    );
+    assert_eq!(postings[0]["commodity"], "USD");
+    // Scaled by one factor, so the event still balances.
+    let sum: rust_decimal::Decimal = postings
+        .iter()
+        .map(|p| {
+            p["amount"]
+                .as_str()
+                .unwrap()
+                .parse::<rust_decimal::Decimal>()
+                .unwrap()
+        })
+        .sum();
+    assert!(sum.is_zero(), "postings: {postings:?}");
+}
+
+#[test]
+fn report_tags_totals_spend_per_tag_and_counts_multi_tag_events_in_each() {
+    let (home, _cmd) = cmd_with_home();
+    let buy = |payee: &str, amount: &str, at: &str, tags: &[&str]| {
+        let mut args = vec![
+            "buy",
+            payee,
+            amount,
+            "USD",
+            "--from",
+            "assets:bank",
+            "--effective-at",
+            at,
+        ];
+        for t in tags {
+            args.extend(["--tag", t]);
+        }
+        run_ok(&home, &args);
+    };
+    buy(
+        "external:airline",
+        "400",
+        "2026-02-03T10:00:00Z",
+        &["trip:cancun"],
+    );
+    buy(
+        "expenses:meals",
+        "60",
+        "2026-02-10T10:00:00Z",
+        &["trip:cancun", "client:acme"],
+    );
+    buy(
+        "expenses:software",
+        "25",
+        "2026-02-12T10:00:00Z",
+        &["client:acme"],
+    );
+    buy(
+        "expenses:software",
+        "99",
+        "2026-03-01T10:00:00Z",
+        &["client:acme"],
+    );
+    run_ok(
+        &home,
+        &[
+            "deposit",
+            "1000",
+            "USD",
+            "--to",
+            "assets:bank",
+            "--from",
+            "income:acme",
+            "--tag",
+            "client:acme",
+            "--effective-at",
+            "2026-02-15T10:00:00Z",
+        ],
+    );
+
+    let out = run_ok_out(&home, &["report", "tags", "--month", "2026-02"]);
+    assert!(out.contains("tag\tcommodity\ttotal\tevents"), "{out}");
+    assert!(out.contains("client:acme\tUSD\t85\t2"), "{out}");
+    assert!(out.contains("trip:cancun\tUSD\t460\t2"), "{out}");
+
+    let out = run_ok_out(
+        &home,
+        &["report", "tags", "--month", "2026-02", "--prefix", "trip:"],
+    );
+    assert!(!out.contains("client:acme"), "{out}");
+    assert!(out.contains("trip:cancun\tUSD\t460\t2"), "{out}");
+
+    // A voided purchase and its void count toward no tag.
+    let out = run_ok_out(
+        &home,
+        &[
+            "buy",
+            "expenses:hotel",
+            "300",
+            "USD",
+            "--from",
+            "assets:bank",
+            "--tag",
+            "trip:cancun",
+            "--effective-at",
+            "2026-02-20T10:00:00Z",
+        ],
+    );
+    let hotel = out.split_whitespace().nth(2).expect("event id").to_string();
+    run_ok(&home, &["event", "void", &hotel[..8]]);
+    let out = run_ok_out(&home, &["report", "tags", "--month", "2026-02"]);
+    assert!(out.contains("trip:cancun\tUSD\t460\t2"), "{out}");
+}
+
+#[test]
+fn report_renderer_receives_the_report_as_json() {
+    let (home, _cmd) = cmd_with_home();
+    run_ok(
+        &home,
+        &[
+            "deposit",
+            "100",
+            "USD",
+            "--from",
+            "income:salary",
+            "--to",
+            "assets:cash",
+            "--effective-at",
+            "2026-02-01T12:00:00Z",
+        ],
+    );
+    run_ok(
+        &home,
+        &[
+            "buy",
+            "bakery",
+            "12.5",
+            "USD",
+            "--from",
+            "assets:cash",
+            "--category",
+            "groceries",
+            "--effective-at",
+            "2026-02-03T12:00:00Z",
+        ],
+    );
+
+    // `cat` echoes the JSON back, which is the simplest possible renderer.
+    let out = run_ok_out(
+        &home,
+        &["report", "--month", "2026-02", "--renderer", "cat"],
+    );
+    let v: serde_json::Value = serde_json::from_str(&out).expect("json");
+    assert_eq!(v["version"], 1);
+    assert_eq!(v["workspace"], "personal");
+    assert_eq!(v["filters"]["month"], "2026-02");
+    assert_eq!(v["events"].as_array().map(Vec::len), Some(2));
+    assert_eq!(v["events"][1]["payee"], "bakery");
+    assert_eq!(v["events"][1]["postings"][0]["amount"], "-12.5");
+    assert_eq!(v["totals"][0]["commodity"], "USD");
+    assert_eq!(v["totals"][0]["net"], "87.5");
+
+    let file = home.path().join("feb.json");
+    run_ok(
+        &home,
+        &[
+            "report",
+            "--renderer",
+            "cat",
+            "--out",
+            file.to_str().expect("utf8 path"),
+        ],
+    );
+    let saved = std::fs::read_to_string(&file).expect("rendered file");
+    assert!(saved.contains("\"bakery\""), "{saved}");
+
+    // A voided purchase and its void stay out of the totals.
+    let out = run_ok_out(
+        &home,
+        &[
+            "buy",
+            "florist",
+            "40",
+            "USD",
+            "--from",
+            "assets:cash",
+            "--effective-at",
+            "2026-02-05T12:00:00Z",
+        ],
+    );
+    let florist = out.split_whitespace().nth(2).expect("event id").to_string();
+    run_ok(&home, &["event", "void", &florist[..8]]);
+    let out = run_ok_out(
+        &home,
+        &["report", "--month", "2026-02", "--renderer", "cat"],
+    );
+    let v: serde_json::Value = serde_json::from_str(&out).expect("json");
+    assert_eq!(v["totals"][0]["out"], "12.5", "{out}");
+    assert_eq!(v["totals"][0]["net"], "87.5", "{out}");
+
+    let mut cmd = bankero_cmd();
+    cmd.env("BANKERO_HOME", home.path());
+    cmd.args(["report", "--renderer", "false"]);
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("Renderer false failed"));
+}
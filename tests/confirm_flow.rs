@@ -147,3 +147,543 @@ fn confirm_mode_computes_basis_deterministically_when_basis_provider_is_set() {
         .stderr(predicate::str::contains("Basis:"))
         .stderr(predicate::str::contains("Transaction value:"));
 }
+
+#[test]
+fn registered_providers_are_described_in_previews_and_conversions() {
+    let home = tempfile::tempdir().expect("tempdir");
+    let run = |args: &[&str]| {
+        let mut cmd = bankero_cmd();
+        cmd.env("BANKERO_HOME", home.path());
+        cmd.args(args);
+        cmd.assert().success()
+    };
+
+    run(&[
+        "provider",
+        "add",
+        "@bcv",
+        "--description",
+        "Banco Central",
+        "--kind",
+        "official",
+        "--url",
+        "https://www.bcv.org.ve",
+    ]);
+    run(&["provider", "add", "@binance", "--kind", "parallel"]);
+    run(&[
+        "rate",
+        "set",
+        "@bcv",
+        "USD",
+        "VES",
+        "40",
+        "--as-of",
+        "2026-02-25T12:00:00Z",
+    ]);
+
+    run(&["provider", "list"])
+        .stdout(predicate::str::contains("@bcv"))
+        .stdout(predicate::str::contains("official"))
+        .stdout(predicate::str::contains("https://www.bcv.org.ve"))
+        .stdout(predicate::str::contains("parallel"));
+
+    let mut cmd = bankero_cmd();
+    cmd.env("BANKERO_HOME", home.path());
+    cmd.args([
+        "move",
+        "4000",
+        "VES",
+        "--from",
+        "assets:wallet",
+        "--to",
+        "external:neighbor",
+        "@bcv",
+        "--confirm",
+        "--effective-at",
+        "2026-02-25T12:00:00Z",
+    ]);
+    cmd.write_stdin("y\n")
+        .assert()
+        .success()
+        .stderr(predicate::str::contains(
+            "Using @bcv (Banco Central, official) rate 40",
+        ))
+        .stderr(predicate::str::contains("Transaction value: 100 USD"));
+
+    run(&["balance", "external", "--convert", "@bcv"])
+        .stdout(predicate::str::contains("total\tUSD\t100"))
+        .stdout(predicate::str::contains(
+            "via\t@bcv (Banco Central, official)",
+        ));
+
+    run(&["provider", "remove", "@bcv"]);
+    run(&["balance", "external", "--convert", "@bcv"])
+        .stdout(predicate::str::contains("via\t@bcv\n"));
+}
+
+#[test]
+fn confirm_over_forces_the_prompt_for_events_above_the_threshold() {
+    let home = tempfile::tempdir().expect("tempdir");
+    let run = |args: &[&str]| {
+        let mut cmd = bankero_cmd();
+        cmd.env("BANKERO_HOME", home.path());
+        cmd.args(args);
+        cmd
+    };
+
+    run(&["config", "set", "confirm_over", "100 usd"])
+        .assert()
+        .success();
+    run(&["config", "get", "confirm_over"])
+        .assert()
+        .success()
+        .stdout("100 USD\n");
+    run(&["config", "set", "confirm_over", "lots"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "Expected \"<amount> <commodity>\"",
+        ));
+    run(&[
+        "rate",
+        "set",
+        "@bcv",
+        "USD",
+        "VES",
+        "40",
+        "--as-of",
+        "2026-02-01T00:00:00Z",
+    ])
+    .assert()
+    .success();
+
+    // 2000 VES is 50 USD: written without a prompt.
+    run(&[
+        "buy",
+        "market",
+        "2000",
+        "VES",
+        "--from",
+        "assets:bank",
+        "@bcv",
+        "--effective-at",
+        "2026-02-10T00:00:00Z",
+    ])
+    .assert()
+    .success()
+    .stderr(predicate::str::contains("Proceed?").not());
+
+    // 8000 VES is 200 USD: previewed and declined, so nothing is written.
+    run(&[
+        "buy",
+        "furniture",
+        "8000",
+        "VES",
+        "--from",
+        "assets:bank",
+        "@bcv",
+        "--effective-at",
+        "2026-02-11T00:00:00Z",
+    ])
+    .write_stdin("n\n")
+    .assert()
+    .success()
+    .stderr(predicate::str::contains(
+        "8000 VES is worth 200 USD, over confirm_over (100 USD).",
+    ))
+    .stderr(predicate::str::contains("Transaction value: 200 USD."))
+    .stderr(predicate::str::contains("Proceed? [Y/n]"));
+
+    // Same-commodity amounts compare directly.
+    run(&[
+        "buy",
+        "laptop",
+        "150",
+        "USD",
+        "--from",
+        "assets:usd",
+        "--effective-at",
+        "2026-02-12T00:00:00Z",
+    ])
+    .write_stdin("y\n")
+    .assert()
+    .success()
+    .stderr(predicate::str::contains("Proceed? [Y/n]"));
+
+    // Without a provider, the latest stored rate for the pair still applies.
+    run(&[
+        "buy",
+        "sofa",
+        "10000",
+        "VES",
+        "--from",
+        "assets:bank",
+        "--effective-at",
+        "2026-02-13T00:00:00Z",
+    ])
+    .write_stdin("n\n")
+    .assert()
+    .success()
+    .stderr(predicate::str::contains(
+        "10000 VES is worth 250 USD, over confirm_over (100 USD).",
+    ))
+    .stderr(predicate::str::contains("Proceed? [Y/n]"));
+
+    // With no rate to compare at all, the prompt is forced rather than skipped.
+    run(&[
+        "buy",
+        "lamp",
+        "30",
+        "EUR",
+        "--from",
+        "assets:eur",
+        "--effective-at",
+        "2026-02-14T00:00:00Z",
+    ])
+    .write_stdin("n\n")
+    .assert()
+    .success()
+    .stderr(predicate::str::contains(
+        "No EUR/USD rate to compare 30 EUR with confirm_over (100 USD).",
+    ))
+    .stderr(predicate::str::contains("Proceed? [Y/n]"));
+
+    run(&["balance", "assets"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("assets:bank\tVES\t-2000"))
+        .stdout(predicate::str::contains("assets:usd\tUSD\t-150"))
+        .stdout(predicate::str::contains("assets:eur").not());
+}
+
+#[test]
+fn business_day_providers_fall_back_to_the_last_business_day_rate() {
+    let home = tempfile::tempdir().expect("tempdir");
+    let run = |args: &[&str]| {
+        let mut cmd = bankero_cmd();
+        cmd.env("BANKERO_HOME", home.path());
+        cmd.args(args);
+        cmd.assert().success()
+    };
+
+    run(&[
+        "provider",
+        "add",
+        "@bcv",
+        "--kind",
+        "official",
+        "--business-days",
+        "--holiday",
+        "2026-10-12",
+    ]);
+    run(&["provider", "list"]).stdout(predicate::str::contains("business days, 1 holiday(s)"));
+    // Adding it again only adds what is given.
+    run(&[
+        "provider",
+        "add",
+        "@bcv",
+        "--description",
+        "Banco Central",
+        "--holiday",
+        "2026-12-25",
+    ]);
+    run(&["provider", "list"]).stdout(
+        predicate::str::contains("| official | Banco Central |")
+            .and(predicate::str::contains("business days, 2 holiday(s)")),
+    );
+    // Friday's rate, then a stray point stored on Saturday.
+    run(&[
+        "rate",
+        "set",
+        "@bcv",
+        "USD",
+        "VES",
+        "40",
+        "--as-of",
+        "2026-10-09T12:00:00Z",
+    ]);
+    run(&[
+        "rate",
+        "set",
+        "@bcv",
+        "USD",
+        "VES",
+        "45",
+        "--as-of",
+        "2026-10-10T12:00:00Z",
+    ]);
+
+    // Monday the 12th is a holiday, so the whole weekend before it is skipped too.
+    let out = run(&[
+        "move",
+        "100",
+        "USD",
+        "--from",
+        "assets:usd",
+        "--to",
+        "assets:ves",
+        "VES",
+        "@bcv",
+        "--effective-at",
+        "2026-10-12T15:00:00Z",
+    ])
+    .stderr(predicate::str::contains(
+        "@bcv does not publish on 2026-10-12 (holiday); using the rate from 2026-10-09",
+    ));
+    let stdout = String::from_utf8_lossy(&out.get_output().stdout).to_string();
+    let id = stdout
+        .split_whitespace()
+        .nth(2)
+        .expect("event id")
+        .to_string();
+
+    run(&["balance", "assets:ves"]).stdout(predicate::str::contains("assets:ves\tVES\t4000"));
+    run(&["show", &id]).stdout(predicate::str::contains(
+        "fallback:     2026-10-12 was a holiday; used the 2026-10-09 rate",
+    ));
+
+    // A rate given on the command line is not a fallback.
+    let out = run(&[
+        "move",
+        "1",
+        "USD",
+        "--from",
+        "assets:usd",
+        "--to",
+        "assets:ves",
+        "VES",
+        "@bcv:42",
+        "--effective-at",
+        "2026-10-12T16:00:00Z",
+    ])
+    .stderr(predicate::str::contains("does not publish").not());
+    let stdout = String::from_utf8_lossy(&out.get_output().stdout).to_string();
+    let id = stdout.split_whitespace().nth(2).expect("event id");
+    run(&["show", id]).stdout(predicate::str::contains("fallback:").not());
+    run(&["balance", "assets:ves"]).stdout(predicate::str::contains("assets:ves\tVES\t4042"));
+
+    // Business days resolve normally.
+    run(&[
+        "rate",
+        "set",
+        "@bcv",
+        "USD",
+        "VES",
+        "41",
+        "--as-of",
+        "2026-10-13T12:00:00Z",
+    ]);
+    run(&[
+        "move",
+        "100",
+        "USD",
+        "--from",
+        "assets:usd",
+        "--to",
+        "assets:ves",
+        "VES",
+        "@bcv",
+        "--effective-at",
+        "2026-10-13T15:00:00Z",
+    ])
+    .stderr(predicate::str::contains("does not publish").not());
+    run(&["balance", "assets:ves"]).stdout(predicate::str::contains("assets:ves\tVES\t8142"));
+}
+
+#[test]
+fn dry_run_prints_the_resolved_event_and_writes_nothing() {
+    let home = tempfile::tempdir().expect("tempdir");
+    let run = |args: &[&str]| {
+        let mut cmd = bankero_cmd();
+        cmd.env("BANKERO_HOME", home.path());
+        cmd.args(args);
+        cmd
+    };
+    for (provider, rate) in [("@bcv", "45.2"), ("@binance", "50")] {
+        run(&[
+            "rate",
+            "set",
+            provider,
+            "USD",
+            "VES",
+            rate,
+            "--as-of",
+            "2026-02-25T12:00:00Z",
+        ])
+        .assert()
+        .success();
+    }
+
+    // No prompt and no stdin: the preview alone, ready for scripts.
+    run(&[
+        "buy",
+        "external:farmatodo",
+        "840",
+        "VES",
+        "--from",
+        "assets:mercantil",
+        "@bcv",
+        "-b",
+        "@binance",
+        "--dry-run",
+        "--effective-at",
+        "2026-02-25T12:00:00Z",
+    ])
+    .assert()
+    .success()
+    .stderr(predicate::str::contains("Transaction value:"))
+    .stderr(predicate::str::contains("Proceed?").not())
+    .stdout(predicate::str::contains("assets:mercantil\tVES\t-840"))
+    .stdout(predicate::str::contains(
+        "rate:         @bcv VES per USD = 45.2",
+    ))
+    .stdout(predicate::str::contains("basis:        fixed 16.80 USD"))
+    .stdout(predicate::str::contains("Dry run: nothing was written."))
+    .stdout(predicate::str::contains("Wrote event").not());
+
+    run(&["report"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("(no events)"));
+}
+
+#[test]
+fn provider_aliases_join_the_old_and_new_names_into_one_series() {
+    let home = tempfile::tempdir().expect("tempdir");
+    let run = |args: &[&str]| {
+        let mut cmd = bankero_cmd();
+        cmd.env("BANKERO_HOME", home.path());
+        cmd.args(args);
+        cmd.assert()
+    };
+    run(&[
+        "rate",
+        "set",
+        "@dolar",
+        "USD",
+        "VES",
+        "36",
+        "--as-of",
+        "2026-01-10T12:00:00Z",
+    ])
+    .success();
+    run(&[
+        "rate",
+        "set",
+        "@binance",
+        "USD",
+        "VES",
+        "40",
+        "--as-of",
+        "2026-02-10T12:00:00Z",
+    ])
+    .success();
+    let january = [
+        "rate",
+        "get",
+        "@binance",
+        "USD",
+        "VES",
+        "--as-of",
+        "2026-01-20",
+    ];
+    run(&january).failure();
+
+    run(&["provider", "alias", "@dolar", "@binance"]).success();
+    run(&["provider", "list"])
+        .success()
+        .stdout(predicate::str::contains("@dolar -> @binance"));
+    run(&january)
+        .success()
+        .stdout(predicate::str::contains("= 36"));
+    run(&[
+        "rate",
+        "get",
+        "@dolar",
+        "USD",
+        "VES",
+        "--as-of",
+        "2026-02-20",
+    ])
+    .success()
+    .stdout(predicate::str::contains("= 40"));
+    // Listing either name shows the whole series, with where each rate came from.
+    run(&["rate", "list", "@binance", "USD", "VES", "--verbose"])
+        .success()
+        .stdout(
+            predicate::str::contains("2026-01-10T12:00:00+00:00")
+                .and(predicate::str::contains("2026-02-10T12:00:00+00:00"))
+                .and(predicate::str::contains("unknown").not()),
+        );
+    // The alias takes the canonical provider's registration.
+    run(&[
+        "provider",
+        "add",
+        "@binance",
+        "--kind",
+        "exchange",
+        "--business-days",
+    ])
+    .success();
+    run(&[
+        "rate",
+        "set",
+        "@binance",
+        "USD",
+        "VES",
+        "41",
+        "--as-of",
+        "2026-02-13T12:00:00Z",
+    ])
+    .success();
+    bankero_cmd()
+        .env("BANKERO_HOME", home.path())
+        .args([
+            "buy",
+            "external:farmatodo",
+            "410",
+            "VES",
+            "--from",
+            "assets:bank",
+            "@dolar",
+            "--confirm",
+            "--effective-at",
+            "2026-02-14T12:00:00Z",
+        ])
+        .write_stdin("y\n")
+        .assert()
+        .success()
+        .stderr(
+            predicate::str::contains("@dolar (exchange)").and(predicate::str::contains(
+                "does not publish on 2026-02-14 (weekend)",
+            )),
+        );
+
+    // Old entries priced under the retired name resolve from the same series.
+    bankero_cmd()
+        .env("BANKERO_HOME", home.path())
+        .args([
+            "buy",
+            "external:farmatodo",
+            "3600",
+            "VES",
+            "--from",
+            "assets:bank",
+            "@dolar",
+            "--confirm",
+            "--effective-at",
+            "2026-01-20T12:00:00Z",
+        ])
+        .write_stdin("y\n")
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("Transaction value: 100 USD"));
+
+    run(&["provider", "alias", "@binance", "@dolar"])
+        .failure()
+        .stderr(predicate::str::contains(
+            "@dolar is already an alias of @binance",
+        ));
+    run(&["provider", "unalias", "@dolar"]).success();
+    run(&january).failure();
+}
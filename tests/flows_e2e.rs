@@ -319,3 +319,3865 @@ fn report_filters_by_range_account_and_commodity() {
     assert!(out_comm.contains("\tmove\t"));
     assert!(!out_comm.contains("\tdeposit\t"));
 }
+
+#[test]
+fn wallet_summarizes_holdings_with_rates_total_and_30_day_change() {
+    let home = tempfile::tempdir().expect("tempdir");
+
+    for (amount, commodity) in [("100", "USDT"), ("0.01", "BTC")] {
+        run_ok(
+            &home,
+            &[
+                "deposit",
+                amount,
+                commodity,
+                "--from",
+                "income:trading",
+                "--to",
+                "assets:binance:spot",
+                "--effective-at",
+                "2026-02-01T00:00:00Z",
+            ],
+        );
+    }
+    run_ok(
+        &home,
+        &[
+            "deposit",
+            "50",
+            "USD",
+            "--from",
+            "income:salary",
+            "--to",
+            "assets:bank",
+            "--effective-at",
+            "2026-02-01T00:00:00Z",
+        ],
+    );
+
+    run_ok(
+        &home,
+        &[
+            "rate",
+            "set",
+            "@binance",
+            "BTC",
+            "USDT",
+            "50000",
+            "--as-of",
+            "2026-02-01T00:00:00Z",
+        ],
+    );
+    run_ok(
+        &home,
+        &[
+            "rate",
+            "set",
+            "@binance",
+            "BTC",
+            "USDT",
+            "60000",
+            "--as-of",
+            "2026-03-03T00:00:00Z",
+        ],
+    );
+
+    let out = run_ok_out(
+        &home,
+        &[
+            "wallet",
+            "assets:binance",
+            "@binance",
+            "--in",
+            "USDT",
+            "--as-of",
+            "2026-03-03T12:00:00Z",
+        ],
+    );
+    assert!(
+        out.contains("BTC\t0.01\t60000\t2026-03-03T00:00:00+00:00\t600.00"),
+        "wallet output: {out}"
+    );
+    assert!(out.contains("USDT\t100\t1\t-\t100"), "wallet output: {out}");
+    assert!(!out.contains("USD\t50"), "wallet output: {out}");
+    assert!(out.contains("total\tUSDT\t700.00"), "wallet output: {out}");
+    assert!(
+        out.contains("change_30d\tUSDT\t100.00\t16.67%"),
+        "wallet output: {out}"
+    );
+
+    // Without a token the venue in the account names the provider.
+    let default_out = run_ok_out(
+        &home,
+        &[
+            "wallet",
+            "assets:binance:spot",
+            "--in",
+            "USDT",
+            "--as-of",
+            "2026-03-03T12:00:00Z",
+        ],
+    );
+    assert_eq!(default_out, out, "wallet output: {default_out}");
+}
+
+#[test]
+fn rate_usage_groups_events_by_the_stored_rate_they_relied_on() {
+    let home = tempfile::tempdir().expect("tempdir");
+
+    for (rate, as_of) in [
+        ("45.2", "2026-02-01T00:00:00Z"),
+        ("46", "2026-02-15T00:00:00Z"),
+    ] {
+        run_ok(
+            &home,
+            &["rate", "set", "@bcv", "USD", "VES", rate, "--as-of", as_of],
+        );
+    }
+
+    let move_at = |effective_at: &str, provider: &str| {
+        run_ok(
+            &home,
+            &[
+                "move",
+                "10",
+                "USD",
+                "--from",
+                "assets:usd",
+                "--to",
+                "assets:ves",
+                "VES",
+                provider,
+                "--effective-at",
+                effective_at,
+            ],
+        );
+    };
+    move_at("2026-02-03T10:00:00Z", "@bcv");
+    move_at("2026-02-05T10:00:00Z", "@bcv:50");
+    move_at("2026-02-20T10:00:00Z", "@bcv");
+    move_at("2026-03-02T10:00:00Z", "@bcv");
+
+    // Split in VES on a USD buy resolves VES per USD from the store.
+    run_ok(
+        &home,
+        &[
+            "buy",
+            "20",
+            "USD",
+            "--from",
+            "assets:cash",
+            "--to",
+            "expenses:local:452:VES",
+            "--to",
+            "expenses:import:10",
+            "--effective-at",
+            "2026-02-10T12:00:00Z",
+            "@bcv",
+        ],
+    );
+
+    let out = run_ok_out(&home, &["rate", "usage", "@bcv", "--month", "2026-02"]);
+    let first = out
+        .find("@bcv VES per USD = 45.2 (as of 2026-02-01T00:00:00+00:00): 2 event(s)")
+        .unwrap_or_else(|| panic!("usage: {out}"));
+    let second = out
+        .find("@bcv VES per USD = 46 (as of 2026-02-15T00:00:00+00:00): 1 event(s)")
+        .unwrap_or_else(|| panic!("usage: {out}"));
+    assert!(first < second, "usage: {out}");
+    assert!(
+        out.contains("@bcv override VES per USD = 50: 1 event(s)"),
+        "usage: {out}"
+    );
+    assert!(out.contains("\tsplit expenses:local"), "usage: {out}");
+    assert!(!out.contains("2026-03-02"), "usage: {out}");
+
+    // The last second of the month is inside --month.
+    move_at("2026-02-28T23:59:59Z", "@bcv");
+    // A rate backfilled before the buy does not re-attribute its split: the split
+    // keeps the row it was converted with.
+    run_ok(
+        &home,
+        &[
+            "rate",
+            "set",
+            "@bcv",
+            "USD",
+            "VES",
+            "45.5",
+            "--as-of",
+            "2026-02-08T00:00:00Z",
+        ],
+    );
+    let out = run_ok_out(&home, &["rate", "usage", "@bcv", "--month", "2026-02"]);
+    assert!(
+        out.contains("@bcv VES per USD = 46 (as of 2026-02-15T00:00:00+00:00): 2 event(s)"),
+        "usage: {out}"
+    );
+    let split = out
+        .lines()
+        .position(|l| l.ends_with("\tsplit expenses:local"))
+        .unwrap_or_else(|| panic!("usage: {out}"));
+    let header = out
+        .lines()
+        .take(split)
+        .filter(|l| !l.starts_with("  "))
+        .last();
+    assert_eq!(
+        header,
+        Some("@bcv VES per USD = 45.2 (as of 2026-02-01T00:00:00+00:00): 2 event(s)"),
+        "usage: {out}"
+    );
+
+    let none = run_ok_out(
+        &home,
+        &["rate", "usage", "@bcv", "EUR", "--month", "2026-02"],
+    );
+    assert!(none.contains("(no events used @bcv)"), "usage: {none}");
+}
+
+#[test]
+fn reprice_compensates_computed_moves_and_updates_the_stored_rate() {
+    let home = tempfile::tempdir().expect("tempdir");
+    let as_of = "2026-02-01T00:00:00Z";
+
+    run_ok(
+        &home,
+        &[
+            "rate", "set", "@bcv", "USD", "VES", "45.2", "--as-of", as_of,
+        ],
+    );
+    // Computed from the stored rate: 10 USD -> 452 VES.
+    run_ok(
+        &home,
+        &[
+            "move",
+            "10",
+            "USD",
+            "--from",
+            "assets:usd",
+            "--to",
+            "assets:ves",
+            "VES",
+            "@bcv",
+            "--effective-at",
+            "2026-02-03T10:00:00Z",
+        ],
+    );
+    // Explicit destination amount: not derived from the stored rate.
+    run_ok(
+        &home,
+        &[
+            "move",
+            "10",
+            "USD",
+            "--from",
+            "assets:usd",
+            "--to",
+            "assets:wallet",
+            "450",
+            "VES",
+            "--effective-at",
+            "2026-02-04T10:00:00Z",
+        ],
+    );
+    run_ok(
+        &home,
+        &[
+            "buy",
+            "20",
+            "USD",
+            "--from",
+            "assets:cash",
+            "--to",
+            "expenses:local:452:VES",
+            "--to",
+            "expenses:import:10",
+            "--effective-at",
+            "2026-02-10T12:00:00Z",
+            "@bcv",
+        ],
+    );
+
+    let reprice = [
+        "reprice",
+        "--provider",
+        "@bcv",
+        "--as-of",
+        as_of,
+        "--new-rate",
+        "46.1",
+    ];
+    let mut dry_run = reprice.to_vec();
+    dry_run.push("--dry-run");
+    let plan = run_ok_out(&home, &dry_run);
+    assert!(
+        plan.contains("Correcting @bcv VES per USD at 2026-02-01T00:00:00+00:00: 45.2 -> 46.1"),
+        "plan: {plan}"
+    );
+    assert!(plan.contains("\tassets:ves\t+9.0\tVES"), "plan: {plan}");
+    assert!(!plan.contains("assets:wallet"), "plan: {plan}");
+    assert!(plan.contains("Needs manual review"), "plan: {plan}");
+    assert!(plan.contains("\tsplit expenses:local"), "plan: {plan}");
+    assert!(plan.contains("Dry run: nothing written."), "plan: {plan}");
+    let out = run_ok_out(&home, &["balance"]);
+    assert!(out.contains("assets:ves\tVES\t452"), "balance: {out}");
+
+    let mut write = reprice.to_vec();
+    write.push("--yes");
+    let done = run_ok_out(&home, &write);
+    assert!(
+        done.contains("Wrote 1 adjustment event(s); @bcv VES per USD at 2026-02-01T00:00:00+00:00 is now 46.1."),
+        "reprice: {done}"
+    );
+
+    let out = run_ok_out(&home, &["balance"]);
+    assert!(out.contains("assets:ves\tVES\t461"), "balance: {out}");
+    // -452 from the buy split's conversion, -9 from the adjustment.
+    assert!(
+        out.contains("equity:conversion\tVES\t-461"),
+        "balance: {out}"
+    );
+    assert!(out.contains("assets:wallet\tVES\t450"), "balance: {out}");
+
+    let rate = run_ok_out(&home, &["rate", "get", "@bcv", "USD", "VES"]);
+    assert!(rate.contains("= 46.1 (as of 2026-02-01"), "rate: {rate}");
+
+    let report = run_ok_out(&home, &["report", "--month", "2026-02"]);
+    assert!(
+        report.contains("2026-02-03T10:00:00+00:00\treprice\t"),
+        "report: {report}"
+    );
+}
+
+#[test]
+fn entered_by_attribution_drives_approval_and_report_filters() {
+    let home = tempfile::tempdir().expect("tempdir");
+    let t = "2026-03-02T12:00:00Z";
+
+    let spend = |amount: &str, who: Option<&str>| {
+        let mut args = vec![
+            "move",
+            amount,
+            "USD",
+            "--from",
+            "assets:cash",
+            "--to",
+            "expenses:groceries",
+            "--effective-at",
+            t,
+        ];
+        if let Some(who) = who {
+            args.extend(["--entered-by", who]);
+        }
+        run_ok(&home, &args);
+    };
+    spend("10", None);
+    spend("20", Some("maria"));
+    spend("30", Some("maria"));
+
+    let report = run_ok_out(&home, &["report", "--entered-by", "maria"]);
+    assert_eq!(report.lines().count(), 2, "report: {report}");
+
+    let waiting = run_ok_out(&home, &["approve"]);
+    assert_eq!(waiting.lines().count(), 3, "approve list: {waiting}");
+    assert!(waiting.contains("\tmaria\t"), "approve list: {waiting}");
+    let first_id = waiting
+        .lines()
+        .nth(1)
+        .and_then(|l| l.split('\t').nth(2))
+        .expect("event id")
+        .to_string();
+
+    // Maria cannot approve her own entries.
+    bankero_cmd()
+        .env("BANKERO_HOME", home.path())
+        .args(["approve", &first_id, "--as", "maria"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("you entered it"));
+
+    let out = run_ok_out(&home, &["approve", &first_id]);
+    assert!(out.contains("Approved 1 event(s)"), "approve: {out}");
+
+    let unapproved = run_ok_out(&home, &["report", "--unapproved"]);
+    assert_eq!(unapproved.lines().count(), 1, "report: {unapproved}");
+    assert!(!unapproved.contains(&first_id), "report: {unapproved}");
+
+    run_ok(&home, &["approve", "--all"]);
+    let out = run_ok_out(&home, &["approve"]);
+    assert!(
+        out.contains("Nothing waiting for approval"),
+        "approve: {out}"
+    );
+}
+
+#[test]
+fn hard_limit_blocks_events_unless_overridden_and_logs_overrides() {
+    let home = tempfile::tempdir().expect("tempdir");
+    let t = "2026-03-02T12:00:00Z";
+
+    run_ok(
+        &home,
+        &["limit", "set", "expenses:gambling", "0", "USD", "--hard"],
+    );
+    run_ok(&home, &["limit", "set", "expenses:food", "50", "USD"]);
+
+    let spend = |amount: &str, to: &str| {
+        let mut cmd = bankero_cmd();
+        cmd.env("BANKERO_HOME", home.path()).args([
+            "move",
+            amount,
+            "USD",
+            "--from",
+            "assets:cash",
+            "--to",
+            to,
+            "--effective-at",
+            t,
+        ]);
+        cmd
+    };
+
+    spend("20", "expenses:gambling:poker")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Blocked by hard limit"));
+    let report = run_ok_out(&home, &["report", "--account", "expenses:gambling"]);
+    assert!(report.contains("(no events)"), "report: {report}");
+
+    spend("20", "expenses:gambling:poker")
+        .arg("--override")
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("This override is logged"));
+
+    // Soft limits only warn.
+    spend("80", "expenses:food")
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("Warning: over limit"));
+
+    let log = run_ok_out(&home, &["limit", "overrides"]);
+    assert!(
+        log.contains("\texpenses:gambling\t0 USD\t20"),
+        "overrides: {log}"
+    );
+
+    let list = run_ok_out(&home, &["limit", "list", "--month", "2026-03"]);
+    assert!(
+        list.contains("expenses:food\tUSD\t50\tsoft\t80\t-30"),
+        "list: {list}"
+    );
+    assert!(
+        list.contains("expenses:gambling\tUSD\t0\thard\t20\t-20"),
+        "list: {list}"
+    );
+}
+
+#[test]
+fn notify_digest_reports_actionable_items_and_only_new_ones_since_last_run() {
+    let home = tempfile::tempdir().expect("tempdir");
+    let t = "2026-03-10T12:00:00Z";
+    let as_of = "2026-03-31T12:00:00Z";
+
+    run_ok(
+        &home,
+        &[
+            "budget",
+            "create",
+            "Food",
+            "100",
+            "USD",
+            "--month",
+            "2026-03",
+            "--category",
+            "expenses:food",
+        ],
+    );
+    run_ok(
+        &home,
+        &[
+            "buy",
+            "external:market",
+            "130",
+            "USD",
+            "--from",
+            "assets:bank",
+            "--category",
+            "expenses:food",
+            "--effective-at",
+            t,
+        ],
+    );
+    for name in ["Bike", "Trip"] {
+        run_ok(
+            &home,
+            &[
+                "piggy",
+                "create",
+                name,
+                "200",
+                "USD",
+                "--from",
+                "assets:bank",
+            ],
+        );
+    }
+    run_ok(&home, &["piggy", "fund", "Bike", "200"]);
+    run_ok(
+        &home,
+        &[
+            "task",
+            "create",
+            "interest-loan",
+            "--account",
+            "liabilities:loan",
+            "--rate",
+            "12%",
+            "--monthly",
+            "--start",
+            "2026-03-31T00:00:00Z",
+        ],
+    );
+
+    let entry_by_maria = |amount: &str| {
+        run_ok(
+            &home,
+            &[
+                "buy",
+                "external:kiosk",
+                amount,
+                "USD",
+                "--from",
+                "assets:bank",
+                "--entered-by",
+                "maria",
+                "--effective-at",
+                t,
+            ],
+        );
+    };
+    entry_by_maria("5");
+
+    let out = run_ok_out(&home, &["notify", "--since", "last-run", "--as-of", as_of]);
+    assert!(out.contains("Bankero digest (4 item(s)):"), "digest: {out}");
+    assert!(
+        out.contains("Food 2026-03: 130 of 100 USD (over by 30)"),
+        "digest: {out}"
+    );
+    assert!(out.contains("Bike: 200 USD reached"), "digest: {out}");
+    assert!(
+        out.contains("interest-loan: due 2026-03-31"),
+        "digest: {out}"
+    );
+
+    // Nothing changed: the quiet digest is empty.
+    let out = run_ok_out(
+        &home,
+        &["notify", "--since", "last-run", "--as-of", as_of, "--quiet"],
+    );
+    assert!(out.is_empty(), "digest: {out}");
+
+    run_ok(&home, &["piggy", "fund", "Trip", "200"]);
+    entry_by_maria("7");
+    let out = run_ok_out(&home, &["notify", "--since", "last-run", "--as-of", as_of]);
+    assert!(out.contains("Trip: 200 USD reached"), "digest: {out}");
+    assert!(!out.contains("Bike"), "digest: {out}");
+    assert!(!out.contains("Food"), "digest: {out}");
+    assert!(!out.contains("interest-loan"), "digest: {out}");
+    assert!(
+        out.contains("Waiting for approval:\n  1 event(s)"),
+        "digest: {out}"
+    );
+}
+
+#[test]
+fn snapshot_compacts_old_events_and_archive_stays_replayable() {
+    let home = tempfile::tempdir().expect("tempdir");
+    let peer = tempfile::tempdir().expect("tempdir");
+    let shared = tempfile::tempdir().expect("tempdir");
+    let dir = shared.path().to_string_lossy().to_string();
+
+    for (amount, at) in [
+        ("100", "2024-03-01T12:00:00Z"),
+        ("50", "2024-07-01T12:00:00Z"),
+        ("25", "2025-02-01T12:00:00Z"),
+    ] {
+        run_ok(
+            &home,
+            &[
+                "deposit",
+                amount,
+                "USD",
+                "--from",
+                "income:salary",
+                "--to",
+                "assets:bank",
+                "--effective-at",
+                at,
+            ],
+        );
+    }
+    // The peer receives the raw 2024 events before they are compacted.
+    run_ok(&home, &["sync", "--dir", &dir, "now"]);
+    run_ok(&peer, &["sync", "--dir", &dir, "now"]);
+
+    let before = run_ok_out(&home, &["balance"]);
+    let out = run_ok_out(
+        &home,
+        &["snapshot", "create", "--before", "2025-01-01", "--yes"],
+    );
+    assert!(out.contains("archived 2 event(s)"), "snapshot: {out}");
+    assert_eq!(run_ok_out(&home, &["balance"]), before);
+
+    let report = run_ok_out(&home, &["report"]);
+    assert_eq!(report.lines().count(), 2, "report: {report}");
+    assert!(report.contains("\tsnapshot\t"), "report: {report}");
+
+    let raw = run_ok_out(&home, &["report", "--include-archived"]);
+    assert_eq!(raw.lines().count(), 3, "report: {raw}");
+    assert!(!raw.contains("\tsnapshot\t"), "report: {raw}");
+    assert_eq!(
+        run_ok_out(&home, &["balance", "--include-archived"]),
+        before
+    );
+
+    // A device that joins after the snapshot still gets the archived history from it.
+    run_ok(&home, &["sync", "--dir", &dir, "now"]);
+    let late = tempfile::tempdir().expect("tempdir");
+    run_ok(&late, &["sync", "--dir", &dir, "now"]);
+    assert_eq!(run_ok_out(&late, &["report"]).lines().count(), 3);
+    assert_eq!(run_ok_out(&late, &["balance"]), before);
+
+    // Archived events coming back from the peer are not resurrected.
+    run_ok(&peer, &["sync", "--dir", &dir, "now"]);
+    run_ok(&home, &["sync", "--dir", &dir, "now"]);
+    assert_eq!(run_ok_out(&home, &["report"]).lines().count(), 2);
+    assert_eq!(run_ok_out(&home, &["balance"]), before);
+
+    let list = run_ok_out(&home, &["snapshot", "list"]);
+    assert!(
+        list.contains("\t2025-01-01T00:00:00+00:00\t2\t"),
+        "list: {list}"
+    );
+}
+
+#[test]
+fn snapshot_before_moves_back_to_the_start_of_its_month() {
+    let home = tempfile::tempdir().expect("tempdir");
+    for at in ["2025-02-20T12:00:00Z", "2025-03-02T12:00:00Z"] {
+        run_ok(
+            &home,
+            &[
+                "deposit",
+                "10",
+                "USD",
+                "--from",
+                "income:salary",
+                "--to",
+                "assets:bank",
+                "--effective-at",
+                at,
+            ],
+        );
+    }
+    let out = bankero_cmd()
+        .env("BANKERO_HOME", home.path())
+        .args(["snapshot", "create", "--before", "2025-03-15", "--yes"])
+        .assert()
+        .success()
+        .get_output()
+        .clone();
+    let stdout = String::from_utf8(out.stdout).expect("utf8 stdout");
+    let stderr = String::from_utf8(out.stderr).expect("utf8 stderr");
+    assert!(
+        stderr.contains("snapshotting before 2025-03-01"),
+        "stderr: {stderr}"
+    );
+    assert!(stdout.contains("archived 1 event(s)"), "snapshot: {stdout}");
+    let march = run_ok_out(&home, &["report", "--month", "2025-03"]);
+    assert_eq!(march.lines().count(), 1, "march: {march}");
+}
+
+#[test]
+fn snapshot_counts_only_current_versions_of_amended_and_voided_events() {
+    let home = tempfile::tempdir().expect("tempdir");
+    let deposit = |amount: &str| {
+        let out = run_ok_out(
+            &home,
+            &[
+                "deposit",
+                amount,
+                "USD",
+                "--from",
+                "income:salary",
+                "--to",
+                "assets:bank",
+                "--effective-at",
+                "2024-03-01T12:00:00Z",
+            ],
+        );
+        out.split_whitespace().nth(2).expect("event id").to_string()
+    };
+    let typo = deposit("96");
+    let mistake = deposit("500");
+    run_ok(&home, &["event", "amend", &typo[..8], "--amount", "56"]);
+    run_ok(&home, &["event", "void", &mistake[..8]]);
+
+    let before = run_ok_out(&home, &["balance", "assets:bank"]);
+    assert!(before.contains("assets:bank\tUSD\t56"), "{before}");
+    run_ok(
+        &home,
+        &["snapshot", "create", "--before", "2025-01-01", "--yes"],
+    );
+    assert_eq!(run_ok_out(&home, &["balance", "assets:bank"]), before);
+}
+
+#[test]
+fn snapshot_keeps_corrections_and_voids_across_the_cut_with_what_they_change() {
+    let home = tempfile::tempdir().expect("tempdir");
+    let deposit = |amount: &str, effective_at: &str| {
+        let out = run_ok_out(
+            &home,
+            &[
+                "deposit",
+                amount,
+                "USD",
+                "--from",
+                "income:salary",
+                "--to",
+                "assets:bank",
+                "--effective-at",
+                effective_at,
+            ],
+        );
+        out.split_whitespace().nth(2).expect("event id").to_string()
+    };
+    deposit("7", "2024-02-01T12:00:00Z");
+    let early = deposit("10", "2024-03-01T12:00:00Z");
+    let late = deposit("200", "2025-02-01T12:00:00Z");
+    let cancelled = deposit("3000", "2025-02-01T12:00:00Z");
+    // An amendment after the cut of an event before it, an amendment before the cut of
+    // an event after it, and a void before the cut of an event after it.
+    run_ok(
+        &home,
+        &[
+            "event",
+            "amend",
+            &early[..8],
+            "--amount",
+            "11",
+            "--effective-at",
+            "2025-02-02T12:00:00Z",
+        ],
+    );
+    run_ok(
+        &home,
+        &[
+            "event",
+            "amend",
+            &late[..8],
+            "--amount",
+            "220",
+            "--effective-at",
+            "2024-06-01T12:00:00Z",
+        ],
+    );
+    run_ok(
+        &home,
+        &[
+            "event",
+            "void",
+            &cancelled[..8],
+            "--effective-at",
+            "2024-06-01T12:00:00Z",
+        ],
+    );
+
+    let before = run_ok_out(&home, &["balance", "assets:bank"]);
+    assert!(before.contains("assets:bank\tUSD\t238"), "{before}");
+    let out = run_ok_out(
+        &home,
+        &["snapshot", "create", "--before", "2025-01-01", "--yes"],
+    );
+    assert!(out.contains("archived 1 event(s)"), "{out}");
+    assert!(out.contains("3 event(s) before"), "{out}");
+    assert_eq!(run_ok_out(&home, &["balance", "assets:bank"]), before);
+    assert_eq!(
+        run_ok_out(&home, &["balance", "assets:bank", "--include-archived"]),
+        before
+    );
+
+    // A second snapshot past every event still leaves the balance alone.
+    run_ok(
+        &home,
+        &["snapshot", "create", "--before", "2025-06-01", "--yes"],
+    );
+    assert_eq!(run_ok_out(&home, &["balance", "assets:bank"]), before);
+}
+
+#[test]
+fn computed_amounts_follow_the_commodity_rounding_rule() {
+    let home = tempfile::tempdir().expect("tempdir");
+    run_ok(
+        &home,
+        &[
+            "rate",
+            "set",
+            "@bcv",
+            "USD",
+            "VES",
+            "45.2345",
+            "--as-of",
+            "2026-02-01T00:00:00Z",
+        ],
+    );
+    let move_ves = |effective_at: &str| {
+        run_ok(
+            &home,
+            &[
+                "move",
+                "10",
+                "USD",
+                "--from",
+                "assets:usd",
+                "--to",
+                "assets:ves",
+                "VES",
+                "@bcv",
+                "--effective-at",
+                effective_at,
+            ],
+        );
+    };
+
+    // Without a rule the exact product (452.345) fits in 8 places and is kept.
+    move_ves("2026-02-02T10:00:00Z");
+    run_ok(&home, &["commodity", "set", "VES", "--places", "2"]);
+    move_ves("2026-02-03T10:00:00Z");
+    run_ok(
+        &home,
+        &[
+            "commodity",
+            "set",
+            "VES",
+            "--places",
+            "2",
+            "--rounding",
+            "half-up",
+        ],
+    );
+    move_ves("2026-02-04T10:00:00Z");
+
+    let list = run_ok_out(&home, &["commodity", "list"]);
+    assert!(list.contains("VES\t2\thalf-up"), "list: {list}");
+
+    let report = |month_day: &str| {
+        run_ok_out(
+            &home,
+            &[
+                "report",
+                "--range",
+                &format!("2026-02-{month_day}..2026-02-{month_day}"),
+                "--output",
+                "csv",
+            ],
+        )
+    };
+    assert!(report("02").contains("assets:ves,VES,452.3450,"));
+    // Bankers rounding sends the half to the even digit, half-up away from zero.
+    assert!(report("03").contains("assets:ves,VES,452.34,"));
+    assert!(report("04").contains("assets:ves,VES,452.35,"));
+
+    // The raw rate is kept, so every move still traces back to the stored row.
+    let usage = run_ok_out(&home, &["rate", "usage", "@bcv"]);
+    assert!(
+        usage.contains("@bcv VES per USD = 45.2345 (as of 2026-02-01T00:00:00+00:00): 3 event(s)"),
+        "usage: {usage}"
+    );
+}
+
+#[test]
+fn rounding_residuals_post_to_the_rounding_account() {
+    let home = tempfile::tempdir().expect("tempdir");
+    run_ok(
+        &home,
+        &[
+            "rate",
+            "set",
+            "@bcv",
+            "USD",
+            "VES",
+            "45.2345",
+            "--as-of",
+            "2026-02-01T00:00:00Z",
+        ],
+    );
+    run_ok(
+        &home,
+        &[
+            "commodity",
+            "set",
+            "VES",
+            "--places",
+            "2",
+            "--rounding",
+            "half-up",
+        ],
+    );
+    run_ok(
+        &home,
+        &[
+            "move",
+            "10",
+            "USD",
+            "--from",
+            "assets:usd",
+            "--to",
+            "assets:ves",
+            "VES",
+            "@bcv",
+            "--effective-at",
+            "2026-02-02T10:00:00Z",
+        ],
+    );
+
+    // 10 USD * 45.2345 = 452.345 VES; the wallet gets 452.35 and the books keep the rest.
+    let out = run_ok_out(&home, &["balance"]);
+    assert!(out.contains("assets:ves\tVES\t452.35\n"), "balance: {out}");
+    assert!(
+        out.contains("equity:rounding\tVES\t-0.005\n"),
+        "balance: {out}"
+    );
+
+    // Converted buy splits that land within the cent of slack post the gap, too.
+    run_ok(
+        &home,
+        &["config", "set", "rounding_account", "equity:fx-rounding"],
+    );
+    run_ok(
+        &home,
+        &[
+            "buy",
+            "10",
+            "USD",
+            "--from",
+            "assets:usd",
+            "--to",
+            "expenses:local:452.35:VES",
+            "--effective-at",
+            "2026-02-03T10:00:00Z",
+            "@bcv",
+        ],
+    );
+    let out = run_ok_out(&home, &["balance", "equity"]);
+    assert!(
+        out.contains("equity:conversion\tUSD\t10.00011054\n"),
+        "balance: {out}"
+    );
+    assert!(
+        out.contains("equity:fx-rounding\tUSD\t-0.00011054\n"),
+        "balance: {out}"
+    );
+    let totals = out.split("(totals)\n").nth(1).expect("totals section");
+    assert!(totals.contains("USD\t10.00000000\n"), "balance: {out}");
+}
+
+#[test]
+fn sell_confirm_previews_realized_gain_from_recorded_basis() {
+    let home = tempfile::tempdir().expect("tempdir");
+    run_ok(
+        &home,
+        &[
+            "move",
+            "2000",
+            "USD",
+            "--from",
+            "assets:bank",
+            "--to",
+            "assets:btc",
+            "0.05",
+            "BTC",
+            "--basis",
+            "2000 USD",
+            "--effective-at",
+            "2026-01-10T12:00:00Z",
+        ],
+    );
+    run_ok(
+        &home,
+        &[
+            "move",
+            "1300",
+            "USD",
+            "--from",
+            "assets:bank",
+            "--to",
+            "assets:btc",
+            "0.05",
+            "BTC",
+            "--basis",
+            "1300 USD",
+            "--effective-at",
+            "2026-01-20T12:00:00Z",
+        ],
+    );
+
+    // Average cost: 3300 USD for 0.1 BTC, so 0.02 BTC carries 660 USD of basis.
+    let sell = |amount: &str, proceeds: &str, effective_at: &str| {
+        let mut cmd = bankero_cmd();
+        cmd.env("BANKERO_HOME", home.path());
+        cmd.args([
+            "sell",
+            amount,
+            "BTC",
+            "--from",
+            "assets:btc",
+            "--to",
+            "assets:bank",
+            proceeds,
+            "USD",
+            "--confirm",
+            "--effective-at",
+            effective_at,
+        ]);
+        cmd.write_stdin("y\n").assert().success()
+    };
+    sell("0.02", "800", "2026-02-01T12:00:00Z").stderr(predicate::str::contains(
+        "Realized gain: 140 USD (proceeds 800 - basis 660).",
+    ));
+    sell("0.02", "600", "2026-02-02T12:00:00Z").stderr(predicate::str::contains(
+        "Realized loss: -60 USD (proceeds 600 - basis 660).",
+    ));
+
+    // The gain is stored on the sell itself, for tax reports.
+    let db_path = home.path().join("data/workspaces/personal/bankero.sqlite3");
+    let conn = rusqlite::Connection::open(db_path).expect("open sqlite");
+    let payload_json: String = conn
+        .query_row(
+            "SELECT payload_json FROM events WHERE action = 'sell' ORDER BY effective_at ASC LIMIT 1",
+            [],
+            |row| row.get(0),
+        )
+        .expect("read sell payload");
+    let v: serde_json::Value = serde_json::from_str(&payload_json).expect("payload json");
+    let gain = &v["metadata"]["realized_gain"];
+    assert_eq!(gain["amount"], "140", "payload: {v}");
+    assert_eq!(gain["basis"], "660", "payload: {v}");
+    assert_eq!(gain["commodity"], "USD", "payload: {v}");
+
+    // A sell over confirm_over is confirmed without --confirm, and previewed the same way.
+    run_ok(&home, &["config", "set", "confirm_over", "100 USD"]);
+    let mut cmd = bankero_cmd();
+    cmd.env("BANKERO_HOME", home.path());
+    cmd.args([
+        "sell",
+        "0.02",
+        "BTC",
+        "--from",
+        "assets:btc",
+        "--to",
+        "assets:bank",
+        "700",
+        "USD",
+        "--effective-at",
+        "2026-02-03T12:00:00Z",
+    ]);
+    cmd.write_stdin("y\n")
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("over confirm_over (100 USD)"))
+        .stderr(predicate::str::contains(
+            "Realized gain: 40 USD (proceeds 700 - basis 660).",
+        ));
+}
+
+#[test]
+fn tax_report_totals_mapped_categories_per_bucket_for_the_year() {
+    let home = tempfile::tempdir().expect("tempdir");
+    run_ok(
+        &home,
+        &[
+            "rate",
+            "set",
+            "@bcv",
+            "USD",
+            "VES",
+            "45.2",
+            "--as-of",
+            "2026-01-01T00:00:00Z",
+        ],
+    );
+    let deposit = |amount: &str, at: &str| {
+        run_ok(
+            &home,
+            &[
+                "deposit",
+                amount,
+                "USD",
+                "--from",
+                "income:salary",
+                "--to",
+                "assets:bank",
+                "--effective-at",
+                at,
+            ],
+        );
+    };
+    deposit("1000", "2026-01-05T12:00:00Z");
+    deposit("1000", "2026-02-05T12:00:00Z");
+    deposit("900", "2025-12-05T12:00:00Z");
+
+    let buy = |args: &[&str]| {
+        let mut full = vec!["buy"];
+        full.extend_from_slice(args);
+        run_ok(&home, &full);
+    };
+    buy(&[
+        "50",
+        "USD",
+        "--payee",
+        "expenses:health:dentist",
+        "--from",
+        "assets:bank",
+        "--effective-at",
+        "2026-03-01T12:00:00Z",
+    ]);
+    // Paid to a payee, but categorized: the outgoing amount counts.
+    buy(&[
+        "30",
+        "USD",
+        "--payee",
+        "external:pharmacy",
+        "--from",
+        "assets:bank",
+        "--category",
+        "expenses:health",
+        "--effective-at",
+        "2026-03-02T12:00:00Z",
+    ]);
+    buy(&[
+        "452",
+        "VES",
+        "--payee",
+        "expenses:office",
+        "--from",
+        "assets:ves",
+        "--effective-at",
+        "2026-03-03T12:00:00Z",
+    ]);
+    buy(&[
+        "15",
+        "USD",
+        "--payee",
+        "expenses:food",
+        "--from",
+        "assets:bank",
+        "--effective-at",
+        "2026-03-04T12:00:00Z",
+    ]);
+
+    let map = home.path().join("tax-map.toml");
+    std::fs::write(
+        &map,
+        r#"
+[buckets.deductible]
+label = "Deductible expenses"
+categories = ["expenses:health"]
+
+[buckets.vat-reclaimable]
+categories = ["expenses:office"]
+
+[buckets.taxable-income]
+label = "Taxable income"
+categories = ["income"]
+"#,
+    )
+    .expect("write map");
+    let map = map.to_str().expect("utf8 path");
+
+    let mut cmd = bankero_cmd();
+    cmd.env("BANKERO_HOME", home.path());
+    cmd.args(["report", "tax", "--year", "2026", "--map", map]);
+    let out = cmd
+        .assert()
+        .success()
+        .stderr(predicate::str::contains(
+            "no rate to USD for VES (1 posting(s))",
+        ))
+        .get_output()
+        .stdout
+        .clone();
+    let out = String::from_utf8(out).expect("utf8");
+    assert!(out.contains("Tax report 2026 (USD)"), "tax: {out}");
+    assert!(
+        out.contains("deductible\tDeductible expenses\t80\n"),
+        "tax: {out}"
+    );
+    assert!(
+        out.contains("taxable-income\tTaxable income\t2000\n"),
+        "tax: {out}"
+    );
+    assert!(
+        out.contains("vat-reclaimable\tvat-reclaimable\t0\n"),
+        "tax: {out}"
+    );
+
+    let csv = run_ok_out(
+        &home,
+        &[
+            "report",
+            "tax",
+            "--year",
+            "2026",
+            "--map",
+            map,
+            "--convert",
+            "@bcv",
+            "--output",
+            "csv",
+        ],
+    );
+    assert!(
+        csv.starts_with("year,bucket,label,commodity,total"),
+        "csv: {csv}"
+    );
+    assert!(csv.contains("2026,vat-reclaimable,,USD,10"), "csv: {csv}");
+
+    std::fs::write(
+        home.path().join("bad.toml"),
+        "[buckets.a]\ncategories = [\"expenses:health\"]\n[buckets.b]\ncategories = [\"expenses:health\"]\n",
+    )
+    .expect("write bad map");
+    let mut cmd = bankero_cmd();
+    cmd.env("BANKERO_HOME", home.path());
+    cmd.args(["report", "tax", "--year", "2026", "--map"]);
+    cmd.arg(home.path().join("bad.toml"));
+    cmd.assert().failure().stderr(predicate::str::contains(
+        "Category expenses:health is mapped to both a and b",
+    ));
+}
+
+#[test]
+fn external_reference_is_stored_on_the_event_and_searchable() {
+    let home = tempfile::tempdir().expect("tempdir");
+    run_ok(
+        &home,
+        &[
+            "buy",
+            "120",
+            "USD",
+            "--payee",
+            "expenses:office",
+            "--from",
+            "assets:bank",
+            "--ref",
+            "INV-2026-0042",
+            "--effective-at",
+            "2026-03-01T12:00:00Z",
+        ],
+    );
+    run_ok(
+        &home,
+        &[
+            "deposit",
+            "500",
+            "USD",
+            "--from",
+            "income:freelance",
+            "--to",
+            "assets:bank",
+            "--ref",
+            "bank-tx-98765",
+            "--effective-at",
+            "2026-03-02T12:00:00Z",
+        ],
+    );
+    run_ok(
+        &home,
+        &[
+            "deposit",
+            "5",
+            "USD",
+            "--from",
+            "income:interest",
+            "--to",
+            "assets:bank",
+            "--effective-at",
+            "2026-03-03T12:00:00Z",
+        ],
+    );
+
+    let out = run_ok_out(&home, &["report", "--ref", "inv-2026"]);
+    assert_eq!(out.lines().count(), 1, "report: {out}");
+    assert!(out.contains("\tbuy\t"), "report: {out}");
+    assert!(out.contains("\tref:INV-2026-0042"), "report: {out}");
+
+    let out = run_ok_out(&home, &["report", "--month", "2026-03"]);
+    assert!(out.contains("\tref:bank-tx-98765"), "report: {out}");
+    assert_eq!(out.matches("ref:").count(), 2, "report: {out}");
+
+    // A payload field, not free-form metadata.
+    let db_path = home.path().join("data/workspaces/personal/bankero.sqlite3");
+    let conn = rusqlite::Connection::open(db_path).expect("open sqlite");
+    let payload_json: String = conn
+        .query_row(
+            "SELECT payload_json FROM events WHERE action = 'buy'",
+            [],
+            |row| row.get(0),
+        )
+        .expect("read buy payload");
+    let v: serde_json::Value = serde_json::from_str(&payload_json).expect("payload json");
+    assert_eq!(v["reference"], "INV-2026-0042", "payload: {v}");
+}
+
+#[test]
+fn show_revalue_replays_an_event_at_another_provider() {
+    let home = tempfile::tempdir().expect("tempdir");
+    for (provider, rate) in [("@bcv", "45.2"), ("@binance", "60")] {
+        run_ok(
+            &home,
+            &[
+                "rate",
+                "set",
+                provider,
+                "USD",
+                "VES",
+                rate,
+                "--as-of",
+                "2026-02-01T00:00:00Z",
+            ],
+        );
+    }
+    run_ok(
+        &home,
+        &[
+            "move",
+            "10",
+            "USD",
+            "--from",
+            "assets:usd",
+            "--to",
+            "assets:ves",
+            "VES",
+            "@bcv",
+            "--effective-at",
+            "2026-02-02T10:00:00Z",
+        ],
+    );
+
+    let db_path = home.path().join("data/workspaces/personal/bankero.sqlite3");
+    let conn = rusqlite::Connection::open(&db_path).expect("open sqlite");
+    let id: String = conn
+        .query_row("SELECT id FROM events WHERE action = 'move'", [], |r| {
+            r.get(0)
+        })
+        .expect("move id");
+
+    let out = run_ok_out(&home, &["show", &id]);
+    assert!(out.contains("assets:ves\tVES\t452"), "show: {out}");
+    assert!(
+        out.contains("rate:         @bcv VES per USD"),
+        "show: {out}"
+    );
+    assert!(!out.contains("revalued"), "show: {out}");
+
+    // A unique prefix is enough.
+    let out = run_ok_out(&home, &["show", &id[..8], "--revalue", "@binance"]);
+    assert!(
+        out.contains("revalued: @binance VES per USD = 60"),
+        "show: {out}"
+    );
+    assert!(
+        out.contains("10 USD -> 600 VES (recorded 452 VES, delta +148 VES)"),
+        "show: {out}"
+    );
+    assert!(
+        out.contains("452 VES -> 7.53333333 USD (recorded 10 USD, delta -2.46666667 USD)"),
+        "show: {out}"
+    );
+
+    bankero_cmd()
+        .env("HOME", home.path())
+        .args(["show", "00000000-dead"])
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains("No such event"));
+}
+
+#[test]
+fn report_rates_audits_where_each_cross_currency_rate_came_from() {
+    let home = tempfile::tempdir().expect("tempdir");
+    run_ok(
+        &home,
+        &[
+            "rate",
+            "set",
+            "@bcv",
+            "USD",
+            "VES",
+            "45.2",
+            "--as-of",
+            "2026-02-01T00:00:00Z",
+        ],
+    );
+    // Stored rate, hand-typed override, and a rate inferred from both amounts.
+    for (to, at) in [
+        (vec!["VES", "@bcv"], "2026-02-02T10:00:00Z"),
+        (vec!["VES", "@bcv:50"], "2026-02-03T10:00:00Z"),
+        (vec!["470", "VES"], "2026-02-04T10:00:00Z"),
+    ] {
+        let mut args = vec![
+            "move",
+            "10",
+            "USD",
+            "--from",
+            "assets:usd",
+            "--to",
+            "assets:ves",
+        ];
+        args.extend(to);
+        args.extend(["--effective-at", at]);
+        run_ok(&home, &args);
+    }
+    run_ok(
+        &home,
+        &[
+            "deposit",
+            "5",
+            "USD",
+            "--from",
+            "income:gift",
+            "--to",
+            "assets:usd",
+            "--effective-at",
+            "2026-02-05T10:00:00Z",
+        ],
+    );
+
+    let out = run_ok_out(&home, &["report", "--rates", "--month", "2026-02"]);
+    assert_eq!(out.lines().count(), 4, "rates: {out}");
+    assert!(
+        out.contains("VES per USD\t45.2\t@bcv\tstored"),
+        "rates: {out}"
+    );
+    assert!(
+        out.contains("VES per USD\t50\t@bcv\toverride"),
+        "rates: {out}"
+    );
+    assert!(
+        out.contains("VES per USD\t47\t@derived\tinferred"),
+        "rates: {out}"
+    );
+    assert!(
+        out.contains("rates: stored 1, override 1, inferred 1, missing 0; hand-typed 2 of 3 (67%)"),
+        "rates: {out}"
+    );
+
+    let csv = run_ok_out(&home, &["report", "--rates", "--output", "csv"]);
+    assert!(
+        csv.starts_with("date,effective_at,action,event_id,base,quote,rate,provider,source,via"),
+        "csv: {csv}"
+    );
+    assert!(
+        csv.contains(",USD,VES,50,@bcv,override,rate_context"),
+        "csv: {csv}"
+    );
+}
+
+#[test]
+fn share_report_redacts_accounts_and_never_exports_notes() {
+    let home = tempfile::tempdir().expect("tempdir");
+    run_ok(
+        &home,
+        &[
+            "deposit",
+            "2000",
+            "USD",
+            "--from",
+            "income:salary:acme",
+            "--to",
+            "assets:banesco:checking",
+            "--note",
+            "payroll ACME confidential",
+            "--effective-at",
+            "2026-02-01T09:00:00Z",
+        ],
+    );
+    run_ok(
+        &home,
+        &[
+            "buy",
+            "expenses:housing:rent",
+            "800",
+            "USD",
+            "--from",
+            "assets:banesco:checking",
+            "--category",
+            "expenses:housing",
+            "--ref",
+            "LEASE-7",
+            "--effective-at",
+            "2026-02-03T09:00:00Z",
+        ],
+    );
+    run_ok(
+        &home,
+        &[
+            "buy",
+            "expenses:food",
+            "30",
+            "USD",
+            "--from",
+            "assets:banesco:checking",
+            "--effective-at",
+            "2026-03-03T09:00:00Z",
+        ],
+    );
+
+    let out = run_ok_out(
+        &home,
+        &[
+            "share", "report", "--month", "2026-02", "--redact", "accounts",
+        ],
+    );
+    assert!(
+        out.starts_with("date\taction\taccount\tcategory\tcommodity\tamount\n"),
+        "share: {out}"
+    );
+    assert!(
+        out.contains("2026-02-01\tdeposit\tBank\t\tUSD\t2000"),
+        "share: {out}"
+    );
+    assert!(
+        out.contains("2026-02-01\tdeposit\tIncome\t\tUSD\t-2000"),
+        "share: {out}"
+    );
+    assert!(
+        out.contains("2026-02-03\tbuy\tExpenses\tExpenses\tUSD\t800"),
+        "share: {out}"
+    );
+    for hidden in [
+        "banesco",
+        "acme",
+        "ACME",
+        "LEASE-7",
+        "rent",
+        "housing",
+        "2026-03-03",
+    ] {
+        assert!(!out.contains(hidden), "share leaks {hidden}: {out}");
+    }
+
+    let csv = run_ok_out(
+        &home,
+        &[
+            "share",
+            "report",
+            "--month",
+            "2026-02",
+            "--redact",
+            "accounts,categories",
+            "--output",
+            "csv",
+        ],
+    );
+    assert!(
+        csv.contains("2026-02-03,buy,Expenses,,USD,800"),
+        "csv: {csv}"
+    );
+    assert!(!csv.contains("housing"), "csv: {csv}");
+
+    // Without --redact accounts stay, notes still do not.
+    let out = run_ok_out(&home, &["share", "report", "--month", "2026-02"]);
+    assert!(out.contains("assets:banesco:checking"), "share: {out}");
+    assert!(!out.contains("confidential"), "share: {out}");
+}
+
+#[test]
+fn sql_views_expose_postings_monthly_category_totals_and_latest_rates() {
+    let home = tempfile::tempdir().expect("tempdir");
+    for (rate, as_of) in [
+        ("36", "2026-03-01T00:00:00Z"),
+        ("40", "2026-03-10T00:00:00Z"),
+    ] {
+        run_ok(
+            &home,
+            &["rate", "set", "@bcv", "USD", "VES", rate, "--as-of", as_of],
+        );
+    }
+    run_ok(
+        &home,
+        &[
+            "deposit",
+            "500",
+            "USD",
+            "--to",
+            "assets:bank",
+            "--from",
+            "income:salary",
+            "--effective-at",
+            "2026-03-01T09:00:00Z",
+        ],
+    );
+    for (amount, at) in [
+        ("30.10", "2026-03-05T09:00:00Z"),
+        ("19.90", "2026-03-20T09:00:00Z"),
+    ] {
+        run_ok(
+            &home,
+            &[
+                "buy",
+                "expenses:food",
+                amount,
+                "USD",
+                "--from",
+                "assets:bank",
+                "--effective-at",
+                at,
+            ],
+        );
+    }
+    run_ok(
+        &home,
+        &[
+            "buy",
+            "external:market",
+            "12",
+            "USD",
+            "--from",
+            "assets:bank",
+            "--category",
+            "expenses:food",
+            "--effective-at",
+            "2026-04-02T09:00:00Z",
+        ],
+    );
+
+    let db_path = home.path().join("data/workspaces/personal/bankero.sqlite3");
+    let conn =
+        rusqlite::Connection::open_with_flags(db_path, rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY)
+            .expect("open sqlite read-only");
+
+    let (account, amount, month): (String, String, String) = conn
+        .query_row(
+            "SELECT account, amount, month FROM v_postings WHERE account = 'assets:bank' AND action = 'deposit'",
+            [],
+            |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?)),
+        )
+        .expect("deposit posting");
+    assert_eq!(
+        (account.as_str(), amount.as_str(), month.as_str()),
+        ("assets:bank", "500", "2026-03")
+    );
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT month, category, commodity, total, events FROM v_monthly_category_totals ORDER BY month, category",
+        )
+        .unwrap();
+    let rows: Vec<(String, String, String, f64, i64)> = stmt
+        .query_map([], |r| {
+            Ok((r.get(0)?, r.get(1)?, r.get(2)?, r.get(3)?, r.get(4)?))
+        })
+        .unwrap()
+        .map(Result::unwrap)
+        .collect();
+    assert_eq!(rows.len(), 3, "rows: {rows:?}");
+    assert_eq!(
+        (rows[0].0.as_str(), rows[0].1.as_str()),
+        ("2026-03", "expenses:food")
+    );
+    assert!(
+        (rows[0].3 - 50.0).abs() < 1e-9 && rows[0].4 == 2,
+        "rows: {rows:?}"
+    );
+    assert_eq!(rows[1].1, "income:salary");
+    assert!((rows[1].3 + 500.0).abs() < 1e-9);
+    assert_eq!(
+        (rows[2].0.as_str(), rows[2].1.as_str()),
+        ("2026-04", "expenses:food")
+    );
+    assert!((rows[2].3 - 12.0).abs() < 1e-9);
+
+    let (rate, as_of): (String, String) = conn
+        .query_row(
+            "SELECT rate, as_of FROM v_rates_latest WHERE provider = 'bcv' AND base = 'USD' AND quote = 'VES'",
+            [],
+            |r| Ok((r.get(0)?, r.get(1)?)),
+        )
+        .expect("latest rate");
+    assert_eq!(rate, "40");
+    assert!(as_of.starts_with("2026-03-10"), "as_of: {as_of}");
+
+    // The views are versioned, not rebuilt on every open.
+    let version: String = conn
+        .query_row(
+            "SELECT value FROM meta WHERE key = 'views_version'",
+            [],
+            |r| r.get(0),
+        )
+        .expect("views version");
+    assert_eq!(version, "2");
+}
+
+#[test]
+fn sql_views_count_only_the_current_version_of_amended_events() {
+    let home = tempfile::tempdir().expect("tempdir");
+    let out = run_ok_out(
+        &home,
+        &[
+            "buy",
+            "expenses:food",
+            "30",
+            "USD",
+            "--from",
+            "assets:bank",
+            "--effective-at",
+            "2026-03-05T09:00:00Z",
+        ],
+    );
+    let original = out.split_whitespace().nth(2).expect("event id").to_string();
+    run_ok(&home, &["event", "amend", &original[..8], "--amount", "40"]);
+
+    let db_path = home.path().join("data/workspaces/personal/bankero.sqlite3");
+    let conn =
+        rusqlite::Connection::open_with_flags(db_path, rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY)
+            .expect("open sqlite read-only");
+    let postings: i64 = conn
+        .query_row("SELECT COUNT(*) FROM v_postings", [], |r| r.get(0))
+        .expect("count postings");
+    assert_eq!(postings, 2);
+    let (total, events): (f64, i64) = conn
+        .query_row(
+            "SELECT total, events FROM v_monthly_category_totals WHERE category = 'expenses:food'",
+            [],
+            |r| Ok((r.get(0)?, r.get(1)?)),
+        )
+        .expect("food total");
+    assert!((total - 40.0).abs() < 1e-9, "total: {total}");
+    assert_eq!(events, 1);
+    let balance = run_ok_out(&home, &["balance", "expenses:food"]);
+    assert!(balance.contains("expenses:food\tUSD\t40"), "{balance}");
+}
+
+#[test]
+fn export_analytics_writes_parquet_per_month_and_refreshes_incrementally() {
+    use arrow_array::{Array, Decimal128Array, StringArray};
+    use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+
+    let read = |path: std::path::PathBuf| {
+        let file = std::fs::File::open(&path).unwrap_or_else(|_| panic!("{path:?}"));
+        ParquetRecordBatchReaderBuilder::try_new(file)
+            .unwrap()
+            .build()
+            .unwrap()
+            .map(Result::unwrap)
+            .collect::<Vec<_>>()
+    };
+
+    let home = tempfile::tempdir().expect("tempdir");
+    let out = home.path().join("analytics");
+    let out_s = out.to_str().unwrap();
+    run_ok(
+        &home,
+        &[
+            "rate",
+            "set",
+            "@bcv",
+            "USD",
+            "VES",
+            "36.5",
+            "--as-of",
+            "2026-03-01T00:00:00Z",
+        ],
+    );
+    run_ok(
+        &home,
+        &[
+            "deposit",
+            "500",
+            "USD",
+            "--to",
+            "assets:bank",
+            "--from",
+            "income:salary",
+            "--effective-at",
+            "2026-02-27T09:00:00Z",
+        ],
+    );
+    // Larger than `Decimal` can hold at 18 decimal places.
+    run_ok(
+        &home,
+        &[
+            "deposit",
+            "150000000000.5",
+            "VES",
+            "--to",
+            "assets:bank",
+            "--from",
+            "income:salary",
+            "--effective-at",
+            "2026-02-28T09:00:00Z",
+        ],
+    );
+    run_ok(
+        &home,
+        &[
+            "buy",
+            "expenses:food",
+            "12.345",
+            "USD",
+            "--from",
+            "assets:bank",
+            "--effective-at",
+            "2026-03-05T09:00:00Z",
+        ],
+    );
+
+    let first = run_ok_out(&home, &["export", "analytics", "--out", out_s]);
+    assert!(
+        first.contains("6 file(s) written, 0 unchanged"),
+        "first: {first}"
+    );
+    for name in [
+        "events/2026-02.parquet",
+        "events/2026-03.parquet",
+        "postings/2026-03.parquet",
+        "rates.parquet",
+        "budgets.parquet",
+        "manifest.json",
+    ] {
+        assert!(out.join(name).exists(), "missing {name}");
+    }
+
+    let batches = read(out.join("postings/2026-03.parquet"));
+    let batch = &batches[0];
+    assert_eq!(batch.num_rows(), 2);
+    let accounts = batch
+        .column_by_name("account")
+        .unwrap()
+        .as_any()
+        .downcast_ref::<StringArray>()
+        .unwrap();
+    let amounts = batch
+        .column_by_name("amount")
+        .unwrap()
+        .as_any()
+        .downcast_ref::<Decimal128Array>()
+        .unwrap();
+    let food = (0..batch.num_rows())
+        .find(|&i| accounts.value(i) == "expenses:food")
+        .expect("food posting");
+    assert_eq!(amounts.value_as_string(food), "12.345000000000000000");
+    let batches = read(out.join("postings/2026-02.parquet"));
+    let amounts = batches[0]
+        .column_by_name("amount")
+        .unwrap()
+        .as_any()
+        .downcast_ref::<Decimal128Array>()
+        .unwrap();
+    assert!(
+        (0..amounts.len()).any(|i| amounts.value_as_string(i) == "150000000000.500000000000000000"),
+        "large amount missing"
+    );
+    let rates = read(out.join("rates.parquet"));
+    assert_eq!(rates[0].num_rows(), 1);
+
+    let again = run_ok_out(&home, &["export", "analytics", "--out", out_s]);
+    assert!(
+        again.contains("0 file(s) written, 6 unchanged"),
+        "again: {again}"
+    );
+
+    run_ok(
+        &home,
+        &[
+            "buy",
+            "expenses:food",
+            "3",
+            "USD",
+            "--from",
+            "assets:bank",
+            "--effective-at",
+            "2026-03-20T09:00:00Z",
+        ],
+    );
+    let refreshed = run_ok_out(&home, &["export", "analytics", "--out", out_s]);
+    assert!(
+        refreshed.contains("wrote\tevents/2026-03.parquet")
+            && refreshed.contains("wrote\tpostings/2026-03.parquet")
+            && refreshed.contains("2 file(s) written, 4 unchanged"),
+        "refreshed: {refreshed}"
+    );
+    assert_eq!(read(out.join("events/2026-03.parquet"))[0].num_rows(), 2);
+}
+
+#[test]
+fn pending_move_parks_money_in_transit_until_settled() {
+    let home = tempfile::tempdir().expect("tempdir");
+    run_ok(
+        &home,
+        &[
+            "deposit",
+            "800",
+            "USD",
+            "--to",
+            "assets:chase",
+            "--from",
+            "income:salary",
+            "--effective-at",
+            "2026-03-01T09:00:00Z",
+        ],
+    );
+    let out = run_ok_out(
+        &home,
+        &[
+            "move",
+            "500",
+            "USD",
+            "--from",
+            "assets:chase",
+            "--to",
+            "assets:mercantil",
+            "--pending",
+            "--effective-at",
+            "2026-03-02T15:00:00Z",
+        ],
+    );
+    assert!(out.contains("In transit to assets:mercantil"), "out: {out}");
+
+    let bal = run_ok_out(&home, &["balance"]);
+    assert!(bal.contains("assets:chase\tUSD\t300"), "bal: {bal}");
+    assert!(bal.contains("assets:in-transit\tUSD\t500"), "bal: {bal}");
+    assert!(!bal.contains("assets:mercantil"), "bal: {bal}");
+
+    let listed = run_ok_out(&home, &["settle"]);
+    assert!(
+        listed.contains("500 USD\tassets:chase\tassets:mercantil"),
+        "listed: {listed}"
+    );
+    let move_id = listed
+        .lines()
+        .nth(1)
+        .and_then(|l| l.split('\t').nth(1))
+        .expect("event id")
+        .to_string();
+
+    bankero_cmd()
+        .env("BANKERO_HOME", home.path())
+        .args([
+            "settle",
+            &move_id[..8],
+            "--effective-at",
+            "2026-03-01T00:00:00Z",
+        ])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("before they were sent"));
+
+    // Settling is an ordinary write: the destination's hard limit applies.
+    run_ok(
+        &home,
+        &["limit", "set", "assets:mercantil", "100", "USD", "--hard"],
+    );
+    bankero_cmd()
+        .env("BANKERO_HOME", home.path())
+        .args([
+            "settle",
+            &move_id[..8],
+            "--effective-at",
+            "2026-03-04T10:00:00Z",
+        ])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Blocked by hard limit"));
+    run_ok(&home, &["limit", "remove", "assets:mercantil"]);
+
+    let settled = run_ok_out(
+        &home,
+        &[
+            "settle",
+            &move_id[..8],
+            "--effective-at",
+            "2026-03-04T10:00:00Z",
+        ],
+    );
+    assert!(
+        settled.contains("500 USD arrived in assets:mercantil after 1 day(s) in transit"),
+        "settled: {settled}"
+    );
+
+    let bal = run_ok_out(&home, &["balance"]);
+    assert!(bal.contains("assets:mercantil\tUSD\t500"), "bal: {bal}");
+    assert!(!bal.contains("assets:in-transit\tUSD\t500"), "bal: {bal}");
+    assert!(run_ok_out(&home, &["settle"]).contains("No transfers in transit."));
+
+    bankero_cmd()
+        .env("BANKERO_HOME", home.path())
+        .args(["settle", &move_id])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("already settled"));
+}
+
+#[test]
+fn interpolate_linear_values_holdings_between_sparse_rate_points() {
+    let home = tempfile::tempdir().expect("tempdir");
+    for (rate, as_of) in [
+        ("36", "2026-03-01T00:00:00Z"),
+        ("40", "2026-03-05T00:00:00Z"),
+    ] {
+        run_ok(
+            &home,
+            &["rate", "set", "@bcv", "USD", "VES", rate, "--as-of", as_of],
+        );
+    }
+    run_ok(
+        &home,
+        &[
+            "deposit",
+            "1000",
+            "VES",
+            "--to",
+            "assets:wallet",
+            "--from",
+            "income:gifts",
+            "--effective-at",
+            "2026-02-20T00:00:00Z",
+        ],
+    );
+    let mid = "2026-03-03T00:00:00Z";
+
+    let stepped = run_ok_out(
+        &home,
+        &["rate", "get", "@bcv", "USD", "VES", "--as-of", mid],
+    );
+    assert!(stepped.contains("= 36 (as of 2026-03-01"), "{stepped}");
+    let smooth = run_ok_out(
+        &home,
+        &[
+            "rate",
+            "get",
+            "@bcv",
+            "USD",
+            "VES",
+            "--as-of",
+            mid,
+            "--interpolate",
+            "linear",
+        ],
+    );
+    assert!(
+        smooth.contains("= 38 (interpolated at 2026-03-03T00:00:00+00:00 between 2026-03-01"),
+        "{smooth}"
+    );
+
+    let stepped = run_ok_out(
+        &home,
+        &[
+            "wallet",
+            "assets:wallet",
+            "@bcv",
+            "--in",
+            "USD",
+            "--as-of",
+            mid,
+        ],
+    );
+    assert!(stepped.contains("total\tUSD\t27.78"), "{stepped}");
+    let smooth = run_ok_out(
+        &home,
+        &[
+            "wallet",
+            "assets:wallet",
+            "@bcv",
+            "--in",
+            "USD",
+            "--as-of",
+            mid,
+            "--interpolate",
+            "linear",
+        ],
+    );
+    assert!(smooth.contains("total\tUSD\t26.32"), "{smooth}");
+
+    // Past the last stored point there is nothing to interpolate towards.
+    let after = run_ok_out(
+        &home,
+        &[
+            "rate",
+            "get",
+            "@bcv",
+            "USD",
+            "VES",
+            "--as-of",
+            "2026-03-09T00:00:00Z",
+            "--interpolate",
+            "linear",
+        ],
+    );
+    assert!(after.contains("= 40 (as of 2026-03-05"), "{after}");
+}
+
+#[test]
+fn meta_flags_are_stored_on_events_and_filter_reports() {
+    let home = tempfile::tempdir().expect("tempdir");
+    run_ok(
+        &home,
+        &[
+            "buy",
+            "hotel",
+            "300",
+            "EUR",
+            "--from",
+            "assets:card",
+            "--meta",
+            "trip=lisbon",
+            "--meta",
+            "order=HX-881",
+        ],
+    );
+    run_ok(
+        &home,
+        &[
+            "move",
+            "50",
+            "EUR",
+            "--from",
+            "assets:card",
+            "--to",
+            "assets:cash",
+            "--meta",
+            "trip=lisbon",
+        ],
+    );
+    run_ok(
+        &home,
+        &["buy", "market", "20", "EUR", "--from", "assets:card"],
+    );
+
+    let trip = run_ok_out(&home, &["report", "--meta", "trip=lisbon"]);
+    assert_eq!(trip.lines().count(), 2, "{trip}");
+    assert!(
+        trip.contains("\tbuy\t") && trip.contains("\tmove\t"),
+        "{trip}"
+    );
+
+    let order = run_ok_out(
+        &home,
+        &["report", "--meta", "trip=lisbon", "--meta", "order=HX-881"],
+    );
+    assert_eq!(order.lines().count(), 1, "{order}");
+
+    let none = run_ok_out(&home, &["report", "--meta", "trip=porto"]);
+    assert_eq!(none.trim(), "(no events)");
+
+    // Bankero's own keys can't be overwritten, and the flag needs KEY=VALUE.
+    for (meta, message) in [
+        ("entered_by=someone", "--meta key 'entered_by' is reserved"),
+        ("pending_to=assets:x", "--meta key 'pending_to' is reserved"),
+        ("lisbon", "Expected KEY=VALUE"),
+    ] {
+        let mut cmd = bankero_cmd();
+        cmd.env("BANKERO_HOME", home.path()).args([
+            "buy",
+            "snack",
+            "3",
+            "EUR",
+            "--from",
+            "assets:card",
+            "--meta",
+            meta,
+        ]);
+        cmd.assert()
+            .failure()
+            .stderr(predicate::str::contains(message));
+    }
+}
+
+#[test]
+fn withdraw_records_its_own_action_and_report_filters_by_action() {
+    let home = tempfile::tempdir().expect("tempdir");
+    run_ok(
+        &home,
+        &[
+            "deposit",
+            "1000",
+            "USD",
+            "--to",
+            "assets:bank",
+            "--from",
+            "income:salary",
+        ],
+    );
+    let out = run_ok_out(
+        &home,
+        &[
+            "withdraw",
+            "200",
+            "USD",
+            "--from",
+            "assets:bank",
+            "--to",
+            "assets:cash",
+            "-m",
+            "ATM",
+        ],
+    );
+    assert!(out.contains("Wrote event"), "{out}");
+
+    let balance = run_ok_out(&home, &["balance", "assets"]);
+    assert!(balance.contains("assets:bank\tUSD\t800"), "{balance}");
+    assert!(balance.contains("assets:cash\tUSD\t200"), "{balance}");
+
+    let withdrawals = run_ok_out(&home, &["report", "--action", "withdraw"]);
+    assert_eq!(withdrawals.lines().count(), 1, "{withdrawals}");
+    assert!(withdrawals.contains("\twithdraw\t"), "{withdrawals}");
+    let deposits = run_ok_out(&home, &["report", "--action", "deposit"]);
+    assert_eq!(deposits.lines().count(), 1, "{deposits}");
+    assert!(deposits.contains("\tdeposit\t"), "{deposits}");
+}
+
+#[test]
+fn refund_reverses_a_buy_in_parts_and_lowers_budget_actuals() {
+    let home = tempfile::tempdir().expect("tempdir");
+    run_ok(
+        &home,
+        &[
+            "budget",
+            "create",
+            "Food",
+            "300",
+            "USD",
+            "--month",
+            "2026-03",
+            "--category",
+            "expenses:food",
+        ],
+    );
+    let out = run_ok_out(
+        &home,
+        &[
+            "buy",
+            "Market",
+            "60",
+            "USD",
+            "--from",
+            "assets:bank",
+            "--category",
+            "expenses:food",
+            "--effective-at",
+            "2026-03-05T12:00:00Z",
+        ],
+    );
+    let buy_id = out.split_whitespace().nth(2).expect("event id").to_string();
+
+    let refund = |args: &[&str]| {
+        let mut cmd = bankero_cmd();
+        cmd.env("BANKERO_HOME", home.path());
+        cmd.args(["refund", &buy_id[..8]]);
+        cmd.args(args);
+        cmd.args(["--effective-at", "2026-03-08T12:00:00Z"]);
+        cmd.assert()
+    };
+    refund(&["--amount", "15", "-m", "one item returned"]).success();
+
+    let report = run_ok_out(&home, &["budget", "report", "--month", "2026-03"]);
+    assert!(
+        report.contains("2026-03\tFood\tUSD\t300\t45\t255"),
+        "{report}"
+    );
+    let bank = run_ok_out(&home, &["balance", "assets:bank"]);
+    assert!(bank.contains("assets:bank\tUSD\t-45"), "{bank}");
+
+    let refunds = run_ok_out(&home, &["report", "--action", "refund"]);
+    assert_eq!(refunds.lines().count(), 1, "{refunds}");
+
+    // Never more than was paid.
+    refund(&["--amount", "50"])
+        .failure()
+        .stderr(predicate::str::contains("only 45 USD of 60 USD is left"));
+
+    // Without --amount the rest comes back.
+    refund(&[]).success();
+    let report = run_ok_out(&home, &["budget", "report", "--month", "2026-03"]);
+    assert!(
+        report.contains("2026-03\tFood\tUSD\t300\t0\t300"),
+        "{report}"
+    );
+    let bank = run_ok_out(&home, &["balance", "assets:bank"]);
+    assert!(bank.contains("assets:bank\tUSD\t0"), "{bank}");
+    refund(&[])
+        .failure()
+        .stderr(predicate::str::contains("Nothing left to refund"));
+}
+
+#[test]
+fn strict_commodities_rejects_typos_until_the_commodity_is_declared() {
+    let home = tempfile::tempdir().expect("tempdir");
+    let deposit = |amount: &str, commodity: &str| {
+        let mut cmd = bankero_cmd();
+        cmd.env("BANKERO_HOME", home.path());
+        cmd.args([
+            "deposit",
+            amount,
+            commodity,
+            "--from",
+            "income:salary",
+            "--to",
+            "assets:bank",
+        ]);
+        cmd.assert()
+    };
+
+    // Off by default: a typo silently opens a new bucket.
+    deposit("5", "VEZ").success();
+
+    run_ok(&home, &["config", "set", "strict_commodities", "true"]);
+    // The reference commodity is always known.
+    deposit("100", "USD").success();
+    deposit("100", "uds")
+        .failure()
+        .stderr(predicate::str::contains("Unknown commodity UDS"))
+        .stderr(predicate::str::contains("Did you mean USD?"))
+        .stderr(predicate::str::contains("bankero commodity declare UDS"));
+    // Seen before (even by mistake) counts as known.
+    deposit("1", "VEZ").success();
+    deposit("3650", "VES")
+        .failure()
+        .stderr(predicate::str::contains("Did you mean VEZ?"));
+
+    run_ok(&home, &["commodity", "declare", "ves", "USDT"]);
+    deposit("3650", "VES").success();
+    let list = run_ok_out(&home, &["commodity", "list"]);
+    assert!(list.contains("VES\t-\t-"), "{list}");
+    assert!(list.contains("USDT\t-\t-"), "{list}");
+
+    let balance = run_ok_out(&home, &["balance", "assets:bank"]);
+    assert!(!balance.contains("UDS"), "{balance}");
+    assert!(balance.contains("assets:bank\tVES\t3650"), "{balance}");
+
+    // Imports and cash counts are checked too; a rejected import writes nothing.
+    let journal = home.path().join("typo.journal");
+    std::fs::write(
+        &journal,
+        "2026-01-05 * Coffee\n    expenses:coffee  3 USD\n    assets:bank\n\n2026-01-06 * Market\n    expenses:food  40 VEs\n    assets:bank\n\n2026-01-07 * Books\n    expenses:books  12 EUU\n    assets:bank\n",
+    )
+    .expect("write journal");
+    let mut cmd = bankero_cmd();
+    cmd.env("BANKERO_HOME", home.path());
+    cmd.args(["import", "ledger", journal.to_str().unwrap()]);
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("Unknown commodity EUU"));
+    let balance = run_ok_out(&home, &["balance"]);
+    assert!(!balance.contains("expenses:coffee"), "{balance}");
+    let mut cmd = bankero_cmd();
+    cmd.env("BANKERO_HOME", home.path());
+    cmd.args([
+        "cashcount",
+        "assets:cash",
+        "--denominations",
+        "10x2 UDS",
+        "--yes",
+    ]);
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("Unknown commodity UDS"));
+
+    run_ok(&home, &["config", "set", "strict_commodities", "false"]);
+    deposit("1", "XYZ").success();
+}
+
+#[test]
+fn event_void_cancels_an_event_in_balances_reports_and_budgets() {
+    let home = tempfile::tempdir().expect("tempdir");
+    run_ok(
+        &home,
+        &[
+            "budget",
+            "create",
+            "Food",
+            "300",
+            "USD",
+            "--month",
+            "2026-03",
+            "--category",
+            "expenses:food",
+        ],
+    );
+    let out = run_ok_out(
+        &home,
+        &[
+            "buy",
+            "expenses:food",
+            "500",
+            "USD",
+            "--from",
+            "assets:bank",
+            "--category",
+            "expenses:food",
+            "--effective-at",
+            "2026-03-05T12:00:00Z",
+        ],
+    );
+    let buy_id = out.split_whitespace().nth(2).expect("event id").to_string();
+    let report = run_ok_out(&home, &["budget", "report", "--month", "2026-03"]);
+    assert!(
+        report.contains("2026-03\tFood\tUSD\t300\t500\t-200"),
+        "{report}"
+    );
+
+    let out = run_ok_out(
+        &home,
+        &[
+            "event",
+            "void",
+            &buy_id[..8],
+            "-m",
+            "typed 500 instead of 50",
+        ],
+    );
+    assert!(out.contains(&format!("Voided {buy_id} (buy).")), "{out}");
+    let void_id = out
+        .lines()
+        .find_map(|l| l.strip_prefix("Wrote event "))
+        .expect("void id")
+        .to_string();
+
+    let bank = run_ok_out(&home, &["balance", "assets:bank"]);
+    assert!(bank.contains("assets:bank\tUSD\t0"), "{bank}");
+    let report = run_ok_out(&home, &["budget", "report", "--month", "2026-03"]);
+    assert!(
+        report.contains("2026-03\tFood\tUSD\t300\t0\t300"),
+        "{report}"
+    );
+
+    // The void is dated like the original, so the month's report shows both.
+    let events = run_ok_out(&home, &["report", "--month", "2026-03"]);
+    assert!(
+        events.contains(&format!(
+            "2026-03-05T12:00:00+00:00\tbuy\t{buy_id}\tvoided by {void_id}"
+        )),
+        "{events}"
+    );
+    assert!(
+        events.contains(&format!(
+            "2026-03-05T12:00:00+00:00\tvoid\t{void_id}\tvoids {buy_id}"
+        )),
+        "{events}"
+    );
+
+    let history = run_ok_out(&home, &["history", &buy_id[..8]]);
+    assert!(
+        history.contains(&format!("voids {}", &buy_id[..8])),
+        "{history}"
+    );
+
+    for args in [
+        vec!["event", "void", &buy_id[..8]],
+        vec!["event", "void", &void_id[..8]],
+        vec!["refund", &buy_id[..8]],
+    ] {
+        let mut cmd = bankero_cmd();
+        cmd.env("BANKERO_HOME", home.path());
+        cmd.args(&args);
+        cmd.assert().failure().stderr(
+            predicate::str::contains("already voided")
+                .or(predicate::str::contains("is itself a void"))
+                .or(predicate::str::contains("nothing to refund")),
+        );
+    }
+}
+
+#[test]
+fn meta_cannot_set_keys_that_link_events() {
+    let home = tempfile::tempdir().expect("tempdir");
+    let out = run_ok_out(
+        &home,
+        &[
+            "deposit",
+            "100",
+            "USD",
+            "--from",
+            "income:salary",
+            "--to",
+            "assets:bank",
+        ],
+    );
+    let first = out.split_whitespace().nth(2).expect("event id").to_string();
+    for key in ["supersedes", "voids", "refunds", "task_id", "import_id"] {
+        let meta = format!("{key}={first}");
+        let mut cmd = bankero_cmd();
+        cmd.env("BANKERO_HOME", home.path());
+        cmd.args([
+            "deposit",
+            "20",
+            "USD",
+            "--from",
+            "income:gift",
+            "--to",
+            "assets:bank",
+            "--meta",
+            &meta,
+        ]);
+        cmd.assert()
+            .failure()
+            .stderr(predicate::str::contains(format!(
+                "--meta key '{key}' is reserved"
+            )));
+    }
+    let bank = run_ok_out(&home, &["balance", "assets:bank"]);
+    assert!(bank.contains("assets:bank\tUSD\t100"), "{bank}");
+}
+
+#[test]
+fn event_amend_supersedes_the_event_and_replay_uses_the_latest_version() {
+    let home = tempfile::tempdir().expect("tempdir");
+    run_ok(
+        &home,
+        &[
+            "budget",
+            "create",
+            "Groceries",
+            "300",
+            "USD",
+            "--month",
+            "2026-03",
+            "--category",
+            "expenses:groceries",
+        ],
+    );
+    let out = run_ok_out(
+        &home,
+        &[
+            "buy",
+            "external:market",
+            "500",
+            "UDS",
+            "--from",
+            "assets:bank",
+            "--category",
+            "expenses:food",
+            "--effective-at",
+            "2026-03-05T12:00:00Z",
+        ],
+    );
+    let original = out.split_whitespace().nth(2).expect("event id").to_string();
+
+    let out = run_ok_out(
+        &home,
+        &[
+            "event",
+            "amend",
+            &original[..8],
+            "--amount",
+            "50",
+            "--commodity",
+            "USD",
+            "--category",
+            "expenses:groceries",
+        ],
+    );
+    assert!(
+        out.contains("amount 500 -> 50 UDS, commodity UDS -> USD"),
+        "{out}"
+    );
+    let amended = out
+        .lines()
+        .find_map(|l| l.strip_prefix("Wrote event "))
+        .expect("amended id")
+        .to_string();
+
+    let bank = run_ok_out(&home, &["balance", "assets:bank"]);
+    assert!(bank.contains("assets:bank\tUSD\t-50"), "{bank}");
+    assert!(!bank.contains("UDS"), "{bank}");
+
+    let events = run_ok_out(&home, &["report", "--month", "mar-2026"]);
+    assert!(events.contains(&amended), "{events}");
+    assert!(!events.contains(&original), "{events}");
+
+    let budget = run_ok_out(&home, &["budget", "report", "--month", "2026-03"]);
+    assert!(
+        budget.contains("2026-03\tGroceries\tUSD\t300\t50\t250"),
+        "{budget}"
+    );
+
+    // Both versions stay in the journal; * marks the one replay uses.
+    let history = run_ok_out(&home, &["history", &original[..8]]);
+    assert!(
+        history.contains(&format!("*   {}\t", &amended[..8])),
+        "{history}"
+    );
+    assert!(
+        history.contains(&format!("supersedes {}", &original[..8])),
+        "{history}"
+    );
+
+    // Only the current version can be corrected again.
+    let mut cmd = bankero_cmd();
+    cmd.env("BANKERO_HOME", home.path());
+    cmd.args(["event", "amend", &original[..8], "--amount", "40"]);
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains(format!(
+            "its current version is {amended}"
+        )));
+    run_ok(&home, &["event", "amend", &amended[..8], "--amount", "45"]);
+    let bank = run_ok_out(&home, &["balance", "assets:bank"]);
+    assert!(bank.contains("assets:bank\tUSD\t-45"), "{bank}");
+
+    // Refunding by the original id refunds what the purchase is now.
+    let mut cmd = bankero_cmd();
+    cmd.env("BANKERO_HOME", home.path());
+    cmd.args(["refund", &original[..8], "--amount", "50"]);
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("only 45 USD of 45 USD is left"));
+}
+
+#[test]
+fn report_totals_sums_money_in_and_out_per_commodity() {
+    let home = tempfile::tempdir().expect("tempdir");
+    for (amount, from, at) in [
+        ("500", "income:client:acme", "2026-01-15T12:00:00Z"),
+        ("700", "income:client:acme", "2026-02-15T12:00:00Z"),
+        ("300", "income:client:other", "2026-02-20T12:00:00Z"),
+        ("900", "income:client:acme", "2026-04-02T12:00:00Z"),
+    ] {
+        run_ok(
+            &home,
+            &[
+                "deposit",
+                amount,
+                "USD",
+                "--from",
+                from,
+                "--to",
+                "assets:bank",
+                "--effective-at",
+                at,
+            ],
+        );
+    }
+    run_ok(
+        &home,
+        &[
+            "buy",
+            "expenses:food",
+            "40",
+            "USD",
+            "--from",
+            "assets:bank",
+            "--effective-at",
+            "2026-02-21T12:00:00Z",
+        ],
+    );
+    // Names that merely start like the filtered accounts are other accounts.
+    run_ok(
+        &home,
+        &[
+            "deposit",
+            "250",
+            "USD",
+            "--from",
+            "income:client:acme-labs",
+            "--to",
+            "assets:bankinter",
+            "--effective-at",
+            "2026-02-22T12:00:00Z",
+        ],
+    );
+
+    // What client:acme paid this quarter, counted where the money landed.
+    let acme = run_ok_out(
+        &home,
+        &[
+            "report",
+            "--range",
+            "2026-01-01..2026-03-31",
+            "--account",
+            "income:client:acme",
+            "--totals",
+        ],
+    );
+    assert!(acme.contains("Totals:"), "{acme}");
+    assert!(acme.contains("  USD\tin 1200\tout 0\tnet 1200"), "{acme}");
+
+    // Filtering on an own account counts only that account's legs.
+    let bank = run_ok_out(
+        &home,
+        &[
+            "report",
+            "--month",
+            "2026-02",
+            "--account",
+            "assets:bank",
+            "--totals",
+        ],
+    );
+    assert!(bank.contains("  USD\tin 1000\tout 40\tnet 960"), "{bank}");
+
+    let plain = run_ok_out(&home, &["report", "--month", "2026-02"]);
+    assert!(!plain.contains("Totals:"), "{plain}");
+
+    let mut cmd = bankero_cmd();
+    cmd.env("BANKERO_HOME", home.path());
+    cmd.args(["report", "--totals", "--output", "csv"]);
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("only available with text output"));
+}
+
+#[test]
+fn event_show_prints_the_stored_payload_as_text_and_json() {
+    let home = tempfile::tempdir().expect("tempdir");
+    let out = run_ok_out(
+        &home,
+        &[
+            "buy",
+            "expenses:food",
+            "12.50",
+            "USD",
+            "--from",
+            "assets:bank",
+            "--category",
+            "groceries",
+            "--ref",
+            "INV-7",
+            "-m",
+            "weekly shop",
+            "--effective-at",
+            "2026-03-02T12:00:00Z",
+        ],
+    );
+    let id = out.split_whitespace().nth(2).expect("event id").to_string();
+
+    let text = run_ok_out(&home, &["event", "show", &id[..8]]);
+    assert!(text.contains(&format!("event_id:     {id}")), "{text}");
+    assert!(text.contains("action:       buy"), "{text}");
+    assert!(text.contains("ref:          INV-7"), "{text}");
+    assert!(text.contains("  assets:bank\tUSD\t-12.50"), "{text}");
+    assert!(text.contains("rate_context:"), "{text}");
+    assert!(text.contains("metadata:"), "{text}");
+
+    let json = run_ok_out(&home, &["event", "show", &id, "--json"]);
+    let v: serde_json::Value = serde_json::from_str(&json).expect("json");
+    assert_eq!(v["event_id"], id.as_str());
+    assert_eq!(v["payload"]["action"], "buy");
+    assert_eq!(v["payload"]["category"], "groceries");
+    assert_eq!(v["payload"]["postings"].as_array().map(Vec::len), Some(2));
+    assert_eq!(v["payload"]["metadata"]["event_id"], id.as_str());
+
+    // `bankero show` is the same command.
+    assert_eq!(run_ok_out(&home, &["show", &id[..8]]), text);
+    assert_eq!(run_ok_out(&home, &["show", &id, "--json"]), json);
+
+    run_ok(&home, &["event", "void", &id[..8]]);
+    let text = run_ok_out(&home, &["event", "show", &id[..8]]);
+    assert!(text.contains("Voided by "), "{text}");
+    assert_eq!(run_ok_out(&home, &["show", &id[..8]]), text);
+}
+
+#[test]
+fn account_default_commodity_fills_in_and_guards_the_account() {
+    let home = tempfile::tempdir().expect("tempdir");
+
+    // Without a default the commodity is still required.
+    let mut cmd = bankero_cmd();
+    cmd.env("BANKERO_HOME", home.path());
+    cmd.args([
+        "deposit",
+        "100",
+        "--to",
+        "assets:bank",
+        "--from",
+        "income:salary",
+    ]);
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("Missing commodity"));
+
+    run_ok(&home, &["commodity", "default", "assets:bank", "usd"]);
+    run_ok(
+        &home,
+        &[
+            "deposit",
+            "100",
+            "--to",
+            "assets:bank",
+            "--from",
+            "income:salary",
+        ],
+    );
+    let bal = run_ok_out(&home, &["balance", "assets:bank"]);
+    assert!(bal.contains("assets:bank\tUSD\t100"), "{bal}");
+
+    let list = run_ok_out(&home, &["commodity", "list"]);
+    assert!(list.contains("assets:bank\tUSD"), "{list}");
+
+    // Posting another commodity to the account is rejected, whichever command does it.
+    let mut cmd = bankero_cmd();
+    cmd.env("BANKERO_HOME", home.path());
+    cmd.args([
+        "deposit",
+        "500",
+        "VES",
+        "--to",
+        "assets:bank",
+        "--from",
+        "income:salary",
+    ]);
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("assets:bank holds USD, not VES"));
+    let mut cmd = bankero_cmd();
+    cmd.env("BANKERO_HOME", home.path());
+    cmd.args([
+        "buy",
+        "external:market",
+        "5",
+        "EUR",
+        "--from",
+        "assets:bank",
+    ]);
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("assets:bank holds USD, not EUR"));
+
+    run_ok(&home, &["commodity", "default", "assets:bank", "--clear"]);
+    run_ok(
+        &home,
+        &[
+            "deposit",
+            "500",
+            "VES",
+            "--to",
+            "assets:bank",
+            "--from",
+            "income:salary",
+        ],
+    );
+}
+
+#[test]
+fn event_search_matches_free_text_within_account_and_month() {
+    let home = tempfile::tempdir().expect("tempdir");
+    let buy = |payee: &str, from: &str, note: &str, at: &str| {
+        let out = run_ok_out(
+            &home,
+            &[
+                "buy",
+                payee,
+                "10",
+                "USD",
+                "--from",
+                from,
+                "-m",
+                note,
+                "--effective-at",
+                at,
+            ],
+        );
+        out.split_whitespace().nth(2).expect("event id").to_string()
+    };
+    let feb_bank = buy(
+        "external:farmatodo",
+        "assets:bank",
+        "medicine",
+        "2026-02-03T12:00:00Z",
+    );
+    let feb_cash = buy(
+        "external:farmatodo",
+        "assets:cash",
+        "snacks",
+        "2026-02-04T12:00:00Z",
+    );
+    let mar_bank = buy(
+        "external:farmatodo",
+        "assets:bank",
+        "vitamins",
+        "2026-03-05T12:00:00Z",
+    );
+    let note_hit = buy(
+        "external:market",
+        "assets:bank",
+        "Farmatodo gift card",
+        "2026-02-06T12:00:00Z",
+    );
+
+    let out = run_ok_out(
+        &home,
+        &[
+            "event",
+            "search",
+            "farmatodo",
+            "--account",
+            "assets:bank",
+            "--month",
+            "2026-02",
+        ],
+    );
+    assert!(out.contains(&feb_bank), "{out}");
+    assert!(out.contains(&note_hit), "{out}");
+    assert!(!out.contains(&feb_cash), "{out}");
+    assert!(!out.contains(&mar_bank), "{out}");
+    assert!(out.contains("\"medicine\""), "{out}");
+
+    let all = run_ok_out(&home, &["event", "search", "FARMATODO"]);
+    assert_eq!(all.lines().count(), 4, "{all}");
+
+    let none = run_ok_out(&home, &["event", "search", "pharmacy"]);
+    assert!(none.contains("(no events match \"pharmacy\")"), "{none}");
+}
+
+#[test]
+fn undo_voids_the_last_event_and_walks_back_on_repeat() {
+    let home = tempfile::tempdir().expect("tempdir");
+    for amount in ["100", "20", "3"] {
+        run_ok(
+            &home,
+            &[
+                "deposit",
+                amount,
+                "USD",
+                "--from",
+                "income:salary",
+                "--to",
+                "assets:bank",
+            ],
+        );
+    }
+
+    // Without a terminal (and without --yes) nothing is written.
+    let out = run_ok_out(&home, &["undo"]);
+    assert!(out.contains("Re-run with --yes"), "{out}");
+    let bal = run_ok_out(&home, &["balance", "assets:bank"]);
+    assert!(bal.contains("assets:bank\tUSD\t123"), "{bal}");
+
+    let out = run_ok_out(&home, &["undo", "--yes"]);
+    assert!(out.contains("Last event: "), "{out}");
+    assert!(out.contains("deposit\t3 USD"), "{out}");
+    let bal = run_ok_out(&home, &["balance", "assets:bank"]);
+    assert!(bal.contains("assets:bank\tUSD\t120"), "{bal}");
+
+    let out = run_ok_out(&home, &["undo", "--yes", "-m", "typo"]);
+    assert!(out.contains("deposit\t20 USD"), "{out}");
+    let bal = run_ok_out(&home, &["balance", "assets:bank"]);
+    assert!(bal.contains("assets:bank\tUSD\t100"), "{bal}");
+
+    // Events without postings (tags, approvals, snapshots) are passed over.
+    run_ok(&home, &["tag", "assets:bank", "--tag", "checked"]);
+    let out = run_ok_out(&home, &["undo", "--yes"]);
+    assert!(out.contains("deposit\t100 USD"), "{out}");
+    let bal = run_ok_out(&home, &["balance", "assets:bank"]);
+    assert!(bal.contains("assets:bank\tUSD\t0"), "{bal}");
+}
+
+#[test]
+fn cashcount_compares_the_drawer_with_the_ledger_and_posts_the_difference() {
+    let home = tempfile::tempdir().expect("tempdir");
+    run_ok(
+        &home,
+        &[
+            "withdraw",
+            "230",
+            "USD",
+            "--from",
+            "assets:bank",
+            "--to",
+            "assets:cash",
+        ],
+    );
+
+    let out = run_ok_out(
+        &home,
+        &[
+            "cashcount",
+            "assets:cash",
+            "--denominations",
+            "20x10 USD, 5x5 USD",
+        ],
+    );
+    assert!(out.contains("  20 USD x 10 = 200"), "{out}");
+    assert!(
+        out.contains("USD\tcounted 225\tledger 230\tdifference -5"),
+        "{out}"
+    );
+    assert!(out.contains("Re-run with --yes"), "{out}");
+
+    // The adjustment is an ordinary write: limits on the counter account apply.
+    run_ok(
+        &home,
+        &[
+            "limit",
+            "set",
+            "expenses:cash-difference",
+            "0",
+            "USD",
+            "--hard",
+        ],
+    );
+    let mut cmd = bankero_cmd();
+    cmd.env("BANKERO_HOME", home.path());
+    cmd.args([
+        "cashcount",
+        "assets:cash",
+        "--denominations",
+        "20x10 USD, 5x5 USD",
+        "--yes",
+    ]);
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("Blocked by hard limit"));
+    run_ok(&home, &["limit", "remove", "expenses:cash-difference"]);
+
+    let out = run_ok_out(
+        &home,
+        &[
+            "cashcount",
+            "assets:cash",
+            "--denominations",
+            "20x10 USD, 5x5 USD",
+            "--yes",
+        ],
+    );
+    assert!(out.contains("Posted the adjustment (-5 USD)."), "{out}");
+    let bal = run_ok_out(&home, &["balance"]);
+    assert!(bal.contains("assets:cash\tUSD\t225"), "{bal}");
+    assert!(bal.contains("expenses:cash-difference\tUSD\t5"), "{bal}");
+
+    // The account's default commodity fills in, and a matching count writes nothing.
+    run_ok(&home, &["commodity", "default", "assets:cash", "USD"]);
+    let out = run_ok_out(
+        &home,
+        &["cashcount", "assets:cash", "--denominations", "20x10, 5x5"],
+    );
+    assert!(out.contains("The count matches the ledger."), "{out}");
+
+    let mut cmd = bankero_cmd();
+    cmd.env("BANKERO_HOME", home.path());
+    cmd.args(["cashcount", "assets:cash", "--denominations", "twenty USD"]);
+    cmd.assert().failure().stderr(predicate::str::contains(
+        "Invalid denomination 'twenty USD'",
+    ));
+}
+
+#[test]
+fn lend_borrow_and_debts_settle_track_who_owes_whom() {
+    let home = tempfile::tempdir().expect("tempdir");
+    run_ok(
+        &home,
+        &["lend", "Ana", "50", "USD", "--from", "assets:cash"],
+    );
+    run_ok(
+        &home,
+        &["borrow", "bob", "100", "usd", "--to", "assets:bank"],
+    );
+    run_ok(
+        &home,
+        &["lend", "bob", "30", "USD", "--from", "assets:cash"],
+    );
+
+    let out = run_ok_out(&home, &["debts"]);
+    assert!(out.contains("ana\tUSD\t50\towes you"), "{out}");
+    // Lending to someone you owe nets out.
+    assert!(out.contains("bob\tUSD\t-70\tyou owe"), "{out}");
+
+    // Partial repayment from Ana comes into the cash account.
+    run_ok(
+        &home,
+        &[
+            "debts",
+            "settle",
+            "ana",
+            "20",
+            "USD",
+            "--account",
+            "assets:cash",
+        ],
+    );
+    let out = run_ok_out(&home, &["debts"]);
+    assert!(out.contains("ana\tUSD\t30\towes you"), "{out}");
+
+    let mut cmd = bankero_cmd();
+    cmd.env("BANKERO_HOME", home.path());
+    cmd.args(["debts", "settle", "ana", "40", "--account", "assets:cash"]);
+    cmd.assert().failure().stderr(predicate::str::contains(
+        "only 30 USD is outstanding with ana",
+    ));
+
+    // Paying Bob back in full goes out of the bank.
+    run_ok(
+        &home,
+        &["debts", "settle", "bob", "--account", "assets:bank"],
+    );
+    let out = run_ok_out(&home, &["debts"]);
+    assert!(!out.contains("bob"), "{out}");
+    let out = run_ok_out(&home, &["debts", "--all"]);
+    assert!(out.contains("bob\tUSD\t0\tsettled"), "{out}");
+
+    let bal = run_ok_out(&home, &["balance"]);
+    assert!(bal.contains("assets:bank\tUSD\t30"), "{bal}");
+    assert!(bal.contains("assets:cash\tUSD\t-60"), "{bal}");
+}
+
+#[test]
+fn future_dated_events_stay_pending_until_their_effective_time() {
+    let home = tempfile::tempdir().expect("tempdir");
+    run_ok(
+        &home,
+        &[
+            "deposit",
+            "1000",
+            "USD",
+            "--from",
+            "income:salary",
+            "--to",
+            "assets:bank",
+            "--effective-at",
+            "2026-01-05T12:00:00Z",
+        ],
+    );
+    let mut cmd = bankero_cmd();
+    cmd.env("BANKERO_HOME", home.path());
+    cmd.args([
+        "buy",
+        "landlord",
+        "800",
+        "USD",
+        "--from",
+        "assets:bank",
+        "--category",
+        "housing",
+        "--effective-at",
+        "2099-01-01T09:00:00Z",
+    ]);
+    cmd.assert().success().stderr(predicate::str::contains(
+        "Pending until 2099-01-01T09:00:00+00:00",
+    ));
+
+    let out = run_ok_out(&home, &["balance", "assets:bank"]);
+    assert!(out.contains("assets:bank\tUSD\t1000"), "balance: {out}");
+    let out = run_ok_out(&home, &["balance", "assets:bank", "--include-pending"]);
+    assert!(out.contains("assets:bank\tUSD\t200"), "balance: {out}");
+
+    let out = run_ok_out(&home, &["pending"]);
+    assert!(
+        out.contains("2099-01-01T09:00:00+00:00\tbuy\t"),
+        "pending: {out}"
+    );
+    assert!(out.contains("800 USD"), "pending: {out}");
+    assert!(out.contains("  assets:bank\tUSD\t-800"), "pending: {out}");
+
+    let out = run_ok_out(&home, &["pending", "--account", "assets:cash"]);
+    assert!(out.contains("(no pending events)"), "pending: {out}");
+
+    // Budgets and reports wait for it too.
+    run_ok(
+        &home,
+        &[
+            "budget",
+            "create",
+            "Rent",
+            "900",
+            "USD",
+            "--month",
+            "2099-01",
+            "--category",
+            "housing",
+        ],
+    );
+    let out = run_ok_out(&home, &["budget", "report", "--month", "2099-01"]);
+    assert!(
+        out.contains("2099-01\tRent\tUSD\t900\t0\t900"),
+        "budget: {out}"
+    );
+    let out = run_ok_out(&home, &["report"]);
+    assert!(!out.contains("2099-01-01"), "report: {out}");
+    let out = run_ok_out(&home, &["report", "--include-pending"]);
+    assert!(
+        out.contains("2099-01-01T09:00:00+00:00\tbuy\t"),
+        "report: {out}"
+    );
+}
+
+#[test]
+fn balance_by_commodity_groups_accounts_with_a_subtotal() {
+    let home = tempfile::tempdir().expect("tempdir");
+    run_ok(
+        &home,
+        &[
+            "deposit",
+            "1000",
+            "USD",
+            "--from",
+            "income:salary",
+            "--to",
+            "assets:bank",
+        ],
+    );
+    run_ok(
+        &home,
+        &[
+            "deposit",
+            "5000",
+            "VES",
+            "--from",
+            "income:freelance",
+            "--to",
+            "assets:banesco",
+        ],
+    );
+    run_ok(
+        &home,
+        &[
+            "buy",
+            "300",
+            "USD",
+            "--from",
+            "assets:bank",
+            "--to",
+            "expenses:rent:300",
+        ],
+    );
+
+    let out = run_ok_out(&home, &["balance", "assets", "--by", "commodity"]);
+    assert!(
+        out.contains("USD\n  assets:bank\t700\n  subtotal\t700\n\nVES\n  assets:banesco\t5000\n  subtotal\t5000\n"),
+        "balance: {out}"
+    );
+    assert!(!out.contains("(totals)"), "balance: {out}");
+
+    let out = run_ok_out(&home, &["balance", "--by", "commodity"]);
+    assert!(
+        out.contains(
+            "USD\n  assets:bank\t700\n  expenses:rent\t300\n  income:salary\t-1000\n  subtotal\t0\n"
+        ),
+        "balance: {out}"
+    );
+
+    let out = run_ok_out(&home, &["balance", "--by", "commodity", "--output", "csv"]);
+    let usd = out.find("balance,income:salary,USD").expect("usd row");
+    let ves = out.find("balance,assets:banesco,VES").expect("ves row");
+    assert!(usd < ves, "csv: {out}");
+}
+
+#[test]
+fn buy_percentage_splits_share_the_amount_and_keep_the_remainder() {
+    let home = tempfile::tempdir().expect("tempdir");
+    run_ok(
+        &home,
+        &[
+            "buy",
+            "99.99",
+            "USD",
+            "--from",
+            "assets:bank",
+            "--to",
+            "expenses:rent:60%",
+            "--to",
+            "expenses:utilities:40%",
+        ],
+    );
+    let out = run_ok_out(&home, &["balance", "expenses"]);
+    // 59.994 and 39.996 round down to cents; the leftover cent goes to the last split.
+    assert!(out.contains("expenses:rent\tUSD\t59.99"), "balance: {out}");
+    assert!(
+        out.contains("expenses:utilities\tUSD\t40.00"),
+        "balance: {out}"
+    );
+
+    let mut cmd = bankero_cmd();
+    cmd.env("BANKERO_HOME", home.path());
+    cmd.args([
+        "buy",
+        "100",
+        "USD",
+        "--from",
+        "assets:bank",
+        "--to",
+        "expenses:rent:60%",
+        "--to",
+        "expenses:utilities:30%",
+    ]);
+    cmd.assert().failure().stderr(predicate::str::contains(
+        "Percentage splits add up to 90%, not 100%",
+    ));
+}
+
+#[test]
+fn move_and_sell_fees_are_posted_from_the_paying_account() {
+    let home = tempfile::tempdir().expect("tempdir");
+    run_ok(
+        &home,
+        &[
+            "move",
+            "100",
+            "USD",
+            "--from",
+            "assets:usd",
+            "--to",
+            "assets:ves",
+            "3600",
+            "VES",
+            "--fee",
+            "1",
+            "USD",
+        ],
+    );
+    run_ok(
+        &home,
+        &[
+            "sell",
+            "0.01",
+            "BTC",
+            "--from",
+            "assets:btc",
+            "--to",
+            "assets:cash",
+            "2400",
+            "USD",
+            "@binance",
+            "--fee",
+            "2.40",
+            "USD",
+            "--fee-account",
+            "expenses:exchange-fees",
+        ],
+    );
+
+    let out = run_ok_out(&home, &["balance"]);
+    assert!(out.contains("assets:usd\tUSD\t-101"), "balance: {out}");
+    assert!(out.contains("assets:ves\tVES\t3600"), "balance: {out}");
+    assert!(out.contains("expenses:fees\tUSD\t1"), "balance: {out}");
+    assert!(out.contains("assets:cash\tUSD\t2397.60"), "balance: {out}");
+    assert!(
+        out.contains("expenses:exchange-fees\tUSD\t2.40"),
+        "balance: {out}"
+    );
+
+    let mut cmd = bankero_cmd();
+    cmd.env("BANKERO_HOME", home.path());
+    cmd.args([
+        "move",
+        "5",
+        "USD",
+        "--from",
+        "assets:usd",
+        "--to",
+        "assets:cash",
+        "--fee",
+        "1",
+        "EUR",
+    ]);
+    cmd.assert().failure().stderr(predicate::str::contains(
+        "must be in the sent (USD) or received commodity",
+    ));
+}
+
+#[test]
+fn effective_at_accepts_bare_dates_and_relative_days() {
+    let home = tempfile::tempdir().expect("tempdir");
+    run_ok(&home, &["config", "set", "time_of_day", "09:30"]);
+    run_ok(&home, &["config", "set", "timezone", "+02:00"]);
+    run_ok(
+        &home,
+        &[
+            "buy",
+            "bakery",
+            "5",
+            "USD",
+            "--from",
+            "assets:cash",
+            "--effective-at",
+            "2026-03-05",
+        ],
+    );
+    run_ok(
+        &home,
+        &[
+            "buy",
+            "kiosk",
+            "2",
+            "USD",
+            "--from",
+            "assets:cash",
+            "--effective-at",
+            "3 days ago",
+        ],
+    );
+
+    let out = run_ok_out(&home, &["register", "assets:cash"]);
+    // 09:30 at +02:00 is 07:30 UTC.
+    assert!(
+        out.contains("2026-03-05T07:30:00+00:00\tbuy\tassets:cash"),
+        "register: {out}"
+    );
+    assert_eq!(
+        out.matches("T07:30:00+00:00\tbuy").count(),
+        2,
+        "register: {out}"
+    );
+
+    let mut cmd = bankero_cmd();
+    cmd.env("BANKERO_HOME", home.path());
+    cmd.args([
+        "buy",
+        "kiosk",
+        "2",
+        "USD",
+        "--from",
+        "assets:cash",
+        "--effective-at",
+        "someday",
+    ]);
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("Invalid timestamp: someday"));
+}
+
+#[test]
+fn buy_can_be_paid_from_several_accounts_and_commodities() {
+    let home = tempfile::tempdir().expect("tempdir");
+    run_ok(
+        &home,
+        &[
+            "rate",
+            "set",
+            "@bcv",
+            "USD",
+            "VES",
+            "25",
+            "--as-of",
+            "2026-01-01T00:00:00Z",
+        ],
+    );
+    run_ok(&home, &["commodity", "default", "assets:banesco", "VES"]);
+    // 30 USD cash plus 500 VES (20 USD at 25 VES/USD).
+    run_ok(
+        &home,
+        &[
+            "buy",
+            "expenses:groceries",
+            "50",
+            "USD",
+            "--from",
+            "assets:cash-usd:30",
+            "--from",
+            "assets:banesco:500",
+            "@bcv",
+        ],
+    );
+
+    let out = run_ok_out(&home, &["balance"]);
+    assert!(out.contains("assets:cash-usd\tUSD\t-30"), "balance: {out}");
+    assert!(out.contains("assets:banesco\tVES\t-500"), "balance: {out}");
+    assert!(
+        out.contains("expenses:groceries\tUSD\t50"),
+        "balance: {out}"
+    );
+    assert!(out.contains("(totals)\nUSD\t0\nVES\t0"), "balance: {out}");
+
+    let mut cmd = bankero_cmd();
+    cmd.env("BANKERO_HOME", home.path());
+    cmd.args([
+        "buy",
+        "expenses:groceries",
+        "50",
+        "USD",
+        "--from",
+        "assets:cash-usd:30",
+        "--from",
+        "assets:banesco:400",
+        "@bcv",
+    ]);
+    cmd.assert().failure().stderr(predicate::str::contains(
+        "--from legs must sum to what the buy pays (46 != 50 USD)",
+    ));
+
+    // A single leg in another commodity pays the whole buy from that account.
+    run_ok(
+        &home,
+        &[
+            "buy",
+            "expenses:groceries",
+            "10",
+            "USD",
+            "--from",
+            "assets:banesco:250",
+            "@bcv",
+        ],
+    );
+    let out = run_ok_out(&home, &["balance", "assets"]);
+    assert!(out.contains("assets:banesco\tVES\t-750"), "balance: {out}");
+    assert!(!out.contains("assets:banesco:250"), "balance: {out}");
+
+    // ...and a single leg that doesn't cover it is refused like several would be.
+    let mut cmd = bankero_cmd();
+    cmd.env("BANKERO_HOME", home.path());
+    cmd.args([
+        "buy",
+        "expenses:groceries",
+        "10",
+        "USD",
+        "--from",
+        "assets:cash-usd:8",
+    ]);
+    cmd.assert().failure().stderr(predicate::str::contains(
+        "--from legs must sum to what the buy pays (8 != 10 USD)",
+    ));
+}
+
+#[test]
+fn exchange_resolves_the_rate_and_records_the_spread() {
+    let home = tempfile::tempdir().expect("tempdir");
+    run_ok(
+        &home,
+        &[
+            "rate",
+            "set",
+            "@bcv",
+            "USD",
+            "VES",
+            "36",
+            "--as-of",
+            "2026-01-01T00:00:00Z",
+        ],
+    );
+
+    let mut cmd = bankero_cmd();
+    cmd.env("BANKERO_HOME", home.path());
+    cmd.args([
+        "exchange",
+        "100",
+        "USD",
+        "->",
+        "VES",
+        "@bcv",
+        "--from",
+        "assets:usd",
+        "--to",
+        "assets:ves",
+    ]);
+    cmd.assert().success().stdout(predicate::str::contains(
+        "Exchanged 100 USD -> 3600 VES at 36 VES per USD (@bcv).",
+    ));
+
+    let out = run_ok_out(
+        &home,
+        &[
+            "exchange",
+            "100",
+            "USD",
+            "VES",
+            "@binance:38.5",
+            "--from",
+            "assets:usd",
+            "--to",
+            "assets:ves",
+            "--vs",
+            "@bcv",
+        ],
+    );
+    assert!(
+        out.contains("Spread vs @bcv (36 VES per USD): +6.94% (+250 VES)"),
+        "exchange: {out}"
+    );
+    let id = out
+        .lines()
+        .find(|l| l.starts_with("Wrote event"))
+        .and_then(|l| l.split_whitespace().nth(2))
+        .expect("event id")
+        .to_string();
+    let out = run_ok_out(&home, &["show", &id]);
+    assert!(
+        out.contains("rate:         @binance VES per USD = 38.5"),
+        "show: {out}"
+    );
+
+    let out = run_ok_out(&home, &["balance", "assets"]);
+    assert!(out.contains("assets:usd\tUSD\t-200"), "balance: {out}");
+    assert!(out.contains("assets:ves\tVES\t7450"), "balance: {out}");
+
+    let mut cmd = bankero_cmd();
+    cmd.env("BANKERO_HOME", home.path());
+    cmd.args([
+        "exchange",
+        "5",
+        "EUR",
+        "VES",
+        "@bcv",
+        "--from",
+        "assets:eur",
+        "--to",
+        "assets:ves",
+    ]);
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("No @bcv rate between EUR and VES"));
+
+    // Global options before the subcommand are skipped when looking for the arrow...
+    let mut cmd = bankero_cmd();
+    cmd.args([
+        "--home",
+        home.path().to_str().expect("utf8 path"),
+        "exchange",
+        "1",
+        "USD",
+        "->",
+        "VES",
+        "@bcv",
+        "--from",
+        "assets:usd",
+        "--to",
+        "assets:ves",
+        "--dry-run",
+    ]);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Dry run: nothing was written."));
+    // ...and other commands that merely mention "exchange" are left alone.
+    let out = run_ok_out(
+        &home,
+        &[
+            "deposit",
+            "5",
+            "USD",
+            "--from",
+            "income:fx",
+            "--to",
+            "assets:usd",
+            "--note",
+            "exchange",
+            "--dry-run",
+            "--ref",
+            "-",
+        ],
+    );
+    assert!(out.contains("Dry run: nothing was written."), "{out}");
+}
+
+#[test]
+fn templates_replay_saved_commands_with_amount_and_extra_flags() {
+    let home = tempfile::tempdir().expect("tempdir");
+    let out = run_ok_out(
+        &home,
+        &[
+            "template",
+            "add",
+            "rent",
+            "--",
+            "buy",
+            "expenses:rent",
+            "480",
+            "USD",
+            "--from",
+            "assets:bank",
+            "--category",
+            "housing",
+            "--tag",
+            "home",
+        ],
+    );
+    assert!(out.contains("Saved template 'rent'"), "add: {out}");
+
+    run_ok(&home, &["rent", "--effective-at", "2026-03-01T09:00:00Z"]);
+    run_ok(
+        &home,
+        &[
+            "template",
+            "use",
+            "rent",
+            "--amount",
+            "500",
+            "--effective-at",
+            "2026-04-01T09:00:00Z",
+        ],
+    );
+    let out = run_ok_out(&home, &["balance", "expenses:rent"]);
+    assert!(out.contains("expenses:rent\tUSD\t980"), "balance: {out}");
+    let out = run_ok_out(
+        &home,
+        &["report", "--month", "2026-04", "--category", "housing"],
+    );
+    assert!(out.contains("buy"), "report: {out}");
+
+    for (args, error) in [
+        (
+            &["template", "add", "buy", "--", "buy", "x", "1", "USD"][..],
+            "is a built-in command",
+        ),
+        (
+            &["template", "add", "bad", "--", "buy", "--bogus"][..],
+            "is not a valid command",
+        ),
+        (&["renta"][..], "Unknown command or template 'renta'"),
+        (&["renta"][..], "Did you mean rent?"),
+        (&["balanse"][..], "unrecognized subcommand 'balanse'"),
+        (&["balanse"][..], "a similar subcommand exists: 'balance'"),
+    ] {
+        let mut cmd = bankero_cmd();
+        cmd.env("BANKERO_HOME", home.path());
+        cmd.args(args);
+        cmd.assert()
+            .failure()
+            .stderr(predicate::str::contains(error));
+    }
+
+    run_ok(&home, &["template", "remove", "rent"]);
+    let out = run_ok_out(&home, &["template", "list"]);
+    assert!(out.contains("(no templates)"), "list: {out}");
+}
+
+#[test]
+fn batch_validates_every_line_then_writes_all_in_one_transaction() {
+    let home = tempfile::tempdir().expect("tempdir");
+    let batch = |input: &str| {
+        let mut cmd = bankero_cmd();
+        cmd.env("BANKERO_HOME", home.path());
+        cmd.arg("batch").write_stdin(input.to_string());
+        cmd.assert()
+    };
+
+    batch(
+        "# March backlog\n\
+         deposit 1000 USD --from income:salary --to assets:bank --effective-at 2026-03-01T09:00:00Z\n\
+         \n\
+         buy store:market 62.5 USD --from assets:bank --note \"weekly shop\" --effective-at 2026-03-03T09:00:00Z\n",
+    )
+    .success()
+    .stdout(predicate::str::contains(
+        "Batch: 2 command(s) written in one transaction.",
+    ));
+
+    // A parse error anywhere stops the batch before anything is written.
+    batch(
+        "buy store:market 10 USD --from assets:bank\n\
+         buy store:market 10 USD --frm assets:bank\n\
+         balance\n",
+    )
+    .failure()
+    .stderr(predicate::str::contains(
+        "2 invalid line(s); nothing was written",
+    ))
+    .stderr(predicate::str::contains(
+        "line 2: unexpected argument '--frm'",
+    ))
+    .stderr(predicate::str::contains(
+        "line 3: `balance` cannot run in a batch",
+    ));
+
+    // A failure while recording rolls back the lines before it.
+    batch(
+        "buy store:market 10 USD --from assets:bank\n\
+         exchange 5 USD VES @nope --from assets:bank --to assets:ves\n",
+    )
+    .failure()
+    .stderr(predicate::str::contains(
+        "line 2 failed; nothing was written",
+    ))
+    .stdout(predicate::str::contains("Wrote event").not());
+
+    let out = run_ok_out(&home, &["balance", "assets"]);
+    assert!(out.contains("assets:bank\tUSD\t937.5"), "balance: {out}");
+
+    // Nothing can answer a confirm_over prompt, so such a line needs batch --yes.
+    run_ok(&home, &["config", "set", "confirm_over", "100 USD"]);
+    let big = "buy store:tv 500 USD --from assets:bank\n";
+    batch(big)
+        .failure()
+        .stderr(predicate::str::contains("re-run the batch with --yes"));
+    let mut cmd = bankero_cmd();
+    cmd.env("BANKERO_HOME", home.path());
+    cmd.args(["batch", "--yes"]).write_stdin(big);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Wrote event"));
+    let out = run_ok_out(&home, &["balance", "assets"]);
+    assert!(out.contains("assets:bank\tUSD\t437.5"), "balance: {out}");
+}
+
+#[test]
+fn writes_wait_for_another_process_instead_of_failing_on_a_locked_journal() {
+    let home = tempfile::tempdir().expect("tempdir");
+    run_ok(
+        &home,
+        &[
+            "deposit",
+            "1",
+            "USD",
+            "--from",
+            "income:misc",
+            "--to",
+            "assets:cash",
+        ],
+    );
+    // A sync in progress (this test process stands in for it) and one left behind by a
+    // process that no longer runs.
+    let ws_dir = home.path().join("data/workspaces/personal");
+    let activity = ws_dir.join("activity");
+    std::fs::create_dir_all(&activity).expect("activity dir");
+    let live = std::process::id();
+    std::fs::write(
+        activity.join(format!("{live}-0.json")),
+        format!(r#"{{"pid": {live}, "what": "a LAN sync with laptop"}}"#),
+    )
+    .expect("write activity");
+    let mut gone = std::process::Command::new(assert_cmd::cargo::cargo_bin!("bankero"))
+        .arg("--version")
+        .stdout(std::process::Stdio::null())
+        .spawn()
+        .expect("spawn");
+    let dead = gone.id();
+    gone.wait().expect("wait");
+    let stale = activity.join(format!("{dead}-0.json"));
+    std::fs::write(
+        &stale,
+        format!(r#"{{"pid": {dead}, "what": "a crashed task run"}}"#),
+    )
+    .expect("write stale activity");
+
+    // Hold the write lock the way a long sync does.
+    let conn = rusqlite::Connection::open(ws_dir.join("bankero.sqlite3")).expect("open sqlite");
+    conn.execute_batch("BEGIN IMMEDIATE").expect("lock");
+    let mut child = std::process::Command::new(assert_cmd::cargo::cargo_bin!("bankero"))
+        .env("BANKERO_HOME", home.path())
+        .args([
+            "deposit",
+            "5",
+            "USD",
+            "--from",
+            "income:misc",
+            "--to",
+            "assets:cash",
+        ])
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .spawn()
+        .expect("spawn deposit");
+
+    // Release the lock only once the deposit says it is waiting.
+    let mut stderr = std::io::BufReader::new(child.stderr.take().expect("stderr"));
+    let mut waiting = String::new();
+    while !waiting.contains("Waiting") {
+        waiting.clear();
+        let read = std::io::BufRead::read_line(&mut stderr, &mut waiting).expect("read stderr");
+        assert!(read > 0, "deposit exited without waiting");
+    }
+    conn.execute_batch("COMMIT").expect("unlock");
+
+    let out = child.wait_with_output().expect("deposit output");
+    assert!(out.status.success(), "deposit failed: {waiting}");
+    assert!(
+        waiting.contains(&format!(
+            "Waiting for a LAN sync with laptop (pid {live}) to finish"
+        )),
+        "stderr: {waiting}"
+    );
+    assert!(!stale.exists(), "stale activity record was kept");
+    let out = run_ok_out(&home, &["balance", "assets:cash"]);
+    assert!(out.contains("assets:cash\tUSD\t6"), "balance: {out}");
+}
@@ -0,0 +1,325 @@
+use assert_cmd::prelude::*;
+use std::process::Command;
+
+fn bankero_cmd() -> Command {
+    Command::new(assert_cmd::cargo::cargo_bin!("bankero"))
+}
+
+fn run_ok_out(home: &tempfile::TempDir, args: &[&str]) -> String {
+    let mut cmd = bankero_cmd();
+    cmd.env("BANKERO_HOME", home.path());
+    cmd.args(args);
+    let out = cmd.assert().success().get_output().stdout.clone();
+    String::from_utf8(out).expect("utf8 stdout")
+}
+
+const GNUCASH_XML: &str = r#"<?xml version="1.0" encoding="utf-8" ?>
+<gnc-v2
+     xmlns:gnc="http://www.gnucash.org/XML/gnc"
+     xmlns:act="http://www.gnucash.org/XML/act"
+     xmlns:book="http://www.gnucash.org/XML/book"
+     xmlns:cmdty="http://www.gnucash.org/XML/cmdty"
+     xmlns:price="http://www.gnucash.org/XML/price"
+     xmlns:split="http://www.gnucash.org/XML/split"
+     xmlns:trn="http://www.gnucash.org/XML/trn"
+     xmlns:ts="http://www.gnucash.org/XML/ts">
+<gnc:book version="2.0.0">
+<book:id type="guid">book0</book:id>
+<gnc:pricedb version="1">
+  <price>
+    <price:id type="guid">p1</price:id>
+    <price:commodity><cmdty:space>CURRENCY</cmdty:space><cmdty:id>EUR</cmdty:id></price:commodity>
+    <price:currency><cmdty:space>CURRENCY</cmdty:space><cmdty:id>USD</cmdty:id></price:currency>
+    <price:time><ts:date>2026-01-10 00:00:00 +0000</ts:date></price:time>
+    <price:source>user:price</price:source>
+    <price:value>11/10</price:value>
+  </price>
+</gnc:pricedb>
+<gnc:account version="2.0.0">
+  <act:name>Root Account</act:name><act:id type="guid">root</act:id><act:type>ROOT</act:type>
+</gnc:account>
+<gnc:account version="2.0.0">
+  <act:name>Assets</act:name><act:id type="guid">a</act:id><act:type>ASSET</act:type>
+  <act:commodity><cmdty:space>CURRENCY</cmdty:space><cmdty:id>USD</cmdty:id></act:commodity>
+  <act:parent type="guid">root</act:parent>
+</gnc:account>
+<gnc:account version="2.0.0">
+  <act:name>Checking Account</act:name><act:id type="guid">chk</act:id><act:type>BANK</act:type>
+  <act:commodity><cmdty:space>CURRENCY</cmdty:space><cmdty:id>USD</cmdty:id></act:commodity>
+  <act:parent type="guid">a</act:parent>
+</gnc:account>
+<gnc:account version="2.0.0">
+  <act:name>Euro Wallet</act:name><act:id type="guid">eur</act:id><act:type>CASH</act:type>
+  <act:commodity><cmdty:space>CURRENCY</cmdty:space><cmdty:id>EUR</cmdty:id></act:commodity>
+  <act:parent type="guid">a</act:parent>
+</gnc:account>
+<gnc:account version="2.0.0">
+  <act:name>Salary</act:name><act:id type="guid">sal</act:id><act:type>INCOME</act:type>
+  <act:commodity><cmdty:space>CURRENCY</cmdty:space><cmdty:id>USD</cmdty:id></act:commodity>
+  <act:parent type="guid">root</act:parent>
+</gnc:account>
+<gnc:transaction version="2.0.0">
+  <trn:id type="guid">t1</trn:id>
+  <trn:currency><cmdty:space>CURRENCY</cmdty:space><cmdty:id>USD</cmdty:id></trn:currency>
+  <trn:date-posted><ts:date>2026-01-05 10:59:00 +0000</ts:date></trn:date-posted>
+  <trn:description>January pay</trn:description>
+  <trn:splits>
+    <trn:split><split:id type="guid">s1</split:id><split:value>100000/100</split:value><split:quantity>100000/100</split:quantity><split:account type="guid">chk</split:account></trn:split>
+    <trn:split><split:id type="guid">s2</split:id><split:value>-100000/100</split:value><split:quantity>-100000/100</split:quantity><split:account type="guid">sal</split:account></trn:split>
+  </trn:splits>
+</gnc:transaction>
+<gnc:transaction version="2.0.0">
+  <trn:id type="guid">t2</trn:id>
+  <trn:currency><cmdty:space>CURRENCY</cmdty:space><cmdty:id>USD</cmdty:id></trn:currency>
+  <trn:date-posted><ts:date>2026-01-10 10:59:00 +0000</ts:date></trn:date-posted>
+  <trn:description>Buy euros</trn:description>
+  <trn:splits>
+    <trn:split><split:id type="guid">s3</split:id><split:value>-11000/100</split:value><split:quantity>-11000/100</split:quantity><split:account type="guid">chk</split:account></trn:split>
+    <trn:split><split:id type="guid">s4</split:id><split:value>11000/100</split:value><split:quantity>10000/100</split:quantity><split:account type="guid">eur</split:account></trn:split>
+  </trn:splits>
+</gnc:transaction>
+</gnc:book>
+</gnc-v2>
+"#;
+
+#[test]
+fn gnucash_xml_import_maps_accounts_commodities_and_prices() {
+    let home = tempfile::tempdir().expect("tempdir");
+    let book = home.path().join("book.gnucash");
+    std::fs::write(&book, GNUCASH_XML).expect("write book");
+
+    let out = run_ok_out(&home, &["import", "gnucash", book.to_str().unwrap()]);
+    assert!(out.contains("2 event(s) written"), "import output: {out}");
+    assert!(out.contains("1 rate(s) stored"), "import output: {out}");
+
+    let out = run_ok_out(&home, &["balance"]);
+    assert!(
+        out.contains("assets:checking-account\tUSD\t890"),
+        "balance: {out}"
+    );
+    assert!(
+        out.contains("assets:euro-wallet\tEUR\t100"),
+        "balance: {out}"
+    );
+    assert!(out.contains("income:salary\tUSD\t-1000"), "balance: {out}");
+    assert!(
+        out.contains("equity:conversion\tEUR\t-100"),
+        "balance: {out}"
+    );
+    assert!(
+        out.contains("equity:conversion\tUSD\t110"),
+        "balance: {out}"
+    );
+
+    let out = run_ok_out(&home, &["rate", "get", "@gnucash", "EUR", "USD"]);
+    assert!(out.contains("1.1"), "rate output: {out}");
+
+    // Re-importing the same book is a no-op for events.
+    let out = run_ok_out(&home, &["import", "gnucash", book.to_str().unwrap()]);
+    assert!(
+        out.contains("0 event(s) written, 2 already present"),
+        "import output: {out}"
+    );
+}
+
+const LEDGER_JOURNAL: &str = r#"; Personal journal
+account Assets:Checking
+
+P 2026/01/10 EUR 1.10 USD
+
+2026-01-05 * (1001) January pay  ; :salary:
+    Assets:Checking Account      $1,000.00
+    Income:Salary
+
+2026/01/06 Groceries
+    Expenses:Food                45.20 USD  ; trip:lisbon
+    Assets:Checking Account
+
+2026-01-06 Groceries
+    Expenses:Food                45.20 USD  ; trip:lisbon
+    Assets:Checking Account
+
+2026-01-10 Buy euros
+    Assets:Euro Wallet           100 EUR @ 1.10 USD
+    Assets:Checking Account
+
+~ monthly
+    Expenses:Rent    500 USD
+    Assets:Checking Account
+"#;
+
+#[test]
+fn ledger_journal_import_balances_elided_postings_costs_and_tags() {
+    let home = tempfile::tempdir().expect("tempdir");
+    let journal = home.path().join("2026.journal");
+    std::fs::write(&journal, LEDGER_JOURNAL).expect("write journal");
+
+    let out = run_ok_out(&home, &["import", "ledger", journal.to_str().unwrap()]);
+    assert!(out.contains("4 event(s) written"), "import output: {out}");
+    assert!(out.contains("1 rate(s) stored"), "import output: {out}");
+
+    let out = run_ok_out(&home, &["balance"]);
+    assert!(
+        out.contains("assets:checking-account\tUSD\t799.60"),
+        "balance: {out}"
+    );
+    assert!(out.contains("expenses:food\tUSD\t90.40"), "balance: {out}");
+    assert!(
+        out.contains("assets:euro-wallet\tEUR\t100"),
+        "balance: {out}"
+    );
+    assert!(
+        out.contains("equity:conversion\tEUR\t-100"),
+        "balance: {out}"
+    );
+    assert!(!out.contains("expenses:rent"), "balance: {out}");
+
+    let out = run_ok_out(&home, &["report", "--tag", "trip:lisbon"]);
+    assert_eq!(out.matches("\timport\t").count(), 2, "report: {out}");
+    let out = run_ok_out(&home, &["report", "--tag", "salary"]);
+    assert_eq!(out.matches("\timport\t").count(), 1, "report: {out}");
+
+    let out = run_ok_out(&home, &["import", "ledger", journal.to_str().unwrap()]);
+    assert!(
+        out.contains("0 event(s) written, 4 already present"),
+        "import output: {out}"
+    );
+}
+
+#[test]
+fn ledger_value_dates_become_effective_at_when_configured() {
+    let home = tempfile::tempdir().expect("tempdir");
+    let journal = home.path().join("bank.journal");
+    std::fs::write(
+        &journal,
+        "2026-01-30=02-02 * Transfer to savings\n    assets:savings  100 USD\n    assets:checking\n",
+    )
+    .expect("write journal");
+
+    run_ok_out(&home, &["config", "set", "import_date", "value"]);
+    run_ok_out(&home, &["import", "ledger", journal.to_str().unwrap()]);
+    let out = run_ok_out(&home, &["register", "assets:savings"]);
+    assert!(
+        out.starts_with("2026-02-02T00:00:00+00:00"),
+        "register: {out}"
+    );
+
+    // The import id keeps the posting date, so switching back does not re-import.
+    run_ok_out(&home, &["config", "set", "import_date", "posting"]);
+    let out = run_ok_out(&home, &["import", "ledger", journal.to_str().unwrap()]);
+    assert!(
+        out.contains("0 event(s) written, 1 already present"),
+        "import output: {out}"
+    );
+
+    // A yearless value date across New Year belongs to the following year.
+    let year_end = home.path().join("year-end.journal");
+    std::fs::write(
+        &year_end,
+        "2025-12-30=01-02 * Card payment\n    liabilities:card  50 USD\n    assets:checking\n",
+    )
+    .expect("write journal");
+    run_ok_out(&home, &["config", "set", "import_date", "value"]);
+    run_ok_out(&home, &["import", "ledger", year_end.to_str().unwrap()]);
+    let out = run_ok_out(&home, &["register", "liabilities:card"]);
+    assert!(
+        out.starts_with("2026-01-02T00:00:00+00:00"),
+        "register: {out}"
+    );
+}
+
+#[test]
+fn exported_events_restore_into_a_fresh_home() {
+    let home = tempfile::tempdir().expect("tempdir");
+    for (amount, day) in [("100", "2026-01-10"), ("40", "2026-02-10")] {
+        run_ok_out(
+            &home,
+            &[
+                "deposit",
+                amount,
+                "USD",
+                "--to",
+                "assets:usd",
+                "--from",
+                "income:salary",
+                "--effective-at",
+                &format!("{day}T12:00:00Z"),
+            ],
+        );
+    }
+
+    let backup = home.path().join("backup.jsonl");
+    run_ok_out(
+        &home,
+        &["export", "events", "--out", backup.to_str().unwrap()],
+    );
+    let lines = std::fs::read_to_string(&backup).expect("read backup");
+    assert_eq!(lines.lines().count(), 2, "backup: {lines}");
+    let first: serde_json::Value =
+        serde_json::from_str(lines.lines().next().unwrap()).expect("jsonl line");
+    assert!(first["id"].is_string() && first["payload"]["postings"].is_array());
+
+    let january = run_ok_out(
+        &home,
+        &[
+            "export",
+            "events",
+            "--range",
+            "2026-01-01..2026-01-31",
+            "--format",
+            "jsonl",
+        ],
+    );
+    assert_eq!(january.lines().count(), 1, "range export: {january}");
+
+    let restored = tempfile::tempdir().expect("tempdir");
+    let out = run_ok_out(&restored, &["import", "events", backup.to_str().unwrap()]);
+    assert!(
+        out.contains("Imported 2 event(s)") && out.contains("0 already present"),
+        "import output: {out}"
+    );
+    let out = run_ok_out(&restored, &["balance", "assets"]);
+    assert!(out.contains("assets:usd\tUSD\t140"), "balance: {out}");
+
+    let out = run_ok_out(&restored, &["import", "events", backup.to_str().unwrap()]);
+    assert!(
+        out.contains("Imported 0 event(s)") && out.contains("2 already present"),
+        "re-import output: {out}"
+    );
+
+    // Another workspace's backup is not mixed into this one.
+    let other = tempfile::tempdir().expect("tempdir");
+    run_ok_out(&other, &["ws", "add", "side"]);
+    run_ok_out(&other, &["ws", "checkout", "side"]);
+    let out = run_ok_out(&other, &["import", "events", backup.to_str().unwrap()]);
+    assert!(
+        out.contains("Imported 0 event(s)") && out.contains("2 skipped from other workspaces"),
+        "other workspace import: {out}"
+    );
+
+    // A file over the sync quotas needs --accept-large, like a sync.
+    let small = tempfile::tempdir().expect("tempdir");
+    run_ok_out(&small, &["config", "set", "sync_max_events", "1"]);
+    let mut cmd = bankero_cmd();
+    cmd.env("BANKERO_HOME", small.path())
+        .args(["import", "events", backup.to_str().unwrap()]);
+    let out = cmd.assert().failure().get_output().stderr.clone();
+    let err = String::from_utf8(out).expect("utf8 stderr");
+    assert!(
+        err.contains("2 new event(s)") && err.contains("sync_max_events (1)"),
+        "quota error: {err}"
+    );
+    let out = run_ok_out(
+        &small,
+        &[
+            "import",
+            "events",
+            backup.to_str().unwrap(),
+            "--accept-large",
+        ],
+    );
+    assert!(
+        out.contains("Imported 2 event(s)"),
+        "accepted import: {out}"
+    );
+}
@@ -37,6 +37,11 @@ fn run_fail_out(home: &tempfile::TempDir, args: &[&str]) -> String {
 fn spawn_expose(home: &tempfile::TempDir) -> (Child, mpsc::Receiver<String>) {
     let mut cmd = bankero_cmd();
     cmd.env("BANKERO_HOME", home.path());
+    // Windows-style identity, so the peer label is checked the same way on every runner.
+    cmd.env_remove("USER")
+        .env_remove("HOSTNAME")
+        .env("USERNAME", "ana")
+        .env("COMPUTERNAME", "FAMILY-PC");
     cmd.args([
         "sync",
         "expose",
@@ -187,6 +192,7 @@ fn lan_sync_isolated_and_deterministic_in_ci() {
         ],
     );
     assert!(out.contains("@1"), "discover output: {out}");
+    assert!(out.contains("ana@FAMILY-PC"), "discover output: {out}");
 
     // Sync from B to A and back.
     println!("[lan_sync_ci] syncing via handle @1 all");
@@ -283,3 +289,107 @@ fn lan_sync_expose_prompts_and_keeps_listening() {
     let _ = child.wait();
     println!("[lan_sync_ci] interactive prompt test complete");
 }
+
+#[test]
+fn lan_sync_expose_refuses_a_revoked_device() {
+    let lost = tempfile::tempdir().expect("tempdir lost");
+    let laptop = tempfile::tempdir().expect("tempdir laptop");
+
+    let login = run_ok_out(&lost, &["login", "--name", "lost_phone"]);
+    let lost_id = login
+        .lines()
+        .find_map(|l| l.strip_prefix("device_id\t"))
+        .expect("device_id line")
+        .to_string();
+    run_ok(&laptop, &["sync", "revoke-device", &lost_id]);
+
+    let (mut child, rx) = spawn_expose(&laptop);
+    let lan_udp = wait_for_lan_udp(&rx);
+    let out = run_ok_out(
+        &lost,
+        &[
+            "sync",
+            "discover",
+            "--target",
+            &lan_udp,
+            "--timeout-ms",
+            "800",
+        ],
+    );
+    assert!(out.contains("@1"), "discover output: {out}");
+
+    let out = run_fail_out(&lost, &["sync", "@1", "all"]);
+    assert!(
+        out.contains(&format!("Device {lost_id} was revoked")),
+        "sync output: {out}"
+    );
+
+    let _ = child.kill();
+    let _ = child.wait();
+}
+
+#[test]
+fn lan_sync_carries_attachments_both_ways() {
+    let home_a = tempfile::tempdir().expect("tempdir home_a");
+    let home_b = tempfile::tempdir().expect("tempdir home_b");
+
+    let attach = |home: &tempfile::TempDir, file: &str, body: &[u8]| {
+        let path = home.path().join(file);
+        std::fs::write(&path, body).expect("write attachment");
+        let out = run_ok_out(
+            home,
+            &[
+                "buy",
+                "external:store",
+                "10",
+                "USD",
+                "--from",
+                "assets:bank",
+                "--attach",
+                path.to_str().expect("utf8 path"),
+            ],
+        );
+        out.split_whitespace()
+            .nth(2)
+            .expect("event id in output")
+            .to_string()
+    };
+    let from_a = attach(&home_a, "a.jpg", b"receipt photo from A");
+    let from_b = attach(&home_b, "b.pdf", b"invoice from B");
+
+    let (mut child, rx) = spawn_expose(&home_a);
+    let lan_udp = wait_for_lan_udp(&rx);
+    run_ok(
+        &home_b,
+        &[
+            "sync",
+            "discover",
+            "--target",
+            &lan_udp,
+            "--timeout-ms",
+            "800",
+        ],
+    );
+    let out = run_ok_out(&home_b, &["sync", "@1", "all"]);
+    assert!(out.contains("- imported attachments: 1"), "sync: {out}");
+
+    let start = Instant::now();
+    while child.try_wait().expect("try_wait").is_none() {
+        if start.elapsed() > Duration::from_secs(3) {
+            let _ = child.kill();
+            panic!("expose did not exit in time");
+        }
+        std::thread::sleep(Duration::from_millis(50));
+    }
+
+    let out = run_ok_out(&home_b, &["event", "attachments", &from_a]);
+    assert!(
+        out.contains("a.jpg") && !out.contains("not on this device"),
+        "B: {out}"
+    );
+    let out = run_ok_out(&home_a, &["event", "attachments", &from_b]);
+    assert!(
+        out.contains("b.pdf") && !out.contains("not on this device"),
+        "A: {out}"
+    );
+}
@@ -1,4 +1,5 @@
 use assert_cmd::prelude::*;
+use predicates::prelude::*;
 use std::process::Command;
 
 fn bankero_cmd() -> Command {
@@ -109,3 +110,335 @@ fn balance_shows_reserved_piggies_and_effective_balance() {
         "balance output: {out}"
     );
 }
+
+#[test]
+fn surplus_sweep_task_reserves_balance_above_floor_into_piggy() {
+    let home = tempfile::tempdir().expect("tempdir");
+
+    run_ok(
+        &home,
+        &[
+            "piggy",
+            "create",
+            "Vacation",
+            "1000",
+            "USD",
+            "--from",
+            "assets:bank",
+        ],
+    );
+    let deposit = |amount: &str, t: &str| {
+        run_ok(
+            &home,
+            &[
+                "deposit",
+                amount,
+                "USD",
+                "--from",
+                "income:salary",
+                "--to",
+                "assets:bank",
+                "--effective-at",
+                t,
+            ],
+        );
+    };
+    deposit("1200", "2026-01-10T09:00:00Z");
+    deposit("600", "2026-02-10T09:00:00Z");
+
+    let out = run_ok_out(
+        &home,
+        &[
+            "piggy",
+            "update",
+            "Vacation",
+            "--fund-surplus-over",
+            "500",
+            "USD",
+            "--start",
+            "2026-01-31T23:59:59Z",
+        ],
+    );
+    assert!(
+        out.contains("task 'piggy-surplus-vacation', next run 2026-01-31T23:59:59+00:00"),
+        "update output: {out}"
+    );
+
+    // Jan: 1200 - 500 = 700. Feb: 1800 - 500 - 700 reserved = 600, capped at the 300 left.
+    let out = run_ok_out(
+        &home,
+        &[
+            "task",
+            "run",
+            "piggy-surplus-vacation",
+            "--as-of",
+            "2026-02-28T23:59:59Z",
+        ],
+    );
+    assert!(
+        out.contains("wrote 0 event(s), 2 piggy fund(s)"),
+        "task run output: {out}"
+    );
+
+    let out = run_ok_out(&home, &["piggy", "status", "Vacation"]);
+    assert!(
+        out.contains("100% (1000 / 1000 USD)"),
+        "status output: {out}"
+    );
+
+    let out = run_ok_out(&home, &["balance", "assets:bank"]);
+    assert!(
+        out.contains("assets:bank\tUSD\t-1000"),
+        "balance output: {out}"
+    );
+    assert!(
+        out.contains("assets:bank\tUSD\t800"),
+        "balance output: {out}"
+    );
+
+    let out = run_ok_out(
+        &home,
+        &["piggy", "update", "Vacation", "--clear-fund-surplus"],
+    );
+    assert!(out.contains("Cleared surplus sweep"), "clear output: {out}");
+    let out = run_ok_out(&home, &["task", "list"]);
+    assert!(
+        out.contains("piggy-surplus-vacation\tpiggy_surplus\tmonthly\tfalse"),
+        "task list: {out}"
+    );
+}
+
+#[test]
+fn piggy_allocate_distributes_by_priority_and_weight() {
+    let home = tempfile::tempdir().expect("tempdir");
+
+    for args in [
+        ["Emergency", "100", "--priority", "1", "--group", "safety"],
+        ["Beach", "500", "--priority", "2", "--group", "vacations"],
+        [
+            "Mountains",
+            "500",
+            "--priority",
+            "2",
+            "--group",
+            "vacations",
+        ],
+    ] {
+        let mut full = vec![
+            "piggy",
+            "create",
+            args[0],
+            args[1],
+            "USD",
+            "--from",
+            "assets:bank",
+        ];
+        full.extend_from_slice(&args[2..]);
+        run_ok(&home, &full);
+    }
+    run_ok(&home, &["piggy", "update", "Mountains", "--weight", "2"]);
+
+    let out = run_ok_out(&home, &["piggy", "allocate", "400", "USD"]);
+    assert!(
+        out.contains("Emergency\tUSD\t100"),
+        "allocate output: {out}"
+    );
+    assert!(out.contains("Beach\tUSD\t100"), "allocate output: {out}");
+    assert!(
+        out.contains("Mountains\tUSD\t200"),
+        "allocate output: {out}"
+    );
+    assert!(
+        out.contains("Allocated 400 of 400 USD."),
+        "allocate output: {out}"
+    );
+
+    // Only the vacations group; Beach fills up and the rest goes to Mountains.
+    let out = run_ok_out(
+        &home,
+        &["piggy", "allocate", "800", "USD", "--group", "vacations"],
+    );
+    assert!(out.contains("Beach\tUSD\t400"), "allocate output: {out}");
+    assert!(
+        out.contains("Mountains\tUSD\t300"),
+        "allocate output: {out}"
+    );
+    assert!(
+        out.contains("Unallocated: 100 USD"),
+        "allocate output: {out}"
+    );
+    assert!(!out.contains("Emergency"), "allocate output: {out}");
+
+    let list = run_ok_out(&home, &["piggy", "list"]);
+    assert!(list.contains("\t2\tvacations"), "list output: {list}");
+}
+
+#[test]
+fn piggy_status_projects_completion_from_recent_pace() {
+    let home = tempfile::tempdir().expect("tempdir");
+
+    run_ok(
+        &home,
+        &[
+            "piggy",
+            "create",
+            "New Car",
+            "5000",
+            "USD",
+            "--from",
+            "assets:savings",
+        ],
+    );
+    for at in [
+        "2026-01-15T12:00:00Z",
+        "2026-02-15T12:00:00Z",
+        "2026-03-15T12:00:00Z",
+    ] {
+        run_ok(
+            &home,
+            &["piggy", "fund", "New Car", "1000", "--effective-at", at],
+        );
+    }
+
+    let out = run_ok_out(
+        &home,
+        &[
+            "piggy",
+            "status",
+            "New Car",
+            "--as-of",
+            "2026-03-31T00:00:00Z",
+        ],
+    );
+    assert!(out.contains("60%"), "status output: {out}");
+    assert!(
+        out.contains("ETA May 2026 at current pace (1000 USD/month over the last 3 month(s))"),
+        "status output: {out}"
+    );
+
+    // A longer window halves the pace and pushes the estimate out.
+    let out = run_ok_out(
+        &home,
+        &[
+            "piggy",
+            "status",
+            "New Car",
+            "--as-of",
+            "2026-03-31T00:00:00Z",
+            "--pace-months",
+            "6",
+        ],
+    );
+    assert!(out.contains("ETA Jul 2026"), "status output: {out}");
+
+    // An empty window is rejected before any status is printed.
+    bankero_cmd()
+        .env("BANKERO_HOME", home.path())
+        .args(["piggy", "status", "New Car", "--pace-months", "0"])
+        .assert()
+        .failure()
+        .stdout("")
+        .stderr(predicate::str::contains("--pace-months"));
+}
+
+#[test]
+fn budget_sweep_task_moves_month_end_remainder_into_piggy() {
+    let home = tempfile::tempdir().expect("tempdir");
+    run_ok(
+        &home,
+        &[
+            "budget",
+            "create",
+            "Food",
+            "300",
+            "USD",
+            "--category",
+            "expenses:food",
+        ],
+    );
+    run_ok(
+        &home,
+        &[
+            "piggy",
+            "create",
+            "Trip",
+            "250",
+            "USD",
+            "--from",
+            "assets:bank",
+        ],
+    );
+    for (amount, at) in [
+        ("120", "2026-03-10T12:00:00Z"),
+        ("280", "2026-04-10T12:00:00Z"),
+    ] {
+        run_ok(
+            &home,
+            &[
+                "buy",
+                "external:market",
+                amount,
+                "USD",
+                "--from",
+                "assets:bank",
+                "--category",
+                "expenses:food",
+                "--effective-at",
+                at,
+            ],
+        );
+    }
+
+    let out = run_ok_out(
+        &home,
+        &[
+            "budget",
+            "update",
+            "Food",
+            "--sweep-to",
+            "Trip",
+            "--start",
+            "2026-03-31T23:59:59Z",
+        ],
+    );
+    assert!(out.contains("task 'budget-sweep-food'"), "update: {out}");
+
+    // March leaves 180, April 20; May's 300 is capped by the 50 left on the piggy.
+    let out = run_ok_out(
+        &home,
+        &[
+            "task",
+            "run",
+            "budget-sweep-food",
+            "--as-of",
+            "2026-05-31T23:59:59Z",
+        ],
+    );
+    assert!(
+        out.contains("wrote 3 event(s), 3 piggy fund(s)"),
+        "task run: {out}"
+    );
+    let out = run_ok_out(&home, &["piggy", "status", "Trip"]);
+    assert!(out.contains("100%"), "status: {out}");
+
+    let report = run_ok_out(&home, &["report", "--month", "2026-03"]);
+    let sweep = report
+        .lines()
+        .find(|l| l.contains("budget_sweep"))
+        .unwrap_or_else(|| panic!("no sweep event: {report}"));
+    let id = sweep.split('\t').nth(2).expect("event id");
+    let out = run_ok_out(&home, &["show", id]);
+    assert!(
+        out.contains(
+            "note:         Budget 'Food' 2026-03: 180 of 300 USD left, 180 to piggy 'Trip'"
+        ),
+        "show: {out}"
+    );
+
+    run_ok(&home, &["budget", "update", "Food", "--clear-sweep"]);
+    let out = run_ok_out(&home, &["task", "list"]);
+    assert!(
+        out.contains("budget-sweep-food\tbudget_sweep\tmonthly\tfalse"),
+        "tasks: {out}"
+    );
+}
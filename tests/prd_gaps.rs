@@ -1,6 +1,4 @@
-//! These tests document gaps vs PRD/README examples.
-//! They are ignored for now so we can freeze current behavior
-//! while keeping a clear target for future fixes.
+//! These tests pin PRD/README examples that used to be gaps in the CLI.
 
 use assert_cmd::prelude::*;
 use std::process::Command;
@@ -10,7 +8,7 @@ fn prd_example_buy_without_payee_should_work() {
     let home = tempfile::tempdir().expect("tempdir");
 
     // PRD example: `bankero buy 500 USD --from assets:bank --to expenses:rent:450 --to expenses:water:50`
-    // Current implementation requires a payee/target argument before amount.
+    // The 2-arg form has no payee; the splits name where the money went.
     let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("bankero"));
     cmd.env("BANKERO_HOME", home.path());
     cmd.args([
@@ -26,4 +24,15 @@ fn prd_example_buy_without_payee_should_work() {
     ]);
 
     cmd.assert().success();
+
+    let out = Command::new(assert_cmd::cargo::cargo_bin!("bankero"))
+        .env("BANKERO_HOME", home.path())
+        .arg("balance")
+        .output()
+        .expect("balance");
+    assert!(out.status.success());
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    assert!(stdout.contains("assets:bank\tUSD\t-500"), "{stdout}");
+    assert!(stdout.contains("expenses:rent\tUSD\t450"), "{stdout}");
+    assert!(stdout.contains("expenses:water\tUSD\t50"), "{stdout}");
 }
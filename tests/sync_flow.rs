@@ -1,4 +1,5 @@
 use assert_cmd::prelude::*;
+use sha2::Digest;
 use std::process::Command;
 
 fn bankero_cmd() -> Command {
@@ -78,3 +79,754 @@ fn sync_transfers_events_between_two_homes() {
 
     println!("[sync_flow] complete");
 }
+
+#[test]
+fn revoked_device_events_are_rejected_on_import() {
+    let lost = tempfile::tempdir().expect("tempdir lost");
+    let laptop = tempfile::tempdir().expect("tempdir laptop");
+    let newcomer = tempfile::tempdir().expect("tempdir newcomer");
+    let sync_dir = tempfile::tempdir().expect("tempdir sync_dir");
+    let dir = sync_dir.path().to_str().expect("utf8 path");
+    for home in [&lost, &laptop, &newcomer] {
+        run_ok(home, &["login", "--sync-dir", dir]);
+    }
+    let deposit = |home: &tempfile::TempDir, amount: &str| {
+        run_ok(
+            home,
+            &[
+                "deposit",
+                amount,
+                "USD",
+                "--to",
+                "assets:cash",
+                "--from",
+                "income:salary",
+            ],
+        );
+    };
+
+    deposit(&lost, "100");
+    run_ok(&lost, &["sync", "now"]);
+    run_ok(&laptop, &["sync", "now"]);
+
+    let status = run_ok_out(&lost, &["sync", "status"]);
+    let lost_id = status
+        .lines()
+        .find_map(|l| l.strip_prefix("device_id\t"))
+        .expect("device_id line")
+        .to_string();
+    let out = run_ok_out(&laptop, &["sync", "revoke-device", &lost_id[..8]]);
+    assert!(
+        out.contains(&format!("Revoked device {lost_id}")) && out.contains("kept 1 event(s)"),
+        "{out}"
+    );
+    run_ok(&laptop, &["sync", "now"]);
+
+    // Whoever has the phone keeps writing; none of it is accepted any more.
+    deposit(&lost, "5000");
+    run_ok(&lost, &["sync", "now"]);
+    let out = run_ok_out(&laptop, &["sync", "now"]);
+    assert!(
+        out.contains("rejected\t1 event(s) from revoked devices"),
+        "{out}"
+    );
+    let balance = run_ok_out(&laptop, &["balance", "assets:cash"]);
+    assert!(balance.contains("assets:cash\tUSD\t100"), "{balance}");
+
+    // A device joining later keeps the pre-revocation history but not the rest.
+    let out = run_ok_out(&newcomer, &["sync", "now"]);
+    assert!(
+        out.contains("rejected\t1 event(s) from revoked devices"),
+        "{out}"
+    );
+    let balance = run_ok_out(&newcomer, &["balance", "assets:cash"]);
+    assert!(balance.contains("assets:cash\tUSD\t100"), "{balance}");
+
+    let mut again = bankero_cmd();
+    again.env("BANKERO_HOME", laptop.path());
+    again.args(["sync", "revoke-device", &lost_id]);
+    again.assert().failure();
+}
+
+#[test]
+fn synced_rates_remember_which_device_they_came_from() {
+    let phone = tempfile::tempdir().expect("tempdir phone");
+    let laptop = tempfile::tempdir().expect("tempdir laptop");
+    let tablet = tempfile::tempdir().expect("tempdir tablet");
+    let sync_dir = tempfile::tempdir().expect("tempdir sync_dir");
+    let dir = sync_dir.path().to_str().expect("utf8 path");
+    for (home, name) in [(&phone, "phone"), (&laptop, "laptop"), (&tablet, "tablet")] {
+        run_ok(home, &["login", "--sync-dir", dir, "--name", name]);
+    }
+
+    run_ok(
+        &phone,
+        &[
+            "rate",
+            "set",
+            "@bcv",
+            "USD",
+            "VES",
+            "36.5",
+            "--as-of",
+            "2026-03-01T00:00:00Z",
+        ],
+    );
+    run_ok(&phone, &["sync", "now"]);
+    run_ok(&laptop, &["sync", "now"]);
+    run_ok(
+        &laptop,
+        &[
+            "rate",
+            "set",
+            "@bcv",
+            "USD",
+            "VES",
+            "3650",
+            "--as-of",
+            "2026-03-02T00:00:00Z",
+        ],
+    );
+    run_ok(&laptop, &["sync", "now"]);
+    run_ok(&tablet, &["sync", "now"]);
+
+    let list = |home: &tempfile::TempDir| {
+        run_ok_out(
+            home,
+            &[
+                "rate",
+                "list",
+                "@bcv",
+                "USD",
+                "VES",
+                "--format",
+                "tsv",
+                "--verbose",
+            ],
+        )
+    };
+    let laptop_rates = list(&laptop);
+    assert!(
+        laptop_rates.contains("2026-03-02T00:00:00+00:00\t3650\tmanual"),
+        "{laptop_rates}"
+    );
+    assert!(
+        laptop_rates.contains("2026-03-01T00:00:00+00:00\t36.5\tsynced from phone (manual)"),
+        "{laptop_rates}"
+    );
+
+    // The tablet hears about both through the laptop, but each keeps its origin.
+    let tablet_rates = list(&tablet);
+    assert!(
+        tablet_rates.contains("3650\tsynced from laptop (manual)"),
+        "{tablet_rates}"
+    );
+    assert!(
+        tablet_rates.contains("36.5\tsynced from phone (manual)"),
+        "{tablet_rates}"
+    );
+
+    // Syncing our own rate back does not relabel it.
+    run_ok(&phone, &["sync", "now"]);
+    let phone_rates = list(&phone);
+    assert!(phone_rates.contains("36.5\tmanual"), "{phone_rates}");
+
+    let plain = run_ok_out(
+        &phone,
+        &["rate", "list", "@bcv", "USD", "VES", "--format", "tsv"],
+    );
+    assert!(!plain.contains("manual"), "{plain}");
+}
+
+/// The one events.jsonl a device exported into the sync folder.
+fn exported_events(sync_dir: &std::path::Path) -> std::path::PathBuf {
+    let mut stack = vec![sync_dir.to_path_buf()];
+    while let Some(dir) = stack.pop() {
+        for entry in std::fs::read_dir(&dir).expect("read_dir") {
+            let path = entry.expect("entry").path();
+            if path.is_dir() {
+                stack.push(path);
+            } else if path.file_name().is_some_and(|n| n == "events.jsonl") {
+                return path;
+            }
+        }
+    }
+    panic!("no events.jsonl under {}", sync_dir.display());
+}
+
+#[test]
+fn a_synced_supersedes_key_not_written_by_event_amend_hides_nothing() {
+    let home = tempfile::tempdir().expect("tempdir home");
+    let sync_dir = tempfile::tempdir().expect("tempdir sync_dir");
+    let dir = sync_dir.path().to_str().expect("utf8 path");
+    run_ok(&home, &["login", "--sync-dir", dir]);
+    run_ok(
+        &home,
+        &[
+            "deposit",
+            "100",
+            "USD",
+            "--from",
+            "income:salary",
+            "--to",
+            "assets:bank",
+        ],
+    );
+    run_ok(&home, &["sync", "now"]);
+
+    // Another device's event names ours in `supersedes` without being an amendment.
+    let exported = std::fs::read_to_string(exported_events(sync_dir.path())).expect("read");
+    let original: serde_json::Value =
+        serde_json::from_str(exported.lines().next().expect("one event")).expect("json");
+    let original_id = original["id"].as_str().expect("id").to_string();
+    let devices = exported_events(sync_dir.path())
+        .parent()
+        .and_then(|p| p.parent())
+        .expect("devices dir")
+        .to_path_buf();
+    let device = "d0000000-0000-4000-8000-000000000000";
+    let mut event = original.clone();
+    event["id"] = serde_json::json!("00000000-0000-4000-8000-000000000001");
+    event["payload"]["device_id"] = serde_json::json!(device);
+    event["payload"]["metadata"]["supersedes"] = serde_json::json!(original_id);
+    let peer = devices.join(device);
+    std::fs::create_dir_all(&peer).expect("mkdir");
+    std::fs::write(peer.join("events.jsonl"), format!("{event}\n")).expect("write");
+    run_ok(&home, &["sync", "now"]);
+
+    let bank = run_ok_out(&home, &["balance", "assets:bank"]);
+    assert!(bank.contains("assets:bank\tUSD\t200"), "{bank}");
+    let history = run_ok_out(&home, &["history", &original_id[..8]]);
+    assert!(!history.contains("supersedes"), "{history}");
+}
+
+#[test]
+fn history_shows_divergent_corrections_from_two_devices_and_resolves_them() {
+    let home = tempfile::tempdir().expect("tempdir home");
+    let sync_dir = tempfile::tempdir().expect("tempdir sync_dir");
+    let dir = sync_dir.path().to_str().expect("utf8 path");
+    run_ok(&home, &["login", "--sync-dir", dir, "--name", "laptop"]);
+    run_ok(
+        &home,
+        &[
+            "buy",
+            "expenses:food",
+            "120",
+            "USD",
+            "--from",
+            "assets:cash",
+            "-m",
+            "groceries",
+        ],
+    );
+    run_ok(&home, &["sync", "now"]);
+
+    // Two offline devices each corrected the purchase before syncing again.
+    let exported = std::fs::read_to_string(exported_events(sync_dir.path())).expect("read");
+    let original: serde_json::Value =
+        serde_json::from_str(exported.lines().next().expect("one event")).expect("json");
+    let original_id = original["id"].as_str().expect("id").to_string();
+    let devices = exported_events(sync_dir.path())
+        .parent()
+        .and_then(|p| p.parent())
+        .expect("devices dir")
+        .to_path_buf();
+    let mut correction_ids = Vec::new();
+    for (i, (amount, created_at)) in [
+        ("12", "2030-01-01T10:00:00Z"),
+        ("102", "2030-01-01T11:00:00Z"),
+    ]
+    .into_iter()
+    .enumerate()
+    {
+        let id = format!("{i}0000000-0000-4000-8000-00000000000{i}");
+        let device = format!("d{i}000000-0000-4000-8000-000000000000");
+        let mut event = original.clone();
+        event["id"] = serde_json::json!(id);
+        let payload = &mut event["payload"];
+        payload["device_id"] = serde_json::json!(device);
+        payload["created_at"] = serde_json::json!(created_at);
+        payload["metadata"]["supersedes"] = serde_json::json!(original_id);
+        payload["metadata"]["correction"] = serde_json::json!("amend");
+        for posting in payload["postings"].as_array_mut().expect("postings") {
+            let negative = posting["amount"].as_str().expect("amount").starts_with('-');
+            posting["amount"] = serde_json::json!(if negative {
+                format!("-{amount}")
+            } else {
+                amount.to_string()
+            });
+        }
+        let peer = devices.join(&device);
+        std::fs::create_dir_all(&peer).expect("mkdir");
+        std::fs::write(peer.join("events.jsonl"), format!("{event}\n")).expect("write");
+        correction_ids.push(id);
+    }
+    run_ok(&home, &["sync", "now"]);
+
+    // Any id in the chain shows the whole chain; the newest correction counts meanwhile.
+    let history = run_ok_out(&home, &["history", &correction_ids[0][..8]]);
+    assert!(
+        history.contains(&format!("History of {original_id}")),
+        "{history}"
+    );
+    assert!(
+        history.contains(&format!("  {}", &original_id[..8]))
+            && history.contains("\toriginal\t120 USD"),
+        "{history}"
+    );
+    // Both corrections are indented under the original they replace.
+    for id in &correction_ids {
+        assert!(history.contains(&format!("   {}\t", &id[..8])), "{history}");
+    }
+    let current = history
+        .lines()
+        .find(|l| l.starts_with('*') && !l.starts_with("* ="))
+        .expect("current version");
+    assert!(current.contains(&correction_ids[1][..8]), "{history}");
+    assert!(current.contains("102 USD"), "{history}");
+    assert!(
+        history.contains(&format!(
+            "Conflict: {} was corrected on more than one device",
+            &original_id[..8]
+        )),
+        "{history}"
+    );
+
+    let mut resolve = assert_cmd::Command::from_std(bankero_cmd());
+    resolve.env("BANKERO_HOME", home.path());
+    resolve.args(["history", &original_id, "--resolve"]);
+    let out = resolve
+        .write_stdin("1\n")
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let out = String::from_utf8(out).expect("utf8");
+    assert!(out.contains("1) 00000000"), "{out}");
+    assert!(
+        out.contains(&format!(
+            "Resolved {}: 00000000 is the canonical version",
+            &original_id[..8]
+        )),
+        "{out}"
+    );
+
+    let history = run_ok_out(&home, &["history", &original_id]);
+    assert!(!history.contains("Conflict"), "{history}");
+    let current = history
+        .lines()
+        .find(|l| l.starts_with('*') && !l.starts_with("* ="))
+        .expect("current version");
+    assert!(
+        current.contains("12 USD") && current.contains("00000000"),
+        "{history}"
+    );
+
+    let again = run_ok_out(&home, &["history", &original_id, "--resolve"]);
+    assert!(again.contains("Nothing to resolve"), "{again}");
+}
+
+#[test]
+fn a_void_of_a_version_amended_on_another_device_voids_the_current_version() {
+    let laptop = tempfile::tempdir().expect("tempdir laptop");
+    let phone = tempfile::tempdir().expect("tempdir phone");
+    let sync_dir = tempfile::tempdir().expect("tempdir sync_dir");
+    let dir = sync_dir.path().to_str().expect("utf8 path");
+    run_ok(&laptop, &["login", "--sync-dir", dir, "--name", "laptop"]);
+    run_ok(&phone, &["login", "--sync-dir", dir, "--name", "phone"]);
+
+    let out = run_ok_out(
+        &laptop,
+        &[
+            "buy",
+            "expenses:food",
+            "120",
+            "USD",
+            "--from",
+            "assets:cash",
+            "--effective-at",
+            "2026-02-25T12:00:00Z",
+        ],
+    );
+    let original = out.split_whitespace().nth(2).expect("event id").to_string();
+    run_ok(&laptop, &["sync", "now"]);
+    run_ok(&phone, &["sync", "now"]);
+
+    // Before they sync again, the laptop corrects the amount and the phone voids the
+    // version it knows.
+    run_ok(
+        &laptop,
+        &["event", "amend", &original[..8], "--amount", "40"],
+    );
+    run_ok(&phone, &["event", "void", &original[..8]]);
+    run_ok(&laptop, &["sync", "now"]);
+    run_ok(&phone, &["sync", "now"]);
+    run_ok(&laptop, &["sync", "now"]);
+
+    for home in [&laptop, &phone] {
+        let out = run_ok_out(home, &["balance", "expenses:food"]);
+        assert!(out.contains("expenses:food\tUSD\t0\n"), "{out}");
+        let out = run_ok_out(home, &["balance", "assets:cash"]);
+        assert!(out.contains("assets:cash\tUSD\t0\n"), "{out}");
+    }
+}
+
+#[test]
+fn piggy_edits_after_the_first_sync_reach_the_other_device() {
+    let phone = tempfile::tempdir().expect("tempdir phone");
+    let laptop = tempfile::tempdir().expect("tempdir laptop");
+    let sync_dir = tempfile::tempdir().expect("tempdir sync_dir");
+    let dir = sync_dir.path().to_str().expect("utf8 path");
+    for (home, name) in [(&phone, "phone"), (&laptop, "laptop")] {
+        run_ok(home, &["login", "--sync-dir", dir, "--name", name]);
+    }
+    run_ok(
+        &phone,
+        &[
+            "piggy",
+            "create",
+            "Car",
+            "5000",
+            "USD",
+            "--from",
+            "assets:savings",
+        ],
+    );
+    run_ok(&phone, &["sync", "now"]);
+    run_ok(&laptop, &["sync", "now"]);
+
+    let car = |home: &tempfile::TempDir| {
+        run_ok_out(home, &["piggy", "list"])
+            .lines()
+            .find(|l| l.starts_with("Car\t"))
+            .expect("Car row")
+            .to_string()
+    };
+    run_ok(
+        &laptop,
+        &[
+            "piggy",
+            "update",
+            "Car",
+            "--priority",
+            "1",
+            "--group",
+            "transport",
+        ],
+    );
+    run_ok(&laptop, &["sync", "now"]);
+    let out = run_ok_out(&phone, &["sync", "now"]);
+    assert!(out.contains("merged\t0 budget(s), 1 piggy(ies)"), "{out}");
+    assert!(car(&phone).ends_with("\t1\ttransport"), "{}", car(&phone));
+
+    // The phone's older copy in the sync folder does not undo the edit, and its own
+    // later edit wins in turn.
+    run_ok(&laptop, &["sync", "now"]);
+    assert!(car(&laptop).ends_with("\t1\ttransport"), "{}", car(&laptop));
+    run_ok(&phone, &["piggy", "update", "Car", "--priority", "2"]);
+    run_ok(&phone, &["sync", "now"]);
+    run_ok(&laptop, &["sync", "now"]);
+    assert!(car(&laptop).ends_with("\t2\ttransport"), "{}", car(&laptop));
+    assert_eq!(car(&laptop), car(&phone));
+}
+
+#[test]
+fn budgets_and_piggies_with_the_same_name_are_renamed_the_same_way_on_every_device() {
+    let phone = tempfile::tempdir().expect("tempdir phone");
+    let laptop = tempfile::tempdir().expect("tempdir laptop");
+    let sync_dir = tempfile::tempdir().expect("tempdir sync_dir");
+    let dir = sync_dir.path().to_str().expect("utf8 path");
+    for (home, name) in [(&phone, "phone"), (&laptop, "laptop")] {
+        run_ok(home, &["login", "--sync-dir", dir, "--name", name]);
+    }
+
+    // The phone creates both first, so its rows keep the names.
+    for (home, amount) in [(&phone, "300"), (&laptop, "250")] {
+        run_ok(
+            home,
+            &[
+                "budget", "create", "Food", amount, "USD", "--month", "2026-03",
+            ],
+        );
+        run_ok(
+            home,
+            &[
+                "piggy",
+                "create",
+                "Car",
+                "5000",
+                "USD",
+                "--from",
+                "assets:savings",
+            ],
+        );
+    }
+    run_ok(&laptop, &["piggy", "fund", "Car", "700", "USD"]);
+
+    run_ok(&phone, &["sync", "now"]);
+    let out = run_ok_out(&laptop, &["sync", "now"]);
+    assert!(out.contains("merged\t1 budget(s), 1 piggy(ies)"), "{out}");
+    assert!(out.contains("renamed\tbudget Food -> Food ("), "{out}");
+    assert!(out.contains("renamed\tpiggy Car -> Car ("), "{out}");
+    run_ok(&phone, &["sync", "now"]);
+
+    let report = |home: &tempfile::TempDir| {
+        let mut rows: Vec<String> = run_ok_out(home, &["budget", "report", "--month", "2026-03"])
+            .lines()
+            .filter(|l| l.starts_with("2026-03\t"))
+            .map(str::to_string)
+            .collect();
+        rows.sort();
+        rows
+    };
+    let phone_budgets = report(&phone);
+    assert_eq!(phone_budgets.len(), 2, "{phone_budgets:?}");
+    assert!(phone_budgets[0].starts_with("2026-03\tFood\tUSD\t300\t"));
+    assert!(phone_budgets[1].starts_with("2026-03\tFood ("));
+    assert!(phone_budgets[1].contains(")\tUSD\t250\t"));
+    assert_eq!(phone_budgets, report(&laptop));
+
+    let piggies = |home: &tempfile::TempDir| {
+        let mut rows: Vec<String> = run_ok_out(home, &["piggy", "list"])
+            .lines()
+            .skip(1)
+            .map(str::to_string)
+            .collect();
+        rows.sort();
+        rows
+    };
+    let phone_piggies = piggies(&phone);
+    assert_eq!(phone_piggies.len(), 2, "{phone_piggies:?}");
+    assert!(phone_piggies[0].starts_with("Car\tUSD\t5000\t0\t"));
+    assert!(phone_piggies[1].starts_with("Car ("));
+    assert!(phone_piggies[1].contains(")\tUSD\t5000\t700\t"));
+    assert_eq!(phone_piggies, piggies(&laptop));
+
+    // Nothing left to merge once both agree.
+    let again = run_ok_out(&laptop, &["sync", "now"]);
+    assert!(!again.contains("renamed"), "{again}");
+}
+
+#[test]
+fn balance_explain_shows_which_device_created_and_funded_each_reservation() {
+    let phone = tempfile::tempdir().expect("tempdir phone");
+    let laptop = tempfile::tempdir().expect("tempdir laptop");
+    let sync_dir = tempfile::tempdir().expect("tempdir sync_dir");
+    let dir = sync_dir.path().to_str().expect("utf8 path");
+    for (home, name) in [(&phone, "phone"), (&laptop, "laptop")] {
+        run_ok(home, &["login", "--sync-dir", dir, "--name", name]);
+    }
+
+    run_ok(
+        &laptop,
+        &[
+            "deposit",
+            "1000",
+            "USD",
+            "--to",
+            "assets:savings",
+            "--from",
+            "income:salary",
+        ],
+    );
+    run_ok(
+        &phone,
+        &[
+            "piggy",
+            "create",
+            "Car",
+            "5000",
+            "USD",
+            "--from",
+            "assets:savings",
+        ],
+    );
+    run_ok(&phone, &["piggy", "fund", "Car", "300", "USD"]);
+    run_ok(
+        &laptop,
+        &[
+            "budget",
+            "create",
+            "Rent",
+            "400",
+            "USD",
+            "--account",
+            "assets:savings",
+        ],
+    );
+
+    // Before syncing, the phone only sees its own piggy.
+    let before = run_ok_out(&phone, &["balance", "assets:savings", "--explain"]);
+    assert!(before.contains("Reservations:"), "{before}");
+    assert!(!before.contains("budget Rent"), "{before}");
+
+    run_ok(&phone, &["sync", "now"]);
+    run_ok(&laptop, &["sync", "now"]);
+    run_ok(&laptop, &["piggy", "fund", "Car", "200", "USD"]);
+    run_ok(&laptop, &["sync", "now"]);
+    run_ok(&phone, &["sync", "now"]);
+
+    let out = run_ok_out(&phone, &["balance", "assets:savings", "--explain"]);
+    let rent = out
+        .lines()
+        .find(|l| l.trim_start().starts_with("budget Rent ("))
+        .unwrap_or_else(|| panic!("no Rent line: {out}"));
+    assert!(rent.contains("\tassets:savings\tUSD\t400\t"), "{rent}");
+    assert!(rent.contains("\tcreated on laptop ("), "{rent}");
+    let car = out
+        .lines()
+        .find(|l| l.trim_start().starts_with("piggy Car ("))
+        .unwrap_or_else(|| panic!("no Car line: {out}"));
+    assert!(car.contains("\tUSD\t500\t"), "{car}");
+    assert!(car.ends_with("created on phone (this device)"), "{car}");
+    assert!(
+        out.contains("    funded 300 USD on phone (this device)"),
+        "{out}"
+    );
+    assert!(out.contains("    funded 200 USD on laptop ("), "{out}");
+
+    bankero_cmd()
+        .env("BANKERO_HOME", phone.path())
+        .args(["balance", "--explain", "--output", "csv"])
+        .assert()
+        .failure();
+}
+
+#[test]
+fn sync_over_the_event_quota_imports_nothing_without_accept_large() {
+    let home_a = tempfile::tempdir().expect("tempdir home_a");
+    let home_b = tempfile::tempdir().expect("tempdir home_b");
+    let sync_dir = tempfile::tempdir().expect("tempdir sync_dir");
+    let dir = sync_dir.path().to_str().expect("utf8 path");
+    run_ok(&home_a, &["login", "--sync-dir", dir]);
+    run_ok(&home_b, &["login", "--sync-dir", dir]);
+    run_ok(&home_b, &["config", "set", "sync_max_events", "10"]);
+
+    let batch = (1..=12)
+        .map(|i| format!("deposit 1 USD --to assets:cash --from income:tips --note \"tip {i}\"\n"))
+        .collect::<String>();
+    let batch_file = home_a.path().join("tips.txt");
+    std::fs::write(&batch_file, batch).expect("write batch");
+    run_ok(
+        &home_a,
+        &["batch", "--file", batch_file.to_str().expect("utf8 path")],
+    );
+    run_ok(&home_a, &["sync", "now"]);
+
+    let out = bankero_cmd()
+        .env("BANKERO_HOME", home_b.path())
+        .args(["sync", "now"])
+        .assert()
+        .failure()
+        .get_output()
+        .stderr
+        .clone();
+    let err = String::from_utf8(out).expect("utf8 stderr");
+    assert!(
+        err.contains("12 new event(s)") && err.contains("sync_max_events (10)"),
+        "stderr: {err}"
+    );
+    assert!(err.contains("--accept-large"), "stderr: {err}");
+    let out = run_ok_out(&home_b, &["balance", "assets:cash"]);
+    assert!(!out.contains("USD"), "nothing should be imported: {out}");
+
+    run_ok(&home_b, &["sync", "now", "--accept-large"]);
+    let out = run_ok_out(&home_b, &["balance", "assets:cash"]);
+    assert!(
+        out.contains("assets:cash\tUSD\t12"),
+        "balance output: {out}"
+    );
+}
+
+#[test]
+fn attachments_are_stored_by_hash_and_travel_with_sync() {
+    let home_a = tempfile::tempdir().expect("tempdir home_a");
+    let home_b = tempfile::tempdir().expect("tempdir home_b");
+    let sync_dir = tempfile::tempdir().expect("tempdir sync_dir");
+    let dir = sync_dir.path().to_str().expect("utf8 path");
+    run_ok(&home_a, &["login", "--sync-dir", dir]);
+    run_ok(&home_b, &["login", "--sync-dir", dir]);
+
+    let receipt = home_a.path().join("tv-receipt.pdf");
+    std::fs::write(&receipt, b"%PDF-1.4 receipt for a TV").expect("write receipt");
+    let out = run_ok_out(
+        &home_a,
+        &[
+            "buy",
+            "external:bestbuy",
+            "899",
+            "USD",
+            "--from",
+            "assets:bank",
+            "--attach",
+            receipt.to_str().expect("utf8 path"),
+        ],
+    );
+    let event_id = out
+        .split_whitespace()
+        .nth(2)
+        .expect("event id in output")
+        .to_string();
+
+    let listed = run_ok_out(&home_a, &["event", "attachments", &event_id]);
+    assert!(
+        listed.contains("tv-receipt.pdf\t25 bytes"),
+        "attachments: {listed}"
+    );
+    let blobs = home_a.path().join("data/workspaces/personal/attachments");
+    assert_eq!(
+        std::fs::read_dir(&blobs).expect("blob dir").count(),
+        1,
+        "one blob named by its hash"
+    );
+
+    run_ok(&home_a, &["sync", "now"]);
+    run_ok(&home_b, &["sync", "now"]);
+
+    let saved = home_b.path().join("warranty");
+    run_ok(
+        &home_b,
+        &[
+            "event",
+            "attachments",
+            &event_id,
+            "--out",
+            saved.to_str().expect("utf8 path"),
+        ],
+    );
+    assert_eq!(
+        std::fs::read(saved.join("tv-receipt.pdf")).expect("saved receipt"),
+        b"%PDF-1.4 receipt for a TV"
+    );
+
+    // A blob no event refers to stays in the folder instead of filling the store.
+    let devices = exported_events(sync_dir.path())
+        .parent()
+        .and_then(|d| d.parent())
+        .expect("devices dir")
+        .to_path_buf();
+    let exported = std::fs::read_dir(&devices)
+        .expect("devices")
+        .map(|e| e.expect("entry").path().join("attachments"))
+        .find(|p| p.exists())
+        .expect("a device exported attachments");
+    let names: Vec<String> = std::fs::read_dir(&exported)
+        .expect("exported blobs")
+        .map(|e| e.expect("entry").file_name().to_string_lossy().into_owned())
+        .collect();
+    assert_eq!(names.len(), 1, "exported without temp files: {names:?}");
+    let stray = b"not attached to anything";
+    let hash: String = sha2::Sha256::digest(stray)
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect();
+    std::fs::write(exported.join(hash), stray).expect("write stray blob");
+    run_ok(&home_b, &["sync", "now"]);
+    let blobs_b = home_b.path().join("data/workspaces/personal/attachments");
+    assert_eq!(
+        std::fs::read_dir(&blobs_b).expect("blob dir").count(),
+        1,
+        "only the receipt is stored"
+    );
+}
@@ -0,0 +1,660 @@
+use assert_cmd::prelude::*;
+use std::process::Command;
+
+fn bankero_cmd() -> Command {
+    Command::new(assert_cmd::cargo::cargo_bin!("bankero"))
+}
+
+fn run_ok(home: &tempfile::TempDir, args: &[&str]) {
+    let mut cmd = bankero_cmd();
+    cmd.env("BANKERO_HOME", home.path());
+    cmd.args(args);
+    cmd.assert().success();
+}
+
+fn run_ok_out(home: &tempfile::TempDir, args: &[&str]) -> String {
+    let mut cmd = bankero_cmd();
+    cmd.env("BANKERO_HOME", home.path());
+    cmd.args(args);
+    let out = cmd.assert().success().get_output().stdout.clone();
+    String::from_utf8(out).expect("utf8 stdout")
+}
+
+#[test]
+fn asset_depreciation_task_posts_monthly_events_to_project() {
+    let home = tempfile::tempdir().expect("tempdir");
+
+    run_ok(&home, &["project", "checkout", "office"]);
+    run_ok(
+        &home,
+        &[
+            "deposit",
+            "3600",
+            "USD",
+            "--from",
+            "assets:bank",
+            "--to",
+            "assets:equipment:laptop",
+            "--effective-at",
+            "2026-01-05T12:00:00Z",
+        ],
+    );
+
+    run_ok(
+        &home,
+        &[
+            "asset",
+            "depreciate",
+            "assets:equipment:laptop",
+            "--method",
+            "straight-line",
+            "--years",
+            "3",
+            "--start",
+            "2026-01-31T23:59:59Z",
+        ],
+    );
+
+    let out = run_ok_out(
+        &home,
+        &[
+            "task",
+            "run",
+            "depreciation-assets-equipment-laptop",
+            "--as-of",
+            "2026-03-31T23:59:59Z",
+        ],
+    );
+    assert!(out.contains("wrote 3 event(s)"), "task run output: {out}");
+    assert!(
+        out.contains("2026-04-30T23:59:59"),
+        "task run output: {out}"
+    );
+
+    // Re-running as of the same time is a no-op (occurrences are tracked).
+    let out = run_ok_out(
+        &home,
+        &[
+            "task",
+            "run",
+            "depreciation-assets-equipment-laptop",
+            "--as-of",
+            "2026-03-31T23:59:59Z",
+        ],
+    );
+    assert!(out.contains("wrote 0 event(s)"), "task run output: {out}");
+
+    let out = run_ok_out(&home, &["balance"]);
+    assert!(
+        out.contains("assets:equipment:laptop\tUSD\t3300"),
+        "balance output: {out}"
+    );
+    assert!(
+        out.contains("expenses:depreciation\tUSD\t300"),
+        "balance output: {out}"
+    );
+
+    let out = run_ok_out(&home, &["report", "--project", "office"]);
+    assert_eq!(
+        out.matches("\tdepreciation\t").count(),
+        3,
+        "report output: {out}"
+    );
+
+    let out = run_ok_out(&home, &["task", "list"]);
+    assert!(
+        out.contains("depreciation-assets-equipment-laptop\tdepreciation\tmonthly\ttrue\t3\t"),
+        "task list output: {out}"
+    );
+}
+
+#[test]
+fn interest_task_accrues_monthly_on_loan_balance() {
+    let home = tempfile::tempdir().expect("tempdir");
+
+    run_ok(
+        &home,
+        &[
+            "deposit",
+            "1000",
+            "USD",
+            "--from",
+            "liabilities:loan",
+            "--to",
+            "assets:bank",
+            "--effective-at",
+            "2026-01-05T12:00:00Z",
+        ],
+    );
+
+    let out = run_ok_out(
+        &home,
+        &[
+            "task",
+            "create",
+            "interest",
+            "--account",
+            "liabilities:loan",
+            "--rate",
+            "24%",
+            "--monthly",
+            "--start",
+            "2026-01-31T23:59:59Z",
+        ],
+    );
+    assert!(out.contains("24% per year"), "task create output: {out}");
+
+    let out = run_ok_out(
+        &home,
+        &["task", "run", "interest", "--as-of", "2026-02-28T23:59:59Z"],
+    );
+    assert!(out.contains("wrote 2 event(s)"), "task run output: {out}");
+
+    // Interest compounds: 1000 * 2% = 20, then 1020 * 2% = 20.40.
+    let out = run_ok_out(&home, &["balance"]);
+    assert!(
+        out.contains("liabilities:loan\tUSD\t-1040.40"),
+        "balance output: {out}"
+    );
+    assert!(
+        out.contains("expenses:interest\tUSD\t40.40"),
+        "balance output: {out}"
+    );
+
+    let mut cmd = bankero_cmd();
+    cmd.env("BANKERO_HOME", home.path());
+    cmd.args(["task", "create", "no-kind", "--monthly"]);
+    cmd.assert().failure();
+}
+
+#[test]
+fn payoff_plans_avalanche_vs_snowball_and_creates_payment_task() {
+    let home = tempfile::tempdir().expect("tempdir");
+    let t = "2026-01-05T12:00:00Z";
+
+    for (amount, debt) in [("1000", "liabilities:card"), ("500", "liabilities:loan")] {
+        run_ok(
+            &home,
+            &[
+                "move",
+                amount,
+                "USD",
+                "--from",
+                debt,
+                "--to",
+                "expenses:stuff",
+                "--effective-at",
+                t,
+            ],
+        );
+    }
+    run_ok(
+        &home,
+        &[
+            "deposit",
+            "2000",
+            "USD",
+            "--from",
+            "income:salary",
+            "--to",
+            "assets:bank",
+            "--effective-at",
+            t,
+        ],
+    );
+
+    let plan = |strategy: &str| {
+        run_ok_out(
+            &home,
+            &[
+                "payoff",
+                "--strategy",
+                strategy,
+                "--budget",
+                "400",
+                "USD",
+                "--rate",
+                "liabilities:card=24%",
+                "--as-of",
+                "2026-01-31T00:00:00Z",
+            ],
+        )
+    };
+
+    let out = plan("avalanche");
+    assert!(
+        out.contains("2026-02\tliabilities:card\t400\t20.00\t620.00"),
+        "plan: {out}"
+    );
+    assert!(
+        out.contains("2026-04\tliabilities:loan\t162.95\t0\t337.05"),
+        "plan: {out}"
+    );
+    assert!(
+        out.contains("2026-04\tliabilities:card\tpaid off"),
+        "plan: {out}"
+    );
+    assert!(
+        out.contains("Debt-free in 4 month(s) (2026-05); total interest 37.05 USD."),
+        "plan: {out}"
+    );
+
+    let out = plan("snowball");
+    assert!(
+        out.contains("2026-02\tliabilities:loan\t400\t0\t100"),
+        "plan: {out}"
+    );
+    assert!(
+        out.contains("Debt-free in 4 month(s) (2026-05); total interest 62.31 USD."),
+        "plan: {out}"
+    );
+
+    let out = run_ok_out(
+        &home,
+        &[
+            "payoff",
+            "--strategy",
+            "snowball",
+            "--budget",
+            "400",
+            "USD",
+            "--create-task",
+            "--from",
+            "assets:bank",
+        ],
+    );
+    assert!(out.contains("Created task 'payoff-snowball'"), "out: {out}");
+
+    let out = run_ok_out(
+        &home,
+        &[
+            "task",
+            "run",
+            "payoff-snowball",
+            "--as-of",
+            "2099-01-01T00:00:00Z",
+        ],
+    );
+    assert!(out.contains("Ran task 'payoff-snowball'"), "out: {out}");
+
+    let bal = run_ok_out(&home, &["balance", "liabilities"]);
+    assert!(bal.contains("liabilities:card\tUSD\t0"), "balance: {bal}");
+    assert!(bal.contains("liabilities:loan\tUSD\t0"), "balance: {bal}");
+    let bal = run_ok_out(&home, &["balance", "assets:bank"]);
+    assert!(bal.contains("500"), "balance: {bal}");
+}
+
+#[test]
+fn report_task_writes_a_dated_html_file_per_month() {
+    let home = tempfile::tempdir().expect("tempdir");
+    let reports = home.path().join("reports");
+    for (amount, at) in [
+        ("120", "2026-02-10T12:00:00Z"),
+        ("75", "2026-03-12T12:00:00Z"),
+    ] {
+        run_ok(
+            &home,
+            &[
+                "deposit",
+                amount,
+                "USD",
+                "--from",
+                "income:salary",
+                "--to",
+                "assets:bank",
+                "--note",
+                "Salary <Feb & Mar>",
+                "--effective-at",
+                at,
+            ],
+        );
+    }
+
+    run_ok(
+        &home,
+        &[
+            "task",
+            "create",
+            "monthly-report",
+            "--report",
+            "report --month {prev-month} --output html",
+            "--out",
+            reports.to_str().expect("utf8 path"),
+            "--start",
+            "2026-03-01T00:00:00Z",
+        ],
+    );
+    let out = run_ok_out(
+        &home,
+        &[
+            "task",
+            "run",
+            "monthly-report",
+            "--as-of",
+            "2026-04-01T00:00:00Z",
+        ],
+    );
+    assert!(out.contains("2 report file(s)"), "task run output: {out}");
+    assert!(
+        out.contains("2026-05-01T00:00:00"),
+        "task run output: {out}"
+    );
+
+    let feb = std::fs::read_to_string(reports.join("monthly-report-2026-03-01.html"))
+        .expect("february report");
+    assert!(feb.starts_with("<!DOCTYPE html>"), "report: {feb}");
+    assert!(feb.contains("<td>120</td>"), "report: {feb}");
+    assert!(!feb.contains("<td>75</td>"), "report: {feb}");
+    assert!(
+        feb.contains("Salary &lt;Feb &amp; Mar&gt;"),
+        "report: {feb}"
+    );
+
+    let mar = std::fs::read_to_string(reports.join("monthly-report-2026-04-01.html"))
+        .expect("march report");
+    assert!(mar.contains("<td>75</td>"), "report: {mar}");
+    assert!(!mar.contains("<td>120</td>"), "report: {mar}");
+}
+
+#[test]
+fn synced_task_events_are_not_materialized_twice() {
+    let laptop = tempfile::tempdir().expect("tempdir");
+    let phone = tempfile::tempdir().expect("tempdir");
+    let sync_dir = tempfile::tempdir().expect("tempdir");
+    let dir = sync_dir.path().to_str().unwrap().to_string();
+    let task = "depreciation-assets-equipment-laptop";
+    let depreciate = [
+        "asset",
+        "depreciate",
+        "assets:equipment:laptop",
+        "--method",
+        "straight-line",
+        "--years",
+        "3",
+        "--start",
+        "2026-01-31T23:59:59Z",
+    ];
+
+    run_ok(
+        &laptop,
+        &[
+            "deposit",
+            "3600",
+            "USD",
+            "--from",
+            "assets:bank",
+            "--to",
+            "assets:equipment:laptop",
+            "--effective-at",
+            "2026-01-05T12:00:00Z",
+        ],
+    );
+    run_ok(&laptop, &["sync", "--dir", &dir, "now"]);
+    run_ok(&phone, &["sync", "--dir", &dir, "now"]);
+    run_ok(&laptop, &depreciate);
+    run_ok(&phone, &depreciate);
+
+    // The laptop runs January and February. A March payment entered by hand with the
+    // same postings is not the task's occurrence, so it is left alone.
+    let out = run_ok_out(
+        &laptop,
+        &["task", "run", task, "--as-of", "2026-02-28T23:59:59Z"],
+    );
+    assert!(out.contains("wrote 2 event(s)"), "laptop run: {out}");
+    run_ok(
+        &laptop,
+        &[
+            "move",
+            "100",
+            "USD",
+            "--from",
+            "assets:equipment:laptop",
+            "--to",
+            "expenses:depreciation",
+            "--effective-at",
+            "2026-03-29T10:00:00Z",
+        ],
+    );
+    run_ok(&laptop, &["sync", "--dir", &dir, "now"]);
+
+    let out = run_ok_out(&phone, &["sync", "--dir", &dir, "now"]);
+    assert!(
+        out.contains("matched\t2 synced event(s) to task occurrences"),
+        "phone sync: {out}"
+    );
+    let out = run_ok_out(
+        &phone,
+        &["task", "run", task, "--as-of", "2026-04-30T23:59:59Z"],
+    );
+    assert!(
+        out.contains("wrote 2 event(s), skipped 2 occurrence(s) already recorded by sync"),
+        "phone run: {out}"
+    );
+
+    let out = run_ok_out(&phone, &["balance"]);
+    assert!(
+        out.contains("assets:equipment:laptop\tUSD\t3100"),
+        "balance output: {out}"
+    );
+    assert!(
+        out.contains("expenses:depreciation\tUSD\t500"),
+        "balance output: {out}"
+    );
+}
+
+#[test]
+fn dca_task_buys_at_the_stored_rate_up_to_the_monthly_cap() {
+    let home = tempfile::tempdir().expect("tempdir");
+    // Quoted the usual way round (USD per BTC); the task inverts it.
+    run_ok(
+        &home,
+        &[
+            "rate",
+            "set",
+            "@binance",
+            "BTC",
+            "USD",
+            "50000",
+            "--as-of",
+            "2026-01-01T00:00:00Z",
+        ],
+    );
+
+    let out = run_ok_out(
+        &home,
+        &[
+            "task",
+            "create",
+            "btc-dca",
+            "--dca",
+            "50",
+            "--commodity",
+            "usd",
+            "--into",
+            "btc",
+            "--provider",
+            "@binance",
+            "--from",
+            "assets:usd",
+            "--to",
+            "assets:btc",
+            "--weekly",
+            "--monthly-cap",
+            "150",
+            "--start",
+            "2026-03-02T12:00:00Z",
+        ],
+    );
+    assert!(
+        out.contains("weekly 50 USD into BTC at @binance, at most 150 USD a month"),
+        "task create output: {out}"
+    );
+
+    // Five Mondays in March, but the cap stops it after three purchases.
+    let out = run_ok_out(
+        &home,
+        &["task", "run", "btc-dca", "--as-of", "2026-03-31T23:59:59Z"],
+    );
+    assert!(out.contains("wrote 3 event(s)"), "task run output: {out}");
+    let out = run_ok_out(&home, &["balance"]);
+    assert!(
+        out.contains("assets:usd\tUSD\t-150"),
+        "balance output: {out}"
+    );
+    assert!(
+        out.contains("assets:btc\tBTC\t0.003"),
+        "balance output: {out}"
+    );
+
+    // A new month resets the cap.
+    let out = run_ok_out(
+        &home,
+        &["task", "run", "btc-dca", "--as-of", "2026-04-06T23:59:59Z"],
+    );
+    assert!(out.contains("wrote 1 event(s)"), "task run output: {out}");
+
+    let out = run_ok_out(&home, &["report", "--month", "2026-04"]);
+    let id = out.split_whitespace().nth(2).expect("event id");
+    let out = run_ok_out(&home, &["event", "show", id, "--json"]);
+    let v: serde_json::Value = serde_json::from_str(&out).expect("json");
+    assert_eq!(v["payload"]["rate_context"]["provider"], "@binance");
+    assert_eq!(v["payload"]["rate_context"]["override_rate"], "50000");
+    assert_eq!(v["payload"]["basis"]["amount"], "50");
+    assert_eq!(v["payload"]["metadata"]["month_spent"], "50");
+
+    // Without a rate for the pair the run fails instead of guessing.
+    run_ok(
+        &home,
+        &[
+            "task",
+            "create",
+            "eth-dca",
+            "--dca",
+            "20",
+            "--commodity",
+            "USD",
+            "--into",
+            "ETH",
+            "--provider",
+            "@binance",
+            "--from",
+            "assets:usd",
+            "--to",
+            "assets:eth",
+            "--monthly",
+            "--start",
+            "2026-03-01T12:00:00Z",
+        ],
+    );
+    let mut cmd = bankero_cmd();
+    cmd.env("BANKERO_HOME", home.path());
+    cmd.args(["task", "run", "eth-dca", "--as-of", "2026-03-02T00:00:00Z"]);
+    cmd.assert().failure().stderr(predicates::str::contains(
+        "no @binance rate between USD and ETH",
+    ));
+}
+
+#[test]
+fn recurring_task_repeats_a_template_event_every_month() {
+    let home = tempfile::tempdir().expect("tempdir");
+    let out = run_ok_out(
+        &home,
+        &[
+            "buy",
+            "landlord",
+            "800",
+            "USD",
+            "--from",
+            "assets:bank",
+            "--category",
+            "rent",
+            "--note",
+            "rent",
+            "--meta",
+            "lease=A1",
+            "@bcv",
+            "--effective-at",
+            "2026-03-01T09:00:00Z",
+        ],
+    );
+    let template = out.split_whitespace().nth(2).expect("event id").to_string();
+
+    let out = run_ok_out(
+        &home,
+        &[
+            "task",
+            "create",
+            "rent",
+            "--from-event",
+            &template,
+            "--monthly",
+        ],
+    );
+    assert!(
+        out.contains("monthly buy 800 USD"),
+        "task create output: {out}"
+    );
+    assert!(
+        out.contains("first run 2026-04-01T09:00:00+00:00"),
+        "task create output: {out}"
+    );
+
+    let out = run_ok_out(
+        &home,
+        &["task", "run", "rent", "--as-of", "2026-06-15T00:00:00Z"],
+    );
+    assert!(out.contains("wrote 3 event(s)"), "task run output: {out}");
+    let out = run_ok_out(
+        &home,
+        &["task", "run", "rent", "--as-of", "2026-06-15T00:00:00Z"],
+    );
+    assert!(out.contains("wrote 0 event(s)"), "task run output: {out}");
+    let out = run_ok_out(&home, &["balance", "assets:bank"]);
+    assert!(
+        out.contains("assets:bank\tUSD\t-3200"),
+        "balance output: {out}"
+    );
+
+    let out = run_ok_out(&home, &["report", "--month", "2026-05"]);
+    let id = out.split_whitespace().nth(2).expect("event id");
+    let out = run_ok_out(&home, &["event", "show", id, "--json"]);
+    let v: serde_json::Value = serde_json::from_str(&out).expect("json");
+    assert_eq!(v["payload"]["action"], "buy");
+    assert_eq!(v["payload"]["effective_at"], "2026-05-01T09:00:00Z");
+    assert_eq!(v["payload"]["note"], "rent");
+    assert_eq!(v["payload"]["metadata"]["payee"], "landlord");
+    assert_eq!(v["payload"]["metadata"]["template_event"], template);
+    // The template's provider and --meta carry over; the rate is looked up at May 1st.
+    assert_eq!(v["payload"]["rate_context"]["provider"], "@bcv");
+    assert_eq!(
+        v["payload"]["rate_context"]["as_of"],
+        "2026-05-01T09:00:00Z"
+    );
+    assert_eq!(v["payload"]["metadata"]["lease"], "A1");
+
+    // Occurrences are checked against limits like any other event.
+    run_ok(&home, &["limit", "set", "landlord", "500", "USD", "--hard"]);
+    let mut cmd = bankero_cmd();
+    cmd.env("BANKERO_HOME", home.path());
+    cmd.args(["task", "run", "rent", "--as-of", "2026-07-15T00:00:00Z"]);
+    cmd.assert()
+        .failure()
+        .stderr(predicates::str::contains("Blocked by hard limit"));
+    run_ok(&home, &["limit", "remove", "landlord"]);
+
+    // A voided event cannot be a template.
+    run_ok(&home, &["event", "void", &template]);
+    let mut cmd = bankero_cmd();
+    cmd.env("BANKERO_HOME", home.path());
+    cmd.args([
+        "task",
+        "create",
+        "rent2",
+        "--from-event",
+        &template,
+        "--weekly",
+    ]);
+    cmd.assert()
+        .failure()
+        .stderr(predicates::str::contains("was voided by event"));
+}